@@ -0,0 +1,8 @@
+#!/bin/sh
+
+set -ex
+
+cargo build --target wasm32-unknown-unknown --release
+cargo run -p wasm-bindgen-cli --bin wasm-bindgen -- \
+  ../target/wasm32-unknown-unknown/release/benchmarks.wasm --out-dir pkg \
+  --no-modules
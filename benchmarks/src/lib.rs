@@ -0,0 +1,76 @@
+//! Benchmarks of the glue generated for calls between JS and wasm, which are
+//! run from `index.html`.
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    pub type MouseEvent;
+
+    #[wasm_bindgen(method, getter, structural, js_name = clientX)]
+    fn client_x_structural(this: &MouseEvent) -> f64;
+
+    #[wasm_bindgen(method, getter, structural, cached, js_name = clientX)]
+    fn client_x_cached(this: &MouseEvent) -> f64;
+
+    #[wasm_bindgen(method, getter, final, js_name = clientX)]
+    fn client_x_final(this: &MouseEvent) -> f64;
+
+    #[wasm_bindgen(js_namespace = Reflect, js_name = get)]
+    fn reflect_get(target: &MouseEvent, key: &JsValue) -> JsValue;
+}
+
+/// Reads `clientX` of `event` `n` times with a `structural` getter, which
+/// reads `event.clientX` every time.
+#[wasm_bindgen]
+pub fn client_x_structural(event: &MouseEvent, n: u32) -> f64 {
+    (0..n).map(|_| event.client_x_structural()).sum()
+}
+
+/// Reads `clientX` of `event` `n` times with a `cached` getter, which caches
+/// the accessor it finds on the prototype of the event.
+#[wasm_bindgen]
+pub fn client_x_cached(event: &MouseEvent, n: u32) -> f64 {
+    (0..n).map(|_| event.client_x_cached()).sum()
+}
+
+/// Reads `clientX` of `a` and `b` in turn `n` times with a `structural`
+/// getter, like a handler of events of two different classes.
+#[wasm_bindgen]
+pub fn client_x_structural_alternating(a: &MouseEvent, b: &MouseEvent, n: u32) -> f64 {
+    (0..n)
+        .map(|i| {
+            let event = if i % 2 == 0 { a } else { b };
+            event.client_x_structural()
+        })
+        .sum()
+}
+
+/// Reads `clientX` of `a` and `b` in turn `n` times with a `cached` getter,
+/// which remembers the accessors of both of their prototypes.
+#[wasm_bindgen]
+pub fn client_x_cached_alternating(a: &MouseEvent, b: &MouseEvent, n: u32) -> f64 {
+    (0..n)
+        .map(|i| {
+            let event = if i % 2 == 0 { a } else { b };
+            event.client_x_cached()
+        })
+        .sum()
+}
+
+/// Reads `clientX` of `event` `n` times with a `final` getter, which is looked
+/// up once when the module is loaded.
+#[wasm_bindgen]
+pub fn client_x_final(event: &MouseEvent, n: u32) -> f64 {
+    (0..n).map(|_| event.client_x_final()).sum()
+}
+
+/// Reads `clientX` of `event` `n` times with `Reflect.get`, looking up the
+/// property by name on every call.
+#[wasm_bindgen]
+pub fn client_x_reflect(event: &MouseEvent, n: u32) -> f64 {
+    let key = JsValue::from_str("clientX");
+    (0..n)
+        .map(|_| reflect_get(event, &key).as_f64().unwrap_or(0.0))
+        .sum()
+}
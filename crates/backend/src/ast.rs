@@ -1,5 +1,6 @@
 use proc_macro2::{Ident, Span};
 use shared;
+use std::path::PathBuf;
 use syn;
 use Diagnostic;
 
@@ -24,6 +25,28 @@ pub struct Program {
     pub dictionaries: Vec<Dictionary>,
     /// custom typescript sections to be included in the definition file
     pub typescript_custom_sections: Vec<String>,
+    /// newtypes declared as branded types in TypeScript
+    pub brands: Vec<Brand>,
+    /// rust enums whose variants have data
+    pub tagged_unions: Vec<TaggedUnion>,
+    /// rust traits declared as interfaces in TypeScript
+    pub traits: Vec<Trait>,
+    /// JS files copied into the output, which imports are from
+    pub snippets: Vec<Snippet>,
+}
+
+/// A JS file which is copied into the `snippets` directory of the output,
+/// either written with `inline_js` or a file of the crate imported with
+/// `module = "/..."`.
+#[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
+#[derive(Clone)]
+pub struct Snippet {
+    /// Where the snippet is written to, relative to the output directory
+    pub path: String,
+    pub contents: String,
+    /// The file the snippet was read from, if any, which the crate needs to
+    /// be rebuilt on changes of
+    pub file: Option<PathBuf>,
 }
 
 /// A rust to js interface. Allows interaction with rust objects/functions
@@ -40,6 +63,9 @@ pub struct Export {
     /// Whether or not this export is flagged as a constructor, returning an
     /// instance of the `impl` type
     pub is_constructor: bool,
+    /// Whether or not this is an `async` constructor, which is exported as a
+    /// static method returning a `Promise` of an instance of the `impl` type
+    pub is_async: bool,
     /// The rust function
     pub function: Function,
     /// Comments extracted from the rust source.
@@ -49,6 +75,12 @@ pub struct Export {
     /// Whether or not this function should be flagged as the wasm start
     /// function.
     pub start: bool,
+    /// Whether or not this function is split into a wasm module of its own,
+    /// which is only loaded once it's first called.
+    pub lazy: bool,
+    /// Whether or not `HashMap`s and `BTreeMap`s are plain objects in JS,
+    /// rather than `Map`s.
+    pub maps_as_objects: bool,
 }
 
 /// The 3 types variations of `self`.
@@ -89,6 +121,19 @@ pub struct ImportFunction {
     pub catch: bool,
     pub variadic: bool,
     pub structural: bool,
+    /// Whether the first argument is passed as `this` rather than as an
+    /// argument
+    pub bind_this: bool,
+    /// Whether calls from worker threads are proxied to the main thread
+    pub main_thread: bool,
+    /// Whether the import is a value which is read rather than called
+    pub static_getter: bool,
+    /// Whether calling the import dynamically imports its module, returning
+    /// a `Promise` of the module's namespace object
+    pub dynamic_import: bool,
+    /// Whether a structural getter or setter caches the accessors it finds
+    /// on the prototypes of objects
+    pub cached: bool,
     pub kind: ImportFunctionKind,
     pub shim: Ident,
     pub doc_comment: Option<String>,
@@ -123,6 +168,8 @@ pub struct Operation {
 #[derive(Clone)]
 pub enum OperationKind {
     Regular,
+    /// Invokes the class itself as a function, like `Date()`
+    Call,
     Getter(Option<Ident>),
     Setter(Option<Ident>),
     IndexingGetter,
@@ -149,8 +196,12 @@ pub struct ImportType {
     pub attrs: Vec<syn::Attribute>,
     pub doc_comment: Option<String>,
     pub instanceof_shim: String,
+    pub duck_shim: String,
     pub extends: Vec<syn::Path>,
     pub vendor_prefixes: Vec<Ident>,
+    /// Whether this is the type of JS objects implementing an exported
+    /// trait, which is declared as the trait's interface in TypeScript
+    pub interface: bool,
 }
 
 #[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
@@ -162,10 +213,17 @@ pub struct ImportEnum {
     pub name: Ident,
     /// The Rust identifiers for the variants
     pub variants: Vec<Ident>,
-    /// The JS string values of the variants
+    /// The JS values of the variants, which are strings unless `numeric`
     pub variant_values: Vec<String>,
+    /// Whether the JS values are numbers rather than strings
+    pub numeric: bool,
+    /// The variant holding any other JS value, like `Other(String)`, without
+    /// which other values are converted to a hidden `__Nonexhaustive` variant
+    pub other: Option<Ident>,
     /// Attributes to apply to the Rust enum
     pub rust_attrs: Vec<syn::Attribute>,
+    /// Doc comments of the enum, for its type in TypeScript
+    pub comments: Vec<String>,
 }
 
 #[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
@@ -187,14 +245,45 @@ pub struct Struct {
     pub js_name: String,
     pub fields: Vec<StructField>,
     pub comments: Vec<String>,
+    /// Whether `toString` and friends are implemented with `Display`, from
+    /// `#[wasm_bindgen(js_tostring)]`.
+    pub js_tostring: bool,
+    /// Whether an `equals` method is implemented with `PartialEq`, from
+    /// `#[wasm_bindgen(js_equals)]`.
+    pub js_equals: bool,
+    /// Whether a `hashCode` method is implemented with `Hash`, from
+    /// `#[wasm_bindgen(js_hash)]`.
+    pub js_hash: bool,
+    /// Whether `toJSON` and `fromJSON` are implemented with serde, from
+    /// `#[wasm_bindgen(json)]`.
+    pub json: bool,
+    /// Whether properties are own enumerable properties of objects in JS,
+    /// from `#[wasm_bindgen(enumerable)]`.
+    pub enumerable: bool,
+    /// Whether objects are frozen in JS, with only getters for properties and
+    /// no `free` method, from `#[wasm_bindgen(frozen)]`.
+    pub frozen: bool,
+    /// Whether objects are converted to plain objects when they're passed to
+    /// imported JS functions by value, from `#[wasm_bindgen(plain)]`.
+    pub plain: bool,
 }
 
 #[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
 #[derive(Clone)]
 pub struct StructField {
     pub name: Ident,
+    /// The name of the property in JS, from `js_name = "..."`.
+    pub js_name: String,
     pub struct_name: Ident,
     pub readonly: bool,
+    /// Whether the field only has a setter, from `skip_getter`.
+    pub writeonly: bool,
+    /// Whether the getter returns a clone of the field, from
+    /// `getter_with_clone`, rather than requiring it to be `Copy`.
+    pub getter_with_clone: bool,
+    /// A function run on values set from JS, from `validate = "..."`, which
+    /// throws its error instead of setting the field if it fails.
+    pub validate: Option<syn::Path>,
     pub ty: syn::Type,
     pub getter: Ident,
     pub setter: Ident,
@@ -207,14 +296,23 @@ pub struct Enum {
     pub name: Ident,
     pub variants: Vec<Variant>,
     pub comments: Vec<String>,
+    /// The integer type the enum is represented as, from its `#[repr]`.
+    pub repr: Ident,
     pub hole: u32,
+    /// Whether the values of the variants have to be read from the compiled
+    /// module, because they couldn't be evaluated by the macro.
+    pub dynamic_values: bool,
+    /// Whether the variants are bits which can be combined, from
+    /// `#[wasm_bindgen(flags)]`.
+    pub flags: bool,
 }
 
 #[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
 #[derive(Clone)]
 pub struct Variant {
     pub name: Ident,
-    pub value: u32,
+    /// The bits of the value, sign-extended to 64 bits if the repr is signed.
+    pub value: u64,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -253,6 +351,56 @@ pub enum ConstValue {
     Null,
 }
 
+/// A newtype which is passed to JS as the value it wraps, and declared as a
+/// branded version of that value's type in TypeScript, from
+/// `#[wasm_bindgen(ts_brand = "...")]`.
+#[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
+#[derive(Clone)]
+pub struct Brand {
+    pub rust_name: Ident,
+    pub ts_name: String,
+    pub inner: syn::Type,
+}
+
+/// A trait exported with `#[wasm_bindgen]`, which is declared as an interface
+/// in TypeScript and implemented for JS objects by calling their methods.
+#[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
+#[derive(Clone)]
+pub struct Trait {
+    pub js_name: String,
+    pub methods: Vec<TraitMethod>,
+    pub comments: Vec<String>,
+}
+
+/// A method of an exported trait, which is called through the imported
+/// method `shim`.
+#[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
+#[derive(Clone)]
+pub struct TraitMethod {
+    pub js_name: String,
+    pub shim: Ident,
+    pub comments: Vec<String>,
+}
+
+/// An exported enum whose variants have data, which is passed to JS as an
+/// object with the name of its variant as its `tag` and the data as its
+/// `value`.
+#[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
+#[derive(Clone)]
+pub struct TaggedUnion {
+    pub name: Ident,
+    pub variants: Vec<TaggedVariant>,
+    pub comments: Vec<String>,
+}
+
+#[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
+#[derive(Clone)]
+pub struct TaggedVariant {
+    pub name: Ident,
+    /// The type of the variant's single field, if it has one.
+    pub ty: Option<syn::Type>,
+}
+
 #[cfg_attr(feature = "extra-traits", derive(Debug, PartialEq, Eq))]
 #[derive(Clone)]
 pub struct Dictionary {
@@ -72,6 +72,26 @@ impl TryToTokens for ast::Program {
         for d in self.dictionaries.iter() {
             d.to_tokens(tokens);
         }
+        for b in self.brands.iter() {
+            b.to_tokens(tokens);
+        }
+        for u in self.tagged_unions.iter() {
+            u.to_tokens(tokens);
+        }
+        // Snippets read from files of the crate are included as well, so the
+        // crate is rebuilt when they change.
+        for s in self.snippets.iter() {
+            if let Some(file) = &s.file {
+                let name = format!("__WBINDGEN_SNIPPET_{}", ShortHash(&s.path));
+                let name = Ident::new(&name, Span::call_site());
+                let file = file.to_string_lossy();
+                (quote! {
+                    #[allow(dead_code)]
+                    const #name: &str = include_str!(#file);
+                })
+                .to_tokens(tokens);
+            }
+        }
 
         Diagnostic::from_vec(errors)?;
 
@@ -128,6 +148,42 @@ impl ToTokens for ast::Struct {
         let name_chars = name_str.chars().map(|c| c as u32);
         let new_fn = Ident::new(&shared::new_function(&name_str), Span::call_site());
         let free_fn = Ident::new(&shared::free_function(&name_str), Span::call_site());
+        if self.js_hash {
+            (quote! {
+                #[allow(clippy::all)]
+                impl #name {
+                    #[doc(hidden)]
+                    pub fn __wbindgen_hash_code(&self) -> u32 {
+                        ::wasm_bindgen::__rt::hash_code(self)
+                    }
+                }
+            })
+            .to_tokens(tokens);
+        }
+        if self.json {
+            (quote! {
+                #[allow(clippy::all)]
+                impl #name {
+                    #[doc(hidden)]
+                    pub fn __wbindgen_to_json(&self) -> ::wasm_bindgen::JsValue {
+                        use wasm_bindgen::__wbindgen_if_not_serde;
+                        __wbindgen_if_not_serde! {
+                            compile_error! {
+                                "`#[wasm_bindgen(json)]` requires the `serde-serialize` \
+                                 feature to be enabled in the `wasm-bindgen` crate"
+                            }
+                        }
+                        ::wasm_bindgen::__rt::to_json(self)
+                    }
+
+                    #[doc(hidden)]
+                    pub fn __wbindgen_from_json(obj: &::wasm_bindgen::JsValue) -> #name {
+                        ::wasm_bindgen::__rt::from_json(obj)
+                    }
+                }
+            })
+            .to_tokens(tokens);
+        }
         (quote! {
             #[allow(clippy::all)]
             impl ::wasm_bindgen::describe::WasmDescribe for #name {
@@ -234,21 +290,6 @@ impl ToTokens for ast::Struct {
                 }
             }
 
-            #[allow(clippy::all)]
-            impl ::wasm_bindgen::convert::RefMutFromWasmAbi for #name {
-                type Abi = u32;
-                type Anchor = ::wasm_bindgen::__rt::RefMut<'static, #name>;
-
-                unsafe fn ref_mut_from_abi(
-                    js: Self::Abi,
-                    _extra: &mut ::wasm_bindgen::convert::Stack,
-                ) -> Self::Anchor {
-                    let js = js as *mut ::wasm_bindgen::__rt::WasmRefCell<#name>;
-                    ::wasm_bindgen::__rt::assert_not_null(js);
-                    (*js).borrow_mut()
-                }
-            }
-
             impl ::wasm_bindgen::convert::OptionIntoWasmAbi for #name {
                 #[inline]
                 fn none() -> Self::Abi { 0 }
@@ -259,9 +300,71 @@ impl ToTokens for ast::Struct {
                 fn is_none(abi: &Self::Abi) -> bool { *abi == 0 }
             }
 
+            impl ::wasm_bindgen::convert::OptionRefFromWasmAbi for #name {
+                #[inline]
+                fn is_none(abi: &Self::Abi) -> bool { *abi == 0 }
+            }
+
+            #[allow(clippy::all)]
+            impl ::wasm_bindgen::convert::VectorIntoWasmAbi for #name {
+                fn vector_into_abi(
+                    vector: ::wasm_bindgen::__rt::std::boxed::Box<[#name]>,
+                    extra: &mut ::wasm_bindgen::convert::Stack,
+                ) -> ::wasm_bindgen::convert::WasmSlice {
+                    use wasm_bindgen::__rt::std::vec::Vec;
+                    use wasm_bindgen::convert::IntoWasmAbi;
+
+                    let ptrs = vector
+                        .into_vec()
+                        .into_iter()
+                        .map(|value| value.into_abi(extra))
+                        .collect::<Vec<u32>>();
+                    ptrs.into_abi(extra)
+                }
+            }
+
+            #[allow(clippy::all)]
+            impl ::wasm_bindgen::convert::VectorFromWasmAbi for #name {
+                unsafe fn vector_from_abi(
+                    js: ::wasm_bindgen::convert::WasmSlice,
+                    extra: &mut ::wasm_bindgen::convert::Stack,
+                ) -> ::wasm_bindgen::__rt::std::boxed::Box<[#name]> {
+                    use wasm_bindgen::__rt::std::vec::Vec;
+                    use wasm_bindgen::convert::FromWasmAbi;
+
+                    <Vec<u32>>::from_abi(js, extra)
+                        .into_iter()
+                        .map(|ptr| <#name as FromWasmAbi>::from_abi(ptr, extra))
+                        .collect::<Vec<#name>>()
+                        .into_boxed_slice()
+                }
+            }
+
         })
         .to_tokens(tokens);
 
+        // Objects of frozen classes can't be changed from JS, so they can't be
+        // borrowed mutably either.
+        if !self.frozen {
+            (quote! {
+                #[allow(clippy::all)]
+                impl ::wasm_bindgen::convert::RefMutFromWasmAbi for #name {
+                    type Abi = u32;
+                    type Anchor = ::wasm_bindgen::__rt::RefMut<'static, #name>;
+
+                    unsafe fn ref_mut_from_abi(
+                        js: Self::Abi,
+                        _extra: &mut ::wasm_bindgen::convert::Stack,
+                    ) -> Self::Anchor {
+                        let js = js as *mut ::wasm_bindgen::__rt::WasmRefCell<#name>;
+                        ::wasm_bindgen::__rt::assert_not_null(js);
+                        (*js).borrow_mut()
+                    }
+                }
+            })
+            .to_tokens(tokens);
+        }
+
         for field in self.fields.iter() {
             field.to_tokens(tokens);
         }
@@ -275,31 +378,41 @@ impl ToTokens for ast::StructField {
         let ty = &self.ty;
         let getter = &self.getter;
         let setter = &self.setter;
-        (quote! {
-            #[no_mangle]
-            #[doc(hidden)]
-            #[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))]
-            #[allow(clippy::all)]
-            pub unsafe extern "C" fn #getter(js: u32)
-                -> <#ty as ::wasm_bindgen::convert::IntoWasmAbi>::Abi
-            {
-                use wasm_bindgen::__rt::{WasmRefCell, assert_not_null};
-                use wasm_bindgen::convert::{GlobalStack, IntoWasmAbi};
-
+        let val = if self.getter_with_clone {
+            quote! { (*js).borrow().#name.clone() }
+        } else {
+            quote! {{
                 fn assert_copy<T: Copy>(){}
                 assert_copy::<#ty>();
+                (*js).borrow().#name
+            }}
+        };
+        if !self.writeonly {
+            (quote! {
+                #[no_mangle]
+                #[doc(hidden)]
+                #[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))]
+                #[allow(clippy::all)]
+                pub unsafe extern "C" fn #getter(js: u32)
+                    -> <#ty as ::wasm_bindgen::convert::IntoWasmAbi>::Abi
+                {
+                    use wasm_bindgen::__rt::{WasmRefCell, assert_not_null};
+                    use wasm_bindgen::convert::{GlobalStack, IntoWasmAbi};
+
+                    let js = js as *mut WasmRefCell<#struct_name>;
+                    assert_not_null(js);
+                    let val = #val;
+                    <#ty as IntoWasmAbi>::into_abi(
+                        val,
+                        &mut GlobalStack::new(),
+                    )
+                }
+            })
+            .to_tokens(tokens);
+        }
 
-                let js = js as *mut WasmRefCell<#struct_name>;
-                assert_not_null(js);
-                let val = (*js).borrow().#name;
-                <#ty as IntoWasmAbi>::into_abi(
-                    val,
-                    &mut GlobalStack::new(),
-                )
-            }
-        })
-        .to_tokens(tokens);
-
+        // The type of the field is described under the name of the getter
+        // even if there's only a setter.
         Descriptor(
             &getter,
             quote! {
@@ -312,6 +425,13 @@ impl ToTokens for ast::StructField {
             return;
         }
 
+        let validate = self.validate.as_ref().map(|validate| {
+            quote! {
+                if let Err(e) = #validate(&val) {
+                    ::wasm_bindgen::throw_val(e.into());
+                }
+            }
+        });
         (quote! {
             #[no_mangle]
             #[doc(hidden)]
@@ -330,6 +450,7 @@ impl ToTokens for ast::StructField {
                     val,
                     &mut GlobalStack::new(),
                 );
+                #validate
                 (*js).borrow_mut().#name = val;
             }
         })
@@ -346,17 +467,17 @@ impl TryToTokens for ast::Export {
         let mut converted_arguments = vec![];
         let ret = Ident::new("_ret", Span::call_site());
 
-        let offset = if self.method_self.is_some() {
-            args.push(quote! { me: u32 });
-            1
-        } else {
-            0
-        };
+        let offset = if self.method_self.is_some() { 1 } else { 0 };
 
+        // The receiver is passed however its class is, which for structs is
+        // as a pointer and for enums is as a number.
         let name = &self.rust_name;
         let receiver = match self.method_self {
             Some(ast::MethodSelf::ByValue) => {
                 let class = self.rust_class.as_ref().unwrap();
+                args.push(quote! {
+                    me: <#class as ::wasm_bindgen::convert::FromWasmAbi>::Abi
+                });
                 arg_conversions.push(quote! {
                     let me = unsafe {
                         <#class as ::wasm_bindgen::convert::FromWasmAbi>::from_abi(
@@ -368,7 +489,13 @@ impl TryToTokens for ast::Export {
                 quote! { me.#name }
             }
             Some(ast::MethodSelf::RefMutable) => {
-                let class = self.rust_class.as_ref().unwrap();
+                // Classes which can't be borrowed mutably, like frozen structs,
+                // are reported at the method taking `&mut self`.
+                let mut class = self.rust_class.clone().unwrap();
+                class.set_span(name.span());
+                args.push(quote! {
+                    me: <#class as ::wasm_bindgen::convert::RefMutFromWasmAbi>::Abi
+                });
                 arg_conversions.push(quote! {
                     let mut me = unsafe {
                         <#class as ::wasm_bindgen::convert::RefMutFromWasmAbi>
@@ -383,6 +510,9 @@ impl TryToTokens for ast::Export {
             }
             Some(ast::MethodSelf::RefShared) => {
                 let class = self.rust_class.as_ref().unwrap();
+                args.push(quote! {
+                    me: <#class as ::wasm_bindgen::convert::RefFromWasmAbi>::Abi
+                });
                 arg_conversions.push(quote! {
                     let me = unsafe {
                         <#class as ::wasm_bindgen::convert::RefFromWasmAbi>
@@ -404,6 +534,26 @@ impl TryToTokens for ast::Export {
         for (i, syn::ArgCaptured { ty, .. }) in self.function.arguments.iter().enumerate() {
             let i = i + offset;
             let ident = Ident::new(&format!("arg{}", i), Span::call_site());
+            if let Some(elem) = option_ref_elem(ty) {
+                args.push(quote! {
+                    #ident: <#elem as ::wasm_bindgen::convert::RefFromWasmAbi>::Abi
+                });
+                arg_conversions.push(quote! {
+                    let #ident = if <#elem as ::wasm_bindgen::convert::OptionRefFromWasmAbi>
+                        ::is_none(&#ident)
+                    {
+                        None
+                    } else {
+                        Some(unsafe {
+                            <#elem as ::wasm_bindgen::convert::RefFromWasmAbi>
+                                ::ref_from_abi(#ident, &mut __stack)
+                        })
+                    };
+                    let #ident = #ident.as_ref().map(|anchor| &**anchor);
+                });
+                converted_arguments.push(quote! { #ident });
+                continue;
+            }
             match *ty {
                 syn::Type::Reference(syn::TypeReference {
                     mutability: Some(_),
@@ -455,6 +605,47 @@ impl TryToTokens for ast::Export {
         if let syn::Type::Reference(_) = syn_ret {
             bail_span!(syn_ret, "cannot return a borrowed ref with #[wasm_bindgen]",)
         }
+        let mut call = quote! { #receiver(#(#converted_arguments),*) };
+        let mut describe_ret = quote! {
+            <#syn_ret as WasmDescribe>::describe();
+        };
+
+        // An `async` constructor returns a `Promise` which is driven by
+        // `wasm-bindgen-futures` and resolves to the constructed instance.
+        let syn_ret = if self.is_async {
+            let class = self.rust_class.as_ref().unwrap();
+            // Spanned on the method, so that a missing `wasm-bindgen-futures`
+            // dependency or `std_future` feature is reported there.
+            let to_promise = quote_spanned! {name.span()=>
+                ::wasm_bindgen_futures::__wbindgen_async_constructor!
+            };
+            call = quote! {
+                ::wasm_bindgen::JsValue::from(
+                    #to_promise({
+                        let __future = #call;
+                        async move {
+                            __future.await.map(::wasm_bindgen::JsValue::from)
+                        }
+                    })
+                )
+            };
+            describe_ret = quote! {
+                inform(GENERIC);
+                inform(7);
+                inform('P' as u32);
+                inform('r' as u32);
+                inform('o' as u32);
+                inform('m' as u32);
+                inform('i' as u32);
+                inform('s' as u32);
+                inform('e' as u32);
+                inform(1);
+                <#class as WasmDescribe>::describe();
+            };
+            quote! { ::wasm_bindgen::JsValue }
+        } else {
+            quote! { #syn_ret }
+        };
         let ret_ty = quote! {
             -> <#syn_ret as ::wasm_bindgen::convert::ReturnWasmAbi>::Abi
         };
@@ -464,9 +655,6 @@ impl TryToTokens for ast::Export {
                     ::wasm_bindgen::convert::GlobalStack::new()
                 })
         };
-        let describe_ret = quote! {
-            <#syn_ret as WasmDescribe>::describe();
-        };
         let nargs = self.function.arguments.len() as u32;
         let argtys = self.function.arguments.iter().map(|arg| &arg.ty);
         let attrs = &self.function.rust_attrs;
@@ -495,7 +683,7 @@ impl TryToTokens for ast::Export {
                         ::wasm_bindgen::convert::GlobalStack::new()
                     };
                     #(#arg_conversions)*
-                    #receiver(#(#converted_arguments),*)
+                    #call
                 };
                 #convert_ret
             }
@@ -560,6 +748,24 @@ impl ToTokens for ast::ImportType {
         let const_name = format!("__wbg_generated_const_{}", rust_name);
         let const_name = Ident::new(&const_name, Span::call_site());
         let instanceof_shim = Ident::new(&self.instanceof_shim, Span::call_site());
+        let duck_shim = Ident::new(&self.duck_shim, Span::call_site());
+        let js_name = &self.js_name;
+        // The types of exported traits are passed like any other JS value but
+        // declared as the trait's interface in TypeScript, which is described
+        // as a generic type without parameters.
+        let describe = if self.interface {
+            let name_len = js_name.len() as u32;
+            let name_chars = js_name.chars().map(|c| c as u32);
+            quote! {
+                use wasm_bindgen::describe::*;
+                inform(GENERIC);
+                inform(#name_len);
+                #(inform(#name_chars);)*
+                inform(0);
+            }
+        } else {
+            quote! { JsValue::describe(); }
+        };
         (quote! {
             #[allow(bad_style)]
             #(#attrs)*
@@ -582,7 +788,7 @@ impl ToTokens for ast::ImportType {
 
                 impl WasmDescribe for #rust_name {
                     fn describe() {
-                        JsValue::describe();
+                        #describe
                     }
                 }
 
@@ -643,6 +849,57 @@ impl ToTokens for ast::ImportType {
                     }
                 }
 
+                use wasm_bindgen::__wbindgen_if_std;
+                __wbindgen_if_std! {
+                    impl wasm_bindgen::convert::VectorIntoWasmAbi for #rust_name {
+                        #[inline]
+                        fn vector_into_abi(
+                            vector: wasm_bindgen::__rt::std::boxed::Box<[#rust_name]>,
+                            extra: &mut Stack,
+                        ) -> wasm_bindgen::convert::WasmSlice {
+                            use wasm_bindgen::__rt::std::vec::Vec;
+
+                            vector
+                                .into_vec()
+                                .into_iter()
+                                .map(|value| value.obj)
+                                .collect::<Vec<JsValue>>()
+                                .into_boxed_slice()
+                                .into_abi(extra)
+                        }
+                    }
+
+                    impl wasm_bindgen::convert::VectorFromWasmAbi for #rust_name {
+                        #[inline]
+                        unsafe fn vector_from_abi(
+                            js: wasm_bindgen::convert::WasmSlice,
+                            extra: &mut Stack,
+                        ) -> wasm_bindgen::__rt::std::boxed::Box<[#rust_name]> {
+                            use wasm_bindgen::__rt::std::boxed::Box;
+                            use wasm_bindgen::__rt::std::vec::Vec;
+
+                            <Box<[JsValue]>>::from_abi(js, extra)
+                                .into_vec()
+                                .into_iter()
+                                .map(|obj| #rust_name { obj })
+                                .collect::<Vec<#rust_name>>()
+                                .into_boxed_slice()
+                        }
+                    }
+
+                    impl wasm_bindgen::convert::MapValue for #rust_name {
+                        #[inline]
+                        fn into_js_value(self) -> JsValue {
+                            self.obj
+                        }
+
+                        #[inline]
+                        fn from_js_value(obj: JsValue) -> Option<#rust_name> {
+                            Some(#rust_name { obj })
+                        }
+                    }
+                }
+
                 // TODO: remove this on the next major version
                 impl From<JsValue> for #rust_name {
                     #[inline]
@@ -694,6 +951,29 @@ impl ToTokens for ast::ImportType {
                         // wrapper around `val`
                         unsafe { &*(val as *const JsValue as *const #rust_name) }
                     }
+
+                    #[inline]
+                    fn class_name() -> Option<&'static str> {
+                        Some(#js_name)
+                    }
+
+                    #[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))]
+                    fn has_methods(val: &JsValue) -> bool {
+                        #[link(wasm_import_module = "__wbindgen_placeholder__")]
+                        extern "C" {
+                            fn #duck_shim(val: u32) -> u32;
+                        }
+                        unsafe {
+                            let idx = val.into_abi(&mut ::wasm_bindgen::convert::GlobalStack::new());
+                            #duck_shim(idx) != 0
+                        }
+                    }
+
+                    #[cfg(not(all(target_arch = "wasm32", not(target_os = "emscripten"))))]
+                    fn has_methods(val: &JsValue) -> bool {
+                        drop(val);
+                        panic!("cannot check methods on non-wasm targets");
+                    }
                 }
 
                 ()
@@ -746,23 +1026,12 @@ impl ToTokens for ast::ImportEnum {
         let vis = &self.vis;
         let name = &self.name;
         let expect_string = format!("attempted to convert invalid {} into JSValue", name);
+        let name_str = name.to_string();
+        let name_len = name_str.len() as u32;
+        let name_chars = name_str.chars().map(|c| c as u32);
         let variants = &self.variants;
-        let variant_strings = &self.variant_values;
         let attrs = &self.rust_attrs;
 
-        let mut current_idx: usize = 0;
-        let variant_indexes: Vec<Literal> = variants
-            .iter()
-            .map(|_| {
-                let this_index = current_idx;
-                current_idx += 1;
-                Literal::usize_unsuffixed(this_index)
-            })
-            .collect();
-
-        // Borrow variant_indexes because we need to use it multiple times inside the quote! macro
-        let variant_indexes_ref = &variant_indexes;
-
         // A vector of EnumName::VariantName tokens for this enum
         let variant_paths: Vec<TokenStream> = self
             .variants
@@ -773,30 +1042,129 @@ impl ToTokens for ast::ImportEnum {
         // Borrow variant_paths because we need to use it multiple times inside the quote! macro
         let variant_paths_ref = &variant_paths;
 
+        // Either a variant holding any other value, or a hidden one standing
+        // for all of them.
+        let (definition, from_other, other_into) = match &self.other {
+            Some(other) => {
+                let ty = if self.numeric {
+                    quote!(f64)
+                } else {
+                    quote!(String)
+                };
+                let into = if self.numeric {
+                    quote!(#name::#other(value) => ::wasm_bindgen::JsValue::from_f64(value))
+                } else {
+                    quote!(#name::#other(value) => ::wasm_bindgen::JsValue::from_str(&value))
+                };
+                (
+                    quote! {
+                        #vis enum #name {
+                            #(#variants,)*
+                            #other(#ty),
+                        }
+                    },
+                    quote!(Some(#name::#other(value))),
+                    into,
+                )
+            }
+            None => {
+                let mut current_idx: usize = 0;
+                let variant_indexes: Vec<Literal> = variants
+                    .iter()
+                    .map(|_| {
+                        let this_index = current_idx;
+                        current_idx += 1;
+                        Literal::usize_unsuffixed(this_index)
+                    })
+                    .collect();
+
+                // Borrow variant_indexes because we need to use it multiple times inside the quote! macro
+                let variant_indexes_ref = &variant_indexes;
+                (
+                    quote! {
+                        #vis enum #name {
+                            #(#variants = #variant_indexes_ref,)*
+                            #[doc(hidden)]
+                            __Nonexhaustive,
+                        }
+                    },
+                    quote!(None),
+                    quote!(#name::__Nonexhaustive => panic!(#expect_string)),
+                )
+            }
+        };
+
+        let (from_js_value, into_js_value) = if self.numeric {
+            let values = self
+                .variant_values
+                .iter()
+                .map(|v| Literal::f64_unsuffixed(v.parse().unwrap()))
+                .collect::<Vec<_>>();
+            let values = &values;
+            (
+                quote! {
+                    let value = obj.as_f64()?;
+                    #(
+                        if value == #values {
+                            return Some(#variant_paths_ref);
+                        }
+                    )*
+                    #from_other
+                },
+                quote! {
+                    #(#variant_paths_ref => ::wasm_bindgen::JsValue::from_f64(#values),)*
+                },
+            )
+        } else {
+            let values = &self.variant_values;
+            (
+                quote! {
+                    let value = obj.as_string()?;
+                    let known = match value.as_str() {
+                        #(#values => Some(#variant_paths_ref),)*
+                        _ => None,
+                    };
+                    known.or_else(|| #from_other)
+                },
+                quote! {
+                    #(#variant_paths_ref => ::wasm_bindgen::JsValue::from_str(#values),)*
+                },
+            )
+        };
+
+        // Values which are neither known nor of the right type for the
+        // variant holding other values can't be represented at all.
+        let from_abi = if self.other.is_some() {
+            let msg = format!(
+                "expected a {} for `{}`",
+                if self.numeric { "number" } else { "string" },
+                name
+            );
+            quote!(::wasm_bindgen::throw_str(#msg))
+        } else {
+            quote!(#name::__Nonexhaustive)
+        };
+
         (quote! {
             #[allow(bad_style)]
             #(#attrs)*
             #[allow(clippy::all)]
-            #vis enum #name {
-                #(#variants = #variant_indexes_ref,)*
-                #[doc(hidden)]
-                __Nonexhaustive,
-            }
+            #definition
 
             #[allow(clippy::all)]
             impl #name {
                 #vis fn from_js_value(obj: &::wasm_bindgen::JsValue) -> Option<#name> {
-                    obj.as_string().and_then(|obj_str| match obj_str.as_str() {
-                        #(#variant_strings => Some(#variant_paths_ref),)*
-                        _ => None,
-                    })
+                    #from_js_value
                 }
             }
 
             #[allow(clippy::all)]
             impl ::wasm_bindgen::describe::WasmDescribe for #name {
                 fn describe() {
-                    ::wasm_bindgen::JsValue::describe()
+                    use wasm_bindgen::describe::*;
+                    inform(IMPORT_ENUM);
+                    inform(#name_len);
+                    #(inform(#name_chars);)*
                 }
             }
 
@@ -820,7 +1188,8 @@ impl ToTokens for ast::ImportEnum {
                     js: Self::Abi,
                     extra: &mut ::wasm_bindgen::convert::Stack,
                 ) -> Self {
-                    #name::from_js_value(&::wasm_bindgen::JsValue::from_abi(js, extra)).unwrap_or(#name::__Nonexhaustive)
+                    #name::from_js_value(&::wasm_bindgen::JsValue::from_abi(js, extra))
+                        .unwrap_or_else(|| #from_abi)
                 }
             }
 
@@ -828,8 +1197,8 @@ impl ToTokens for ast::ImportEnum {
             impl From<#name> for ::wasm_bindgen::JsValue {
                 fn from(obj: #name) -> ::wasm_bindgen::JsValue {
                     match obj {
-                        #(#variant_paths_ref => ::wasm_bindgen::JsValue::from_str(#variant_strings),)*
-                        #name::__Nonexhaustive => panic!(#expect_string),
+                        #into_js_value
+                        #other_into,
                     }
                 }
             }
@@ -1049,58 +1418,166 @@ impl ToTokens for ast::Enum {
     fn to_tokens(&self, into: &mut TokenStream) {
         let enum_name = &self.name;
         let hole = &self.hole;
-        let cast_clauses = self.variants.iter().map(|variant| {
-            let variant_name = &variant.name;
-            quote! {
-                if js == #enum_name::#variant_name as u32 {
-                    #enum_name::#variant_name
+        let repr = &self.repr;
+        let repr_str = repr.to_string();
+        let wide = repr_str == "i64" || repr_str == "u64";
+        let variants = &self.variants.iter().map(|v| &v.name).collect::<Vec<_>>();
+
+        // Enums are passed as `i32` or `u32` depending on their signedness,
+        // unless they're 64 bits wide in which case they're passed just like
+        // their repr and are `BigInt`s in JS.
+        let abi = if wide {
+            quote! { <#repr as ::wasm_bindgen::convert::IntoWasmAbi>::Abi }
+        } else if repr_str.starts_with('i') {
+            quote! { i32 }
+        } else {
+            quote! { u32 }
+        };
+        let (into_abi, from_abi, describe) = if wide {
+            (
+                quote! {
+                    ::wasm_bindgen::convert::IntoWasmAbi::into_abi(self as #repr, _extra)
+                },
+                quote! {
+                    let js = <#repr as ::wasm_bindgen::convert::FromWasmAbi>::from_abi(js, _extra);
+                },
+                quote! { <#repr as WasmDescribe>::describe(); },
+            )
+        } else {
+            (
+                quote! { self as #abi },
+                quote! {},
+                quote! {
+                    inform(ENUM);
+                    inform(#hole);
+                },
+            )
+        };
+        let cast = if wide { quote! { #repr } } else { abi.clone() };
+
+        if self.dynamic_values {
+            let values_name = Ident::new(
+                &shared::enum_values(&enum_name.to_string()),
+                Span::call_site(),
+            );
+            (quote! {
+                #[no_mangle]
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                #[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))]
+                #[allow(clippy::all)]
+                pub extern "C" fn #values_name() {
+                    use wasm_bindgen::describe::*;
+                    #(
+                        inform(#enum_name::#variants as u64 as u32);
+                        inform((#enum_name::#variants as u64 >> 32) as u32);
+                    )*
                 }
-            }
-        });
+            })
+            .to_tokens(into);
+        }
+        if self.dynamic_values && !wide {
+            let assert_name = Ident::new(
+                &format!("__WBINDGEN_ENUM_HOLE_{}", enum_name),
+                Span::call_site(),
+            );
+            (quote! {
+                // The largest value is reserved to represent `None`, so make
+                // sure no variant has it. If one does this fails to compile
+                // with an array length mismatch.
+                #[allow(dead_code, non_upper_case_globals)]
+                const #assert_name: [(); 0] =
+                    [(); 0 #(| (#enum_name::#variants as #abi as u32 == #hole) as usize)*];
+            })
+            .to_tokens(into);
+        }
+        if !wide {
+            (quote! {
+                #[allow(clippy::all)]
+                impl ::wasm_bindgen::convert::OptionFromWasmAbi for #enum_name {
+                    #[inline]
+                    fn is_none(val: &#abi) -> bool { *val as u32 == #hole }
+                }
+
+                #[allow(clippy::all)]
+                impl ::wasm_bindgen::convert::OptionIntoWasmAbi for #enum_name {
+                    #[inline]
+                    fn none() -> Self::Abi { #hole as #abi }
+                }
+            })
+            .to_tokens(into);
+        }
         (quote! {
             #[allow(clippy::all)]
             impl ::wasm_bindgen::convert::IntoWasmAbi for #enum_name {
-                type Abi = u32;
+                type Abi = #abi;
 
                 #[inline]
-                fn into_abi(self, _extra: &mut ::wasm_bindgen::convert::Stack) -> u32 {
-                    self as u32
+                fn into_abi(self, _extra: &mut ::wasm_bindgen::convert::Stack) -> #abi {
+                    #into_abi
                 }
             }
 
             #[allow(clippy::all)]
             impl ::wasm_bindgen::convert::FromWasmAbi for #enum_name {
-                type Abi = u32;
+                type Abi = #abi;
 
                 #[inline]
                 unsafe fn from_abi(
-                    js: u32,
+                    js: #abi,
                     _extra: &mut ::wasm_bindgen::convert::Stack,
                 ) -> Self {
-                    #(#cast_clauses else)* {
+                    #from_abi
+                    #(
+                        if js == #enum_name::#variants as #cast {
+                            #enum_name::#variants
+                        } else
+                    )* {
                         ::wasm_bindgen::throw_str("invalid enum value passed")
                     }
                 }
             }
 
+            // Only used for `&self` and `&mut self` methods, the value is
+            // simply copied. Values in JS are plain numbers, so changes made
+            // through `&mut self` aren't seen by the caller.
             #[allow(clippy::all)]
-            impl ::wasm_bindgen::convert::OptionFromWasmAbi for #enum_name {
+            impl ::wasm_bindgen::convert::RefFromWasmAbi for #enum_name {
+                type Abi = #abi;
+                type Anchor = ::wasm_bindgen::__rt::core::mem::ManuallyDrop<#enum_name>;
+
                 #[inline]
-                fn is_none(val: &u32) -> bool { *val == #hole }
+                unsafe fn ref_from_abi(
+                    js: #abi,
+                    extra: &mut ::wasm_bindgen::convert::Stack,
+                ) -> Self::Anchor {
+                    ::wasm_bindgen::__rt::core::mem::ManuallyDrop::new(
+                        <#enum_name as ::wasm_bindgen::convert::FromWasmAbi>::from_abi(js, extra),
+                    )
+                }
             }
 
             #[allow(clippy::all)]
-            impl ::wasm_bindgen::convert::OptionIntoWasmAbi for #enum_name {
+            impl ::wasm_bindgen::convert::RefMutFromWasmAbi for #enum_name {
+                type Abi = #abi;
+                type Anchor = ::wasm_bindgen::__rt::core::mem::ManuallyDrop<#enum_name>;
+
                 #[inline]
-                fn none() -> Self::Abi { #hole }
+                unsafe fn ref_mut_from_abi(
+                    js: #abi,
+                    extra: &mut ::wasm_bindgen::convert::Stack,
+                ) -> Self::Anchor {
+                    ::wasm_bindgen::__rt::core::mem::ManuallyDrop::new(
+                        <#enum_name as ::wasm_bindgen::convert::FromWasmAbi>::from_abi(js, extra),
+                    )
+                }
             }
 
             #[allow(clippy::all)]
             impl ::wasm_bindgen::describe::WasmDescribe for #enum_name {
                 fn describe() {
                     use wasm_bindgen::describe::*;
-                    inform(ENUM);
-                    inform(#hole);
+                    #describe
                 }
             }
         })
@@ -1195,6 +1672,207 @@ impl ToTokens for ast::Const {
     }
 }
 
+impl ToTokens for ast::Brand {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.rust_name;
+        let inner = &self.inner;
+        let ts_name_len = self.ts_name.len() as u32;
+        let ts_name_chars = self.ts_name.chars().map(|c| c as u32);
+        (quote! {
+            #[allow(clippy::all)]
+            impl ::wasm_bindgen::describe::WasmDescribe for #name {
+                fn describe() {
+                    use wasm_bindgen::describe::*;
+                    inform(NAMED);
+                    inform(#ts_name_len);
+                    #(inform(#ts_name_chars);)*
+                    <#inner as WasmDescribe>::describe();
+                }
+            }
+
+            #[allow(clippy::all)]
+            impl ::wasm_bindgen::convert::IntoWasmAbi for #name {
+                type Abi = <#inner as ::wasm_bindgen::convert::IntoWasmAbi>::Abi;
+
+                #[inline]
+                fn into_abi(self, extra: &mut ::wasm_bindgen::convert::Stack) -> Self::Abi {
+                    ::wasm_bindgen::convert::IntoWasmAbi::into_abi(self.0, extra)
+                }
+            }
+
+            #[allow(clippy::all)]
+            impl ::wasm_bindgen::convert::FromWasmAbi for #name {
+                type Abi = <#inner as ::wasm_bindgen::convert::FromWasmAbi>::Abi;
+
+                #[inline]
+                unsafe fn from_abi(
+                    js: Self::Abi,
+                    extra: &mut ::wasm_bindgen::convert::Stack,
+                ) -> Self {
+                    #name(::wasm_bindgen::convert::FromWasmAbi::from_abi(js, extra))
+                }
+            }
+        })
+        .to_tokens(tokens);
+    }
+}
+
+impl ToTokens for ast::TaggedUnion {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.name;
+        let name_str = self.name.to_string();
+        let name_len = name_str.len() as u32;
+        let name_chars = name_str.chars().map(|c| c as u32);
+        let tag_fn = Ident::new(&shared::tagged_union_tag(&name_str), Span::call_site());
+        let tags = self.variants.iter().enumerate().map(|(i, v)| {
+            let variant = &v.name;
+            let i = i as u32;
+            match v.ty {
+                Some(_) => quote! { #name::#variant(_) => #i },
+                None => quote! { #name::#variant => #i },
+            }
+        });
+        (quote! {
+            #[allow(clippy::all)]
+            impl ::wasm_bindgen::describe::WasmDescribe for #name {
+                fn describe() {
+                    use wasm_bindgen::describe::*;
+                    inform(TAGGED_UNION);
+                    inform(#name_len);
+                    #(inform(#name_chars);)*
+                }
+            }
+
+            #[allow(clippy::all)]
+            impl ::wasm_bindgen::convert::IntoWasmAbi for #name {
+                type Abi = u32;
+
+                fn into_abi(self, _extra: &mut ::wasm_bindgen::convert::Stack) -> u32 {
+                    use wasm_bindgen::__rt::std::boxed::Box;
+                    Box::into_raw(Box::new(self)) as u32
+                }
+            }
+
+            #[allow(clippy::all)]
+            impl ::wasm_bindgen::convert::FromWasmAbi for #name {
+                type Abi = u32;
+
+                unsafe fn from_abi(js: u32, _extra: &mut ::wasm_bindgen::convert::Stack) -> Self {
+                    use wasm_bindgen::__rt::std::boxed::Box;
+                    use wasm_bindgen::__rt::assert_not_null;
+
+                    let ptr = js as *mut #name;
+                    assert_not_null(ptr);
+                    *Box::from_raw(ptr)
+                }
+            }
+
+            #[allow(clippy::all)]
+            impl ::wasm_bindgen::convert::OptionIntoWasmAbi for #name {
+                #[inline]
+                fn none() -> Self::Abi { 0 }
+            }
+
+            #[allow(clippy::all)]
+            impl ::wasm_bindgen::convert::OptionFromWasmAbi for #name {
+                #[inline]
+                fn is_none(abi: &Self::Abi) -> bool { *abi == 0 }
+            }
+
+            #[no_mangle]
+            #[doc(hidden)]
+            #[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))]
+            #[allow(clippy::all)]
+            pub unsafe extern "C" fn #tag_fn(js: u32) -> u32 {
+                use wasm_bindgen::__rt::assert_not_null;
+
+                let ptr = js as *const #name;
+                assert_not_null(ptr);
+                match *ptr {
+                    #(#tags,)*
+                }
+            }
+        })
+        .to_tokens(tokens);
+
+        // Every variant has a function to create one from JS and one to
+        // consume one when it's passed to JS, and the type of the value of
+        // those with values is described by the latter.
+        for v in self.variants.iter() {
+            let variant = &v.name;
+            let variant_str = variant.to_string();
+            let unwrap_fn = shared::tagged_union_unwrap(&name_str, &variant_str);
+            let unwrap_fn = Ident::new(&unwrap_fn, Span::call_site());
+            let new_fn = shared::tagged_union_new(&name_str, &variant_str);
+            let new_fn = Ident::new(&new_fn, Span::call_site());
+            let ty = match &v.ty {
+                Some(ty) => ty,
+                None => {
+                    (quote! {
+                        #[no_mangle]
+                        #[doc(hidden)]
+                        #[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))]
+                        #[allow(clippy::all)]
+                        pub unsafe extern "C" fn #unwrap_fn(js: u32) {
+                            use wasm_bindgen::convert::{FromWasmAbi, GlobalStack};
+                            drop(<#name as FromWasmAbi>::from_abi(js, &mut GlobalStack::new()));
+                        }
+
+                        #[no_mangle]
+                        #[doc(hidden)]
+                        #[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))]
+                        #[allow(clippy::all)]
+                        pub extern "C" fn #new_fn() -> u32 {
+                            use wasm_bindgen::convert::{IntoWasmAbi, GlobalStack};
+                            <#name as IntoWasmAbi>::into_abi(#name::#variant, &mut GlobalStack::new())
+                        }
+                    })
+                    .to_tokens(tokens);
+                    continue;
+                }
+            };
+            (quote! {
+                #[no_mangle]
+                #[doc(hidden)]
+                #[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))]
+                #[allow(clippy::all, unreachable_patterns)]
+                pub unsafe extern "C" fn #unwrap_fn(js: u32)
+                    -> <#ty as ::wasm_bindgen::convert::IntoWasmAbi>::Abi
+                {
+                    use wasm_bindgen::convert::{FromWasmAbi, IntoWasmAbi, GlobalStack};
+                    match <#name as FromWasmAbi>::from_abi(js, &mut GlobalStack::new()) {
+                        #name::#variant(val) => {
+                            <#ty as IntoWasmAbi>::into_abi(val, &mut GlobalStack::new())
+                        }
+                        _ => ::wasm_bindgen::throw_str("tagged union has a different tag"),
+                    }
+                }
+
+                #[no_mangle]
+                #[doc(hidden)]
+                #[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))]
+                #[allow(clippy::all)]
+                pub unsafe extern "C" fn #new_fn(
+                    val: <#ty as ::wasm_bindgen::convert::FromWasmAbi>::Abi,
+                ) -> u32 {
+                    use wasm_bindgen::convert::{FromWasmAbi, IntoWasmAbi, GlobalStack};
+                    let val = <#ty as FromWasmAbi>::from_abi(val, &mut GlobalStack::new());
+                    <#name as IntoWasmAbi>::into_abi(#name::#variant(val), &mut GlobalStack::new())
+                }
+            })
+            .to_tokens(tokens);
+
+            Descriptor(
+                &unwrap_fn,
+                quote! {
+                    <#ty as WasmDescribe>::describe();
+                },
+            )
+            .to_tokens(tokens);
+        }
+    }
+}
+
 impl ToTokens for ast::Dictionary {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let name = &self.name;
@@ -1402,3 +2080,31 @@ impl<'a, T: ToTokens> ToTokens for Descriptor<'a, T> {
         .to_tokens(tokens);
     }
 }
+
+/// Returns `T` if `ty` is an `Option<&T>`, which exported functions receive
+/// through `OptionRefFromWasmAbi`.
+fn option_ref_elem(ty: &syn::Type) -> Option<&syn::Type> {
+    let path = match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path,
+        _ => return None,
+    };
+    let seg = path.segments.last()?.into_value();
+    if seg.ident != "Option" {
+        return None;
+    }
+    let generics = match &seg.arguments {
+        syn::PathArguments::AngleBracketed(generics) => generics,
+        _ => return None,
+    };
+    if generics.args.len() != 1 {
+        return None;
+    }
+    match generics.args.first()?.into_value() {
+        syn::GenericArgument::Type(syn::Type::Reference(syn::TypeReference {
+            mutability: None,
+            elem,
+            ..
+        })) => Some(elem),
+        _ => None,
+    }
+}
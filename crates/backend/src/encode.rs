@@ -54,6 +54,16 @@ fn shared_program<'a>(
             .map(|a| shared_struct(a, intern))
             .collect(),
         enums: prog.enums.iter().map(|a| shared_enum(a, intern)).collect(),
+        tagged_unions: prog
+            .tagged_unions
+            .iter()
+            .map(|a| shared_tagged_union(a, intern))
+            .collect(),
+        traits: prog
+            .traits
+            .iter()
+            .map(|a| shared_trait(a, intern))
+            .collect(),
         imports: prog
             .imports
             .iter()
@@ -64,6 +74,14 @@ fn shared_program<'a>(
             .iter()
             .map(|x| -> &'a str { &x })
             .collect(),
+        snippets: prog
+            .snippets
+            .iter()
+            .map(|s| Snippet {
+                path: &s.path,
+                contents: &s.contents,
+            })
+            .collect(),
         // version: shared::version(),
         // schema_version: shared::SCHEMA_VERSION.to_string(),
     })
@@ -83,6 +101,45 @@ fn shared_export<'a>(export: &'a ast::Export, intern: &'a Interner) -> Export<'a
         function: shared_function(&export.function, intern),
         comments: export.comments.iter().map(|s| &**s).collect(),
         start: export.start,
+        lazy: export.lazy,
+        maps_as_objects: export.maps_as_objects,
+        throws: export
+            .function
+            .ret
+            .as_ref()
+            .and_then(result_error)
+            .map(|e| intern.intern(e)),
+    }
+}
+
+/// Returns the name of `E` if `ty` is a `Result<T, E>` whose errors are
+/// something other than a plain `JsValue`, like an exported struct.
+fn result_error(ty: &syn::Type) -> Option<&Ident> {
+    let path = match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path,
+        _ => return None,
+    };
+    let seg = path.segments.last()?.into_value();
+    if seg.ident != "Result" {
+        return None;
+    }
+    let generics = match &seg.arguments {
+        syn::PathArguments::AngleBracketed(generics) => generics,
+        _ => return None,
+    };
+    if generics.args.len() != 2 {
+        return None;
+    }
+    let ident = match generics.args.last()?.into_value() {
+        syn::GenericArgument::Type(syn::Type::Path(syn::TypePath { qself: None, path })) => {
+            &path.segments.last()?.into_value().ident
+        }
+        _ => return None,
+    };
+    if ident == "JsValue" {
+        None
+    } else {
+        Some(ident)
     }
 }
 
@@ -99,6 +156,9 @@ fn shared_enum<'a>(e: &'a ast::Enum, intern: &'a Interner) -> Enum<'a> {
             .map(|v| shared_variant(v, intern))
             .collect(),
         comments: e.comments.iter().map(|s| &**s).collect(),
+        repr: intern.intern(&e.repr),
+        dynamic_values: e.dynamic_values,
+        flags: e.flags,
     }
 }
 
@@ -141,6 +201,7 @@ fn shared_import_function<'a>(
                     let is_static = *is_static;
                     let kind = match kind {
                         ast::OperationKind::Regular => OperationKind::Regular,
+                        ast::OperationKind::Call => OperationKind::Call,
                         ast::OperationKind::Getter(g) => {
                             let g = g.as_ref().map(|g| intern.intern(g));
                             OperationKind::Getter(g.unwrap_or_else(|| i.infer_getter_property()))
@@ -169,6 +230,11 @@ fn shared_import_function<'a>(
         catch: i.catch,
         method,
         structural: i.structural,
+        bind_this: i.bind_this,
+        main_thread: i.main_thread,
+        static_getter: i.static_getter,
+        dynamic_import: i.dynamic_import,
+        cached: i.cached,
         function: shared_function(&i.function, intern),
         variadic: i.variadic,
     })
@@ -185,12 +251,19 @@ fn shared_import_type<'a>(i: &'a ast::ImportType, intern: &'a Interner) -> Impor
     ImportType {
         name: &i.js_name,
         instanceof_shim: &i.instanceof_shim,
+        duck_shim: &i.duck_shim,
         vendor_prefixes: i.vendor_prefixes.iter().map(|x| intern.intern(x)).collect(),
     }
 }
 
-fn shared_import_enum<'a>(_i: &'a ast::ImportEnum, _intern: &'a Interner) -> ImportEnum {
-    ImportEnum {}
+fn shared_import_enum<'a>(i: &'a ast::ImportEnum, intern: &'a Interner) -> ImportEnum<'a> {
+    ImportEnum {
+        name: intern.intern(&i.name),
+        variant_values: i.variant_values.iter().map(|s| &**s).collect(),
+        numeric: i.numeric,
+        other: i.other.is_some(),
+        comments: i.comments.iter().map(|s| &**s).collect(),
+    }
 }
 
 fn shared_struct<'a>(s: &'a ast::Struct, intern: &'a Interner) -> Struct<'a> {
@@ -202,13 +275,49 @@ fn shared_struct<'a>(s: &'a ast::Struct, intern: &'a Interner) -> Struct<'a> {
             .map(|s| shared_struct_field(s, intern))
             .collect(),
         comments: s.comments.iter().map(|s| &**s).collect(),
+        js_tostring: s.js_tostring,
+        enumerable: s.enumerable,
+        frozen: s.frozen,
+        plain: s.plain,
+    }
+}
+
+fn shared_tagged_union<'a>(u: &'a ast::TaggedUnion, intern: &'a Interner) -> TaggedUnion<'a> {
+    TaggedUnion {
+        name: intern.intern(&u.name),
+        variants: u
+            .variants
+            .iter()
+            .map(|v| TaggedVariant {
+                name: intern.intern(&v.name),
+                has_value: v.ty.is_some(),
+            })
+            .collect(),
+        comments: u.comments.iter().map(|s| &**s).collect(),
+    }
+}
+
+fn shared_trait<'a>(t: &'a ast::Trait, intern: &'a Interner) -> Trait<'a> {
+    Trait {
+        name: &t.js_name,
+        methods: t
+            .methods
+            .iter()
+            .map(|m| TraitMethod {
+                name: &m.js_name,
+                shim: intern.intern(&m.shim),
+                comments: m.comments.iter().map(|s| &**s).collect(),
+            })
+            .collect(),
+        comments: t.comments.iter().map(|s| &**s).collect(),
     }
 }
 
 fn shared_struct_field<'a>(s: &'a ast::StructField, intern: &'a Interner) -> StructField<'a> {
     StructField {
-        name: intern.intern(&s.name),
+        name: &s.js_name,
         readonly: s.readonly,
+        writeonly: s.writeonly,
         comments: s.comments.iter().map(|s| &**s).collect(),
     }
 }
@@ -260,6 +369,17 @@ impl Encode for u32 {
     }
 }
 
+impl Encode for u64 {
+    fn encode(&self, dst: &mut Encoder) {
+        let mut val = *self;
+        while (val >> 7) != 0 {
+            dst.byte((val as u8) | 0x80);
+            val >>= 7;
+        }
+        dst.byte(val as u8);
+    }
+}
+
 impl Encode for usize {
     fn encode(&self, dst: &mut Encoder) {
         assert!(*self <= u32::max_value() as usize);
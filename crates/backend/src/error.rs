@@ -26,6 +26,7 @@ enum Repr {
     Single {
         text: String,
         span: Option<(Span, Span)>,
+        help: Option<String>,
     },
     SynError(Error),
     Multi {
@@ -39,6 +40,7 @@ impl Diagnostic {
             inner: Repr::Single {
                 text: text.into(),
                 span: None,
+                help: None,
             },
         }
     }
@@ -48,6 +50,7 @@ impl Diagnostic {
             inner: Repr::Single {
                 text: text.into(),
                 span: Some((span, span)),
+                help: None,
             },
         }
     }
@@ -57,10 +60,23 @@ impl Diagnostic {
             inner: Repr::Single {
                 text: text.into(),
                 span: extract_spans(node),
+                help: None,
             },
         }
     }
 
+    /// Attaches a suggestion for how to fix this error, shown after the
+    /// error's message.
+    ///
+    /// Has no effect on diagnostics which are a list of other diagnostics or
+    /// come from `syn`.
+    pub fn help<T: Into<String>>(mut self, help: T) -> Diagnostic {
+        if let Repr::Single { help: h, .. } = &mut self.inner {
+            *h = Some(help.into());
+        }
+        self
+    }
+
     pub fn from_vec(diagnostics: Vec<Diagnostic>) -> Result<(), Diagnostic> {
         if diagnostics.len() == 0 {
             Ok(())
@@ -101,13 +117,17 @@ fn extract_spans(node: &ToTokens) -> Option<(Span, Span)> {
 impl ToTokens for Diagnostic {
     fn to_tokens(&self, dst: &mut TokenStream) {
         match &self.inner {
-            Repr::Single { text, span } => {
+            Repr::Single { text, span, help } => {
                 let cs2 = (Span::call_site(), Span::call_site());
                 let (start, end) = span.unwrap_or(cs2);
                 dst.append(Ident::new("compile_error", start));
                 dst.append(Punct::new('!', Spacing::Alone));
                 let mut message = TokenStream::new();
-                message.append(Literal::string(text));
+                let text = match help {
+                    Some(help) => format!("{}\n\nhelp: {}", text, help),
+                    None => text.clone(),
+                };
+                message.append(Literal::string(&text));
                 let mut group = Group::new(Delimiter::Brace, message);
                 group.set_span(end);
                 dst.append(group);
@@ -0,0 +1,241 @@
+//! A description of the JS API of the generated bindings, which is written
+//! out as JSON so the next build can be checked for breaking changes against
+//! it.
+//!
+//! The API is a flat map from the names of exported items, with members of
+//! classes and enums named like `Class.member`, to a description of their
+//! signature such as `method(a: number): string`. Any change to a signature is
+//! considered breaking, even ones which are compatible in practice like a new
+//! optional argument, as these descriptions aren't understood any further.
+
+use failure::{bail, Error, ResultExt};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// Version of the JSON format, bumped when it changes incompatibly.
+const VERSION: u64 = 1;
+
+#[derive(Default)]
+pub struct Api {
+    items: BTreeMap<String, String>,
+}
+
+impl Api {
+    pub fn insert(&mut self, name: &str, signature: &str) {
+        self.items.insert(name.to_string(), signature.to_string());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.items.get(name).map(|s| &s[..])
+    }
+
+    pub fn to_json(&self) -> String {
+        let json = json!({
+            "version": VERSION,
+            "items": self.items,
+        });
+        let mut json = serde_json::to_string_pretty(&json).unwrap();
+        json.push('\n');
+        json
+    }
+
+    pub fn from_json(json: &str) -> Result<Api, Error> {
+        let json: Value = serde_json::from_str(json).context("invalid JSON")?;
+        match json.get("version").and_then(|v| v.as_u64()) {
+            Some(VERSION) => {}
+            Some(v) => bail!("unsupported version {} of the API description", v),
+            None => bail!("not a description of an API"),
+        }
+        let mut api = Api::default();
+        let items = match json.get("items").and_then(|i| i.as_object()) {
+            Some(items) => items,
+            None => bail!("not a description of an API"),
+        };
+        for (name, signature) in items {
+            match signature.as_str() {
+                Some(signature) => api.insert(name, signature),
+                None => bail!("the signature of `{}` isn't a string", name),
+            }
+        }
+        Ok(api)
+    }
+
+    /// Lists how this API breaks code written against `previous`, where
+    /// `renames` are pairs of old and new names of top-level exports which
+    /// were renamed since.
+    pub fn breaking_changes(&self, previous: &Api, renames: &[(String, String)]) -> Vec<String> {
+        let mut changes = Vec::new();
+        for (name, signature) in previous.items.iter() {
+            let renamed = |name: &str| {
+                let (top, rest) = match name.find('.') {
+                    Some(i) => (&name[..i], &name[i..]),
+                    None => (name, ""),
+                };
+                renames
+                    .iter()
+                    .find(|(old, _)| old == top)
+                    .map(|(_, new)| format!("{}{}", new, rest))
+            };
+            let (current_name, current) = match self.get(name) {
+                Some(s) => (name.clone(), s),
+                None => match renamed(name).and_then(|n| self.get(&n).map(|s| (n, s))) {
+                    Some(pair) => pair,
+                    None => {
+                        changes.push(format!("`{}` was removed", name));
+                        continue;
+                    }
+                },
+            };
+            if current != signature {
+                changes.push(format!(
+                    "`{}` changed from `{}` to `{}`",
+                    current_name, signature, current
+                ));
+            }
+        }
+        changes
+    }
+}
+
+/// Parses a comma-separated list of renames like `old=new`.
+pub fn parse_renames(list: &str) -> Result<Vec<(String, String)>, Error> {
+    let mut renames = Vec::new();
+    for rename in list.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let mut parts = rename.splitn(2, '=').map(|s| s.trim());
+        match (parts.next(), parts.next()) {
+            (Some(old), Some(new)) if !old.is_empty() && !new.is_empty() => {
+                if old.contains('.') || new.contains('.') {
+                    bail!("only top-level exports can be renamed, not `{}`", rename);
+                }
+                renames.push((old.to_string(), new.to_string()));
+            }
+            _ => bail!("invalid rename `{}`, expected `old=new`", rename),
+        }
+    }
+    Ok(renames)
+}
+
+#[cfg(test)]
+fn api(items: &[(&str, &str)]) -> Api {
+    let mut api = Api::default();
+    for (name, signature) in items {
+        api.insert(name, signature);
+    }
+    api
+}
+
+#[test]
+fn round_trips_through_json() {
+    let previous = api(&[
+        ("greet", "greet(name: string): void"),
+        ("Foo.bar", "bar(): number"),
+    ]);
+    let api = Api::from_json(&previous.to_json()).unwrap();
+    assert_eq!(api.items, previous.items);
+    assert!(Api::from_json("{\"version\": 2, \"items\": {}}").is_err());
+    assert!(Api::from_json("{\"items\": {}}").is_err());
+    assert!(Api::from_json("{\"version\": 1, \"items\": {\"f\": 1}}").is_err());
+}
+
+#[test]
+fn unchanged_and_added_exports_are_not_breaking() {
+    let previous = api(&[("greet", "greet(name: string): void")]);
+    let current = api(&[
+        ("greet", "greet(name: string): void"),
+        ("Foo", "class Foo"),
+        ("Foo.bar", "bar(): number"),
+    ]);
+    assert!(current.breaking_changes(&previous, &[]).is_empty());
+}
+
+#[test]
+fn removed_exports_are_breaking() {
+    let previous = api(&[
+        ("greet", "greet(name: string): void"),
+        ("Foo", "class Foo"),
+        ("Foo.bar", "bar(): number"),
+    ]);
+    let current = api(&[("Foo", "class Foo")]);
+    assert_eq!(
+        current.breaking_changes(&previous, &[]),
+        ["`Foo.bar` was removed", "`greet` was removed"]
+    );
+}
+
+#[test]
+fn changed_signatures_are_breaking() {
+    let previous = api(&[
+        ("greet", "greet(name: string): void"),
+        ("Foo.bar", "bar(): number"),
+    ]);
+    let current = api(&[
+        ("greet", "greet(name: string, loud?: boolean): void"),
+        ("Foo.bar", "bar(): string"),
+    ]);
+    assert_eq!(
+        current.breaking_changes(&previous, &[]),
+        [
+            "`Foo.bar` changed from `bar(): number` to `bar(): string`",
+            "`greet` changed from `greet(name: string): void` to \
+             `greet(name: string, loud?: boolean): void`",
+        ]
+    );
+}
+
+#[test]
+fn renamed_exports_are_compared_under_their_new_names() {
+    let previous = api(&[
+        ("greet", "greet(name: string): void"),
+        ("Foo", "class Foo"),
+        ("Foo.bar", "bar(): number"),
+        ("Foo.baz", "baz(): number"),
+    ]);
+    let current = api(&[
+        ("hello", "hello(name: string): void"),
+        ("Qux", "class Qux"),
+        ("Qux.bar", "bar(): number"),
+        ("Qux.baz", "baz(): string"),
+    ]);
+    // Without the renames everything in `previous` was removed.
+    assert_eq!(current.breaking_changes(&previous, &[]).len(), 4);
+    let renames = parse_renames("greet=hello, Foo=Qux").unwrap();
+    assert_eq!(
+        current.breaking_changes(&previous, &renames),
+        [
+            "`Qux` changed from `class Foo` to `class Qux`",
+            "`Qux.baz` changed from `baz(): number` to `baz(): string`",
+            "`hello` changed from `greet(name: string): void` to \
+             `hello(name: string): void`",
+        ]
+    );
+}
+
+#[test]
+fn parses_renames() {
+    assert!(parse_renames("").unwrap().is_empty());
+    assert!(parse_renames(" , ").unwrap().is_empty());
+    assert_eq!(
+        parse_renames("a=b, c = d,").unwrap(),
+        [
+            ("a".to_string(), "b".to_string()),
+            ("c".to_string(), "d".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn rejects_malformed_renames() {
+    for list in &["a", "a=", "=b", "a=b,c", " = "] {
+        let err = parse_renames(list).unwrap_err().to_string();
+        assert!(err.starts_with("invalid rename"), "{}: {}", list, err);
+    }
+    for list in &["Foo.bar=baz", "foo=Bar.baz"] {
+        let err = parse_renames(list).unwrap_err().to_string();
+        assert!(
+            err.starts_with("only top-level exports"),
+            "{}: {}",
+            list,
+            err
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! A cache of generated bindings, for skipping all the work of generating them
+//! again when neither the input wasm file nor the configuration has changed.
+//!
+//! Each entry in the cache is a directory, named after a hash of the input and
+//! the configuration, containing a copy of every file that was written to the
+//! output directory, in the same directories as they were written to.
+
+use failure::{Error, ResultExt};
+use sha2::{Digest, Sha384};
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+/// Hashes the input and the configuration into the name of a cache entry.
+///
+/// `DefaultHasher` is neither stable across releases nor collision resistant,
+/// so this is a SHA-384 hash instead, fed through `Hasher` so that anything
+/// which is `Hash` can be part of the name.
+pub struct Key(Sha384);
+
+impl Key {
+    pub fn new() -> Key {
+        Key(Sha384::new())
+    }
+
+    /// Returns the name of the cache entry.
+    pub fn name(self) -> String {
+        self.0.result()[..16]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+impl Hasher for Key {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.input(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let mut ret = [0; 8];
+        ret.copy_from_slice(&self.0.clone().result()[..8]);
+        u64::from_le_bytes(ret)
+    }
+}
+
+/// Copies the files in the cache `entry` to `out_dir`, returning whether the
+/// entry existed.
+pub fn restore(entry: &Path, out_dir: &Path) -> Result<bool, Error> {
+    let files = match fs::read_dir(entry) {
+        Ok(files) => files,
+        Err(_) => return Ok(false),
+    };
+    for file in files {
+        let file = file?;
+        let dst = out_dir.join(file.file_name());
+        // Snippets are in directories of their own.
+        if file.file_type()?.is_dir() {
+            fs::create_dir_all(&dst)
+                .with_context(|_| format!("failed to create `{}`", dst.display()))?;
+            restore(&file.path(), &dst)?;
+            continue;
+        }
+        fs::copy(file.path(), &dst).with_context(|_| {
+            format!(
+                "failed to copy `{}` from the cache to `{}`",
+                file.path().display(),
+                dst.display()
+            )
+        })?;
+    }
+    Ok(true)
+}
+
+/// Stores copies of `files`, which were written to `out_dir`, in the cache as
+/// `entry`.
+///
+/// The entry is populated in a temporary directory and then renamed into
+/// place, so concurrent builds will never see a partially written entry.
+pub fn store(entry: &Path, out_dir: &Path, files: &[PathBuf]) -> Result<(), Error> {
+    let parent = entry.parent().unwrap();
+    fs::create_dir_all(parent)
+        .with_context(|_| format!("failed to create cache directory `{}`", parent.display()))?;
+    let tmp = tempfile::Builder::new()
+        .prefix(".tmp")
+        .tempdir_in(parent)
+        .context("failed to create a temporary cache directory")?;
+    for file in files {
+        let dst = tmp.path().join(file.strip_prefix(out_dir).unwrap());
+        fs::create_dir_all(dst.parent().unwrap())
+            .context("failed to create a temporary cache directory")?;
+        fs::copy(file, &dst)
+            .with_context(|_| format!("failed to copy `{}` to the cache", file.display()))?;
+    }
+    // If another build stored this entry first it'll have the same contents,
+    // so failing to rename is fine.
+    let tmp = tmp.into_path();
+    if fs::rename(&tmp, entry).is_err() {
+        drop(fs::remove_dir_all(&tmp));
+    }
+    Ok(())
+}
@@ -37,6 +37,21 @@ impl<'src> Decode<'src> for u32 {
     }
 }
 
+impl<'src> Decode<'src> for u64 {
+    fn decode(data: &mut &'src [u8]) -> Self {
+        let mut cur = 0;
+        let mut offset = 0;
+        loop {
+            let byte = get(data);
+            cur |= ((byte & 0x7f) as u64) << offset;
+            if byte & 0x80 == 0 {
+                break cur;
+            }
+            offset += 7;
+        }
+    }
+}
+
 impl<'src> Decode<'src> for &'src str {
     fn decode(data: &mut &'src [u8]) -> &'src str {
         let n = u32::decode(data);
@@ -36,6 +36,12 @@ tys! {
     OPTIONAL
     UNIT
     CLAMPED
+    NAMED
+    GENERIC
+    TAGGED_UNION
+    IMPORT_ENUM
+    MAP
+    CLOSURE_WITH_THIS
 }
 
 #[derive(Debug)]
@@ -59,12 +65,32 @@ pub enum Descriptor {
     Vector(Box<Descriptor>),
     String,
     Anyref,
-    Enum { hole: u32 },
+    /// An exported enum whose values are numbers, with the value of the ABI,
+    /// as seen by JS, that represents `None`.
+    Enum { hole: i32 },
     RustStruct(String),
     Char,
     Option(Box<Descriptor>),
     Unit,
     Clamped(Box<Descriptor>),
+    /// A value declared as a different type in TypeScript, like a newtype
+    /// with `#[wasm_bindgen(ts_brand = "...")]`.
+    Named(String, Box<Descriptor>),
+    /// A generic JS type like `Array<T>`, which is passed as an `Anyref` but
+    /// has its parameters listed in TypeScript. Without parameters this is
+    /// the interface of an exported trait.
+    Generic {
+        name: String,
+        params: Vec<Descriptor>,
+    },
+    /// An exported enum with data, passed as a pointer to it.
+    TaggedUnion(String),
+    /// An enum of JS strings or numbers, passed as an `Anyref` but declared
+    /// as a union of its values in TypeScript.
+    ImportEnum(String),
+    /// A map with string keys and values of the given type, passed as a
+    /// vector of `Anyref`s holding keys followed by their values.
+    Map(Box<Descriptor>),
 }
 
 #[derive(Debug)]
@@ -80,6 +106,9 @@ pub struct Closure {
     pub dtor_idx: u32,
     pub function: Function,
     pub mutable: bool,
+    /// Whether the `this` the JS closure is called with is passed as the
+    /// first argument, see `Closure::wrap_with_this`.
+    pub with_this: bool,
 }
 
 #[derive(Copy, Clone)]
@@ -120,7 +149,8 @@ impl Descriptor {
             F64 => Descriptor::F64,
             BOOLEAN => Descriptor::Boolean,
             FUNCTION => Descriptor::Function(Box::new(Function::decode(data))),
-            CLOSURE => Descriptor::Closure(Box::new(Closure::decode(data))),
+            CLOSURE => Descriptor::Closure(Box::new(Closure::decode(data, false))),
+            CLOSURE_WITH_THIS => Descriptor::Closure(Box::new(Closure::decode(data, true))),
             REF => Descriptor::Ref(Box::new(Descriptor::_decode(data))),
             REFMUT => Descriptor::RefMut(Box::new(Descriptor::_decode(data))),
             SLICE => Descriptor::Slice(Box::new(Descriptor::_decode(data))),
@@ -128,7 +158,9 @@ impl Descriptor {
             OPTIONAL => Descriptor::Option(Box::new(Descriptor::_decode(data))),
             STRING => Descriptor::String,
             ANYREF => Descriptor::Anyref,
-            ENUM => Descriptor::Enum { hole: get(data) },
+            ENUM => Descriptor::Enum {
+                hole: get(data) as i32,
+            },
             RUST_STRUCT => {
                 let name = (0..get(data))
                     .map(|_| char::from_u32(get(data)).unwrap())
@@ -138,6 +170,34 @@ impl Descriptor {
             CHAR => Descriptor::Char,
             UNIT => Descriptor::Unit,
             CLAMPED => Descriptor::Clamped(Box::new(Descriptor::_decode(data))),
+            NAMED => {
+                let name = (0..get(data))
+                    .map(|_| char::from_u32(get(data)).unwrap())
+                    .collect();
+                Descriptor::Named(name, Box::new(Descriptor::_decode(data)))
+            }
+            GENERIC => {
+                let name = (0..get(data))
+                    .map(|_| char::from_u32(get(data)).unwrap())
+                    .collect();
+                let params = (0..get(data))
+                    .map(|_| Descriptor::_decode(data))
+                    .collect();
+                Descriptor::Generic { name, params }
+            }
+            TAGGED_UNION => {
+                let name = (0..get(data))
+                    .map(|_| char::from_u32(get(data)).unwrap())
+                    .collect();
+                Descriptor::TaggedUnion(name)
+            }
+            IMPORT_ENUM => {
+                let name = (0..get(data))
+                    .map(|_| char::from_u32(get(data)).unwrap())
+                    .collect();
+                Descriptor::ImportEnum(name)
+            }
+            MAP => Descriptor::Map(Box::new(Descriptor::_decode(data))),
             other => panic!("unknown descriptor: {}", other),
         }
     }
@@ -186,6 +246,25 @@ impl Descriptor {
         }
     }
 
+    /// Returns whether this type, or one of the types in it, is passed to JS
+    /// as a `BigInt`.
+    pub fn uses_bigint(&self) -> bool {
+        match *self {
+            Descriptor::I64 | Descriptor::U64 => true,
+            Descriptor::Function(ref f) => f.uses_bigint(),
+            Descriptor::Closure(ref c) => c.function.uses_bigint(),
+            Descriptor::Ref(ref d)
+            | Descriptor::RefMut(ref d)
+            | Descriptor::Slice(ref d)
+            | Descriptor::Vector(ref d)
+            | Descriptor::Option(ref d)
+            | Descriptor::Clamped(ref d)
+            | Descriptor::Named(_, ref d)
+            | Descriptor::Map(ref d) => d.uses_bigint(),
+            _ => false,
+        }
+    }
+
     pub fn is_ref_anyref(&self) -> bool {
         match *self {
             Descriptor::Ref(ref s) => s.is_anyref(),
@@ -242,6 +321,18 @@ impl Descriptor {
         }
     }
 
+    /// The exported Rust struct this is a `Vec` or boxed slice of, whose
+    /// elements cross the ABI as an array of pointers.
+    pub fn vector_rust_struct(&self) -> Option<&str> {
+        match *self {
+            Descriptor::Vector(ref d) => match **d {
+                Descriptor::RustStruct(ref s) => Some(s),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     pub fn rust_struct(&self) -> Option<&str> {
         let inner = match *self {
             Descriptor::Ref(ref d) => &**d,
@@ -266,6 +357,18 @@ impl Descriptor {
         }
     }
 
+    /// The name of the tagged union this is, possibly optional.
+    pub fn tagged_union(&self) -> Option<(&str, bool)> {
+        match *self {
+            Descriptor::TaggedUnion(ref s) => Some((s, false)),
+            Descriptor::Option(ref d) => match **d {
+                Descriptor::TaggedUnion(ref s) => Some((s, true)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     pub fn is_by_ref(&self) -> bool {
         match *self {
             Descriptor::Ref(_) | Descriptor::RefMut(_) => true,
@@ -286,6 +389,55 @@ impl Descriptor {
             _ => false,
         }
     }
+
+    /// Returns this type with the generic JS type or imported enum in it
+    /// replaced by the `Anyref` it's passed as, or `None` if it isn't one.
+    pub fn erase_generic(&self) -> Option<Descriptor> {
+        match *self {
+            Descriptor::Generic { .. } | Descriptor::ImportEnum(_) => Some(Descriptor::Anyref),
+            Descriptor::Ref(ref d) => d.erase_generic().map(|d| Descriptor::Ref(Box::new(d))),
+            Descriptor::RefMut(ref d) => d.erase_generic().map(|d| Descriptor::RefMut(Box::new(d))),
+            Descriptor::Option(ref d) => d.erase_generic().map(|d| Descriptor::Option(Box::new(d))),
+            Descriptor::Vector(ref d) => d.erase_generic().map(|d| Descriptor::Vector(Box::new(d))),
+            _ => None,
+        }
+    }
+
+    /// The TypeScript type of this type when it's a parameter of a generic
+    /// JS type, where anything that isn't known more precisely is `any`.
+    pub fn ts_type(&self) -> String {
+        if self.is_number() {
+            return "number".to_string();
+        }
+        match *self {
+            Descriptor::I64 | Descriptor::U64 => "BigInt".to_string(),
+            Descriptor::Boolean => "boolean".to_string(),
+            Descriptor::String | Descriptor::Char => "string".to_string(),
+            Descriptor::RustStruct(ref name)
+            | Descriptor::TaggedUnion(ref name)
+            | Descriptor::ImportEnum(ref name) => name.clone(),
+            Descriptor::Named(_, ref d) | Descriptor::Ref(ref d) | Descriptor::RefMut(ref d) => {
+                d.ts_type()
+            }
+            Descriptor::Option(ref d) => format!("{} | undefined", d.ts_type()),
+            Descriptor::Map(ref d) => format!("Map<string, {}>", d.ts_type()),
+            Descriptor::Vector(ref d) => match self.vector_kind() {
+                Some(VectorKind::Anyref) | None => format!("{}[]", d.ts_type()),
+                Some(kind) => kind.js_ty().to_string(),
+            },
+            Descriptor::Generic {
+                ref name,
+                ref params,
+            } => {
+                if params.is_empty() {
+                    return name.clone();
+                }
+                let params = params.iter().map(|p| p.ts_type()).collect::<Vec<_>>();
+                format!("{}<{}>", name, params.join(", "))
+            }
+            _ => "any".to_string(),
+        }
+    }
 }
 
 fn get(a: &mut &[u32]) -> u32 {
@@ -295,7 +447,7 @@ fn get(a: &mut &[u32]) -> u32 {
 }
 
 impl Closure {
-    fn decode(data: &mut &[u32]) -> Closure {
+    fn decode(data: &mut &[u32], with_this: bool) -> Closure {
         let shim_idx = get(data);
         let dtor_idx = get(data);
         let mutable = get(data) == REFMUT;
@@ -304,6 +456,7 @@ impl Closure {
             shim_idx,
             dtor_idx,
             mutable,
+            with_this,
             function: Function::decode(data),
         }
     }
@@ -321,6 +474,12 @@ impl Function {
             ret: Descriptor::_decode(data),
         }
     }
+
+    /// Returns whether any argument or the return value of this function is
+    /// passed as a `BigInt`.
+    pub fn uses_bigint(&self) -> bool {
+        self.arguments.iter().any(|d| d.uses_bigint()) || self.ret.uses_bigint()
+    }
 }
 
 impl VectorKind {
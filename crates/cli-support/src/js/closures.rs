@@ -12,7 +12,7 @@
 use crate::descriptor::Descriptor;
 use crate::js::js2rust::{ExportedShim, Js2Rust};
 use crate::js::Context;
-use failure::Error;
+use failure::{bail, Error};
 use std::collections::{BTreeMap, HashSet};
 use std::mem;
 use walrus::ir::{Expr, ExprId};
@@ -177,6 +177,10 @@ impl ClosureDescriptors {
             let import_name = format!("__wbindgen_closure_wrapper{}", func.index());
 
             let closure = instr.descriptor.closure().unwrap();
+            input.check_compat("a `Closure`", &instr.descriptor)?;
+            if closure.with_this && closure.function.arguments.is_empty() {
+                bail!("closures wrapped with `Closure::wrap_with_this` must take `this` as their first argument");
+            }
 
             let mut shim = closure.shim_idx;
             let (js, _ts, _js_doc) = {
@@ -200,20 +204,29 @@ impl ClosureDescriptors {
                 )
             };
             input.function_table_needed = true;
+            // Closures taking `this` can't be bound to `cb`, which is their
+            // own state, so they pass on the `this` they're called with.
+            let real = if closure.with_this {
+                "function(...args) { return cb.call(cb, this, ...args); }"
+            } else {
+                "cb.bind(cb)"
+            };
             let body = format!(
-                "function(a, b, _ignored) {{
+                "function(a/*: number*/, b/*: number*/, _ignored/*: number*/) {{
+
                     const f = wasm.__wbg_function_table.get({});
                     const d = wasm.__wbg_function_table.get({});
                     const cb = {};
                     cb.a = a;
                     cb.cnt = 1;
-                    let real = cb.bind(cb);
+                    let real = {};
                     real.original = cb;
                     return {};
                 }}",
                 shim,
                 closure.dtor_idx,
                 js,
+                real,
                 input.add_heap_object("real"),
             );
             input.export(&import_name, &body, None);
@@ -1,4 +1,4 @@
-use crate::descriptor::{Descriptor, Function};
+use crate::descriptor::{Descriptor, Function, VectorKind};
 use crate::js::Context;
 use failure::{bail, Error};
 
@@ -48,6 +48,25 @@ pub struct Js2Rust<'a, 'b: 'a> {
     /// metadata for anyref transformations
     anyref_args: Vec<(usize, bool)>,
     ret_anyref: bool,
+
+    /// Name reported to middleware registered with `addMiddleware`, if this
+    /// shim should be wrapped with middleware at all.
+    middleware: Option<String>,
+
+    /// Name this shim's calls are counted under with `--profile-boundary`.
+    profile: Option<String>,
+
+    /// Whether this shim calls into the lazily loaded wasm module, which
+    /// makes it `async` so the module can be loaded first.
+    lazy: bool,
+
+    /// Exported class that errors returned by the Rust function are thrown
+    /// as, documented with `@throws`.
+    throws: Option<String>,
+
+    /// Whether `HashMap`s and `BTreeMap`s are returned as plain objects
+    /// rather than `Map`s.
+    maps_as_objects: bool,
 }
 
 pub enum ExportedShim<'a> {
@@ -70,6 +89,11 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
             constructor: None,
             anyref_args: Vec::new(),
             ret_anyref: false,
+            middleware: None,
+            profile: None,
+            lazy: false,
+            throws: None,
+            maps_as_objects: false,
         }
     }
 
@@ -83,6 +107,48 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
         Ok(self)
     }
 
+    /// Flag this shim as an export which invokes middleware registered with
+    /// `addMiddleware`, reporting calls with the `name` given.
+    ///
+    /// With `--profile-boundary` calls of the shim are also counted under
+    /// `name`. This does nothing unless either of those was enabled in the
+    /// configuration.
+    pub fn middleware(&mut self, name: &str) -> &mut Self {
+        if self.cx.config.middleware {
+            self.middleware = Some(name.to_string());
+        }
+        if self.cx.config.profile_boundary {
+            self.profile = Some(format!("export {}", name));
+        }
+        self
+    }
+
+    /// Flag this shim as an export of the lazily loaded wasm module, which is
+    /// invoked as `lazyWasm` rather than `wasm`.
+    ///
+    /// The shim is `async` and returns a `Promise` of its result, as the
+    /// module may have to be loaded first.
+    pub fn lazy(&mut self, lazy: bool) -> &mut Self {
+        self.lazy = lazy;
+        self
+    }
+
+    /// Documents that this shim throws instances of the exported `class`,
+    /// for Rust functions returning a `Result` with that as its error.
+    pub fn throws(&mut self, class: Option<&str>) -> &mut Self {
+        self.throws = class.map(|s| s.to_string());
+        self
+    }
+
+    /// Flag this shim as an export marked `#[wasm_bindgen(maps_as_objects)]`,
+    /// which returns maps as plain objects and declares them as `Record`s.
+    ///
+    /// Maps passed to the shim may be either objects or `Map`s regardless.
+    pub fn maps_as_objects(&mut self, maps_as_objects: bool) -> &mut Self {
+        self.maps_as_objects = maps_as_objects;
+        self
+    }
+
     pub fn constructor(&mut self, class: Option<&str>) -> &mut Self {
         self.constructor = class.map(|s| s.to_string());
         self
@@ -145,22 +211,134 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
     }
 
     pub fn argument(&mut self, arg: &Descriptor) -> Result<&mut Self, Error> {
+        if let Descriptor::Named(brand, inner) = arg {
+            self.argument(inner)?;
+            let ty = &mut self.js_arguments.last_mut().unwrap().1;
+            self.cx.expose_ts_brand(brand, ty);
+            *ty = brand.clone();
+            return Ok(self);
+        }
+        if let Some(erased) = arg.erase_generic() {
+            self.argument(&erased)?;
+            self.js_arguments.last_mut().unwrap().1 = arg.ts_type();
+            return Ok(self);
+        }
         let i = self.arg_idx;
         let name = self.abi_arg();
 
+        if let Some((union, optional)) = arg.tagged_union() {
+            if optional {
+                self.cx.expose_is_like_none();
+                self.js_arguments
+                    .push((name.clone(), format!("{} | undefined", union)));
+                self.rust_arguments
+                    .push(format!("isLikeNone({0}) ? 0 : pass{1}({0})", name, union));
+            } else {
+                self.js_arguments.push((name.clone(), union.to_string()));
+                self.rust_arguments.push(format!("pass{}({})", union, name));
+            }
+            return Ok(self);
+        }
+
         let (arg, optional) = match arg {
             Descriptor::Option(t) => (&**t, true),
             _ => (arg, false),
         };
 
+        if let Descriptor::Map(value) = arg {
+            let ty = self.map_ts_type(value);
+            self.js_arguments.push((
+                name.clone(),
+                if optional {
+                    format!("{} | undefined", ty)
+                } else {
+                    ty
+                },
+            ));
+            self.cx.expose_map_entries();
+            let func = self.cx.pass_to_wasm_function(VectorKind::Anyref)?;
+            let val = if optional {
+                self.cx.expose_is_like_none();
+                format!("isLikeNone({0}) ? 0 : {1}(mapEntries({0}))", name, func)
+            } else {
+                format!("{}(mapEntries({}))", func, name)
+            };
+            self.prelude(&format!(
+                "const ptr{i} = {val};\nconst len{i} = WASM_VECTOR_LEN;",
+                i = i,
+                val = val,
+            ));
+            self.rust_arguments.push(format!("ptr{}", i));
+            self.rust_arguments.push(format!("len{}", i));
+            return Ok(self);
+        }
+
+        if let Some(class) = arg.vector_rust_struct() {
+            self.assert_not_frozen(class)?;
+            let ty = format!("{}[]", class);
+            self.js_arguments.push((
+                name.clone(),
+                if optional {
+                    format!("{} | undefined", ty)
+                } else {
+                    ty
+                },
+            ));
+            self.prelude(&format!("const ptrs{} = [];", i));
+            if optional {
+                self.cx.expose_is_like_none();
+                self.prelude(&format!("if (!isLikeNone({})) {{", name));
+            }
+            // Everything's checked before any of the objects is moved into
+            // Rust, so that none of them are lost if one is invalid.
+            if self.cx.config.debug {
+                self.prelude(&format!("for (const elem of {}) {{", name));
+                self.assert_class("elem", class);
+                self.assert_not_moved("elem");
+                self.prelude("}");
+            }
+            // Objects passed twice end up as a null pointer the second time,
+            // which Rust rejects, rather than being freed twice.
+            self.prelude(&format!(
+                "\
+                 for (const elem of {name}) {{
+                     ptrs{i}.push(elem.ptr);
+                     elem.ptr = 0;
+                 }}
+                 ",
+                i = i,
+                name = name,
+            ));
+            let func = self.cx.pass_to_wasm_function(VectorKind::U32)?;
+            let val = if optional {
+                self.prelude("}");
+                format!("isLikeNone({}) ? 0 : {}(ptrs{})", name, func, i)
+            } else {
+                format!("{}(ptrs{})", func, i)
+            };
+            self.prelude(&format!(
+                "const ptr{i} = {val};\nconst len{i} = WASM_VECTOR_LEN;",
+                i = i,
+                val = val,
+            ));
+            self.rust_arguments.push(format!("ptr{}", i));
+            self.rust_arguments.push(format!("len{}", i));
+            return Ok(self);
+        }
+
         if let Some(kind) = arg.vector_kind() {
-            self.js_arguments
-                .push((name.clone(), kind.js_ty().to_string()));
+            if optional {
+                self.js_arguments
+                    .push((name.clone(), format!("{} | undefined", kind.js_ty())));
+            } else {
+                self.js_arguments
+                    .push((name.clone(), kind.js_ty().to_string()));
+            }
 
             let func = self.cx.pass_to_wasm_function(kind)?;
             let val = if optional {
                 self.cx.expose_is_like_none();
-                format!("isLikeNone({}) ? [0, 0] : {}({})", name, func, name)
+                format!("isLikeNone({}) ? 0 : {}({})", name, func, name)
             } else {
                 format!("{}({})", func, name)
             };
@@ -170,8 +348,8 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
                 val = val,
             ));
             if arg.is_by_ref() || arg.is_clamped_by_ref() {
-                if optional {
-                    bail!("optional slices aren't currently supported");
+                if optional && (arg.is_mut_ref() || arg.is_clamped_by_ref()) {
+                    bail!("optional mutable slices aren't currently supported");
                 }
                 if arg.is_mut_ref() {
                     let get = self.cx.memview_function(kind);
@@ -188,13 +366,25 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
                         size = kind.size()
                     ));
                 }
-                self.finally(&format!(
-                    "\
-                     wasm.__wbindgen_free(ptr{i}, len{i} * {size});\n\
-                     ",
-                    i = i,
-                    size = kind.size()
-                ));
+                if optional {
+                    self.finally(&format!(
+                        "\
+                         if (ptr{i} !== 0) {{\n\
+                         wasm.__wbindgen_free(ptr{i}, len{i} * {size});\n\
+                         }}\n\
+                         ",
+                        i = i,
+                        size = kind.size()
+                    ));
+                } else {
+                    self.finally(&format!(
+                        "\
+                         wasm.__wbindgen_free(ptr{i}, len{i} * {size});\n\
+                         ",
+                        i = i,
+                        size = kind.size()
+                    ));
+                }
                 self.cx.require_internal_export("__wbindgen_free")?;
             }
             self.rust_arguments.push(format!("ptr{}", i));
@@ -336,7 +526,20 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
                         .push(format!("isLikeNone({0}) ? {1} : {0}", name, hole));
                     return Ok(self);
                 }
+                Descriptor::Ref(ref inner) if inner.rust_struct().is_some() => {
+                    let s = inner.rust_struct().unwrap();
+                    self.cx.expose_is_like_none();
+                    self.js_arguments.push((name.clone(), format!("{} | undefined", s)));
+                    self.prelude(&format!("if (!isLikeNone({})) {{", name));
+                    self.assert_class(&name, s);
+                    self.assert_not_moved(&name);
+                    self.prelude("}");
+                    self.rust_arguments
+                        .push(format!("isLikeNone({0}) ? 0 : {0}.ptr", name));
+                    return Ok(self);
+                }
                 Descriptor::RustStruct(ref s) => {
+                    self.assert_not_frozen(s)?;
                     self.js_arguments.push((name.clone(), format!("{} | undefined", s)));
                     self.prelude(&format!("let ptr{} = 0;", i));
                     self.prelude(&format!("if ({0} !== null && {0} !== undefined) {{", name));
@@ -362,6 +565,7 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
             if arg.is_by_ref() {
                 self.rust_arguments.push(format!("{}.ptr", name));
             } else {
+                self.assert_not_frozen(s)?;
                 self.prelude(&format!("const ptr{} = {}.ptr;", i, name));
                 self.prelude(&format!("{}.ptr = 0;", name));
                 self.rust_arguments.push(format!("ptr{}", i));
@@ -450,11 +654,39 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
     }
 
     pub fn ret(&mut self, ty: &Descriptor) -> Result<&mut Self, Error> {
+        if let Descriptor::Named(brand, inner) = ty {
+            self.ret(inner)?;
+            self.cx.expose_ts_brand(brand, &self.ret_ty);
+            self.ret_ty = brand.clone();
+            return Ok(self);
+        }
+        if let Some(erased) = ty.erase_generic() {
+            self.ret(&erased)?;
+            self.ret_ty = ty.ts_type();
+            return Ok(self);
+        }
+        if let Some((union, optional)) = ty.tagged_union() {
+            if optional {
+                self.ret_ty = format!("{} | undefined", union);
+                self.ret_expr = format!(
+                    "
+                    const ptr = RET;
+                    return ptr === 0 ? undefined : take{}(ptr);
+                ",
+                    union,
+                );
+            } else {
+                self.ret_ty = union.to_string();
+                self.ret_expr = format!("return take{}(RET);", union);
+            }
+            return Ok(self);
+        }
         if let Some(name) = ty.rust_struct() {
             match &self.constructor {
                 Some(class) if class == name => {
                     self.ret_expr = format!("this.ptr = RET;");
-                    if self.cx.config.weak_refs {
+                    let frozen = self.cx.frozen_classes.contains(name);
+                    if self.cx.config.weak_refs && !frozen {
                         self.ret_expr.push_str(&format!(
                             "\
                             addCleanup(this, this.ptr, free{});
@@ -462,6 +694,24 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
                             name
                         ));
                     }
+                    if self.cx.enumerable_classes.contains(name) {
+                        self.cx.expose_expose_properties();
+                        self.ret_expr.push_str(&format!(
+                            "\
+                            exposeProperties(this, {}.prototype);
+                        ",
+                            name
+                        ));
+                    }
+                    if frozen {
+                        self.cx.expose_freeze_object();
+                        self.ret_expr.push_str(&format!(
+                            "\
+                            freezeObject(this, this.ptr, free{});
+                        ",
+                            name
+                        ));
+                    }
                 }
                 Some(class) => bail!("constructor for `{}` cannot return `{}`", class, name),
                 None => {
@@ -488,6 +738,81 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
             _ => (ty, false),
         };
 
+        if let Descriptor::Map(value) = ty {
+            let ret_ty = self.map_ts_type(value);
+            self.ret_ty = if optional {
+                format!("{} | undefined", ret_ty)
+            } else {
+                ret_ty
+            };
+            let take = if self.maps_as_objects {
+                self.cx.expose_object_from_entries()
+            } else {
+                self.cx.expose_map_from_entries()
+            };
+            let f = self.cx.expose_get_vector_from_wasm(VectorKind::Anyref)?;
+            self.cx.expose_global_argument_ptr()?;
+            self.cx.expose_uint32_memory();
+            self.cx.require_internal_export("__wbindgen_free")?;
+            self.prelude("const retptr = globalArgumentPtr();");
+            self.rust_arguments.insert(0, "retptr".to_string());
+            self.ret_expr = format!(
+                "\
+                 RET;\n\
+                 const mem = getUint32Memory();\n\
+                 const rustptr = mem[retptr / 4];\n\
+                 const rustlen = mem[retptr / 4 + 1];\n\
+                 {guard}
+                 const realRet = {take}({f}(rustptr, rustlen));\n\
+                 wasm.__wbindgen_free(rustptr, rustlen * 4);\n\
+                 return realRet;\n\
+                 ",
+                f = f,
+                take = take,
+                guard = if optional {
+                    "if (rustptr === 0) return;"
+                } else {
+                    ""
+                },
+            );
+            return Ok(self);
+        }
+
+        if let Some(class) = ty.vector_rust_struct() {
+            self.ret_ty = if optional {
+                format!("{}[] | undefined", class)
+            } else {
+                format!("{}[]", class)
+            };
+            self.cx.require_class_wrap(class);
+            let f = self.cx.expose_get_vector_from_wasm(VectorKind::U32)?;
+            self.cx.expose_global_argument_ptr()?;
+            self.cx.expose_uint32_memory();
+            self.cx.require_internal_export("__wbindgen_free")?;
+            self.prelude("const retptr = globalArgumentPtr();");
+            self.rust_arguments.insert(0, "retptr".to_string());
+            self.ret_expr = format!(
+                "\
+                 RET;\n\
+                 const mem = getUint32Memory();\n\
+                 const rustptr = mem[retptr / 4];\n\
+                 const rustlen = mem[retptr / 4 + 1];\n\
+                 {guard}
+                 const realRet = Array.from({f}(rustptr, rustlen), ptr => {class}.__wrap(ptr));\n\
+                 wasm.__wbindgen_free(rustptr, rustlen * 4);\n\
+                 return realRet;\n\
+                 ",
+                f = f,
+                class = class,
+                guard = if optional {
+                    "if (rustptr === 0) return;"
+                } else {
+                    ""
+                },
+            );
+            return Ok(self);
+        }
+
         if let Some(ty) = ty.vector_kind() {
             self.ret_ty = ty.js_ty().to_string();
             let f = self.cx.expose_get_vector_from_wasm(ty)?;
@@ -723,6 +1048,9 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
             .iter()
             .map(|a| format!("@param {{{}}} {}\n", a.1, a.0))
             .collect();
+        if let Some(class) = &self.throws {
+            ret.push_str(&format!("@throws {{{}}}\n", class));
+        }
         ret.push_str(&format!("@returns {{{}}}", self.ret_ty));
         ret
     }
@@ -742,13 +1070,51 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
         invoc: &str,
         exported_shim: ExportedShim,
     ) -> (String, String, String) {
+        let emit_ts = self.cx.config.emit_ts;
+        if self.lazy {
+            self.ret_ty = format!("Promise<{}>", self.ret_ty);
+        }
         let js_args = self
             .js_arguments
             .iter()
-            .map(|s| &s.0[..])
+            .map(|s| {
+                if emit_ts {
+                    format!("{}: {}", s.0, s.1)
+                } else {
+                    s.0.clone()
+                }
+            })
             .collect::<Vec<_>>()
             .join(", ");
-        let mut js = format!("{}({}) {{\n", prefix, js_args);
+        // Constructors and setters can't be annotated with a return type, and
+        // `void` is what TypeScript infers anyway.
+        let js_ret = if emit_ts && self.constructor.is_none() && self.ret_ty != "void" {
+            format!(": {}", self.ret_ty)
+        } else {
+            String::new()
+        };
+        let mut js = format!("{}({}){} {{\n", prefix, js_args, js_ret);
+        // The lazy module is loaded before anything else, so nothing is
+        // passed to wasm yet while waiting for it.
+        if self.lazy {
+            js = format!("async {}", js);
+            js.push_str("const lazyWasm = await loadLazyWasm();\n");
+        }
+        // With middleware the whole body is moved into an arrow function
+        // which preserves `this` and `arguments` of the shim.
+        if let Some(name) = &self.middleware {
+            self.cx.expose_call_with_middleware();
+            js.push_str(&format!(
+                "return _callWithMiddleware('{}', arguments, () => {{\n",
+                name
+            ));
+        }
+        // Profiling goes inside of middleware so only the call into wasm
+        // itself is timed.
+        if let Some(name) = &self.profile {
+            self.cx.expose_profile_boundary();
+            js.push_str(&format!("return _profileBoundary('{}', () => {{\n", name));
+        }
         js.push_str(&self.prelude);
         let rust_args = self.rust_arguments.join(", ");
 
@@ -770,6 +1136,12 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
             )
         };
         js.push_str(&invoc);
+        if self.profile.is_some() {
+            js.push_str("\n});");
+        }
+        if self.middleware.is_some() {
+            js.push_str("\n});");
+        }
         js.push_str("\n}");
         let ts_args = self
             .js_arguments
@@ -808,6 +1180,15 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
         (js, ts, self.js_doc_comments())
     }
 
+    /// The TypeScript type of a map with values described by `value`.
+    fn map_ts_type(&self, value: &Descriptor) -> String {
+        if self.maps_as_objects {
+            format!("Record<string, {}>", value.ts_type())
+        } else {
+            format!("Map<string, {}>", value.ts_type())
+        }
+    }
+
     fn assert_class(&mut self, arg: &str, class: &str) {
         if !self.cx.config.debug {
             return
@@ -829,4 +1210,16 @@ impl<'a, 'b> Js2Rust<'a, 'b> {
             arg,
         ));
     }
+
+    /// Objects of frozen classes can't have their pointer cleared, so they
+    /// can't be passed to Rust by value.
+    fn assert_not_frozen(&self, class: &str) -> Result<(), Error> {
+        if self.cx.frozen_classes.contains(class) {
+            bail!(
+                "frozen class `{}` can only be passed to Rust by reference",
+                class
+            );
+        }
+        Ok(())
+    }
 }
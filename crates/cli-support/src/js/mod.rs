@@ -1,8 +1,11 @@
+use crate::api::Api;
 use crate::decode;
 use crate::descriptor::{Descriptor, VectorKind};
+use crate::snippets::Declarations;
 use crate::Bindgen;
 use failure::{bail, Error, ResultExt};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::mem;
 use walrus::{MemoryId, Module};
 use wasm_bindgen_wasm_interpreter::Interpreter;
 
@@ -25,6 +28,9 @@ pub struct Context<'a> {
     pub config: &'a Bindgen,
     pub module: &'a mut Module,
     pub start: Option<String>,
+    /// Intrinsics which have been bound but not yet exported, see
+    /// `write_intrinsics`.
+    pub intrinsics: Vec<(String, String)>,
 
     /// A map which maintains a list of what identifiers we've imported and what
     /// they're named locally.
@@ -49,11 +55,49 @@ pub struct Context<'a> {
     pub direct_imports: HashMap<&'a str, (&'a str, &'a str)>,
 
     pub exported_classes: Option<HashMap<String, ExportedClass>>,
+    pub exported_enums: Option<HashMap<String, ExportedEnum>>,
+    /// Every class exported from a Rust struct, including those of programs
+    /// whose bindings haven't been generated yet.
+    pub declared_classes: HashSet<String>,
+    /// Classes whose properties are own enumerable properties of their
+    /// objects, from `#[wasm_bindgen(enumerable)]`.
+    pub enumerable_classes: HashSet<String>,
+    /// Classes whose objects are frozen and freed by the garbage collector,
+    /// from `#[wasm_bindgen(frozen)]`.
+    pub frozen_classes: HashSet<String>,
+    /// Classes whose objects are passed to imported functions as plain
+    /// objects, from `#[wasm_bindgen(plain)]`.
+    pub plain_classes: HashSet<String>,
     pub function_table_needed: bool,
     pub interpreter: &'a mut Interpreter,
+    /// Descriptors interpreted ahead of time, keyed by the name of their
+    /// descriptor function.
+    pub descriptors: HashMap<String, Descriptor>,
     pub memory: MemoryId,
+    /// The names the wasm module exports exception tags under, which aren't
+    /// among the exports of `module` as they're only put back once it's been
+    /// emitted.
+    pub exported_tags: Vec<String>,
 
     pub anyref: wasm_bindgen_anyref_xform::Context,
+
+    /// Names of branded types which have been declared in TypeScript.
+    pub ts_brands: HashSet<String>,
+
+    /// Description of everything exported, see the `api` module.
+    pub api: Api,
+    /// Pairs of old and new names of exports, which get deprecated aliases
+    /// under their old names.
+    pub api_renames: Vec<(String, String)>,
+    /// What the shims of imports are a shim of, keyed by their generated
+    /// names, to name their sources in the source map.
+    pub source_names: HashMap<String, String>,
+    /// Exports marked `#[wasm_bindgen(lazy)]`, which are split into a wasm
+    /// module of their own.
+    pub lazy_exports: Vec<String>,
+    /// What the snippets of the programs are imported as, keyed by the
+    /// modules they're imported from, to declare them in TypeScript.
+    pub snippets: HashMap<String, Declarations>,
 }
 
 #[derive(Default)]
@@ -63,6 +107,19 @@ pub struct ExportedClass {
     typescript: String,
     has_constructor: bool,
     wrap_needed: bool,
+    js_tostring: bool,
+}
+
+/// An exported enum, which is written out once all the programs have been
+/// processed since its methods may be in other programs.
+#[derive(Default)]
+pub struct ExportedEnum {
+    repr: String,
+    comments: String,
+    variants: String,
+    typescript: String,
+    methods: String,
+    typescript_methods: String,
 }
 
 pub struct SubContext<'a, 'b: 'a> {
@@ -76,6 +133,7 @@ pub enum ImportTarget {
     Method(String),
     Constructor(String),
     StructuralMethod(String),
+    Static(String),
     StructuralGetter(Option<String>, String),
     StructuralSetter(Option<String>, String),
     StructuralIndexingGetter(Option<String>),
@@ -109,6 +167,76 @@ enum Import<'a> {
     },
 }
 
+/// Stands in for the name of the wasm file in the generated JS when it's named
+/// after its hash, which isn't known until the wasm file is emitted.
+pub const HASHED_WASM_FILE: &str = "__wbindgen_hashed_wasm_file__";
+/// Stands in for the subresource integrity metadata of a hashed wasm file.
+pub const HASHED_WASM_INTEGRITY: &str = "__wbindgen_hashed_wasm_integrity__";
+/// Stands in for the hash of the final wasm file, which the module cache of
+/// `--no-modules` uses to tell whether a cached module is out of date.
+pub const WASM_HASH: &str = "__wbindgen_wasm_hash__";
+
+/// Caching of compiled modules in IndexedDB for `--no-modules`, used when
+/// `init` is passed `cache: true`.
+///
+/// Modules are stored under the URL they're loaded from along with the hash
+/// of the wasm file, so a new build replaces the module cached by the last
+/// one. Not every engine can store a `WebAssembly.Module` in IndexedDB, and
+/// those that can't simply compile the module on every load.
+///
+/// The hash in here is the `WASM_HASH` placeholder.
+const MODULE_CACHE_JS: &str = "
+    function idbRequest(request) {
+        return new Promise((resolve, reject) => {
+            request.onsuccess = () => resolve(request.result);
+            request.onerror = () => reject(request.error);
+        });
+    }
+    function compileModule(data) {
+        if (typeof WebAssembly.compileStreaming === 'function') {
+            return WebAssembly.compileStreaming(data).catch(() => {
+                return data
+                    .then(r => r.arrayBuffer())
+                    .then(bytes => WebAssembly.compile(bytes));
+            });
+        }
+        return data
+            .then(r => r.arrayBuffer())
+            .then(bytes => WebAssembly.compile(bytes));
+    }
+    function loadCachedModule(path, compile) {
+        const url = typeof path === 'object' && path !== null && 'url' in path
+            ? path.url
+            : String(path);
+        const hash = '__wbindgen_wasm_hash__';
+        const open = indexedDB.open('wasm-bindgen-modules', 1);
+        open.onupgradeneeded = () => open.result.createObjectStore('modules');
+        return idbRequest(open)
+            .then(db => {
+                const get = db.transaction('modules').objectStore('modules').get(url);
+                return idbRequest(get).then(entry => ({ db, entry }));
+            })
+            .catch(() => ({ db: null, entry: undefined }))
+            .then(({ db, entry }) => {
+                if (entry && entry.hash === hash && entry.module instanceof WebAssembly.Module) {
+                    return entry.module;
+                }
+                return compile().then(module => {
+                    if (db !== null) {
+                        try {
+                            db.transaction('modules', 'readwrite')
+                                .objectStore('modules')
+                                .put({ hash, module }, url);
+                        } catch (e) {
+                            // This engine can't clone modules.
+                        }
+                    }
+                    return module;
+                });
+            });
+    }
+";
+
 const INITIAL_HEAP_VALUES: &[&str] = &["undefined", "null", "true", "false"];
 // Must be kept in sync with `src/lib.rs` of the `wasm-bindgen` crate
 const INITIAL_HEAP_OFFSET: usize = 32;
@@ -138,6 +266,8 @@ impl<'a> Context<'a> {
         } else {
             if contents.starts_with("function") {
                 format!("export function {}{}\n", name, &contents[8..])
+            } else if contents.starts_with("async function") {
+                format!("export async function {}{}\n", name, &contents[14..])
             } else if contents.starts_with("class") {
                 format!("export {}\n", contents)
             } else {
@@ -174,7 +304,7 @@ impl<'a> Context<'a> {
             );
             me.expose_get_string_from_wasm();
             Ok(format!(
-                "function(p, l) {{ return {}; }}",
+                "function(p/*: number*/, l/*: number*/) {{ return {}; }}",
                 me.add_heap_object("getStringFromWasm(p, l)")
             ))
         })?;
@@ -187,7 +317,7 @@ impl<'a> Context<'a> {
                 true,
             );
             Ok(format!(
-                "function(i) {{ return {}; }}",
+                "function(i/*: number*/) {{ return {}; }}",
                 me.add_heap_object("i")
             ))
         })?;
@@ -202,7 +332,7 @@ impl<'a> Context<'a> {
             me.expose_uint8_memory();
             Ok(format!(
                 "
-                function(n, invalid) {{
+                function(n/*: number*/, invalid/*: number*/) {{
                     let obj = {};
                     if (typeof(obj) === 'number') return obj;
                     getUint8Memory()[invalid] = 1;
@@ -221,7 +351,7 @@ impl<'a> Context<'a> {
                 false,
             );
             Ok(format!(
-                "function(i) {{ return {} === null ? 1 : 0; }}",
+                "function(i/*: number*/) {{ return {} === null ? 1 : 0; }}",
                 me.get_object("i")
             ))
         })?;
@@ -234,7 +364,7 @@ impl<'a> Context<'a> {
                 false,
             );
             Ok(format!(
-                "function(i) {{ return {} === undefined ? 1 : 0; }}",
+                "function(i/*: number*/) {{ return {} === undefined ? 1 : 0; }}",
                 me.get_object("i")
             ))
         })?;
@@ -248,7 +378,7 @@ impl<'a> Context<'a> {
             );
             Ok(format!(
                 "
-                function(i) {{
+                function(i/*: number*/) {{
                     let v = {};
                     return typeof(v) === 'boolean' ? (v ? 1 : 0) : 2;
                 }}
@@ -267,7 +397,7 @@ impl<'a> Context<'a> {
             me.expose_get_string_from_wasm();
             let expr = "ptr === 0 ? Symbol() : Symbol(getStringFromWasm(ptr, len))";
             Ok(format!(
-                "function(ptr, len) {{ return {}; }}",
+                "function(ptr/*: number*/, len/*: number*/) {{ return {}; }}",
                 me.add_heap_object(expr)
             ))
         })?;
@@ -280,7 +410,7 @@ impl<'a> Context<'a> {
                 false,
             );
             Ok(format!(
-                "function(i) {{ return typeof({}) === 'symbol' ? 1 : 0; }}",
+                "function(i/*: number*/) {{ return typeof({}) === 'symbol' ? 1 : 0; }}",
                 me.get_object("i")
             ))
         })?;
@@ -294,7 +424,7 @@ impl<'a> Context<'a> {
             );
             Ok(format!(
                 "
-                function(i) {{
+                function(i/*: number*/) {{
                     const val = {};
                     return typeof(val) === 'object' && val !== null ? 1 : 0;
                 }}",
@@ -310,7 +440,7 @@ impl<'a> Context<'a> {
                 false,
             );
             Ok(format!(
-                "function(i) {{ return typeof({}) === 'function' ? 1 : 0; }}",
+                "function(i/*: number*/) {{ return typeof({}) === 'function' ? 1 : 0; }}",
                 me.get_object("i")
             ))
         })?;
@@ -323,7 +453,7 @@ impl<'a> Context<'a> {
                 false,
             );
             Ok(format!(
-                "function(i) {{ return typeof({}) === 'string' ? 1 : 0; }}",
+                "function(i/*: number*/) {{ return typeof({}) === 'string' ? 1 : 0; }}",
                 me.get_object("i")
             ))
         })?;
@@ -339,7 +469,7 @@ impl<'a> Context<'a> {
             );
             Ok(format!(
                 "
-                function(i, len_ptr) {{
+                function(i/*: number*/, len_ptr/*: number*/) {{
                     let obj = {};
                     if (typeof(obj) !== 'string') return 0;
                     const ptr = passStringToWasm(obj);
@@ -356,7 +486,7 @@ impl<'a> Context<'a> {
             me.expose_uint32_memory();
 
             let debug_str = "
-                val => {
+                (val/*: any*/)/*: string*/ => {
                     // primitive types
                     const type = typeof val;
                     if (type == 'number' || type == 'boolean' || val == null) {
@@ -423,7 +553,7 @@ impl<'a> Context<'a> {
             ";
             Ok(format!(
                 "
-                function(i, len_ptr) {{
+                function(i/*: number*/, len_ptr/*: number*/) {{
                     const debug_str = {};
                     const toString = Object.prototype.toString;
                     const val = {};
@@ -438,6 +568,74 @@ impl<'a> Context<'a> {
             ))
         })?;
 
+        self.bind("__wbindgen_has_class_name", &|me| {
+            me.expose_get_string_from_wasm();
+            me.anyref.import_xform(
+                "__wbindgen_placeholder__",
+                "__wbindgen_has_class_name",
+                &[(0, false)],
+                false,
+            );
+            Ok(format!(
+                "
+                function(i/*: number*/, ptr/*: number*/, len/*: number*/) {{
+                    const val = {};
+                    const name = getStringFromWasm(ptr, len);
+                    if (val === null || (typeof(val) !== 'object' && typeof(val) !== 'function')) {{
+                        return 0;
+                    }}
+                    if (val[Symbol.toStringTag] === name) {{
+                        return 1;
+                    }}
+                    let proto = Object.getPrototypeOf(val);
+                    while (proto !== null) {{
+                        if (Object.prototype.hasOwnProperty.call(proto, 'constructor') &&
+                            proto.constructor.name === name) {{
+                            return 1;
+                        }}
+                        proto = Object.getPrototypeOf(proto);
+                    }}
+                    return 0;
+                }}
+                ",
+                me.get_object("i"),
+            ))
+        })?;
+
+        self.bind("__wbindgen_type_name", &|me| {
+            me.expose_pass_string_to_wasm()?;
+            me.expose_uint32_memory();
+            me.anyref.import_xform(
+                "__wbindgen_placeholder__",
+                "__wbindgen_type_name",
+                &[(0, false)],
+                false,
+            );
+            Ok(format!(
+                "
+                function(i/*: number*/, len_ptr/*: number*/) {{
+                    const val = {};
+                    let name/*: string*/ = typeof(val);
+                    if (val === null) {{
+                        name = 'null';
+                    }} else if (name === 'object' || name === 'function') {{
+                        const proto = Object.getPrototypeOf(val);
+                        const ctor = proto === null ? undefined : proto.constructor;
+                        if (ctor && typeof(ctor.name) === 'string' && ctor.name.length > 0) {{
+                            name = ctor.name;
+                        }} else {{
+                            name = 'Object';
+                        }}
+                    }}
+                    const ptr = passStringToWasm(name);
+                    getUint32Memory()[len_ptr / 4] = WASM_VECTOR_LEN;
+                    return ptr;
+                }}
+                ",
+                me.get_object("i"),
+            ))
+        })?;
+
         self.bind("__wbindgen_cb_drop", &|me| {
             me.anyref.import_xform(
                 "__wbindgen_placeholder__",
@@ -447,7 +645,7 @@ impl<'a> Context<'a> {
             );
             Ok(format!(
                 "
-                function(i) {{
+                function(i/*: number*/) {{
                     const obj = {}.original;
                     if (obj.cnt-- == 1) {{
                         obj.a = 0;
@@ -470,7 +668,7 @@ impl<'a> Context<'a> {
                     &[(0, true)],
                     false,
                 );
-                String::from("function(obj) {}")
+                String::from("function(obj/*: any*/) {}")
             } else {
                 me.expose_drop_ref();
                 "dropObject".to_string()
@@ -487,7 +685,10 @@ impl<'a> Context<'a> {
             );
             let expr = "JSON.parse(getStringFromWasm(ptr, len))";
             let expr = me.add_heap_object(expr);
-            Ok(format!("function(ptr, len) {{ return {}; }}", expr))
+            Ok(format!(
+                "function(ptr/*: number*/, len/*: number*/) {{ return {}; }}",
+                expr
+            ))
         })?;
 
         self.bind("__wbindgen_json_serialize", &|me| {
@@ -501,7 +702,7 @@ impl<'a> Context<'a> {
             me.expose_uint32_memory();
             Ok(format!(
                 "
-                function(idx, ptrptr) {{
+                function(idx/*: number*/, ptrptr/*: number*/) {{
                     const ptr = passStringToWasm(JSON.stringify({}));
                     getUint32Memory()[ptrptr / 4] = ptr;
                     return WASM_VECTOR_LEN;
@@ -511,9 +712,69 @@ impl<'a> Context<'a> {
             ))
         })?;
 
+        self.bind("__wbindgen_json_to_string", &|me| {
+            me.anyref.import_xform(
+                "__wbindgen_placeholder__",
+                "__wbindgen_json_to_string",
+                &[(0, false)],
+                false,
+            );
+            me.expose_pass_string_to_wasm()?;
+            me.expose_uint32_memory();
+            me.expose_handle_error()?;
+            Ok(format!(
+                "
+                function(i/*: number*/, len_ptr/*: number*/, exnptr/*: number*/) {{
+                    try {{
+                        const obj = {};
+                        const json = JSON.stringify(obj);
+                        if (json === undefined) {{
+                            throw new TypeError(`${{typeof obj}} values can't be converted to JSON`);
+                        }}
+                        const ptr = passStringToWasm(json);
+                        getUint32Memory()[len_ptr / 4] = WASM_VECTOR_LEN;
+                        return ptr;
+                    }} catch (e) {{
+                        handleError(exnptr, e);
+                        return 0;
+                    }}
+                }}
+                ",
+                me.get_object("i"),
+            ))
+        })?;
+
+        self.bind("__wbindgen_json_from_str", &|me| {
+            me.expose_get_string_from_wasm();
+            me.expose_uint8_memory();
+            me.anyref.import_xform(
+                "__wbindgen_placeholder__",
+                "__wbindgen_json_from_str",
+                &[],
+                true,
+            );
+            // Either the parsed value or the exception is returned, so both
+            // are passed to wasm the same way.
+            Ok(format!(
+                "
+                function(ptr/*: number*/, len/*: number*/, failed_ptr/*: number*/) {{
+                    let val/*: any*/;
+                    try {{
+                        val = JSON.parse(getStringFromWasm(ptr, len));
+                    }} catch (e) {{
+                        getUint8Memory()[failed_ptr] = 1;
+                        val = e;
+                    }}
+                    return {};
+                }}
+                ",
+                me.add_heap_object("val"),
+            ))
+        })?;
+
         self.bind("__wbindgen_jsval_eq", &|me| {
             Ok(format!(
-                "function(a, b) {{ return {} === {} ? 1 : 0; }}",
+                "function(a/*: number*/, b/*: number*/) {{ return {} === {} ? 1 : 0; }}",
                 me.get_object("a"),
                 me.get_object("b")
             ))
@@ -527,6 +788,77 @@ impl<'a> Context<'a> {
             ))
         })?;
 
+        self.bind("__wbindgen_global", &|me| {
+            me.anyref
+                .import_xform("__wbindgen_placeholder__", "__wbindgen_global", &[], true);
+            // `global` is only declared when `@types/node` is installed.
+            let ignore = if me.config.emit_ts { "// @ts-ignore\n" } else { "" };
+            me.global(&format!(
+                "
+                let cachedGlobal/*: any*/ = null;
+                function getGlobal() {{
+                    if (cachedGlobal !== null) return cachedGlobal;
+                    if (typeof globalThis !== 'undefined') cachedGlobal = globalThis;
+                    else if (typeof self !== 'undefined') cachedGlobal = self;
+                    else if (typeof window !== 'undefined') cachedGlobal = window;
+                    {}else if (typeof global !== 'undefined') cachedGlobal = global;
+                    else throw new Error('unable to locate the global object');
+                    return cachedGlobal;
+                }}
+                ",
+                ignore
+            ));
+
+            Ok(format!(
+                "function() {{ return {}; }}",
+                me.add_heap_object("getGlobal()")
+            ))
+        })?;
+
+        self.bind("__wbindgen_import", &|me| {
+            me.anyref
+                .import_xform("__wbindgen_placeholder__", "__wbindgen_import", &[], true);
+            me.expose_get_string_from_wasm();
+            let import = if me.use_node_require() {
+                "new Promise(resolve => resolve(require(specifier)))"
+            } else {
+                "import(specifier)"
+            };
+            Ok(format!(
+                "
+                function(ptr/*: number*/, len/*: number*/) {{
+                    const specifier = getStringFromWasm(ptr, len);
+                    return {};
+                }}
+                ",
+                me.add_heap_object(import)
+            ))
+        })?;
+
+        self.bind("__wbindgen_now", &|me| {
+            me.expose_host_sources();
+            Ok(String::from("function() { return hostNow(); }"))
+        })?;
+
+        self.bind("__wbindgen_random", &|me| {
+            me.expose_host_sources();
+            Ok(String::from("function() { return hostRandom(); }"))
+        })?;
+
+        self.bind("__wbindgen_random_fill", &|me| {
+            me.expose_host_sources();
+            me.expose_uint8_memory();
+            Ok(String::from(
+                "
+                function(ptr/*: number*/, len/*: number*/) {
+                    const buf = new Uint8Array(len);
+                    hostRandomFill(buf);
+                    getUint8Memory().set(buf, ptr);
+                }
+                ",
+            ))
+        })?;
+
         self.bind("__wbindgen_module", &|me| {
             if !me.config.no_modules {
                 bail!(
@@ -542,13 +874,36 @@ impl<'a> Context<'a> {
 
         self.bind("__wbindgen_rethrow", &|me| {
             Ok(format!(
-                "function(idx) {{ throw {}; }}",
+                "function(idx/*: number*/) {{ throw {}; }}",
                 me.take_object("idx")
             ))
         })?;
 
         closures::rewrite(self).with_context(|_| "failed to generate internal closure shims")?;
         self.write_classes()?;
+        self.write_enums();
+        self.write_renamed_exports()?;
+        if self.config.middleware {
+            self.expose_call_with_middleware();
+        }
+        if self.config.profile_boundary {
+            self.expose_profile_boundary();
+        }
+        if self.lazy_exports.len() > 0 {
+            self.global(&format!(
+                "
+                let LAZY_WASM/*: Promise<any> | undefined*/;
+
+                function loadLazyWasm() {{
+                    if (LAZY_WASM === undefined) {{
+                        LAZY_WASM = import('./{}_lazy_bg');
+                    }}
+                    return LAZY_WASM;
+                }}
+            ",
+                module_name
+            ));
+        }
         self.anyref.run(self.module)?;
 
         // After the anyref pass has executed, if this intrinsic is needed then
@@ -574,7 +929,7 @@ impl<'a> Context<'a> {
             me.expose_add_heap_object();
             Ok(String::from(
                 "
-                function(idx) {
+                function(idx/*: number*/) {
                     return addHeapObject(getObject(idx));
                 }
                 ",
@@ -585,7 +940,7 @@ impl<'a> Context<'a> {
         // remove usages of this.
         self.bind("__wbindgen_object_drop_ref", &|me| {
             me.expose_drop_ref();
-            Ok(String::from("function(i) { dropObject(i); }"))
+            Ok(String::from("function(i/*: number*/) { dropObject(i); }"))
         })?;
 
         self.unexport_unused_internal_exports();
@@ -629,20 +984,27 @@ impl<'a> Context<'a> {
             me.expose_get_string_from_wasm();
             Ok(String::from(
                 "
-                function(ptr, len) {
+                function(ptr/*: number*/, len/*: number*/) {
                     throw new Error(getStringFromWasm(ptr, len));
                 }
+
                 ",
             ))
         })?;
 
+        self.write_intrinsics();
         self.rewrite_imports(module_name);
+        if self.config.required_imports {
+            self.write_required_imports();
+        }
         self.update_producers_section();
 
         // Cause any future calls to `should_write_global` to panic, making sure
         // we don't ask for items which we can no longer emit.
         drop(self.exposed_globals.take().unwrap());
 
+        let set_tags = self.export_tags();
+
         let mut js = if self.config.threads.is_some() {
             // TODO: It's not clear right now how to best use threads with
             // bundlers like webpack. We need a way to get the existing
@@ -674,9 +1036,33 @@ impl<'a> Context<'a> {
     var memory;
     const __exports = {{}};
     {globals}
+    const imports = {{ './{module}': __exports }};
+    function initSync(module_or_bytes, maybe_memory) {{
+        let module;
+        if (module_or_bytes instanceof WebAssembly.Module) {{
+            module = module_or_bytes;
+            memory = __exports.memory = maybe_memory;
+        }} else {{
+            {sync_guard}
+            module = new WebAssembly.Module(module_or_bytes);
+            memory = __exports.memory = {init_memory};
+        }}
+        const instance = new WebAssembly.Instance(module, imports);
+        wasm = init.wasm = instance.exports;
+        {set_tags}
+        init.__wbindgen_wasm_instance = instance;
+        init.__wbindgen_wasm_module = module;
+        init.__wbindgen_wasm_memory = __exports.memory;
+        {start}
+    }}
+    function initThread(module, memory) {{
+        if (!(module instanceof WebAssembly.Module) || !(memory instanceof WebAssembly.Memory)) {{
+            throw new Error('`initThread` must be given the module and memory of the main thread');
+        }}
+        initSync(module, memory);
+    }}
     function init(module_or_path, maybe_memory) {{
         let result;
-        const imports = {{ './{module}': __exports }};
         if (module_or_path instanceof WebAssembly.Module) {{
             memory = __exports.memory = maybe_memory;
             result = WebAssembly.instantiate(module_or_path, imports)
@@ -706,12 +1092,15 @@ impl<'a> Context<'a> {
         }}
         return result.then(({{instance, module}}) => {{
             wasm = init.wasm = instance.exports;
+            {set_tags}
             init.__wbindgen_wasm_instance = instance;
             init.__wbindgen_wasm_module = module;
             init.__wbindgen_wasm_memory = __exports.memory;
             {start}
         }});
     }};
+    init.initSync = initSync;
+    init.initThread = initThread;
     self.{global_name} = Object.assign(init, __exports);
 }})();",
                 globals = self.globals,
@@ -722,6 +1111,8 @@ impl<'a> Context<'a> {
                     .as_ref()
                     .map(|s| &**s)
                     .unwrap_or("wasm_bindgen"),
+                sync_guard = self.sync_init_guard("module_or_bytes"),
+                set_tags = set_tags,
                 init_memory = memory,
                 start = if has_start_function {
                     "wasm.__wbindgen_start();"
@@ -730,22 +1121,74 @@ impl<'a> Context<'a> {
                 },
             )
         } else if self.config.no_modules {
+            // All state of the glue lives inside `newInstance` so it can be
+            // run again by `initInstance` with `--multi-instance` to create
+            // fully independent instances of the module, each with their own
+            // memory and classes.
             format!(
                 "\
 (function() {{
+    {script_url}
+    function isInitOptions(arg) {{
+        return typeof arg === 'object' && arg !== null &&
+            !(arg instanceof WebAssembly.Module) &&
+            !(typeof Request === 'function' && arg instanceof Request) &&
+            !(typeof URL === 'function' && arg instanceof URL);
+    }}
+    function fetchWasm(path, options) {{
+        // A custom fetcher may decline to load the file by resolving to
+        // nothing, leaving it to `fetch`.
+        const custom = options.fetcher === undefined ? undefined : options.fetcher(path);
+        return Promise.resolve(custom).then(response => {{
+            if (response === undefined || response === null) {{
+                return fetch(path{fetch_options});
+            }}
+            return response;
+        }});
+    }}
+    {module_cache}
+    function newInstance() {{
     var wasm;
     const __exports = {{}};
     {globals}
+    const imports = {{ './{module}': __exports }};
+    let resolveReady;
+    const ready = new Promise(resolve => resolveReady = resolve);
+    function finishInit(instance) {{
+        wasm = init.wasm = instance.exports;
+        {set_tags}
+        {start}
+        resolveReady(init);
+        return init;
+    }}
+    function initSync(module_or_bytes) {{
+        if (!(module_or_bytes instanceof WebAssembly.Module)) {{
+            {sync_guard}
+            module_or_bytes = new WebAssembly.Module(module_or_bytes);
+        }}
+        return finishInit(new WebAssembly.Instance(module_or_bytes, imports));
+    }}
     function init(path_or_module) {{
+        let options = {{}};
+        if (isInitOptions(path_or_module)) {{
+            options = path_or_module;
+            path_or_module = undefined;
+        }}
+        {default_path}
         let instantiation;
-        const imports = {{ './{module}': __exports }};
         if (path_or_module instanceof WebAssembly.Module) {{
             instantiation = WebAssembly.instantiate(path_or_module, imports)
                 .then(instance => {{
                     return {{ instance, module: path_or_module }}
                 }});
+        }} else if (options.cache && typeof indexedDB !== 'undefined') {{
+            instantiation = loadCachedModule(path_or_module, () => compileModule(fetchWasm(path_or_module, options)))
+                .then(module => WebAssembly.instantiate(module, imports))
+                .then(instance => {{
+                    return {{ instance }}
+                }});
         }} else {{
-            const data = fetch(path_or_module);
+            const data = fetchWasm(path_or_module, options);
             if (typeof WebAssembly.instantiateStreaming === 'function') {{
                 instantiation = WebAssembly.instantiateStreaming(data, imports)
                     .catch(e => {{
@@ -764,15 +1207,49 @@ impl<'a> Context<'a> {
                     .then(buffer => WebAssembly.instantiate(buffer, imports));
             }}
         }}
-        return instantiation.then(({{instance}}) => {{
-            wasm = init.wasm = instance.exports;
-            {start}
-        }});
+        return instantiation.then(({{instance}}) => finishInit(instance));
     }};
-    self.{global_name} = Object.assign(init, __exports);
+    init.initSync = initSync;
+    init.ready = ready;
+    return Object.assign(init, __exports);
+    }}
+    const init = newInstance();
+    {init_instance}
+    if (typeof module === 'object' && typeof module.exports === 'object') {{
+        module.exports = init;
+    }} else if (typeof define === 'function' && define.amd) {{
+        define([], () => init);
+    }} else {{
+        (typeof self !== 'undefined' ? self : this).{global_name} = init;
+    }}
 }})();",
                 globals = self.globals,
                 module = module_name,
+                sync_guard = self.sync_init_guard("module_or_bytes"),
+                set_tags = set_tags,
+                init_instance = if self.config.multi_instance {
+                    "init.initInstance = function(path_or_module) {
+                        return newInstance()(path_or_module);
+                    };"
+                } else {
+                    ""
+                },
+                script_url = match self.config.wasm_url_base.as_ref().map(|s| &**s) {
+                    // The script is only current while it first runs.
+                    None | Some("script") if !self.config.browser_extension => {
+                        "const scriptUrl = typeof document !== 'undefined' && document.currentScript \
+                             ? document.currentScript.src \
+                             : self.location.href;"
+                    }
+                    _ => "",
+                },
+                default_path = self.default_wasm_path(module_name),
+                module_cache = MODULE_CACHE_JS,
+                fetch_options = if self.config.hashed_wasm {
+                    format!(", {{ integrity: '{}' }}", HASHED_WASM_INTEGRITY)
+                } else {
+                    String::new()
+                },
                 global_name = self
                     .config
                     .no_modules_global
@@ -785,6 +1262,8 @@ impl<'a> Context<'a> {
                     ""
                 },
             )
+        } else if self.config.deno {
+            self.deno_js(module_name, has_start_function, &set_tags)
         } else {
             // In the "we're pretending to be an ES module use case if we've got
             // a start function then we use an injected shim to actually execute
@@ -794,11 +1273,12 @@ impl<'a> Context<'a> {
                 self.inject_start_shim();
             }
 
-            let import_wasm = if self.globals.len() == 0 {
+            let import_wasm = if self.globals.len() == 0 && set_tags.is_empty() {
                 String::new()
             } else if self.use_node_require() {
                 self.footer
-                    .push_str(&format!("wasm = require('./{}_bg');", module_name));
+                    .push_str(&format!("wasm = require('./{}_bg');\n", module_name));
+                self.footer.push_str(&set_tags);
                 format!("var wasm;")
             } else {
                 format!("import * as wasm from './{}_bg';", module_name)
@@ -825,9 +1305,239 @@ impl<'a> Context<'a> {
             js = js.replace("\n\n\n", "\n\n");
         }
 
+        if self.config.no_modules {
+            self.typescript.push_str(if self.config.threads.is_some() {
+                "export function initSync(module_or_bytes: WebAssembly.Module | BufferSource, \
+                 maybe_memory?: WebAssembly.Memory): void;\n\
+                 export function initThread(module: WebAssembly.Module, \
+                 memory: WebAssembly.Memory): void;\n"
+            } else {
+                "export function initSync(module_or_bytes: WebAssembly.Module | \
+                 BufferSource): typeof wasm_bindgen;\n\
+                 export const ready: Promise<typeof wasm_bindgen>;\n\
+                 export interface InitOptions {\n  \
+                 base?: string | URL;\n  \
+                 fetcher?: (url: string) => Promise<Response | undefined> | Response | undefined;\n  \
+                 cache?: boolean;\n\
+                 }\n"
+            });
+            if self.config.multi_instance {
+                self.typescript.push_str(
+                    "export function initInstance(module_or_path?: WebAssembly.Module | \
+                     RequestInfo | InitOptions): Promise<typeof wasm_bindgen>;\n",
+                );
+            }
+            return Ok((js, self.no_modules_typescript()));
+        }
+
         Ok((js, self.typescript.clone()))
     }
 
+    /// Generates the glue for `--deno`, an ES module which instantiates the
+    /// wasm module itself with top-level `await`, as Deno can't import wasm
+    /// modules directly.
+    ///
+    /// The wasm module imports the glue's shims from the glue itself, which
+    /// is passed to it by importing the glue from within itself.
+    fn deno_js(&mut self, module_name: &str, has_start_function: bool, set_tags: &str) -> String {
+        let extension = if self.config.emit_ts { "ts" } else { "js" };
+        let modules = self
+            .module
+            .imports
+            .iter()
+            .map(|i| i.module.clone())
+            .collect::<BTreeSet<_>>();
+        let mut import_decls = String::new();
+        let mut wasm_imports = String::new();
+        for (i, module) in modules.iter().enumerate() {
+            let specifier = if *module == format!("./{}", module_name) {
+                format!("./{}.{}", module_name, extension)
+            } else {
+                module.clone()
+            };
+            import_decls.push_str(&format!(
+                "import * as __wbg_import{} from '{}';\n",
+                i, specifier
+            ));
+            wasm_imports.push_str(&format!("'{}': __wbg_import{},\n", module, i));
+        }
+
+        // Deno picks up declarations for JS files through a reference to them.
+        let types = if self.config.typescript && !self.config.emit_ts {
+            format!("/// <reference types=\"./{}.d.ts\" />\n", module_name)
+        } else {
+            String::new()
+        };
+        format!(
+            "\
+            {types}\
+            /* tslint:disable */\n\
+            {import_decls}\n\
+            {imports}\n\
+            {imports_post}\n\
+            let wasm;\n\
+
+            {globals}\n\
+            {footer}\n\
+
+            const wasmUrl = new URL('./{module}_bg.wasm', import.meta.url);
+            const wasmBytes = wasmUrl.protocol === 'file:'
+                ? await Deno.readFile(wasmUrl)
+                : await fetch(wasmUrl).then(response => response.arrayBuffer());
+            const wasmImports = {{
+                {wasm_imports}
+            }};
+            wasm = (await WebAssembly.instantiate(wasmBytes, wasmImports)).instance.exports;
+            {set_tags}
+            {start}
+            ",
+            types = types,
+            import_decls = import_decls,
+            imports = self.imports,
+            imports_post = self.imports_post,
+            globals = self.globals,
+            footer = self.footer,
+            module = module_name,
+            wasm_imports = wasm_imports,
+            set_tags = set_tags,
+            start = if has_start_function {
+                "wasm.__wbindgen_start();"
+            } else {
+                ""
+            },
+        )
+    }
+
+    /// Exposes the exception tags exported by the wasm module, returning JS
+    /// which sets them once the module has been instantiated for targets
+    /// which instantiate it themselves.
+    ///
+    /// Rust panics are thrown with the `__cpp_exception` tag, which is
+    /// exposed as `panicTag` so JS catching an exception thrown by wasm can
+    /// tell whether it's a panic with `e.is(panicTag)`.
+    fn export_tags(&mut self) -> String {
+        let mut set = String::new();
+        for name in self.exported_tags.clone() {
+            let js_name = match name.as_str() {
+                "__cpp_exception" => "panicTag",
+                name if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => name,
+                _ => continue,
+            };
+            let ts = format!("export const {}: WebAssembly.Tag;\n", js_name);
+            self.typescript.push_str(&ts);
+            if self.config.no_modules {
+                set.push_str(&format!("init.{} = wasm.{};\n", js_name, name));
+            } else if self.config.deno {
+                self.global(&format!("export let {}/*: WebAssembly.Tag*/;\n", js_name));
+                set.push_str(&format!("{} = wasm.{};\n", js_name, name));
+            } else if self.use_node_require() {
+                set.push_str(&format!("module.exports.{} = wasm.{};\n", js_name, name));
+            } else {
+                self.export(js_name, &format!("wasm.{}", name), None);
+            }
+        }
+        set
+    }
+
+    /// With `--no-modules` there's no module to export from, so wrap all the
+    /// TypeScript declarations in a namespace describing the global value.
+    fn no_modules_typescript(&self) -> String {
+        let global = self
+            .config
+            .no_modules_global
+            .as_ref()
+            .map(|s| &**s)
+            .unwrap_or("wasm_bindgen");
+        let (args, ret) = if self.config.threads.is_some() {
+            (
+                "module_or_path: WebAssembly.Module | RequestInfo, maybe_memory?: WebAssembly.Memory",
+                "void",
+            )
+        } else {
+            (
+                "module_or_path?: WebAssembly.Module | RequestInfo | wasm_bindgen.InitOptions",
+                "typeof wasm_bindgen",
+            )
+        };
+        let body = self
+            .typescript
+            .trim_start_matches("/* tslint:disable */\n")
+            .replace("typeof wasm_bindgen", &format!("typeof {}", global));
+        format!(
+            "/* tslint:disable */\n\
+             declare namespace {global} {{\n\
+             {body}\n\
+             }}\n\
+             \n\
+             declare function {global}({args}): Promise<{ret}>;\n",
+            global = global,
+            body = body,
+            args = args.replace("wasm_bindgen", global),
+            ret = ret.replace("wasm_bindgen", global),
+        )
+    }
+
+    /// Returns JS which picks the wasm file to load when `init` isn't given
+    /// one, resolving its name against the `base` passed to `init` or else
+    /// the base configured with `--wasm-url-base`.
+    ///
+    /// For browser extensions the wasm file is packaged alongside the JS, so
+    /// by default it's loaded from the extension's own origin. This is the
+    /// only way to load it which Manifest V3's restrictions on remote code
+    /// allow anyway. Otherwise it's loaded from next to the script by default.
+    fn default_wasm_path(&self, module_name: &str) -> String {
+        let file = if self.config.hashed_wasm {
+            HASHED_WASM_FILE.to_string()
+        } else {
+            format!("{}_bg.wasm", module_name)
+        };
+        let default = if self.config.browser_extension {
+            format!(
+                "(typeof browser !== 'undefined' ? browser.runtime : chrome.runtime)\
+                 .getURL('{}')",
+                file
+            )
+        } else {
+            let base = match self.config.wasm_url_base.as_ref().map(|s| &**s) {
+                None | Some("script") => "scriptUrl".to_string(),
+                Some("document") => "document.baseURI".to_string(),
+                Some(url) => format!("{:?}", url),
+            };
+            format!("new URL('{}', {}).href", file, base)
+        };
+        format!(
+            "\
+            if (path_or_module === undefined) {{
+                path_or_module = options.base !== undefined
+                    ? new URL('{}', options.base).href
+                    : {};
+            }}
+            ",
+            file, default
+        )
+    }
+
+    /// Returns JS which throws if the bytes in `bytes` are too large to be
+    /// compiled synchronously, as configured by `--sync-init-max-size`.
+    fn sync_init_guard(&self, bytes: &str) -> String {
+        let max = match self.config.sync_init_max_size {
+            Some(max) => max,
+            None => return String::new(),
+        };
+        format!(
+            "\
+            if ({bytes}.byteLength > {max}) {{
+                throw new Error(`module is ${{{bytes}.byteLength}} bytes, but \
+                                 at most {max} bytes can be compiled \
+                                 synchronously; use the asynchronous \
+                                 \\`init\\` function instead`);
+            }}
+            ",
+            bytes = bytes,
+            max = max,
+        )
+    }
+
     fn bind(
         &mut self,
         name: &str,
@@ -838,47 +1548,211 @@ impl<'a> Context<'a> {
         }
         let contents = f(self)
             .with_context(|_| format!("failed to generate internal JS function `{}`", name))?;
-        self.export(name, &contents, None);
+        self.intrinsics.push((name.to_string(), contents));
         Ok(())
     }
 
-    fn write_classes(&mut self) -> Result<(), Error> {
-        for (class, exports) in self.exported_classes.take().unwrap() {
-            self.write_class(&class, &exports)?;
+    /// Exports all intrinsics bound with `bind` which the wasm module still
+    /// imports.
+    ///
+    /// Passes like the anyref transformation and gc can remove the last use
+    /// of an import after it's been bound, so intrinsics are only emitted
+    /// once the module is otherwise finished to keep the import object to the
+    /// bare minimum.
+    fn write_intrinsics(&mut self) {
+        for (name, contents) in mem::replace(&mut self.intrinsics, Vec::new()) {
+            if self.wasm_import_needed(&name) {
+                self.export(&name, &contents, None);
+            }
         }
-        Ok(())
     }
 
-    fn write_class(&mut self, name: &str, class: &ExportedClass) -> Result<(), Error> {
-        let mut dst = format!("class {} {{\n", name);
-        let mut ts_dst = format!("export {}", dst);
+    /// Exports a `requiredImports` function describing everything the wasm
+    /// module imports, for hosts which instantiate the module themselves.
+    fn write_required_imports(&mut self) {
+        let imports = self
+            .module
+            .imports
+            .iter()
+            .map(|import| {
+                let kind = match import.kind {
+                    walrus::ImportKind::Function(_) => "function",
+                    walrus::ImportKind::Table(_) => "table",
+                    walrus::ImportKind::Memory(_) => "memory",
+                    walrus::ImportKind::Global(_) => "global",
+                };
+                format!(
+                    "{{ module: '{}', name: '{}', kind: '{}' }}",
+                    import.module, import.name, kind
+                )
+            })
+            .collect::<Vec<_>>();
+        self.export(
+            "requiredImports",
+            &format!("function() {{\nreturn [\n{}\n];\n}}", imports.join(",\n")),
+            Some(format_doc_comments(
+                &[" Returns the module and name of every import of the wasm module."],
+                None,
+            )),
+        );
+        self.typescript.push_str(
+            "export function requiredImports(): { module: string, name: string, \
+             kind: 'function' | 'table' | 'memory' | 'global' }[];\n",
+        );
+    }
 
-        let (mkweakref, freeref) = if self.config.weak_refs {
-            // When weak refs are enabled we use them to automatically free the
-            // contents of an exported rust class when it's gc'd. Note that a
-            // manual `free` function still exists for deterministic
-            // destruction.
-            //
-            // This is implemented by using a `WeakRefGroup` to run finalizers
-            // for all `WeakRef` objects that it creates. Upon construction of
-            // a new wasm object we use `makeRef` with "holdings" of a thunk to
-            // free the wasm instance.  Once the `this` (the instance we're
-            // creating) is gc'd then the finalizer will run with the
-            // `WeakRef`, and we'll pull out the `holdings`, our pointer.
-            //
-            // Note, though, that if manual finalization happens we want to
-            // cancel the `WeakRef`-generated finalization, so we retain the
-            // `WeakRef` in a global map. This global map is then used to
-            // `drop()` the `WeakRef` (cancel finalization) whenever it is
-            // finalized.
-            self.expose_cleanup_groups();
-            let mk = format!("addCleanup(this, this.ptr, free{});", name);
-            let free = "
-                CLEANUPS_MAP.get(ptr).drop();
-                CLEANUPS_MAP.delete(ptr);
-            ";
-            (mk, free)
-        } else {
+    /// Returns the exported class that errors of `export` are thrown as, if
+    /// it returns a `Result` with an exported struct as its error.
+    fn thrown_class<'c>(&self, export: &decode::Export<'c>) -> Option<&'c str> {
+        export
+            .throws
+            .filter(|name| self.declared_classes.contains(*name))
+    }
+
+    /// Records the types in `program` that need to be known while generating
+    /// bindings for other programs, like enums so impl blocks for them can be
+    /// told apart from those of structs.
+    pub fn declare_types(&mut self, program: &decode::Program) {
+        for struct_ in program.structs.iter() {
+            self.declared_classes.insert(struct_.name.to_string());
+            if struct_.enumerable {
+                self.enumerable_classes.insert(struct_.name.to_string());
+            }
+            if struct_.frozen {
+                self.frozen_classes.insert(struct_.name.to_string());
+            }
+            if struct_.plain {
+                self.plain_classes.insert(struct_.name.to_string());
+            }
+        }
+        let enums = self.exported_enums.as_mut().expect("enums already written");
+        for enum_ in program.enums.iter() {
+            enums.insert(
+                enum_.name.to_string(),
+                ExportedEnum {
+                    repr: enum_.repr.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    fn write_enums(&mut self) {
+        for (name, enum_) in self.exported_enums.take().unwrap() {
+            // Methods are non-enumerable so only the variants show up when
+            // iterating over the enum.
+            let contents = if enum_.methods.is_empty() {
+                format!("Object.freeze({{ {} }})", enum_.variants)
+            } else {
+                format!(
+                    "Object.freeze(Object.defineProperties({{ {} }}, {{\n{}}}))",
+                    enum_.variants, enum_.methods
+                )
+            };
+            self.export(&name, &contents, Some(enum_.comments));
+            // Arguments and return values of the enum's type are annotated
+            // with its name, so it needs to be a type as well as a value.
+            if self.config.emit_ts {
+                let ty = if enum_.repr.ends_with("64") { "BigInt" } else { "number" };
+                self.global(&format!("export type {} = {};\n", name, ty));
+            }
+            self.typescript.push_str(&enum_.typescript);
+            if enum_.repr.ends_with("64") {
+                self.typescript.push_str(&enum_.typescript_methods);
+                self.typescript.push_str("\n};\n");
+            } else {
+                self.typescript.push_str("\n}\n");
+                if !enum_.typescript_methods.is_empty() {
+                    self.typescript
+                        .push_str(&format!("export namespace {} {{\n", name));
+                    self.typescript.push_str(&enum_.typescript_methods);
+                    self.typescript.push_str("}\n");
+                }
+            }
+        }
+    }
+
+    /// Exports deprecated aliases of exports under their old names.
+    fn write_renamed_exports(&mut self) -> Result<(), Error> {
+        for (old, new) in self.api_renames.clone() {
+            let signature = match self.api.get(&new) {
+                Some(s) => s.to_string(),
+                None => bail!("can't rename `{}` to `{}` as nothing is exported as `{}`", old, new, new),
+            };
+            if self.api.get(&old).is_some() {
+                bail!("can't rename `{}` to `{}` as `{}` is still exported", old, new, old);
+            }
+            let target = if self.use_node_require() {
+                format!("module.exports.{}", new)
+            } else if self.config.no_modules {
+                format!("__exports.{}", new)
+            } else {
+                new.clone()
+            };
+            let deprecated = format!("/**\n* @deprecated Use `{}` instead.\n*/\n", new);
+            self.export(&old, &target, Some(deprecated.clone()));
+            self.typescript.push_str(&deprecated);
+            self.typescript
+                .push_str(&format!("export const {}: typeof {};\n", old, new));
+            if signature == "class" {
+                self.typescript
+                    .push_str(&format!("export type {} = {};\n", old, new));
+            }
+            self.api.insert(&old, &signature);
+        }
+        Ok(())
+    }
+
+    fn write_classes(&mut self) -> Result<(), Error> {
+        for (class, exports) in self.exported_classes.take().unwrap() {
+            self.write_class(&class, &exports)?;
+        }
+        Ok(())
+    }
+
+    fn write_class(&mut self, name: &str, class: &ExportedClass) -> Result<(), Error> {
+        let mut dst = format!("class {} {{\n", name);
+        let mut ts_dst = format!("export {}", dst);
+        if self.config.emit_ts {
+            dst.push_str("ptr: number;\n");
+        }
+
+        self.api.insert(name, "class");
+
+        let frozen = self.frozen_classes.contains(name);
+        let (mkweakref, freeref) = if frozen {
+            // Frozen objects can't have their pointer cleared by `free`, so
+            // they're only ever freed once they've been gc'd, which
+            // `freezeObject` arranges for.
+            self.expose_freeze_object();
+            let mk = format!("freezeObject(this, this.ptr, free{});", name);
+            (mk, "")
+        } else if self.config.weak_refs {
+            // When weak refs are enabled we use them to automatically free the
+            // contents of an exported rust class when it's gc'd. Note that a
+            // manual `free` function still exists for deterministic
+            // destruction.
+            //
+            // This is implemented by using a `WeakRefGroup` to run finalizers
+            // for all `WeakRef` objects that it creates. Upon construction of
+            // a new wasm object we use `makeRef` with "holdings" of a thunk to
+            // free the wasm instance.  Once the `this` (the instance we're
+            // creating) is gc'd then the finalizer will run with the
+            // `WeakRef`, and we'll pull out the `holdings`, our pointer.
+            //
+            // Note, though, that if manual finalization happens we want to
+            // cancel the `WeakRef`-generated finalization, so we retain the
+            // `WeakRef` in a global map. This global map is then used to
+            // `drop()` the `WeakRef` (cancel finalization) whenever it is
+            // finalized.
+            self.expose_cleanup_groups();
+            let mk = format!("addCleanup(this, this.ptr, free{});", name);
+            let free = "
+                CLEANUPS_MAP.get(ptr).drop();
+                CLEANUPS_MAP.delete(ptr);
+            ";
+            (mk, free)
+        } else {
             (String::new(), "")
         };
 
@@ -900,28 +1774,36 @@ impl<'a> Context<'a> {
                 .import_xform("__wbindgen_placeholder__", &new_name, &[], true);
             let expr = format!("{}.__wrap(ptr)", name);
             let expr = self.add_heap_object(&expr);
-            let body = format!("function(ptr) {{ return {}; }}", expr);
+            let body = format!("function(ptr/*: number*/) {{ return {}; }}", expr);
             self.export(&new_name, &body, None);
         }
 
         if wrap_needed {
+            let expose = if self.enumerable_classes.contains(name) {
+                self.expose_expose_properties();
+                format!("exposeProperties(obj, {}.prototype);", name)
+            } else {
+                String::new()
+            };
             dst.push_str(&format!(
                 "
-                static __wrap(ptr) {{
+                static __wrap(ptr/*: number*/) {{
                     const obj = Object.create({}.prototype);
                     obj.ptr = ptr;
                     {}
+                    {}
                     return obj;
                 }}
                 ",
                 name,
+                expose,
                 mkweakref.replace("this", "obj"),
             ));
         }
 
         self.global(&format!(
             "
-            function free{}(ptr) {{
+            function free{}(ptr/*: number*/) {{
                 {}
                 wasm.{}(ptr);
             }}
@@ -930,17 +1812,31 @@ impl<'a> Context<'a> {
             freeref,
             wasm_bindgen_shared::free_function(&name)
         ));
-        dst.push_str(&format!(
-            "
-            free() {{
-                const ptr = this.ptr;
-                this.ptr = 0;
-                free{}(ptr);
-            }}
-            ",
-            name,
-        ));
-        ts_dst.push_str("  free(): void;");
+        if !frozen {
+            dst.push_str(&format!(
+                "
+                free() {{
+                    const ptr = this.ptr;
+                    this.ptr = 0;
+                    free{}(ptr);
+                }}
+                ",
+                name,
+            ));
+            ts_dst.push_str("  free(): void;");
+            self.api.insert(&format!("{}.free", name), "method(): void");
+        }
+        if class.js_tostring {
+            // Node's `util.inspect`, and so `console.log`, formats objects
+            // with this method.
+            dst.push_str(
+                "
+                [Symbol.for('nodejs.util.inspect.custom')]() {
+                    return this.toString();
+                }
+                ",
+            );
+        }
         dst.push_str(&class.contents);
         ts_dst.push_str(&class.typescript);
         dst.push_str("}\n");
@@ -948,6 +1844,10 @@ impl<'a> Context<'a> {
 
         self.export(&name, &dst, Some(class.comments.clone()));
         self.typescript.push_str(&ts_dst);
+        if class.js_tostring {
+            self.expose_devtools_formatter();
+            self.global(&format!("formattedClasses.push({});\n", name));
+        }
 
         Ok(())
     }
@@ -1079,7 +1979,7 @@ impl<'a> Context<'a> {
         // the linked list of heap slots that are free.
         self.global(&format!(
             "
-            function dropObject(idx) {{
+            function dropObject(idx/*: number*/) {{
                 if (idx < {}) return;
                 heap[idx] = heap_next;
                 heap_next = idx;
@@ -1115,7 +2015,7 @@ impl<'a> Context<'a> {
 
         // Accessing a heap object is just a simple index operation due to how
         // the stack/heap are laid out.
-        self.global("function getObject(idx) { return heap[idx]; }");
+        self.global("function getObject(idx/*: number*/) { return heap[idx]; }");
     }
 
     fn expose_assert_num(&mut self) {
@@ -1124,7 +2024,7 @@ impl<'a> Context<'a> {
         }
         self.global(&format!(
             "
-            function _assertNum(n) {{
+            function _assertNum(n/*: any*/) {{
                 if (typeof(n) !== 'number') throw new Error('expected a number argument');
             }}
             "
@@ -1137,7 +2037,7 @@ impl<'a> Context<'a> {
         }
         self.global(&format!(
             "
-            function _assertBoolean(n) {{
+            function _assertBoolean(n/*: any*/) {{
                 if (typeof(n) !== 'boolean') {{
                     throw new Error('expected a boolean argument');
                 }}
@@ -1170,7 +2070,7 @@ impl<'a> Context<'a> {
         };
         self.global(&format!(
             "
-            function passStringToWasm(arg) {{
+            function passStringToWasm(arg/*: string*/) {{
                 {}
                 const buf = cachedTextEncoder.encode(arg);
                 const ptr = wasm.__wbindgen_malloc(buf.length);
@@ -1186,32 +2086,32 @@ impl<'a> Context<'a> {
 
     fn expose_pass_array8_to_wasm(&mut self) -> Result<(), Error> {
         self.expose_uint8_memory();
-        self.pass_array_to_wasm("passArray8ToWasm", "getUint8Memory", 1)
+        self.pass_array_to_wasm("passArray8ToWasm", "getUint8Memory", 1, "number")
     }
 
     fn expose_pass_array16_to_wasm(&mut self) -> Result<(), Error> {
         self.expose_uint16_memory();
-        self.pass_array_to_wasm("passArray16ToWasm", "getUint16Memory", 2)
+        self.pass_array_to_wasm("passArray16ToWasm", "getUint16Memory", 2, "number")
     }
 
     fn expose_pass_array32_to_wasm(&mut self) -> Result<(), Error> {
         self.expose_uint32_memory();
-        self.pass_array_to_wasm("passArray32ToWasm", "getUint32Memory", 4)
+        self.pass_array_to_wasm("passArray32ToWasm", "getUint32Memory", 4, "number")
     }
 
     fn expose_pass_array64_to_wasm(&mut self) -> Result<(), Error> {
         self.expose_uint64_memory();
-        self.pass_array_to_wasm("passArray64ToWasm", "getUint64Memory", 8)
+        self.pass_array_to_wasm("passArray64ToWasm", "getUint64Memory", 8, "bigint")
     }
 
     fn expose_pass_array_f32_to_wasm(&mut self) -> Result<(), Error> {
         self.expose_f32_memory();
-        self.pass_array_to_wasm("passArrayF32ToWasm", "getFloat32Memory", 4)
+        self.pass_array_to_wasm("passArrayF32ToWasm", "getFloat32Memory", 4, "number")
     }
 
     fn expose_pass_array_f64_to_wasm(&mut self) -> Result<(), Error> {
         self.expose_f64_memory();
-        self.pass_array_to_wasm("passArrayF64ToWasm", "getFloat64Memory", 8)
+        self.pass_array_to_wasm("passArrayF64ToWasm", "getFloat64Memory", 8, "number")
     }
 
     fn expose_pass_array_jsvalue_to_wasm(&mut self) -> Result<(), Error> {
@@ -1226,7 +2126,7 @@ impl<'a> Context<'a> {
             self.expose_add_to_anyref_table()?;
             self.global(
                 "
-                function passArrayJsValueToWasm(array) {
+                function passArrayJsValueToWasm(array/*: any[]*/) {
                     const ptr = wasm.__wbindgen_malloc(array.length * 4);
                     const mem = getUint32Memory();
                     for (let i = 0; i < array.length; i++) {
@@ -1241,7 +2141,7 @@ impl<'a> Context<'a> {
             self.expose_add_heap_object();
             self.global(
                 "
-                function passArrayJsValueToWasm(array) {
+                function passArrayJsValueToWasm(array/*: any[]*/) {
                     const ptr = wasm.__wbindgen_malloc(array.length * 4);
                     const mem = getUint32Memory();
                     for (let i = 0; i < array.length; i++) {
@@ -1262,6 +2162,7 @@ impl<'a> Context<'a> {
         name: &'static str,
         delegate: &str,
         size: usize,
+        elem: &str,
     ) -> Result<(), Error> {
         if !self.should_write_global(name) {
             return Ok(());
@@ -1270,7 +2171,7 @@ impl<'a> Context<'a> {
         self.expose_wasm_vector_len();
         self.global(&format!(
             "
-            function {}(arg) {{
+            function {}(arg/*: ArrayLike<{elem}>*/) {{
                 const ptr = wasm.__wbindgen_malloc(arg.length * {size});
                 {}().set(arg, ptr / {size});
                 WASM_VECTOR_LEN = arg.length;
@@ -1279,7 +2180,8 @@ impl<'a> Context<'a> {
             ",
             name,
             delegate,
-            size = size
+            size = size,
+            elem = elem,
         ));
         Ok(())
     }
@@ -1299,6 +2201,17 @@ impl<'a> Context<'a> {
     }
 
     fn expose_text_processor(&mut self, s: &str) {
+        // Older engines may not have the encoding API at all, in which case
+        // we bring our own. Node.js has always had it in `util`.
+        if self.config.compat.is_some() && !self.config.nodejs {
+            self.expose_compat_text_processor(s);
+            self.global(&format!(
+                "const l{0}/*: any*/ = typeof {0} === 'undefined' ? Compat{0} : {0};",
+                s
+            ));
+            self.global(&format!("let cached{0} = new l{0}();", s));
+            return;
+        }
         if self.config.nodejs_experimental_modules {
             self.imports
                 .push_str(&format!("import {{ {} }} from 'util';\n", s));
@@ -1306,13 +2219,15 @@ impl<'a> Context<'a> {
         } else if self.config.nodejs {
             self.global(&format!("const {0} = require('util').{0};", s));
             self.global(&format!("let cached{0} = new {0}('utf-8');", s));
-        } else if !(self.config.browser || self.config.no_modules) {
+        } else if !(self.config.browser || self.config.no_modules || self.config.deno) {
+            // `require` is only declared when `@types/node` is installed.
+            let ignore = if self.config.emit_ts { "// @ts-ignore\n" } else { "" };
             self.global(&format!(
                 "
-                    const l{0} = typeof {0} === 'undefined' ? \
+                    {1}const l{0} = typeof {0} === 'undefined' ? \
                         require('util').{0} : {0};\
                 ",
-                s
+                s, ignore
             ));
             self.global(&format!("let cached{0} = new l{0}('utf-8');", s));
         } else {
@@ -1320,6 +2235,74 @@ impl<'a> Context<'a> {
         }
     }
 
+    /// Defines `CompatTextEncoder` or `CompatTextDecoder`, which implement
+    /// as much of the encoding API as the rest of the glue uses, for
+    /// `--compat`.
+    fn expose_compat_text_processor(&mut self, s: &str) {
+        if s == "TextEncoder" {
+            self.global(
+                "
+                class CompatTextEncoder {
+                    encode(s/*: string*/) {
+                        const bytes = [];
+                        for (let i = 0; i < s.length; i++) {
+                            let c = s.codePointAt(i);
+                            if (c > 0xffff) {
+                                i++;
+                            } else if (c >= 0xd800 && c < 0xe000) {
+                                // A lone surrogate
+                                c = 0xfffd;
+                            }
+                            if (c < 0x80) {
+                                bytes.push(c);
+                            } else if (c < 0x800) {
+                                bytes.push(0xc0 | (c >> 6), 0x80 | (c & 0x3f));
+                            } else if (c < 0x10000) {
+                                bytes.push(0xe0 | (c >> 12), 0x80 | ((c >> 6) & 0x3f), 0x80 | (c & 0x3f));
+                            } else {
+                                bytes.push(
+                                    0xf0 | (c >> 18),
+                                    0x80 | ((c >> 12) & 0x3f),
+                                    0x80 | ((c >> 6) & 0x3f),
+                                    0x80 | (c & 0x3f)
+                                );
+                            }
+                        }
+                        return new Uint8Array(bytes);
+                    }
+                }
+            ",
+            );
+        } else {
+            // Strings from Rust are always valid UTF-8.
+            self.global(
+                "
+                class CompatTextDecoder {
+                    decode(bytes/*: Uint8Array*/) {
+                        let s = '';
+                        for (let i = 0; i < bytes.length; ) {
+                            const b = bytes[i++];
+                            let c;
+                            if (b < 0x80) {
+                                c = b;
+                            } else if (b < 0xe0) {
+                                c = ((b & 0x1f) << 6) | (bytes[i++] & 0x3f);
+                            } else if (b < 0xf0) {
+                                c = ((b & 0x0f) << 12) | ((bytes[i++] & 0x3f) << 6) | (bytes[i++] & 0x3f);
+                            } else {
+                                c = ((b & 0x07) << 18) | ((bytes[i++] & 0x3f) << 12) |
+                                    ((bytes[i++] & 0x3f) << 6) | (bytes[i++] & 0x3f);
+                            }
+                            s += String.fromCodePoint(c);
+                        }
+                        return s;
+                    }
+                }
+            ",
+            );
+        }
+    }
+
     fn expose_get_string_from_wasm(&mut self) {
         if !self.should_write_global("get_string_from_wasm") {
             return;
@@ -1340,7 +2323,7 @@ impl<'a> Context<'a> {
 
         self.global(&format!(
             "
-            function getStringFromWasm(ptr, len) {{
+            function getStringFromWasm(ptr/*: number*/, len/*: number*/) {{
                 return cachedTextDecoder.decode(getUint8Memory().{}(ptr, ptr + len));
             }}
         ",
@@ -1357,7 +2340,7 @@ impl<'a> Context<'a> {
             self.expose_anyref_table();
             self.global(
                 "
-                function getArrayJsValueFromWasm(ptr, len) {
+                function getArrayJsValueFromWasm(ptr/*: number*/, len/*: number*/) {
                     const mem = getUint32Memory();
                     const slice = mem.subarray(ptr / 4, ptr / 4 + len);
                     const result = [];
@@ -1374,7 +2357,7 @@ impl<'a> Context<'a> {
             self.expose_take_object();
             self.global(
                 "
-                function getArrayJsValueFromWasm(ptr, len) {
+                function getArrayJsValueFromWasm(ptr/*: number*/, len/*: number*/) {
                     const mem = getUint32Memory();
                     const slice = mem.subarray(ptr / 4, ptr / 4 + len);
                     const result = [];
@@ -1450,7 +2433,7 @@ impl<'a> Context<'a> {
         }
         self.global(&format!(
             "
-            function {name}(ptr, len) {{
+            function {name}(ptr/*: number*/, len/*: number*/) {{
                 return {mem}().subarray(ptr / {size}, ptr / {size} + len);
             }}
             ",
@@ -1568,7 +2551,7 @@ impl<'a> Context<'a> {
         let mem = self.memory();
         self.global(&format!(
             "
-            let cache{name} = null;
+            let cache{name}/*: {js} | null*/ = null;
             function {name}() {{
                 if (cache{name} === null || cache{name}.buffer !== {mem}.buffer) {{
                     cache{name} = new {js}({mem}.buffer);
@@ -1588,7 +2571,7 @@ impl<'a> Context<'a> {
         }
         self.global(
             "
-            function _assertClass(instance, klass) {
+            function _assertClass(instance/*: any*/, klass/*: any*/) {
                 if (!(instance instanceof klass)) {
                     throw new Error(`expected instance of ${klass.name}`);
                 }
@@ -1598,6 +2581,67 @@ impl<'a> Context<'a> {
         );
     }
 
+    /// Flattens a `Map` or plain object passed to Rust into an array of its
+    /// keys followed by their values.
+    fn expose_map_entries(&mut self) {
+        if !self.should_write_global("map_entries") {
+            return;
+        }
+        self.global(
+            "
+            function mapEntries(map/*: any*/) {
+                const entries = map instanceof Map ? map.entries() : Object.entries(map);
+                const flat = [];
+                for (const [key, value] of entries) {
+                    flat.push(key, value);
+                }
+                return flat;
+            }
+            ",
+        );
+    }
+
+    fn expose_map_from_entries(&mut self) -> &'static str {
+        if self.should_write_global("map_from_entries") {
+            self.global(
+                "
+                function mapFromEntries(flat/*: any[]*/) {
+                    const map = new Map();
+                    for (let i = 0; i < flat.length; i += 2) {
+                        map.set(flat[i], flat[i + 1]);
+                    }
+                    return map;
+                }
+                ",
+            );
+        }
+        "mapFromEntries"
+    }
+
+    fn expose_object_from_entries(&mut self) -> &'static str {
+        if self.should_write_global("object_from_entries") {
+            // Keys are defined as properties rather than assigned, so a key
+            // like `__proto__` doesn't change the prototype.
+            self.global(
+                "
+                function objectFromEntries(flat/*: any[]*/) {
+                    const obj = {};
+                    for (let i = 0; i < flat.length; i += 2) {
+                        Object.defineProperty(obj, flat[i], {
+                            value: flat[i + 1],
+                            writable: true,
+                            enumerable: true,
+                            configurable: true,
+                        });
+                    }
+                    return obj;
+                }
+                ",
+            );
+        }
+        "objectFromEntries"
+    }
+
     fn expose_global_stack_pointer(&mut self) {
         if !self.should_write_global("stack_pointer") {
             return;
@@ -1618,7 +2662,7 @@ impl<'a> Context<'a> {
         // just handles it today...
         self.global(
             "
-            function addBorrowedObject(obj) {
+            function addBorrowedObject(obj/*: any*/) {
                 if (stack_pointer == 1) throw new Error('out of js stack');
                 heap[--stack_pointer] = obj;
                 return stack_pointer;
@@ -1635,7 +2679,7 @@ impl<'a> Context<'a> {
         self.expose_drop_ref();
         self.global(
             "
-            function takeObject(idx) {
+            function takeObject(idx/*: number*/) {
                 const ret = getObject(idx);
                 dropObject(idx);
                 return ret;
@@ -1666,7 +2710,7 @@ impl<'a> Context<'a> {
         // one more slot and use that.
         self.global(&format!(
             "
-            function addHeapObject(obj) {{
+            function addHeapObject(obj/*: any*/) {{
                 if (heap_next === heap.length) heap.push(heap.length + 1);
                 const idx = heap_next;
                 heap_next = heap[idx];
@@ -1688,7 +2732,7 @@ impl<'a> Context<'a> {
             self.expose_add_to_anyref_table()?;
             self.global(
                 "
-                function handleError(exnptr, e) {
+                function handleError(exnptr/*: number*/, e/*: any*/) {
                     const idx = addToAnyrefTable(e);
                     const view = getUint32Memory();
                     view[exnptr / 4] = 1;
@@ -1700,7 +2744,7 @@ impl<'a> Context<'a> {
             self.expose_add_heap_object();
             self.global(
                 "
-                function handleError(exnptr, e) {
+                function handleError(exnptr/*: number*/, e/*: any*/) {
                     const view = getUint32Memory();
                     view[exnptr / 4] = 1;
                     view[exnptr / 4 + 1] = addHeapObject(e);
@@ -1820,7 +2864,7 @@ impl<'a> Context<'a> {
         self.require_internal_export("__wbindgen_global_argument_ptr")?;
         self.global(
             "
-            let cachedGlobalArgumentPtr = null;
+            let cachedGlobalArgumentPtr/*: number | null*/ = null;
             function globalArgumentPtr() {
                 if (cachedGlobalArgumentPtr === null) {
                     cachedGlobalArgumentPtr = wasm.__wbindgen_global_argument_ptr();
@@ -1847,7 +2891,7 @@ impl<'a> Context<'a> {
         // #109
         self.global(
             "
-            function GetOwnOrInheritedPropertyDescriptor(obj, id) {
+            function GetOwnOrInheritedPropertyDescriptor(obj/*: any*/, id/*: string*/)/*: any*/ {
               while (obj) {
                 let desc = Object.getOwnPropertyDescriptor(obj, id);
                 if (desc) return desc;
@@ -1859,6 +2903,60 @@ impl<'a> Context<'a> {
         );
     }
 
+    /// Structural getters and setters with `#[wasm_bindgen(cached)]` look up
+    /// the accessor function of the prototype of the objects they're called
+    /// with once, and cache it along with the prototype it was found for.
+    /// The last few prototypes are remembered, so calls alternating between
+    /// objects of a handful of classes, like the different kinds of events
+    /// passed to one handler, don't look up the accessor each time.
+    ///
+    /// Properties which aren't accessors, like those of plain objects, are
+    /// read and written directly. Properties defined on objects themselves,
+    /// rather than on their prototype, aren't seen if the prototype has an
+    /// accessor of the same name.
+    fn expose_cached_accessor(&mut self) {
+        if !self.should_write_global("cached_accessor") {
+            return;
+        }
+        self.expose_get_inherited_descriptor();
+        self.global(
+            "
+            function cachedAccessor(name/*: string*/, kind/*: string*/) {
+                // The most recently seen prototypes come first.
+                const protos/*: any[]*/ = [];
+                const accessors/*: any[]*/ = [];
+                const lookup = (obj/*: any*/) => {
+                    const proto = Object.getPrototypeOf(obj);
+                    for (let i = 0; i < protos.length; i++) {
+                        if (protos[i] === proto) {
+                            return accessors[i];
+                        }
+                    }
+                    const accessor = GetOwnOrInheritedPropertyDescriptor(proto, name)[kind] || null;
+                    if (protos.length === 4) {
+                        protos.pop();
+                        accessors.pop();
+                    }
+                    protos.unshift(proto);
+                    accessors.unshift(accessor);
+                    return accessor;
+                };
+                if (kind === 'get') {
+                    return (obj/*: any*/) => {
+                        const get = lookup(obj);
+                        return get === null ? obj[name] : get.call(obj);
+                    };
+                }
+                return (obj/*: any*/, value/*: any*/) => {
+                    const set = lookup(obj);
+                    if (set === null) obj[name] = value;
+                    else set.call(obj, value);
+                };
+            }
+            ",
+        );
+    }
+
     fn expose_u32_cvt_shim(&mut self) -> &'static str {
         let name = "u32CvtShim";
         if !self.should_write_global(name) {
@@ -1894,17 +2992,619 @@ impl<'a> Context<'a> {
         name
     }
 
-    fn expose_is_like_none(&mut self) {
-        if !self.should_write_global("is_like_none") {
-            return;
+    /// Registers a custom formatter with DevTools, which formats instances of
+    /// the classes in `formattedClasses` with their `toString` method.
+    ///
+    /// Formatters are only used if custom formatters are enabled in the
+    /// DevTools settings.
+    fn expose_devtools_formatter(&mut self) {
+        if !self.should_write_global("devtools_formatter") {
+            return;
+        }
+        self.global(
+            "
+            const formattedClasses/*: any[]*/ = [];
+
+            if (typeof window === 'object') {
+                const win/*: any*/ = window;
+                win.devtoolsFormatters = (win.devtoolsFormatters || []).concat([{
+                    header(obj/*: any*/) {
+                        if (!formattedClasses.some(c => obj instanceof c)) {
+                            return null;
+                        }
+                        return ['span', {}, obj.toString()];
+                    },
+                    hasBody() {
+                        return false;
+                    },
+                }]);
+            }
+        ",
+        );
+    }
+
+    /// Defines the getters and setters of a class's prototype as enumerable
+    /// own properties of an object, so `Object.keys` and spreading see them.
+    /// The pointer is hidden so it isn't copied along with them.
+    fn expose_expose_properties(&mut self) {
+        if !self.should_write_global("expose_properties") {
+            return;
+        }
+        self.global(
+            "
+            function exposeProperties(obj/*: any*/, proto/*: any*/) {
+                Object.defineProperty(obj, 'ptr', { enumerable: false });
+                for (const name of Object.getOwnPropertyNames(proto)) {
+                    const desc = Object.getOwnPropertyDescriptor(proto, name);
+                    if (desc.get === undefined) {
+                        continue;
+                    }
+                    Object.defineProperty(obj, name, {
+                        get: desc.get,
+                        set: desc.set,
+                        enumerable: true,
+                        configurable: true,
+                    });
+                }
+            }
+        ",
+        );
+    }
+
+    /// Converts an object of a `#[wasm_bindgen(plain)]` class into a plain
+    /// object with the values of its getters, freeing the Rust value.
+    fn expose_plain_object(&mut self) {
+        if !self.should_write_global("plain_object") {
+            return;
+        }
+        self.global(
+            "
+            function plainObject(obj) {
+                const ret = {};
+                const proto = Object.getPrototypeOf(obj);
+                for (const name of Object.getOwnPropertyNames(proto)) {
+                    const desc = Object.getOwnPropertyDescriptor(proto, name);
+                    if (desc.get !== undefined) {
+                        ret[name] = obj[name];
+                    }
+                }
+                obj.free();
+                return ret;
+            }
+        ",
+        );
+    }
+
+    /// Freezes an object of a `#[wasm_bindgen(frozen)]` class, registering
+    /// it to have its Rust value freed once it's been gc'd.
+    fn expose_freeze_object(&mut self) {
+        if !self.should_write_global("freeze_object") {
+            return;
+        }
+        // Without `FinalizationRegistry` frozen objects are never freed, which
+        // leaks but at least works.
+        let registry = if self.config.compat.is_some() {
+            "typeof FinalizationRegistry === 'undefined' ? { register() {} } : \
+             new FinalizationRegistry((free/*: () => void*/) => free())"
+        } else {
+            "new FinalizationRegistry((free/*: () => void*/) => free())"
+        };
+        self.global(&format!(
+            "
+            const FROZEN_CLEANUPS/*: {{ register(obj: object, free: () => void): void }}*/ = {};
+
+            function freezeObject(obj/*: object*/, ptr/*: number*/, free/*: (ptr: number) => void*/) {{
+
+                FROZEN_CLEANUPS.register(obj, () => free(ptr));
+                Object.freeze(obj);
+            }}
+        ",
+            registry
+        ));
+    }
+
+    /// Declares `name` as a branded version of the TypeScript type `ty`, which
+    /// values of type `ty` have to be cast to.
+    fn expose_ts_brand(&mut self, name: &str, ty: &str) {
+        if !self.ts_brands.insert(name.to_string()) {
+            return;
+        }
+        let decl = format!(
+            "export type {} = {} & {{ readonly __brand: '{}' }};\n",
+            name, ty, name
+        );
+        if self.config.emit_ts {
+            self.global(&decl);
+        }
+        self.typescript.push_str(&decl);
+    }
+
+    fn expose_is_like_none(&mut self) {
+        if !self.should_write_global("is_like_none") {
+            return;
+        }
+        self.global(
+            "
+            function isLikeNone(x/*: any*/)/*: boolean*/ {
+                return x === undefined || x === null;
+            }
+        ",
+        );
+    }
+
+    fn expose_call_with_middleware(&mut self) {
+        if !self.should_write_global("call_with_middleware") {
+            return;
+        }
+        self.global(
+            "
+            const MIDDLEWARE/*: Middleware[]*/ = [];
+
+            function _callWithMiddleware(name/*: string*/, args/*: any*/, f/*: () => any*/) {
+                if (MIDDLEWARE.length === 0) {
+                    return f();
+                }
+                args = Array.prototype.slice.call(args);
+                const active = MIDDLEWARE.slice();
+                let ret/*: any*/;
+                try {
+                    for (const m of active) {
+                        if (m.onCall) m.onCall(name, args);
+                    }
+                    ret = f();
+                } catch (e) {
+                    for (const m of active) {
+                        if (m.onError) m.onError(name, e, args);
+                    }
+                    throw e;
+                }
+                for (const m of active) {
+                    if (m.onReturn) m.onReturn(name, ret, args);
+                }
+                return ret;
+            }
+        ",
+        );
+        self.export(
+            "addMiddleware",
+            "
+            function(m/*: Middleware*/)/*: () => void*/ {
+                MIDDLEWARE.push(m);
+                return () => {
+                    const idx = MIDDLEWARE.indexOf(m);
+                    if (idx !== -1) MIDDLEWARE.splice(idx, 1);
+                };
+            }
+            ",
+            Some(format_doc_comments(
+                &[
+                    " Registers hooks which are invoked around every call to an exported",
+                    " function or method. Returns a function which unregisters them.",
+                ],
+                None,
+            )),
+        );
+        let middleware = "export interface Middleware {\n\
+                          \x20 onCall?(name: string, args: any[]): void;\n\
+                          \x20 onReturn?(name: string, ret: any, args: any[]): void;\n\
+                          \x20 onError?(name: string, error: any, args: any[]): void;\n\
+                          }\n";
+        if self.config.emit_ts {
+            self.global(middleware);
+        }
+        self.typescript.push_str(middleware);
+        self.typescript
+            .push_str("export function addMiddleware(middleware: Middleware): () => void;\n");
+    }
+
+    /// Defines the clock and random number generator behind
+    /// `wasm_bindgen::now`, `random` and `fill_random`, along with the
+    /// `setHostSources` export which replaces them.
+    fn expose_host_sources(&mut self) {
+        if !self.should_write_global("host_sources") {
+            return;
+        }
+        // `getRandomValues` refuses to fill more than 64K at once.
+        let get_random_values = "
+            for (let i = 0; i < buf.length; i += 65536) {
+                crypto.getRandomValues(buf.subarray(i, i + 65536));
+            }
+        ";
+        let secure_fill = if self.config.nodejs_experimental_modules {
+            self.imports
+                .push_str("import { randomFillSync } from 'crypto';\n");
+            "randomFillSync(buf);".to_string()
+        } else if self.config.nodejs {
+            "require('crypto').randomFillSync(buf);".to_string()
+        } else if !(self.config.browser || self.config.no_modules || self.config.deno) {
+            // `require` is only declared when `@types/node` is installed.
+            let ignore = if self.config.emit_ts { "// @ts-ignore\n" } else { "" };
+            format!(
+                "
+                if (typeof crypto === 'undefined') {{
+                    {}require('crypto').randomFillSync(buf);
+                    return;
+                }}
+                {}
+                ",
+                ignore, get_random_values
+            )
+        } else {
+            get_random_values.to_string()
+        };
+        // By default random bytes come from the host's secure generator,
+        // while a replacement `random` is used for bytes as well so a
+        // recording or simulation covers both.
+        self.global(&format!(
+            "
+            function defaultRandomFill(buf/*: Uint8Array*/) {{
+                {}
+            }}
+
+            let hostNow = () => Date.now();
+            let hostRandom = () => Math.random();
+            let hostRandomFill = defaultRandomFill;
+            ",
+            secure_fill
+        ));
+        self.export(
+            "setHostSources",
+            "
+            function(sources/*: HostSources*/) {
+                if (sources.now !== undefined) {
+                    hostNow = sources.now === null ? () => Date.now() : sources.now;
+                }
+                if (sources.random === null) {
+                    hostRandom = () => Math.random();
+                    hostRandomFill = defaultRandomFill;
+                } else if (sources.random !== undefined) {
+                    const random = sources.random;
+                    hostRandom = random;
+                    hostRandomFill = buf => {
+                        for (let i = 0; i < buf.length; i++) {
+                            buf[i] = Math.floor(random() * 256);
+                        }
+                    };
+                }
+            }
+            ",
+            Some(format_doc_comments(
+                &[
+                    " Replaces the clock and random number generator used by the wasm",
+                    " module, for example to replay a recording or to make a simulation",
+                    " deterministic. Sources which are `null` go back to the defaults, and",
+                    " those which are left out are unchanged.",
+                ],
+                None,
+            )),
+        );
+        let sources = "export interface HostSources {\n\
+                       \x20 now?: (() => number) | null;\n\
+                       \x20 random?: (() => number) | null;\n\
+                       }\n";
+        if self.config.emit_ts {
+            self.global(sources);
+        }
+        self.typescript.push_str(sources);
+        self.typescript
+            .push_str("export function setHostSources(sources: HostSources): void;\n");
+    }
+
+    fn expose_profile_boundary(&mut self) {
+        if !self.should_write_global("profile_boundary") {
+            return;
+        }
+        self.global(
+            "
+            const BOUNDARY_STATS/*: Map<string, BoundaryStats>*/ = new Map();
+
+            const boundaryNow = typeof performance === 'object' && typeof performance.now === 'function'
+                ? () => performance.now()
+                : () => Date.now();
+
+            function _profileBoundary(name/*: string*/, f/*: () => any*/) {
+                const start = boundaryNow();
+                try {
+                    return f();
+                } finally {
+                    const time = boundaryNow() - start;
+                    const stats = BOUNDARY_STATS.get(name);
+                    if (stats === undefined) {
+                        BOUNDARY_STATS.set(name, { name, calls: 1, time });
+                    } else {
+                        stats.calls += 1;
+                        stats.time += time;
+                    }
+                }
+            }
+        ",
+        );
+        self.export(
+            "__wbg_boundary_stats",
+            "
+            function(reset/*?: boolean*/)/*: BoundaryStats[]*/ {
+                const stats = Array.from(BOUNDARY_STATS.values(), s => Object.assign({}, s));
+                stats.sort((a, b) => b.time - a.time);
+                if (reset) BOUNDARY_STATS.clear();
+                return stats;
+            }
+            ",
+            Some(format_doc_comments(
+                &[
+                    " Returns how often each import and export shim was called and the",
+                    " milliseconds spent in it, slowest first. Pass `true` to also reset",
+                    " the counts.",
+                ],
+                None,
+            )),
+        );
+        let stats = "export interface BoundaryStats {\n\
+                     \x20 name: string;\n\
+                     \x20 calls: number;\n\
+                     \x20 time: number;\n\
+                     }\n";
+        if self.config.emit_ts {
+            self.global(stats);
+        }
+        self.typescript.push_str(stats);
+        self.typescript
+            .push_str("export function __wbg_boundary_stats(reset?: boolean): BoundaryStats[];\n");
+    }
+
+    /// Records that the export `name` is split into the lazily loaded wasm
+    /// module, if that's supported by the configuration.
+    fn check_lazy_export(&mut self, name: &str) -> Result<(), Error> {
+        if self.config.nodejs || self.config.no_modules || self.config.deno {
+            bail!(
+                "exported function `{}` is lazy, but lazy exports are only \
+                 supported in ES module output for bundlers or `--browser`",
+                name
+            );
+        }
+        if self.config.threads.is_some() || self.config.anyref {
+            bail!(
+                "exported function `{}` is lazy, but lazy exports can't be \
+                 combined with threads or anyref yet",
+                name
+            );
+        }
+        self.lazy_exports.push(name.to_string());
+        Ok(())
+    }
+
+    /// Checks that `what`, with the type `descriptor`, doesn't pass any 64-bit
+    /// integers with `--compat`, as those are `BigInt`s in JS.
+    fn check_compat(&self, what: &str, descriptor: &Descriptor) -> Result<(), Error> {
+        let compat = match &self.config.compat {
+            Some(compat) => compat,
+            None => return Ok(()),
+        };
+        if descriptor.uses_bigint() {
+            bail!(
+                "{} passes a 64-bit integer, which is a `BigInt` in JS, \
+                 but `BigInt` isn't available with `--compat {}`",
+                what,
+                compat
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks that the arguments and return value of an import marked
+    /// `#[wasm_bindgen(main_thread)]` can be passed between threads.
+    ///
+    /// JS objects belong to the heap of the thread which created them, so
+    /// they can't be passed at all. Without threads each thread also has its
+    /// own memory, so only numbers can be passed and nothing can be returned,
+    /// since calls are only sent to the main thread without waiting for them.
+    fn check_main_thread_import(
+        &self,
+        name: &str,
+        function: &crate::descriptor::Function,
+    ) -> Result<(), Error> {
+        fn shared_memory_ok(d: &Descriptor) -> bool {
+            match d {
+                Descriptor::Anyref
+                | Descriptor::Generic { .. }
+                | Descriptor::ImportEnum(_)
+                | Descriptor::Map(_)
+                | Descriptor::Function(_)
+                | Descriptor::Closure(_) => false,
+                Descriptor::Ref(d)
+                | Descriptor::RefMut(d)
+                | Descriptor::Slice(d)
+                | Descriptor::Vector(d)
+                | Descriptor::Option(d)
+                | Descriptor::Clamped(d)
+                | Descriptor::Named(_, d) => shared_memory_ok(d),
+                _ => true,
+            }
+        }
+        fn number(d: &Descriptor) -> bool {
+            match d {
+                Descriptor::Named(_, d) => number(d),
+                Descriptor::Boolean | Descriptor::Char => true,
+                d => d.is_number() || d.get_64().is_some(),
+            }
+        }
+
+        if self.config.threads.is_some() {
+            if !function.arguments.iter().all(shared_memory_ok) || !shared_memory_ok(&function.ret)
+            {
+                bail!(
+                    "imported function `{}` is marked `main_thread` but JS objects \
+                     can't be passed to or returned from the main thread",
+                    name
+                );
+            }
+        } else {
+            if !function.arguments.iter().all(number) {
+                bail!(
+                    "imported function `{}` is marked `main_thread` and can only \
+                     take numbers without threads, as each thread has its own memory",
+                    name
+                );
+            }
+            match function.ret {
+                Descriptor::Unit => {}
+                _ => bail!(
+                    "imported function `{}` is marked `main_thread` and can't \
+                     return a value without threads, as calls are sent to the \
+                     main thread without waiting for them",
+                    name
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// Wraps the `js` shim of an import marked `#[wasm_bindgen(main_thread)]`
+    /// so calls on other threads are sent to the main thread and run there.
+    fn proxy_to_main_thread(&mut self, shim: &str, js: &str) -> Result<String, Error> {
+        self.expose_call_on_main_thread()?;
+        self.global(&format!("MAIN_THREAD_IMPORTS.{} = {};", shim, js));
+        Ok(format!(
+            "function(...args/*: any[]*/) {{ return callOnMainThread('{}', args); }}",
+            shim
+        ))
+    }
+
+    fn expose_call_on_main_thread(&mut self) -> Result<(), Error> {
+        if !self.should_write_global("call_on_main_thread") {
+            return Ok(());
+        }
+
+        // Workers send calls to the main thread with `postMessage`, which in
+        // Node.js goes through `worker_threads`.
+        let node = self.config.nodejs || self.config.no_modules;
+        // Neither worker nor Node.js globals are declared without their
+        // type definitions.
+        let ignore = if self.config.emit_ts {
+            "// @ts-ignore\n"
+        } else {
+            ""
+        };
+        self.global(&format!(
+            "
+            const MAIN_THREAD_IMPORTS/*: any*/ = {{}};
+
+            const ON_MAIN_THREAD = (() => {{
+                {ignore}if (typeof WorkerGlobalScope !== 'undefined' && self instanceof WorkerGlobalScope) {{
+                    return false;
+                }}
+                {node}
+                return true;
+            }})();
+
+            function postToMainThread(message/*: any*/) {{
+                if (typeof WorkerGlobalScope !== 'undefined') {{
+                    {ignore}self.postMessage(message);
+                }} else {{
+                    {ignore}require('worker_threads').parentPort.postMessage(message);
+                }}
+            }}
+            ",
+            node = if node {
+                format!(
+                    "{}try {{ return require('worker_threads').isMainThread; }} catch (e) {{}}",
+                    ignore
+                )
+            } else {
+                String::new()
+            },
+            ignore = ignore,
+        ));
+
+        let mem = self.memory();
+        if self.config.threads.is_some() {
+            // With threads the memory is shared, so arguments in memory can
+            // be read on the main thread, and the worker blocks until the
+            // main thread stores the result next to a status word it waits on.
+            self.require_internal_export("__wbindgen_malloc")?;
+            self.require_internal_export("__wbindgen_free")?;
+            self.global(&format!(
+                "
+                function callOnMainThread(name/*: string*/, args/*: any[]*/) {{
+                    if (ON_MAIN_THREAD) {{
+                        return MAIN_THREAD_IMPORTS[name](...args);
+                    }}
+                    const slot = wasm.__wbindgen_malloc(16);
+                    const status = new Int32Array({mem}.buffer);
+                    Atomics.store(status, slot >> 2, 0);
+                    postToMainThread({{ __wbindgen_main_thread: {{ name, args, slot }} }});
+                    Atomics.wait(status, slot >> 2, 0);
+                    const ok = Atomics.load(status, slot >> 2) === 1;
+                    const ret = new DataView({mem}.buffer).getFloat64(slot + 8, true);
+                    wasm.__wbindgen_free(slot, 16);
+                    if (!ok) {{
+                        throw new Error(`imported function ${{name}} threw on the main thread`);
+                    }}
+                    return ret;
+                }}
+
+                function runMainThreadCall(call/*: any*/) {{
+                    const status = new Int32Array({mem}.buffer);
+                    let ret;
+                    try {{
+                        ret = MAIN_THREAD_IMPORTS[call.name](...call.args);
+                    }} catch (e) {{
+                        Atomics.store(status, call.slot >> 2, 2);
+                        Atomics.notify(status, call.slot >> 2);
+                        throw e;
+                    }}
+                    new DataView({mem}.buffer).setFloat64(call.slot + 8, ret === undefined ? 0 : ret, true);
+                    Atomics.store(status, call.slot >> 2, 1);
+                    Atomics.notify(status, call.slot >> 2);
+                }}
+                ",
+                mem = mem,
+            ));
+        } else {
+            // Without threads each worker has its own instance and memory,
+            // so calls only carry numbers and aren't waited for.
+            self.global(
+                "
+                function callOnMainThread(name/*: string*/, args/*: any[]*/) {
+                    if (ON_MAIN_THREAD) {
+                        return MAIN_THREAD_IMPORTS[name](...args);
+                    }
+                    postToMainThread({ __wbindgen_main_thread: { name, args } });
+                }
+
+                function runMainThreadCall(call/*: any*/) {
+                    MAIN_THREAD_IMPORTS[call.name](...call.args);
+                }
+                ",
+            );
         }
-        self.global(
+
+        self.export(
+            "proxyMainThreadCalls",
             "
-            function isLikeNone(x) {
-                return x === undefined || x === null;
+            function(worker/*: any*/) {
+                const onMessage = (data/*: any*/) => {
+                    if (data && data.__wbindgen_main_thread) {
+                        runMainThreadCall(data.__wbindgen_main_thread);
+                    }
+                };
+                if (typeof worker.on === 'function') {
+                    worker.on('message', onMessage);
+                } else {
+                    worker.addEventListener('message', (e/*: any*/) => onMessage(e.data));
+                }
             }
-        ",
+            ",
+            Some(format_doc_comments(
+                &[
+                    " Runs calls to imports marked `#[wasm_bindgen(main_thread)]` which",
+                    " are sent by `worker` on this thread, which must be the main thread.",
+                ],
+                None,
+            )),
         );
+        self.typescript
+            .push_str("export function proxyMainThreadCalls(worker: any): void;\n");
+        Ok(())
     }
 
     fn expose_cleanup_groups(&mut self) {
@@ -1916,7 +3616,7 @@ impl<'a> Context<'a> {
                 const CLEANUPS = new WeakRefGroup(x => x.holdings());
                 const CLEANUPS_MAP = new Map();
 
-                function addCleanup(obj, ptr, free) {
+                function addCleanup(obj/*: object*/, ptr/*: number*/, free/*: (ptr: number) => void*/) {
                     const ref = CLEANUPS.makeRef(obj, () => free(ptr));
                     CLEANUPS_MAP.set(ptr, ref);
                 }
@@ -1926,12 +3626,16 @@ impl<'a> Context<'a> {
 
     fn describe(&mut self, name: &str) -> Option<Descriptor> {
         let name = format!("__wbindgen_describe_{}", name);
+        if let Some(descriptor) = self.descriptors.remove(&name) {
+            return Some(descriptor);
+        }
         let descriptor = self.interpreter.interpret_descriptor(&name, self.module)?;
         Some(Descriptor::decode(descriptor))
     }
 
     fn global(&mut self, s: &str) {
-        let s = s.trim();
+        let s = type_annotations(s.trim(), self.config.emit_ts);
+        let s = &s[..];
 
         // Ensure a blank line between adjacent items, and ensure everything is
         // terminated with a newline.
@@ -2060,13 +3764,45 @@ impl<'a> Context<'a> {
         let method_data = match &import.method {
             Some(data) => data,
             None => {
+                // The specifier is written out literally, rather than passed
+                // to `wasm_bindgen::import_module` at runtime, so bundlers can
+                // see which module is imported and split it off.
+                if import.dynamic_import {
+                    let module = name.module().unwrap();
+                    let import_module = if self.use_node_require() {
+                        format!(
+                            "new Promise(resolve => resolve(require(String.raw`{}`)))",
+                            module
+                        )
+                    } else {
+                        format!("import('{}')", module)
+                    };
+                    self.global(&format!(
+                        "function {}_import() {{ return {}; }}",
+                        import.shim, import_module
+                    ));
+                    return Ok(ImportTarget::Function(format!("{}_import", import.shim)));
+                }
                 let name = self.import_identifier(name);
+                // Statics are read on every access, rather than once, so
+                // they can also be used for values which change over time.
+                if import.static_getter {
+                    return Ok(ImportTarget::Static(name));
+                }
+                // Binding `this` is the same as calling a method through
+                // `Function.prototype.call`.
+                let target = |name| {
+                    if import.bind_this {
+                        ImportTarget::Method(name)
+                    } else {
+                        ImportTarget::Function(name)
+                    }
+                };
                 if import.structural || !name.contains(".") {
-                    return Ok(ImportTarget::Function(name));
+                    return Ok(target(name));
                 }
                 self.global(&format!("const {}_target = {};", import.shim, name));
-                let target = format!("{}_target", import.shim);
-                return Ok(ImportTarget::Function(target));
+                return Ok(target(format!("{}_target", import.shim)));
             }
         };
 
@@ -2077,6 +3813,9 @@ impl<'a> Context<'a> {
             }
             decode::MethodKind::Operation(op) => op,
         };
+        if let decode::OperationKind::Call = op.kind {
+            return Ok(ImportTarget::Function(class));
+        }
         if import.structural {
             let class = if op.is_static {
                 Some(class.clone())
@@ -2085,6 +3824,7 @@ impl<'a> Context<'a> {
             };
 
             return Ok(match &op.kind {
+                decode::OperationKind::Call => unreachable!(),
                 decode::OperationKind::Regular => {
                     let name = import.function.name.to_string();
                     match class {
@@ -2092,6 +3832,22 @@ impl<'a> Context<'a> {
                         None => ImportTarget::StructuralMethod(name),
                     }
                 }
+                decode::OperationKind::Getter(g) if import.cached && class.is_none() => {
+                    self.expose_cached_accessor();
+                    self.global(&format!(
+                        "const {}_target = cachedAccessor('{}', 'get');",
+                        import.shim, g
+                    ));
+                    ImportTarget::Function(format!("{}_target", import.shim))
+                }
+                decode::OperationKind::Setter(s) if import.cached && class.is_none() => {
+                    self.expose_cached_accessor();
+                    self.global(&format!(
+                        "const {}_target = cachedAccessor('{}', 'set');",
+                        import.shim, s
+                    ));
+                    ImportTarget::Function(format!("{}_target", import.shim))
+                }
                 decode::OperationKind::Getter(g) => {
                     ImportTarget::StructuralGetter(class, g.to_string())
                 }
@@ -2116,6 +3872,7 @@ impl<'a> Context<'a> {
             if op.is_static { "" } else { ".prototype" }
         );
         let (mut target, name) = match &op.kind {
+            decode::OperationKind::Call => unreachable!(),
             decode::OperationKind::Regular => (
                 format!("{}.{}", target, import.function.name),
                 &import.function.name,
@@ -2262,7 +4019,8 @@ impl<'a> Context<'a> {
         self.require_internal_export("__wbindgen_anyref_table_alloc")?;
         self.global(
             "
-                function addToAnyrefTable(obj) {
+                function addToAnyrefTable(obj/*: any*/) {
+
                     const idx = wasm.__wbindgen_anyref_table_alloc();
                     wasm.__wbg_anyref_table.set(idx, obj);
                     return idx;
@@ -2311,6 +4069,9 @@ impl<'a, 'b> SubContext<'a, 'b> {
                 )
             })?;
         }
+        for t in self.program.traits.iter() {
+            self.generate_trait(t);
+        }
         for f in self.program.imports.iter() {
             if let decode::ImportKind::Type(ty) = &f.kind {
                 self.register_vendor_prefix(ty);
@@ -2320,7 +4081,12 @@ impl<'a, 'b> SubContext<'a, 'b> {
             self.generate_import(f)?;
         }
         for e in self.program.enums.iter() {
-            self.generate_enum(e);
+            self.generate_enum(e)?;
+        }
+        for u in self.program.tagged_unions.iter() {
+            self.generate_tagged_union(u).with_context(|_| {
+                format!("failed to generate bindings for Rust enum `{}`", u.name)
+            })?;
         }
         for s in self.program.structs.iter() {
             self.generate_struct(s).with_context(|_| {
@@ -2349,12 +4115,27 @@ impl<'a, 'b> SubContext<'a, 'b> {
         if export.start {
             self.set_start_function(export.function.name)?;
         }
+        self.cx.check_compat(
+            &format!("exported function `{}`", export.function.name),
+            &descriptor,
+        )?;
+        let invoc = if export.lazy {
+            self.cx.check_lazy_export(export.function.name)?;
+            format!("lazyWasm.{}", export.function.name)
+        } else {
+            format!("wasm.{}", export.function.name)
+        };
 
+        let throws = self.cx.thrown_class(export);
         let (js, ts, js_doc) = Js2Rust::new(&export.function.name, self.cx)
+            .middleware(&export.function.name)
+            .lazy(export.lazy)
+            .throws(throws)
+            .maps_as_objects(export.maps_as_objects)
             .process(descriptor.unwrap_function())?
             .finish(
                 "function",
-                &format!("wasm.{}", export.function.name),
+                &invoc,
                 ExportedShim::Named(&export.function.name),
             );
         self.cx.export(
@@ -2363,6 +4144,15 @@ impl<'a, 'b> SubContext<'a, 'b> {
             Some(format_doc_comments(&export.comments, Some(js_doc))),
         );
         self.cx.globals.push_str("\n");
+        self.cx.api.insert(
+            &export.function.name,
+            &format!("function{}", api_signature(&ts, &export.function.name)),
+        );
+        if let Some(class) = throws {
+            self.cx
+                .typescript
+                .push_str(&format!("/** @throws {{{}}} */\n", class));
+        }
         self.cx.typescript.push_str("export ");
         self.cx.typescript.push_str(&ts);
         self.cx.typescript.push_str("\n");
@@ -2387,6 +4177,15 @@ impl<'a, 'b> SubContext<'a, 'b> {
         class_name: &'b str,
         export: &decode::Export,
     ) -> Result<(), Error> {
+        if self
+            .cx
+            .exported_enums
+            .as_ref()
+            .expect("enums already written")
+            .contains_key(class_name)
+        {
+            return self.generate_export_for_enum(class_name, export);
+        }
         let wasm_name =
             wasm_bindgen_shared::struct_function_export_name(class_name, &export.function.name);
 
@@ -2395,13 +4194,30 @@ impl<'a, 'b> SubContext<'a, 'b> {
             Some(d) => d,
         };
 
+        if export.consumed && self.cx.frozen_classes.contains(class_name) {
+            bail!(
+                "methods of frozen classes can't take `self` by value, \
+                 but `{}::{}` does",
+                class_name,
+                export.function.name
+            );
+        }
+        self.cx.check_compat(
+            &format!("`{}::{}`", class_name, export.function.name),
+            &descriptor,
+        )?;
+
         let function_name = if export.is_constructor {
             "constructor"
         } else {
             &export.function.name
         };
+        let throws = self.cx.thrown_class(export);
         let (js, ts, js_doc) = Js2Rust::new(function_name, self.cx)
+            .middleware(&format!("{}.{}", class_name, export.function.name))
             .method(export.method, export.consumed)
+            .throws(throws)
+            .maps_as_objects(export.maps_as_objects)
             .constructor(if export.is_constructor {
                 Some(class_name)
             } else {
@@ -2414,6 +4230,18 @@ impl<'a, 'b> SubContext<'a, 'b> {
                 ExportedShim::Named(&wasm_name),
             );
 
+        let kind = if export.is_constructor {
+            "constructor"
+        } else if export.method {
+            "method"
+        } else {
+            "static method"
+        };
+        self.cx.api.insert(
+            &format!("{}.{}", class_name, function_name),
+            &format!("{}{}", kind, api_signature(&ts, function_name)),
+        );
+
         let class = self
             .cx
             .exported_classes
@@ -2425,6 +4253,11 @@ impl<'a, 'b> SubContext<'a, 'b> {
             .contents
             .push_str(&format_doc_comments(&export.comments, Some(js_doc)));
 
+        if let Some(thrown) = throws {
+            class
+                .typescript
+                .push_str(&format!("  /** @throws {{{}}} */\n", thrown));
+        }
         class.typescript.push_str("  "); // Indentation
 
         if export.is_constructor {
@@ -2445,6 +4278,79 @@ impl<'a, 'b> SubContext<'a, 'b> {
         Ok(())
     }
 
+    /// Generates a method of an enum, which is a function on the enum object
+    /// taking the value as the first argument if it has a receiver.
+    fn generate_export_for_enum(
+        &mut self,
+        enum_name: &str,
+        export: &decode::Export,
+    ) -> Result<(), Error> {
+        if export.is_constructor {
+            bail!(
+                "enums can't have constructors, but `{}::{}` is one",
+                enum_name,
+                export.function.name
+            );
+        }
+        let wasm_name =
+            wasm_bindgen_shared::struct_function_export_name(enum_name, &export.function.name);
+
+        let descriptor = match self.cx.describe(&wasm_name) {
+            None => return Ok(()),
+            Some(d) => d,
+        };
+
+        self.cx.check_compat(
+            &format!("`{}::{}`", enum_name, export.function.name),
+            &descriptor,
+        )?;
+
+        let receiver = match &self.cx.exported_enums.as_ref().unwrap()[enum_name].repr[..] {
+            "i64" => Descriptor::I64,
+            "u64" => Descriptor::U64,
+            "i8" | "i16" | "i32" => Descriptor::I32,
+            _ => Descriptor::U32,
+        };
+        let mut js2rust = Js2Rust::new(&export.function.name, self.cx);
+        js2rust
+            .middleware(&format!("{}.{}", enum_name, export.function.name))
+            .maps_as_objects(export.maps_as_objects);
+        if export.method {
+            js2rust.argument(&receiver)?;
+        }
+        let (js, ts, js_doc) = js2rust.process(descriptor.unwrap_function())?.finish(
+            "",
+            &format!("wasm.{}", wasm_name),
+            ExportedShim::Named(&wasm_name),
+        );
+        self.cx.api.insert(
+            &format!("{}.{}", enum_name, export.function.name),
+            &format!("function{}", api_signature(&ts, &export.function.name)),
+        );
+
+        let enum_ = self
+            .cx
+            .exported_enums
+            .as_mut()
+            .unwrap()
+            .get_mut(enum_name)
+            .unwrap();
+        enum_
+            .methods
+            .push_str(&format_doc_comments(&export.comments, Some(js_doc)));
+        enum_
+            .methods
+            .push_str(&format!("{}: {{ value{} }},\n", export.function.name, js));
+        if enum_.repr.ends_with("64") {
+            enum_.typescript_methods.push_str(&format!("\n  {}", ts));
+        } else {
+            enum_
+                .typescript_methods
+                .push_str(&format!("  export function {}\n", ts));
+        }
+        Ok(())
+    }
+
     fn generate_import(&mut self, import: &decode::Import<'b>) -> Result<(), Error> {
         match import.kind {
             decode::ImportKind::Function(ref f) => {
@@ -2465,11 +4371,97 @@ impl<'a, 'b> SubContext<'a, 'b> {
                     format!("failed to generate bindings for JS import `{}`", ty.name,)
                 })?;
             }
-            decode::ImportKind::Enum(_) => {}
+            decode::ImportKind::Enum(ref e) => self.generate_import_enum(e),
         }
         Ok(())
     }
 
+    /// Declares an imported enum in TypeScript as a union of its values, like
+    /// `type Direction = "up" | "down"`. Nothing is needed in JS as the values
+    /// are passed as they are.
+    fn generate_import_enum(&mut self, enum_: &decode::ImportEnum) {
+        let mut values = enum_
+            .variant_values
+            .iter()
+            .map(|v| {
+                if enum_.numeric {
+                    v.to_string()
+                } else {
+                    serde_json::to_string(v).unwrap()
+                }
+            })
+            .collect::<Vec<_>>();
+        if enum_.other {
+            values.push(if enum_.numeric { "number" } else { "string" }.to_string());
+        }
+        let ty = values.join(" | ");
+        let decl = format!(
+            "{}export type {} = {};\n",
+            format_doc_comments(&enum_.comments, None),
+            enum_.name,
+            ty
+        );
+        if self.cx.config.emit_ts {
+            self.cx.global(&decl);
+        }
+        self.cx.typescript.push_str(&decl);
+        self.cx.api.insert(enum_.name, &format!("type {}", ty));
+    }
+
+    /// Declares an exported trait as an interface in TypeScript, with the
+    /// signatures of its methods taken from the imports they're called
+    /// through. Nothing is needed in JS as any object with the methods will
+    /// do.
+    fn generate_trait(&mut self, trait_: &decode::Trait<'b>) {
+        let mut members = String::new();
+        self.cx.api.insert(trait_.name, "interface");
+        for method in trait_.methods.iter() {
+            let descriptor = match self.cx.describe(method.shim) {
+                Some(d) => d,
+                None => continue,
+            };
+            let signature = {
+                let function = descriptor.unwrap_function();
+                // The first argument is the object the method is called on.
+                let args = function
+                    .arguments
+                    .iter()
+                    .skip(1)
+                    .enumerate()
+                    .map(|(i, arg)| format!("arg{}: {}", i, arg.ts_type()))
+                    .collect::<Vec<_>>();
+                let ret = match function.ret {
+                    Descriptor::Unit => "void".to_string(),
+                    ref ty => ty.ts_type(),
+                };
+                format!("{}({}): {}", method.name, args.join(", "), ret)
+            };
+            // The import of the method is generated later on, which needs the
+            // descriptor as well.
+            self.cx
+                .descriptors
+                .insert(format!("__wbindgen_describe_{}", method.shim), descriptor);
+
+            members.push_str(&format_doc_comments(&method.comments, None));
+            members.push_str(&signature);
+            members.push_str(";\n");
+            self.cx.api.insert(
+                &format!("{}.{}", trait_.name, method.name),
+                &format!("method{}", api_signature(&signature, method.name)),
+            );
+        }
+        let decl = format!(
+            "{}export interface {} {{\n{}}}\n",
+            format_doc_comments(&trait_.comments, None),
+            trait_.name,
+            members
+        );
+        if self.cx.config.emit_ts {
+            self.cx.global(&decl);
+        }
+        self.cx.typescript.push_str(&decl);
+    }
+
     fn generate_import_static(
         &mut self,
         info: &decode::Import<'b>,
@@ -2481,6 +4473,11 @@ impl<'a, 'b> SubContext<'a, 'b> {
             return Ok(());
         }
 
+        match info.js_namespace {
+            Some(ns) => self.declare_snippet_import(info, |d| d.value(ns)),
+            None => self.declare_snippet_import(info, |d| d.value(import.name)),
+        }
+
         // TODO: should support more types to import here
         let obj = self.import_name(info, &import.name)?;
         self.cx
@@ -2488,6 +4485,9 @@ impl<'a, 'b> SubContext<'a, 'b> {
             .import_xform("__wbindgen_placeholder__", &import.shim, &[], true);
         let body = format!("function() {{ return {}; }}", self.cx.add_heap_object(&obj));
         self.cx.export(&import.shim, &body, None);
+        self.cx
+            .source_names
+            .insert(import.shim.to_string(), format!("imports/{}", import.name));
         Ok(())
     }
 
@@ -2510,6 +4510,7 @@ impl<'a, 'b> SubContext<'a, 'b> {
             None => return Ok(()),
             Some(d) => d,
         };
+        self.declare_snippet_function(info, import, descriptor.unwrap_function());
 
         // Figure out the name that we're importing to dangle further references
         // off of. This is the function name if there's no method all here, or
@@ -2519,6 +4520,15 @@ impl<'a, 'b> SubContext<'a, 'b> {
             None => self.determine_import(info, &import.function.name)?,
         };
 
+        if import.main_thread {
+            self.cx
+                .check_main_thread_import(&import.function.name, descriptor.unwrap_function())?;
+        }
+        self.cx.check_compat(
+            &format!("imported function `{}`", import.function.name),
+            &descriptor,
+        )?;
+
         // Build up our shim's state, and we'll use that to guide whether we
         // actually emit an import here or not.
         let mut shim = Rust2Js::new(self.cx);
@@ -2533,7 +4543,14 @@ impl<'a, 'b> SubContext<'a, 'b> {
         // anything (all argument/return conversions are noops) then we can wire
         // up the wasm import directly to the destination. We don't actually
         // wire up anything here, but we record it to get wired up later.
-        if import.method.is_none() && shim.is_noop() {
+        if import.method.is_none()
+            && !shim.cx.config.profile_boundary
+            && !import.bind_this
+            && !import.main_thread
+            && !import.static_getter
+            && !import.dynamic_import
+            && shim.is_noop()
+        {
             if let Import::Module {
                 module,
                 name,
@@ -2558,7 +4575,19 @@ impl<'a, 'b> SubContext<'a, 'b> {
         // here (possibly emitting some glue in our JS module) and then emit the
         // shim as the wasm will be importing the shim.
         let target = shim.cx.generated_import_target(name, import)?;
-        let js = shim.finish(&target, &import.shim)?;
+        let label = match &import.method {
+            Some(data) => format!("{}.{}", data.class, import.function.name),
+            None => import.function.name.to_string(),
+        };
+        let mut js = shim
+            .profile_boundary(&format!("import {}", label))
+            .finish(&target, &import.shim)?;
+        shim.cx
+            .source_names
+            .insert(import.shim.to_string(), format!("imports/{}", label));
+        if import.main_thread {
+            js = shim.cx.proxy_to_main_thread(&import.shim, &js)?;
+        }
         shim.cx.export(&import.shim, &js, None);
         Ok(())
     }
@@ -2568,6 +4597,11 @@ impl<'a, 'b> SubContext<'a, 'b> {
         info: &decode::Import<'b>,
         import: &decode::ImportType<'b>,
     ) -> Result<(), Error> {
+        match info.js_namespace {
+            Some(ns) => self.declare_snippet_import(info, |d| d.value(ns)),
+            None => self.declare_snippet_import(info, |d| d.class(import.name)),
+        }
+        self.generate_duck_shim(import);
         if !self.cx.wasm_import_needed(&import.instanceof_shim) {
             return Ok(());
         }
@@ -2579,7 +4613,7 @@ impl<'a, 'b> SubContext<'a, 'b> {
             false,
         );
         let body = format!(
-            "function(idx) {{ return {} instanceof {} ? 1 : 0; }}",
+            "function(idx/*: number*/) {{ return {} instanceof {} ? 1 : 0; }}",
             self.cx.get_object("idx"),
             name
         );
@@ -2587,27 +4621,235 @@ impl<'a, 'b> SubContext<'a, 'b> {
         Ok(())
     }
 
-    fn generate_enum(&mut self, enum_: &decode::Enum) {
-        let mut variants = String::new();
+    /// Declares what an import is imported as in the `.d.ts` file of the
+    /// snippet it's imported from, if it's imported from one.
+    fn declare_snippet_import(
+        &mut self,
+        info: &decode::Import<'b>,
+        declare: impl FnOnce(&mut Declarations),
+    ) {
+        if let Some(decls) = info.module.and_then(|m| self.cx.snippets.get_mut(m)) {
+            declare(decls);
+        }
+    }
 
-        for variant in enum_.variants.iter() {
-            variants.push_str(&format!("{}:{},", variant.name, variant.value));
+    /// Declares an imported function in the `.d.ts` file of its snippet,
+    /// with its arguments and return value typed from its descriptor.
+    fn declare_snippet_function(
+        &mut self,
+        info: &decode::Import<'b>,
+        import: &decode::ImportFunction<'b>,
+        function: &crate::descriptor::Function,
+    ) {
+        // Dynamic imports import the snippet itself rather than an export.
+        if import.dynamic_import {
+            return;
         }
-        self.cx.export(
-            &enum_.name,
-            &format!("Object.freeze({{ {} }})", variants),
-            Some(format_doc_comments(&enum_.comments, None)),
+        if let Some(ns) = info.js_namespace {
+            return self.declare_snippet_import(info, |d| d.value(ns));
+        }
+        // Methods are passed the object they're called on first.
+        let skip = match &import.method {
+            Some(decode::MethodData {
+                kind:
+                    decode::MethodKind::Operation(decode::Operation {
+                        is_static: false,
+                        ..
+                    }),
+                ..
+            }) => 1,
+            _ => 0,
+        };
+        let args = function
+            .arguments
+            .iter()
+            .skip(skip)
+            .map(|arg| arg.ts_type())
+            .collect::<Vec<_>>();
+        let ret = match function.ret {
+            Descriptor::Unit => "void".to_string(),
+            ref ty => ty.ts_type(),
+        };
+        let name = import.function.name;
+        self.declare_snippet_import(info, |d| match &import.method {
+            None => d.function(name, &args, &ret),
+            Some(data) => match &data.kind {
+                decode::MethodKind::Constructor => d.constructor(data.class, &args),
+                decode::MethodKind::Operation(op) => match op.kind {
+                    decode::OperationKind::Regular => {
+                        d.method(data.class, op.is_static, name, &args, &ret)
+                    }
+                    decode::OperationKind::Getter(prop) => {
+                        d.property(data.class, op.is_static, prop, &ret)
+                    }
+                    decode::OperationKind::Setter(prop) => {
+                        let ty = args.get(0).map(|s| s.as_str()).unwrap_or("any");
+                        d.property(data.class, op.is_static, prop, ty)
+                    }
+                    _ => d.class(data.class),
+                },
+            },
+        });
+    }
+
+    /// Generates the check of whether a value has all the methods declared
+    /// for an imported type, which can be used instead of `instanceof` for
+    /// objects which merely look like instances.
+    fn generate_duck_shim(&mut self, import: &decode::ImportType<'b>) {
+        if !self.cx.wasm_import_needed(&import.duck_shim) {
+            return;
+        }
+        let methods = self
+            .program
+            .imports
+            .iter()
+            .filter_map(|i| match &i.kind {
+                decode::ImportKind::Function(f) => Some(f),
+                _ => None,
+            })
+            .filter(|f| match &f.method {
+                Some(decode::MethodData {
+                    class,
+                    kind:
+                        decode::MethodKind::Operation(decode::Operation {
+                            is_static: false,
+                            kind: decode::OperationKind::Regular,
+                        }),
+                }) => *class == import.name,
+                _ => false,
+            })
+            .map(|f| f.function.name)
+            .collect::<BTreeSet<_>>();
+        let mut check = "1".to_string();
+        if !methods.is_empty() {
+            let checks = methods
+                .iter()
+                .map(|name| format!("typeof(val['{}']) === 'function'", name))
+                .collect::<Vec<_>>();
+            check = format!("{} ? 1 : 0", checks.join(" && "));
+        }
+        self.cx.anyref.import_xform(
+            "__wbindgen_placeholder__",
+            &import.duck_shim,
+            &[(0, false)],
+            false,
         );
-        self.cx
-            .typescript
-            .push_str(&format!("export enum {} {{", enum_.name));
+        let body = format!(
+            "
+            function(idx/*: number*/) {{
+                const val = {};
 
-        for variant in enum_.variants.iter() {
-            self.cx
-                .typescript
-                .push_str(&format!("\n  {},", variant.name));
+                if (val === null || (typeof(val) !== 'object' && typeof(val) !== 'function')) {{
+                    return 0;
+                }}
+                return {};
+            }}
+            ",
+            self.cx.get_object("idx"),
+            check,
+        );
+        self.cx.export(&import.duck_shim, &body, None);
+    }
+
+    fn generate_enum(&mut self, enum_: &decode::Enum) -> Result<(), Error> {
+        let values = if enum_.dynamic_values {
+            // The macro couldn't evaluate some discriminant, so the values are
+            // informed by a function exported for this purpose, as pairs of
+            // low and high bits.
+            let name = wasm_bindgen_shared::enum_values(&enum_.name);
+            match self.cx.interpreter.interpret_descriptor(&name, self.cx.module) {
+                Some(values) if values.len() == enum_.variants.len() * 2 => values
+                    .chunks(2)
+                    .map(|v| u64::from(v[0]) | (u64::from(v[1]) << 32))
+                    .collect(),
+                _ => bail!("failed to find the values of enum `{}`", enum_.name),
+            }
+        } else {
+            enum_.variants.iter().map(|v| v.value).collect::<Vec<_>>()
+        };
+
+        // Values are sign-extended bits, and enums 64 bits wide are `BigInt`s
+        // in JS.
+        let bigint = enum_.repr == "i64" || enum_.repr == "u64";
+        if let (true, Some(compat)) = (bigint, &self.cx.config.compat) {
+            bail!(
+                "enum `{}` is `#[repr({})]`, so its values are `BigInt`s in JS, \
+                 but `BigInt` isn't available with `--compat {}`",
+                enum_.name,
+                enum_.repr,
+                compat
+            );
+        }
+        let values = values
+            .into_iter()
+            .map(|value| match enum_.repr {
+                "u64" => format!("{}n", value),
+                "i64" => format!("{}n", value as i64),
+                "i8" | "i16" | "i32" => (value as i64).to_string(),
+                _ => (value as u32).to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut variants = String::new();
+        self.cx.api.insert(&enum_.name, "enum");
+        for (variant, value) in enum_.variants.iter().zip(values.iter()) {
+            variants.push_str(&format!("{}:{},", variant.name, value));
+            self.cx.api.insert(
+                &format!("{}.{}", enum_.name, variant.name),
+                &format!("variant = {}", value),
+            );
+        }
+
+        // TypeScript enums can't have `BigInt` values, so those are declared
+        // as a frozen object instead. The declarations are closed once any
+        // methods have been added.
+        let mut ts = String::new();
+        if bigint {
+            ts.push_str(&format!("export const {}: {{", enum_.name));
+            for variant in enum_.variants.iter() {
+                ts.push_str(&format!("\n  readonly {}: BigInt;", variant.name));
+            }
+        } else {
+            ts.push_str(&format!("export enum {} {{", enum_.name));
+            for (variant, value) in enum_.variants.iter().zip(values.iter()) {
+                ts.push_str(&format!("\n  {} = {},", variant.name, value));
+            }
+        }
+
+        let exported = self
+            .cx
+            .exported_enums
+            .as_mut()
+            .expect("enums already written")
+            .entry(enum_.name.to_string())
+            .or_insert(ExportedEnum::default());
+        exported.repr = enum_.repr.to_string();
+        exported.comments = format_doc_comments(&enum_.comments, None);
+        exported.variants = variants;
+        exported.typescript = ts;
+
+        // The variants of flags enums are bits, so they get helpers for
+        // working with sets of them.
+        if enum_.flags {
+            let unsigned = if enum_.repr.starts_with('u') { " >>> 0" } else { "" };
+            exported.methods.push_str(&format!(
+                "\
+                 combine: {{ value(...flags) {{ \
+                     return flags.reduce((bits, flag) => bits | flag, 0){}; \
+                 }} }},
+                 contains: {{ value(bits, flags) {{ \
+                     return ((bits & flags) ^ flags) === 0; \
+                 }} }},
+                ",
+                unsigned,
+            ));
+            exported.typescript_methods.push_str(&format!(
+                "  export function combine(...flags: {}[]): number;\n  \
+                 export function contains(bits: number, flags: number): boolean;\n",
+                enum_.name,
+            ));
         }
-        self.cx.typescript.push_str("\n}\n");
+        Ok(())
     }
 
     fn generate_struct(&mut self, struct_: &decode::Struct) -> Result<(), Error> {
@@ -2620,34 +4862,67 @@ impl<'a, 'b> SubContext<'a, 'b> {
                 None => continue,
                 Some(d) => d,
             };
+            self.cx.check_compat(
+                &format!("field `{}::{}`", struct_.name, field.name),
+                &descriptor,
+            )?;
 
-            let set = {
+            let (set, signature) = {
                 let setter = ExportedShim::Named(&wasm_setter);
                 let mut cx = Js2Rust::new(&field.name, self.cx);
-                cx.method(true, false)
+                cx.middleware(&format!("set {}.{}", struct_.name, field.name))
+                    .method(true, false)
                     .argument(&descriptor)?
                     .ret(&Descriptor::Unit)?;
-                ts_dst.push_str(&format!(
-                    "\n  {}{}: {};",
-                    if field.readonly { "readonly " } else { "" },
-                    field.name,
+                // TypeScript can't express a property that's only written
+                // to, so those are declared as a setter instead.
+                if field.writeonly {
+                    ts_dst.push_str(&format!(
+                        "\n  set {}(value: {});",
+                        field.name, &cx.js_arguments[0].1
+                    ));
+                } else {
+                    ts_dst.push_str(&format!(
+                        "\n  {}{}: {};",
+                        if field.readonly { "readonly " } else { "" },
+                        field.name,
+                        &cx.js_arguments[0].1
+                    ));
+                }
+                let signature = format!(
+                    "{}field: {}",
+                    if field.readonly {
+                        "readonly "
+                    } else if field.writeonly {
+                        "writeonly "
+                    } else {
+                        ""
+                    },
                     &cx.js_arguments[0].1
-                ));
-                cx.finish("", &format!("wasm.{}", wasm_setter), setter).0
+                );
+                (cx.finish("", &format!("wasm.{}", wasm_setter), setter).0, signature)
             };
-            let getter = ExportedShim::Named(&wasm_getter);
-            let (get, _ts, js_doc) = Js2Rust::new(&field.name, self.cx)
-                .method(true, false)
-                .ret(&descriptor)?
-                .finish("", &format!("wasm.{}", wasm_getter), getter);
+            self.cx
+                .api
+                .insert(&format!("{}.{}", struct_.name, field.name), &signature);
             if !dst.ends_with("\n") {
                 dst.push_str("\n");
             }
-            dst.push_str(&format_doc_comments(&field.comments, Some(js_doc)));
-            dst.push_str("get ");
-            dst.push_str(&field.name);
-            dst.push_str(&get);
-            dst.push_str("\n");
+            if field.writeonly {
+                dst.push_str(&format_doc_comments(&field.comments, None));
+            } else {
+                let getter = ExportedShim::Named(&wasm_getter);
+                let (get, _ts, js_doc) = Js2Rust::new(&field.name, self.cx)
+                    .middleware(&format!("get {}.{}", struct_.name, field.name))
+                    .method(true, false)
+                    .ret(&descriptor)?
+                    .finish("", &format!("wasm.{}", wasm_getter), getter);
+                dst.push_str(&format_doc_comments(&field.comments, Some(js_doc)));
+                dst.push_str("get ");
+                dst.push_str(&field.name);
+                dst.push_str(&get);
+                dst.push_str("\n");
+            }
             if !field.readonly {
                 dst.push_str("set ");
                 dst.push_str(&field.name);
@@ -2663,6 +4938,7 @@ impl<'a, 'b> SubContext<'a, 'b> {
             .entry(struct_.name.to_string())
             .or_insert_with(Default::default);
         class.comments = format_doc_comments(&struct_.comments, None);
+        class.js_tostring = struct_.js_tostring;
         class.contents.push_str(&dst);
         class.contents.push_str("\n");
         class.typescript.push_str(&ts_dst);
@@ -2670,6 +4946,123 @@ impl<'a, 'b> SubContext<'a, 'b> {
         Ok(())
     }
 
+    /// Generates `passFoo` and `takeFoo`, which convert between objects like
+    /// `{ tag: 'Variant', value: ... }` and pointers to the tagged union `Foo`
+    /// in wasm's memory, along with its type in TypeScript.
+    fn generate_tagged_union(&mut self, union: &decode::TaggedUnion) -> Result<(), Error> {
+        let name = union.name;
+        let emit_ts = self.cx.config.emit_ts;
+        let mut pass_cases = String::new();
+        let mut take_cases = String::new();
+        let mut ts_variants = Vec::new();
+        for (i, variant) in union.variants.iter().enumerate() {
+            let new_fn = wasm_bindgen_shared::tagged_union_new(name, variant.name);
+            let unwrap_fn = wasm_bindgen_shared::tagged_union_unwrap(name, variant.name);
+            if !variant.has_value {
+                pass_cases.push_str(&format!(
+                    "case '{}': return wasm.{}();\n",
+                    variant.name, new_fn
+                ));
+                take_cases.push_str(&format!(
+                    "case {}: wasm.{}(ptr); return {{ tag: '{}' }};\n",
+                    i, unwrap_fn, variant.name
+                ));
+                ts_variants.push(format!("{{ tag: '{}' }}", variant.name));
+                continue;
+            }
+            let descriptor = match self.cx.describe(&unwrap_fn) {
+                None => continue,
+                Some(d) => d,
+            };
+
+            let pass = format!("pass{}_{}", name, variant.name);
+            let (js, ts) = {
+                let mut cx = Js2Rust::new(&pass, self.cx);
+                cx.argument(&descriptor)?.ret(&Descriptor::U32)?;
+                let ts = cx.js_arguments[0].1.clone();
+                let prefix = format!("function {}", pass);
+                let shim = ExportedShim::Named(&new_fn);
+                (cx.finish(&prefix, &format!("wasm.{}", new_fn), shim).0, ts)
+            };
+            self.cx.global(&js);
+            pass_cases.push_str(&format!(
+                "case '{}': return {}(obj.value);\n",
+                variant.name, pass
+            ));
+
+            let take = format!("take{}_{}", name, variant.name);
+            let js = {
+                let mut cx = Js2Rust::new(&take, self.cx);
+                cx.js_arguments.push(("ptr".to_string(), "number".to_string()));
+                cx.rust_argument("ptr").ret(&descriptor)?;
+                let prefix = format!("function {}", take);
+                let shim = ExportedShim::Named(&unwrap_fn);
+                cx.finish(&prefix, &format!("wasm.{}", unwrap_fn), shim).0
+            };
+            self.cx.global(&js);
+            take_cases.push_str(&format!(
+                "case {}: return {{ tag: '{}', value: {}(ptr) }};\n",
+                i, variant.name, take
+            ));
+            ts_variants.push(format!("{{ tag: '{}', value: {} }}", variant.name, ts));
+        }
+
+        let tag_fn = wasm_bindgen_shared::tagged_union_tag(name);
+        let (obj, ptr, pass_ret, take_ret) = if emit_ts {
+            (
+                format!("obj: {}", name),
+                "ptr: number".to_string(),
+                ": number".to_string(),
+                format!(": {}", name),
+            )
+        } else {
+            ("obj".to_string(), "ptr".to_string(), String::new(), String::new())
+        };
+        self.cx.global(&format!(
+            "
+            function pass{name}({obj}){pass_ret} {{
+                switch (obj.tag) {{
+                    {pass_cases}
+                    default: throw new Error(`invalid tag of {name}: ${{obj.tag}}`);
+                }}
+            }}
+            ",
+            name = name,
+            obj = obj,
+            pass_ret = pass_ret,
+            pass_cases = pass_cases,
+        ));
+        self.cx.global(&format!(
+            "
+            function take{name}({ptr}){take_ret} {{
+                switch (wasm.{tag_fn}(ptr)) {{
+                    {take_cases}
+                    default: throw new Error('invalid tag of {name}');
+                }}
+            }}
+            ",
+            name = name,
+            ptr = ptr,
+            take_ret = take_ret,
+            tag_fn = tag_fn,
+            take_cases = take_cases,
+        ));
+
+        let ty = ts_variants.join(" | ");
+        let decl = format!(
+            "{}export type {} = {};\n",
+            format_doc_comments(&union.comments, None),
+            name,
+            ty
+        );
+        if emit_ts {
+            self.cx.global(&decl);
+        }
+        self.cx.typescript.push_str(&decl);
+        self.cx.api.insert(name, &format!("type {}", ty));
+        Ok(())
+    }
+
     fn register_vendor_prefix(&mut self, info: &decode::ImportType<'b>) {
         if info.vendor_prefixes.len() == 0 {
             return;
@@ -2697,6 +5090,19 @@ impl<'a, 'b> SubContext<'a, 'b> {
             }
         }
 
+        // Snippets are ES modules, which can't be loaded with `require`.
+        if self.cx.use_node_require() {
+            if let Some(module) = &import.module {
+                if self.cx.snippets.contains_key(*module) {
+                    bail!(
+                        "import from local JS snippet `{}` not allowed with `--nodejs`; \
+                         use `--nodejs-experimental-modules` or `--browser` instead",
+                        module
+                    );
+                }
+            }
+        }
+
         // Similar to `--no-modules`, only allow vendor prefixes basically for web
         // apis, shouldn't be necessary for things like npm packages or other
         // imported items.
@@ -2786,6 +5192,17 @@ fn generate_identifier(name: &str, used_names: &mut HashMap<String, usize>) -> S
     }
 }
 
+/// Strips the name off of a TypeScript signature, `foo(a: number): number;`,
+/// for the description of the API.
+fn api_signature<'a>(ts: &'a str, name: &str) -> &'a str {
+    let ts = ts.trim_end_matches(';');
+    if ts.starts_with(name) {
+        &ts[name.len()..]
+    } else {
+        ts
+    }
+}
+
 fn format_doc_comments(comments: &[&str], js_doc_comments: Option<String>) -> String {
     let body: String = comments
         .iter()
@@ -2799,6 +5216,55 @@ fn format_doc_comments(comments: &[&str], js_doc_comments: Option<String>) -> St
     format!("/**\n{}{}*/\n", body, doc)
 }
 
+/// Handles the TypeScript types of the glue's internal helpers, which are
+/// written in comments like `function getObject(idx/*: number*/)`, or
+/// `reset/*?: boolean*/` for optional parameters.
+///
+/// With `--emit-ts` the comments become the types of the helpers, otherwise
+/// they're removed from the JS.
+fn type_annotations(js: &str, emit_ts: bool) -> String {
+    let mut ret = String::with_capacity(js.len());
+    let mut rest = js;
+    loop {
+        let start = match (rest.find("/*:"), rest.find("/*?:")) {
+            (Some(a), Some(b)) => a.min(b),
+            (Some(a), None) | (None, Some(a)) => a,
+            (None, None) => break,
+        };
+        let end = match rest[start..].find("*/") {
+            Some(i) => start + i,
+            None => break,
+        };
+        ret.push_str(&rest[..start]);
+        if emit_ts {
+            ret.push_str(rest[start + 2..end].trim_end());
+        }
+        rest = &rest[end + 2..];
+    }
+    ret.push_str(rest);
+    ret
+}
+
+#[test]
+fn type_annotations_become_types_or_are_removed() {
+    let js = "function f(a/*: number*/, b/*?: string */)/*: any*/ { return a; }";
+    assert_eq!(
+        type_annotations(js, true),
+        "function f(a: number, b?: string): any { return a; }"
+    );
+    assert_eq!(
+        type_annotations(js, false),
+        "function f(a, b) { return a; }"
+    );
+    // Other comments are left alone.
+    let js = "/* a comment */ let x/*: Uint8Array | null*/ = null;";
+    assert_eq!(
+        type_annotations(js, true),
+        "/* a comment */ let x: Uint8Array | null = null;"
+    );
+    assert_eq!(type_annotations(js, false), "/* a comment */ let x = null;");
+}
+
 #[test]
 fn test_generate_identifier() {
     let mut used_names: HashMap<String, usize> = HashMap::new();
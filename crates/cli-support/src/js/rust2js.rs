@@ -1,4 +1,4 @@
-use crate::descriptor::{Descriptor, Function};
+use crate::descriptor::{Descriptor, Function, VectorKind};
 use crate::js::js2rust::ExportedShim;
 use crate::js::{Context, ImportTarget, Js2Rust};
 use failure::{bail, Error};
@@ -45,6 +45,9 @@ pub struct Rust2Js<'a, 'b: 'a> {
     /// or not.
     pub anyref_args: Vec<(usize, bool)>,
     pub ret_anyref: bool,
+
+    /// Name this shim's calls are counted under with `--profile-boundary`.
+    profile: Option<String>,
 }
 
 impl<'a, 'b> Rust2Js<'a, 'b> {
@@ -63,6 +66,7 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
             variadic: false,
             anyref_args: Vec::new(),
             ret_anyref: false,
+            profile: None,
         }
     }
 
@@ -81,6 +85,17 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
         self
     }
 
+    /// Counts calls of this shim and the time spent in them under `name`.
+    ///
+    /// This does nothing unless `--profile-boundary` was enabled in the
+    /// configuration.
+    pub fn profile_boundary(&mut self, name: &str) -> &mut Self {
+        if self.cx.config.profile_boundary {
+            self.profile = Some(name.to_string());
+        }
+        self
+    }
+
     /// Generates all bindings necessary for the signature in `Function`,
     /// creating necessary argument conversions and return value processing.
     pub fn process(&mut self, function: &Function) -> Result<&mut Self, Error> {
@@ -100,13 +115,86 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
     }
 
     fn argument(&mut self, arg: &Descriptor) -> Result<(), Error> {
+        // Branded types are only different in TypeScript.
+        if let Descriptor::Named(_, inner) = arg {
+            return self.argument(inner);
+        }
+        if let Some(erased) = arg.erase_generic() {
+            return self.argument(&erased);
+        }
         let abi = self.shim_argument();
 
+        if let Some((union, optional)) = arg.tagged_union() {
+            self.js_arguments.push(if optional {
+                format!("{0} === 0 ? undefined : take{1}({0})", abi, union)
+            } else {
+                format!("take{}({})", union, abi)
+            });
+            return Ok(());
+        }
+
         let (arg, optional) = match arg {
             Descriptor::Option(t) => (&**t, true),
             _ => (arg, false),
         };
 
+        // Imported functions always see maps as `Map`s.
+        if let Descriptor::Map(_) = arg {
+            let abi2 = self.shim_argument();
+            let f = self.cx.expose_get_vector_from_wasm(VectorKind::Anyref)?;
+            let take = self.cx.expose_map_from_entries();
+            self.prelude(&format!(
+                "\
+                 let v{0};
+                 {start}
+                 v{0} = {take}({f}({0}, {1}));
+                 wasm.__wbindgen_free({0}, {1} * 4);
+                 {end}\
+                 ",
+                abi,
+                abi2,
+                f = f,
+                take = take,
+                start = if optional {
+                    format!("if ({} !== 0) {{", abi)
+                } else {
+                    String::new()
+                },
+                end = if optional { "}" } else { "" },
+            ));
+            self.cx.require_internal_export("__wbindgen_free")?;
+            self.js_arguments.push(format!("v{}", abi));
+            return Ok(());
+        }
+
+        if let Some(class) = arg.vector_rust_struct() {
+            let abi2 = self.shim_argument();
+            let f = self.cx.expose_get_vector_from_wasm(VectorKind::U32)?;
+            self.cx.require_class_wrap(class);
+            self.prelude(&format!(
+                "\
+                 let v{0};
+                 {start}
+                 v{0} = Array.from({f}({0}, {1}), ptr => {class}.__wrap(ptr));
+                 wasm.__wbindgen_free({0}, {1} * 4);
+                 {end}\
+                 ",
+                abi,
+                abi2,
+                f = f,
+                class = class,
+                start = if optional {
+                    format!("if ({} !== 0) {{", abi)
+                } else {
+                    String::new()
+                },
+                end = if optional { "}" } else { "" },
+            ));
+            self.cx.require_internal_export("__wbindgen_free")?;
+            self.js_arguments.push(format!("v{}", abi));
+            return Ok(());
+        }
+
         if let Some(ty) = arg.vector_kind() {
             let abi2 = self.shim_argument();
             let f = self.cx.expose_get_vector_from_wasm(ty)?;
@@ -224,8 +312,8 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
                     return Ok(());
                 }
                 Descriptor::RustStruct(ref class) => {
-                    self.cx.require_class_wrap(class);
-                    let assign = format!("let c{0} = {0} === 0 ? undefined : {1}.__wrap({0});", abi, class);
+                    let value = self.wrap_struct(class, &abi);
+                    let assign = format!("let c{0} = {0} === 0 ? undefined : {1};", abi, value);
                     self.prelude(&assign);
                     self.js_arguments.push(format!("c{}", abi));
                     return Ok(());
@@ -264,8 +352,8 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
             if arg.is_by_ref() {
                 bail!("cannot invoke JS functions with custom ref types yet")
             }
-            self.cx.require_class_wrap(class);
-            let assign = format!("let c{0} = {1}.__wrap({0});", abi, class);
+            let value = self.wrap_struct(class, &abi);
+            let assign = format!("let c{0} = {1};", abi, value);
             self.prelude(&assign);
             self.js_arguments.push(format!("c{}", abi));
             return Ok(());
@@ -324,6 +412,27 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
     }
 
     fn ret(&mut self, ty: &Descriptor) -> Result<(), Error> {
+        if let Descriptor::Named(_, inner) = ty {
+            return self.ret(inner);
+        }
+        if let Some(erased) = ty.erase_generic() {
+            return self.ret(&erased);
+        }
+        if let Some((union, optional)) = ty.tagged_union() {
+            self.ret_expr = if optional {
+                self.cx.expose_is_like_none();
+                format!(
+                    "
+                    const val = JS;
+                    return isLikeNone(val) ? 0 : pass{}(val);
+                ",
+                    union
+                )
+            } else {
+                format!("return pass{}(JS);", union)
+            };
+            return Ok(());
+        }
         if let Descriptor::Unit = ty {
             self.ret_expr = "JS;".to_string();
             return Ok(());
@@ -335,6 +444,79 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
         if ty.is_by_ref() {
             bail!("cannot return a reference from JS to Rust")
         }
+        if let Descriptor::Map(_) = ty {
+            let f = self.cx.pass_to_wasm_function(VectorKind::Anyref)?;
+            self.cx.expose_map_entries();
+            self.cx.expose_uint32_memory();
+            self.shim_arguments.insert(0, "ret".to_string());
+            let (start, end) = if optional {
+                self.cx.expose_is_like_none();
+                ("if (!isLikeNone(val)) {", "}")
+            } else {
+                ("", "")
+            };
+            self.ret_expr = format!(
+                "\
+                const val = JS;
+                let retptr = 0;
+                let retlen = 0;
+                {start}
+                retptr = {f}(mapEntries(val));
+                retlen = WASM_VECTOR_LEN;
+                {end}
+                const mem = getUint32Memory();
+                mem[ret / 4] = retptr;
+                mem[ret / 4 + 1] = retlen;
+                ",
+                start = start,
+                end = end,
+                f = f,
+            );
+            return Ok(());
+        }
+
+        if let Some(class) = ty.vector_rust_struct() {
+            // As with single structs, everything returned is checked to be an
+            // instance of `class` before any of it is moved into Rust.
+            let f = self.cx.pass_to_wasm_function(VectorKind::U32)?;
+            self.cx.expose_uint32_memory();
+            self.shim_arguments.insert(0, "ret".to_string());
+            let (start, end) = if optional {
+                self.cx.expose_is_like_none();
+                ("if (!isLikeNone(val)) {", "}")
+            } else {
+                ("", "")
+            };
+            self.ret_expr = format!(
+                "\
+                const val = JS;
+                let retptr = 0;
+                let retlen = 0;
+                {start}
+                for (const elem of val) {{
+                    if (!(elem instanceof {class})) {{
+                        throw new Error('expected value of type {class}');
+                    }}
+                }}
+                const ptrs = [];
+                for (const elem of val) {{
+                    ptrs.push(elem.ptr);
+                    elem.ptr = 0;
+                }}
+                retptr = {f}(ptrs);
+                retlen = WASM_VECTOR_LEN;
+                {end}
+                const mem = getUint32Memory();
+                mem[ret / 4] = retptr;
+                mem[ret / 4 + 1] = retlen;
+                ",
+                start = start,
+                end = end,
+                class = class,
+                f = f,
+            );
+            return Ok(());
+        }
         if let Some(ty) = ty.vector_kind() {
             let f = self.cx.pass_to_wasm_function(ty)?;
             self.cx.expose_uint32_memory();
@@ -612,15 +794,23 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
 
     pub fn finish(&mut self, invoc: &ImportTarget, shim: &str) -> Result<String, Error> {
         let mut ret = String::new();
-        ret.push_str("function(");
-        ret.push_str(&self.shim_arguments.join(", "));
+        // Everything passed from wasm is a number.
+        let mut args = self
+            .shim_arguments
+            .iter()
+            .map(|arg| format!("{}/*: number*/", arg))
+            .collect::<Vec<_>>();
         if self.catch {
-            if self.shim_arguments.len() > 0 {
-                ret.push_str(", ")
-            }
-            ret.push_str("exnptr");
+            args.push("exnptr/*: number*/".to_string());
         }
+        ret.push_str("function(");
+        ret.push_str(&args.join(", "));
+
         ret.push_str(") {\n");
+        if let Some(name) = &self.profile {
+            self.cx.expose_profile_boundary();
+            ret.push_str(&format!("return _profileBoundary('{}', () => {{\n", name));
+        }
         ret.push_str(&self.prelude);
 
         let variadic = self.variadic;
@@ -671,6 +861,12 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
                 };
                 handle_variadic(&format!("{}.{}", receiver, f), args)?
             }
+            ImportTarget::Static(name) => {
+                if variadic || self.js_arguments.len() > 0 {
+                    bail!("static getters can't have arguments");
+                }
+                self.ret_expr.replace("JS", &name)
+            }
             ImportTarget::StructuralGetter(class, field) => {
                 let (receiver, _) = fixed("getter", class, 0)?;
                 let expr = format!("{}.{}", receiver, field);
@@ -739,6 +935,9 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
             );
         }
         ret.push_str(&invoc);
+        if self.profile.is_some() {
+            ret.push_str("\n});");
+        }
 
         ret.push_str("\n}\n");
 
@@ -771,6 +970,18 @@ impl<'a, 'b> Rust2Js<'a, 'b> {
         Ok(ret)
     }
 
+    /// Returns the JS object for a Rust struct of `class` passed by value as
+    /// the pointer `abi`.
+    fn wrap_struct(&mut self, class: &str, abi: &str) -> String {
+        self.cx.require_class_wrap(class);
+        let value = format!("{}.__wrap({})", class, abi);
+        if self.cx.plain_classes.contains(class) {
+            self.cx.expose_plain_object();
+            return format!("plainObject({})", value);
+        }
+        value
+    }
+
     fn global_idx(&mut self) -> usize {
         let ret = self.global_idx;
         self.global_idx += 1;
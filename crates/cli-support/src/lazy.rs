@@ -0,0 +1,204 @@
+//! Splitting exports marked `#[wasm_bindgen(lazy)]` into a wasm module of
+//! their own, `<name>_lazy_bg.wasm`, which the glue only imports the first
+//! time one of them is called.
+//!
+//! The lazy module starts out as a copy of the main module, of which only the
+//! lazy exports and whatever they call are kept. Its memory, function table
+//! and mutable globals, like the stack pointer, are imported from the main
+//! module rather than being its own, so both modules share all of their
+//! state. Functions called from both modules end up in both of them, which
+//! costs some size but means neither has to call into the other.
+
+use failure::{bail, Error, ResultExt};
+use walrus::{ExportItem, GlobalKind, ImportKind, Module, TableKind};
+
+/// Prefix of the exports of the main module which the lazy module imports.
+const SHARED_PREFIX: &str = "__wbg_lazy_";
+
+/// Moves the exports named `lazy` out of `module` into a new module, which
+/// imports everything it shares with `module` from the module named `main`.
+pub fn split(module: &mut Module, lazy: &[String], main: &str) -> Result<Module, Error> {
+    for name in lazy {
+        if !module.exports.iter().any(|e| e.name == *name) {
+            bail!("lazy export `{}` not found in the wasm module", name);
+        }
+    }
+
+    // Export everything that's shared under names of our own, so they can be
+    // found in the copy as well.
+    let memories = module.memories.iter().map(|m| m.id()).collect::<Vec<_>>();
+    for (i, id) in memories.into_iter().enumerate() {
+        module
+            .exports
+            .add(&format!("{}memory{}", SHARED_PREFIX, i), id);
+    }
+    if let Some(id) = module.tables.main_function_table()? {
+        module.exports.add(&format!("{}table", SHARED_PREFIX), id);
+    }
+    let globals = module
+        .globals
+        .iter()
+        .filter(|g| g.mutable)
+        .map(|g| g.id())
+        .collect::<Vec<_>>();
+    for (i, id) in globals.into_iter().enumerate() {
+        module
+            .exports
+            .add(&format!("{}global{}", SHARED_PREFIX, i), id);
+    }
+
+    let bytes = module.emit_wasm()?;
+    let mut lazy_module = walrus::ModuleConfig::new()
+        .strict_validate(false)
+        .parse(&bytes)
+        .context("failed to parse a copy of the wasm module")?;
+    import_shared(&mut lazy_module, main);
+
+    let to_delete = lazy_module
+        .exports
+        .iter()
+        .filter(|e| !lazy.contains(&e.name))
+        .map(|e| e.id())
+        .collect::<Vec<_>>();
+    for id in to_delete {
+        lazy_module.exports.delete(id);
+    }
+    lazy_module.start = None;
+    walrus::passes::gc::run(&mut lazy_module);
+
+    let to_delete = module
+        .exports
+        .iter()
+        .filter(|e| lazy.contains(&e.name))
+        .map(|e| e.id())
+        .collect::<Vec<_>>();
+    for id in to_delete {
+        module.exports.delete(id);
+    }
+    walrus::passes::gc::run(module);
+
+    Ok(lazy_module)
+}
+
+/// Switches everything the copy of the main module exports as shared over to
+/// being imported from `main`.
+fn import_shared(module: &mut Module, main: &str) {
+    let mut memories = Vec::new();
+    let mut tables = Vec::new();
+    let mut globals = Vec::new();
+    for export in module.exports.iter() {
+        if !export.name.starts_with(SHARED_PREFIX) {
+            continue;
+        }
+        match export.item {
+            ExportItem::Memory(id) => memories.push((export.name.clone(), id)),
+            ExportItem::Table(id) => tables.push((export.name.clone(), id)),
+            ExportItem::Global(id) => globals.push((export.name.clone(), id)),
+            ExportItem::Function(_) => {}
+        }
+    }
+
+    // The main module has already initialized memory and the table, so the
+    // lazy module's copies of their contents are dropped.
+    for (name, id) in memories {
+        let import = module.imports.add(main, &name, ImportKind::Memory(id));
+        let memory = module.memories.get_mut(id);
+        memory.import = Some(import);
+        memory.data = Default::default();
+    }
+    for (name, id) in tables {
+        let import = module.imports.add(main, &name, ImportKind::Table(id));
+        let table = module.tables.get_mut(id);
+        table.import = Some(import);
+        if let TableKind::Function(f) = &mut table.kind {
+            f.elements.clear();
+            f.relative_elements.clear();
+        }
+    }
+    for (name, id) in globals {
+        let import = module.imports.add(main, &name, ImportKind::Global(id));
+        module.globals.get_mut(id).kind = GlobalKind::Import(import);
+    }
+}
+
+#[test]
+fn splits_lazy_exports() {
+    // (import "__wbindgen_placeholder__" "__wbindgen_describe"
+    //   (func $describe (param i32)))
+    // (table 1 anyfunc)
+    // (memory 1)
+    // (global $sp (mut i32) (i32.const 1024))
+    // (func $shared (result i32) i32.const 7)
+    // (func (export "eager") call $shared call $describe)
+    // (func (export "lazy")
+    //   ;; Goes through the stack in memory, like a debug build would.
+    //   global.get $sp i32.const 16 i32.sub global.set $sp
+    //   global.get $sp call $shared i32.store
+    //   global.get $sp i32.load i32.const 1 i32.add call $describe
+    //   global.get $sp i32.const 16 i32.add global.set $sp)
+    // (elem (i32.const 0) 2)
+    // (data (i32.const 0) "hi")
+    let wasm = b"\0asm\x01\0\0\0\
+        \x01\x0c\x03\x60\x01\x7f\x00\x60\x00\x01\x7f\x60\x00\x00\
+        \x02\x30\x01\x18__wbindgen_placeholder__\x13__wbindgen_describe\x00\x00\
+        \x03\x04\x03\x01\x02\x02\
+        \x04\x04\x01\x70\x00\x01\
+        \x05\x03\x01\x00\x01\
+        \x06\x07\x01\x7f\x01\x41\x80\x08\x0b\
+        \x07\x10\x02\x05eager\x00\x02\x04lazy\x00\x03\
+        \x09\x07\x01\x00\x41\x00\x0b\x01\x02\
+        \x0a\x2f\x03\
+        \x04\x00\x41\x07\x0b\
+        \x06\x00\x10\x01\x10\x00\x0b\
+        \x21\x00\x23\x00\x41\x10\x6b\x24\x00\x23\x00\x10\x01\x36\x02\x00\
+        \x23\x00\x28\x02\x00\x41\x01\x6a\x10\x00\x23\x00\x41\x10\x6a\x24\x00\x0b\
+        \x0b\x08\x01\x00\x41\x00\x0b\x02hi";
+    let mut module = walrus::ModuleConfig::new().parse(wasm).unwrap();
+    let lazy = split(&mut module, &["lazy".to_string()], "./main_bg").unwrap();
+
+    // The main module keeps its other exports, and exports what it shares.
+    let exports = module
+        .exports
+        .iter()
+        .map(|e| e.name.clone())
+        .collect::<Vec<_>>();
+    assert!(exports.contains(&"eager".to_string()));
+    assert!(!exports.contains(&"lazy".to_string()));
+    for name in ["memory0", "table", "global0"].iter() {
+        assert!(exports.contains(&format!("{}{}", SHARED_PREFIX, name)));
+    }
+
+    // The lazy module only exports the lazy function, and imports whatever
+    // it uses of the main module's state.
+    let exports = lazy
+        .exports
+        .iter()
+        .map(|e| e.name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(exports, ["lazy"]);
+    let shared = lazy
+        .imports
+        .iter()
+        .filter(|i| i.module == "./main_bg")
+        .map(|i| i.name.clone())
+        .collect::<Vec<_>>();
+    assert!(shared.contains(&format!("{}memory0", SHARED_PREFIX)));
+    assert!(shared.contains(&format!("{}global0", SHARED_PREFIX)));
+    assert!(shared.iter().all(|name| name.starts_with(SHARED_PREFIX)));
+    assert!(lazy.memories.iter().all(|m| m.import.is_some()));
+    assert!(lazy.tables.iter().all(|t| t.import.is_some()));
+
+    // Both functions still work, and the lazy one has its own copy of the
+    // function it has in common with the eager one.
+    let mut interpreter = wasm_bindgen_wasm_interpreter::Interpreter::new(&module).unwrap();
+    assert_eq!(
+        interpreter.interpret_descriptor("eager", &module),
+        Some(&[7][..])
+    );
+    assert_eq!(interpreter.interpret_descriptor("lazy", &module), None);
+    let mut interpreter = wasm_bindgen_wasm_interpreter::Interpreter::new(&lazy).unwrap();
+    assert_eq!(
+        interpreter.interpret_descriptor("lazy", &lazy),
+        Some(&[8][..])
+    );
+}
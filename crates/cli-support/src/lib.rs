@@ -1,17 +1,33 @@
 #![doc(html_root_url = "https://docs.rs/wasm-bindgen-cli-support/0.2")]
 
+use crate::descriptor::Descriptor;
 use failure::{bail, Error, ResultExt};
-use std::collections::BTreeSet;
+use rayon::prelude::*;
+use sha2::{Digest, Sha384};
+use std::collections::{BTreeSet, HashMap};
 use std::env;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::mem;
 use std::path::{Path, PathBuf};
 use std::str;
+use std::time::Instant;
 use walrus::Module;
+use wasm_bindgen_wasm_interpreter::Interpreter;
 
+mod api;
+mod cache;
 mod decode;
 mod descriptor;
 mod js;
+mod lazy;
+mod loader;
+mod minify;
+mod passthrough;
+mod proposals;
+mod snippets;
+mod source_map;
+mod stream;
 pub mod wasm2es6js;
 
 pub struct Bindgen {
@@ -22,6 +38,8 @@ pub struct Bindgen {
     browser: bool,
     no_modules: bool,
     no_modules_global: Option<String>,
+    multi_instance: bool,
+    deno: bool,
     debug: bool,
     typescript: bool,
     demangle: bool,
@@ -29,6 +47,26 @@ pub struct Bindgen {
     remove_name_section: bool,
     remove_producers_section: bool,
     emit_start: bool,
+    middleware: bool,
+    profile_boundary: bool,
+    sync_init_max_size: Option<usize>,
+    required_imports: bool,
+    browser_extension: bool,
+    wasm_features: Vec<String>,
+    variants: Option<String>,
+    cache_dir: Option<PathBuf>,
+    single_threaded: bool,
+    low_memory: bool,
+    split_debuginfo: Option<String>,
+    hashed_wasm: bool,
+    wasm_url_base: Option<String>,
+    minify_js: bool,
+    source_map: bool,
+    emit_ts: bool,
+    compat: Option<String>,
+    api_json: bool,
+    api_baseline: Option<PathBuf>,
+    api_renames: Option<String>,
     // Experimental support for `WeakRefGroup`, an upcoming ECMAScript feature.
     // Currently only enable-able through an env var.
     weak_refs: bool,
@@ -40,10 +78,19 @@ pub struct Bindgen {
 
 enum Input {
     Path(PathBuf),
+    Bytes(Vec<u8>, String),
     Module(Module, String),
     None,
 }
 
+/// The files generated by `Bindgen::generate_output`, kept in memory.
+pub struct Output {
+    files: Vec<(PathBuf, Vec<u8>)>,
+    // Files which are only put together as they're written by `emit`, as
+    // they're made of sections of an input mapped with `low_memory`.
+    spliced: Vec<(PathBuf, stream::Spliced)>,
+}
+
 impl Bindgen {
     pub fn new() -> Bindgen {
         Bindgen {
@@ -54,6 +101,8 @@ impl Bindgen {
             browser: false,
             no_modules: false,
             no_modules_global: None,
+            multi_instance: false,
+            deno: false,
             debug: false,
             typescript: false,
             demangle: true,
@@ -61,6 +110,26 @@ impl Bindgen {
             remove_name_section: false,
             remove_producers_section: false,
             emit_start: true,
+            middleware: false,
+            profile_boundary: false,
+            sync_init_max_size: None,
+            required_imports: false,
+            browser_extension: false,
+            wasm_features: Vec::new(),
+            variants: None,
+            cache_dir: None,
+            single_threaded: false,
+            low_memory: false,
+            split_debuginfo: None,
+            hashed_wasm: false,
+            wasm_url_base: None,
+            minify_js: false,
+            source_map: false,
+            emit_ts: false,
+            compat: None,
+            api_json: false,
+            api_baseline: None,
+            api_renames: None,
             weak_refs: env::var("WASM_BINDGEN_WEAKREF").is_ok(),
             threads: threads_config(),
             anyref: env::var("WASM_BINDGEN_ANYREF").is_ok(),
@@ -77,6 +146,14 @@ impl Bindgen {
         self
     }
 
+    /// Specify the contents of the input wasm file, for builds which already
+    /// have it in memory. Generated files are named after `name`, like they
+    /// would be after the file name of an input path.
+    pub fn input_bytes(&mut self, name: &str, bytes: Vec<u8>) -> &mut Bindgen {
+        self.input = Input::Bytes(bytes, name.to_string());
+        self
+    }
+
     /// Explicitly specify the already parsed input module.
     pub fn input_module(&mut self, name: &str, module: Module) -> &mut Bindgen {
         let name = name.to_string();
@@ -104,11 +181,48 @@ impl Bindgen {
         self
     }
 
+    /// Let `--no-modules` output create independent instances of the module,
+    /// each with its own memory and classes, with `initInstance`.
+    pub fn multi_instance(&mut self, multi_instance: bool) -> &mut Bindgen {
+        self.multi_instance = multi_instance;
+        self
+    }
+
     pub fn no_modules_global(&mut self, name: &str) -> &mut Bindgen {
         self.no_modules_global = Some(name.to_string());
         self
     }
 
+    /// Generate an ES module for Deno, which loads and instantiates the wasm
+    /// file itself with `Deno.readFile`, or `fetch` when it's served over
+    /// HTTP.
+    pub fn deno(&mut self, deno: bool) -> &mut Bindgen {
+        self.deno = deno;
+        self
+    }
+
+    /// Tailor `--no-modules` output for use in browser extensions, loading
+    /// the wasm file from the extension's package by default.
+    ///
+    /// This requires `no_modules` to be enabled as well, generating bindings
+    /// fails otherwise.
+    pub fn browser_extension(&mut self, browser_extension: bool) -> &mut Bindgen {
+        self.browser_extension = browser_extension;
+        self
+    }
+
+    /// Prepares the module to be instantiated on many threads sharing its
+    /// memory, which is otherwise only enabled by `WASM_BINDGEN_THREADS` in
+    /// the environment.
+    pub fn threads(&mut self, threads: bool) -> &mut Bindgen {
+        if !threads {
+            self.threads = None;
+        } else if self.threads.is_none() {
+            self.threads = Some(wasm_bindgen_threads_xform::Config::new());
+        }
+        self
+    }
+
     pub fn debug(&mut self, debug: bool) -> &mut Bindgen {
         self.debug = debug;
         self
@@ -144,40 +258,273 @@ impl Bindgen {
         self
     }
 
+    /// Wrap all exported functions and methods so they invoke middleware
+    /// registered at runtime through the generated `addMiddleware` function.
+    pub fn middleware(&mut self, middleware: bool) -> &mut Bindgen {
+        self.middleware = middleware;
+        self
+    }
+
+    /// Count the calls of every import and export shim and the time spent in
+    /// them, which can be retrieved through the generated
+    /// `__wbg_boundary_stats` function.
+    pub fn profile_boundary(&mut self, profile: bool) -> &mut Bindgen {
+        self.profile_boundary = profile;
+        self
+    }
+
+    /// Make the generated `initSync` function throw if it's passed more than
+    /// `size` bytes, as browsers refuse to synchronously compile large modules
+    /// on the main thread.
+    ///
+    /// Only `--no-modules` output has an `initSync` function, as it's the only
+    /// output initialized by calling a function at all.
+    pub fn sync_init_max_size(&mut self, size: usize) -> &mut Bindgen {
+        self.sync_init_max_size = Some(size);
+        self
+    }
+
+    /// Export a `requiredImports` function from the generated JS listing all
+    /// imports of the final wasm module.
+    pub fn required_imports(&mut self, required_imports: bool) -> &mut Bindgen {
+        self.required_imports = required_imports;
+        self
+    }
+
+    /// Override the wasm target features the input module is assumed to use.
+    ///
+    /// `features` is a comma-separated list of LLVM feature names, where a
+    /// leading `-` means the feature isn't used even if the module says it
+    /// is, and a leading `+` means it is used even if the module doesn't say
+    /// so. Instructions of the `tail-call` and `relaxed-simd` features are
+    /// passed through to the output unchanged.
+    pub fn wasm_features(&mut self, features: &str) -> &mut Bindgen {
+        self.wasm_features = features
+            .split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect();
+        self
+    }
+
+    /// Also emit a `{name}_loader.js` which imports the first of `variants`
+    /// whose wasm features are all supported by the engine it runs in,
+    /// falling back to the output of this run.
+    ///
+    /// `variants` is a comma-separated list of builds like
+    /// `simd+threads=./mt/app.js`, in order of preference.
+    pub fn variants(&mut self, variants: &str) -> &mut Bindgen {
+        self.variants = Some(variants.to_string());
+        self
+    }
+
+    /// Cache generated bindings in `dir`, keyed by the contents of the input
+    /// and the configuration, and reuse them if nothing has changed.
+    ///
+    /// Only has an effect when the input is a path or bytes, as a parsed
+    /// module would have to be emitted again to look it up.
+    pub fn cache_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Bindgen {
+        self.cache_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Do all work on the current thread, at the cost of speed.
+    ///
+    /// Work which is otherwise done in parallel needs a copy of some state per
+    /// thread, like the interpreter describing exports, so this lowers peak
+    /// memory usage somewhat. Exports are also described as they're needed
+    /// rather than all up front. The module itself is still parsed into memory
+    /// in its entirety.
+    pub fn single_threaded(&mut self, single_threaded: bool) -> &mut Bindgen {
+        self.single_threaded = single_threaded;
+        self
+    }
+
+    /// Keep the data and debug info of the input out of memory, for modules
+    /// too large to otherwise process.
+    ///
+    /// The input file is mapped into memory rather than read, and its data
+    /// and DWARF sections aren't parsed but written straight from the mapped
+    /// file into the output files. Everything else, including all code, is
+    /// still parsed into memory. Data can't be set aside when it's prepared
+    /// for threads or when instructions refer to its segments.
+    ///
+    /// Only has an effect when the input is a path and the output is written
+    /// by `generate`, as `generate_output` keeps all output in memory.
+    pub fn low_memory(&mut self, low_memory: bool) -> &mut Bindgen {
+        self.low_memory = low_memory;
+        self
+    }
+
+    /// Move the DWARF debug info of the input into a separate wasm file named
+    /// `name` in the output directory, which the main wasm file references
+    /// through an `external_debug_info` section.
+    ///
+    /// Debuggers which support it load the debug info from `name` relative to
+    /// the URL of the main wasm file when it's needed.
+    pub fn split_debuginfo(&mut self, name: &str) -> &mut Bindgen {
+        self.split_debuginfo = Some(name.to_string());
+        self
+    }
+
+    /// Name the wasm file after a hash of its contents, `stem.<hash>_bg.wasm`,
+    /// so it can be cached forever, and have the generated JS check its
+    /// subresource integrity when fetching it.
+    ///
+    /// The JS then loads the wasm file from next to itself by default, as it
+    /// knows its name.
+    pub fn hashed_wasm(&mut self, hashed: bool) -> &mut Bindgen {
+        self.hashed_wasm = hashed;
+        self
+    }
+
+    /// Configure what the default URL of the wasm file is resolved against
+    /// in `--no-modules` output, when `init` isn't given a URL or a base.
+    ///
+    /// This is either `script` for the URL of the script itself, which is the
+    /// default, `document` for the base URL of the document, or a URL to use
+    /// as the base.
+    pub fn wasm_url_base(&mut self, base: &str) -> &mut Bindgen {
+        self.wasm_url_base = Some(base.to_string());
+        self
+    }
+
+    /// Remove comments and whitespace from the generated JS.
+    pub fn minify_js(&mut self, minify: bool) -> &mut Bindgen {
+        self.minify_js = minify;
+        self
+    }
+
+    /// Emit a source map of the JS glue next to it, which relates each of its
+    /// functions and classes to the export or import it's for.
+    pub fn source_map(&mut self, source_map: bool) -> &mut Bindgen {
+        self.source_map = source_map;
+        self
+    }
+
+    /// Emit the glue as TypeScript rather than JS, so exported functions and
+    /// classes are annotated with the same types their declarations would
+    /// have otherwise been given in a `.d.ts` file.
+    pub fn emit_ts(&mut self, emit: bool) -> &mut Bindgen {
+        self.emit_ts = emit;
+        self
+    }
+
+    /// Only use JS features of the ECMAScript version `compat`, currently
+    /// only `es2017`, for older engines like the WebViews of embedded
+    /// devices.
+    ///
+    /// `TextEncoder` and `TextDecoder` fall back to a JS implementation where
+    /// they're missing, and 64-bit integers, which are passed as `BigInt`s,
+    /// are rejected.
+    pub fn compat(&mut self, compat: &str) -> &mut Bindgen {
+        self.compat = Some(compat.to_string());
+        self
+    }
+
+    /// Also write a description of the exported JS API to `{name}.api.json`,
+    /// which a later build can be checked against with `api_baseline`.
+    pub fn api_json(&mut self, json: bool) -> &mut Bindgen {
+        self.api_json = json;
+        self
+    }
+
+    /// Fail if the exported JS API has breaking changes compared to the one
+    /// described by `path`, as written by `api_json` for a previous build.
+    pub fn api_baseline<P: AsRef<Path>>(&mut self, path: P) -> &mut Bindgen {
+        self.api_baseline = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Declare that exports have been renamed, which keeps their old names
+    /// working as deprecated aliases.
+    ///
+    /// `renames` is a comma-separated list of top-level exports like
+    /// `old=new`.
+    pub fn api_renames(&mut self, renames: &str) -> &mut Bindgen {
+        self.api_renames = Some(renames.to_string());
+        self
+    }
+
     pub fn generate<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
-        self._generate(path.as_ref())
+        let out_dir = path.as_ref();
+        // On a miss the input is read once more to generate the bindings,
+        // which is cheap next to generating them.
+        let cache_entry = match &self.cache_dir {
+            Some(dir) => self.cache_key(out_dir)?.map(|key| dir.join(key)),
+            None => None,
+        };
+        if let Some(entry) = &cache_entry {
+            if cache::restore(entry, out_dir)? {
+                return Ok(());
+            }
+        }
+        let written = self._generate()?.emit(out_dir)?;
+        if let Some(entry) = cache_entry {
+            cache::store(&entry, out_dir, &written)?;
+        }
+        Ok(())
     }
 
-    fn _generate(&mut self, out_dir: &Path) -> Result<(), Error> {
-        let (mut module, stem) = match self.input {
+    /// Generates bindings without writing anything to disk, returning the
+    /// contents of every file which `generate` would otherwise write.
+    ///
+    /// This is meant for build tools which run wasm-bindgen as a library and
+    /// handle the output themselves. The cache configured with `cache_dir`
+    /// isn't used.
+    ///
+    /// There's no separate type of diagnostics: nothing is reported besides
+    /// the generated files, and anything which stops bindings from being
+    /// generated is an error. Errors describe what went wrong from the
+    /// outermost context inwards, which `Error::iter_chain` walks through one
+    /// cause at a time.
+    pub fn generate_output(&mut self) -> Result<Output, Error> {
+        let mut output = self._generate()?;
+        for (path, contents) in output.spliced.drain(..) {
+            output.files.push((path, contents.to_vec()));
+        }
+        Ok(output)
+    }
+
+    fn _generate(&mut self) -> Result<Output, Error> {
+        let mut phase = Instant::now();
+        // The sections of the input which are set aside in low memory mode.
+        let mut mapped = None;
+        let (mut module, stem, passed_through) = match self.input {
             Input::None => bail!("must have an input by now"),
             Input::Module(ref mut m, ref name) => {
                 let blank_module = Module::default();
-                (mem::replace(m, blank_module), &name[..])
+                (mem::replace(m, blank_module), &name[..], None)
+            }
+            Input::Bytes(ref bytes, ref input_name) => {
+                let (module, passed_through) = self.parse_input(bytes)?;
+                let stem = match &self.out_name {
+                    Some(name) => &name,
+                    None => &input_name[..],
+                };
+                (module, stem, passed_through)
             }
             Input::Path(ref path) => {
-                let contents = fs::read(&path)
-                    .with_context(|_| format!("failed to read `{}`", path.display()))?;
-                let module = walrus::ModuleConfig::new()
-                    // Skip validation of the module as LLVM's output is
-                    // generally already well-formed and so we won't gain much
-                    // from re-validating. Additionally LLVM's current output
-                    // for threads includes atomic instructions but doesn't
-                    // include shared memory, so it fails that part of
-                    // validation!
-                    .strict_validate(false)
-                    .generate_dwarf(self.keep_debug)
-                    .generate_name_section(!self.remove_name_section)
-                    .generate_producers_section(!self.remove_producers_section)
-                    .parse(&contents)
-                    .context("failed to parse input file as wasm")?;
+                let contents = if self.low_memory {
+                    stream::Input::map(path, self.threads.is_none()).map(|(input, rest)| {
+                        mapped = Some(input);
+                        rest
+                    })
+                } else {
+                    fs::read(path).map_err(Error::from)
+                };
+                let contents =
+                    contents.with_context(|_| format!("failed to read `{}`", path.display()))?;
+                let (module, passed_through) = self.parse_input(&contents)?;
                 let stem = match &self.out_name {
                     Some(name) => &name,
                     None => path.file_stem().unwrap().to_str().unwrap(),
                 };
-                (module, stem)
+                (module, stem, passed_through)
             }
         };
+        finish_phase(&mut phase, "parsing the input");
 
         // This isn't the hardest thing in the world too support but we
         // basically don't know how to rationalize #[wasm_bindgen(start)] and
@@ -194,6 +541,76 @@ impl Bindgen {
                    already have a start function");
         }
 
+        if self.browser_extension && !self.no_modules {
+            bail!("browser extension output requires `--no-modules` output");
+        }
+        if self.hashed_wasm && (!self.no_modules || self.threads.is_some()) {
+            bail!("hashed wasm files are only supported for `--no-modules` output without threads");
+        }
+        if self.multi_instance && (!self.no_modules || self.threads.is_some()) {
+            bail!("independent instances can only be created by `--no-modules` output without threads");
+        }
+        if self.sync_init_max_size.is_some() && !self.no_modules {
+            bail!("`initSync` is only generated for `--no-modules` output");
+        }
+        if self.wasm_url_base.is_some() && (!self.no_modules || self.browser_extension) {
+            bail!("the base of the wasm file's URL can only be configured for `--no-modules` output");
+        }
+        if self.deno && (self.nodejs || self.no_modules || self.browser) {
+            bail!("`--deno` can't be combined with `--nodejs`, `--browser` or `--no-modules`");
+        }
+        if self.deno && self.threads.is_some() {
+            bail!("threads aren't supported in Deno output yet");
+        }
+        if self.emit_ts && (self.no_modules || self.nodejs) {
+            bail!("TypeScript glue can only be emitted for ES module output");
+        }
+        if self.source_map && (self.minify_js || self.emit_ts) {
+            bail!("source maps can't be emitted for minified or TypeScript glue yet");
+        }
+        if self.emit_ts && self.minify_js {
+            bail!("TypeScript glue can't be minified, minify the compiled JS instead");
+        }
+        if self.emit_ts && (self.anyref || self.weak_refs) {
+            bail!("TypeScript glue isn't supported with anyref or weak references yet");
+        }
+        if let Some(compat) = &self.compat {
+            if compat != "es2017" {
+                bail!("unsupported `--compat` of `{}`, only `es2017` is supported", compat);
+            }
+            if self.deno {
+                bail!("Deno output uses top-level `await`, which `--compat es2017` doesn't allow");
+            }
+            if self.weak_refs {
+                bail!("weak refs aren't available with `--compat es2017`");
+            }
+        }
+        let api_renames = match &self.api_renames {
+            Some(list) => api::parse_renames(list)?,
+            None => Vec::new(),
+        };
+        let variants = match &self.variants {
+            Some(spec) => {
+                if self.deno || (self.nodejs && !self.nodejs_experimental_modules) {
+                    bail!(
+                        "a loader for variants can only be generated for ES module \
+                         or `--no-modules` output"
+                    );
+                }
+                loader::parse(spec)?
+            }
+            None => Vec::new(),
+        };
+
+        // Tags aren't among the module's exports while it's being processed,
+        // as our parser can't represent them, so the glue is told about the
+        // ones which will be exported separately.
+        let tags = passed_through.as_ref().map(|t| &t[..]).unwrap_or(&[]);
+        let exported_tags = tags
+            .iter()
+            .flat_map(|t| t.exports.iter().cloned())
+            .collect::<Vec<_>>();
+
         let mut program_storage = Vec::new();
         let programs = extract_programs(&mut module, &mut program_storage)
             .with_context(|_| "failed to extract wasm-bindgen custom sections")?;
@@ -202,9 +619,11 @@ impl Bindgen {
             cfg.run(&mut module)
                 .with_context(|_| "failed to prepare module for threading")?;
         }
+        finish_phase(&mut phase, "extracting custom sections");
 
         if self.demangle {
-            demangle(&mut module);
+            demangle(&mut module, !self.single_threaded);
+            finish_phase(&mut phase, "demangling");
         }
 
         // Here we're actually instantiating the module we've parsed above for
@@ -220,7 +639,14 @@ impl Bindgen {
         // This means that whenever we encounter an import or export we'll
         // execute a shim function which informs us about its type so we can
         // then generate the appropriate bindings.
-        let mut instance = wasm_bindgen_wasm_interpreter::Interpreter::new(&module)?;
+        let mut instance = Interpreter::new(&module)?;
+        let descriptors = if self.single_threaded {
+            HashMap::new()
+        } else {
+            let descriptors = describe_all(&module, &instance);
+            finish_phase(&mut phase, "describing exports");
+            descriptors
+        };
 
         let mut memories = module.memories.iter().map(|m| m.id());
         let memory = memories.next();
@@ -230,7 +656,7 @@ impl Bindgen {
         drop(memories);
         let memory = memory.unwrap_or_else(|| module.memories.add_local(false, 1, None));
 
-        let (js, ts) = {
+        let (js, ts, api, source_names, lazy_exports, snippets) = {
             let mut cx = js::Context {
                 globals: String::new(),
                 imports: String::new(),
@@ -242,19 +668,40 @@ impl Bindgen {
                 imported_names: Default::default(),
                 imported_identifiers: Default::default(),
                 exported_classes: Some(Default::default()),
+                exported_enums: Some(Default::default()),
+                declared_classes: Default::default(),
+                enumerable_classes: Default::default(),
+                frozen_classes: Default::default(),
+                plain_classes: Default::default(),
+                ts_brands: Default::default(),
                 config: &self,
                 module: &mut module,
                 function_table_needed: false,
                 interpreter: &mut instance,
                 memory,
+                exported_tags: exported_tags.clone(),
                 imported_functions: Default::default(),
                 imported_statics: Default::default(),
                 direct_imports: Default::default(),
                 start: None,
+                intrinsics: Vec::new(),
+                descriptors,
                 anyref: Default::default(),
+                api: Default::default(),
+                api_renames: api_renames.clone(),
+                source_names: Default::default(),
+                lazy_exports: Vec::new(),
+                snippets: programs
+                    .iter()
+                    .flat_map(|p| p.snippets.iter())
+                    .map(|s| (format!("./{}", s.path), Default::default()))
+                    .collect(),
             };
             cx.anyref.enabled = self.anyref;
             cx.anyref.prepare(cx.module)?;
+            for program in programs.iter() {
+                cx.declare_types(program);
+            }
             for program in programs.iter() {
                 js::SubContext {
                     program,
@@ -263,48 +710,297 @@ impl Bindgen {
                 }
                 .generate()?;
             }
-            cx.finalize(stem)?
+            let (js, ts) = cx.finalize(stem)?;
+            (
+                js,
+                ts,
+                cx.api,
+                cx.source_names,
+                cx.lazy_exports,
+                cx.snippets,
+            )
         };
+        finish_phase(&mut phase, "generating JS");
+
+        if let Some(path) = &self.api_baseline {
+            let previous = fs::read_to_string(path)
+                .with_context(|_| format!("failed to read `{}`", path.display()))?;
+            let previous = api::Api::from_json(&previous)
+                .with_context(|_| format!("failed to parse `{}`", path.display()))?;
+            let changes = api.breaking_changes(&previous, &api_renames);
+            if changes.len() > 0 {
+                bail!(
+                    "the exported JS API has breaking changes compared to `{}`:\n  {}",
+                    path.display(),
+                    changes.join("\n  ")
+                );
+            }
+        }
 
         let extension = if self.nodejs_experimental_modules {
             "mjs"
         } else {
             "js"
         };
-        let js_path = out_dir.join(stem).with_extension(extension);
-        fs::write(&js_path, reset_indentation(&js))
-            .with_context(|_| format!("failed to write `{}`", js_path.display()))?;
+        let mut output = Output {
+            files: Vec::new(),
+            spliced: Vec::new(),
+        };
+        let js_path = if self.emit_ts {
+            Path::new(stem).with_extension("ts")
+        } else {
+            Path::new(stem).with_extension(extension)
+        };
 
-        if self.typescript {
+        // TypeScript glue is its own declaration file.
+        if self.typescript && !self.emit_ts {
             let ts_path = js_path.with_extension("d.ts");
-            fs::write(&ts_path, ts)
-                .with_context(|_| format!("failed to write `{}`", ts_path.display()))?;
+            output.add(&ts_path, ts);
         }
 
-        let wasm_path = out_dir.join(format!("{}_bg", stem)).with_extension("wasm");
+        // Snippets are written out where they're imported from, each with the
+        // TypeScript declarations of what's imported from it. They're minified
+        // along with the glue, but otherwise left as they were written.
+        let mut written = BTreeSet::new();
+        for snippet in programs.iter().flat_map(|p| p.snippets.iter()) {
+            if !written.insert(snippet.path) {
+                continue;
+            }
+            let path = Path::new(snippet.path);
+            if self.minify_js {
+                output.add(path, minify::minify(snippet.contents));
+            } else {
+                output.add(path, snippet.contents);
+            }
+            if self.typescript || self.emit_ts {
+                let decls = &snippets[&format!("./{}", snippet.path)];
+                output.add(&path.with_extension("d.ts"), decls.to_ts());
+            }
+        }
+
+        if self.api_json {
+            let json_path = PathBuf::from(format!("{}.api.json", stem));
+            output.add(&json_path, api.to_json());
+        }
+
+        let wasm_path = PathBuf::from(format!("{}_bg", stem)).with_extension("wasm");
+
+        // Lazy exports are moved out of the module before anything else is
+        // derived from its exports.
+        if lazy_exports.len() > 0 {
+            if tags.len() > 0 {
+                bail!("lazy exports can't be split out of modules using exception handling yet");
+            }
+            let mut lazy = lazy::split(&mut module, &lazy_exports, &format!("./{}_bg", stem))
+                .with_context(|_| "failed to split lazy exports into their own module")?;
+            let lazy_path = PathBuf::from(format!("{}_lazy_bg", stem));
+            if self.typescript || self.emit_ts {
+                let ts = wasm2es6js::typescript(&lazy)?;
+                output.add(&lazy_path.with_extension("d.ts"), ts);
+            }
+            let mut lazy_bytes = lazy.emit_wasm()?;
+            if passed_through.is_some() {
+                lazy_bytes = passthrough::raise(lazy_bytes, &[])?;
+            }
+            output.add(&lazy_path.with_extension("wasm"), lazy_bytes);
+            finish_phase(&mut phase, "splitting lazy exports");
+        }
 
         if self.nodejs {
             let js_path = wasm_path.with_extension(extension);
-            let shim = self.generate_node_wasm_import(&module, &wasm_path);
-            fs::write(&js_path, shim)
-                .with_context(|_| format!("failed to write `{}`", js_path.display()))?;
+            let shim = self.generate_node_wasm_import(&module, &exported_tags, &wasm_path);
+            output.add(&js_path, shim);
         }
 
-        if self.typescript {
+        // The glue imports the wasm module, so TypeScript glue needs to know
+        // what its exports are as well.
+        if self.typescript || self.emit_ts {
             let ts_path = wasm_path.with_extension("d.ts");
-            let ts = wasm2es6js::typescript(&module)?;
-            fs::write(&ts_path, ts)
-                .with_context(|_| format!("failed to write `{}`", ts_path.display()))?;
+            let mut ts = wasm2es6js::typescript(&module)?;
+            for name in exported_tags.iter() {
+                ts.push_str(&format!("export const {}: WebAssembly.Tag;\n", name));
+            }
+            output.add(&ts_path, ts);
         }
 
-        let wasm_bytes = module.emit_wasm()?;
-        fs::write(&wasm_path, wasm_bytes)
-            .with_context(|_| format!("failed to write `{}`", wasm_path.display()))?;
+        if variants.len() > 0 {
+            let baseline = format!("./{}.{}", stem, extension);
+            let global = match &self.no_modules_global {
+                Some(global) => global,
+                None => "wasm_bindgen",
+            };
+            let global = if self.no_modules { Some(global) } else { None };
+            let (js, ts) = loader::generate(&variants, &baseline, global);
+            let js_path = PathBuf::from(format!("{}_loader", stem)).with_extension(extension);
+            output.add(&js_path, self.format_js(&js));
+            if self.typescript {
+                let ts_path = js_path.with_extension("d.ts");
+                output.add(&ts_path, ts);
+            }
+        }
 
-        Ok(())
+        if let Some(name) = &self.split_debuginfo {
+            let debug = split_debuginfo(&mut module, name).emit_wasm()?;
+            let debug_path = PathBuf::from(name);
+            match &mapped {
+                Some(input) => output.add_spliced(&debug_path, input.debuginfo(debug)),
+                None => output.add(&debug_path, debug),
+            }
+        }
+
+        let mut wasm_bytes = module.emit_wasm()?;
+        drop(module);
+        if let Some(tags) = &passed_through {
+            wasm_bytes = passthrough::raise(wasm_bytes, tags)?;
+        }
+        let (wasm_bytes, wasm) = match &mapped {
+            Some(input) => {
+                let keep_debug = self.keep_debug && self.split_debuginfo.is_none();
+                (Vec::new(), Some(input.splice(wasm_bytes, keep_debug)?))
+            }
+            None => (wasm_bytes, None),
+        };
+        finish_phase(&mut phase, "emitting the wasm module");
+
+        // Now that the wasm is final the JS can be told what it's called and
+        // what its hash is.
+        let digest = match &wasm {
+            Some(wasm) => {
+                let mut h = Sha384::new();
+                for chunk in wasm.chunks() {
+                    h.input(chunk);
+                }
+                h.result()
+            }
+            None => Sha384::digest(&wasm_bytes),
+        };
+        let hash = digest[..8]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        let js = js.replace(js::WASM_HASH, &hash);
+        let (js, wasm_path) = if self.hashed_wasm {
+            let name = format!("{}.{}_bg.wasm", stem, hash);
+            let integrity = format!("sha384-{}", base64::encode(&digest));
+            let js = js
+                .replace(js::HASHED_WASM_FILE, &name)
+                .replace(js::HASHED_WASM_INTEGRITY, &integrity);
+            (js, PathBuf::from(name))
+        } else {
+            (js, wasm_path)
+        };
+        let mut js = self.format_js(&js);
+        if self.source_map {
+            let file = js_path.file_name().unwrap().to_string_lossy().into_owned();
+            let map = source_map::generate(&js, &file, stem, &source_names);
+            let map_path = PathBuf::from(format!("{}.map", file));
+            output.add(&map_path, map);
+            js.push_str(&format!("//# sourceMappingURL={}.map\n", file));
+        }
+        output.add(&js_path, js);
+        match wasm {
+            Some(wasm) => output.add_spliced(&wasm_path, wasm),
+            None => output.add(&wasm_path, wasm_bytes),
+        }
+
+        Ok(output)
+    }
+
+    /// Parses the input module, returning the tags taken out of it if any
+    /// instructions had to be replaced to be passed through to the output.
+    fn parse_input(
+        &self,
+        contents: &[u8],
+    ) -> Result<(Module, Option<Vec<passthrough::Tag>>), Error> {
+        let features = proposals::check(contents, &self.wasm_features)?;
+        let lowered = passthrough::lower(contents, features)?;
+        let module = walrus::ModuleConfig::new()
+            // Skip validation of the module as LLVM's output is generally
+            // already well-formed and so we won't gain much from
+            // re-validating. Additionally LLVM's current output for threads
+            // includes atomic instructions but doesn't include shared memory,
+            // so it fails that part of validation!
+            .strict_validate(false)
+            .generate_dwarf(self.keep_debug || self.split_debuginfo.is_some())
+            .generate_name_section(!self.remove_name_section)
+            .generate_producers_section(!self.remove_producers_section)
+            .parse(lowered.as_ref().map(|l| &l.wasm[..]).unwrap_or(contents))
+            .context("failed to parse input file as wasm")?;
+        Ok((module, lowered.map(|l| l.tags)))
     }
 
-    fn generate_node_wasm_import(&self, m: &Module, path: &Path) -> String {
+    /// Returns the name of the cache entry for generating bindings for the
+    /// input into `out_dir` with the current configuration, if the input can
+    /// be cached.
+    ///
+    /// Input which uses unsupported proposals is rejected before anything is
+    /// looked up, just like it would be on a miss.
+    fn cache_key(&self, out_dir: &Path) -> Result<Option<String>, Error> {
+        let map;
+        let contents;
+        let (wasm, name): (&[u8], &Path) = match &self.input {
+            Input::Path(path) => {
+                let wasm: &[u8] = if self.low_memory {
+                    map = stream::map(path)
+                        .with_context(|_| format!("failed to read `{}`", path.display()))?;
+                    &map[..]
+                } else {
+                    contents = fs::read(path)
+                        .with_context(|_| format!("failed to read `{}`", path.display()))?;
+                    &contents[..]
+                };
+                (wasm, path.as_path())
+            }
+            Input::Bytes(bytes, name) => (&bytes[..], Path::new(name)),
+            // Parsed modules would have to be emitted again to be hashed, which
+            // is a good part of the work of generating bindings anyway.
+            Input::Module(..) | Input::None => return Ok(None),
+        };
+        proposals::check(wasm, &self.wasm_features)?;
+
+        let mut h = cache::Key::new();
+        wasm_bindgen_shared::version().hash(&mut h);
+        wasm.hash(&mut h);
+        out_dir.hash(&mut h);
+        name.hash(&mut h);
+        self.out_name.hash(&mut h);
+        (self.nodejs, self.nodejs_experimental_modules, self.browser).hash(&mut h);
+        self.deno.hash(&mut h);
+        (self.no_modules, &self.no_modules_global, self.browser_extension).hash(&mut h);
+        self.multi_instance.hash(&mut h);
+        (self.debug, self.typescript, self.demangle, self.keep_debug).hash(&mut h);
+        (self.remove_name_section, self.remove_producers_section).hash(&mut h);
+        (self.emit_start, self.middleware, self.required_imports).hash(&mut h);
+        self.profile_boundary.hash(&mut h);
+        (self.sync_init_max_size, &self.wasm_features, &self.variants).hash(&mut h);
+        (self.single_threaded, self.low_memory).hash(&mut h);
+        (&self.split_debuginfo, self.hashed_wasm).hash(&mut h);
+        (&self.wasm_url_base, self.minify_js, self.emit_ts).hash(&mut h);
+        (self.source_map, &self.compat).hash(&mut h);
+        (self.api_json, &self.api_renames).hash(&mut h);
+        // A cached build was checked against the baseline it was built with,
+        // which may have been updated since.
+        let baseline = self.api_baseline.as_ref().map(|p| fs::read(p).ok());
+        (&self.api_baseline, baseline).hash(&mut h);
+        // Unstable features are configured through environment variables, so
+        // all of them are part of the key as well.
+        let mut vars = env::vars()
+            .filter(|(k, _)| k.starts_with("WASM_BINDGEN_"))
+            .collect::<Vec<_>>();
+        vars.sort();
+        vars.hash(&mut h);
+        Ok(Some(h.name()))
+    }
+
+    fn format_js(&self, js: &str) -> String {
+        if self.minify_js {
+            minify::minify(js)
+        } else {
+            reset_indentation(js)
+        }
+    }
+
+    fn generate_node_wasm_import(&self, m: &Module, tags: &[String], path: &Path) -> String {
         let mut imports = BTreeSet::new();
         for import in m.imports.iter() {
             imports.insert(&import.module);
@@ -359,11 +1055,12 @@ impl Bindgen {
         ));
 
         if self.nodejs_experimental_modules {
-            for entry in m.exports.iter() {
+            let names = m.exports.iter().map(|e| &e.name).chain(tags);
+            for name in names {
                 shim.push_str("export const ");
-                shim.push_str(&entry.name);
+                shim.push_str(name);
                 shim.push_str(" = wasmInstance.exports.");
-                shim.push_str(&entry.name);
+                shim.push_str(name);
                 shim.push_str(";\n");
             }
         } else {
@@ -498,6 +1195,81 @@ fn verify_schema_matches<'a>(data: &'a [u8]) -> Result<Option<&'a str>, Error> {
     Ok(Some(their_version))
 }
 
+/// Moves all DWARF sections in `module` into a new module, replacing them with
+/// an `external_debug_info` section pointing at `url`.
+///
+/// The sections are moved as they are, so like with `keep_debug` the code
+/// offsets in them are those of the input and may be stale for code which
+/// was changed or moved while generating bindings.
+fn split_debuginfo(module: &mut Module, url: &str) -> Module {
+    let mut debug = Module::default();
+    let (dwarf, rest): (Vec<_>, Vec<_>) = mem::replace(&mut module.custom, Vec::new())
+        .into_iter()
+        .partition(|s| s.name.starts_with(".debug_"));
+    debug.custom = dwarf;
+    module.custom = rest;
+
+    // The section's contents are the URL as a length-prefixed string.
+    let mut value = Vec::new();
+    passthrough::uleb(&mut value, url.len() as u64);
+    value.extend_from_slice(url.as_bytes());
+    module.custom.push(walrus::CustomSection {
+        name: "external_debug_info".to_string(),
+        value,
+    });
+    debug
+}
+
+impl Output {
+    /// Returns the name and contents of each generated file. Names are
+    /// relative to the output directory.
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &[u8])> {
+        self.files
+            .iter()
+            .map(|(path, contents)| (&**path, &contents[..]))
+    }
+
+    /// Returns the contents of the generated file called `name`, if any.
+    pub fn file<P: AsRef<Path>>(&self, name: P) -> Option<&[u8]> {
+        self.files
+            .iter()
+            .find(|(path, _)| path == name.as_ref())
+            .map(|(_, contents)| &contents[..])
+    }
+
+    /// Writes all generated files into `out_dir`, returning their paths.
+    pub fn emit<P: AsRef<Path>>(&self, out_dir: P) -> Result<Vec<PathBuf>, Error> {
+        let mut written = Vec::new();
+        for (name, contents) in self.files.iter() {
+            let path = out_dir.as_ref().join(name);
+            // Snippets are written to directories of their own.
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|_| format!("failed to create `{}`", parent.display()))?;
+            }
+            fs::write(&path, contents)
+                .with_context(|_| format!("failed to write `{}`", path.display()))?;
+            written.push(path);
+        }
+        for (name, contents) in self.spliced.iter() {
+            let path = out_dir.as_ref().join(name);
+            fs::File::create(&path)
+                .and_then(|mut file| contents.write_to(&mut file))
+                .with_context(|_| format!("failed to write `{}`", path.display()))?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    fn add(&mut self, path: &Path, contents: impl Into<Vec<u8>>) {
+        self.files.push((path.to_path_buf(), contents.into()));
+    }
+
+    fn add_spliced(&mut self, path: &Path, contents: stream::Spliced) {
+        self.spliced.push((path.to_path_buf(), contents));
+    }
+}
+
 fn reset_indentation(s: &str) -> String {
     let mut indent: u32 = 0;
     let mut dst = String::new();
@@ -543,14 +1315,333 @@ fn threads_config() -> Option<wasm_bindgen_threads_xform::Config> {
     Some(cfg)
 }
 
-fn demangle(module: &mut Module) {
-    for func in module.funcs.iter_mut() {
-        let name = match &func.name {
-            Some(name) => name,
-            None => continue,
-        };
-        if let Ok(sym) = rustc_demangle::try_demangle(name) {
-            func.name = Some(sym.to_string());
+fn demangle(module: &mut Module, parallel: bool) {
+    if !parallel {
+        for func in module.funcs.iter_mut() {
+            let name = match &func.name {
+                Some(name) => name,
+                None => continue,
+            };
+            if let Ok(sym) = rustc_demangle::try_demangle(name) {
+                func.name = Some(sym.to_string());
+            }
         }
+        return;
+    }
+
+    // Large debug builds have hundreds of thousands of functions, so demangle
+    // their names in parallel.
+    let names = module
+        .funcs
+        .iter()
+        .map(|f| f.name.clone())
+        .collect::<Vec<_>>();
+    let names = names
+        .into_par_iter()
+        .map(|name| {
+            let name = name?;
+            let sym = rustc_demangle::try_demangle(&name).ok()?;
+            Some(sym.to_string())
+        })
+        .collect::<Vec<_>>();
+    for (func, name) in module.funcs.iter_mut().zip(names) {
+        if let Some(name) = name {
+            func.name = Some(name);
+        }
+    }
+}
+
+/// Runs all descriptor functions in `module` ahead of time.
+///
+/// Interpreting descriptors is one of the slowest parts of processing large
+/// modules, but each descriptor is independent of all the others so they can
+/// be interpreted in parallel. The interpreter is only cloned once for each
+/// piece of work rayon splits the descriptors into, of which there are about
+/// as many as there are threads, rather than once per descriptor.
+fn describe_all(module: &Module, interpreter: &Interpreter) -> HashMap<String, Descriptor> {
+    let names = module
+        .exports
+        .iter()
+        .filter(|e| e.name.starts_with("__wbindgen_describe_"))
+        .map(|e| e.name.clone())
+        .collect::<Vec<_>>();
+    names
+        .par_iter()
+        .map_init(
+            || interpreter.clone(),
+            |interpreter, name| {
+                let descriptor = interpreter.interpret_descriptor(name, module)?;
+                Some((name.clone(), Descriptor::decode(descriptor)))
+            },
+        )
+        .flatten()
+        .collect()
+}
+
+/// Logs how long the phase of generating bindings which started at `start`
+/// took, and starts timing the next one from now.
+///
+/// These are logged at the `info` level, so running the CLI with
+/// `RUST_LOG=wasm_bindgen_cli_support=info` shows where the time goes.
+fn finish_phase(start: &mut Instant, phase: &str) {
+    log::info!("{} took {:?}", phase, start.elapsed());
+    *start = Instant::now();
+}
+
+#[test]
+fn passes_tail_calls_through() {
+    // (type (func (result i32)))
+    // (func (export "f") (type 0) return_call 1)
+    // (func (type 0) i32.const 1)
+    let wasm = b"\0asm\x01\0\0\0\
+        \x01\x05\x01\x60\x00\x01\x7f\
+        \x03\x03\x02\x00\x00\
+        \x07\x05\x01\x01f\x00\x00\
+        \x0a\x0b\x02\x04\x00\x12\x01\x0b\x04\x00\x41\x01\x0b";
+    let output = Bindgen::new()
+        .input_bytes("tail", wasm.to_vec())
+        .wasm_features("+tail-call")
+        .generate_output()
+        .unwrap();
+    let wasm = output.file("tail_bg.wasm").unwrap();
+    // The body of `f`, with no locals, `return_call` and `end`.
+    assert!(wasm
+        .windows(4)
+        .any(|w| w[0] == 0x04 && w[1] == 0x00 && w[2] == 0x12 && w[3] < 0x80));
+
+    // Proposals which can't be passed through are rejected for input which
+    // isn't read from a file as well.
+    let result = Bindgen::new()
+        .input_bytes("tail", b"\0asm\x01\0\0\0".to_vec())
+        .wasm_features("+gc")
+        .generate_output();
+    assert!(result.is_err());
+}
+
+#[test]
+fn generate_writes_the_files_of_generate_output() {
+    // (func (export "f"))
+    let wasm = b"\0asm\x01\0\0\0\
+        \x01\x04\x01\x60\x00\x00\
+        \x03\x02\x01\x00\
+        \x07\x05\x01\x01f\x00\x00\
+        \x0a\x04\x01\x02\x00\x0b";
+    let output = Bindgen::new()
+        .input_bytes("files", wasm.to_vec())
+        .generate_output()
+        .unwrap();
+    let out_dir = tempfile::tempdir().unwrap();
+    Bindgen::new()
+        .input_bytes("files", wasm.to_vec())
+        .generate(out_dir.path())
+        .unwrap();
+
+    let mut written = fs::read_dir(out_dir.path())
+        .unwrap()
+        .map(|entry| PathBuf::from(entry.unwrap().file_name()))
+        .collect::<Vec<_>>();
+    written.sort();
+    let mut generated = output
+        .files()
+        .map(|(name, _)| name.to_path_buf())
+        .collect::<Vec<_>>();
+    generated.sort();
+    assert_eq!(written, generated);
+    assert!(generated.contains(&PathBuf::from("files_bg.wasm")));
+
+    for (name, contents) in output.files() {
+        let path = out_dir.path().join(name);
+        assert_eq!(fs::read(&path).unwrap(), contents, "{}", path.display());
+        assert_eq!(output.file(name), Some(contents));
+    }
+}
+
+#[test]
+fn creates_instances_only_for_no_modules() {
+    // (func (export "f"))
+    let wasm = b"\0asm\x01\0\0\0\
+        \x01\x04\x01\x60\x00\x00\
+        \x03\x02\x01\x00\
+        \x07\x05\x01\x01f\x00\x00\
+        \x0a\x04\x01\x02\x00\x0b";
+    for &multi_instance in [false, true].iter() {
+        let output = Bindgen::new()
+            .input_bytes("multi", wasm.to_vec())
+            .no_modules(true)
+            .typescript(true)
+            .multi_instance(multi_instance)
+            .generate_output()
+            .unwrap();
+        let js = str::from_utf8(output.file("multi.js").unwrap()).unwrap();
+        let ts = str::from_utf8(output.file("multi.d.ts").unwrap()).unwrap();
+        assert_eq!(js.contains("init.initInstance = "), multi_instance);
+        assert_eq!(ts.contains("function initInstance("), multi_instance);
+    }
+
+    for nodejs in [false, true].iter() {
+        let err = Bindgen::new()
+            .input_bytes("multi", wasm.to_vec())
+            .nodejs(*nodejs)
+            .multi_instance(true)
+            .generate_output()
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("`--no-modules`"), "{}", err);
+    }
+}
+
+#[test]
+fn guards_init_sync_only_for_no_modules() {
+    // (func (export "f"))
+    let wasm = b"\0asm\x01\0\0\0\
+        \x01\x04\x01\x60\x00\x00\
+        \x03\x02\x01\x00\
+        \x07\x05\x01\x01f\x00\x00\
+        \x0a\x04\x01\x02\x00\x0b";
+    let output = Bindgen::new()
+        .input_bytes("sync", wasm.to_vec())
+        .no_modules(true)
+        .sync_init_max_size(4096)
+        .generate_output()
+        .unwrap();
+    let js = str::from_utf8(output.file("sync.js").unwrap()).unwrap();
+    assert!(js.contains("init.initSync = initSync;"));
+    assert!(js.contains("module_or_bytes.byteLength > 4096"));
+
+    for &(deno, nodejs) in [(false, false), (true, false), (false, true)].iter() {
+        let err = Bindgen::new()
+            .input_bytes("sync", wasm.to_vec())
+            .deno(deno)
+            .nodejs(nodejs)
+            .sync_init_max_size(4096)
+            .generate_output()
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("`--no-modules`"), "{}", err);
+    }
+}
+
+#[test]
+fn loads_wasm_from_the_extension_package() {
+    // (func (export "f"))
+    let wasm = b"\0asm\x01\0\0\0\
+        \x01\x04\x01\x60\x00\x00\
+        \x03\x02\x01\x00\
+        \x07\x05\x01\x01f\x00\x00\
+        \x0a\x04\x01\x02\x00\x0b";
+    let output = Bindgen::new()
+        .input_bytes("ext", wasm.to_vec())
+        .no_modules(true)
+        .browser_extension(true)
+        .generate_output()
+        .unwrap();
+    let js = str::from_utf8(output.file("ext.js").unwrap()).unwrap();
+    assert!(js.contains(
+        "(typeof browser !== 'undefined' ? browser.runtime : chrome.runtime)\
+         .getURL('ext_bg.wasm')"
+    ));
+    // Content scripts run in the page, so their URL isn't the extension's.
+    assert!(!js.contains("scriptUrl"));
+
+    let err = Bindgen::new()
+        .input_bytes("ext", wasm.to_vec())
+        .browser_extension(true)
+        .generate_output()
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("`--no-modules`"), "{}", err);
+}
+
+#[test]
+fn restores_cached_files_until_the_configuration_changes() {
+    // (func (export "f"))
+    let wasm = b"\0asm\x01\0\0\0\
+        \x01\x04\x01\x60\x00\x00\
+        \x03\x02\x01\x00\
+        \x07\x05\x01\x01f\x00\x00\
+        \x0a\x04\x01\x02\x00\x0b";
+    let cache_dir = tempfile::tempdir().unwrap();
+    let out_dir = tempfile::tempdir().unwrap();
+    let generate = |debug| {
+        Bindgen::new()
+            .input_bytes("cached", wasm.to_vec())
+            .cache_dir(cache_dir.path())
+            .typescript(true)
+            .debug(debug)
+            .generate(out_dir.path())
+            .unwrap();
+    };
+    let read_output = || {
+        let mut files = fs::read_dir(out_dir.path())
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                let contents = fs::read(&path).unwrap();
+                (path, contents)
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    };
+    let entries = || fs::read_dir(cache_dir.path()).unwrap().count();
+
+    generate(false);
+    let generated = read_output();
+    assert!(generated.len() > 1);
+    assert_eq!(entries(), 1);
+
+    // Every file is restored from the cache after the output is deleted.
+    for (path, _) in generated.iter() {
+        fs::remove_file(path).unwrap();
     }
+    generate(false);
+    assert_eq!(read_output(), generated);
+
+    // Files really are copied from the entry rather than generated again...
+    let entry = fs::read_dir(cache_dir.path())
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    fs::write(entry.join("cached.js"), "// cached").unwrap();
+    generate(false);
+    let js = out_dir.path().join("cached.js");
+    assert_eq!(fs::read(&js).unwrap(), b"// cached");
+
+    // ...until the configuration changes, which misses the cache.
+    generate(true);
+    assert_ne!(fs::read(&js).unwrap(), b"// cached");
+    assert_eq!(entries(), 2);
+}
+
+#[test]
+fn splits_debuginfo_into_its_own_file() {
+    // (func (export "f")) with a `.debug_info` section of `1 2 3`
+    let wasm = b"\0asm\x01\0\0\0\
+        \x01\x04\x01\x60\x00\x00\
+        \x03\x02\x01\x00\
+        \x07\x05\x01\x01f\x00\x00\
+        \x0a\x04\x01\x02\x00\x0b\
+        \x00\x0f\x0b.debug_info\x01\x02\x03";
+    let output = Bindgen::new()
+        .input_bytes("split", wasm.to_vec())
+        .split_debuginfo("split.debug.wasm")
+        .generate_output()
+        .unwrap();
+    let contains =
+        |haystack: &[u8], needle: &[u8]| haystack.windows(needle.len()).any(|w| w == needle);
+
+    // The main module refers to the debug info by its URL, as a
+    // length-prefixed string, instead of containing it.
+    let main = output.file("split_bg.wasm").unwrap();
+    assert!(contains(
+        main,
+        b"\x13external_debug_info\x10split.debug.wasm"
+    ));
+    assert!(!contains(main, b".debug_info"));
+
+    let debug = output.file("split.debug.wasm").unwrap();
+    assert!(contains(debug, b"\x0b.debug_info\x01\x02\x03"));
+    assert!(!contains(debug, b"external_debug_info"));
 }
@@ -0,0 +1,311 @@
+//! Generation of a loader choosing between builds of a module at runtime.
+//!
+//! Builds making use of newer wasm features like SIMD or threads won't work
+//! in engines that don't support them yet, so applications wanting to use
+//! them typically ship a baseline build as well. The loader generated here
+//! probes for each feature with `WebAssembly.validate` on a tiny module using
+//! it, and then imports the first build whose features are all supported,
+//! falling back to the output of this run of `wasm-bindgen`.
+//!
+//! Each build is imported with a literal specifier so bundlers can still see
+//! and process all of them.
+
+use failure::{bail, Error};
+
+/// Small modules which only validate if a feature is supported, along with
+/// the LLVM names of the features.
+const PROBES: &[(&str, &[u8])] = &[
+    // (func (result i32) i32.const 0 i32.extend8_s)
+    (
+        "sign-ext",
+        &[
+            0, 97, 115, 109, 1, 0, 0, 0, 1, 5, 1, 96, 0, 1, 127, 3, 2, 1, 0, 10, 7, 1, 5, 0, 65, 0,
+            192, 11,
+        ],
+    ),
+    // (func (result i32) f32.const 0 i32.trunc_sat_f32_s)
+    (
+        "nontrapping-fptoint",
+        &[
+            0, 97, 115, 109, 1, 0, 0, 0, 1, 5, 1, 96, 0, 1, 127, 3, 2, 1, 0, 10, 11, 1, 9, 0, 67, 0,
+            0, 0, 0, 252, 0, 11,
+        ],
+    ),
+    // (memory 1) (func i32.const 0 i32.const 0 i32.const 0 memory.fill)
+    (
+        "bulk-memory",
+        &[
+            0, 97, 115, 109, 1, 0, 0, 0, 1, 4, 1, 96, 0, 0, 3, 2, 1, 0, 5, 3, 1, 0, 1, 10, 13, 1,
+            11, 0, 65, 0, 65, 0, 65, 0, 252, 11, 0, 11,
+        ],
+    ),
+    // (func (result v128) i32.const 0 i8x16.splat)
+    (
+        "simd",
+        &[
+            0, 97, 115, 109, 1, 0, 0, 0, 1, 5, 1, 96, 0, 1, 123, 3, 2, 1, 0, 10, 8, 1, 6, 0, 65, 0,
+            253, 15, 11,
+        ],
+    ),
+    // (memory 1 1 shared)
+    (
+        "threads",
+        &[0, 97, 115, 109, 1, 0, 0, 0, 5, 4, 1, 3, 1, 1],
+    ),
+];
+
+/// An alternative build of the module, used if all of `features` are
+/// supported.
+#[derive(Debug, PartialEq)]
+pub struct Variant {
+    pub features: Vec<String>,
+    pub path: String,
+}
+
+/// Parses a comma-separated list of variants like
+/// `simd+threads=./mt/app.js,simd=./simd/app.js`.
+///
+/// Variants are listed in order of preference.
+pub fn parse(spec: &str) -> Result<Vec<Variant>, Error> {
+    let mut ret = Vec::new();
+    for variant in spec.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let mut parts = variant.splitn(2, '=');
+        let features = parts.next().unwrap();
+        let path = match parts.next() {
+            Some(path) if !path.is_empty() => path,
+            _ => bail!(
+                "invalid variant `{}`, expected a list of features and a path \
+                 like `simd+threads=./mt/app.js`",
+                variant
+            ),
+        };
+        let features = features
+            .split('+')
+            .map(|s| s.trim().to_string())
+            .collect::<Vec<_>>();
+        for feature in features.iter() {
+            if !PROBES.iter().any(|(name, _)| name == feature) {
+                let known = PROBES.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+                bail!(
+                    "unknown wasm feature `{}` in variant `{}`, supported features \
+                     are: {}",
+                    feature,
+                    variant,
+                    known.join(", ")
+                );
+            }
+        }
+        ret.push(Variant {
+            features,
+            path: path.to_string(),
+        });
+    }
+    Ok(ret)
+}
+
+/// Generates the JS and TypeScript for a loader choosing between `variants`,
+/// falling back to `baseline`.
+///
+/// With `--no-modules` output, `no_modules_global` is the name of the global
+/// each build defines. The loader then loads builds as scripts instead of
+/// importing them, and is itself the global `{no_modules_global}_loader`.
+pub fn generate(
+    variants: &[Variant],
+    baseline: &str,
+    no_modules_global: Option<&str>,
+) -> (String, String) {
+    let mut js = String::from(
+        "\
+// Loads the build of this module best suited to the current engine. Generated
+// by wasm-bindgen, do not edit.
+
+",
+    );
+    if no_modules_global.is_some() {
+        js.push_str("(function() {\n");
+    }
+
+    js.push_str("const probes = {\n");
+    for (name, bytes) in PROBES {
+        if !variants.iter().any(|v| v.features.iter().any(|f| f == name)) {
+            continue;
+        }
+        let bytes = bytes.iter().map(|b| b.to_string()).collect::<Vec<_>>();
+        js.push_str(&format!("'{}': [{}],\n", name, bytes.join(", ")));
+    }
+    js.push_str("};\n\n");
+
+    js.push_str(
+        "\
+const supported = {};
+
+function supports(feature) {
+if (!(feature in supported)) {
+let ok = typeof WebAssembly === 'object' && WebAssembly.validate(new Uint8Array(probes[feature]));
+// shared memory is only usable if `SharedArrayBuffer` is available
+if (ok && feature === 'threads') ok = typeof SharedArrayBuffer === 'function';
+supported[feature] = ok;
+}
+return supported[feature];
+}
+
+",
+    );
+
+    // Paths are written as JSON strings, which are valid JS strings as well.
+    let choose = |js: &mut String, load: &dyn Fn(&str) -> String| {
+        for variant in variants {
+            let cond = variant
+                .features
+                .iter()
+                .map(|f| format!("supports('{}')", f))
+                .collect::<Vec<_>>()
+                .join(" && ");
+            let path = serde_json::to_string(&variant.path).unwrap();
+            js.push_str(&format!("if ({}) return {};\n", cond, load(&path)));
+        }
+        let path = serde_json::to_string(baseline).unwrap();
+        js.push_str(&format!("return {};\n", load(&path)));
+    };
+
+    let global = match no_modules_global {
+        Some(global) => global,
+        None => {
+            js.push_str(
+                "\
+/**
+* Returns the path of the build which `load` would import.
+* @returns {string}
+*/
+export function variant() {
+",
+            );
+            choose(&mut js, &|path| path.to_string());
+            js.push_str("}\n\n");
+
+            js.push_str(
+                "\
+/**
+* Imports the build best suited to the current engine, resolving to its
+* module namespace object.
+* @returns {Promise<any>}
+*/
+export default function load() {
+",
+            );
+            choose(&mut js, &|path| format!("import({})", path));
+            js.push_str("}\n");
+
+            let ts = String::from(
+                "\
+/* tslint:disable */
+export function variant(): string;
+export default function load(): Promise<any>;
+",
+            );
+            return (js, ts);
+        }
+    };
+
+    // Scripts are loaded relative to the loader, rather than to the
+    // document like a plain `<script>` would be.
+    js.push_str(
+        "\
+const base = typeof document === 'undefined' ? self.location.href : document.currentScript.src;
+
+function variant() {
+",
+    );
+    choose(&mut js, &|path| path.to_string());
+    js.push_str("}\n\n");
+    js.push_str(&format!(
+        "\
+function load() {{
+const url = new URL(variant(), base).href;
+return new Promise((resolve, reject) => {{
+if (typeof document === 'undefined') {{
+importScripts(url);
+return resolve(self.{global});
+}}
+const script = document.createElement('script');
+script.src = url;
+script.onload = () => resolve(self.{global});
+script.onerror = () => reject(new Error(`failed to load ${{url}}`));
+document.head.appendChild(script);
+}});
+}}
+
+self.{global}_loader = {{ variant, load }};
+}})();
+",
+        global = global,
+    ));
+
+    let ts = format!(
+        "\
+/* tslint:disable */
+declare namespace {global}_loader {{
+/** Returns the path of the build which `load` would load. */
+export function variant(): string;
+/** Loads the build best suited to the current engine, resolving to its `{global}`. */
+export function load(): Promise<typeof {global}>;
+}}
+",
+        global = global,
+    );
+    (js, ts)
+}
+
+#[test]
+fn parses_variants() {
+    let variants = parse("simd+threads=./mt/app.js, simd=./simd/app.js").unwrap();
+    assert_eq!(
+        variants,
+        [
+            Variant {
+                features: vec!["simd".to_string(), "threads".to_string()],
+                path: "./mt/app.js".to_string(),
+            },
+            Variant {
+                features: vec!["simd".to_string()],
+                path: "./simd/app.js".to_string(),
+            },
+        ]
+    );
+    assert!(parse("simd").is_err());
+    assert!(parse("gc=./gc/app.js").is_err());
+}
+
+#[test]
+fn generates_loader() {
+    let variants = parse("simd+threads=./mt/app.js").unwrap();
+    let (js, _) = generate(&variants, "./app.js", None);
+    assert!(js.contains("'simd': ["));
+    assert!(!js.contains("'bulk-memory'"));
+    assert!(js.contains(
+        "if (supports('simd') && supports('threads')) return import(\"./mt/app.js\");"
+    ));
+    assert!(js.contains("return import(\"./app.js\");"));
+    assert!(js.contains("export default function load()"));
+}
+
+#[test]
+fn escapes_paths() {
+    let variants = parse("simd=./it's \"simd\"/app.js").unwrap();
+    let (js, _) = generate(&variants, "./app.js", None);
+    assert!(js.contains("return \"./it's \\\"simd\\\"/app.js\";"));
+    assert!(js.contains("return import(\"./it's \\\"simd\\\"/app.js\");"));
+}
+
+#[test]
+fn generates_script_loader_for_no_modules() {
+    let variants = parse("simd=./simd/app.js").unwrap();
+    let (js, ts) = generate(&variants, "./app.js", Some("app"));
+    assert!(!js.contains("export "));
+    assert!(!js.contains("import("));
+    assert!(js.contains("if (supports('simd')) return \"./simd/app.js\";"));
+    assert!(js.contains("resolve(self.app)"));
+    assert!(js.contains("self.app_loader = { variant, load };"));
+    assert!(ts.contains("declare namespace app_loader {"));
+    assert!(ts.contains("export function load(): Promise<typeof app>;"));
+}
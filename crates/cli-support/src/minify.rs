@@ -0,0 +1,299 @@
+//! A conservative minifier for the JS we generate.
+//!
+//! This only removes comments and whitespace, it never renames anything, so
+//! exported names and the names imports are looked up by are all preserved.
+//! Newlines are kept wherever automatic semicolon insertion could depend on
+//! them, which is rare as the generated JS mostly has explicit semicolons, so
+//! the output is typically a handful of very long lines.
+//!
+//! This isn't a full JS parser, just enough of a tokenizer to tell strings,
+//! template literals, regular expressions and comments apart from everything
+//! else, which is all that's needed to know where whitespace matters.
+
+/// Characters after which a newline can't be a place where a semicolon is
+/// automatically inserted, as the statement can't end there.
+///
+/// Note that `+` and `-` aren't included as they may be `++` or `--`.
+const CONTINUES_AFTER: &str = "{([,;:=&|?!<>*%^~.";
+
+/// Characters before which a newline can't be a place where a semicolon is
+/// automatically inserted, as they can't start a statement.
+const CONTINUES_BEFORE: &str = "})],;:.?=&|<>*%^";
+
+pub fn minify(js: &str) -> String {
+    let chars = js.chars().collect::<Vec<_>>();
+    let mut dst = String::with_capacity(js.len());
+    // Nesting of template literals and the `${...}` substitutions inside of
+    // them, tracking how many braces are open in each substitution so we know
+    // which `}` closes it.
+    let mut templates: Vec<usize> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\'' | '"' => {
+                i = copy_string(&chars, i, c, &mut dst);
+            }
+            '`' => {
+                i = copy_template(&chars, i + 1, &mut dst, &mut templates, true);
+            }
+            '{' if !templates.is_empty() => {
+                *templates.last_mut().unwrap() += 1;
+                dst.push(c);
+                i += 1;
+            }
+            '}' if !templates.is_empty() => {
+                let depth = templates.last_mut().unwrap();
+                if *depth == 0 {
+                    templates.pop();
+                    i = copy_template(&chars, i, &mut dst, &mut templates, false);
+                } else {
+                    *depth -= 1;
+                    dst.push(c);
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                let mut newline = false;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    newline = newline || chars[i] == '\n';
+                    i += 1;
+                }
+                i += 2;
+                // A comment counts as whitespace, which matters if it's the
+                // only thing separating two tokens.
+                i = skip_whitespace(&chars, i, newline, &mut dst);
+            }
+            '/' if starts_regex(&dst) => {
+                i = copy_regex(&chars, i, &mut dst);
+            }
+            c if c.is_whitespace() => {
+                i = skip_whitespace(&chars, i, false, &mut dst);
+            }
+            c => {
+                dst.push(c);
+                i += 1;
+            }
+        }
+    }
+    dst.push('\n');
+    dst
+}
+
+/// Skips over whitespace and comments starting at `i`, emitting whatever
+/// whitespace is still needed to separate the tokens on either side.
+fn skip_whitespace(chars: &[char], mut i: usize, mut newline: bool, dst: &mut String) -> usize {
+    loop {
+        match chars.get(i) {
+            Some(c) if c.is_whitespace() => {
+                newline = newline || *c == '\n';
+                i += 1;
+            }
+            Some('/') if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            Some('/') if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    newline = newline || chars[i] == '\n';
+                    i += 1;
+                }
+                i += 2;
+            }
+            _ => break,
+        }
+    }
+    let prev = match dst.chars().last() {
+        Some(c) => c,
+        None => return i,
+    };
+    let next = match chars.get(i) {
+        Some(c) => *c,
+        None => return i,
+    };
+    if newline && !CONTINUES_AFTER.contains(prev) && !CONTINUES_BEFORE.contains(next) {
+        dst.push('\n');
+    } else if needs_space(prev, next) {
+        dst.push(' ');
+    }
+    i
+}
+
+/// Returns whether two tokens ending in `prev` and starting with `next` would
+/// turn into something else if they were next to each other.
+fn needs_space(prev: char, next: char) -> bool {
+    (is_ident(prev) && is_ident(next))
+        || (prev == next && "+-/".contains(prev))
+        || (prev.is_ascii_digit() && next == '.')
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$' || c as u32 >= 0x80
+}
+
+/// Guesses whether a `/` following `dst` starts a regular expression rather
+/// than being a division, based on the token before it.
+fn starts_regex(dst: &str) -> bool {
+    let trimmed = dst.trim_end();
+    let prev = match trimmed.chars().last() {
+        Some(c) => c,
+        None => return true,
+    };
+    if is_ident(prev) {
+        let word_start = trimmed
+            .rfind(|c: char| !is_ident(c))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let word = &trimmed[word_start..];
+        return ["return", "typeof", "case", "do", "else", "in", "of", "void"].contains(&word);
+    }
+    !")]}".contains(prev)
+}
+
+fn copy_string(chars: &[char], mut i: usize, quote: char, dst: &mut String) -> usize {
+    dst.push(quote);
+    i += 1;
+    while i < chars.len() {
+        let c = chars[i];
+        dst.push(c);
+        i += 1;
+        if c == '\\' {
+            if let Some(c) = chars.get(i) {
+                dst.push(*c);
+                i += 1;
+            }
+        } else if c == quote {
+            break;
+        }
+    }
+    i
+}
+
+/// Copies a part of a template literal up to its end or to the next `${`,
+/// starting right after its opening backtick or at the `}` which ends the
+/// previous substitution.
+fn copy_template(
+    chars: &[char],
+    mut i: usize,
+    dst: &mut String,
+    templates: &mut Vec<usize>,
+    start: bool,
+) -> usize {
+    if start {
+        dst.push('`');
+    } else {
+        dst.push('}');
+        i += 1;
+    }
+    while i < chars.len() {
+        let c = chars[i];
+        dst.push(c);
+        i += 1;
+        match c {
+            '\\' => {
+                if let Some(c) = chars.get(i) {
+                    dst.push(*c);
+                    i += 1;
+                }
+            }
+            '`' => break,
+            '$' if chars.get(i) == Some(&'{') => {
+                dst.push('{');
+                templates.push(0);
+                return i + 1;
+            }
+            _ => {}
+        }
+    }
+    i
+}
+
+fn copy_regex(chars: &[char], mut i: usize, dst: &mut String) -> usize {
+    dst.push('/');
+    i += 1;
+    let mut class = false;
+    while i < chars.len() {
+        let c = chars[i];
+        dst.push(c);
+        i += 1;
+        match c {
+            '\\' => {
+                if let Some(c) = chars.get(i) {
+                    dst.push(*c);
+                    i += 1;
+                }
+            }
+            '[' => class = true,
+            ']' => class = false,
+            '/' if !class => break,
+            '\n' => break,
+            _ => {}
+        }
+    }
+    i
+}
+
+#[test]
+fn removes_whitespace_and_comments() {
+    let js = "\
+        // a comment\n\
+        function add(a, b) {\n\
+            /* another\n   comment */\n\
+            return a + b;\n\
+        }\n";
+    assert_eq!(minify(js), "function add(a,b){return a+b;}\n");
+}
+
+#[test]
+fn keeps_template_literals() {
+    assert_eq!(
+        minify("const s = `a  ${ b + `c ${ d }` }  e // f`;"),
+        "const s=`a  ${b+`c ${d}`}  e // f`;\n"
+    );
+    // Braces inside of substitutions don't end them.
+    assert_eq!(
+        minify("const s = `${ { a: 1 }.a }  /* x */`;"),
+        "const s=`${{a:1}.a}  /* x */`;\n"
+    );
+}
+
+#[test]
+fn tells_regexes_from_divisions() {
+    assert_eq!(minify("const x = a / b / c;"), "const x=a/b/c;\n");
+    assert_eq!(minify("const x = (a) / 2 / (b);"), "const x=(a)/2/(b);\n");
+    assert_eq!(
+        minify("const x = /a  b\\/ [/]/g.test(s);"),
+        "const x=/a  b\\/ [/]/g.test(s);\n"
+    );
+    assert_eq!(minify("return /a  b/.test(s);"), "return/a  b/.test(s);\n");
+    assert_eq!(minify("f(a, / c /);"), "f(a,/ c /);\n");
+}
+
+#[test]
+fn keeps_newlines_for_semicolon_insertion() {
+    // A newline after `return` ends the statement.
+    assert_eq!(minify("return\na + b"), "return\na+b\n");
+    // A newline before `++` makes it apply to the next line.
+    assert_eq!(minify("a\n++b"), "a\n++b\n");
+    assert_eq!(minify("a = b\n--c"), "a=b\n--c\n");
+    // Newlines which can't end a statement are removed.
+    assert_eq!(minify("a = [\n1,\n2\n]\n.length"), "a=[1,2].length\n");
+}
+
+#[test]
+fn separates_operators() {
+    assert_eq!(minify("a + +b"), "a+ +b\n");
+    assert_eq!(minify("a - -b"), "a- -b\n");
+    assert_eq!(minify("a++ + b"), "a++ +b\n");
+    assert_eq!(minify("a + -b"), "a+-b\n");
+    assert_eq!(minify("1 .toString()"), "1 .toString()\n");
+    assert_eq!(minify("typeof  x"), "typeof x\n");
+}
@@ -0,0 +1,1333 @@
+//! Passing instructions of proposals our wasm parser doesn't understand
+//! through to the output unchanged.
+//!
+//! Tail calls, relaxed SIMD and exception handling mostly add instructions,
+//! which only ever show up in code compiled from Rust that wasm-bindgen
+//! doesn't need to look into. Before the module is parsed each of them is
+//! replaced with a call to a marker function of the same type, which the
+//! parser does understand and which keeps whatever the instruction refers to
+//! alive and numbered correctly however the module is transformed. Once the
+//! output has been emitted the calls to markers are turned back into the
+//! instructions.
+//!
+//! A tail call becomes a regular call followed by a call to a marker and a
+//! `return`, as the marker itself can't refer to the function being called.
+//! A `try` becomes a `block` starting with a call to a marker, and each of
+//! its `catch` clauses a call to a marker which takes what the block returns
+//! and returns what the tag throws, so the stack looks the same to the code
+//! after it. The markers are left in the output as tiny functions which are
+//! never called.
+//!
+//! Exception handling also adds tags, which the parser can't represent at
+//! all. They're taken out of the module along with their imports and
+//! exports, and put back once the output has been emitted. Nothing besides
+//! the instructions above refers to tags, so wasm-bindgen's passes can't
+//! affect them.
+
+use crate::proposals::Reader;
+use failure::{bail, format_err, Error, ResultExt};
+use std::collections::HashMap;
+
+/// The proposals whose instructions are passed through.
+#[derive(Default, Clone, Copy)]
+pub struct Features {
+    pub tail_call: bool,
+    pub relaxed_simd: bool,
+    pub exception_handling: bool,
+}
+
+/// A module whose instructions have been replaced with calls to markers.
+#[derive(Debug)]
+pub struct Lowered {
+    pub wasm: Vec<u8>,
+    /// The tags taken out of the module, in the order they're numbered in
+    /// once `raise` has put them back.
+    pub tags: Vec<Tag>,
+}
+
+/// An exception tag of a module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    /// The types of the values thrown with the tag.
+    pub params: Vec<u8>,
+    /// The module and name the tag is imported from, if it's imported.
+    pub import: Option<(String, String)>,
+    /// The names the tag is exported under.
+    pub exports: Vec<String>,
+}
+
+/// What the body of every marker function starts with, `"wbgp"` as an
+/// `i32.const`, followed by the kind of the marker as another `i32.const`.
+const MAGIC: i64 = 0x7762_6770;
+
+/// Kinds of markers besides those of relaxed SIMD, whose kinds are their
+/// opcodes.
+///
+/// All kinds are positive, so their signed LEB128 encoding can be read back
+/// with `Reader::u32`.
+const RETURN_CALL: u32 = 0;
+const RETURN_CALL_INDIRECT: u32 = 1;
+const TRY: u32 = 2;
+const CATCH_ALL: u32 = 3;
+
+/// Kinds of markers of instructions with an immediate, a tag index or a
+/// label depth, which is added to the kind.
+const CATCH: u32 = 0x1_0000;
+const THROW: u32 = 0x2_0000;
+const RETHROW: u32 = 0x3_0000;
+const DELEGATE: u32 = 0x4_0000;
+
+const V128: u8 = 0x7b;
+
+/// The order non-custom sections appear in, which isn't the order of their
+/// ids as the tag section was added later.
+const SECTION_ORDER: &[u8] = &[1, 2, 3, 4, 5, 13, 6, 7, 8, 9, 12, 10, 11];
+
+/// Returns whether `wasm` defines or imports any tags, with which it throws
+/// and catches exceptions, so exception handling is used even if it isn't
+/// listed among its target features.
+pub fn uses_tags(wasm: &[u8]) -> bool {
+    let sections = match sections(wasm) {
+        Some(sections) => sections,
+        None => return false,
+    };
+    sections.iter().any(|(id, payload)| match id {
+        2 => read_imports(&mut Reader { data: payload }).map_or(false, |i| !i.1.is_empty()),
+        13 => true,
+        _ => false,
+    })
+}
+
+/// Replaces the instructions of `features` in `wasm` with calls to markers
+/// and takes out its tags, returning `None` if there's nothing to replace or
+/// take out.
+///
+/// The module was compiled with `features` enabled, so failing to read it
+/// here is an error rather than a reason to hand it to the parser as is,
+/// which would otherwise fail on the first instruction it doesn't know with
+/// a much less helpful error.
+pub fn lower(wasm: &[u8], features: Features) -> Result<Option<Lowered>, Error> {
+    if !features.tail_call && !features.relaxed_simd && !features.exception_handling {
+        return Ok(None);
+    }
+    let err = |what: &str| format_err!("failed to read {} to pass instructions through", what);
+    let sections = sections(wasm).ok_or_else(|| err("the sections of the module"))?;
+
+    let mut types = Vec::new();
+    let mut funcs = Vec::new();
+    let mut imported_tags = Vec::new();
+    let mut defined_tags = Vec::new();
+    let mut tag_exports = Vec::new();
+    for (id, payload) in sections.iter() {
+        let mut data = Reader { data: payload };
+        match id {
+            1 => {
+                types = read_types(&mut data).ok_or_else(|| err("the type section"))?;
+            }
+            2 => {
+                let (imported, tags) =
+                    read_imports(&mut data).ok_or_else(|| err("the import section"))?;
+                funcs.extend(imported);
+                imported_tags = tags;
+            }
+            3 => {
+                let count = data.u32().ok_or_else(|| err("the function section"))?;
+                for _ in 0..count {
+                    funcs.push(data.u32().ok_or_else(|| err("the function section"))?);
+                }
+            }
+            7 => {
+                tag_exports =
+                    read_tag_exports(&mut data).ok_or_else(|| err("the export section"))?;
+            }
+            13 => {
+                defined_tags = read_tags(&mut data).ok_or_else(|| err("the tag section"))?;
+            }
+            _ => {}
+        }
+    }
+
+    // Tags imported from `env` are left for the linker to define, which
+    // nothing does when the module is loaded from JS, so the module defines
+    // them itself instead and exports them for the glue to use. The tags
+    // which are still imported come first when they're put back, so the
+    // others are numbered after them.
+    let mut tags = Vec::new();
+    let mut old_tags = vec![(0, Vec::new()); imported_tags.len() + defined_tags.len()];
+    let from_env = |i: &usize| imported_tags[*i].0 == "env";
+    let imported = (0..imported_tags.len()).filter(|i| !from_env(i));
+    let defined = (0..imported_tags.len()).filter(from_env);
+    for old in imported
+        .chain(defined)
+        .chain(imported_tags.len()..old_tags.len())
+    {
+        let (import, ty) = match imported_tags.get(old) {
+            Some((module, name, ty)) if *module != "env" => {
+                (Some((module.to_string(), name.to_string())), *ty)
+            }
+            Some((_, _, ty)) => (None, *ty),
+            None => (None, defined_tags[old - imported_tags.len()]),
+        };
+        let params = match types.get(ty as usize) {
+            Some((params, results)) if results.is_empty() => params.clone(),
+            _ => bail!("tag {} has an invalid type", old),
+        };
+        let mut exports = tag_exports
+            .iter()
+            .filter(|(_, tag)| *tag as usize == old)
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>();
+        if let Some((_, name, _)) = imported_tags.get(old).filter(|_| import.is_none()) {
+            exports.push(name.to_string());
+        }
+        old_tags[old] = (tags.len() as u32, params.clone());
+        tags.push(Tag {
+            params,
+            import,
+            exports,
+        });
+    }
+
+    let mut markers = Markers {
+        types_before: types.len(),
+        types,
+        funcs_before: funcs.len() as u32,
+        funcs: Vec::new(),
+        ids: HashMap::new(),
+    };
+    let mut code = Vec::new();
+    let mut bodies = 0;
+    for (id, payload) in sections.iter() {
+        if *id != 10 {
+            continue;
+        }
+        let mut data = Reader { data: payload };
+        bodies = data.u32().ok_or_else(|| err("the code section"))?;
+        for i in 0..bodies {
+            let body = data
+                .u32()
+                .and_then(|len| data.bytes(len as usize))
+                .ok_or_else(|| err("the code section"))?;
+            let index = markers.funcs_before as usize - bodies as usize + i as usize;
+            let body =
+                lower_body(body, &funcs, &old_tags, &mut markers, features).with_context(|_| {
+                    format!("failed to pass instructions of function {} through", index)
+                })?;
+            uleb(&mut code, body.len() as u64);
+            code.extend_from_slice(&body);
+        }
+    }
+    if markers.funcs.is_empty() && tags.is_empty() {
+        return Ok(None);
+    }
+
+    let mut ret = wasm[..8].to_vec();
+    for (id, payload) in sections.iter() {
+        let payload = match id {
+            1 => {
+                let new = &markers.types[markers.types_before..];
+                let existing = skip_count(payload).ok_or_else(|| err("the type section"))?;
+                let mut types = Vec::new();
+                uleb(&mut types, markers.types.len() as u64);
+                types.extend_from_slice(existing);
+                for (params, results) in new {
+                    types.push(0x60);
+                    uleb(&mut types, params.len() as u64);
+                    types.extend_from_slice(params);
+                    uleb(&mut types, results.len() as u64);
+                    types.extend_from_slice(results);
+                }
+                types
+            }
+            2 => without_tags(payload, read_import).ok_or_else(|| err("the import section"))?,
+            3 => {
+                let err = || err("the function section");
+                let count = Reader { data: payload }.u32().ok_or_else(err)? as usize;
+                let existing = skip_count(payload).ok_or_else(err)?;
+                let mut section = Vec::new();
+                uleb(&mut section, (count + markers.funcs.len()) as u64);
+                section.extend_from_slice(existing);
+                for (ty, _) in markers.funcs.iter() {
+                    uleb(&mut section, *ty as u64);
+                }
+                section
+            }
+            7 => without_tags(payload, read_export).ok_or_else(|| err("the export section"))?,
+            10 => {
+                let mut section = Vec::new();
+                uleb(&mut section, (bodies as usize + markers.funcs.len()) as u64);
+                section.extend_from_slice(&code);
+                for (_, kind) in markers.funcs.iter() {
+                    let body = marker_body(*kind);
+                    uleb(&mut section, body.len() as u64);
+                    section.extend_from_slice(&body);
+                }
+                section
+            }
+            13 => continue,
+            _ => payload.to_vec(),
+        };
+        ret.push(*id);
+        uleb(&mut ret, payload.len() as u64);
+        ret.extend_from_slice(&payload);
+    }
+    Ok(Some(Lowered { wasm: ret, tags }))
+}
+
+/// Reads the function types of the type section.
+fn read_types(data: &mut Reader) -> Option<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut types = Vec::new();
+    for _ in 0..data.u32()? {
+        if data.byte()? != 0x60 {
+            return None;
+        }
+        let params = data.vec_bytes()?.to_vec();
+        let results = data.vec_bytes()?.to_vec();
+        types.push((params, results));
+    }
+    Some(types)
+}
+
+/// Reads the import section, returning the types of the imported functions
+/// and the module, name and type of the imported tags.
+fn read_imports<'a>(data: &mut Reader<'a>) -> Option<(Vec<u32>, Vec<(&'a str, &'a str, u32)>)> {
+    let mut funcs = Vec::new();
+    let mut tags = Vec::new();
+    for _ in 0..data.u32()? {
+        let (module, name, kind, ty) = read_import(data)?;
+        match kind {
+            0 => funcs.push(ty),
+            4 => tags.push((module, name, ty)),
+            _ => {}
+        }
+    }
+    Some((funcs, tags))
+}
+
+/// Reads an import, returning its kind and, for functions and tags, the
+/// index of its type.
+fn read_import<'a>(data: &mut Reader<'a>) -> Option<(&'a str, &'a str, u8, u32)> {
+    let module = data.string()?;
+    let name = data.string()?;
+    let kind = data.byte()?;
+    let ty = match kind {
+        0 => data.u32()?,
+        1 => {
+            data.byte()?;
+            data.limits()?;
+            0
+        }
+        2 => {
+            data.limits()?;
+            0
+        }
+        3 => {
+            data.bytes(2)?;
+            0
+        }
+        4 => {
+            // The attribute of the tag, which is always 0 for exceptions.
+            data.byte()?;
+            data.u32()?
+        }
+        _ => return None,
+    };
+    Some((module, name, kind, ty))
+}
+
+/// Reads an export, returning its kind and the index of what it exports.
+fn read_export<'a>(data: &mut Reader<'a>) -> Option<(&'a str, &'a str, u8, u32)> {
+    let name = data.string()?;
+    let kind = data.byte()?;
+    Some(("", name, kind, data.u32()?))
+}
+
+/// Reads the export section, returning the names and indices of the
+/// exported tags.
+fn read_tag_exports<'a>(data: &mut Reader<'a>) -> Option<Vec<(&'a str, u32)>> {
+    let mut tags = Vec::new();
+    for _ in 0..data.u32()? {
+        let (_, name, kind, index) = read_export(data)?;
+        if kind == 4 {
+            tags.push((name, index));
+        }
+    }
+    Some(tags)
+}
+
+/// Reads the tag section, returning the types of the tags.
+fn read_tags(data: &mut Reader) -> Option<Vec<u32>> {
+    let mut tags = Vec::new();
+    for _ in 0..data.u32()? {
+        if data.byte()? != 0 {
+            return None;
+        }
+        tags.push(data.u32()?);
+    }
+    Some(tags)
+}
+
+/// Returns the import or export section `payload` without its tags, where
+/// `read` reads one of its entries.
+fn without_tags<'a>(
+    payload: &'a [u8],
+    read: fn(&mut Reader<'a>) -> Option<(&'a str, &'a str, u8, u32)>,
+) -> Option<Vec<u8>> {
+    let mut data = Reader { data: payload };
+    let mut count = 0;
+    let mut entries = Vec::new();
+    for _ in 0..data.u32()? {
+        let start = payload.len() - data.data.len();
+        if read(&mut data)?.2 != 4 {
+            count += 1;
+            entries.extend_from_slice(&payload[start..payload.len() - data.data.len()]);
+        }
+    }
+    let mut section = Vec::new();
+    uleb(&mut section, count);
+    section.extend_from_slice(&entries);
+    Some(section)
+}
+
+/// Turns calls to markers in `wasm` back into the instructions they replaced
+/// and puts back the `tags` that were taken out of it.
+pub fn raise(wasm: Vec<u8>, tags: &[Tag]) -> Result<Vec<u8>, Error> {
+    let err = || format_err!("failed to restore instructions passed through");
+    let sections = sections(&wasm).ok_or_else(err)?;
+
+    let mut types = Vec::new();
+    let mut imported = 0;
+    let mut markers = HashMap::new();
+    for (id, payload) in sections.iter() {
+        let mut data = Reader { data: payload };
+        match id {
+            1 => types = read_types(&mut data).ok_or_else(err)?,
+            2 => imported = read_imports(&mut data).ok_or_else(err)?.0.len() as u32,
+            10 => {
+                for i in 0..data.u32().ok_or_else(err)? {
+                    let len = data.u32().ok_or_else(err)? as usize;
+                    let body = data.bytes(len).ok_or_else(err)?;
+                    if let Some(kind) = marker_kind(body) {
+                        markers.insert(imported + i, kind);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if markers.is_empty() && tags.is_empty() {
+        return Ok(wasm);
+    }
+
+    // The types of tags may have been removed from the module if nothing
+    // else uses them, so they're looked up again.
+    let types_before = types.len();
+    let mut tag_types = Vec::new();
+    for tag in tags {
+        let ty = match types
+            .iter()
+            .position(|t| t.0 == tag.params && t.1.is_empty())
+        {
+            Some(i) => i,
+            None => {
+                types.push((tag.params.clone(), Vec::new()));
+                types.len() - 1
+            }
+        };
+        tag_types.push(ty as u64);
+    }
+    let mut new_types = Vec::new();
+    for (params, results) in types[types_before..].iter() {
+        new_types.push(0x60);
+        uleb(&mut new_types, params.len() as u64);
+        new_types.extend_from_slice(params);
+        uleb(&mut new_types, results.len() as u64);
+        new_types.extend_from_slice(results);
+    }
+    let mut imports = Vec::new();
+    let mut defined = Vec::new();
+    let mut exports = Vec::new();
+    for (i, tag) in tags.iter().enumerate() {
+        match &tag.import {
+            Some((module, name)) => {
+                for s in [module, name].iter() {
+                    uleb(&mut imports, s.len() as u64);
+                    imports.extend_from_slice(s.as_bytes());
+                }
+                imports.extend_from_slice(&[4, 0]);
+                uleb(&mut imports, tag_types[i]);
+            }
+            None => {
+                defined.push(0);
+                uleb(&mut defined, tag_types[i]);
+            }
+        }
+        for name in tag.exports.iter() {
+            uleb(&mut exports, name.len() as u64);
+            exports.extend_from_slice(name.as_bytes());
+            exports.push(4);
+            uleb(&mut exports, i as u64);
+        }
+    }
+    let defined_tags = tags.iter().filter(|t| t.import.is_none()).count();
+    let additions = [
+        (1, types.len() - types_before, new_types),
+        (2, tags.len() - defined_tags, imports),
+        (13, defined_tags, defined),
+        (7, tags.iter().map(|t| t.exports.len()).sum(), exports),
+    ];
+
+    let mut new_sections = Vec::new();
+    for (id, payload) in sections.iter() {
+        let payload = if *id == 10 && !markers.is_empty() {
+            let mut data = Reader { data: payload };
+            let mut section = Vec::new();
+            let count = data.u32().ok_or_else(err)?;
+            uleb(&mut section, count as u64);
+            for _ in 0..count {
+                let len = data.u32().ok_or_else(err)? as usize;
+                let body = data.bytes(len).ok_or_else(err)?;
+                let body = match marker_kind(body) {
+                    Some(_) => body.to_vec(),
+                    None => raise_body(body, &markers)?,
+                };
+                uleb(&mut section, body.len() as u64);
+                section.extend_from_slice(&body);
+            }
+            section
+        } else {
+            payload.to_vec()
+        };
+        new_sections.push((*id, payload));
+    }
+    for (id, count, entries) in additions.iter() {
+        if *count == 0 {
+            continue;
+        }
+        let (i, existing) = match new_sections.iter().position(|s| s.0 == *id) {
+            Some(i) => (i, new_sections.remove(i).1),
+            None => {
+                let order = |id| SECTION_ORDER.iter().position(|i| *i == id);
+                let before = new_sections
+                    .iter()
+                    .rposition(|s| order(s.0) < order(*id) && s.0 != 0)
+                    .map_or(0, |i| i + 1);
+                (before, vec![0])
+            }
+        };
+        let mut data = Reader { data: &existing };
+        let mut section = Vec::new();
+        uleb(
+            &mut section,
+            (data.u32().ok_or_else(err)? as usize + count) as u64,
+        );
+        section.extend_from_slice(data.data);
+        section.extend_from_slice(entries);
+        new_sections.insert(i, (*id, section));
+    }
+
+    let mut ret = wasm[..8].to_vec();
+    for (id, payload) in new_sections {
+        ret.push(id);
+        uleb(&mut ret, payload.len() as u64);
+        ret.extend_from_slice(&payload);
+    }
+    Ok(ret)
+}
+
+/// Marker functions added to a module, along with the types they have.
+struct Markers {
+    types: Vec<(Vec<u8>, Vec<u8>)>,
+    types_before: usize,
+    funcs_before: u32,
+    /// The type and kind of each marker.
+    funcs: Vec<(u32, u32)>,
+    /// The index of the marker of each kind and type.
+    ids: HashMap<(u32, u32), u32>,
+}
+
+impl Markers {
+    /// Returns the index of the marker of `kind` with the given type.
+    fn get(&mut self, kind: u32, params: &[u8], results: &[u8]) -> u32 {
+        let ty = match self
+            .types
+            .iter()
+            .position(|t| t.0 == params && t.1 == results)
+        {
+            Some(i) => i as u32,
+            None => {
+                self.types.push((params.to_vec(), results.to_vec()));
+                self.types.len() as u32 - 1
+            }
+        };
+        let funcs_before = self.funcs_before;
+        let funcs = &mut self.funcs;
+        *self.ids.entry((kind, ty)).or_insert_with(|| {
+            funcs.push((ty, kind));
+            funcs_before + funcs.len() as u32 - 1
+        })
+    }
+
+    /// Adds a call to the marker of `kind` with the given type to `code`.
+    fn call(&mut self, code: &mut Vec<u8>, kind: u32, params: &[u8], results: &[u8]) {
+        code.push(0x10);
+        uleb(code, self.get(kind, params, results) as u64);
+    }
+}
+
+/// Replaces the instructions of `features` in `body` with calls to markers,
+/// where `tags` are the new index and parameters of each tag.
+fn lower_body(
+    body: &[u8],
+    funcs: &[u32],
+    tags: &[(u32, Vec<u8>)],
+    markers: &mut Markers,
+    features: Features,
+) -> Result<Vec<u8>, Error> {
+    let eof = || format_err!("unexpected end of the function body");
+    let mut data = Reader { data: body };
+    for _ in 0..data.u32().ok_or_else(eof)? {
+        data.u32().ok_or_else(eof)?;
+        data.byte().ok_or_else(eof)?;
+    }
+    let mut ret = body[..body.len() - data.data.len()].to_vec();
+    // What each enclosing block returns, which a `catch` takes.
+    let mut blocks = Vec::new();
+    let eh = features.exception_handling;
+    while !data.data.is_empty() {
+        let start = body.len() - data.data.len();
+        let op = data.byte().ok_or_else(eof)?;
+        match op {
+            0x12 if features.tail_call => {
+                let func = data.u32().ok_or_else(eof)?;
+                let results = match funcs.get(func as usize) {
+                    Some(ty) => match markers.types.get(*ty as usize) {
+                        Some(ty) => ty.1.clone(),
+                        None => bail!("function {} has an unknown type", func),
+                    },
+                    None => bail!("`return_call` of unknown function {}", func),
+                };
+                ret.push(0x10);
+                uleb(&mut ret, func as u64);
+                markers.call(&mut ret, RETURN_CALL, &results, &results);
+                ret.push(0x0f);
+            }
+            0x13 if features.tail_call => {
+                let ty = data.u32().ok_or_else(eof)?;
+                let table = data.u32().ok_or_else(eof)?;
+                let results = match markers.types.get(ty as usize) {
+                    Some(ty) => ty.1.clone(),
+                    None => bail!("`return_call_indirect` of unknown type {}", ty),
+                };
+                ret.push(0x11);
+                uleb(&mut ret, ty as u64);
+                uleb(&mut ret, table as u64);
+                markers.call(&mut ret, RETURN_CALL_INDIRECT, &results, &results);
+                ret.push(0x0f);
+            }
+            0xfd => {
+                let op = data.u32().ok_or_else(eof)?;
+                match relaxed_simd_arity(op) {
+                    Some(arity) if features.relaxed_simd => {
+                        markers.call(&mut ret, op, &vec![V128; arity], &[V128]);
+                    }
+                    _ => {
+                        skip_simd(op, &mut data).ok_or_else(eof)?;
+                        ret.extend_from_slice(&body[start..body.len() - data.data.len()]);
+                    }
+                }
+            }
+            // `block`, `loop`, `if` and `try`
+            0x02 | 0x03 | 0x04 | 0x06 => {
+                let results = match data.block_results(&markers.types[..markers.types_before]) {
+                    Some(results) => results,
+                    None => bail!("invalid block type at offset {}", start),
+                };
+                blocks.push(results);
+                if op == 0x06 && eh {
+                    ret.push(0x02);
+                    ret.extend_from_slice(&body[start + 1..body.len() - data.data.len()]);
+                    markers.call(&mut ret, TRY, &[], &[]);
+                } else {
+                    ret.extend_from_slice(&body[start..body.len() - data.data.len()]);
+                }
+            }
+            // `catch`, `throw`, `rethrow`, `delegate` and `catch_all`
+            0x07 | 0x08 | 0x09 | 0x18 | 0x19 if eh => {
+                let imm = if op == 0x19 {
+                    0
+                } else {
+                    data.u32().ok_or_else(eof)?
+                };
+                let tag = || match tags.get(imm as usize) {
+                    Some((index, params)) => Ok((with_immediate(*index)?, params)),
+                    None => Err(format_err!("unknown tag {} at offset {}", imm, start)),
+                };
+                let results = match blocks.last() {
+                    Some(results) => results.clone(),
+                    None if op == 0x08 || op == 0x09 => Vec::new(),
+                    None => bail!(
+                        "`catch` or `delegate` outside of a `try` at offset {}",
+                        start
+                    ),
+                };
+                match op {
+                    0x07 => {
+                        let (index, params) = tag()?;
+                        markers.call(&mut ret, CATCH + index, &results, params);
+                    }
+                    0x08 => {
+                        let (index, params) = tag()?;
+                        markers.call(&mut ret, THROW + index, params, &[]);
+                        ret.push(0x00);
+                    }
+                    0x09 => {
+                        markers.call(&mut ret, RETHROW + with_immediate(imm)?, &[], &[]);
+                        ret.push(0x00);
+                    }
+                    0x18 => {
+                        markers.call(
+                            &mut ret,
+                            DELEGATE + with_immediate(imm)?,
+                            &results,
+                            &results,
+                        );
+                        ret.push(0x0b);
+                        blocks.pop();
+                    }
+                    _ => markers.call(&mut ret, CATCH_ALL, &results, &[]),
+                }
+            }
+            // `throw_ref` and `try_table`
+            0x0a | 0x1f => bail!(
+                "the `exnref` version of exception handling isn't supported by \
+                 wasm-bindgen yet, only its legacy version"
+            ),
+            op => {
+                if skip_immediates(op, &mut data).is_none() {
+                    bail!(
+                        "failed to read the instruction with opcode 0x{:02x} at offset {}",
+                        op,
+                        start
+                    );
+                }
+                if op == 0x0b {
+                    blocks.pop();
+                }
+                ret.extend_from_slice(&body[start..body.len() - data.data.len()]);
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Checks that the immediate of an instruction fits in the kind of its marker.
+fn with_immediate(imm: u32) -> Result<u32, Error> {
+    if imm >= CATCH {
+        bail!(
+            "immediate {} of exception handling instruction is too large",
+            imm
+        );
+    }
+    Ok(imm)
+}
+
+fn raise_body(body: &[u8], markers: &HashMap<u32, u32>) -> Result<Vec<u8>, Error> {
+    let err = || format_err!("failed to restore instructions passed through");
+    let mut data = Reader { data: body };
+    for _ in 0..data.u32().ok_or_else(err)? {
+        data.u32().ok_or_else(err)?;
+        data.byte().ok_or_else(err)?;
+    }
+    let mut ret = body[..body.len() - data.data.len()].to_vec();
+    // Where the previous instruction starts in `ret`, and its opcode.
+    let mut prev = None;
+    while !data.data.is_empty() {
+        let start = body.len() - data.data.len();
+        let out = ret.len();
+        let op = data.byte().ok_or_else(err)?;
+        let kind = if op == 0x10 {
+            let func = data.u32().ok_or_else(err)?;
+            markers.get(&func).cloned()
+        } else {
+            skip_immediates(op, &mut data).ok_or_else(err)?;
+            None
+        };
+        // The instruction a marker replaced along with what followed it, if
+        // anything, and whether it needs to be there.
+        let mut replace = |op: u8, imm: Option<u32>, next: Option<u8>| -> Result<(), Error> {
+            if let Some(next) = next {
+                if data.data.first() != Some(&next) {
+                    bail!("exception handling marker isn't followed by 0x{:02x}", next);
+                }
+                data.byte().ok_or_else(err)?;
+            }
+            ret.push(op);
+            if let Some(imm) = imm {
+                uleb(&mut ret, imm as u64);
+            }
+            Ok(())
+        };
+        match kind {
+            Some(TRY) => {
+                match prev {
+                    Some((at, 0x02)) => ret[at] = 0x06,
+                    _ => bail!("`try` marker isn't at the start of a block"),
+                }
+                prev = None;
+            }
+            Some(CATCH_ALL) => {
+                replace(0x19, None, None)?;
+                prev = Some((out, 0x19));
+            }
+            Some(kind) if kind >= CATCH => {
+                let imm = Some(kind & 0xffff);
+                match kind & !0xffff {
+                    CATCH => replace(0x07, imm, None)?,
+                    THROW => replace(0x08, imm, Some(0x00))?,
+                    RETHROW => replace(0x09, imm, Some(0x00))?,
+                    DELEGATE => replace(0x18, imm, Some(0x0b))?,
+                    _ => bail!("unknown kind of marker {}", kind),
+                }
+                prev = Some((out, ret[out]));
+            }
+            Some(kind @ RETURN_CALL) | Some(kind @ RETURN_CALL_INDIRECT) => {
+                let call = if kind == RETURN_CALL { 0x10 } else { 0x11 };
+                match prev {
+                    Some((at, op)) if op == call => ret[at] = call + 2,
+                    _ => bail!("tail call marker isn't preceded by a call"),
+                }
+                // The call is followed by a `return`, unless it's the last
+                // thing in the function.
+                match data.data.first() {
+                    Some(0x0f) => {
+                        data.byte().ok_or_else(err)?;
+                    }
+                    Some(0x0b) if data.data.len() == 1 => {}
+                    _ => bail!("tail call marker isn't followed by a return"),
+                }
+                prev = None;
+            }
+            Some(op) => {
+                ret.push(0xfd);
+                uleb(&mut ret, op as u64);
+                prev = Some((out, 0xfd));
+            }
+            None => {
+                ret.extend_from_slice(&body[start..body.len() - data.data.len()]);
+                prev = Some((out, op));
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Returns the sections of `wasm` as their ids and payloads.
+fn sections(wasm: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    let mut data = Reader { data: wasm };
+    if data.bytes(8)? != b"\0asm\x01\0\0\0" {
+        return None;
+    }
+    let mut ret = Vec::new();
+    while !data.data.is_empty() {
+        let id = data.byte()?;
+        let len = data.u32()? as usize;
+        ret.push((id, data.bytes(len)?));
+    }
+    Some(ret)
+}
+
+/// Returns the entries of a section, without their count.
+fn skip_count(payload: &[u8]) -> Option<&[u8]> {
+    let mut data = Reader { data: payload };
+    data.u32()?;
+    Some(data.data)
+}
+
+fn marker_body(kind: u32) -> Vec<u8> {
+    // No locals, `i32.const MAGIC`, `drop`, `i32.const kind`, `drop`,
+    // `unreachable` and `end`.
+    let mut body = vec![0x00, 0x41];
+    sleb(&mut body, MAGIC);
+    body.extend_from_slice(&[0x1a, 0x41]);
+    sleb(&mut body, kind as i64);
+    body.extend_from_slice(&[0x1a, 0x00, 0x0b]);
+    body
+}
+
+/// Returns the kind of the marker with `body`, if it's a marker.
+fn marker_kind(body: &[u8]) -> Option<u32> {
+    let mut prefix = vec![0x00, 0x41];
+    sleb(&mut prefix, MAGIC);
+    prefix.extend_from_slice(&[0x1a, 0x41]);
+    if !body.starts_with(&prefix) {
+        return None;
+    }
+    let mut data = Reader {
+        data: &body[prefix.len()..],
+    };
+    let kind = data.u32()?;
+    if data.byte()? != 0x1a {
+        return None;
+    }
+    Some(kind)
+}
+
+/// Returns the number of operands of relaxed SIMD instruction `op`, all of
+/// which are `v128` like its result, or `None` if it isn't one.
+fn relaxed_simd_arity(op: u32) -> Option<usize> {
+    match op {
+        0x101..=0x104 => Some(1),
+        0x100 | 0x10d..=0x112 => Some(2),
+        0x105..=0x10c | 0x113 => Some(3),
+        _ => None,
+    }
+}
+
+/// Skips over the immediates of the instruction with opcode `op`, returning
+/// `None` for opcodes we don't know.
+fn skip_immediates(op: u8, data: &mut Reader) -> Option<()> {
+    match op {
+        0x00 | 0x01 | 0x05 | 0x0b | 0x0f | 0x19 | 0x1a | 0x1b | 0x45..=0xc4 | 0xd1 => {}
+        0x02 | 0x03 | 0x04 | 0x06 => data.block_type()?,
+        0x07
+        | 0x08
+        | 0x09
+        | 0x0c
+        | 0x0d
+        | 0x10
+        | 0x12
+        | 0x18
+        | 0x20..=0x26
+        | 0x3f
+        | 0x40
+        | 0xd2 => {
+            data.u32()?;
+        }
+        0x0e => {
+            for _ in 0..=data.u32()? {
+                data.u32()?;
+            }
+        }
+        0x11 | 0x13 => {
+            data.u32()?;
+            data.u32()?;
+        }
+        0x1c => {
+            data.vec_bytes()?;
+        }
+        0x28..=0x3e => data.memarg()?,
+        0x41 | 0x42 | 0xd0 => data.leb()?,
+        0x43 => {
+            data.bytes(4)?;
+        }
+        0x44 => {
+            data.bytes(8)?;
+        }
+        0xfc => match data.u32()? {
+            0..=7 => {}
+            9 | 11 | 13 | 15 | 16 | 17 => {
+                data.u32()?;
+            }
+            8 | 10 | 12 | 14 => {
+                data.u32()?;
+                data.u32()?;
+            }
+            _ => return None,
+        },
+        0xfd => {
+            let op = data.u32()?;
+            skip_simd(op, data)?;
+        }
+        0xfe => match data.u32()? {
+            // `atomic.fence`
+            0x03 => {
+                data.byte()?;
+            }
+            _ => data.memarg()?,
+        },
+        _ => return None,
+    }
+    Some(())
+}
+
+/// Skips over the immediates of the SIMD instruction `op`.
+fn skip_simd(op: u32, data: &mut Reader) -> Option<()> {
+    match op {
+        // Loads and stores
+        0..=11 | 92 | 93 => data.memarg()?,
+        // `v128.const` and `i8x16.shuffle`
+        12 | 13 => {
+            data.bytes(16)?;
+        }
+        // Extracting and replacing lanes
+        21..=34 => {
+            data.byte()?;
+        }
+        // Loading and storing lanes
+        84..=91 => {
+            data.memarg()?;
+            data.byte()?;
+        }
+        _ => {}
+    }
+    Some(())
+}
+
+impl<'a> Reader<'a> {
+    /// Returns a vector of bytes, like the value types of a function type.
+    fn vec_bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.bytes(len)
+    }
+
+    /// Skips over the limits of a table or memory.
+    fn limits(&mut self) -> Option<()> {
+        let flags = self.byte()?;
+        self.leb()?;
+        if flags & 1 != 0 {
+            self.leb()?;
+        }
+        Some(())
+    }
+
+    /// Skips over a LEB128 number of any size.
+    fn leb(&mut self) -> Option<()> {
+        while self.byte()? & 0x80 != 0 {}
+        Some(())
+    }
+
+    fn memarg(&mut self) -> Option<()> {
+        let align = self.u32()?;
+        // With multiple memories a memory index follows the alignment.
+        if align & 0x40 != 0 {
+            self.u32()?;
+        }
+        self.leb()
+    }
+
+    /// Reads a block type, returning the types of what the block returns.
+    fn block_results(&mut self, types: &[(Vec<u8>, Vec<u8>)]) -> Option<Vec<u8>> {
+        match *self.data.first()? {
+            0x40 => {
+                self.byte()?;
+                Some(Vec::new())
+            }
+            0x7b..=0x7f | 0x70 | 0x6f => Some(vec![self.byte()?]),
+            // The index of a type, as a signed LEB128 number which is never
+            // negative, and so reads the same as an unsigned one.
+            _ => Some(types.get(self.u32()? as usize)?.1.clone()),
+        }
+    }
+
+    fn block_type(&mut self) -> Option<()> {
+        match *self.data.first()? {
+            // Empty, or one of the value types.
+            0x40 | 0x7b..=0x7f | 0x70 | 0x6f => {
+                self.byte()?;
+                Some(())
+            }
+            // The index of a type.
+            _ => self.leb(),
+        }
+    }
+}
+
+/// Appends `val` to `dst` as an unsigned LEB128.
+pub fn uleb(dst: &mut Vec<u8>, mut val: u64) {
+    loop {
+        let byte = (val & 0x7f) as u8;
+        val >>= 7;
+        if val == 0 {
+            dst.push(byte);
+            break;
+        }
+        dst.push(byte | 0x80);
+    }
+}
+
+fn sleb(dst: &mut Vec<u8>, mut val: i64) {
+    loop {
+        let byte = (val & 0x7f) as u8;
+        val >>= 7;
+        if (val == 0 && byte & 0x40 == 0) || (val == -1 && byte & 0x40 != 0) {
+            dst.push(byte);
+            break;
+        }
+        dst.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+const ALL: Features = Features {
+    tail_call: true,
+    relaxed_simd: true,
+    exception_handling: true,
+};
+
+#[cfg(test)]
+fn module(sections: &[(u8, &[u8])]) -> Vec<u8> {
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    for (id, payload) in sections {
+        wasm.push(*id);
+        uleb(&mut wasm, payload.len() as u64);
+        wasm.extend_from_slice(payload);
+    }
+    wasm
+}
+
+#[cfg(test)]
+fn bodies(wasm: &[u8]) -> Vec<Vec<u8>> {
+    let (_, code) = sections(wasm)
+        .unwrap()
+        .into_iter()
+        .find(|s| s.0 == 10)
+        .unwrap();
+    let mut data = Reader { data: code };
+    (0..data.u32().unwrap())
+        .map(|_| {
+            let len = data.u32().unwrap() as usize;
+            data.bytes(len).unwrap().to_vec()
+        })
+        .collect()
+}
+
+#[test]
+fn lowers_nothing() {
+    // (func (result i32) i32.const 1)
+    let wasm = module(&[
+        (1, b"\x01\x60\x00\x01\x7f"),
+        (3, b"\x01\x00"),
+        (10, b"\x01\x04\x00\x41\x01\x0b"),
+    ]);
+    assert!(lower(&wasm, ALL).unwrap().is_none());
+    assert!(lower(&wasm, Features::default()).unwrap().is_none());
+    assert_eq!(raise(wasm.clone(), &[]).unwrap(), wasm);
+}
+
+#[test]
+fn passes_tail_calls_through() {
+    // (type (func (result i32)))
+    // (import "a" "b" (func (type 0)))
+    // (table 1 funcref)
+    // (func (type 0) return_call 0)
+    // (func (type 0) i32.const 0 return_call_indirect (type 0))
+    let wasm = module(&[
+        (1, b"\x01\x60\x00\x01\x7f"),
+        (2, b"\x01\x01a\x01b\x00\x00"),
+        (3, b"\x02\x00\x00"),
+        (4, b"\x01\x70\x00\x01"),
+        (
+            10,
+            b"\x02\x04\x00\x12\x00\x0b\x07\x00\x41\x00\x13\x00\x00\x0b",
+        ),
+    ]);
+    let features = Features {
+        tail_call: false,
+        ..ALL
+    };
+    assert!(lower(&wasm, features).unwrap().is_none());
+    let lowered = lower(&wasm, ALL).unwrap().unwrap().wasm;
+    let lowered_bodies = bodies(&lowered);
+    // Markers are added after the existing functions, with the same type
+    // as they return what the call returns.
+    assert_eq!(lowered_bodies.len(), 4);
+    assert_eq!(lowered_bodies[0], b"\x00\x10\x00\x10\x03\x0f\x0b");
+    assert_eq!(
+        lowered_bodies[1],
+        b"\x00\x41\x00\x11\x00\x00\x10\x04\x0f\x0b"
+    );
+    assert_eq!(marker_kind(&lowered_bodies[2]), Some(RETURN_CALL));
+    assert_eq!(marker_kind(&lowered_bodies[3]), Some(RETURN_CALL_INDIRECT));
+
+    let raised = raise(lowered, &[]).unwrap();
+    let raised_bodies = bodies(&raised);
+    assert_eq!(raised_bodies[..2], bodies(&wasm)[..]);
+    assert_eq!(raised_bodies[2..], lowered_bodies[2..]);
+}
+
+#[test]
+fn passes_relaxed_simd_through() {
+    // (type (func (param v128 v128 v128) (result v128)))
+    // (func (type 0)
+    //   local.get 0 local.get 1 local.get 2 f32x4.relaxed_madd
+    //   local.get 1 i8x16.relaxed_swizzle)
+    let body: &[u8] = b"\x00\x20\x00\x20\x01\x20\x02\xfd\x85\x02\x20\x01\xfd\x80\x02\x0b";
+    let mut code = vec![0x01, body.len() as u8];
+    code.extend_from_slice(body);
+    let wasm = module(&[
+        (1, b"\x01\x60\x03\x7b\x7b\x7b\x01\x7b"),
+        (3, b"\x01\x00"),
+        (10, &code),
+    ]);
+    let lowered = lower(&wasm, ALL).unwrap().unwrap().wasm;
+    let lowered_bodies = bodies(&lowered);
+    assert_eq!(
+        lowered_bodies[0],
+        b"\x00\x20\x00\x20\x01\x20\x02\x10\x01\x20\x01\x10\x02\x0b"
+    );
+    assert_eq!(marker_kind(&lowered_bodies[1]), Some(0x105));
+    assert_eq!(marker_kind(&lowered_bodies[2]), Some(0x100));
+    assert_eq!(bodies(&raise(lowered, &[]).unwrap())[0], body);
+}
+
+#[test]
+fn encodes_leb128() {
+    let mut buf = Vec::new();
+    sleb(&mut buf, MAGIC);
+    assert_eq!(buf, [0xf0, 0xce, 0x89, 0xbb, 0x07]);
+    let mut buf = Vec::new();
+    sleb(&mut buf, 0x113);
+    assert_eq!(buf, [0x93, 0x02]);
+    assert_eq!(Reader { data: &buf }.u32(), Some(0x113));
+}
+
+#[test]
+fn passes_llvm_output_through() {
+    // See `tests/fixtures/passthrough.rs` for where this comes from.
+    let wasm = include_bytes!("../tests/fixtures/passthrough.wasm");
+    let lowered = lower(wasm, ALL).unwrap().unwrap().wasm;
+    let lowered_bodies = bodies(&lowered);
+    let kinds = lowered_bodies[5..]
+        .iter()
+        .map(|body| marker_kind(body).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, [RETURN_CALL, 0x105, 0x100, RETURN_CALL_INDIRECT]);
+
+    // LLVM pads the indices of calls so they can be relocated, which the
+    // instructions put back don't keep, but everything else is as it was.
+    let raised = raise(lowered, &[]).unwrap();
+    let raised_bodies = bodies(&raised);
+    assert_eq!(raised_bodies.len(), 9);
+    let expected: [&[u8]; 5] = [
+        b"\x00\x20\x01\x20\x00\x12\x00\x0b",
+        b"\x00\x20\x00\x20\x01\x20\x02\xfd\x85\x02\x0b",
+        b"\x00\x20\x00\x20\x01\xfd\x80\x02\x0b",
+        b"\x00\x20\x01\x20\x00\x12\x01\x0b",
+        b"\x00\x20\x00\x20\x01\x41\x00\x28\x02\x80\x80\xc0\x80\x00\x13\x00\x00\x0b",
+    ];
+    assert_eq!(raised_bodies[..5], expected);
+    assert_eq!(raised_bodies[5..], lowered_bodies[5..]);
+}
+
+#[test]
+fn fails_to_lower_unknown_instructions() {
+    // (func return_call 0 ...) followed by the reserved opcode 0x27.
+    let wasm = module(&[
+        (1, b"\x01\x60\x00\x00"),
+        (3, b"\x01\x00"),
+        (10, b"\x01\x05\x00\x12\x00\x27\x0b"),
+    ]);
+    let err = lower(&wasm, ALL).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "failed to pass instructions of function 0 through"
+    );
+    assert_eq!(
+        err.iter_causes().next().unwrap().to_string(),
+        "failed to read the instruction with opcode 0x27 at offset 3"
+    );
+}
+
+#[test]
+fn passes_llvm_exceptions_through() {
+    // See `tests/fixtures/exceptions.ll` for where this comes from.
+    let wasm = include_bytes!("../tests/fixtures/exceptions.wasm");
+    let lowered = lower(wasm, ALL).unwrap().unwrap();
+    let tag = Tag {
+        params: vec![0x7f],
+        import: None,
+        exports: vec!["__cpp_exception".to_string()],
+    };
+    assert_eq!(lowered.tags, [tag]);
+    assert!(!uses_tags(&lowered.wasm));
+    let lowered_bodies = bodies(&lowered.wasm);
+    let kinds = lowered_bodies[3..]
+        .iter()
+        .map(|body| marker_kind(body).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, [THROW, TRY, CATCH, CATCH_ALL, RETHROW]);
+
+    let raised = raise(lowered.wasm, &lowered.tags).unwrap();
+    assert!(uses_tags(&raised));
+    let expected: [&[u8]; 3] = [
+        b"\x00\x20\x00\x08\x00\x0b",
+        b"\x01\x01\x7f\x23\x80\x80\x80\x80\x00\x21\x01\x06\x40\x20\x00\x10\x80\x80\x80\x80\x00\
+          \x07\x00\x1a\x20\x01\x24\x80\x80\x80\x80\x00\x41\x01\x0f\x0b\x41\x00\x0b",
+        b"\x01\x01\x7f\x23\x80\x80\x80\x80\x00\x21\x01\x06\x40\x20\x00\x10\x80\x80\x80\x80\x00\
+          \x19\x20\x01\x24\x80\x80\x80\x80\x00\x10\x81\x80\x80\x80\x00\x09\x00\x0b\x0b",
+    ];
+    assert_eq!(bodies(&raised)[..3], expected);
+}
+
+#[test]
+fn defines_tags_imported_from_env() {
+    // (type (func (param i32)))
+    // (type (func))
+    // (import "env" "__cpp_exception" (tag (type 0)))
+    // (import "js" "error" (tag (type 0)))
+    // (func (type 1)
+    //   try i32.const 1 throw 1 delegate 0
+    //   i32.const 2 throw 0)
+    let wasm = module(&[
+        (1, b"\x02\x60\x01\x7f\x00\x60\x00\x00"),
+        (
+            2,
+            b"\x02\x03env\x0f__cpp_exception\x04\x00\x00\x02js\x05error\x04\x00\x00",
+        ),
+        (3, b"\x01\x01"),
+        (
+            10,
+            b"\x01\x0e\x00\x06\x40\x41\x01\x08\x01\x18\x00\x41\x02\x08\x00\x0b",
+        ),
+    ]);
+    let lowered = lower(&wasm, ALL).unwrap().unwrap();
+    // The tag which is still imported comes first.
+    let error = Tag {
+        params: vec![0x7f],
+        import: Some(("js".to_string(), "error".to_string())),
+        exports: Vec::new(),
+    };
+    let exception = Tag {
+        params: vec![0x7f],
+        import: None,
+        exports: vec!["__cpp_exception".to_string()],
+    };
+    assert_eq!(lowered.tags, [error, exception]);
+    let lowered_bodies = bodies(&lowered.wasm);
+    assert_eq!(
+        lowered_bodies[0],
+        b"\x00\x02\x40\x10\x01\x41\x01\x10\x02\x00\x10\x03\x0b\x41\x02\x10\x04\x00\x0b"
+    );
+    let kinds = lowered_bodies[1..]
+        .iter()
+        .map(|body| marker_kind(body).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, [TRY, THROW, DELEGATE, THROW + 1]);
+
+    let raised = raise(lowered.wasm, &lowered.tags).unwrap();
+    assert_eq!(
+        bodies(&raised)[0],
+        b"\x00\x06\x40\x41\x01\x08\x00\x18\x00\x41\x02\x08\x01\x0b"
+    );
+    let sections = sections(&raised).unwrap();
+    let ids = sections.iter().map(|s| s.0).collect::<Vec<_>>();
+    assert_eq!(ids, [1, 2, 3, 13, 7, 10]);
+    assert_eq!(sections[1].1, b"\x01\x02js\x05error\x04\x00\x00");
+    assert_eq!(sections[3].1, b"\x01\x00\x00");
+    assert_eq!(sections[4].1, b"\x01\x0f__cpp_exception\x04\x01");
+}
+
+#[test]
+fn rejects_exnref() {
+    // (func try_table end)
+    let wasm = module(&[
+        (1, b"\x01\x60\x00\x00"),
+        (3, b"\x01\x00"),
+        (10, b"\x01\x06\x00\x1f\x40\x00\x0b\x0b"),
+    ]);
+    let err = lower(&wasm, ALL).unwrap_err();
+    assert!(err
+        .iter_causes()
+        .any(|e| e.to_string().contains("`exnref`")));
+}
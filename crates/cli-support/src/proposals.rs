@@ -0,0 +1,248 @@
+//! Detection of WebAssembly proposals which `wasm-bindgen` can't process.
+//!
+//! Our wasm parser only understands a fixed set of proposals, and modules
+//! using anything else fail to parse with a fairly inscrutable error about an
+//! invalid byte at some offset. To give a more actionable error we take a
+//! quick look at the raw module before parsing it to see if it uses any
+//! proposal we know we don't support.
+//!
+//! Besides looking for the telltale signs of some proposals, LLVM records the
+//! target features a module was compiled with in the `target_features` custom
+//! section, which is how features that only affect instructions are found.
+//! LLVM lists features that are enabled even if they're never actually used,
+//! so `--wasm-features` can override the section's contents.
+//!
+//! Some proposals only add instructions, which are passed through to the
+//! output instead, see the `passthrough` module.
+
+use crate::passthrough;
+use failure::{bail, Error};
+
+/// Target features, as named by LLVM, that our parser can't handle.
+const UNSUPPORTED_FEATURES: &[(&str, &str)] = &[
+    ("gc", "GC"),
+    ("memory64", "memory64"),
+    ("multimemory", "multiple memories"),
+];
+
+/// Checks that `wasm` doesn't use any unsupported proposal, returning which of
+/// the proposals whose instructions are passed through it uses.
+///
+/// `features` is a list of target features like `+tail-call` or
+/// `-relaxed-simd` which override the features listed in the module, where a
+/// `+` means the feature is used even if the module doesn't say so.
+///
+/// This is best-effort: if anything looks off we stop looking and let the
+/// real parser report the problem.
+pub fn check(wasm: &[u8], features: &[String]) -> Result<passthrough::Features, Error> {
+    let mut enabled = Vec::new();
+    let mut disabled = Vec::new();
+    for feature in features {
+        if feature.starts_with('-') {
+            disabled.push(&feature[1..]);
+            continue;
+        }
+        let name = feature.trim_start_matches('+');
+        if let Some((_, proposal)) = UNSUPPORTED_FEATURES.iter().find(|f| f.0 == name) {
+            bail!(
+                "the {} proposal isn't supported by wasm-bindgen yet",
+                proposal
+            );
+        }
+        enabled.push(name);
+    }
+    let used = target_features(wasm).unwrap_or_default();
+    let found = find_unsupported(wasm).or_else(|| {
+        UNSUPPORTED_FEATURES
+            .iter()
+            .find(|(name, _)| used.contains(name) && !disabled.contains(name))
+            .map(|(_, proposal)| *proposal)
+    });
+    match found {
+        Some(proposal @ "GC") | Some(proposal @ "typed function references") => bail!(
+            "the input wasm file uses the {} proposal, which wasm-bindgen \
+             doesn't support yet; references to JS objects can be passed to \
+             wasm with `externref` through the experimental `WASM_BINDGEN_ANYREF` \
+             mode instead",
+            proposal
+        ),
+        Some(proposal) => bail!(
+            "the input wasm file uses the {} proposal, which wasm-bindgen \
+             doesn't support yet; try compiling without it, or if it's \
+             enabled but not actually used pass `--wasm-features` to \
+             disable it",
+            proposal
+        ),
+        None => {}
+    }
+    let uses =
+        |name| (used.contains(&name) || enabled.contains(&name)) && !disabled.contains(&name);
+    Ok(passthrough::Features {
+        tail_call: uses("tail-call"),
+        relaxed_simd: uses("relaxed-simd"),
+        exception_handling: uses("exception-handling") || passthrough::uses_tags(wasm),
+    })
+}
+
+/// Returns the name of the first unsupported proposal found in `wasm`.
+fn find_unsupported(wasm: &[u8]) -> Option<&'static str> {
+    let mut data = Reader { data: wasm };
+    if data.bytes(8)? != b"\0asm\x01\0\0\0" {
+        return None;
+    }
+    while !data.data.is_empty() {
+        let id = data.byte()?;
+        let len = data.u32()? as usize;
+        let mut section = Reader {
+            data: data.bytes(len)?,
+        };
+        match id {
+            1 => {
+                if let Some(p) = section.find_gc_types() {
+                    return Some(p);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the features listed as used in the `target_features` section.
+fn target_features(wasm: &[u8]) -> Option<Vec<&str>> {
+    let mut data = Reader { data: wasm };
+    data.bytes(8)?;
+    while !data.data.is_empty() {
+        let id = data.byte()?;
+        let len = data.u32()? as usize;
+        let mut section = Reader {
+            data: data.bytes(len)?,
+        };
+        if id != 0 || section.string()? != "target_features" {
+            continue;
+        }
+        let mut ret = Vec::new();
+        for _ in 0..section.u32()? {
+            let prefix = section.byte()?;
+            let name = section.string()?;
+            // `+` means used, `=` means required, and `-` means disallowed
+            if prefix == b'+' || prefix == b'=' {
+                ret.push(name);
+            }
+        }
+        return Some(ret);
+    }
+    None
+}
+
+pub struct Reader<'a> {
+    pub data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Scans the type section for types which aren't plain function types over
+    /// the value types we support.
+    ///
+    /// Unlike the proposals in `passthrough` these can't be passed through,
+    /// as they're used by functions, locals and globals which our parser has
+    /// to represent, so modules using them are rejected.
+    fn find_gc_types(&mut self) -> Option<&'static str> {
+        for _ in 0..self.u32()? {
+            match self.byte()? {
+                0x60 => {}
+                // `rec`, `sub`, `sub final`, `struct` and `array` types
+                0x4e | 0x50 | 0x4f | 0x5f | 0x5e => return Some("GC"),
+                _ => return None,
+            }
+            for _ in 0..2 {
+                for _ in 0..self.u32()? {
+                    match self.byte()? {
+                        // `i32`, `i64`, `f32`, `f64`, `v128`, `funcref` and
+                        // `externref`
+                        0x7f | 0x7e | 0x7d | 0x7c | 0x7b | 0x70 | 0x6f => {}
+                        // `(ref null ht)` and `(ref ht)`
+                        0x63 | 0x64 => return Some("typed function references"),
+                        // Abbreviations for GC reference types like
+                        // `anyref`, `eqref` and `structref`
+                        0x6e | 0x6d | 0x6c | 0x6b | 0x6a | 0x71 | 0x72 | 0x73 => return Some("GC"),
+                        _ => return None,
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn byte(&mut self) -> Option<u8> {
+        let (&a, rest) = self.data.split_first()?;
+        self.data = rest;
+        Some(a)
+    }
+
+    pub fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.data.len() < n {
+            return None;
+        }
+        let (a, b) = self.data.split_at(n);
+        self.data = b;
+        Some(a)
+    }
+
+    pub fn string(&mut self) -> Option<&'a str> {
+        let len = self.u32()? as usize;
+        std::str::from_utf8(self.bytes(len)?).ok()
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        let mut ret = 0;
+        for i in 0..5 {
+            let byte = self.byte()?;
+            ret |= u32::from(byte & 0x7f) << (i * 7);
+            if byte & 0x80 == 0 {
+                return Some(ret);
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn detects_unsupported_proposals() {
+    // (type (func (param i32)))
+    let func = b"\0asm\x01\0\0\0\x01\x05\x01\x60\x01\x7f\x00";
+    assert_eq!(find_unsupported(func), None);
+    // (type (struct))
+    let gc = b"\0asm\x01\0\0\0\x01\x03\x01\x5f\x00";
+    assert_eq!(find_unsupported(gc), Some("GC"));
+    // (type (func (param (ref null 0))))
+    let typed = b"\0asm\x01\0\0\0\x01\x06\x01\x60\x01\x63\x00\x00";
+    assert_eq!(find_unsupported(typed), Some("typed function references"));
+    // (type (func)) (tag (type 0))
+    let eh = b"\0asm\x01\0\0\0\x01\x04\x01\x60\x00\x00\x0d\x03\x01\x00\x00";
+    assert_eq!(find_unsupported(eh), None);
+    assert!(check(eh, &[]).unwrap().exception_handling);
+    assert!(!check(func, &[]).unwrap().exception_handling);
+}
+
+#[test]
+fn reads_target_features() {
+    let mut wasm = b"\0asm\x01\0\0\0\x00\x29\x0ftarget_features\x02".to_vec();
+    wasm.extend_from_slice(b"+\x09tail-call");
+    wasm.extend_from_slice(b"-\x0bbulk-memory");
+    assert_eq!(target_features(&wasm), Some(vec!["tail-call"]));
+    assert!(check(&wasm, &[]).unwrap().tail_call);
+    assert!(!check(&wasm, &["-tail-call".to_string()]).unwrap().tail_call);
+    assert!(!check(&wasm, &[]).unwrap().relaxed_simd);
+    assert!(
+        check(&wasm, &["+relaxed-simd".to_string()])
+            .unwrap()
+            .relaxed_simd
+    );
+    assert!(check(&wasm, &["+gc".to_string()]).is_err());
+
+    let mut wasm = b"\0asm\x01\0\0\0\x00\x25\x0ftarget_features\x01".to_vec();
+    wasm.extend_from_slice(b"+\x12exception-handling");
+    assert!(check(&wasm, &[]).unwrap().exception_handling);
+    let disabled = check(&wasm, &["-exception-handling".to_string()]).unwrap();
+    assert!(!disabled.exception_handling);
+}
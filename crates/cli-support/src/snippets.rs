@@ -0,0 +1,169 @@
+//! TypeScript declarations of JS snippets.
+//!
+//! Snippets are the JS files of `#[wasm_bindgen(inline_js)]` and of
+//! `#[wasm_bindgen(module = "/...")]` imports, which are copied next to the
+//! generated JS. Their contents are arbitrary JS, so what they export is
+//! instead declared from how they're imported, with a `.d.ts` file next to
+//! each of them.
+
+use std::collections::BTreeMap;
+
+/// What a snippet is known to export, collected from its imports.
+#[derive(Default)]
+pub struct Declarations {
+    /// Declarations of exported functions and values, keyed by their names.
+    items: BTreeMap<String, String>,
+    /// Members of exported classes, keyed by the names of the classes and
+    /// then by the names of the members.
+    classes: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Declarations {
+    /// Declares an exported function.
+    pub fn function(&mut self, name: &str, args: &[String], ret: &str) {
+        let decl = format!(
+            "export function {}({}): {};\n",
+            name,
+            signature_args(args),
+            ret
+        );
+        self.items.entry(name.to_string()).or_insert(decl);
+    }
+
+    /// Declares an exported value which isn't known more precisely, like a
+    /// static or a namespace.
+    pub fn value(&mut self, name: &str) {
+        let decl = format!("export const {}: any;\n", name);
+        self.items.entry(name.to_string()).or_insert(decl);
+    }
+
+    /// Declares an exported class, whose members may be declared later on.
+    pub fn class(&mut self, name: &str) {
+        self.classes.entry(name.to_string()).or_default();
+    }
+
+    /// Declares the constructor of an exported class.
+    pub fn constructor(&mut self, class: &str, args: &[String]) {
+        let decl = format!("constructor({});\n", signature_args(args));
+        self.member(class, "constructor", decl);
+    }
+
+    /// Declares a method of an exported class.
+    pub fn method(&mut self, class: &str, is_static: bool, name: &str, args: &[String], ret: &str) {
+        let decl = format!(
+            "{}{}({}): {};\n",
+            if is_static { "static " } else { "" },
+            name,
+            signature_args(args),
+            ret
+        );
+        let key = if is_static {
+            format!("static {}", name)
+        } else {
+            name.to_string()
+        };
+        self.member(class, &key, decl);
+    }
+
+    /// Declares a property of an exported class, as read by a getter or
+    /// written by a setter.
+    pub fn property(&mut self, class: &str, is_static: bool, name: &str, ty: &str) {
+        let decl = format!(
+            "{}{}: {};\n",
+            if is_static { "static " } else { "" },
+            name,
+            ty
+        );
+        let key = if is_static {
+            format!("static {}", name)
+        } else {
+            name.to_string()
+        };
+        self.member(class, &key, decl);
+    }
+
+    fn member(&mut self, class: &str, key: &str, decl: String) {
+        self.classes
+            .entry(class.to_string())
+            .or_default()
+            .entry(key.to_string())
+            .or_insert(decl);
+    }
+
+    /// Generates the contents of the `.d.ts` file of the snippet.
+    pub fn to_ts(&self) -> String {
+        let mut ts = format!("/* tslint:disable */\n");
+        for decl in self.items.values() {
+            ts.push_str(decl);
+        }
+        for (name, members) in self.classes.iter() {
+            ts.push_str(&format!("export class {} {{\n", name));
+            for decl in members.values() {
+                ts.push_str("  ");
+                ts.push_str(decl);
+            }
+            ts.push_str("}\n");
+        }
+        ts
+    }
+}
+
+fn signature_args(args: &[String]) -> String {
+    args.iter()
+        .enumerate()
+        .map(|(i, ty)| format!("arg{}: {}", i, ty))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[test]
+fn declares_nothing() {
+    let decls = Declarations::default();
+    assert_eq!(decls.to_ts(), "/* tslint:disable */\n");
+}
+
+#[test]
+fn declares_functions_and_values() {
+    let mut decls = Declarations::default();
+    decls.function(
+        "add",
+        &["number".to_string(), "number".to_string()],
+        "number",
+    );
+    decls.function("log", &[], "void");
+    decls.value("config");
+    // Importing the same function twice only declares it once.
+    decls.function("log", &[], "void");
+    assert_eq!(
+        decls.to_ts(),
+        "/* tslint:disable */\n\
+         export function add(arg0: number, arg1: number): number;\n\
+         export const config: any;\n\
+         export function log(): void;\n"
+    );
+}
+
+#[test]
+fn declares_classes() {
+    let mut decls = Declarations::default();
+    decls.class("Empty");
+    decls.class("Counter");
+    decls.constructor("Counter", &["number".to_string()]);
+    decls.method("Counter", false, "increment", &[], "void");
+    decls.method("Counter", true, "zero", &[], "Counter");
+    decls.property("Counter", false, "count", "number");
+    // A setter of the same property doesn't declare it again.
+    decls.property("Counter", false, "count", "number");
+    assert_eq!(
+        decls.to_ts(),
+        "/* tslint:disable */\n\
+         export class Counter {\n  \
+         constructor(arg0: number);\n  \
+         count: number;\n  \
+         increment(): void;\n  \
+         static zero(): Counter;\n\
+         }\n\
+         export class Empty {\n\
+         }\n"
+    );
+}
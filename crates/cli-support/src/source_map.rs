@@ -0,0 +1,163 @@
+//! Source maps for the generated JS glue.
+//!
+//! There's no original JS that the glue was written from, so instead the map
+//! relates each top-level function and class of the glue to a source of its
+//! own, named after the export or import it implements. Devtools then show
+//! frames of the glue as `wasm-bindgen:///<module>/greet.js` for an export
+//! `greet`, or `wasm-bindgen:///<module>/imports/Date.now.js` for the shim of
+//! an imported `Date.now`, rather than as a line somewhere in a large file.
+//!
+//! Each of those sources contains the code of its function or class, so the
+//! lines of the glue map one to one onto the lines of a source. Everything
+//! else, like the glue's own imports and initialization, isn't mapped.
+
+use std::collections::HashMap;
+
+use serde_json::json;
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Generates the source map of the formatted glue `js`, which is written to
+/// `file`.
+///
+/// `labels` maps names of shims in the glue to what they're a shim of, for
+/// shims whose name is generated.
+pub fn generate(js: &str, file: &str, module: &str, labels: &HashMap<String, String>) -> String {
+    let mut sources = Vec::new();
+    let mut contents = Vec::<String>::new();
+    let mut used = HashMap::new();
+    // The source each line of the glue maps to, if any, and its line in
+    // there.
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+
+    // The glue of `--no-modules` is all wrapped in a function, so whatever's
+    // directly in there is what counts as the top level.
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let base = js
+        .lines()
+        .filter(|l| declared_name(l.trim_start()).is_some())
+        .map(indent)
+        .min()
+        .unwrap_or(0);
+
+    for line in js.lines() {
+        let toplevel = !line.trim().is_empty() && indent(line) <= base;
+        if toplevel && !line.trim_start().starts_with(|c| "})]".contains(c)) {
+            current = declared_name(line.trim_start()).map(|name| {
+                let label = labels.get(name).map(|s| &s[..]).unwrap_or(name);
+                let count = used.entry(label.to_string()).or_insert(0);
+                *count += 1;
+                let path = if *count == 1 {
+                    format!("wasm-bindgen:///{}/{}.js", module, label)
+                } else {
+                    format!("wasm-bindgen:///{}/{}{}.js", module, label, count)
+                };
+                sources.push(path);
+                contents.push(String::new());
+                (sources.len() - 1, 0)
+            });
+        }
+        match &mut current {
+            Some((source, source_line)) => {
+                contents[*source].push_str(line);
+                contents[*source].push_str("\n");
+                lines.push(Some((*source, *source_line)));
+                *source_line += 1;
+            }
+            None => lines.push(None),
+        }
+    }
+
+    let map = json!({
+        "version": 3,
+        "file": file,
+        "sources": sources,
+        "sourcesContent": contents,
+        "names": [],
+        "mappings": mappings(&lines),
+    });
+    serde_json::to_string(&map).unwrap()
+}
+
+/// Returns the name declared by a top-level `line` of the glue, if it starts
+/// a function or class.
+fn declared_name(line: &str) -> Option<&str> {
+    let mut rest = line;
+    for prefix in &["export ", "module.exports.", "__exports."] {
+        if rest.starts_with(prefix) {
+            rest = &rest[prefix.len()..];
+        }
+    }
+    for keyword in &["async function ", "function ", "class "] {
+        if rest.starts_with(keyword) {
+            return ident(&rest[keyword.len()..]);
+        }
+    }
+    // `module.exports.foo = function(...)` and `export const foo = ...`
+    let rest = if rest.starts_with("const ") {
+        &rest[6..]
+    } else if line.starts_with("export ") || rest.len() == line.len() {
+        return None;
+    } else {
+        rest
+    };
+    let name = ident(rest)?;
+    let value = rest[name.len()..].trim_start();
+    if !value.starts_with('=') {
+        return None;
+    }
+    // `module.exports.Foo = Foo;` exports a class declared right before it.
+    if value[1..].trim() == format!("{};", name) {
+        return None;
+    }
+    Some(name)
+}
+
+fn ident(s: &str) -> Option<&str> {
+    let end = s
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .unwrap_or(s.len());
+    if end == 0 {
+        None
+    } else {
+        Some(&s[..end])
+    }
+}
+
+/// Encodes the `mappings` of a source map where every line of the glue maps
+/// from its start to the start of `lines[i]`, if it's mapped.
+fn mappings(lines: &[Option<(usize, usize)>]) -> String {
+    let mut dst = String::new();
+    // Source indices and lines are encoded relative to the previous segment.
+    let mut prev = (0, 0);
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            dst.push(';');
+        }
+        if let Some((source, source_line)) = *line {
+            vlq(&mut dst, 0);
+            vlq(&mut dst, source as i64 - prev.0 as i64);
+            vlq(&mut dst, source_line as i64 - prev.1 as i64);
+            vlq(&mut dst, 0);
+            prev = (source, source_line);
+        }
+    }
+    dst
+}
+
+/// Appends `n` encoded as a base64 VLQ.
+fn vlq(dst: &mut String, n: i64) {
+    let mut n = if n < 0 { ((-n) << 1) | 1 } else { n << 1 };
+    loop {
+        let mut digit = n & 0b11111;
+        n >>= 5;
+        if n > 0 {
+            digit |= 0b100000;
+        }
+        dst.push(BASE64[digit as usize] as char);
+        if n == 0 {
+            break;
+        }
+    }
+}
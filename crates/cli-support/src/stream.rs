@@ -0,0 +1,302 @@
+//! Generating bindings for huge modules with bounded memory, see
+//! `Bindgen::low_memory`.
+//!
+//! Most of a large debug build is DWARF and data, neither of which
+//! wasm-bindgen needs to look at. Rather than reading the input, it's mapped
+//! into memory and only a copy of its other sections is parsed. The data and
+//! debug sections are left where they are and written straight from the
+//! mapping into the output files, after the rest of the module has been
+//! emitted. The code section still has to be parsed like everything else, as
+//! our passes transform it.
+
+use crate::proposals::Reader;
+use failure::{bail, Error};
+use memmap::Mmap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+
+/// The sections of an input module which were set aside before parsing it.
+pub struct Input {
+    map: Rc<Mmap>,
+    data: Option<Range<usize>>,
+    debug: Vec<Range<usize>>,
+}
+
+/// A file made of generated bytes and sections of the input, which are only
+/// put together as it's written.
+pub struct Spliced {
+    pieces: Vec<Piece>,
+}
+
+enum Piece {
+    Bytes(Vec<u8>),
+    Input(Rc<Mmap>, Range<usize>),
+}
+
+/// Maps the file at `path` into memory.
+pub fn map(path: &Path) -> Result<Mmap, Error> {
+    let file = File::open(path)?;
+    // This is only unsafe if the file is modified while it's mapped, in which
+    // case the output would be garbage regardless.
+    Ok(unsafe { Mmap::map(&file)? })
+}
+
+impl Input {
+    /// Maps the module at `path` into memory, returning its sections besides
+    /// the ones set aside, which is what should be parsed.
+    ///
+    /// The data section is only set aside if `move_data` is true and nothing
+    /// refers to its segments, so that none of our passes could need them.
+    pub fn map(path: &Path, move_data: bool) -> Result<(Input, Vec<u8>), Error> {
+        let map = map(path)?;
+        let mut input = Input {
+            map: Rc::new(map),
+            data: None,
+            debug: Vec::new(),
+        };
+        let rest = match split(&input.map, move_data) {
+            Some((kept, data, debug)) => {
+                input.data = data;
+                input.debug = debug;
+                let mut rest = input.map[..8].to_vec();
+                for range in kept {
+                    rest.extend_from_slice(&input.map[range]);
+                }
+                rest
+            }
+            // Leave it to the parser to report what's wrong with the module.
+            None => input.map.to_vec(),
+        };
+        Ok((input, rest))
+    }
+
+    /// Puts the data section set aside back into the emitted module `wasm`,
+    /// along with the debug sections if `keep_debug` is true.
+    pub fn splice(&self, mut wasm: Vec<u8>, keep_debug: bool) -> Result<Spliced, Error> {
+        // The data section goes after every other known section, but before
+        // custom sections like the name section which follow them.
+        let mut at = 8;
+        let mut data = Reader { data: &wasm[8..] };
+        while !data.data.is_empty() {
+            let (id, len) = match (data.byte(), data.u32()) {
+                (Some(id), Some(len)) => (id, len as usize),
+                _ => bail!("failed to read the sections of the emitted module"),
+            };
+            if data.bytes(len).is_none() {
+                bail!("failed to read the sections of the emitted module");
+            }
+            if id == 11 && self.data.is_some() {
+                bail!("the emitted module unexpectedly has data of its own");
+            }
+            if id != 0 {
+                at = wasm.len() - data.data.len();
+            }
+        }
+        let tail = wasm.split_off(at);
+        let mut pieces = vec![Piece::Bytes(wasm)];
+        if let Some(range) = &self.data {
+            pieces.push(Piece::Input(self.map.clone(), range.clone()));
+        }
+        pieces.push(Piece::Bytes(tail));
+        if keep_debug {
+            pieces.extend(self.debug_pieces());
+        }
+        Ok(Spliced { pieces })
+    }
+
+    /// Puts the debug sections set aside into the emitted module `wasm`, as
+    /// the contents of a separate debug info file.
+    pub fn debuginfo(&self, wasm: Vec<u8>) -> Spliced {
+        let mut pieces = vec![Piece::Bytes(wasm)];
+        pieces.extend(self.debug_pieces());
+        Spliced { pieces }
+    }
+
+    fn debug_pieces<'a>(&'a self) -> impl Iterator<Item = Piece> + 'a {
+        self.debug
+            .iter()
+            .map(move |range| Piece::Input(self.map.clone(), range.clone()))
+    }
+}
+
+/// Returns the ranges of the sections of `wasm` which should be parsed, of
+/// its data section if it should be set aside, and of its debug sections.
+fn split(
+    wasm: &[u8],
+    move_data: bool,
+) -> Option<(Vec<Range<usize>>, Option<Range<usize>>, Vec<Range<usize>>)> {
+    let mut data = Reader { data: wasm };
+    if data.bytes(8)? != b"\0asm\x01\0\0\0" {
+        return None;
+    }
+    let mut kept = Vec::new();
+    let mut data_section = None;
+    let mut debug = Vec::new();
+    let mut data_count = false;
+    while !data.data.is_empty() {
+        let start = wasm.len() - data.data.len();
+        let id = data.byte()?;
+        let len = data.u32()? as usize;
+        let payload = data.bytes(len)?;
+        let range = start..wasm.len() - data.data.len();
+        match id {
+            0 if Reader { data: payload }.string()?.starts_with(".debug_") => {
+                debug.push(range);
+            }
+            // Segments can only be referred to by index if there's a data
+            // count section.
+            12 => {
+                data_count = true;
+                kept.push(range);
+            }
+            11 if move_data && !data_count && only_constant_offsets(payload) => {
+                data_section = Some(range);
+            }
+            _ => kept.push(range),
+        }
+    }
+    Some((kept, data_section, debug))
+}
+
+/// Returns whether all segments of the data section `payload` are active
+/// segments of the first memory at constant offsets.
+///
+/// Segments at the offset of a global keep the global alive, so taking them
+/// out of the module could have it removed.
+fn only_constant_offsets(payload: &[u8]) -> bool {
+    let mut data = Reader { data: payload };
+    let count = match data.u32() {
+        Some(count) => count,
+        None => return false,
+    };
+    for _ in 0..count {
+        // `i32.const` takes a signed LEB, which can be skipped like an
+        // unsigned one.
+        let segment = (|| {
+            if data.u32()? != 0 || data.byte()? != 0x41 {
+                return None;
+            }
+            data.u32()?;
+            if data.byte()? != 0x0b {
+                return None;
+            }
+            let len = data.u32()? as usize;
+            data.bytes(len)
+        })();
+        if segment.is_none() {
+            return false;
+        }
+    }
+    data.data.is_empty()
+}
+
+impl Spliced {
+    /// Returns the contents of the file, piece by piece.
+    pub fn chunks(&self) -> impl Iterator<Item = &[u8]> {
+        self.pieces.iter().map(|piece| match piece {
+            Piece::Bytes(bytes) => &bytes[..],
+            Piece::Input(map, range) => &map[range.clone()],
+        })
+    }
+
+    pub fn write_to(&self, dst: &mut impl Write) -> io::Result<()> {
+        for chunk in self.chunks() {
+            dst.write_all(chunk)?;
+        }
+        Ok(())
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut ret = Vec::new();
+        for chunk in self.chunks() {
+            ret.extend_from_slice(chunk);
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+fn section(id: u8, payload: &[u8]) -> Vec<u8> {
+    let mut ret = vec![id, payload.len() as u8];
+    ret.extend_from_slice(payload);
+    ret
+}
+
+#[cfg(test)]
+fn custom(name: &str, contents: &[u8]) -> Vec<u8> {
+    let mut payload = vec![name.len() as u8];
+    payload.extend_from_slice(name.as_bytes());
+    payload.extend_from_slice(contents);
+    section(0, &payload)
+}
+
+#[cfg(test)]
+fn map_module(sections: &[Vec<u8>], move_data: bool) -> (Input, Vec<u8>) {
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    for section in sections {
+        wasm.extend_from_slice(section);
+    }
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(file.path(), &wasm).unwrap();
+    Input::map(file.path(), move_data).unwrap()
+}
+
+#[test]
+fn sets_aside_data_and_debug_info() {
+    let code = section(10, &[1, 2, 0, 0x0b]);
+    // One segment at offset -1, which takes a two byte LEB.
+    let data = section(11, &[1, 0, 0x41, 0x7f, 0x0b, 2, 0xaa, 0xbb]);
+    let info = custom(".debug_info", &[1, 2, 3]);
+    let name = custom("name", &[4]);
+    let (input, rest) = map_module(
+        &[code.clone(), data.clone(), info.clone(), name.clone()],
+        true,
+    );
+    assert_eq!(rest, [&b"\0asm\x01\0\0\0"[..], &code, &name].concat());
+
+    // The emitted module has a type section ahead of the code section now.
+    let types = section(1, &[0]);
+    let emitted = [&b"\0asm\x01\0\0\0"[..], &types, &code, &name].concat();
+    let spliced = input.splice(emitted.clone(), true).unwrap();
+    let expected = [&b"\0asm\x01\0\0\0"[..], &types, &code, &data, &name, &info].concat();
+    assert_eq!(spliced.to_vec(), expected);
+    let spliced = input.splice(emitted.clone(), false).unwrap();
+    let expected = [&b"\0asm\x01\0\0\0"[..], &types, &code, &data, &name].concat();
+    assert_eq!(spliced.to_vec(), expected);
+
+    let debug = input.debuginfo(b"\0asm\x01\0\0\0".to_vec());
+    assert_eq!(debug.to_vec(), [&b"\0asm\x01\0\0\0"[..], &info].concat());
+
+    // Data the emitted module has itself would be clobbered.
+    let emitted = [&b"\0asm\x01\0\0\0"[..], &code, &data].concat();
+    assert!(input.splice(emitted, false).is_err());
+}
+
+#[test]
+fn keeps_data_which_could_be_referred_to() {
+    let code = section(10, &[1, 2, 0, 0x0b]);
+    let data = section(11, &[1, 0, 0x41, 0, 0x0b, 1, 0xaa]);
+    let (_, rest) = map_module(&[code.clone(), data.clone()], false);
+    assert_eq!(rest, [&b"\0asm\x01\0\0\0"[..], &code, &data].concat());
+
+    // Segments are passive or at the offset of a global, or there's a data
+    // count section for instructions referring to them.
+    for data in [
+        section(11, &[1, 1, 1, 0xaa]),
+        section(11, &[1, 0, 0x23, 0, 0x0b, 1, 0xaa]),
+    ]
+    .iter()
+    {
+        let (_, rest) = map_module(&[code.clone(), data.clone()], true);
+        assert_eq!(rest, [&b"\0asm\x01\0\0\0"[..], &code, data].concat());
+    }
+    let count = section(12, &[1]);
+    let (_, rest) = map_module(&[count.clone(), code.clone(), data.clone()], true);
+    assert_eq!(
+        rest,
+        [&b"\0asm\x01\0\0\0"[..], &count, &code, &data].concat()
+    );
+}
@@ -0,0 +1,49 @@
+; The source of `exceptions.wasm`, which the tests of `passthrough.rs` use to
+; check that LLVM's output for exception handling makes it through unchanged:
+;
+;     llc -O2 -filetype=obj -mattr=+exception-handling -exception-model=wasm \
+;         -wasm-enable-eh exceptions.ll -o exceptions.o
+;     wasm-ld --no-entry --allow-undefined --export=thrower --export=catcher \
+;         --export=cleaner --export=__cpp_exception exceptions.o -o exceptions.wasm
+
+target datalayout = "e-m:e-p:32:32-p10:8:8-p20:8:8-i64:64-n32:64-S128-ni:1:10:20"
+target triple = "wasm32-unknown-unknown"
+
+declare void @llvm.wasm.throw(i32, i8*)
+declare i32 @__gxx_wasm_personality_v0(...)
+declare i8* @llvm.wasm.get.exception(token)
+declare i32 @llvm.wasm.get.ehselector(token)
+declare void @may_throw(i32)
+declare void @cleanup()
+
+define void @thrower(i8* %p) {
+  call void @llvm.wasm.throw(i32 0, i8* %p)
+  unreachable
+}
+
+define i32 @catcher(i32 %x) personality i32 (...)* @__gxx_wasm_personality_v0 {
+entry:
+  invoke void @may_throw(i32 %x) to label %ok unwind label %dispatch
+ok:
+  ret i32 0
+dispatch:
+  %cs = catchswitch within none [label %handler] unwind to caller
+handler:
+  %cp = catchpad within %cs [i8* null]
+  %e = call i8* @llvm.wasm.get.exception(token %cp)
+  %s = call i32 @llvm.wasm.get.ehselector(token %cp)
+  catchret from %cp to label %done
+done:
+  ret i32 1
+}
+
+define void @cleaner(i32 %x) personality i32 (...)* @__gxx_wasm_personality_v0 {
+entry:
+  invoke void @may_throw(i32 %x) to label %ok unwind label %cleanup
+ok:
+  ret void
+cleanup:
+  %c = cleanuppad within none []
+  call void @cleanup() [ "funclet"(token %c) ]
+  cleanupret from %c unwind to caller
+}
@@ -0,0 +1,80 @@
+// The source of `passthrough.wasm`, which the tests of `passthrough.rs` use
+// to check that LLVM's output for tail calls and relaxed SIMD makes it
+// through unchanged. It doesn't use `core` so that it can be rebuilt with
+// any nightly without a wasm target installed for the standard library:
+//
+//     rustc +nightly --target wasm32-unknown-unknown -C opt-level=2 \
+//         -C target-feature=+simd128,+relaxed-simd,+tail-call \
+//         -C panic=abort -C link-arg=--allow-undefined \
+//         passthrough.rs -o passthrough.wasm
+
+#![feature(no_core, lang_items, repr_simd, link_llvm_intrinsics, simd_ffi, abi_unadjusted, explicit_tail_calls)]
+#![no_core]
+#![crate_type = "cdylib"]
+#![allow(internal_features, improper_ctypes, incomplete_features)]
+
+#[lang = "pointee_sized"]
+pub trait PointeeSized {}
+#[lang = "meta_sized"]
+pub trait MetaSized: PointeeSized {}
+#[lang = "sized"]
+pub trait Sized: MetaSized {}
+#[lang = "copy"]
+pub trait Copy {}
+impl Copy for f32 {}
+#[lang = "drop_glue"]
+fn drop_glue<T: ?Sized>(_: *mut T) {}
+#[lang = "drop"]
+pub trait Drop {}
+#[lang = "destruct"]
+pub trait Destruct {}
+impl Copy for i8 {}
+impl Copy for u32 {}
+impl<T: Copy, const N: usize> Copy for [T; N] {}
+
+#[repr(simd)]
+pub struct F32x4([f32; 4]);
+impl Copy for F32x4 {}
+#[repr(simd)]
+pub struct I8x16([i8; 16]);
+impl Copy for I8x16 {}
+
+extern "unadjusted" {
+    #[link_name = "llvm.wasm.relaxed.madd.v4f32"]
+    fn madd(a: F32x4, b: F32x4, c: F32x4) -> F32x4;
+    #[link_name = "llvm.wasm.relaxed.swizzle"]
+    fn swizzle(a: I8x16, s: I8x16) -> I8x16;
+}
+
+#[no_mangle]
+pub extern "C" fn relaxed_madd(a: F32x4, b: F32x4, c: F32x4) -> F32x4 {
+    unsafe { madd(a, b, c) }
+}
+
+#[no_mangle]
+pub extern "C" fn relaxed_swizzle(a: I8x16, s: I8x16) -> I8x16 {
+    unsafe { swizzle(a, s) }
+}
+
+extern "C" {
+    fn step(a: u32, b: u32) -> u32;
+}
+
+#[inline(never)]
+#[no_mangle]
+pub extern "C" fn forward(a: u32, b: u32) -> u32 {
+    unsafe { step(b, a) }
+}
+
+#[no_mangle]
+pub extern "C" fn tail(a: u32, b: u32) -> u32 {
+    become forward(b, a)
+}
+
+#[no_mangle]
+pub static mut CALLBACK: extern "C" fn(u32, u32) -> u32 = forward;
+
+#[no_mangle]
+pub extern "C" fn tail_indirect(a: u32, b: u32) -> u32 {
+    become unsafe { CALLBACK }(a, b)
+}
@@ -0,0 +1,94 @@
+//! Support for collecting code coverage of test runs.
+//!
+//! When a test binary is compiled with `-C instrument-coverage` and the
+//! `coverage` feature of `wasm-bindgen-test`, the test harness captures the
+//! raw profile of the run once all tests have finished and hands it to us,
+//! and we write it to a `.profraw` file for `llvm-profdata`, `llvm-cov` or
+//! `grcov` to process. In node.js this is done directly through the `fs`
+//! module, whereas in browsers the profile is posted to the test server.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use failure::{Error, ResultExt};
+
+#[derive(Clone)]
+pub struct Coverage {
+    dir: PathBuf,
+    stem: String,
+}
+
+impl Coverage {
+    /// Configures where the coverage of the tests in `wasm_file` goes.
+    ///
+    /// Profiles are written to `WASM_BINDGEN_TEST_COVERAGE_DIR`, or
+    /// `wbg-coverage` next to our temporary directory by default, and are
+    /// named after the test binary. When tests are sharded with `--jobs` each
+    /// shard writes its own profile, which `llvm-profdata merge` combines.
+    pub fn new(wasm_file: &Path, tmpdir: &Path) -> Coverage {
+        let stem = wasm_file
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let dir = match env::var_os("WASM_BINDGEN_TEST_COVERAGE_DIR") {
+            Some(dir) => env::current_dir().unwrap().join(dir),
+            None => tmpdir.with_file_name("wbg-coverage"),
+        };
+        let coverage = Coverage { dir, stem };
+        drop(fs::remove_file(coverage.path(None)));
+        coverage
+    }
+
+    /// Returns the file the profile of `shard` is written to.
+    pub fn path(&self, shard: Option<u32>) -> PathBuf {
+        match shard {
+            Some(shard) => self.dir.join(format!("{}-{}.profraw", self.stem, shard)),
+            None => self.dir.join(format!("{}.profraw", self.stem)),
+        }
+    }
+
+    /// Returns JS which defines the `__wbgtest_coverage_write` global for
+    /// node.js
+    pub fn node_js(&self) -> String {
+        format!(
+            r#"
+            global.__wbgtest_coverage_write = data => {{
+                const fs = require('fs');
+                const path = require('path');
+                const shard = process.env.WASM_BINDGEN_TEST_SHARD;
+                const name = shard === undefined ? {stem} : `${{{stem}}}-${{shard}}`;
+                const dir = {dir};
+                fs.mkdirSync(dir, {{ recursive: true }});
+                fs.writeFileSync(path.join(dir, `${{name}}.profraw`), data);
+            }};
+            "#,
+            stem = serde_json::to_string(&self.stem).unwrap(),
+            dir = serde_json::to_string(&self.dir.to_string_lossy()).unwrap(),
+        )
+    }
+
+    /// Returns JS which defines the `__wbgtest_coverage_write` global in a
+    /// browser.
+    ///
+    /// The profile is a view of the wasm memory, so it's copied before being
+    /// sent as shared memory can't be.
+    pub fn browser_js(&self) -> String {
+        r#"
+        self.__wbgtest_coverage_write = data => {
+            const shard = new URLSearchParams(location.search).get('shard');
+            __wbgtest_post(shard === null ? '/__wbgtest_coverage' : `/__wbgtest_coverage?shard=${shard}`, data.slice());
+        };
+        "#
+        .to_string()
+    }
+
+    /// Writes the profile of `shard` sent to us by a browser.
+    pub fn write(&self, shard: Option<u32>, data: &[u8]) -> Result<(), Error> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|_| format!("failed to create `{}`", self.dir.display()))?;
+        let path = self.path(shard);
+        fs::write(&path, data).with_context(|_| format!("failed to write `{}`", path.display()))?;
+        Ok(())
+    }
+}
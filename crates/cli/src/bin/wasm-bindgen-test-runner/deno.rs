@@ -0,0 +1,159 @@
+//! Support for executing tests in Deno, for suites configured with
+//! `run_in_deno`.
+//!
+//! Deno can't import the bindings generated for node.js, so they're generated
+//! with `--no-modules` instead and the test script, an ES module, instantiates
+//! them itself. The harness features of the node.js backend are written
+//! against node's `fs` and `process` modules, which the script provides
+//! through Deno's node.js compatibility.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use failure::{Error, ResultExt};
+
+use crate::coverage::Coverage;
+use crate::jobs;
+use crate::profiles::Profiles;
+use crate::report::Report;
+use crate::snapshot::Snapshots;
+
+pub fn execute(
+    module: &str,
+    tmpdir: &Path,
+    args: &[OsString],
+    tests: &[String],
+    snapshots: &Snapshots,
+    profiles: &Profiles,
+    coverage: &Coverage,
+    report: Option<&Report>,
+    prelude: &str,
+    jobs: u32,
+) -> Result<(), Error> {
+    let mut js_to_execute = format!(
+        r#"
+        import {{ createRequire }} from 'node:module';
+        import process from 'node:process';
+
+        globalThis.global = globalThis;
+        globalThis.process = process;
+        globalThis.require = createRequire(import.meta.url);
+
+        const handlers = {{}};
+
+        const wrap = method => {{
+            const og = console[method];
+            const on_method = `on_console_${{method}}`;
+            console[method] = function (...args) {{
+                if (handlers[on_method]) {{
+                    handlers[on_method](args);
+                }}
+                og.apply(this, args);
+            }};
+        }};
+
+        // override `console.log` and `console.error` etc... before we import
+        // tests to ensure they're bound correctly in wasm.
+        wrap("debug");
+        wrap("log");
+        wrap("info");
+        wrap("warn");
+        wrap("error");
+
+        global.__wbg_test_invoke = f => f();
+
+        {prelude}
+
+        async function main(tests) {{
+            await import('./{module}.js');
+            const bytes = Deno.readFileSync(new URL('./{module}_bg.wasm', import.meta.url));
+            const support = wasm_bindgen.initSync(bytes);
+            const wasm = support.wasm;
+
+            const cx = new support.WasmBindgenTestContext();
+            handlers.on_console_debug = support.__wbgtest_console_debug;
+            handlers.on_console_log = support.__wbgtest_console_log;
+            handlers.on_console_info = support.__wbgtest_console_info;
+            handlers.on_console_warn = support.__wbgtest_console_warn;
+            handlers.on_console_error = support.__wbgtest_console_error;
+            global.__wbgtest_boundary_stats = support.__wbg_boundary_stats;
+
+            // Forward runtime arguments, which are basically only used for
+            // test filters for now.
+            cx.args(Deno.args);
+
+            const ok = await cx.run(tests.map(n => wasm[n]));
+            if (!ok)
+                Deno.exit(1);
+        }}
+
+        const tests = [];
+    "#,
+        prelude = prelude,
+        module = module,
+    );
+    js_to_execute.push_str(&snapshots.node_js());
+    js_to_execute.push_str(&profiles.node_js());
+    js_to_execute.push_str(&coverage.node_js());
+    if let Some(report) = report {
+        js_to_execute.push_str(&report.node_js());
+    }
+
+    for test in tests {
+        js_to_execute.push_str(&format!("tests.push('{}')\n", test));
+    }
+    js_to_execute.push_str(
+        "
+        const shard = Number(process.env.WASM_BINDGEN_TEST_SHARD || 0);
+        const shards = Number(process.env.WASM_BINDGEN_TEST_SHARDS || 1);
+        main(tests.filter((_, i) => i % shards === shard))
+            .catch(e => {
+                console.error(e);
+                Deno.exit(1);
+            });
+    ",
+    );
+
+    let js_path = tmpdir.join("run.mjs");
+    fs::write(&js_path, js_to_execute).context("failed to write JS file")?;
+
+    // Tests read and write snapshots, reports and such, and shards are told
+    // which tests to run through the environment, so that's all allowed.
+    // Anything else can be granted through `DENO_ARGS`.
+    let extra_deno_args = env::var("DENO_ARGS")
+        .unwrap_or_default()
+        .split(",")
+        .map(|s| s.to_string())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    let command = || {
+        let mut cmd = Command::new("deno");
+        cmd.arg("run")
+            .arg("--allow-read")
+            .arg("--allow-write")
+            .arg("--allow-env")
+            .args(&extra_deno_args)
+            .arg(&js_path)
+            .args(args);
+        cmd
+    };
+    jobs::execute("deno", &command, jobs)
+}
+
+/// Returns the `file:` URL of the directory `dir`, which the bindings are
+/// configured to load the wasm file relative to by default.
+///
+/// The bindings otherwise resolve it against the location of the script, but
+/// Deno doesn't have a location unless it's run with `--location`.
+pub fn dir_url(dir: &Path) -> String {
+    let path = dir.to_string_lossy().replace('\\', "/");
+    let path = path.trim_end_matches('/');
+    if path.starts_with('/') {
+        format!("file://{}/", path)
+    } else {
+        format!("file:///{}/", path)
+    }
+}
@@ -0,0 +1,103 @@
+// Fake implementations of the timer and clock APIs, installed before any tests
+// are loaded when a test suite is configured with
+// `wasm_bindgen_test_configure!(fake_timers)`.
+//
+// Time only moves forward when the test asks it to through the functions on
+// `__wbgtest_timers`, which back the `wasm_bindgen_test::timers` module.
+(function(global) {
+    const RealDate = global.Date;
+    const epoch = RealDate.now();
+    const timers = new Map();
+    let now = 0;
+    let nextId = 1;
+
+    function schedule(f, delay, args, repeat) {
+        const id = nextId++;
+        delay = Math.max(0, Number(delay) || 0);
+        timers.set(id, { at: now + delay, seq: id, f, args, delay, repeat });
+        return id;
+    }
+
+    function clear(id) {
+        timers.delete(id);
+    }
+
+    function next(limit) {
+        let found = null;
+        let foundId = null;
+        for (const [id, timer] of timers) {
+            if (timer.at > limit)
+                continue;
+            if (found === null || timer.at < found.at || (timer.at === found.at && timer.seq < found.seq)) {
+                found = timer;
+                foundId = id;
+            }
+        }
+        return found === null ? null : [foundId, found];
+    }
+
+    function fire(id, timer) {
+        now = timer.at;
+        if (timer.repeat) {
+            timer.at = now + Math.max(1, timer.delay);
+            timer.seq = nextId++;
+        } else {
+            timers.delete(id);
+        }
+        if (typeof timer.f === 'function')
+            timer.f.apply(null, timer.args);
+    }
+
+    global.setTimeout = (f, delay, ...args) => schedule(f, delay, args, false);
+    global.setInterval = (f, delay, ...args) => schedule(f, delay, args, true);
+    global.clearTimeout = clear;
+    global.clearInterval = clear;
+    if (typeof global.requestAnimationFrame === 'function') {
+        global.requestAnimationFrame = f => schedule(() => f(now), 16, [], false);
+        global.cancelAnimationFrame = clear;
+    }
+
+    // This is a function rather than a subclass so that it can be called
+    // without `new`, which returns the current time as a string.
+    function Date(...args) {
+        if (new.target === undefined)
+            return new RealDate(epoch + now).toString();
+        if (args.length === 0)
+            args = [epoch + now];
+        return Reflect.construct(RealDate, args, new.target);
+    }
+    Object.setPrototypeOf(Date, RealDate);
+    Date.prototype = RealDate.prototype;
+    Date.now = () => epoch + now;
+    global.Date = Date;
+    if (typeof global.performance === 'object' && global.performance !== null)
+        global.performance.now = () => now;
+
+    global.__wbgtest_timers = {
+        now() {
+            return now;
+        },
+
+        advance(ms) {
+            const target = now + Math.max(0, ms);
+            let item;
+            while ((item = next(target)) !== null)
+                fire(item[0], item[1]);
+            now = target;
+        },
+
+        runAll(limit) {
+            let fired = 0;
+            let item;
+            while ((item = next(Infinity)) !== null) {
+                if (fired++ >= limit)
+                    throw new Error(`aborting after running ${limit} timers, is there an infinite interval?`);
+                fire(item[0], item[1]);
+            }
+        },
+
+        pending() {
+            return timers.size;
+        },
+    };
+})(typeof window === 'undefined' ? global : window);
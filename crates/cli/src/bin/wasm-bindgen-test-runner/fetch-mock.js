@@ -0,0 +1,130 @@
+// A fake `fetch` installed before any tests are loaded when a test suite is
+// configured with `wasm_bindgen_test_configure!(mock_fetch)`.
+//
+// Responses are registered from Rust through the `wasm_bindgen_test::fetch`
+// module, and every request made is recorded so tests can make assertions
+// about them. Requests which don't match any mock are rejected so tests stay
+// hermetic.
+(function(global) {
+    const mocks = [];
+    const requests = [];
+
+    function resolveUrl(url) {
+        const base = typeof location === 'undefined' ? 'http://localhost/' : location.href;
+        try {
+            return new URL(url, base);
+        } catch (e) {
+            return null;
+        }
+    }
+
+    function matches(mock, method, url) {
+        if (mock.method !== null && mock.method !== method)
+            return false;
+        if (mock.url === url)
+            return true;
+        const a = resolveUrl(mock.url);
+        const b = resolveUrl(url);
+        if (a === null || b === null)
+            return false;
+        // a pattern without a query string matches any query string
+        if (a.search === '')
+            return a.origin === b.origin && a.pathname === b.pathname;
+        return a.href === b.href;
+    }
+
+    function normalizeHeaders(headers) {
+        const ret = {};
+        if (headers === undefined || headers === null)
+            return ret;
+        if (typeof Headers === 'function') {
+            for (const [k, v] of new Headers(headers))
+                ret[k] = v;
+            return ret;
+        }
+        const entries = Array.isArray(headers) ? headers : Object.entries(headers);
+        for (const [k, v] of entries)
+            ret[k.toLowerCase()] = String(v);
+        return ret;
+    }
+
+    function makeResponse(mock) {
+        const init = { status: mock.status, headers: mock.headers };
+        if (typeof Response === 'function')
+            return new Response(mock.body, init);
+
+        // Minimal stand-in for environments without `Response`, like older
+        // versions of node.
+        const body = mock.body === null ? '' : mock.body;
+        return {
+            ok: mock.status >= 200 && mock.status < 300,
+            status: mock.status,
+            headers: { get: name => {
+                const v = mock.headers[name.toLowerCase()];
+                return v === undefined ? null : v;
+            } },
+            text: () => Promise.resolve(body),
+            json: () => Promise.resolve(JSON.parse(body)),
+        };
+    }
+
+    global.fetch = function(input, init) {
+        init = init || {};
+        const url = typeof input === 'string' ? input : String(input.url || input);
+        const method = String(init.method || input.method || 'GET').toUpperCase();
+        const body = init.body === undefined || init.body === null ? null : String(init.body);
+        const headers = normalizeHeaders(init.headers || input.headers);
+        requests.push({ method, url, body, headers });
+
+        let mock = null;
+        for (let i = mocks.length - 1; i >= 0; i--) {
+            if (mocks[i].remaining !== 0 && matches(mocks[i], method, url)) {
+                mock = mocks[i];
+                break;
+            }
+        }
+        if (mock === null) {
+            const msg = `wasm-bindgen-test: no mock registered for ${method} ${url}`;
+            return Promise.reject(new TypeError(msg));
+        }
+        if (mock.remaining > 0)
+            mock.remaining -= 1;
+
+        return new Promise((resolve, reject) => {
+            const respond = () => {
+                if (mock.error !== null)
+                    reject(new TypeError(mock.error));
+                else
+                    resolve(makeResponse(mock));
+            };
+            if (mock.delay > 0)
+                setTimeout(respond, mock.delay);
+            else
+                respond();
+        });
+    };
+
+    global.__wbgtest_fetch = {
+        mock(method, url, status, headers, body, delay, error, times) {
+            mocks.push({
+                method: method === undefined ? null : method.toUpperCase(),
+                url,
+                status,
+                headers,
+                body: body === undefined ? null : body,
+                delay,
+                error: error === undefined ? null : error,
+                remaining: times === 0 ? -1 : times,
+            });
+        },
+
+        requests() {
+            return requests.slice();
+        },
+
+        reset() {
+            mocks.length = 0;
+            requests.length = 0;
+        },
+    };
+})(typeof window === 'undefined' ? global : window);
@@ -0,0 +1,316 @@
+//! Support for the `golden` module of `wasm-bindgen-test`.
+//!
+//! Browsers send us the pixels of whatever a test rendered, and we compare
+//! them against a golden PNG checked in next to the tests. Decoding and
+//! encoding PNGs is a lot easier here than in the browser, and this way diffs
+//! of failed comparisons can be written straight to disk for inspection.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use failure::{bail, Error, ResultExt};
+
+/// The largest possible value of `color_delta`, between black and white.
+const MAX_DELTA: f64 = 35215.0;
+
+#[derive(Clone)]
+pub struct Goldens {
+    dir: PathBuf,
+    artifacts: PathBuf,
+    update: bool,
+}
+
+/// An image as 8-bit RGBA pixels, row by row from the top.
+struct Image {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl Goldens {
+    /// Configures golden images from the environment of this process.
+    ///
+    /// Golden images live in `tests/golden` relative to the crate being
+    /// tested unless `WASM_BINDGEN_TEST_GOLDEN_DIR` says otherwise, and just
+    /// like snapshots they're only written if
+    /// `WASM_BINDGEN_TEST_UPDATE_SNAPSHOTS` is set. The images which failed to
+    /// match and their diffs are written to `WASM_BINDGEN_TEST_ARTIFACTS_DIR`,
+    /// or `wbg-artifacts` next to our temporary directory by default.
+    pub fn from_env(tmpdir: &Path) -> Goldens {
+        let dir = match env::var_os("WASM_BINDGEN_TEST_GOLDEN_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from("tests").join("golden"),
+        };
+        let artifacts = match env::var_os("WASM_BINDGEN_TEST_ARTIFACTS_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => tmpdir.with_file_name("wbg-artifacts"),
+        };
+        let cwd = env::current_dir().unwrap();
+        Goldens {
+            dir: cwd.join(dir),
+            artifacts: cwd.join(artifacts),
+            update: env::var("WASM_BINDGEN_TEST_UPDATE_SNAPSHOTS").is_ok(),
+        }
+    }
+
+    /// Returns JS which defines the `__wbgtest_golden_*` globals in a
+    /// browser.
+    ///
+    /// Pixels are sent to the server with a synchronous request, whose
+    /// response is a description of how the comparison failed, if it did.
+    /// Service workers can't make synchronous requests, so comparisons fail
+    /// with an error there instead.
+    pub fn browser_js(&self) -> String {
+        r#"
+        self.__wbgtest_golden_compare = (name, width, height, pixels, threshold) => {
+            if (typeof XMLHttpRequest === 'undefined')
+                return 'golden images can\'t be compared in service workers, which can\'t make synchronous requests to the test server';
+            const xhr = new XMLHttpRequest();
+            const query = `width=${width}&height=${height}&threshold=${threshold}`;
+            xhr.open('POST', `/__wbgtest_golden/${name}?${query}`, false);
+            xhr.send(pixels);
+            return xhr.status === 200 ? undefined : xhr.responseText;
+        };
+        self.__wbgtest_golden_read_canvas = canvas => {
+            // There's no document in workers, but they have offscreen
+            // canvases.
+            const copy = typeof document === 'undefined'
+                ? new OffscreenCanvas(canvas.width, canvas.height)
+                : document.createElement('canvas');
+            copy.width = canvas.width;
+            copy.height = canvas.height;
+            const cx = copy.getContext('2d');
+            cx.drawImage(canvas, 0, 0);
+            return cx.getImageData(0, 0, copy.width, copy.height).data;
+        };
+        "#
+        .to_string()
+    }
+
+    /// Compares pixels sent to us by a browser against the golden image
+    /// `name`, returning a description of the difference if they don't match.
+    ///
+    /// Pixels differ if their perceptual distance, from 0 to 1, is larger
+    /// than `threshold`.
+    pub fn compare(
+        &self,
+        name: &str,
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+        threshold: f64,
+    ) -> Result<Option<String>, Error> {
+        // Names are validated on the wasm side, but double check here so a
+        // page can't touch files outside of our directories.
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid {
+            bail!("invalid golden image name `{}`", name);
+        }
+        if rgba.len() as u64 != u64::from(width) * u64::from(height) * 4 {
+            bail!(
+                "expected {} bytes of pixels for a {}x{} image, got {}",
+                u64::from(width) * u64::from(height) * 4,
+                width,
+                height,
+                rgba.len()
+            );
+        }
+        if !(threshold >= 0.0 && threshold <= 1.0) {
+            bail!("threshold must be between 0 and 1, not {}", threshold);
+        }
+        let actual = Image {
+            width,
+            height,
+            rgba,
+        };
+        let path = self.dir.join(format!("{}.png", name));
+
+        let expected = if path.exists() {
+            read_png(&path)?
+        } else if self.update {
+            write_png(&self.dir, &path, &actual)?;
+            return Ok(None);
+        } else {
+            let actual_path = self.artifacts.join(format!("{}.actual.png", name));
+            write_png(&self.artifacts, &actual_path, &actual)?;
+            return Ok(Some(format!(
+                "golden image `{}` does not exist\n\n\
+                 the rendered image was written to `{}`, rerun with \
+                 `WASM_BINDGEN_TEST_UPDATE_SNAPSHOTS=1` in the environment \
+                 to create it",
+                path.display(),
+                actual_path.display(),
+            )));
+        };
+
+        let mismatch = if expected.width != actual.width || expected.height != actual.height {
+            Some((
+                format!(
+                    "it is {}x{} but the rendered image is {}x{}",
+                    expected.width, expected.height, actual.width, actual.height
+                ),
+                None,
+            ))
+        } else {
+            let (differing, diff) = diff(&expected, &actual, threshold);
+            if differing == 0 {
+                None
+            } else {
+                Some((
+                    format!(
+                        "{} of {} pixels differ by more than {}",
+                        differing,
+                        u64::from(width) * u64::from(height),
+                        threshold
+                    ),
+                    Some(diff),
+                ))
+            }
+        };
+        let (reason, diff) = match mismatch {
+            Some(mismatch) => mismatch,
+            None => return Ok(None),
+        };
+        if self.update {
+            write_png(&self.dir, &path, &actual)?;
+            return Ok(None);
+        }
+
+        let actual_path = self.artifacts.join(format!("{}.actual.png", name));
+        write_png(&self.artifacts, &actual_path, &actual)?;
+        let mut msg = format!(
+            "golden image `{}` does not match, {}\n\n\
+             the rendered image was written to `{}`",
+            path.display(),
+            reason,
+            actual_path.display(),
+        );
+        if let Some(diff) = diff {
+            let diff_path = self.artifacts.join(format!("{}.diff.png", name));
+            write_png(&self.artifacts, &diff_path, &diff)?;
+            msg.push_str(&format!(
+                " and the differences to `{}`",
+                diff_path.display()
+            ));
+        }
+        msg.push_str(
+            ", rerun with `WASM_BINDGEN_TEST_UPDATE_SNAPSHOTS=1` in the \
+             environment to accept the new image",
+        );
+        Ok(Some(msg))
+    }
+}
+
+/// Counts the pixels of two images of the same size which differ by more
+/// than `threshold`, and renders an image of the differences.
+///
+/// Like most image diffing tools, the diff is a faded copy of the expected
+/// image with differing pixels highlighted in red.
+fn diff(expected: &Image, actual: &Image, threshold: f64) -> (usize, Image) {
+    let limit = MAX_DELTA * threshold * threshold;
+    let mut differing = 0;
+    let mut rgba = Vec::with_capacity(expected.rgba.len());
+    for (a, b) in expected.rgba.chunks(4).zip(actual.rgba.chunks(4)) {
+        if color_delta(a, b) > limit {
+            differing += 1;
+            rgba.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let (r, g, b) = blend(a);
+            let y = 255.0 + (luma(r, g, b) - 255.0) * 0.1;
+            let y = y.round() as u8;
+            rgba.extend_from_slice(&[y, y, y, 255]);
+        }
+    }
+    let diff = Image {
+        width: expected.width,
+        height: expected.height,
+        rgba,
+    };
+    (differing, diff)
+}
+
+/// The squared perceptual distance between two RGBA pixels in the YIQ color
+/// space, as described in "Measuring perceived color difference using YIQ
+/// NTSC transmission color space in mobile applications" by Kotsarenko and
+/// Ramos.
+fn color_delta(a: &[u8], b: &[u8]) -> f64 {
+    if a == b {
+        return 0.0;
+    }
+    let (r1, g1, b1) = blend(a);
+    let (r2, g2, b2) = blend(b);
+    let y = luma(r1, g1, b1) - luma(r2, g2, b2);
+    let i = (r1 * 0.59597799 - g1 * 0.27417610 - b1 * 0.32180189)
+        - (r2 * 0.59597799 - g2 * 0.27417610 - b2 * 0.32180189);
+    let q = (r1 * 0.21147017 - g1 * 0.52261711 + b1 * 0.31114694)
+        - (r2 * 0.21147017 - g2 * 0.52261711 + b2 * 0.31114694);
+    0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q
+}
+
+/// Blends a pixel with a white background according to its alpha.
+fn blend(px: &[u8]) -> (f64, f64, f64) {
+    let alpha = f64::from(px[3]) / 255.0;
+    let blend = |c: u8| 255.0 + (f64::from(c) - 255.0) * alpha;
+    (blend(px[0]), blend(px[1]), blend(px[2]))
+}
+
+fn luma(r: f64, g: f64, b: f64) -> f64 {
+    r * 0.29889531 + g * 0.58662247 + b * 0.11448223
+}
+
+fn read_png(path: &Path) -> Result<Image, Error> {
+    let file = File::open(path).with_context(|_| format!("failed to open `{}`", path.display()))?;
+    let mut decoder = png::Decoder::new(file);
+    decoder.set(png::Transformations::EXPAND);
+    let (info, mut reader) = decoder
+        .read_info()
+        .with_context(|_| format!("failed to decode `{}`", path.display()))?;
+    let mut buf = vec![0; info.buffer_size()];
+    reader
+        .next_frame(&mut buf)
+        .with_context(|_| format!("failed to decode `{}`", path.display()))?;
+    if info.bit_depth != png::BitDepth::Eight {
+        bail!(
+            "golden image `{}` must have 8 bits per channel",
+            path.display()
+        );
+    }
+    let rgba = match info.color_type {
+        png::ColorType::RGBA => buf,
+        png::ColorType::RGB => buf
+            .chunks(3)
+            .flat_map(|px| vec![px[0], px[1], px[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buf
+            .chunks(2)
+            .flat_map(|px| vec![px[0], px[0], px[0], px[1]])
+            .collect(),
+        png::ColorType::Grayscale => buf.iter().flat_map(|&y| vec![y, y, y, 255]).collect(),
+        png::ColorType::Indexed => {
+            bail!("failed to expand the palette of `{}`", path.display())
+        }
+    };
+    Ok(Image {
+        width: info.width,
+        height: info.height,
+        rgba,
+    })
+}
+
+fn write_png(dir: &Path, path: &Path, image: &Image) -> Result<(), Error> {
+    fs::create_dir_all(dir).with_context(|_| format!("failed to create `{}`", dir.display()))?;
+    let file =
+        File::create(path).with_context(|_| format!("failed to create `{}`", path.display()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width, image.height);
+    encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&image.rgba))
+        .with_context(|_| format!("failed to write `{}`", path.display()))?;
+    Ok(())
+}
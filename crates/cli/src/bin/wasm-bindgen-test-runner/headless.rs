@@ -1,3 +1,4 @@
+use crate::jobs::Totals;
 use crate::shell::Shell;
 use curl::easy::Easy;
 use failure::{bail, format_err, Error, ResultExt};
@@ -5,6 +6,7 @@ use log::{debug, warn};
 use serde::{Deserialize, Serialize};
 use serde_json::{self, json};
 use std::env;
+use std::fs;
 use std::io::{self, Read};
 use std::net::{SocketAddr, TcpListener, TcpStream};
 use std::path::{Path, PathBuf};
@@ -19,63 +21,65 @@ use std::time::{Duration, Instant};
 /// binary, controlling it, running tests, scraping output, displaying output,
 /// etc. It will return `Ok` if all tests finish successfully, and otherwise it
 /// will return an error if some tests failed.
-pub fn run(server: &SocketAddr, shell: &Shell) -> Result<(), Error> {
+///
+/// `timeout` is the default timeout of each test in seconds, if configured,
+/// `profile` is where to write a performance trace of the test run, and
+/// `jobs` is how many browsers to shard the tests across, and `https` is
+/// whether the server uses TLS with a self-signed certificate.
+///
+/// With `list` the harness only lists the tests for `--list`, which are
+/// printed without their summary if `terse`.
+pub fn run(
+    server: &SocketAddr,
+    shell: &Shell,
+    timeout: Option<u32>,
+    profile: Option<&Path>,
+    jobs: u32,
+    https: bool,
+    list: bool,
+    terse: bool,
+) -> Result<(), Error> {
     let (driver, args) = Driver::find()?;
+    let gpu = Gpu::from_env()?;
+    if profile.is_some() {
+        match driver {
+            Driver::Chrome(_) => {}
+            _ => bail!(
+                "`--profile-tests` is only supported with chromedriver, not {}",
+                driver.browser()
+            ),
+        }
+    }
+    if jobs > 1 {
+        if let Driver::Safari(_) = driver {
+            bail!("`--jobs` isn't supported with safaridriver, which can only drive one Safari");
+        }
+    }
+    if https {
+        // safaridriver has no way of accepting a self-signed certificate.
+        if let Driver::Safari(_) = driver {
+            bail!("`--https` isn't supported with safaridriver");
+        }
+    }
     println!(
         "Running headless tests in {} with `{}`",
         driver.browser(),
         driver.path().display()
     );
 
-    // Allow tests to run in parallel (in theory) by finding any open port
-    // available for our driver. We can't bind the port for the driver, but
-    // hopefully the OS gives this invocation unique ports across processes
-    let driver_addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
-
-    // Spawn the driver binary, collecting its stdout/stderr in separate
-    // threads. We'll print this output later.
-    shell.status("Spawning Geckodriver...");
-    let mut cmd = Command::new(driver.path());
-    cmd.args(&args)
-        .arg(format!("--port={}", driver_addr.port().to_string()));
-    let mut child = BackgroundChild::spawn(driver.path(), &mut cmd, shell)?;
-
-    // Wait for the driver to come online and bind its port before we try to
-    // connect to it.
-    let start = Instant::now();
-    let max = Duration::new(5, 0);
-    let mut bound = false;
-    while start.elapsed() < max {
-        if TcpStream::connect(&driver_addr).is_ok() {
-            bound = true;
-            break;
-        }
-        thread::sleep(Duration::from_millis(100));
+    // Each job gets its own driver and browser, as some drivers only support
+    // one session at a time, and runs its shard of the tests in there.
+    let mut pages = Vec::new();
+    for shard in 0..jobs {
+        let url = if jobs == 1 {
+            String::new()
+        } else {
+            format!("/?shard={}&shards={}", shard, jobs)
+        };
+        pages.push(Page::open(
+            &driver, &args, gpu, profile, https, server, &url, shell,
+        )?);
     }
-    if !bound {
-        bail!("driver failed to bind port during startup")
-    }
-
-    let mut client = Client {
-        handle: Easy::new(),
-        driver_addr,
-        session: None,
-    };
-    shell.status("Starting new webdriver session...");
-    // Allocate a new session with the webdriver protocol, and once we've done
-    // so schedule the browser to get closed with a call to `close_window`.
-    let id = client.new_session(&driver)?;
-    client.session = Some(id.clone());
-
-    // Visit our local server to open up the page that runs tests, and then get
-    // some handles to objects on the page which we'll be scraping output from.
-    let url = format!("http://{}", server);
-    shell.status(&format!("Visiting {}...", url));
-    client.goto(&id, &url)?;
-    shell.status("Loading page elements...");
-    let output = client.element(&id, "#output")?;
-    let logs = client.element(&id, "#console_log")?;
-    let errors = client.element(&id, "#console_error")?;
 
     // At this point we need to wait for the test to finish before we can take a
     // look at what happened. There appears to be no great way to do this with
@@ -83,7 +87,10 @@ pub fn run(server: &SocketAddr, shell: &Shell) -> Result<(), Error> {
     // just go with a loop.
     //
     // We periodically check the page to see if the output contains a known
-    // string to only be printed when tests have finished running.
+    // string to only be printed when tests have finished running. As long as
+    // the output keeps changing tests are still making progress, so we only
+    // give up once it's been the same for a while, which is longer than the
+    // timeout of each test so the harness can report those itself.
     //
     // TODO: harness failures aren't well handled here, they always force a
     //       timeout. These sorts of failures could be "you typo'd the path to a
@@ -91,10 +98,20 @@ pub fn run(server: &SocketAddr, shell: &Shell) -> Result<(), Error> {
     //       this on the page and look for such output here, printing diagnostic
     //       information.
     shell.status("Waiting for test to finish...");
-    let start = Instant::now();
-    let max = Duration::new(20, 0);
+    let mut start = Instant::now();
+    let max = Duration::new(timeout.map(|t| u64::from(t) + 5).unwrap_or(0).max(20), 0);
+    let mut last = vec![String::new(); pages.len()];
     while start.elapsed() < max {
-        if client.text(&id, &output)?.contains("test result: ") {
+        let mut finished = true;
+        for (page, last) in pages.iter_mut().zip(last.iter_mut()) {
+            let text = page.client.text(&page.id, &page.output)?;
+            finished = finished && is_finished(&text, list);
+            if text != *last {
+                start = Instant::now();
+                *last = text;
+            }
+        }
+        if finished {
             break;
         }
         thread::sleep(Duration::from_millis(100));
@@ -105,37 +122,181 @@ pub fn run(server: &SocketAddr, shell: &Shell) -> Result<(), Error> {
     // what happened on the console. Currently we just do this by scraping the
     // output of various fields and printing them out, hopefully providing
     // enough diagnostic info to see what went wrong (if anything).
-    let output = client.text(&id, &output)?;
-    let logs = client.text(&id, &logs)?;
-    let errors = client.text(&id, &errors)?;
-
-    if output.contains("test result: ") {
-        println!("{}", output);
+    let mut totals = Totals::default();
+    let mut ok = true;
+    for (i, page) in pages.iter_mut().enumerate() {
+        if jobs > 1 {
+            println!("job {} of {}:", i + 1, jobs);
+        }
+        let output = page.client.text(&page.id, &page.output)?;
+        let logs = page.client.text(&page.id, &page.logs)?;
+        let errors = page.client.text(&page.id, &page.errors)?;
+
+        if let Some(path) = profile {
+            shell.status("Collecting performance trace...");
+            let events = page.client.trace_events(&page.id)?;
+            let trace = json!({ "traceEvents": events });
+            fs::write(path, serde_json::to_string(&trace)?)
+                .with_context(|_| format!("failed to write `{}`", path.display()))?;
+            shell.clear();
+            println!("wrote performance trace to `{}`", path.display());
+        }
 
-        // If the tests harness finished (either successfully or unsuccessfully)
-        // then in theory all the info needed to debug the failure is in its own
-        // output, so we shouldn't need the driver logs to get printed.
-        child.print_stdio_on_drop = false;
-    } else {
-        println!("failed to detect test as having been run");
-        if output.len() > 0 {
-            println!("output div contained:\n{}", tab(&output));
+        if list && is_finished(&output, list) {
+            for line in output.lines() {
+                let summary = line.is_empty() || line.ends_with(" benchmarks");
+                if !(terse && summary) {
+                    println!("{}", line);
+                }
+            }
+            page.child.print_stdio_on_drop = false;
+            continue;
+        } else if is_finished(&output, list) {
+            println!("{}", output);
+
+            // If the tests harness finished (either successfully or
+            // unsuccessfully) then in theory all the info needed to debug the
+            // failure is in its own output, so we shouldn't need the driver
+            // logs to get printed.
+            page.child.print_stdio_on_drop = false;
+        } else {
+            println!("failed to detect test as having been run");
+            if output.len() > 0 {
+                println!("output div contained:\n{}", tab(&output));
+            }
         }
+        if logs.len() > 0 {
+            println!("console.log div contained:\n{}", tab(&logs));
+        }
+        if errors.len() > 0 {
+            println!("console.log div contained:\n{}", tab(&errors));
+        }
+
+        totals.add(&output);
+        ok = ok && !list && output.contains("test result: ok");
     }
-    if logs.len() > 0 {
-        println!("console.log div contained:\n{}", tab(&logs));
-    }
-    if errors.len() > 0 {
-        println!("console.log div contained:\n{}", tab(&errors));
+    if jobs > 1 {
+        totals.print(jobs);
     }
 
-    if !output.contains("test result: ok") {
+    if !ok {
         bail!("some tests failed")
     }
 
     Ok(())
 }
 
+/// Returns whether the harness has finished, based on the `output` it printed
+/// to the page.
+fn is_finished(output: &str, list: bool) -> bool {
+    if list {
+        output.lines().any(|l| l.ends_with(" benchmarks"))
+    } else {
+        output.contains("test result: ")
+    }
+}
+
+/// A browser running tests, along with the driver controlling it.
+///
+/// Note that the client is dropped before the driver, so the browser's
+/// window can be closed before the driver is killed.
+struct Page<'a> {
+    client: Client,
+    child: BackgroundChild<'a>,
+    id: String,
+    output: String,
+    logs: String,
+    errors: String,
+}
+
+impl<'a> Page<'a> {
+    /// Spawns a driver and has it open a browser visiting `path` on our
+    /// server.
+    fn open(
+        driver: &Driver,
+        args: &[String],
+        gpu: Option<Gpu>,
+        profile: Option<&Path>,
+        https: bool,
+        server: &SocketAddr,
+        path: &str,
+        shell: &'a Shell,
+    ) -> Result<Page<'a>, Error> {
+        // Allow tests to run in parallel (in theory) by finding any open port
+        // available for our driver. We can't bind the port for the driver, but
+        // hopefully the OS gives this invocation unique ports across processes
+        let driver_addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+
+        // Spawn the driver binary, collecting its stdout/stderr in separate
+        // threads. We'll print this output later.
+        shell.status("Spawning Geckodriver...");
+        let mut cmd = Command::new(driver.path());
+        cmd.args(args);
+        match driver {
+            // `safaridriver` only accepts the port as a separate argument.
+            Driver::Safari(_) => cmd.arg("--port").arg(driver_addr.port().to_string()),
+            _ => cmd.arg(format!("--port={}", driver_addr.port().to_string())),
+        };
+        let child = BackgroundChild::spawn(driver.path(), &mut cmd, shell)?;
+
+        // Wait for the driver to come online and bind its port before we try to
+        // connect to it.
+        let start = Instant::now();
+        let max = Duration::new(5, 0);
+        let mut bound = false;
+        while start.elapsed() < max {
+            if TcpStream::connect(&driver_addr).is_ok() {
+                bound = true;
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        if !bound {
+            bail!("driver failed to bind port during startup")
+        }
+
+        let mut client = Client {
+            handle: Easy::new(),
+            driver_addr,
+            session: None,
+            delete_session: false,
+        };
+        shell.status("Starting new webdriver session...");
+        // Allocate a new session with the webdriver protocol, and once we've
+        // done so schedule the browser to get closed with a call to
+        // `close_window`.
+        let id = client.new_session(driver, gpu, profile.is_some(), https)?;
+        client.session = Some(id.clone());
+
+        // Visit our local server to open up the page that runs tests, and then
+        // get some handles to objects on the page which we'll be scraping
+        // output from.
+        //
+        // Safari is only driven reliably when pages are loaded from
+        // `localhost` rather than from a loopback IP address, so go through
+        // the name instead, which resolves to the same address.
+        let scheme = if https { "https" } else { "http" };
+        let url = match driver {
+            Driver::Safari(_) => format!("{}://localhost:{}{}", scheme, server.port(), path),
+            _ => format!("{}://{}{}", scheme, server, path),
+        };
+        shell.status(&format!("Visiting {}...", url));
+        client.goto(&id, &url)?;
+        shell.status("Loading page elements...");
+        let output = client.element(&id, "#output")?;
+        let logs = client.element(&id, "#console_log")?;
+        let errors = client.element(&id, "#console_error")?;
+        Ok(Page {
+            client,
+            child,
+            id,
+            output,
+            logs,
+            errors,
+        })
+    }
+}
+
 enum Driver {
     Gecko(PathBuf),
     Safari(PathBuf),
@@ -238,10 +399,96 @@ an issue against rustwasm/wasm-bindgen!
     }
 }
 
+/// Presets for configuring graphics support in the browser, selected through
+/// `WASM_BINDGEN_TEST_GPU`.
+///
+/// Getting WebGL and WebGPU working in headless browsers, especially on CI
+/// machines without a GPU, requires a handful of obscure flags and the
+/// intention here is to encapsulate them.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Gpu {
+    /// Software rendering, which works everywhere including CI. In Chrome
+    /// this uses SwiftShader through ANGLE.
+    SwiftShader,
+    /// Use the machine's actual GPU, for when it has one.
+    Hardware,
+}
+
+impl Gpu {
+    fn from_env() -> Result<Option<Gpu>, Error> {
+        let value = match env::var("WASM_BINDGEN_TEST_GPU") {
+            Ok(s) => s,
+            Err(_) => return Ok(None),
+        };
+        Ok(Some(match value.as_str() {
+            "swiftshader" | "software" => Gpu::SwiftShader,
+            "hardware" => Gpu::Hardware,
+            s => bail!(
+                "unknown value `{}` for `WASM_BINDGEN_TEST_GPU`, expected \
+                 `swiftshader` or `hardware`",
+                s
+            ),
+        }))
+    }
+
+    /// Extra command line arguments for Chrome.
+    fn chrome_args(&self) -> &'static [&'static str] {
+        match self {
+            Gpu::SwiftShader => &[
+                "use-gl=angle",
+                "use-angle=swiftshader",
+                "enable-unsafe-swiftshader",
+                "enable-unsafe-webgpu",
+                "ignore-gpu-blocklist",
+            ],
+            Gpu::Hardware => &[
+                "use-gl=egl",
+                "enable-gpu-rasterization",
+                "enable-unsafe-webgpu",
+                "ignore-gpu-blocklist",
+            ],
+        }
+    }
+
+    /// Extra preferences for Firefox.
+    fn firefox_prefs(&self) -> serde_json::Value {
+        match self {
+            Gpu::SwiftShader => json!({
+                "webgl.force-enabled": true,
+                "webgl.disable-fail-if-major-performance-caveat": true,
+                "layers.acceleration.disabled": true,
+                "dom.webgpu.enabled": true,
+            }),
+            Gpu::Hardware => json!({
+                "webgl.force-enabled": true,
+                "layers.acceleration.force-enabled": true,
+                "dom.webgpu.enabled": true,
+            }),
+        }
+    }
+}
+
+/// Trace categories recorded with `--profile-tests`, the same ones DevTools
+/// records in its performance panel along with the V8 sampling profiler and
+/// wasm compilation.
+const TRACE_CATEGORIES: &str = "devtools.timeline,\
+                                disabled-by-default-devtools.timeline,\
+                                disabled-by-default-devtools.timeline.frame,\
+                                toplevel,\
+                                blink.console,\
+                                blink.user_timing,\
+                                v8,\
+                                v8.execute,\
+                                v8.wasm,\
+                                disabled-by-default-v8.cpu_profiler";
+
 struct Client {
     handle: Easy,
     driver_addr: SocketAddr,
     session: Option<String>,
+    /// Whether the session has to be deleted when we're done rather than just
+    /// having its window closed, see `new_safari_session`.
+    delete_session: bool,
 }
 
 enum Method<'a> {
@@ -255,7 +502,13 @@ enum Method<'a> {
 // copied the `webdriver-client` crate when writing the below bindings.
 
 impl Client {
-    fn new_session(&mut self, driver: &Driver) -> Result<String, Error> {
+    fn new_session(
+        &mut self,
+        driver: &Driver,
+        gpu: Option<Gpu>,
+        profile: bool,
+        https: bool,
+    ) -> Result<String, Error> {
         match driver {
             Driver::Gecko(_) => {
                 #[derive(Deserialize)]
@@ -268,12 +521,17 @@ impl Client {
                     #[serde(rename = "sessionId")]
                     session_id: String,
                 }
+                let mut options = json!({
+                    "args": ["-headless"],
+                });
+                if let Some(gpu) = gpu {
+                    options["prefs"] = gpu.firefox_prefs();
+                }
                 let request = json!({
                     "capabilities": {
                         "alwaysMatch": {
-                            "moz:firefoxOptions": {
-                                "args": ["-headless"],
-                            }
+                            "moz:firefoxOptions": options,
+                            "acceptInsecureCerts": https,
                         }
                     }
                 });
@@ -281,29 +539,11 @@ impl Client {
                 Ok(x.value.session_id)
             }
             Driver::Safari(_) => {
-                #[derive(Deserialize)]
-                struct Response {
-                    // returned by `--legacy`
-                    #[serde(rename = "sessionId")]
-                    session_id: Option<String>,
-                    // returned by the now-default `--w3c` mode
-                    value: Option<Value>,
+                if gpu.is_some() {
+                    warn!("`WASM_BINDGEN_TEST_GPU` has no effect with safaridriver");
                 }
-                #[derive(Deserialize)]
-                struct Value {
-                    #[serde(rename = "sessionId")]
-                    session_id: String,
-                }
-                let request = json!({
-                    // this is needed for the now `--legacy` mode
-                    "desiredCapabilities": {
-                    },
-                    // this is needed for the now `--w3c` (default) mode
-                    "capabilities": {
-                    }
-                });
-                let x: Response = self.post("/session", &request)?;
-                Ok(x.session_id.or(x.value.map(|v| v.session_id)).unwrap())
+                self.delete_session = true;
+                self.new_safari_session()
             }
             Driver::Chrome(_) => {
                 #[derive(Deserialize)]
@@ -311,26 +551,144 @@ impl Client {
                     #[serde(rename = "sessionId")]
                     session_id: String,
                 }
-                let request = json!({
+                let mut args = vec![
+                    "headless",
+                    // See https://stackoverflow.com/questions/50642308/
+                    // for what this funky `disable-dev-shm-usage`
+                    // option is
+                    "disable-dev-shm-usage",
+                    "no-sandbox",
+                ];
+                if let Some(gpu) = gpu {
+                    args.extend_from_slice(gpu.chrome_args());
+                }
+                if https {
+                    // Our certificate is self-signed for each run.
+                    args.push("ignore-certificate-errors");
+                }
+                let mut request = json!({
                     "desiredCapabilities": {
                         "goog:chromeOptions": {
-                            "args": [
-                                "headless",
-                                // See https://stackoverflow.com/questions/50642308/
-                                // for what this funky `disable-dev-shm-usage`
-                                // option is
-                                "disable-dev-shm-usage",
-                                "no-sandbox",
-                            ],
+                            "args": args,
                         },
+                        "acceptInsecureCerts": https,
                     }
                 });
+                if profile {
+                    // Chromedriver records a trace with these categories
+                    // into the performance log, which is what DevTools
+                    // records in its performance panel.
+                    let caps = &mut request["desiredCapabilities"];
+                    caps["goog:chromeOptions"]["perfLoggingPrefs"] = json!({
+                        "enableNetwork": false,
+                        "enablePage": false,
+                        "traceCategories": TRACE_CATEGORIES,
+                    });
+                    caps["loggingPrefs"] = json!({ "performance": "ALL" });
+                    caps["goog:loggingPrefs"] = json!({ "performance": "ALL" });
+                }
                 let x: Response = self.post("/session", &request)?;
                 Ok(x.session_id)
             }
         }
     }
 
+    /// There's only one Safari per machine, and it can only be paired with a
+    /// single WebDriver session at a time, even across `safaridriver`
+    /// processes. Creating a session fails while another one exists, so when
+    /// several test binaries run at once they have to wait for their turn,
+    /// and each has to delete its session when it's done for the next one to
+    /// be created.
+    fn new_safari_session(&mut self) -> Result<String, Error> {
+        let start = Instant::now();
+        let max = Duration::new(60, 0);
+        loop {
+            match self.try_new_safari_session() {
+                Ok(id) => return Ok(id),
+                Err(e) => {
+                    if start.elapsed() > max {
+                        let msg = "failed to create a Safari session, is another one \
+                                   still paired with Safari?";
+                        return Err(e.context(msg).into());
+                    }
+                    debug!("failed to create a Safari session, retrying: {}", e);
+                    thread::sleep(Duration::from_millis(500));
+                }
+            }
+        }
+    }
+
+    fn try_new_safari_session(&mut self) -> Result<String, Error> {
+        #[derive(Deserialize)]
+        struct Response {
+            // returned by `--legacy`
+            #[serde(rename = "sessionId")]
+            session_id: Option<String>,
+            // returned by the now-default `--w3c` mode
+            value: Option<Value>,
+        }
+        #[derive(Deserialize)]
+        struct Value {
+            #[serde(rename = "sessionId")]
+            session_id: String,
+        }
+        let request = json!({
+            // this is needed for the now `--legacy` mode
+            "desiredCapabilities": {
+            },
+            // this is needed for the now `--w3c` (default) mode
+            "capabilities": {
+            }
+        });
+        let x: Response = self.post("/session", &request)?;
+        x.session_id
+            .or(x.value.map(|v| v.session_id))
+            .ok_or(format_err!("failed to find session id in response"))
+    }
+
+    /// Returns the trace events recorded in the performance log since the
+    /// session started, which is only enabled by `new_session` when
+    /// profiling.
+    fn trace_events(&mut self, id: &str) -> Result<Vec<serde_json::Value>, Error> {
+        #[derive(Deserialize)]
+        struct Response {
+            value: Vec<Entry>,
+        }
+        #[derive(Deserialize)]
+        struct Entry {
+            message: String,
+        }
+        #[derive(Deserialize)]
+        struct Message {
+            message: Inner,
+        }
+        #[derive(Deserialize)]
+        struct Inner {
+            method: String,
+            #[serde(default)]
+            params: serde_json::Value,
+        }
+
+        let request = json!({ "type": "performance" });
+        let x: Response = self.post(&format!("/session/{}/log", id), &request)?;
+        let mut events = Vec::new();
+        for entry in x.value {
+            let msg: Message = serde_json::from_str(&entry.message)?;
+            if msg.message.method == "Tracing.dataCollected" {
+                events.push(msg.message.params);
+            }
+        }
+        Ok(events)
+    }
+
+    fn delete_session(&mut self, id: &str) -> Result<(), Error> {
+        #[derive(Deserialize)]
+        struct Response {}
+        let x: Response = self.delete(&format!("/session/{}", id))?;
+        drop(x);
+        Ok(())
+    }
+
     fn close_window(&mut self, id: &str) -> Result<(), Error> {
         #[derive(Deserialize)]
         struct Response {}
@@ -465,6 +823,11 @@ impl Drop for Client {
         if let Err(e) = self.close_window(&id) {
             warn!("failed to close window {:?}", e);
         }
+        if self.delete_session {
+            if let Err(e) = self.delete_session(&id) {
+                warn!("failed to delete session {:?}", e);
+            }
+        }
     }
 }
 
@@ -0,0 +1,144 @@
+//! Support for `--jobs`, running the tests of a binary in several node.js or
+//! Deno processes or browsers at once.
+//!
+//! Tests are sharded round-robin, so shard `i` of `n` runs every `n`th test
+//! starting at the `i`th, which spreads slow tests defined next to each other
+//! across shards. Each shard runs its own copy of the test harness which
+//! prints its own results, and here we tally them up into one summary.
+
+use std::process::{Command, Stdio};
+use std::thread;
+
+use failure::{bail, Error, ResultExt};
+
+/// Runs the test script built by `command` with `program`, either `node` or
+/// `deno`, in `jobs` processes at once.
+///
+/// A single job replaces this process with `program` entirely.
+pub fn execute(program: &str, command: &dyn Fn() -> Command, jobs: u32) -> Result<(), Error> {
+    if jobs == 1 {
+        return exec(program, &mut command());
+    }
+
+    // Run all shards at once, collecting their output in the background so
+    // none of them block on a full pipe, and print it all once they're done.
+    let mut shards = Vec::new();
+    for shard in 0..jobs {
+        let child = command()
+            .env("WASM_BINDGEN_TEST_SHARD", shard.to_string())
+            .env("WASM_BINDGEN_TEST_SHARDS", jobs.to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|_| format!("failed to execute `{}`", program))?;
+        shards.push(thread::spawn(move || child.wait_with_output()));
+    }
+    let mut totals = Totals::default();
+    let mut crashed = false;
+    for (i, shard) in shards.into_iter().enumerate() {
+        let output = shard
+            .join()
+            .unwrap()
+            .with_context(|_| format!("failed to execute `{}`", program))?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        println!("job {} of {}:", i + 1, jobs);
+        print!("{}", stdout);
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        if totals.add(&stdout) && !output.status.success() {
+            println!(
+                "{} exited with {} after the tests passed",
+                program, output.status
+            );
+            crashed = true;
+        }
+    }
+    totals.print(jobs);
+    if !totals.ok() || crashed {
+        bail!("some tests failed")
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn exec(program: &str, cmd: &mut Command) -> Result<(), Error> {
+    use std::os::unix::prelude::*;
+    Err(Error::from(cmd.exec())
+        .context(format!("failed to execute `{}`", program))
+        .into())
+}
+
+#[cfg(windows)]
+fn exec(_program: &str, cmd: &mut Command) -> Result<(), Error> {
+    use std::process;
+    let status = cmd.status()?;
+    process::exit(status.code().unwrap_or(3));
+}
+
+/// Totals of the results printed by the test harness of each shard.
+#[derive(Default)]
+pub struct Totals {
+    passed: usize,
+    failed: usize,
+    ignored: usize,
+    unfinished: usize,
+}
+
+impl Totals {
+    /// Adds the results in the output of a shard, returning whether all of
+    /// its tests passed.
+    pub fn add(&mut self, output: &str) -> bool {
+        // Looks like `test result: ok. 3 passed; 0 failed; 0 ignored`
+        let line = match output
+            .lines()
+            .rev()
+            .find(|l| l.starts_with("test result: "))
+        {
+            Some(line) => line,
+            None => {
+                self.unfinished += 1;
+                return false;
+            }
+        };
+        let words = line
+            .split_whitespace()
+            .map(|w| w.trim_end_matches(';'))
+            .collect::<Vec<_>>();
+        for pair in words.windows(2) {
+            let n = match pair[0].parse::<usize>() {
+                Ok(n) => n,
+                Err(_) => continue,
+            };
+            match pair[1] {
+                "passed" => self.passed += n,
+                "failed" => self.failed += n,
+                "ignored" => self.ignored += n,
+                _ => {}
+            }
+        }
+        line.starts_with("test result: ok")
+    }
+
+    /// Returns whether every shard finished with all of its tests passing.
+    pub fn ok(&self) -> bool {
+        self.failed == 0 && self.unfinished == 0
+    }
+
+    /// Prints a summary of all shards, in the same format as the harness.
+    pub fn print(&self, jobs: u32) {
+        if self.unfinished > 0 {
+            println!(
+                "{} of {} jobs failed to finish running their tests",
+                self.unfinished, jobs
+            );
+        }
+        println!(
+            "test result ({} jobs): {}. {} passed; {} failed; {} ignored\n",
+            jobs,
+            if self.ok() { "ok" } else { "FAILED" },
+            self.passed,
+            self.failed,
+            self.ignored,
+        );
+    }
+}
@@ -23,10 +23,18 @@ use wasm_bindgen_cli_support::Bindgen;
 #[global_allocator]
 static ALLOC: std::alloc::System = std::alloc::System;
 
+mod coverage;
+mod deno;
+mod golden;
 mod headless;
+mod jobs;
 mod node;
+mod profiles;
+mod report;
 mod server;
 mod shell;
+mod snapshot;
+mod tls;
 
 fn main() {
     env_logger::init();
@@ -45,12 +53,77 @@ fn rmain() -> Result<(), Error> {
     let mut args = env::args_os().skip(1);
     let shell = shell::Shell::new();
 
-    // Currently no flags are supported, and assume there's only one argument
-    // which is the wasm file to test. This'll want to improve over time!
+    // The first argument is the wasm file to test, and all others are
+    // forwarded to the test harness except for our own options, which may be
+    // passed either as `--option value` or `--option=value`.
     let wasm_file_to_test = match args.next() {
         Some(file) => PathBuf::from(file),
         None => bail!("must have a file to test as first argument"),
     };
+    let mut format = None;
+    let mut profile = None;
+    let mut jobs = None;
+    let mut https = false;
+    let mut profile_boundary = false;
+    let mut harness_args = Vec::new();
+    while let Some(arg) = args.next() {
+        let arg = match arg.into_string() {
+            Ok(arg) => arg,
+            Err(arg) => {
+                harness_args.push(arg);
+                continue;
+            }
+        };
+        if arg == "--https" {
+            https = true;
+            continue;
+        }
+        if arg == "--profile-boundary" {
+            profile_boundary = true;
+            continue;
+        }
+        let (name, value) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => (&arg[..i], Some(arg[i + 1..].to_string())),
+            _ => (&arg[..], None),
+        };
+        let dst = match name {
+            "--format" => &mut format,
+            "--profile-tests" => &mut profile,
+            "--jobs" => &mut jobs,
+            _ => {
+                harness_args.push(arg.into());
+                continue;
+            }
+        };
+        let value = value.or_else(|| args.next().map(|s| s.to_string_lossy().into_owned()));
+        let value = match value {
+            Some(value) => value,
+            None => bail!("`{}` requires a value", name),
+        };
+        // libtest's own formats are only used by `--list`, which the harness
+        // implements, so those are forwarded rather than being reports.
+        if name == "--format" && (value == "terse" || value == "pretty") {
+            harness_args.push(format!("--format={}", value).into());
+            continue;
+        }
+        *dst = Some(value);
+    }
+    let profile = profile.map(PathBuf::from);
+    let jobs = match jobs {
+        Some(s) => match s.parse::<u32>() {
+            Ok(n) if n > 0 => n,
+            _ => bail!("invalid `--jobs` of `{}`, expected a positive number", s),
+        },
+        None => 1,
+    };
+    if jobs > 1 && (format.is_some() || profile.is_some()) {
+        bail!("`--jobs` can't be combined with `--format` or `--profile-tests` yet");
+    }
+    let list = harness_args.iter().any(|a| a == "--list");
+    let terse = harness_args.iter().any(|a| a == "--format=terse");
+    if list && jobs > 1 {
+        bail!("`--list` can't be combined with `--jobs`");
+    }
 
     // Assume a cargo-like directory layout and generate output at
     // `target/wasm32-unknown-unknown/wbg-tmp/...`
@@ -65,6 +138,13 @@ fn rmain() -> Result<(), Error> {
     drop(fs::remove_dir_all(&tmpdir));
     fs::create_dir(&tmpdir).context("creating temporary directory")?;
 
+    let profiles = profiles::Profiles::new(&wasm_file_to_test, &tmpdir);
+    let coverage = coverage::Coverage::new(&wasm_file_to_test, &tmpdir);
+    let report = match format {
+        Some(format) => Some(report::Report::new(&format, &wasm_file_to_test, &tmpdir)?),
+        None => None,
+    };
+
     let module = "wasm-bindgen-test";
 
     // Collect all tests that the test harness is supposed to run. We assume
@@ -89,35 +169,165 @@ fn rmain() -> Result<(), Error> {
         return Ok(());
     }
 
-    // Figure out if this tests is supposed to execute in node.js or a browser.
-    // That's done on a per-test-binary basis with the
-    // `wasm_bindgen_test_configure` macro, which emits a custom section for us
-    // to read later on.
+    // Figure out if this tests is supposed to execute in node.js or a browser,
+    // and whether any other harness features were requested. That's done on a
+    // per-test-binary basis with the `wasm_bindgen_test_configure` macro,
+    // which emits a custom section for us to read later on.
     let mut node = true;
+    let mut fake_timers = false;
+    let mut mock_fetch = false;
+    let mut allow_console_errors = false;
+    let mut worker_threads = false;
+    let mut deno = false;
+    let mut middleware = false;
+    let mut worker = None;
     for custom in wasm.custom.iter() {
         if custom.name != "__wasm_bindgen_test_unstable" {
             continue;
         }
-        node = !custom.value.contains(&0x01);
+        node = node && !custom.value.contains(&0x01);
+        fake_timers = fake_timers || custom.value.contains(&0x02);
+        mock_fetch = mock_fetch || custom.value.contains(&0x03);
+        allow_console_errors = allow_console_errors || custom.value.contains(&0x04);
+        worker_threads = worker_threads || custom.value.contains(&0x05);
+        deno = deno || custom.value.contains(&0x06);
+        middleware = middleware || custom.value.contains(&0x0a);
+        for (byte, kind) in [(0x07, "dedicated"), (0x08, "shared"), (0x09, "service")].iter() {
+            if custom.value.contains(byte) {
+                if worker.is_some() && worker != Some(*kind) {
+                    bail!("tests can only be configured to run in one kind of worker");
+                }
+                worker = Some(*kind);
+                node = false;
+            }
+        }
+    }
+    if worker_threads && !node {
+        bail!("`run_in_worker_threads` tests can only be run in node.js, not in a browser");
+    }
+    if deno && !node {
+        bail!("`run_in_deno` can't be combined with running in a browser");
+    }
+    if deno && worker_threads {
+        bail!("`run_in_worker_threads` tests can only be run in node.js, not in Deno");
     }
     let headless = env::var("NO_HEADLESS").is_err();
     let debug = env::var("WASM_BINDGEN_NO_DEBUG").is_err();
+    let snapshots = snapshot::Snapshots::from_env();
+    let timeout = match env::var("WASM_BINDGEN_TEST_TIMEOUT") {
+        Ok(s) => match s.parse::<u32>() {
+            Ok(secs) => Some(secs),
+            Err(_) => bail!(
+                "invalid `WASM_BINDGEN_TEST_TIMEOUT` of `{}`, expected a number of seconds",
+                s
+            ),
+        },
+        Err(_) => None,
+    };
+
+    // JS which needs to execute before any tests are loaded, installing
+    // harness features that tests may rely on.
+    //
+    // Test timeouts and durations always use the real timers, which are
+    // saved before they may be replaced by fake ones.
+    let mut prelude = String::new();
+    prelude.push_str(
+        "(function(global) {\n\
+             global.__wbgtest_real_timers = {\n\
+                 setTimeout: global.setTimeout.bind(global),\n\
+                 clearTimeout: global.clearTimeout.bind(global),\n\
+                 now: Date.now.bind(Date),\n\
+             };\n\
+         })(typeof window === 'undefined' ? global : window);\n",
+    );
+    if let Some(secs) = timeout {
+        prelude.push_str(&format!(
+            "(typeof window === 'undefined' ? global : window)\
+             .__wbgtest_timeout = {};\n",
+            u64::from(secs) * 1000
+        ));
+    }
+    if fake_timers {
+        prelude.push_str(include_str!("fake-timers.js"));
+    }
+    if mock_fetch {
+        prelude.push_str(include_str!("fetch-mock.js"));
+    }
+    if profile.is_some() {
+        prelude.push_str("self.__wbgtest_profile = true;\n");
+    }
+    if allow_console_errors {
+        prelude.push_str(
+            "(typeof window === 'undefined' ? global : window)\
+             .__wbgtest_allow_console_errors = true;\n",
+        );
+    }
 
     // Make the generated bindings available for the tests to execute against.
     shell.status("Executing bindgen...");
     let mut b = Bindgen::new();
     b.debug(debug)
-        .nodejs(node)
-        .input_module(module, wasm)
+        .nodejs(node && !worker_threads && !deno)
+        .no_modules(worker_threads || deno)
+        .threads(worker_threads);
+    if deno {
+        b.wasm_url_base(&deno::dir_url(&tmpdir));
+    }
+    b.input_module(module, wasm)
         .keep_debug(false)
         .emit_start(false)
+        .profile_boundary(profile_boundary)
+        .middleware(middleware)
         .generate(&tmpdir)
         .context("executing `wasm-bindgen` over the wasm file")?;
     shell.clear();
 
-    // If we're executing in node.js, that module will take it from here.
+    // If we're executing in node.js or Deno, that module will take it from
+    // here.
     if node {
-        return node::execute(&module, &tmpdir, &args.collect::<Vec<_>>(), &tests);
+        let runtime = if deno { "Deno" } else { "node.js" };
+        if profile.is_some() {
+            bail!(
+                "`--profile-tests` is only supported in headless Chrome, not {}",
+                runtime
+            );
+        }
+        if https {
+            bail!("`--https` is only supported in browsers, not {}", runtime);
+        }
+        if deno {
+            return deno::execute(
+                &module,
+                &tmpdir,
+                &harness_args,
+                &tests,
+                &snapshots,
+                &profiles,
+                &coverage,
+                report.as_ref(),
+                &prelude,
+                jobs,
+            );
+        }
+        return node::execute(
+            &module,
+            &tmpdir,
+            &harness_args,
+            &tests,
+            &snapshots,
+            &profiles,
+            &coverage,
+            report.as_ref(),
+            &prelude,
+            worker_threads,
+            jobs,
+        );
+    }
+
+    // Headless browsers detect that `--list` has finished by its summary, so
+    // the harness always prints one and it's left out afterwards for `terse`.
+    if headless && terse {
+        harness_args.retain(|a| a != "--format=terse");
     }
 
     // Otherwise we're executing in a browser. Spawn a server which serves up
@@ -131,8 +341,16 @@ fn rmain() -> Result<(), Error> {
         headless,
         &module,
         &tmpdir,
-        &args.collect::<Vec<_>>(),
+        &harness_args,
         &tests,
+        &snapshots,
+        &golden::Goldens::from_env(&tmpdir),
+        &profiles,
+        &coverage,
+        report.as_ref(),
+        &prelude,
+        https,
+        worker,
     )
     .context("failed to spawn server")?;
     let addr = srv.server_addr();
@@ -140,8 +358,15 @@ fn rmain() -> Result<(), Error> {
     // TODO: eventually we should provide the ability to exit at some point
     // (gracefully) here, but for now this just runs forever.
     if !headless {
+        if profile.is_some() {
+            bail!("`--profile-tests` is only supported in headless Chrome");
+        }
+        if jobs > 1 {
+            bail!("`--jobs` is only supported in node.js and headless browsers");
+        }
         println!(
-            "Interactive browsers tests are now available at http://{}",
+            "Interactive browsers tests are now available at {}://{}",
+            if https { "https" } else { "http" },
             addr
         );
         println!("");
@@ -153,6 +378,24 @@ fn rmain() -> Result<(), Error> {
     }
 
     thread::spawn(|| srv.run());
-    headless::run(&addr, &shell)?;
-    Ok(())
+    let result = headless::run(
+        &addr,
+        &shell,
+        timeout,
+        profile.as_ref().map(|p| &**p),
+        jobs,
+        https,
+        list,
+        terse,
+    );
+    if profiles.path().exists() {
+        println!("wrote profiles to `{}`", profiles.path().display());
+    }
+    for shard in 0..jobs {
+        let path = coverage.path(if jobs == 1 { None } else { Some(shard) });
+        if path.exists() {
+            println!("wrote coverage to `{}`", path.display());
+        }
+    }
+    result
 }
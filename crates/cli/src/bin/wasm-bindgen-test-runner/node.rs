@@ -6,12 +6,44 @@ use std::process::Command;
 
 use failure::{Error, ResultExt};
 
+use crate::coverage::Coverage;
+use crate::jobs;
+use crate::profiles::Profiles;
+use crate::report::Report;
+use crate::snapshot::Snapshots;
+
 pub fn execute(
     module: &str,
     tmpdir: &Path,
     args: &[OsString],
     tests: &[String],
+    snapshots: &Snapshots,
+    profiles: &Profiles,
+    coverage: &Coverage,
+    report: Option<&Report>,
+    prelude: &str,
+    worker_threads: bool,
+    jobs: u32,
 ) -> Result<(), Error> {
+    // With worker threads the bindings are generated with `--no-modules` and
+    // instantiated by hand, so they can be instantiated again on each worker.
+    let (load, prelude) = if worker_threads {
+        let worker_path = tmpdir.join("worker.js");
+        fs::write(&worker_path, include_str!("worker.js")).context("failed to write JS file")?;
+        (
+            format!("const {{ support, wasm }} = loadWithWorkers(\"{}\");", module),
+            format!("{}\n{}", include_str!("worker-threads.js"), prelude),
+        )
+    } else {
+        (
+            format!(
+                "const support = require(\"./{0}\");\n\
+                 const wasm = require(\"./{0}_bg\");",
+                module
+            ),
+            prelude.to_string(),
+        )
+    };
     let mut js_to_execute = format!(
         r#"
         const {{ exit }} = require('process');
@@ -40,9 +72,10 @@ pub fn execute(
 
         global.__wbg_test_invoke = f => f();
 
+        {0}
+
         async function main(tests) {{
-            const support = require("./{0}");
-            const wasm = require("./{0}_bg");
+            {1}
 
             cx = new support.WasmBindgenTestContext();
             handlers.on_console_debug = support.__wbgtest_console_debug;
@@ -50,6 +83,7 @@ pub fn execute(
             handlers.on_console_info = support.__wbgtest_console_info;
             handlers.on_console_warn = support.__wbgtest_console_warn;
             handlers.on_console_error = support.__wbgtest_console_error;
+            global.__wbgtest_boundary_stats = support.__wbg_boundary_stats;
 
             // Forward runtime arguments. These arguments are also arguments to the
             // `wasm-bindgen-test-runner` which forwards them to node which we
@@ -64,8 +98,14 @@ pub fn execute(
 
         const tests = [];
     "#,
-        module
+        prelude, load,
     );
+    js_to_execute.push_str(&snapshots.node_js());
+    js_to_execute.push_str(&profiles.node_js());
+    js_to_execute.push_str(&coverage.node_js());
+    if let Some(report) = report {
+        js_to_execute.push_str(&report.node_js());
+    }
 
     // Note that we're collecting *JS objects* that represent the functions to
     // execute, and then those objects are passed into wasm for it to execute
@@ -73,10 +113,13 @@ pub fn execute(
     for test in tests {
         js_to_execute.push_str(&format!("tests.push('{}')\n", test));
     }
-    // And as a final addendum, exit with a nonzero code if any tests fail.
+    // With `--jobs` each process only runs its shard of the tests, and as a
+    // final addendum, exit with a nonzero code if any tests fail.
     js_to_execute.push_str(
         "
-        main(tests)
+        const shard = Number(process.env.WASM_BINDGEN_TEST_SHARD || 0);
+        const shards = Number(process.env.WASM_BINDGEN_TEST_SHARDS || 1);
+        main(tests.filter((_, i) => i % shards === shard))
             .catch(e => {
                 console.error(e);
                 exit(1);
@@ -100,26 +143,14 @@ pub fn execute(
         .map(|s| s.to_string())
         .filter(|s| !s.is_empty())
         .collect::<Vec<_>>();
-    exec(
-        Command::new("node")
-            .env("NODE_PATH", env::join_paths(&path).unwrap())
+    let node_path = env::join_paths(&path).unwrap();
+    let command = || {
+        let mut cmd = Command::new("node");
+        cmd.env("NODE_PATH", &node_path)
             .args(&extra_node_args)
             .arg(&js_path)
-            .args(args),
-    )
-}
-
-#[cfg(unix)]
-fn exec(cmd: &mut Command) -> Result<(), Error> {
-    use std::os::unix::prelude::*;
-    Err(Error::from(cmd.exec())
-        .context("failed to execute `node`")
-        .into())
-}
-
-#[cfg(windows)]
-fn exec(cmd: &mut Command) -> Result<(), Error> {
-    use std::process;
-    let status = cmd.status()?;
-    process::exit(status.code().unwrap_or(3));
+            .args(args);
+        cmd
+    };
+    jobs::execute("node", &command, jobs)
 }
@@ -0,0 +1,88 @@
+//! Support for the `profiling` module of `wasm-bindgen-test`.
+//!
+//! Each profile stopped by a test is handed to us as a line of JSON, which we
+//! append to a file named after the test binary. In node.js this is done
+//! directly through the `fs` module, whereas in browsers profiles are posted
+//! to the test server.
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use failure::{Error, ResultExt};
+
+#[derive(Clone)]
+pub struct Profiles {
+    path: PathBuf,
+}
+
+impl Profiles {
+    /// Configures where the profiles of the tests in `wasm_file` go.
+    ///
+    /// Profiles are written to `WASM_BINDGEN_TEST_PROFILE_DIR`, or
+    /// `wbg-profiles` next to our temporary directory by default. Profiles of
+    /// a previous run of the same test binary are removed.
+    pub fn new(wasm_file: &Path, tmpdir: &Path) -> Profiles {
+        let stem = wasm_file
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let dir = match env::var_os("WASM_BINDGEN_TEST_PROFILE_DIR") {
+            Some(dir) => env::current_dir().unwrap().join(dir),
+            None => tmpdir.with_file_name("wbg-profiles"),
+        };
+        let path = dir.join(format!("{}.jsonl", stem));
+        drop(fs::remove_file(&path));
+        Profiles { path }
+    }
+
+    /// Returns the file profiles are written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns JS which defines the `__wbgtest_profiles_write` global for
+    /// node.js
+    pub fn node_js(&self) -> String {
+        format!(
+            r#"
+            global.__wbgtest_profiles_write = line => {{
+                const fs = require('fs');
+                const path = require('path');
+                const file = {path};
+                fs.mkdirSync(path.dirname(file), {{ recursive: true }});
+                fs.appendFileSync(file, line + '\n');
+            }};
+            "#,
+            path = serde_json::to_string(&self.path.to_string_lossy()).unwrap(),
+        )
+    }
+
+    /// Returns JS which defines the `__wbgtest_profiles_write` global in a
+    /// browser.
+    pub fn browser_js(&self) -> String {
+        r#"
+        self.__wbgtest_profiles_write = line => {
+            __wbgtest_post('/__wbgtest_profiles', line);
+        };
+        "#
+        .to_string()
+    }
+
+    /// Appends a profile sent to us by a browser.
+    pub fn append(&self, line: &[u8]) -> Result<(), Error> {
+        let dir = self.path.parent().unwrap();
+        fs::create_dir_all(dir)
+            .with_context(|_| format!("failed to create `{}`", dir.display()))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|_| format!("failed to open `{}`", self.path.display()))?;
+        file.write_all(line)
+            .and_then(|()| file.write_all(b"\n"))
+            .with_context(|_| format!("failed to write `{}`", self.path.display()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+//! Support for `--format`, writing machine-readable reports of test results.
+//!
+//! The report itself is rendered by the test harness in wasm, which knows
+//! about every test's result and output. All we do is tell it which format to
+//! use and write the report it hands back to a file, either directly through
+//! the `fs` module in node.js or on its behalf when it's posted to the test
+//! server from a browser.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use failure::{bail, Error, ResultExt};
+
+#[derive(Clone)]
+pub struct Report {
+    format: &'static str,
+    suite: String,
+    path: PathBuf,
+}
+
+impl Report {
+    /// Configures a report in `format` of the tests in `wasm_file`.
+    ///
+    /// Reports are written to `WASM_BINDGEN_TEST_REPORT_DIR`, or `wbg-reports`
+    /// next to our temporary directory by default, and are named after the
+    /// test binary so the reports of all test binaries of a workspace can be
+    /// collected from one place.
+    pub fn new(format: &str, wasm_file: &Path, tmpdir: &Path) -> Result<Report, Error> {
+        let (format, extension) = match format {
+            "json" => ("json", "json"),
+            "junit" => ("junit", "xml"),
+            _ => bail!(
+                "unknown format `{}`, expected either `json` or `junit`",
+                format
+            ),
+        };
+        let stem = wasm_file
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let dir = match env::var_os("WASM_BINDGEN_TEST_REPORT_DIR") {
+            Some(dir) => env::current_dir().unwrap().join(dir),
+            None => tmpdir.with_file_name("wbg-reports"),
+        };
+        Ok(Report {
+            format,
+            suite: suite_name(&stem).to_string(),
+            path: dir.join(format!("{}.{}", stem, extension)),
+        })
+    }
+
+    /// Returns JS which defines the `__wbgtest_report` global for node.js
+    pub fn node_js(&self) -> String {
+        format!(
+            r#"
+            global.__wbgtest_report = {{
+                format: {format},
+                suite: {suite},
+                write: contents => {{
+                    const fs = require('fs');
+                    const path = require('path');
+                    const file = {path};
+                    fs.mkdirSync(path.dirname(file), {{ recursive: true }});
+                    fs.writeFileSync(file, contents);
+                }},
+            }};
+            "#,
+            format = serde_json::to_string(self.format).unwrap(),
+            suite = serde_json::to_string(&self.suite).unwrap(),
+            path = serde_json::to_string(&self.path.to_string_lossy()).unwrap(),
+        )
+    }
+
+    /// Returns JS which defines the `__wbgtest_report` global in a browser.
+    ///
+    /// The report is sent back to the server with `__wbgtest_post` so it's
+    /// guaranteed to be written by the time the test suite finishes.
+    pub fn browser_js(&self) -> String {
+        format!(
+            r#"
+            self.__wbgtest_report = {{
+                format: {format},
+                suite: {suite},
+                write: contents => __wbgtest_post('/__wbgtest_report', contents),
+            }};
+            "#,
+            format = serde_json::to_string(self.format).unwrap(),
+            suite = serde_json::to_string(&self.suite).unwrap(),
+        )
+    }
+
+    /// Writes a report sent to us by a browser.
+    pub fn write(&self, contents: &[u8]) -> Result<(), Error> {
+        let dir = self.path.parent().unwrap();
+        fs::create_dir_all(dir)
+            .with_context(|_| format!("failed to create `{}`", dir.display()))?;
+        fs::write(&self.path, contents)
+            .with_context(|_| format!("failed to write `{}`", self.path.display()))?;
+        Ok(())
+    }
+}
+
+/// Strips the hash Cargo appends to the names of test binaries, turning
+/// `web-0123456789abcdef` into `web`.
+fn suite_name(stem: &str) -> &str {
+    match stem.rfind('-') {
+        Some(i) if stem.len() - i == 17 && stem[i + 1..].chars().all(|c| c.is_ascii_hexdigit()) => {
+            &stem[..i]
+        }
+        _ => stem,
+    }
+}
@@ -1,12 +1,21 @@
+use std::env;
 use std::ffi::OsString;
 use std::fs;
+use std::io::Read;
 use std::net::SocketAddr;
 use std::path::Path;
 
-use failure::{format_err, Error, ResultExt};
+use failure::{bail, format_err, Error, ResultExt};
 use rouille::{Request, Response, Server};
 use wasm_bindgen_cli_support::wasm2es6js::Config;
 
+use crate::coverage::Coverage;
+use crate::golden::Goldens;
+use crate::profiles::Profiles;
+use crate::report::Report;
+use crate::snapshot::Snapshots;
+use crate::tls;
+
 pub fn spawn(
     addr: &SocketAddr,
     headless: bool,
@@ -14,6 +23,14 @@ pub fn spawn(
     tmpdir: &Path,
     args: &[OsString],
     tests: &[String],
+    snapshots: &Snapshots,
+    goldens: &Goldens,
+    profiles: &Profiles,
+    coverage: &Coverage,
+    report: Option<&Report>,
+    prelude: &str,
+    https: bool,
+    worker: Option<&str>,
 ) -> Result<Server<impl Fn(&Request) -> Response + Send + Sync>, Error> {
     let mut js_to_execute = format!(
         r#"
@@ -25,12 +42,14 @@ pub fn spawn(
             __wbgtest_console_warn,
             __wbgtest_console_error
         }} from './{0}';
+        import * as bindings from './{0}';
         import * as wasm from './{0}_bg';
 
         // Now that we've gotten to the point where JS is executing, update our
         // status text as at this point we should be asynchronously fetching the
-        // wasm module.
-        document.getElementById('output').textContent = "Loading wasm module...";
+        // wasm module. In a worker the page does this itself.
+        if (typeof document !== 'undefined')
+            document.getElementById('output').textContent = "Loading wasm module...";
 
         async function main(test) {{
             // this is a facet of using wasm2es6js, a hack until browsers have
@@ -38,11 +57,12 @@ pub fn spawn(
             await wasm.booted;
 
             const cx = new Context();
-            window.on_console_debug = __wbgtest_console_debug;
-            window.on_console_log = __wbgtest_console_log;
-            window.on_console_info = __wbgtest_console_info;
-            window.on_console_warn = __wbgtest_console_warn;
-            window.on_console_error = __wbgtest_console_error;
+            self.on_console_debug = __wbgtest_console_debug;
+            self.on_console_log = __wbgtest_console_log;
+            self.on_console_info = __wbgtest_console_info;
+            self.on_console_warn = __wbgtest_console_warn;
+            self.on_console_error = __wbgtest_console_error;
+            self.__wbgtest_boundary_stats = bindings.__wbg_boundary_stats;
 
             // Forward runtime arguments. These arguments are also arguments to the
             // `wasm-bindgen-test-runner` which forwards them to node which we
@@ -53,16 +73,72 @@ pub fn spawn(
             await cx.run(test.map(s => wasm[s]));
         }}
 
+        // Results are posted back to us with synchronous requests, so
+        // they're written by the time the tests have finished. Workers
+        // instead relay them through the page, which makes the request in
+        // order with the output of the tests.
+        if (self.__wbgtest_post === undefined) {{
+            self.__wbgtest_post = (url, body) => {{
+                const xhr = new XMLHttpRequest();
+                xhr.open('POST', url, false);
+                xhr.send(body);
+            }};
+        }}
+
         const tests = [];
     "#,
         module, args,
     );
+    js_to_execute.push_str(&snapshots.browser_js()?);
+    js_to_execute.push_str(&goldens.browser_js());
+    js_to_execute.push_str(&profiles.browser_js());
+    js_to_execute.push_str(&coverage.browser_js());
+    if let Some(report) = report {
+        js_to_execute.push_str(&report.browser_js());
+    }
     for test in tests {
         js_to_execute.push_str(&format!("tests.push('{}');\n", test));
     }
-    js_to_execute.push_str("main(tests);\n");
+    // With `--jobs` each page is told which shard of the tests to run.
+    js_to_execute.push_str(
+        "
+        const params = new URLSearchParams(location.search);
+        const shard = Number(params.get('shard') || 0);
+        const shards = Number(params.get('shards') || 1);
+        export const done = main(tests.filter((_, i) => i % shards === shard));
+        ",
+    );
 
-    let js_path = tmpdir.join("run.js");
+    // Harness features which need to be set up before anything else on the
+    // page are loaded through a classic (non-module) script which executes
+    // before `run.js` and its imports.
+    let prelude_path = tmpdir.join("prelude.js");
+    fs::write(&prelude_path, prelude).context("failed to write JS file")?;
+
+    // When running in a worker the page only starts the worker and shows the
+    // output it sends back, whereas the tests are run by `tests.js` in the
+    // worker, after `worker-prelude.js` has set up sending output to the page
+    // and then the prelude.
+    let js_path = match worker {
+        Some(kind) => {
+            let page = format!(
+                "const kind = {};\n{}",
+                serde_json::to_string(kind).unwrap(),
+                include_str!("worker-page.js")
+            );
+            fs::write(tmpdir.join("run.js"), page).context("failed to write JS file")?;
+            let worker_prelude = format!("{}\n{}", include_str!("worker-prelude.js"), prelude);
+            fs::write(tmpdir.join("worker-prelude.js"), worker_prelude)
+                .context("failed to write JS file")?;
+            fs::write(
+                tmpdir.join("test-worker.js"),
+                include_str!("test-worker.js"),
+            )
+            .context("failed to write JS file")?;
+            tmpdir.join("tests.js")
+        }
+        None => tmpdir.join("run.js"),
+    };
     fs::write(&js_path, js_to_execute).context("failed to write JS file")?;
 
     // No browser today supports a wasm file as ES modules natively, so we need
@@ -84,20 +160,141 @@ pub fn spawn(
     fs::write(tmpdir.join(format!("{}_bg.wasm", module)), wasm)
         .context("failed to write wasm file")?;
 
+    // The root path gets our canned `index.html`. The two templates here
+    // differ slightly in the default routing of `console.log`, going to an
+    // HTML element during headless testing so we can try to scrape its
+    // output.
+    let index = if headless {
+        include_str!("index-headless.html")
+    } else {
+        include_str!("index.html")
+    };
+    let index = match env::var_os("WASM_BINDGEN_TEST_HTML") {
+        Some(path) => custom_index(path.as_ref(), index)?,
+        None => index.to_string(),
+    };
+
     // For now, always run forever on this port. We may update this later!
     let tmpdir = tmpdir.to_path_buf();
-    let srv = Server::new(addr, move |request| {
-        // The root path gets our canned `index.html`. The two templates here
-        // differ slightly in the default routing of `console.log`, going to an
-        // HTML element during headless testing so we can try to scrape its
-        // output.
+    let snapshots = snapshots.clone();
+    let goldens = goldens.clone();
+    let profiles = profiles.clone();
+    let coverage = coverage.clone();
+    let report = report.cloned();
+    let isolated = env::var("WASM_BINDGEN_TEST_CROSS_ORIGIN_ISOLATED").is_ok();
+    let handler = move |request: &Request| {
+        // Cross-origin isolation unlocks APIs like
+        // `performance.measureUserAgentSpecificMemory`, but it also prevents
+        // the page from loading cross-origin resources which don't opt in, so
+        // it's only enabled on request.
+        let isolate = |response: Response| {
+            if !isolated {
+                return response;
+            }
+            response
+                .with_unique_header("Cross-Origin-Opener-Policy", "same-origin")
+                .with_unique_header("Cross-Origin-Embedder-Policy", "require-corp")
+        };
+
         if request.url() == "/" {
-            let s = if headless {
-                include_str!("index-headless.html")
-            } else {
-                include_str!("index.html")
+            return isolate(Response::from_data("text/html", index.clone()));
+        }
+
+        // Updated snapshots from `assert_js_snapshot!` are posted back to us
+        // so we can write them to disk.
+        if request.method() == "POST" && request.url().starts_with("/__wbgtest_snapshot/") {
+            let name = &request.url()["/__wbgtest_snapshot/".len()..];
+            let mut contents = Vec::new();
+            if let Some(mut data) = request.data() {
+                if data.read_to_end(&mut contents).is_err() {
+                    return Response::empty_400();
+                }
+            }
+            return match snapshots.write(name, &contents) {
+                Ok(()) => Response::text(""),
+                Err(e) => Response::text(e.to_string()).with_status_code(400),
+            };
+        }
+
+        // Profiles from the `profiling` module are posted to us one at a
+        // time as they're stopped.
+        if request.method() == "POST" && request.url() == "/__wbgtest_profiles" {
+            let mut line = Vec::new();
+            if let Some(mut data) = request.data() {
+                if data.read_to_end(&mut line).is_err() {
+                    return Response::empty_400();
+                }
+            }
+            return match profiles.append(&line) {
+                Ok(()) => Response::text(""),
+                Err(e) => Response::text(e.to_string()).with_status_code(500),
+            };
+        }
+
+        // The coverage of the run is posted back to us once all tests have
+        // finished, if the test binary was instrumented.
+        if request.method() == "POST" && request.url() == "/__wbgtest_coverage" {
+            let shard = match request.get_param("shard") {
+                Some(s) => match s.parse() {
+                    Ok(shard) => Some(shard),
+                    Err(_) => return Response::empty_400(),
+                },
+                None => None,
+            };
+            let mut data = Vec::new();
+            if let Some(mut body) = request.data() {
+                if body.read_to_end(&mut data).is_err() {
+                    return Response::empty_400();
+                }
+            }
+            return match coverage.write(shard, &data) {
+                Ok(()) => Response::text(""),
+                Err(e) => Response::text(e.to_string()).with_status_code(500),
+            };
+        }
+
+        // The report requested with `--format` is posted back to us once all
+        // tests have finished.
+        if request.method() == "POST" && request.url() == "/__wbgtest_report" {
+            let report = match &report {
+                Some(report) => report,
+                None => return Response::empty_404(),
+            };
+            let mut contents = Vec::new();
+            if let Some(mut data) = request.data() {
+                if data.read_to_end(&mut contents).is_err() {
+                    return Response::empty_400();
+                }
+            }
+            return match report.write(&contents) {
+                Ok(()) => Response::text(""),
+                Err(e) => Response::text(e.to_string()).with_status_code(500),
+            };
+        }
+
+        // Rendered images from the `golden` module are posted to us to be
+        // compared against golden images on disk, with a description of any
+        // difference sent back.
+        if request.method() == "POST" && request.url().starts_with("/__wbgtest_golden/") {
+            let name = &request.url()["/__wbgtest_golden/".len()..];
+            let width = request.get_param("width").and_then(|s| s.parse().ok());
+            let height = request.get_param("height").and_then(|s| s.parse().ok());
+            let threshold = request.get_param("threshold").and_then(|s| s.parse().ok());
+            let (width, height, threshold) = match (width, height, threshold) {
+                (Some(w), Some(h), Some(t)) => (w, h, t),
+                _ => return Response::empty_400(),
+            };
+            let mut pixels = Vec::new();
+            if let Some(mut data) = request.data() {
+                if data.read_to_end(&mut pixels).is_err() {
+                    return Response::empty_400();
+                }
+            }
+            return match goldens.compare(name, width, height, pixels, threshold) {
+                Ok(None) => Response::text(""),
+                Ok(Some(msg)) => Response::text(msg).with_status_code(409),
+                Err(e) => Response::text(e.to_string()).with_status_code(400),
             };
-            return Response::from_data("text/html", s);
         }
 
         // Otherwise we need to find the asset here. It may either be in our
@@ -110,11 +307,35 @@ pub fn spawn(
         // Make sure browsers don't cache anything (Chrome appeared to with this
         // header?)
         response.headers.retain(|(k, _)| k != "Cache-Control");
-        return response;
-    })
-    .map_err(|e| format_err!("{}", e))?;
+        return isolate(response);
+    };
+    let srv = if https {
+        tls::server(addr, handler)?
+    } else {
+        Server::new(addr, handler).map_err(|e| format_err!("{}", e))?
+    };
     return Ok(srv);
 
+    /// Reads the template at `path`, which is the `WASM_BINDGEN_TEST_HTML`
+    /// configured by the user, and adds the body of our canned `index` to the
+    /// end of its body. That's where the elements and scripts running the
+    /// tests go, after anything in the template which tests depend on.
+    fn custom_index(path: &Path, index: &str) -> Result<String, Error> {
+        let mut template = fs::read_to_string(path)
+            .with_context(|_| format!("failed to read `{}`", path.display()))?;
+        let start = index.find("<body>").unwrap() + "<body>".len();
+        let end = index.rfind("</body>").unwrap();
+        let harness = &index[start..end];
+        match template.rfind("</body>") {
+            Some(i) => template.insert_str(i, harness),
+            None => bail!(
+                "`{}` has no `</body>` to add the test harness to",
+                path.display()
+            ),
+        }
+        Ok(template)
+    }
+
     fn try_asset(request: &Request, dir: &Path) -> Response {
         let response = rouille::match_assets(request, dir);
         if response.is_success() {
@@ -0,0 +1,124 @@
+//! Support for the `assert_js_snapshot!` macro of `wasm-bindgen-test`.
+//!
+//! Tests themselves can't touch the filesystem, so it's our job to read and
+//! write snapshot files on their behalf. In node.js this is done directly
+//! through the `fs` module, whereas in browsers we preload all existing
+//! snapshots into the page and accept updated snapshots over HTTP.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use failure::{bail, Error, ResultExt};
+
+#[derive(Clone)]
+pub struct Snapshots {
+    dir: PathBuf,
+    update: bool,
+}
+
+impl Snapshots {
+    /// Configures snapshots from the environment of this process.
+    ///
+    /// Snapshots live in `tests/snapshots` relative to the crate being tested
+    /// (Cargo executes us in the crate's directory) unless
+    /// `WASM_BINDGEN_TEST_SNAPSHOT_DIR` says otherwise, and they're only ever
+    /// written if `WASM_BINDGEN_TEST_UPDATE_SNAPSHOTS` is set.
+    pub fn from_env() -> Snapshots {
+        let dir = match env::var_os("WASM_BINDGEN_TEST_SNAPSHOT_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from("tests").join("snapshots"),
+        };
+        let dir = env::current_dir().unwrap().join(dir);
+        Snapshots {
+            dir,
+            update: env::var("WASM_BINDGEN_TEST_UPDATE_SNAPSHOTS").is_ok(),
+        }
+    }
+
+    /// Returns JS which defines the `__wbgtest_snapshot_*` globals for node.js
+    pub fn node_js(&self) -> String {
+        format!(
+            r#"
+            const snapshotDir = {dir};
+            global.__wbgtest_snapshot_update = {update};
+            global.__wbgtest_snapshot_read = name => {{
+                const file = require('path').join(snapshotDir, name);
+                try {{
+                    return require('fs').readFileSync(file, 'utf8');
+                }} catch (e) {{
+                    return undefined;
+                }}
+            }};
+            global.__wbgtest_snapshot_write = (name, contents) => {{
+                const fs = require('fs');
+                if (!fs.existsSync(snapshotDir))
+                    fs.mkdirSync(snapshotDir, {{ recursive: true }});
+                fs.writeFileSync(require('path').join(snapshotDir, name), contents);
+            }};
+            "#,
+            dir = serde_json::to_string(&self.dir.to_string_lossy()).unwrap(),
+            update = self.update,
+        )
+    }
+
+    /// Returns JS which defines the `__wbgtest_snapshot_*` globals in a
+    /// browser.
+    ///
+    /// All existing snapshots are embedded in the returned JS, and updates
+    /// are sent back to the server with `__wbgtest_post` so they're
+    /// guaranteed to be written by the time the test suite finishes.
+    pub fn browser_js(&self) -> Result<String, Error> {
+        let mut snapshots = serde_json::Map::new();
+        if self.dir.exists() {
+            let entries = fs::read_dir(&self.dir)
+                .with_context(|_| format!("failed to read `{}`", self.dir.display()))?;
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension().and_then(|s| s.to_str()) != Some("snap") {
+                    continue;
+                }
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                let contents = fs::read_to_string(&path)
+                    .with_context(|_| format!("failed to read `{}`", path.display()))?;
+                snapshots.insert(name, contents.into());
+            }
+        }
+        Ok(format!(
+            r#"
+            const snapshots = {snapshots};
+            self.__wbgtest_snapshot_update = {update};
+            self.__wbgtest_snapshot_read = name => snapshots[name];
+            self.__wbgtest_snapshot_write = (name, contents) => {{
+                snapshots[name] = contents;
+                __wbgtest_post(`/__wbgtest_snapshot/${{name}}`, contents);
+            }};
+            "#,
+            snapshots = serde_json::Value::Object(snapshots),
+            update = self.update,
+        ))
+    }
+
+    /// Writes a snapshot sent to us by a browser.
+    pub fn write(&self, name: &str, contents: &[u8]) -> Result<(), Error> {
+        if !self.update {
+            bail!("snapshots can only be written in update mode");
+        }
+        // Names are sanitized on the wasm side, but double check here so a
+        // page can't write outside of the snapshot directory.
+        let valid = name.ends_with(".snap")
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+            && !name.starts_with('.');
+        if !valid {
+            bail!("invalid snapshot name `{}`", name);
+        }
+        fs::create_dir_all(&self.dir)
+            .with_context(|_| format!("failed to create `{}`", self.dir.display()))?;
+        let path = self.dir.join(name);
+        fs::write(&path, contents)
+            .with_context(|_| format!("failed to write `{}`", path.display()))?;
+        Ok(())
+    }
+}
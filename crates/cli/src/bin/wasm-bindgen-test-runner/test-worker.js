@@ -0,0 +1,11 @@
+// The entry point of workers running tests, which loads the tests after
+// setting up sending their output to the page.
+
+import './worker-prelude.js';
+import { done } from './tests.js';
+
+// Service workers are stopped when they're idle, so keep this one installing
+// until all tests have finished.
+if (typeof ServiceWorkerGlobalScope !== 'undefined' && self instanceof ServiceWorkerGlobalScope) {
+    self.addEventListener('install', e => e.waitUntil(done));
+}
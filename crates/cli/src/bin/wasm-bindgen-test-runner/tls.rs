@@ -0,0 +1,73 @@
+//! Support for `--https`, serving tests over TLS.
+//!
+//! Some APIs are only available in secure contexts, and while browsers treat
+//! pages on `127.0.0.1` as secure already, that's not true of every API in
+//! every browser or of pages served under other names. With `--https` the
+//! test server uses a self-signed certificate generated for each run, which
+//! the browser is told to accept when the WebDriver session is created.
+//!
+//! Generating certificates requires OpenSSL, so this is only available when
+//! built with the `https` feature.
+
+use std::net::SocketAddr;
+
+use failure::Error;
+use rouille::{Request, Response, Server};
+
+#[cfg(feature = "https")]
+pub fn server<F>(addr: &SocketAddr, handler: F) -> Result<Server<F>, Error>
+where
+    F: Fn(&Request) -> Response + Send + Sync + 'static,
+{
+    use failure::format_err;
+
+    let (certificate, private_key) = self_signed_certificate()?;
+    Server::new_ssl(addr, handler, certificate, private_key).map_err(|e| format_err!("{}", e))
+}
+
+#[cfg(not(feature = "https"))]
+pub fn server<F>(_addr: &SocketAddr, _handler: F) -> Result<Server<F>, Error>
+where
+    F: Fn(&Request) -> Response + Send + Sync + 'static,
+{
+    failure::bail!(
+        "`--https` requires `wasm-bindgen-test-runner` to be built with the \
+         `https` feature of `wasm-bindgen-cli`"
+    )
+}
+
+/// Generates a certificate for `localhost` and `127.0.0.1` valid for a day,
+/// returning it and its private key in PEM format.
+#[cfg(feature = "https")]
+fn self_signed_certificate() -> Result<(Vec<u8>, Vec<u8>), Error> {
+    use openssl::asn1::Asn1Time;
+    use openssl::bn::BigNum;
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::{X509NameBuilder, X509};
+
+    let key = PKey::from_rsa(Rsa::generate(2048)?)?;
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_text("CN", "localhost")?;
+    let name = name.build();
+
+    let mut builder = X509::builder()?;
+    builder.set_version(2)?;
+    builder.set_serial_number(&*BigNum::from_u32(1)?.to_asn1_integer()?)?;
+    builder.set_subject_name(&name)?;
+    builder.set_issuer_name(&name)?;
+    builder.set_pubkey(&key)?;
+    builder.set_not_before(&*Asn1Time::days_from_now(0)?)?;
+    builder.set_not_after(&*Asn1Time::days_from_now(1)?)?;
+    let alt_names = SubjectAlternativeName::new()
+        .dns("localhost")
+        .ip("127.0.0.1")
+        .build(&builder.x509v3_context(None, None))?;
+    builder.append_extension(alt_names)?;
+    builder.sign(&key, MessageDigest::sha256())?;
+    let certificate = builder.build();
+
+    Ok((certificate.to_pem()?, key.private_key_to_pem_pkcs8()?))
+}
@@ -0,0 +1,51 @@
+// Runs on the test page for suites configured to run in a worker, after a
+// `kind` of worker is defined above. The tests themselves run in
+// `test-worker.js`, which sends us their output to show on the page just like
+// when tests run on the page itself.
+
+const output = document.getElementById('output');
+output.textContent = `Starting ${kind} worker...`;
+let cleared = false;
+
+function onMessage(data) {
+    if (data.output !== undefined) {
+        if (!cleared) {
+            output.textContent = '';
+            cleared = true;
+        }
+        output.textContent += data.output;
+    } else if (data.console !== undefined) {
+        // The page's console may itself be routed into the page by the
+        // headless test harness.
+        console[data.console](...data.args);
+    } else if (data.post !== undefined) {
+        const xhr = new XMLHttpRequest();
+        xhr.open('POST', data.post, false);
+        xhr.send(data.body);
+    }
+}
+
+function onError(e) {
+    output.textContent += `\nerror in ${kind} worker: ${e.message || e}\n`;
+}
+
+// The shard of tests to run is passed on to the worker through its URL.
+const url = `test-worker.js${location.search}`;
+
+if (kind === 'dedicated') {
+    const worker = new Worker(url, { type: 'module' });
+    worker.onmessage = e => onMessage(e.data);
+    worker.onerror = onError;
+} else if (kind === 'shared') {
+    const worker = new SharedWorker(url, { type: 'module' });
+    worker.port.onmessage = e => onMessage(e.data);
+    worker.onerror = onError;
+} else if (kind === 'service') {
+    navigator.serviceWorker.onmessage = e => onMessage(e.data);
+    // Make sure a service worker left over from a previous run doesn't get in
+    // the way of the new one.
+    navigator.serviceWorker.getRegistrations()
+        .then(registrations => Promise.all(registrations.map(r => r.unregister())))
+        .then(() => navigator.serviceWorker.register(url, { type: 'module', scope: './' }))
+        .catch(onError);
+}
@@ -0,0 +1,56 @@
+// Sets up a worker to run tests, before the runner's prelude and the tests
+// are loaded. There's no page in a worker, so all output of the tests is sent
+// to the page which started the worker instead.
+
+const send = (() => {
+    if (typeof ServiceWorkerGlobalScope !== 'undefined' && self instanceof ServiceWorkerGlobalScope) {
+        // Messages are sent in order as they're all chained on the same
+        // promise.
+        const clients = self.clients.matchAll({ includeUncontrolled: true, type: 'window' });
+        return msg => clients.then(clients => clients.forEach(c => c.postMessage(msg)));
+    }
+    if (typeof SharedWorkerGlobalScope !== 'undefined' && self instanceof SharedWorkerGlobalScope) {
+        // Tests start running before the page connects, so hold on to their
+        // output until it does.
+        const queue = [];
+        let port = null;
+        self.onconnect = e => {
+            port = e.ports[0];
+            queue.forEach(msg => port.postMessage(msg));
+            queue.length = 0;
+        };
+        return msg => port ? port.postMessage(msg) : queue.push(msg);
+    }
+    return msg => self.postMessage(msg);
+})();
+
+self.__wbgtest_output = text => send({ output: text });
+
+// Snapshots, profiles, reports and coverage are posted to the test server by
+// the page, as service workers can't make synchronous requests. Messages are
+// handled in order, so they're written before the page sees the results of
+// the tests.
+self.__wbgtest_post = (url, body) => send({ post: url, body });
+
+const wrap = method => {
+    const og = console[method];
+    const on_method = `on_console_${method}`;
+    console[method] = function (...args) {
+        if (self[on_method]) {
+            self[on_method](args);
+        }
+        send({ console: method, args: args.map(String) });
+        og.apply(this, args);
+    };
+};
+
+wrap("debug");
+wrap("log");
+wrap("info");
+wrap("warn");
+wrap("error");
+
+self.__wbg_test_invoke = f => f();
+
+// The prelude picks `global` when there's no `window`.
+const global = self;
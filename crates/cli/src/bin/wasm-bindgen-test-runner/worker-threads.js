@@ -0,0 +1,74 @@
+// A pool of `worker_threads` workers, used when a test suite is configured
+// with `wasm_bindgen_test_configure!(run_in_worker_threads)`.
+//
+// The test binary is instantiated on the main thread, where tests run, and on
+// every worker with the same shared memory. Work queued by
+// `wasm_bindgen_test::threads::spawn` is put in a queue in the shared memory,
+// which the workers take work from in `worker.js` without involving the main
+// thread, so the main thread can block waiting for work to be done. Work
+// queued for the main thread by `wasm_bindgen_test::threads::run_on_main` is
+// posted back here from the workers, as are calls of imports marked
+// `#[wasm_bindgen(main_thread)]`.
+function loadWithWorkers(module) {
+    const { Worker } = require('worker_threads');
+    const fs = require('fs');
+    const os = require('os');
+    const path = require('path');
+
+    // The `--no-modules` output defines itself on `self`.
+    global.self = global;
+    require(`./${module}`);
+    const support = global.wasm_bindgen;
+    support.initSync(fs.readFileSync(path.join(__dirname, `${module}_bg.wasm`)));
+
+    const threads = Number(process.env.WASM_BINDGEN_TEST_THREADS) || os.cpus().length;
+    const workers = [];
+    // Workers wait for work forever, so they only keep the process alive
+    // while there's work they haven't finished yet.
+    let pending = 0;
+    for (let i = 0; i < threads; i++) {
+        const worker = new Worker(path.join(__dirname, 'worker.js'), {
+            workerData: {
+                module,
+                wasmModule: support.__wbindgen_wasm_module,
+                memory: support.__wbindgen_wasm_memory,
+            },
+        });
+        worker.unref();
+        // Only defined if the test binary has any `main_thread` imports.
+        if (support.proxyMainThreadCalls) {
+            support.proxyMainThreadCalls(worker);
+        }
+        worker.on('message', message => {
+            if (message !== null && message.__wbindgen_main_thread) {
+                return;
+            }
+            if (message !== null) {
+                support.__wbgtest_main_entry(message.main);
+                return;
+            }
+            pending -= 1;
+            if (pending === 0) {
+                workers.forEach(w => w.unref());
+            }
+        });
+        worker.on('error', e => {
+            console.error('worker thread failed:', e);
+            process.exit(1);
+        });
+        workers.push(worker);
+    }
+
+    global.__wbgtest_threads = threads;
+    global.__wbgtest_spawn = () => {
+        if (pending === 0) {
+            workers.forEach(w => w.ref());
+        }
+        pending += 1;
+    };
+    global.__wbgtest_run_on_main = work => {
+        setImmediate(() => support.__wbgtest_main_entry(work));
+    };
+
+    return { support, wasm: support.wasm };
+}
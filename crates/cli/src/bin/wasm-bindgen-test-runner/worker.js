@@ -0,0 +1,16 @@
+// Entry point of the workers started by `worker-threads.js`, which run work
+// queued by `wasm_bindgen_test::threads::spawn` one at a time, forever. Work
+// they queue with `wasm_bindgen_test::threads::run_on_main` is posted back to
+// the main thread, while `null` signals that they're done with a piece of
+// work.
+const { parentPort, workerData } = require('worker_threads');
+
+global.self = global;
+require(`./${workerData.module}`);
+const support = global.wasm_bindgen;
+support.initSync(workerData.wasmModule, workerData.memory);
+
+global.__wbgtest_run_on_main = work => parentPort.postMessage({ main: work });
+global.__wbgtest_work_done = () => parentPort.postMessage(null);
+
+support.__wbgtest_worker_entry();
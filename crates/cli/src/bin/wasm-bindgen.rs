@@ -25,6 +25,9 @@ Options:
     --browser                    Generate output that only works in a browser
     --no-modules                 Generate output that only works in a browser (without modules)
     --no-modules-global VAR      Name of the global variable to initialize
+    --multi-instance             Add `initInstance` to --no-modules output
+    --deno                       Generate output that only works in Deno
+    --browser-extension          Generate output for a browser extension (requires and implies --no-modules)
     --typescript                 Output a TypeScript definition file (on by default)
     --no-typescript              Don't emit a *.d.ts file
     --debug                      Include otherwise-extraneous debug checks in output
@@ -32,6 +35,25 @@ Options:
     --keep-debug                 Keep debug sections in wasm files
     --remove-name-section        Remove the debugging `name` section of the file
     --remove-producers-section   Remove the telemetry `producers` section
+    --middleware                 Allow hooking calls to exports with `addMiddleware`
+    --profile-boundary           Count calls and time spent in each import and export shim
+    --sync-init-max-size BYTES   Largest module `initSync` accepts (requires --no-modules)
+    --required-imports           Export a `requiredImports` function describing all imports
+    --wasm-features LIST         Override the wasm features the input is assumed to use
+    --variants LIST              Emit a loader choosing between builds by wasm feature support
+    --cache-dir DIR              Reuse previously generated bindings cached in this directory
+    --single-threaded            Do all work on one thread, using somewhat less memory
+    --low-memory                 Write data and debug info to the output without parsing it
+    --split-debuginfo FILE       Move DWARF debug info into a separate wasm file
+    --hashed-wasm                Name the wasm file after its hash and check its integrity
+    --minify-js                  Remove comments and whitespace from the generated JS
+    --source-map                 Emit a source map for the generated JS
+    --emit-ts                    Emit the glue as TypeScript instead of JS
+    --compat VERSION             Only use JS features of an older ECMAScript version (es2017)
+    --api-json                   Describe the exported JS API in a JSON file
+    --api-baseline FILE          Fail on breaking changes to the JS API described by FILE
+    --api-renames LIST           Keep renamed exports like `old=new` as deprecated aliases
+    --wasm-url-base BASE         Resolve the default wasm URL against `script`, `document` or a URL
     -V --version                 Print the version number of wasm-bindgen
 ";
 
@@ -40,6 +62,8 @@ struct Args {
     flag_nodejs: bool,
     flag_browser: bool,
     flag_no_modules: bool,
+    flag_browser_extension: bool,
+    flag_multi_instance: bool,
     flag_typescript: bool,
     flag_no_typescript: bool,
     flag_out_dir: Option<PathBuf>,
@@ -48,9 +72,29 @@ struct Args {
     flag_version: bool,
     flag_no_demangle: bool,
     flag_no_modules_global: Option<String>,
+    flag_deno: bool,
     flag_remove_name_section: bool,
     flag_remove_producers_section: bool,
     flag_keep_debug: bool,
+    flag_middleware: bool,
+    flag_profile_boundary: bool,
+    flag_sync_init_max_size: Option<usize>,
+    flag_required_imports: bool,
+    flag_wasm_features: Option<String>,
+    flag_variants: Option<String>,
+    flag_cache_dir: Option<PathBuf>,
+    flag_single_threaded: bool,
+    flag_low_memory: bool,
+    flag_split_debuginfo: Option<String>,
+    flag_hashed_wasm: bool,
+    flag_minify_js: bool,
+    flag_source_map: bool,
+    flag_emit_ts: bool,
+    flag_api_json: bool,
+    flag_api_baseline: Option<PathBuf>,
+    flag_api_renames: Option<String>,
+    flag_wasm_url_base: Option<String>,
+    flag_compat: Option<String>,
     arg_input: Option<PathBuf>,
 }
 
@@ -87,16 +131,56 @@ fn rmain(args: &Args) -> Result<(), Error> {
     b.input_path(input)
         .nodejs(args.flag_nodejs)
         .browser(args.flag_browser)
-        .no_modules(args.flag_no_modules)
+        .no_modules(args.flag_no_modules || args.flag_browser_extension)
+        .browser_extension(args.flag_browser_extension)
+        .multi_instance(args.flag_multi_instance)
+        .deno(args.flag_deno)
         .debug(args.flag_debug)
         .demangle(!args.flag_no_demangle)
         .keep_debug(args.flag_keep_debug)
         .remove_name_section(args.flag_remove_name_section)
         .remove_producers_section(args.flag_remove_producers_section)
+        .middleware(args.flag_middleware)
+        .profile_boundary(args.flag_profile_boundary)
+        .required_imports(args.flag_required_imports)
+        .single_threaded(args.flag_single_threaded)
+        .low_memory(args.flag_low_memory)
+        .hashed_wasm(args.flag_hashed_wasm)
+        .minify_js(args.flag_minify_js)
+        .source_map(args.flag_source_map)
+        .emit_ts(args.flag_emit_ts)
+        .api_json(args.flag_api_json)
         .typescript(typescript);
     if let Some(ref name) = args.flag_no_modules_global {
         b.no_modules_global(name);
     }
+    if let Some(ref features) = args.flag_wasm_features {
+        b.wasm_features(features);
+    }
+    if let Some(ref compat) = args.flag_compat {
+        b.compat(compat);
+    }
+    if let Some(ref base) = args.flag_wasm_url_base {
+        b.wasm_url_base(base);
+    }
+    if let Some(ref name) = args.flag_split_debuginfo {
+        b.split_debuginfo(name);
+    }
+    if let Some(ref path) = args.flag_api_baseline {
+        b.api_baseline(path);
+    }
+    if let Some(ref renames) = args.flag_api_renames {
+        b.api_renames(renames);
+    }
+    if let Some(ref dir) = args.flag_cache_dir {
+        b.cache_dir(dir);
+    }
+    if let Some(ref variants) = args.flag_variants {
+        b.variants(variants);
+    }
+    if let Some(size) = args.flag_sync_init_max_size {
+        b.sync_init_max_size(size);
+    }
     if let Some(ref name) = args.flag_out_name {
         b.out_name(name);
     }
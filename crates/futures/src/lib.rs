@@ -25,6 +25,11 @@
 //! systems and make sure that Rust/JavaScript can work together with
 //! asynchronous and I/O work.
 //!
+//! With the `std_future` feature enabled the
+//! [`std_future`](./std_future/index.html) module additionally converts
+//! `std::future::Future`s into `Promise`s, which is required to export `async`
+//! constructors with `#[wasm_bindgen(constructor)]`.
+//!
 //! # Example Usage
 //!
 //! This example wraps JavaScript's `Promise.resolve()` into a Rust `Future` for
@@ -115,8 +120,36 @@ use futures::executor::{self, Notify, Spawn};
 use futures::future;
 use futures::prelude::*;
 use futures::sync::oneshot;
-use js_sys::{Function, Promise};
+use js_sys::{Function, Object, Promise};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+#[cfg(feature = "std_future")]
+pub mod std_future;
+
+// Used by `#[wasm_bindgen]` to turn the future of an `async` constructor into a
+// `Promise`, so that forgetting the `std_future` feature is reported as such
+// rather than as a missing `std_future` module.
+#[cfg(feature = "std_future")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __wbindgen_async_constructor {
+    ($($future:tt)*) => {
+        $crate::std_future::future_to_promise($($future)*)
+    };
+}
+
+#[cfg(not(feature = "std_future"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __wbindgen_async_constructor {
+    ($($future:tt)*) => {
+        compile_error!(
+            "`async` constructors require the `std_future` feature of \
+             `wasm-bindgen-futures`, which isn't enabled"
+        )
+    };
+}
 
 /// A Rust `Future` backed by a JavaScript `Promise`.
 ///
@@ -397,3 +430,14 @@ where
             .or_else(|()| future::ok::<JsValue, JsValue>(JsValue::undefined())),
     );
 }
+
+/// Dynamically imports the JS module `specifier`, resolving to the module's
+/// namespace object.
+///
+/// This is a typed wrapper around `wasm_bindgen::import_module`, see its
+/// documentation for more details. The returned future fails with the error
+/// thrown by the import if the module can't be loaded.
+pub fn import_module(specifier: &str) -> impl Future<Item = Object, Error = JsValue> {
+    let promise: Promise = wasm_bindgen::import_module(specifier).unchecked_into();
+    JsFuture::from(promise).map(|module| module.unchecked_into())
+}
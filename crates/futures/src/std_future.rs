@@ -0,0 +1,130 @@
+//! Converting `std::future::Future`s into JavaScript `Promise`s.
+//!
+//! This module is enabled with the `std_future` feature of this crate, and is
+//! what `async` constructors exported with `#[wasm_bindgen(constructor)]` use
+//! to return a `Promise` of their instance to JavaScript.
+
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::mem::ManuallyDrop;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use js_sys::{Function, Promise};
+use wasm_bindgen::prelude::*;
+
+/// Converts a Rust `Future<Output = Result<JsValue, JsValue>>` into a
+/// JavaScript `Promise`.
+///
+/// This is the `std::future::Future` counterpart of the crate's
+/// [`future_to_promise`](../fn.future_to_promise.html), and it has the same
+/// panic behavior: if the `future` panics then the returned `Promise` will
+/// never resolve.
+pub fn future_to_promise<F>(future: F) -> Promise
+where
+    F: Future<Output = Result<JsValue, JsValue>> + 'static,
+{
+    let mut future = Some(future);
+    Promise::new(&mut |resolve, reject| {
+        let task = Rc::new(Task {
+            future: RefCell::new(Some(Box::pin(future.take().unwrap()))),
+            resolve,
+            reject,
+            queued: Cell::new(false),
+        });
+        Task::run(&task);
+    })
+}
+
+struct Task {
+    // The future being driven, which is `None` once it has completed.
+    future: RefCell<Option<Pin<Box<Future<Output = Result<JsValue, JsValue>>>>>>,
+
+    // Our two callbacks connected to the `Promise` that we returned to
+    // JavaScript.
+    resolve: Function,
+    reject: Function,
+
+    // Whether a poll of the future has been scheduled on the microtask queue
+    // and hasn't run yet, to coalesce several wakeups into one poll.
+    queued: Cell<bool>,
+}
+
+impl Task {
+    fn run(me: &Rc<Task>) {
+        me.queued.set(false);
+        let result = {
+            let mut slot = me.future.borrow_mut();
+            let poll = match slot.as_mut() {
+                Some(future) => {
+                    let waker = waker(me.clone());
+                    future.as_mut().poll(&mut Context::from_waker(&waker))
+                }
+                None => return,
+            };
+            match poll {
+                Poll::Ready(result) => {
+                    *slot = None;
+                    result
+                }
+                Poll::Pending => return,
+            }
+        };
+        let (val, f) = match result {
+            Ok(value) => (value, &me.resolve),
+            Err(value) => (value, &me.reject),
+        };
+        drop(f.call1(&JsValue::undefined(), &val));
+    }
+
+    fn wake(me: Rc<Task>) {
+        if me.queued.replace(true) {
+            return;
+        }
+
+        // Like the futures 0.1 executor we don't poll immediately, but rather
+        // on the next turn of the microtask queue, which also avoids polling
+        // the future while it's already being polled.
+        let promise = Promise::resolve(&JsValue::undefined());
+        let slot = Rc::new(RefCell::new(None));
+        let slot2 = slot.clone();
+        let closure = Closure::wrap(Box::new(move |_| {
+            let myself = slot2.borrow_mut().take();
+            debug_assert!(myself.is_some());
+            Task::run(&me);
+        }) as Box<FnMut(JsValue)>);
+        promise.then(&closure);
+        *slot.borrow_mut() = Some(closure);
+    }
+}
+
+// Wasm is single threaded, so our wakers are plain `Rc`s of their task even
+// though `Waker` is `Send` and `Sync`.
+fn waker(task: Rc<Task>) -> Waker {
+    unsafe { Waker::from_raw(raw_waker(task)) }
+}
+
+fn raw_waker(task: Rc<Task>) -> RawWaker {
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop_waker);
+
+    unsafe fn clone(data: *const ()) -> RawWaker {
+        let task = ManuallyDrop::new(Rc::from_raw(data as *const Task));
+        raw_waker(Rc::clone(&task))
+    }
+
+    unsafe fn wake(data: *const ()) {
+        Task::wake(Rc::from_raw(data as *const Task));
+    }
+
+    unsafe fn wake_by_ref(data: *const ()) {
+        let task = ManuallyDrop::new(Rc::from_raw(data as *const Task));
+        Task::wake(Rc::clone(&task));
+    }
+
+    unsafe fn drop_waker(data: *const ()) {
+        drop(Rc::from_raw(data as *const Task));
+    }
+
+    RawWaker::new(Rc::into_raw(task) as *const (), &VTABLE)
+}
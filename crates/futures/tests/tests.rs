@@ -115,3 +115,35 @@ fn can_create_multiple_futures_from_same_promise() -> impl Future<Item = (), Err
     let b = JsFuture::from(promise);
     futures::future::join_all(vec![a, b]).map(|_| ())
 }
+
+#[wasm_bindgen_test(async)]
+fn import_module_loads_module() -> impl Future<Item = (), Error = JsValue> {
+    wasm_bindgen_futures::import_module("path").map(|path| {
+        let join = js_sys::Reflect::get(&path, &JsValue::from_str("join")).unwrap();
+        assert!(join.is_function());
+    })
+}
+
+#[wasm_bindgen_test(async)]
+fn import_module_missing_is_error() -> impl Future<Item = (), Error = JsValue> {
+    wasm_bindgen_futures::import_module("./this-module-does-not-exist.js")
+        .map(|_| unreachable!())
+        .or_else(|e| {
+            assert!(e.is_object());
+            Ok(())
+        })
+}
+
+#[wasm_bindgen(module = "path")]
+extern "C" {
+    #[wasm_bindgen(dynamic_import)]
+    fn import_path() -> js_sys::Promise;
+}
+
+#[wasm_bindgen_test(async)]
+fn dynamic_import_loads_module() -> impl Future<Item = (), Error = JsValue> {
+    JsFuture::from(import_path()).map(|path| {
+        let join = js_sys::Reflect::get(&path, &JsValue::from_str("join")).unwrap();
+        assert!(join.is_function());
+    })
+}
@@ -21,8 +21,14 @@
 extern crate wasm_bindgen;
 
 use std::fmt;
-use std::mem;
-
+use std::marker::PhantomData;
+use std::mem::{self, ManuallyDrop};
+use std::ops::Deref;
+
+use wasm_bindgen::convert::{
+    FromWasmAbi, IntoWasmAbi, OptionFromWasmAbi, OptionIntoWasmAbi, RefFromWasmAbi, Stack,
+};
+use wasm_bindgen::describe::{inform, WasmDescribe, GENERIC};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
@@ -196,6 +202,11 @@ extern "C" {
     #[wasm_bindgen(method, js_name = forEach)]
     pub fn for_each(this: &Array, callback: &mut FnMut(JsValue, u32, Array));
 
+    /// Retrieves the element at the index, or `undefined` if the index is out
+    /// of range.
+    #[wasm_bindgen(method, structural, indexing_getter)]
+    pub fn get(this: &Array, index: u32) -> JsValue;
+
     /// The includes() method determines whether an array includes a certain
     /// element, returning true or false as appropriate.
     ///
@@ -438,6 +449,81 @@ extern "C" {
     /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/ArrayBuffer/slice)
     #[wasm_bindgen(method, js_name = slice)]
     pub fn slice_with_end(this: &ArrayBuffer, begin: u32, end: u32) -> ArrayBuffer;
+
+    /// Creates a resizable `ArrayBuffer` of `length` bytes, which can later
+    /// be grown up to `options.maxByteLength` bytes with `resize()`.
+    ///
+    /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/ArrayBuffer/ArrayBuffer)
+    #[wasm_bindgen(constructor, catch)]
+    pub fn new_with_options(length: u32, options: &Object) -> Result<ArrayBuffer, JsValue>;
+
+    /// The `resizable` property is true if this `ArrayBuffer` was created
+    /// with a `maxByteLength` and can be resized.
+    ///
+    /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/ArrayBuffer/resizable)
+    #[wasm_bindgen(method, getter)]
+    pub fn resizable(this: &ArrayBuffer) -> bool;
+
+    /// The `maxByteLength` property is the length, in bytes, that this
+    /// `ArrayBuffer` can be resized to. For buffers which aren't resizable
+    /// this is the same as `byteLength`.
+    ///
+    /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/ArrayBuffer/maxByteLength)
+    #[wasm_bindgen(method, getter, js_name = maxByteLength)]
+    pub fn max_byte_length(this: &ArrayBuffer) -> u32;
+
+    /// The `detached` property is true once this `ArrayBuffer` has been
+    /// transferred, after which it's empty and can't be used anymore.
+    ///
+    /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/ArrayBuffer/detached)
+    #[wasm_bindgen(method, getter)]
+    pub fn detached(this: &ArrayBuffer) -> bool;
+
+    /// The `resize()` method resizes this `ArrayBuffer` to `new_length`
+    /// bytes. Bytes added by growing the buffer are zeroed.
+    ///
+    /// Throws if the buffer isn't resizable or `new_length` is larger than
+    /// its `maxByteLength`.
+    ///
+    /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/ArrayBuffer/resize)
+    #[wasm_bindgen(method, catch)]
+    pub fn resize(this: &ArrayBuffer, new_length: u32) -> Result<(), JsValue>;
+
+    /// The `transfer()` method moves the contents of this `ArrayBuffer` into
+    /// a new `ArrayBuffer` of the same length, without copying them where the
+    /// engine is able to, and detaches this one. The new buffer is resizable
+    /// if this one is.
+    ///
+    /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/ArrayBuffer/transfer)
+    #[wasm_bindgen(method, catch)]
+    pub fn transfer(this: &ArrayBuffer) -> Result<ArrayBuffer, JsValue>;
+
+    /// Like `transfer()` but the new buffer is `new_length` bytes long,
+    /// truncating the contents or padding them with zeroes.
+    ///
+    /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/ArrayBuffer/transfer)
+    #[wasm_bindgen(method, catch, js_name = transfer)]
+    pub fn transfer_with_length(
+        this: &ArrayBuffer,
+        new_length: u32,
+    ) -> Result<ArrayBuffer, JsValue>;
+
+    /// The `transferToFixedLength()` method is like `transfer()`, except that
+    /// the new buffer is never resizable.
+    ///
+    /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/ArrayBuffer/transferToFixedLength)
+    #[wasm_bindgen(method, catch, js_name = transferToFixedLength)]
+    pub fn transfer_to_fixed_length(this: &ArrayBuffer) -> Result<ArrayBuffer, JsValue>;
+
+    /// Like `transferToFixedLength()` but the new buffer is `new_length`
+    /// bytes long, truncating the contents or padding them with zeroes.
+    ///
+    /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/ArrayBuffer/transferToFixedLength)
+    #[wasm_bindgen(method, catch, js_name = transferToFixedLength)]
+    pub fn transfer_to_fixed_length_with_length(
+        this: &ArrayBuffer,
+        new_length: u32,
+    ) -> Result<ArrayBuffer, JsValue>;
 }
 
 // Array Iterator
@@ -717,6 +803,175 @@ extern "C" {
     pub fn set_float64_endian(this: &DataView, byte_offset: usize, value: f64, little_endian: bool);
 }
 
+/// Plain old data which can be read from and written to a `DataView` as a
+/// whole, like the numbers of a binary protocol frame or a `#[repr(C)]`
+/// struct of them.
+///
+/// Reading a value with [`DataView::read`] copies all of its bytes into wasm
+/// at once, rather than calling into JS for every field like `get_uint16`
+/// and friends do.
+///
+/// # Safety
+///
+/// Any bit pattern must be a valid value of the type, and it can't contain
+/// padding, as values are created from and turned into raw bytes. Structs
+/// should be `#[repr(C)]` or `#[repr(transparent)]` for their layout to be
+/// that of the data being read.
+///
+/// ```no_run
+/// use js_sys::{DataView, Pod};
+///
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Header {
+///     kind: u16,
+///     flags: u16,
+///     len: u32,
+/// }
+///
+/// unsafe impl Pod for Header {
+///     fn swap_bytes(self) -> Header {
+///         Header {
+///             kind: self.kind.swap_bytes(),
+///             flags: self.flags.swap_bytes(),
+///             len: self.len.swap_bytes(),
+///         }
+///     }
+/// }
+///
+/// # fn parse(view: &DataView) {
+/// // Network byte order is big endian.
+/// let header: Header = view.read(0, false);
+/// # }
+/// ```
+pub unsafe trait Pod: Copy + 'static {
+    /// Reverses the order of the bytes of each number in `self`, converting
+    /// it between little and big endian.
+    fn swap_bytes(self) -> Self;
+}
+
+macro_rules! pod_ints {
+    ($($t:ident)*) => ($(
+        unsafe impl Pod for $t {
+            fn swap_bytes(self) -> $t {
+                $t::swap_bytes(self)
+            }
+        }
+    )*)
+}
+
+pod_ints!(u8 i8 u16 i16 u32 i32 u64 i64);
+
+unsafe impl Pod for f32 {
+    fn swap_bytes(self) -> f32 {
+        f32::from_bits(self.to_bits().swap_bytes())
+    }
+}
+
+unsafe impl Pod for f64 {
+    fn swap_bytes(self) -> f64 {
+        f64::from_bits(self.to_bits().swap_bytes())
+    }
+}
+
+macro_rules! pod_arrays {
+    ($($n:tt)*) => ($(
+        unsafe impl<T: Pod> Pod for [T; $n] {
+            fn swap_bytes(mut self) -> [T; $n] {
+                for x in self.iter_mut() {
+                    *x = x.swap_bytes();
+                }
+                self
+            }
+        }
+    )*)
+}
+
+pod_arrays!(
+    0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16
+    17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32
+);
+
+impl DataView {
+    /// Reads a `T` at `byte_offset` from the start of the view, whose numbers
+    /// are little endian if `little_endian` is true and big endian otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `T` doesn't fit in the view at `byte_offset`.
+    pub fn read<T: Pod>(&self, byte_offset: usize, little_endian: bool) -> T {
+        // `Pod` types are valid for any bytes, including all zeros.
+        let mut value = unsafe { mem::zeroed::<T>() };
+        self.read_slice(byte_offset, std::slice::from_mut(&mut value), little_endian);
+        value
+    }
+
+    /// Reads `dst.len()` consecutive values of `T` at `byte_offset` from the
+    /// start of the view into `dst`, all in one go.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values don't fit in the view at `byte_offset`.
+    pub fn read_slice<T: Pod>(&self, byte_offset: usize, dst: &mut [T], little_endian: bool) {
+        let len = dst.len() * mem::size_of::<T>();
+        let bytes = unsafe { std::slice::from_raw_parts_mut(dst.as_mut_ptr() as *mut u8, len) };
+        self.bytes(byte_offset, len).copy_to(bytes);
+        if little_endian != cfg!(target_endian = "little") {
+            for x in dst.iter_mut() {
+                *x = x.swap_bytes();
+            }
+        }
+    }
+
+    /// Writes `value` at `byte_offset` from the start of the view, with its
+    /// numbers little endian if `little_endian` is true and big endian
+    /// otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `T` doesn't fit in the view at `byte_offset`.
+    pub fn write<T: Pod>(&self, byte_offset: usize, value: T, little_endian: bool) {
+        self.write_slice(byte_offset, &[value], little_endian);
+    }
+
+    /// Writes all of `src` at `byte_offset` from the start of the view, all
+    /// in one go.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values don't fit in the view at `byte_offset`.
+    pub fn write_slice<T: Pod>(&self, byte_offset: usize, src: &[T], little_endian: bool) {
+        let swapped;
+        let src = if little_endian != cfg!(target_endian = "little") {
+            swapped = src.iter().map(|x| x.swap_bytes()).collect::<Vec<_>>();
+            &swapped[..]
+        } else {
+            src
+        };
+        let len = src.len() * mem::size_of::<T>();
+        let bytes = unsafe { std::slice::from_raw_parts(src.as_ptr() as *const u8, len) };
+        self.bytes(byte_offset, len).copy_from_slice(bytes);
+    }
+
+    /// Returns a `Uint8Array` of the `len` bytes at `byte_offset` from the
+    /// start of the view.
+    fn bytes(&self, byte_offset: usize, len: usize) -> Uint8Array {
+        let fits = byte_offset
+            .checked_add(len)
+            .map_or(false, |end| end <= self.byte_length());
+        assert!(
+            fits,
+            "{} bytes at offset {} are out of bounds of a DataView",
+            len, byte_offset
+        );
+        Uint8Array::new_with_byte_offset_and_length(
+            &self.buffer(),
+            (self.byte_offset() + byte_offset) as u32,
+            len as u32,
+        )
+    }
+}
+
 // Error
 #[wasm_bindgen]
 extern "C" {
@@ -795,6 +1050,9 @@ extern "C" {
     /// An `Float32Array()` which creates an array with the given buffer but is a
     /// view starting at `byte_offset`.
     ///
+    /// If `buffer` is a resizable `ArrayBuffer` the array tracks its length,
+    /// growing and shrinking along with the buffer as it's resized.
+    ///
     /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Float32Array)
     #[wasm_bindgen(constructor)]
     pub fn new_with_byte_offset(buffer: &JsValue, byte_offset: u32) -> Float32Array;
@@ -884,6 +1142,9 @@ extern "C" {
     /// An `Float64Array()` which creates an array with the given buffer but is a
     /// view starting at `byte_offset`.
     ///
+    /// If `buffer` is a resizable `ArrayBuffer` the array tracks its length,
+    /// growing and shrinking along with the buffer as it's resized.
+    ///
     /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Float64Array)
     #[wasm_bindgen(constructor)]
     pub fn new_with_byte_offset(buffer: &JsValue, byte_offset: u32) -> Float64Array;
@@ -1108,6 +1369,9 @@ extern "C" {
     /// An `Int8Array()` which creates an array with the given buffer but is a
     /// view starting at `byte_offset`.
     ///
+    /// If `buffer` is a resizable `ArrayBuffer` the array tracks its length,
+    /// growing and shrinking along with the buffer as it's resized.
+    ///
     /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Int8Array)
     #[wasm_bindgen(constructor)]
     pub fn new_with_byte_offset(buffer: &JsValue, byte_offset: u32) -> Int8Array;
@@ -1197,6 +1461,9 @@ extern "C" {
     /// An `Int16Array()` which creates an array with the given buffer but is a
     /// view starting at `byte_offset`.
     ///
+    /// If `buffer` is a resizable `ArrayBuffer` the array tracks its length,
+    /// growing and shrinking along with the buffer as it's resized.
+    ///
     /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Int16Array)
     #[wasm_bindgen(constructor)]
     pub fn new_with_byte_offset(buffer: &JsValue, byte_offset: u32) -> Int16Array;
@@ -1286,6 +1553,9 @@ extern "C" {
     /// An `Int32Array()` which creates an array with the given buffer but is a
     /// view starting at `byte_offset`.
     ///
+    /// If `buffer` is a resizable `ArrayBuffer` the array tracks its length,
+    /// growing and shrinking along with the buffer as it's resized.
+    ///
     /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Int32Array)
     #[wasm_bindgen(constructor)]
     pub fn new_with_byte_offset(buffer: &JsValue, byte_offset: u32) -> Int32Array;
@@ -3075,6 +3345,9 @@ extern "C" {
     /// An `Uint8Array()` which creates an array with the given buffer but is a
     /// view starting at `byte_offset`.
     ///
+    /// If `buffer` is a resizable `ArrayBuffer` the array tracks its length,
+    /// growing and shrinking along with the buffer as it's resized.
+    ///
     /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Uint8Array)
     #[wasm_bindgen(constructor)]
     pub fn new_with_byte_offset(buffer: &JsValue, byte_offset: u32) -> Uint8Array;
@@ -3166,6 +3439,9 @@ extern "C" {
     /// An `Uint8ClampedArray()` which creates an array with the given buffer but is a
     /// view starting at `byte_offset`.
     ///
+    /// If `buffer` is a resizable `ArrayBuffer` the array tracks its length,
+    /// growing and shrinking along with the buffer as it's resized.
+    ///
     /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Uint8ClampedArray)
     #[wasm_bindgen(constructor)]
     pub fn new_with_byte_offset(buffer: &JsValue, byte_offset: u32) -> Uint8ClampedArray;
@@ -3255,6 +3531,9 @@ extern "C" {
     /// An `Uint16Array()` which creates an array with the given buffer but is a
     /// view starting at `byte_offset`.
     ///
+    /// If `buffer` is a resizable `ArrayBuffer` the array tracks its length,
+    /// growing and shrinking along with the buffer as it's resized.
+    ///
     /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Uint16Array)
     #[wasm_bindgen(constructor)]
     pub fn new_with_byte_offset(buffer: &JsValue, byte_offset: u32) -> Uint16Array;
@@ -3338,6 +3617,9 @@ extern "C" {
     /// An `Uint32Array()` which creates an array with the given buffer but is a
     /// view starting at `byte_offset`.
     ///
+    /// If `buffer` is a resizable `ArrayBuffer` the array tracks its length,
+    /// growing and shrinking along with the buffer as it's resized.
+    ///
     /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Uint32Array)
     #[wasm_bindgen(constructor)]
     pub fn new_with_byte_offset(buffer: &JsValue, byte_offset: u32) -> Uint32Array;
@@ -4377,6 +4659,14 @@ extern "C" {
     #[derive(Clone, Debug)]
     pub type Symbol;
 
+    /// The `Symbol.asyncIterator` well-known symbol specifies the default
+    /// async iterator for an object. If this property is set on an object,
+    /// it is an async iterable and can be used in a `for await...of` loop.
+    ///
+    /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Symbol/asyncIterator)
+    #[wasm_bindgen(static_method_of = Symbol, getter, structural, js_name = asyncIterator)]
+    pub fn async_iterator() -> Symbol;
+
     /// The `Symbol.hasInstance` well-known symbol is used to determine
     /// if a constructor object recognizes an object as its instance.
     /// The `instanceof` operator's behavior can be customized by this symbol.
@@ -4408,6 +4698,15 @@ extern "C" {
     #[wasm_bindgen(static_method_of = Symbol, getter, structural, js_name = match)]
     pub fn match_() -> Symbol;
 
+    /// The `Symbol.matchAll` well-known symbol specifies the method that
+    /// returns an iterator of all matches of a regular expression against a
+    /// string. This function is called by the `String.prototype.matchAll()`
+    /// method.
+    ///
+    /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Symbol/matchAll)
+    #[wasm_bindgen(static_method_of = Symbol, getter, structural, js_name = matchAll)]
+    pub fn match_all() -> Symbol;
+
     /// The `Symbol.replace` well-known symbol specifies the method that
     /// replaces matched substrings of a string.  This function is called by the
     /// `String.prototype.replace()` method.
@@ -4478,6 +4777,13 @@ extern "C" {
     #[wasm_bindgen(static_method_of = Symbol, js_name = keyFor)]
     pub fn key_for(sym: &Symbol) -> JsValue;
 
+    /// The `description` property returns the optional description of a
+    /// symbol, or `undefined` if it was created without one.
+    ///
+    /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Symbol/description)
+    #[wasm_bindgen(method, getter, structural)]
+    pub fn description(this: &Symbol) -> JsValue;
+
     /// The toString() method returns a string representing the specified Symbol object.
     ///
     /// [MDN documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Symbol/toString)
@@ -4499,6 +4805,40 @@ extern "C" {
     pub fn value_of(this: &Symbol) -> Symbol;
 }
 
+impl Symbol {
+    /// Defines a method keyed by this symbol on `target`, which calls `f`.
+    ///
+    /// Like the methods of a class, the method is writable and configurable
+    /// but not enumerable. This is mostly useful for implementing protocols
+    /// keyed by well-known symbols, for example making an object iterable:
+    ///
+    /// ```no_run
+    /// use js_sys::{Object, Symbol};
+    /// use wasm_bindgen::prelude::*;
+    ///
+    /// # fn make_iterator() -> JsValue { JsValue::undefined() }
+    /// let iterable = Object::new();
+    /// let f = Closure::wrap(Box::new(make_iterator) as Box<FnMut() -> JsValue>);
+    /// Symbol::iterator().define_method(&iterable, &f);
+    /// f.forget();
+    /// ```
+    ///
+    /// The method can only be called while `f` is alive, so it usually needs
+    /// to be forgotten or otherwise kept around as long as `target` is.
+    pub fn define_method<T: ?Sized>(&self, target: &Object, f: &Closure<T>) {
+        let descriptor = Object::new();
+        for (key, value) in &[
+            ("value", f.as_ref().clone()),
+            ("writable", JsValue::TRUE),
+            ("enumerable", JsValue::FALSE),
+            ("configurable", JsValue::TRUE),
+        ] {
+            Reflect::set(&descriptor, &JsValue::from(*key), value).unwrap_throw();
+        }
+        Object::define_property(target, self.as_ref(), &descriptor);
+    }
+}
+
 #[allow(non_snake_case)]
 pub mod Intl {
     use super::*;
@@ -4810,30 +5150,7 @@ extern "C" {
 /// This allows access to the global properties and global names by accessing
 /// the `Object` returned.
 pub fn global() -> Object {
-    thread_local!(static GLOBAL: Object = {
-        // According to StackOverflow you can access the global object via:
-        //
-        //      const global = Function('return this')();
-        //
-        // I think that's because the manufactured function isn't in "strict" mode.
-        // It also turns out that non-strict functions will ignore `undefined`
-        // values for `this` when using the `apply` function.
-        //
-        // As a result we use the equivalent of this snippet to get a handle to the
-        // global object in a sort of roundabout way that should hopefully work in
-        // all contexts like ESM, node, browsers, etc.
-        let this = Function::new_no_args("return this")
-            .call0(&JsValue::undefined())
-            .ok();
-
-        // Note that we avoid `unwrap()` on `call0` to avoid code size bloat, we
-        // just handle the `Err` case as returning a different object.
-        debug_assert!(this.is_some());
-        match this {
-            Some(this) => this.unchecked_into(),
-            None => JsValue::undefined().unchecked_into(),
-        }
-    });
+    thread_local!(static GLOBAL: Object = wasm_bindgen::global().unchecked_into());
 
     GLOBAL.with(|g| g.clone())
 }
@@ -4860,6 +5177,12 @@ macro_rules! arrays {
             /// Finally, the returned object is disconnected from the input
             /// slice's lifetime, so there's no guarantee that the data is read
             /// at the right time.
+            ///
+            /// Most uses of this function are better served by the safe
+            /// `copy_from_slice`, `copy_to_vec` and `move_into_wasm`, which
+            /// only use a view of wasm's memory for as long as the copy takes.
+            /// An invalidated view doesn't fail loudly, it's detached from the
+            /// grown memory and has a length of zero from then on.
             pub unsafe fn view(rust: &[$ty]) -> $name {
                 let buf = wasm_bindgen::memory();
                 let mem = buf.unchecked_ref::<WebAssembly::Memory>();
@@ -4889,6 +5212,45 @@ macro_rules! arrays {
                 let offset = dst.as_ptr() as usize / mem::size_of::<$ty>();
                 all_wasm_memory.set(self, offset as u32);
             }
+
+            /// Copy the contents of the source Rust slice into this JS typed
+            /// array.
+            ///
+            /// This function will efficiently copy the memory from this wasm
+            /// module's own linear memory into the typed array, without the
+            /// intermediate allocations of converting from a slice.
+            ///
+            /// # Panics
+            ///
+            /// This function will panic if this typed array's length is
+            /// different than the length of the provided `src` array.
+            pub fn copy_from_slice(&self, src: &[$ty]) {
+                assert_eq!(self.length() as usize, src.len());
+                // Nothing is allocated while the view exists, so it can't be
+                // invalidated before it's copied from.
+                let view = unsafe { $name::view(src) };
+                self.set(&view, 0);
+            }
+
+            /// Copy the contents of this JS typed array into a new `Vec`.
+            pub fn copy_to_vec(&self) -> Vec<$ty> {
+                self.move_into_wasm(Vec::new())
+            }
+
+            /// Copy the contents of this JS typed array into `dst`, replacing
+            /// what it contained, and return it.
+            ///
+            /// The allocation of `dst` is reused if it's large enough, so a
+            /// buffer can be refilled over and over without allocating, for
+            /// example in an audio callback.
+            pub fn move_into_wasm(&self, mut dst: Vec<$ty>) -> Vec<$ty> {
+                // Any growth of wasm's memory has to happen before the copy,
+                // which takes its own view of the memory.
+                dst.clear();
+                dst.resize(self.length() as usize, 0 as $ty);
+                self.copy_to(&mut dst);
+                dst
+            }
         }
     )*)
 }
@@ -4904,3 +5266,317 @@ arrays! {
     Float32Array: f32,
     Float64Array: f64,
 }
+
+// Typed containers
+//
+// These wrap the untyped `Array`, `Map` and `Promise` with the types of what
+// they contain, so imported functions can be declared as returning, say, an
+// `ArrayOf<Item>` rather than an `Array` of anything. They're passed to and
+// from JS exactly like the types they wrap, and show up as `Array<Item>` and
+// so on in the generated TypeScript.
+//
+// Nothing about the contents is checked when they cross into Rust. Accessing
+// elements casts them unchecked to the declared type, asserting in debug
+// builds that objects are instances of it, and the `try_` accessors check each
+// element with `dyn_into` instead.
+
+macro_rules! generic_types {
+    ($(
+        $(#[$meta:meta])*
+        $name:ident<$($param:ident),*>($inner:ident);
+    )*) => ($(
+        $(#[$meta])*
+        #[repr(transparent)]
+        pub struct $name<$($param),*> {
+            inner: $inner,
+            ty: PhantomData<($($param,)*)>,
+        }
+
+        impl<$($param),*> $name<$($param),*> {
+            /// Wraps an untyped value, assuming without any checks that what
+            /// it contains is of the declared types.
+            pub fn unchecked_from(inner: $inner) -> Self {
+                $name {
+                    inner,
+                    ty: PhantomData,
+                }
+            }
+
+            /// Returns the untyped value.
+            pub fn into_inner(self) -> $inner {
+                self.inner
+            }
+        }
+
+        impl<$($param),*> Deref for $name<$($param),*> {
+            type Target = $inner;
+
+            fn deref(&self) -> &$inner {
+                &self.inner
+            }
+        }
+
+        impl<$($param),*> AsRef<JsValue> for $name<$($param),*> {
+            fn as_ref(&self) -> &JsValue {
+                self.inner.as_ref()
+            }
+        }
+
+        impl<$($param),*> From<$name<$($param),*>> for JsValue {
+            fn from(value: $name<$($param),*>) -> JsValue {
+                value.inner.into()
+            }
+        }
+
+        impl<$($param),*> Clone for $name<$($param),*> {
+            fn clone(&self) -> Self {
+                $name::unchecked_from(self.inner.clone())
+            }
+        }
+
+        impl<$($param),*> fmt::Debug for $name<$($param),*> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.inner.fmt(f)
+            }
+        }
+
+        impl<$($param),*> JsCast for $name<$($param),*> {
+            fn instanceof(val: &JsValue) -> bool {
+                $inner::instanceof(val)
+            }
+
+            fn unchecked_from_js(val: JsValue) -> Self {
+                $name::unchecked_from($inner::unchecked_from_js(val))
+            }
+
+            fn unchecked_from_js_ref(val: &JsValue) -> &Self {
+                // This is a `#[repr(transparent)]` wrapper of the inner type.
+                let inner = $inner::unchecked_from_js_ref(val);
+                unsafe { &*(inner as *const $inner as *const Self) }
+            }
+
+            fn class_name() -> Option<&'static str> {
+                $inner::class_name()
+            }
+
+            fn has_methods(val: &JsValue) -> bool {
+                $inner::has_methods(val)
+            }
+        }
+
+        impl<$($param: WasmDescribe),*> IntoWasmAbi for $name<$($param),*> {
+            type Abi = <$inner as IntoWasmAbi>::Abi;
+
+            fn into_abi(self, extra: &mut Stack) -> Self::Abi {
+                self.inner.into_abi(extra)
+            }
+        }
+
+        impl<'a, $($param: WasmDescribe),*> IntoWasmAbi for &'a $name<$($param),*> {
+            type Abi = <&'a $inner as IntoWasmAbi>::Abi;
+
+            fn into_abi(self, extra: &mut Stack) -> Self::Abi {
+                (&self.inner).into_abi(extra)
+            }
+        }
+
+        impl<$($param: WasmDescribe),*> OptionIntoWasmAbi for $name<$($param),*> {
+            fn none() -> Self::Abi {
+                <$inner as OptionIntoWasmAbi>::none()
+            }
+        }
+
+        impl<'a, $($param: WasmDescribe),*> OptionIntoWasmAbi for &'a $name<$($param),*> {
+            fn none() -> Self::Abi {
+                <&'a $inner as OptionIntoWasmAbi>::none()
+            }
+        }
+
+        impl<$($param: WasmDescribe),*> FromWasmAbi for $name<$($param),*> {
+            type Abi = <$inner as FromWasmAbi>::Abi;
+
+            unsafe fn from_abi(js: Self::Abi, extra: &mut Stack) -> Self {
+                $name::unchecked_from($inner::from_abi(js, extra))
+            }
+        }
+
+        impl<$($param: WasmDescribe),*> OptionFromWasmAbi for $name<$($param),*> {
+            fn is_none(abi: &Self::Abi) -> bool {
+                <$inner as OptionFromWasmAbi>::is_none(abi)
+            }
+        }
+
+        impl<$($param: WasmDescribe),*> RefFromWasmAbi for $name<$($param),*> {
+            type Abi = <$inner as RefFromWasmAbi>::Abi;
+            type Anchor = ManuallyDrop<Self>;
+
+            unsafe fn ref_from_abi(js: Self::Abi, extra: &mut Stack) -> Self::Anchor {
+                let inner = ManuallyDrop::into_inner($inner::ref_from_abi(js, extra));
+                ManuallyDrop::new($name::unchecked_from(inner))
+            }
+        }
+    )*)
+}
+
+generic_types! {
+    /// An `Array` whose elements are all of type `T`.
+    ArrayOf<T>(Array);
+
+    /// A `Map` whose keys are of type `K` and values of type `V`.
+    MapOf<K, V>(Map);
+
+    /// A `Promise` which resolves to a value of type `T`.
+    PromiseOf<T>(Promise);
+}
+
+// The descriptors of the generic types are written out by hand as they can't
+// contain loops, so the names are spelled out character by character.
+
+impl<T: WasmDescribe> WasmDescribe for ArrayOf<T> {
+    fn describe() {
+        inform(GENERIC);
+        inform(5);
+        inform('A' as u32);
+        inform('r' as u32);
+        inform('r' as u32);
+        inform('a' as u32);
+        inform('y' as u32);
+        inform(1);
+        T::describe();
+    }
+}
+
+impl<K: WasmDescribe, V: WasmDescribe> WasmDescribe for MapOf<K, V> {
+    fn describe() {
+        inform(GENERIC);
+        inform(3);
+        inform('M' as u32);
+        inform('a' as u32);
+        inform('p' as u32);
+        inform(2);
+        K::describe();
+        V::describe();
+    }
+}
+
+impl<T: WasmDescribe> WasmDescribe for PromiseOf<T> {
+    fn describe() {
+        inform(GENERIC);
+        inform(7);
+        inform('P' as u32);
+        inform('r' as u32);
+        inform('o' as u32);
+        inform('m' as u32);
+        inform('i' as u32);
+        inform('s' as u32);
+        inform('e' as u32);
+        inform(1);
+        T::describe();
+    }
+}
+
+/// Casts an element of a typed container to its declared type without
+/// checking it, other than asserting in debug builds that objects are
+/// instances of it. Primitives aren't checked as they're never instances of
+/// anything, even when they're declared as, say, a `JsString`.
+fn unchecked_element<T: JsCast>(value: JsValue) -> T {
+    debug_assert!(
+        !value.is_object() || T::instanceof(&value),
+        "element of a typed container isn't of its declared type"
+    );
+    value.unchecked_into()
+}
+
+impl<T> ArrayOf<T> {
+    /// Creates a new empty array.
+    pub fn new() -> ArrayOf<T> {
+        ArrayOf::unchecked_from(Array::new())
+    }
+}
+
+impl<T> Default for ArrayOf<T> {
+    fn default() -> ArrayOf<T> {
+        ArrayOf::new()
+    }
+}
+
+impl<T: JsCast> ArrayOf<T> {
+    /// Returns the element at `index`, without checking that it's a `T`.
+    ///
+    /// Debug builds assert that the element is an instance of `T` if it's an
+    /// object.
+    pub fn get(&self, index: u32) -> T {
+        unchecked_element(self.inner.get(index))
+    }
+
+    /// Returns the element at `index` if it's a `T`, or the element itself
+    /// if it isn't.
+    pub fn try_get(&self, index: u32) -> Result<T, JsValue> {
+        self.inner.get(index).dyn_into()
+    }
+
+    /// Adds an element to the end of the array, returning the new length of
+    /// the array.
+    pub fn push(&self, value: &T) -> u32 {
+        self.inner.push(value.as_ref())
+    }
+
+    /// Iterates over the elements of the array, as with `get`.
+    pub fn iter<'a>(&'a self) -> impl std::iter::Iterator<Item = T> + 'a {
+        (0..self.inner.length()).map(move |i| self.get(i))
+    }
+
+    /// Copies the elements of the array into a `Vec`, as with `get`.
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().collect()
+    }
+}
+
+impl<K, V> MapOf<K, V> {
+    /// Creates a new empty map.
+    pub fn new() -> MapOf<K, V> {
+        MapOf::unchecked_from(Map::new())
+    }
+}
+
+impl<K, V> Default for MapOf<K, V> {
+    fn default() -> MapOf<K, V> {
+        MapOf::new()
+    }
+}
+
+impl<K: JsCast, V: JsCast> MapOf<K, V> {
+    /// Returns the value of `key`, without checking that it's a `V`, or
+    /// `None` if the map doesn't contain `key`.
+    ///
+    /// Debug builds assert that the value is an instance of `V` if it's an
+    /// object.
+    pub fn get(&self, key: &K) -> Option<V> {
+        if !self.inner.has(key.as_ref()) {
+            return None;
+        }
+        Some(unchecked_element(self.inner.get(key.as_ref())))
+    }
+
+    /// Returns the value of `key` if it's a `V`, the value itself if it isn't,
+    /// or `None` if the map doesn't contain `key`.
+    pub fn try_get(&self, key: &K) -> Option<Result<V, JsValue>> {
+        if !self.inner.has(key.as_ref()) {
+            return None;
+        }
+        Some(self.inner.get(key.as_ref()).dyn_into())
+    }
+
+    /// Sets the value of `key`.
+    pub fn set(&self, key: &K, value: &V) -> &MapOf<K, V> {
+        self.inner.set(key.as_ref(), value.as_ref());
+        self
+    }
+}
+
+impl<T: JsCast> PromiseOf<T> {
+    /// Returns a promise resolved with `value`.
+    pub fn resolve(value: &T) -> PromiseOf<T> {
+        PromiseOf::unchecked_from(Promise::resolve(value.as_ref()))
+    }
+}
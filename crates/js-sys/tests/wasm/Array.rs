@@ -1,6 +1,6 @@
 use js_sys::*;
+use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use wasm_bindgen::JsValue;
 use wasm_bindgen_test::*;
 
 macro_rules! js_array {
@@ -323,3 +323,29 @@ fn array_inheritance() {
     assert!(array.is_instance_of::<Object>());
     let _: &Object = array.as_ref();
 }
+
+#[wasm_bindgen_test]
+fn array_of() {
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = Array, js_name = of)]
+        fn dates(a: &Date, b: &Date) -> ArrayOf<Date>;
+        #[wasm_bindgen(js_namespace = Array, js_name = of)]
+        fn mixed(a: &Date, b: &JsValue) -> ArrayOf<Date>;
+    }
+
+    let epoch = Date::new(&0.into());
+    let later = Date::new(&1000.into());
+    let array = dates(&epoch, &later);
+    assert_eq!(array.length(), 2);
+    assert_eq!(array.get(1).get_time(), 1000.);
+    let times = array.iter().map(|d| d.get_time()).collect::<Vec<_>>();
+    assert_eq!(times, [0., 1000.]);
+
+    array.push(&Date::new(&2000.into()));
+    assert_eq!(array.to_vec().len(), 3);
+
+    let array = mixed(&epoch, &"not a date".into());
+    assert!(array.try_get(0).is_ok());
+    assert_eq!(array.try_get(1).unwrap_err(), "not a date");
+}
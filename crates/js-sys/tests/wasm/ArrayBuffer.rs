@@ -43,3 +43,62 @@ fn arraybuffer_inheritance() {
     assert!(buf.is_instance_of::<Object>());
     let _: &Object = buf.as_ref();
 }
+
+// Resizable buffers and transferring are recent additions, so these tests are
+// skipped on engines which don't support them yet.
+fn supports(buf: &ArrayBuffer, property: &str) -> bool {
+    Reflect::has(buf, &property.into()).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn resize() {
+    let opts = Object::new();
+    Reflect::set(&opts, &"maxByteLength".into(), &8.into()).unwrap();
+    let buf = ArrayBuffer::new_with_options(4, &opts).unwrap();
+    if !supports(&buf, "resizable") {
+        return;
+    }
+    assert!(buf.resizable());
+    assert_eq!(buf.max_byte_length(), 8);
+
+    let array = Uint8Array::new(&buf);
+    assert_eq!(array.length(), 4);
+    buf.resize(8).unwrap();
+    assert_eq!(buf.byte_length(), 8);
+    assert_eq!(array.length(), 8);
+    assert!(buf.resize(9).is_err());
+
+    let fixed = ArrayBuffer::new(4);
+    assert!(!fixed.resizable());
+    assert_eq!(fixed.max_byte_length(), 4);
+    assert!(fixed.resize(2).is_err());
+}
+
+#[wasm_bindgen_test]
+fn transfer() {
+    let buf = ArrayBuffer::new(4);
+    if !supports(&buf, "transfer") {
+        return;
+    }
+    Uint8Array::new(&buf).fill(7, 0, 4);
+    let moved = buf.transfer().unwrap();
+    assert!(buf.detached());
+    assert_eq!(buf.byte_length(), 0);
+    assert_eq!(moved.byte_length(), 4);
+    assert!(buf.transfer().is_err());
+
+    let longer = moved.transfer_with_length(6).unwrap();
+    let mut bytes = [0; 6];
+    Uint8Array::new(&longer).copy_to(&mut bytes);
+    assert_eq!(bytes, [7, 7, 7, 7, 0, 0]);
+
+    let opts = Object::new();
+    Reflect::set(&opts, &"maxByteLength".into(), &8.into()).unwrap();
+    let resizable = ArrayBuffer::new_with_options(4, &opts).unwrap();
+    assert!(resizable.transfer().unwrap().resizable());
+    let resizable = ArrayBuffer::new_with_options(4, &opts).unwrap();
+    let fixed = resizable.transfer_to_fixed_length().unwrap();
+    assert!(!fixed.resizable());
+    let fixed = fixed.transfer_to_fixed_length_with_length(2).unwrap();
+    assert_eq!(fixed.byte_length(), 2);
+}
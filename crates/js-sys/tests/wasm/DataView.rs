@@ -82,3 +82,65 @@ fn dataview_inheritance() {
     assert!(v.is_instance_of::<Object>());
     let _: &Object = v.as_ref();
 }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+struct Header {
+    kind: u16,
+    flags: u16,
+    len: u32,
+}
+
+unsafe impl Pod for Header {
+    fn swap_bytes(self) -> Header {
+        Header {
+            kind: self.kind.swap_bytes(),
+            flags: self.flags.swap_bytes(),
+            len: self.len.swap_bytes(),
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn read_and_write_pods() {
+    let bytes = Uint8Array::new(&JsValue::from(12));
+    bytes.copy_from_slice(&[0xff, 0xff, 0, 1, 0, 2, 0, 0, 0, 3, 0xff, 0xff]);
+    let v = DataView::new(&bytes.buffer(), 2, 8);
+
+    let header: Header = v.read(0, false);
+    assert_eq!(
+        header,
+        Header {
+            kind: 1,
+            flags: 2,
+            len: 3
+        }
+    );
+    assert_eq!(v.read::<u32>(4, false), 3);
+    assert_eq!(v.read::<u32>(4, true), 0x0300_0000);
+    assert_eq!(v.read::<[u8; 2]>(0, false), [0, 1]);
+
+    let mut halves = [0u16; 4];
+    v.read_slice(0, &mut halves, false);
+    assert_eq!(halves, [1, 2, 0, 3]);
+
+    v.write(
+        0,
+        Header {
+            kind: 4,
+            flags: 5,
+            len: 6,
+        },
+        true,
+    );
+    assert_eq!(v.get_uint16_endian(0, true), 4);
+    assert_eq!(v.get_uint16_endian(2, true), 5);
+    assert_eq!(v.get_uint32_endian(4, true), 6);
+
+    v.write_slice(0, &[1.5f32, -2.0], false);
+    assert_eq!(v.get_float32(0), 1.5);
+    assert_eq!(v.get_float32(4), -2.0);
+
+    // The view itself is only 8 bytes long.
+    assert_eq!(bytes.copy_to_vec()[10..], [0xff, 0xff]);
+}
@@ -1,5 +1,6 @@
 use js_sys::*;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 use wasm_bindgen_test::*;
 
 #[wasm_bindgen(module = "tests/wasm/Symbol.js")]
@@ -114,3 +115,42 @@ fn value_of() {
     let a = gensym(JsValue::undefined());
     assert_eq!(JsValue::from(a.value_of()), JsValue::from(a));
 }
+
+#[wasm_bindgen_test]
+fn async_iterator() {
+    assert_eq!(
+        Symbol::async_iterator().to_string(),
+        "Symbol(Symbol.asyncIterator)"
+    );
+}
+
+#[wasm_bindgen_test]
+fn match_all() {
+    assert_eq!(Symbol::match_all().to_string(), "Symbol(Symbol.matchAll)");
+}
+
+#[wasm_bindgen_test]
+fn description() {
+    assert_eq!(Symbol::for_("foo").description(), "foo");
+    assert_eq!(Symbol::iterator().description(), "Symbol.iterator");
+    assert!(gensym(JsValue::undefined()).description().is_undefined());
+}
+
+#[wasm_bindgen_test]
+fn define_method() {
+    let obj = Object::new();
+    let f = Closure::wrap(Box::new(|| JsValue::from(42)) as Box<FnMut() -> JsValue>);
+    Symbol::to_primitive().define_method(&obj, &f);
+
+    let method = Reflect::get(&obj, &Symbol::to_primitive()).unwrap();
+    let method = method.dyn_into::<Function>().unwrap();
+    assert_eq!(method.call0(&obj).unwrap(), 42);
+    assert_eq!(Object::keys(&obj).length(), 0);
+
+    let descriptor = Object::get_own_property_descriptor(&obj, &Symbol::to_primitive());
+    assert_eq!(Reflect::get(&descriptor, &"writable".into()).unwrap(), true);
+    assert_eq!(
+        Reflect::get(&descriptor, &"configurable".into()).unwrap(),
+        true
+    );
+}
@@ -121,3 +121,35 @@ fn copy_to() {
         assert_eq!(*i, 5);
     }
 }
+
+#[wasm_bindgen_test]
+fn copy_from_slice() {
+    let x = [1.5, 2.5, 3.5];
+    let array = Float32Array::new(&3.into());
+    array.copy_from_slice(&x);
+    array.for_each(&mut |x, i, _| {
+        assert_eq!(x, i as f32 + 1.5);
+    });
+}
+
+#[wasm_bindgen_test]
+fn copy_to_vec() {
+    let array = Float32Array::new(&4.into());
+    array.fill(2.0, 0, 4);
+    assert_eq!(array.copy_to_vec(), vec![2.0; 4]);
+    assert_eq!(Float32Array::new(&0.into()).copy_to_vec(), Vec::<f32>::new());
+}
+
+#[wasm_bindgen_test]
+fn move_into_wasm() {
+    let array = Float32Array::new(&3.into());
+    array.fill(7.0, 0, 3);
+    let buf = Vec::with_capacity(16);
+    let ptr = buf.as_ptr();
+    let buf = array.move_into_wasm(buf);
+    assert_eq!(buf, vec![7.0; 3]);
+    assert_eq!(buf.as_ptr(), ptr);
+
+    let buf = Float32Array::new(&1.into()).move_into_wasm(buf);
+    assert_eq!(buf, vec![0.0]);
+}
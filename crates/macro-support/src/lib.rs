@@ -47,10 +47,23 @@ pub fn expand_class_marker(
 ) -> Result<TokenStream, Diagnostic> {
     parser::reset_attrs_used();
     let mut item = syn::parse2::<syn::ImplItemMethod>(input)?;
-    let opts: ClassMarker = syn::parse2(attr)?;
+    let opts: ClassMarkers = syn::parse2(attr)?;
 
+    // Methods of generic impls are parsed once for each of their classes,
+    // with the type parameter replaced in their signature.
     let mut program = backend::ast::Program::default();
-    item.macro_parse(&mut program, (&opts.class, &opts.js_class))?;
+    let mut attrs = None;
+    for marker in opts.0.iter() {
+        let mut method = item.clone();
+        if let Some((param, ty)) = &marker.instance {
+            parser::substitute_type_param_in_sig(&mut method.sig, param, ty)?;
+        }
+        method.macro_parse(&mut program, (&marker.class, &marker.js_class))?;
+        attrs = Some(method.attrs);
+    }
+    if let Some(attrs) = attrs {
+        item.attrs = attrs;
+    }
     parser::assert_all_attrs_checked(); // same as above
 
     // This is where things are slightly different, we are being expanded in the
@@ -90,9 +103,19 @@ pub fn expand_class_marker(
     Ok(tokens)
 }
 
+struct ClassMarkers(syn::punctuated::Punctuated<ClassMarker, Token![,]>);
+
 struct ClassMarker {
     class: syn::Ident,
     js_class: String,
+    /// The type parameter of a generic impl and the type it is for `class`.
+    instance: Option<(syn::Ident, syn::Type)>,
+}
+
+impl Parse for ClassMarkers {
+    fn parse(input: ParseStream) -> SynResult<Self> {
+        syn::punctuated::Punctuated::parse_terminated(input).map(ClassMarkers)
+    }
 }
 
 impl Parse for ClassMarker {
@@ -100,6 +123,19 @@ impl Parse for ClassMarker {
         let class = input.parse::<syn::Ident>()?;
         input.parse::<Token![=]>()?;
         let js_class = input.parse::<syn::LitStr>()?.value();
-        Ok(ClassMarker { class, js_class })
+        let instance = if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let param = content.parse::<syn::Ident>()?;
+            content.parse::<Token![=]>()?;
+            Some((param, content.parse::<syn::Type>()?))
+        } else {
+            None
+        };
+        Ok(ClassMarker {
+            class,
+            js_class,
+            instance,
+        })
     }
 }
@@ -1,4 +1,7 @@
 use std::cell::Cell;
+use std::env;
+use std::fs;
+use std::path::Path;
 
 use backend::ast;
 use backend::util::{ident_ty, ShortHash};
@@ -33,6 +36,7 @@ macro_rules! attrgen {
             (static_method_of, StaticMethodOf(Span, Ident)),
             (js_namespace, JsNamespace(Span, Ident)),
             (module, Module(Span, String, Span)),
+            (inline_js, InlineJs(Span, String, Span)),
             (getter, Getter(Span, Option<Ident>)),
             (setter, Setter(Span, Option<Ident>)),
             (indexing_getter, IndexingGetter(Span)),
@@ -41,13 +45,37 @@ macro_rules! attrgen {
             (structural, Structural(Span)),
             (final_("final"), Final(Span)),
             (readonly, Readonly(Span)),
+            (skip, Skip(Span)),
+            (skip_getter, SkipGetter(Span)),
+            (skip_setter, SkipSetter(Span)),
+            (getter_with_clone, GetterWithClone(Span)),
+            (accessor, Accessor(Span)),
+            (validate, Validate(Span, String, Span)),
             (js_name, JsName(Span, String, Span)),
             (js_class, JsClass(Span, String, Span)),
             (extends, Extends(Span, syn::Path)),
             (vendor_prefix, VendorPrefix(Span, Ident)),
             (variadic, Variadic(Span)),
+            (this, This(Span)),
+            (main_thread, MainThread(Span)),
+            (dynamic_import, DynamicImport(Span)),
+            (static_getter, StaticGetter(Span)),
+            (cached, Cached(Span)),
+            (call, Call(Span)),
             (typescript_custom_section, TypescriptCustomSection(Span)),
             (start, Start(Span)),
+            (lazy, Lazy(Span)),
+            (maps_as_objects, MapsAsObjects(Span)),
+            (flags, Flags(Span)),
+            (js_tostring, JsToString(Span)),
+            (js_equals, JsEquals(Span)),
+            (js_hash, JsHash(Span)),
+            (json, Json(Span)),
+            (enumerable, Enumerable(Span)),
+            (frozen, Frozen(Span)),
+            (plain, Plain(Span)),
+            (ts_brand, TsBrand(Span, String, Span)),
+            (instantiate, Instantiate(Span, Vec<(syn::Type, String)>)),
         }
     };
 }
@@ -238,6 +266,23 @@ impl Parse for BindgenAttr {
                 return Ok(BindgenAttr::$variant(attr_span, input.parse()?));
             });
 
+            (@parser $variant:ident(Span, Vec<(syn::Type, String)>)) => ({
+                let content;
+                parenthesized!(content in input);
+                let mut list = Vec::new();
+                while !content.is_empty() {
+                    let ty = content.parse::<syn::Type>()?;
+                    content.parse::<Token![=]>()?;
+                    let name = content.parse::<syn::LitStr>()?.value();
+                    list.push((ty, name));
+                    if content.is_empty() {
+                        break;
+                    }
+                    content.parse::<Token![,]>()?;
+                }
+                return Ok(BindgenAttr::$variant(attr_span, list))
+            });
+
             (@parser $variant:ident(Span, String, Span)) => ({
                 input.parse::<Token![=]>()?;
                 let (val, span) = match input.parse::<syn::LitStr>() {
@@ -288,38 +333,103 @@ impl<'a> ConvertToAst<BindgenAttrs> for &'a mut syn::ItemStruct {
     type Target = ast::Struct;
 
     fn convert(self, opts: BindgenAttrs) -> Result<Self::Target, Diagnostic> {
-        if self.generics.params.len() > 0 {
-            bail_span!(
-                self.generics,
-                "structs with #[wasm_bindgen] cannot have lifetime or \
-                 type parameters currently"
-            );
+        match opts.instantiate() {
+            Some(_) => {
+                if let Some((_, span)) = opts.js_name() {
+                    return Err(Diagnostic::span_error(
+                        span,
+                        "generic structs are named in JS by `instantiate`, not `js_name`",
+                    ));
+                }
+                type_param(&self.generics)?;
+            }
+            None if self.generics.params.len() > 0 => {
+                return Err(err_span!(
+                    self.generics,
+                    "structs with #[wasm_bindgen] cannot have lifetime or \
+                     type parameters currently"
+                )
+                .help(
+                    "list the types to export a generic struct for with \
+                     `#[wasm_bindgen(instantiate(u8 = \"FooU8\"))]`",
+                ));
+            }
+            None => {}
         }
         let mut fields = Vec::new();
+        let frozen = opts.frozen().is_some();
         let js_name = opts
             .js_name()
             .map(|s| s.0.to_string())
             .unwrap_or(self.ident.to_string());
         if let syn::Fields::Named(names) = &mut self.fields {
             for field in names.named.iter_mut() {
-                match field.vis {
-                    syn::Visibility::Public(..) => {}
-                    _ => continue,
-                }
+                let opts = BindgenAttrs::find(&mut field.attrs)?;
+                // Private fields are only exported when asked to with
+                // `accessor`, typically along with `validate`.
+                let exported = match field.vis {
+                    syn::Visibility::Public(..) => true,
+                    _ => opts.accessor().is_some(),
+                };
                 let name = match &field.ident {
-                    Some(n) => n,
-                    None => continue,
+                    Some(n) if exported && opts.skip().is_none() => n,
+                    _ => {
+                        opts.check_used()?;
+                        continue;
+                    }
                 };
-                let name_str = name.to_string();
-                let getter = shared::struct_field_get(&js_name, &name_str);
-                let setter = shared::struct_field_set(&js_name, &name_str);
-                let opts = BindgenAttrs::find(&mut field.attrs)?;
+                let field_js_name = opts
+                    .js_name()
+                    .map(|s| s.0.to_string())
+                    .unwrap_or(name.to_string());
+                let getter = shared::struct_field_get(&js_name, &field_js_name);
+                let setter = shared::struct_field_set(&js_name, &field_js_name);
                 assert_not_variadic(&opts)?;
                 let comments = extract_doc_comments(&field.attrs);
+                // `skip_setter` is another name for `readonly`, to go along
+                // with `skip_getter`.
+                let readonly = frozen || opts.readonly().is_some() || opts.skip_setter().is_some();
+                let writeonly = opts.skip_getter().is_some();
+                let getter_with_clone = opts.getter_with_clone().is_some();
+                if let Some(span) = opts.skip_getter() {
+                    if frozen {
+                        return Err(Diagnostic::span_error(
+                            *span,
+                            "fields of frozen structs can't be set from JS, so \
+                             they can't skip their getter",
+                        ));
+                    }
+                    if readonly {
+                        return Err(Diagnostic::span_error(
+                            *span,
+                            "fields can't skip both their getter and setter, use `skip` instead",
+                        ));
+                    }
+                    if getter_with_clone {
+                        return Err(Diagnostic::span_error(
+                            *span,
+                            "fields without a getter can't be `getter_with_clone`",
+                        ));
+                    }
+                }
+                let validate = match opts.validate() {
+                    Some((_, span)) if readonly => {
+                        return Err(Diagnostic::span_error(
+                            span,
+                            "readonly fields can't be validated, as they can't be set from JS",
+                        ));
+                    }
+                    Some((path, span)) => Some(syn::LitStr::new(path, span).parse::<syn::Path>()?),
+                    None => None,
+                };
                 fields.push(ast::StructField {
                     name: name.clone(),
+                    js_name: field_js_name,
                     struct_name: self.ident.clone(),
-                    readonly: opts.readonly().is_some(),
+                    readonly,
+                    writeonly,
+                    getter_with_clone,
+                    validate,
                     ty: field.ty.clone(),
                     getter: Ident::new(&getter, Span::call_site()),
                     setter: Ident::new(&setter, Span::call_site()),
@@ -329,16 +439,220 @@ impl<'a> ConvertToAst<BindgenAttrs> for &'a mut syn::ItemStruct {
             }
         }
         let comments: Vec<String> = extract_doc_comments(&self.attrs);
+        let js_tostring = opts.js_tostring().is_some();
+        let js_equals = opts.js_equals().is_some();
+        let js_hash = opts.js_hash().is_some();
+        let json = opts.json().is_some();
+        let enumerable = opts.enumerable().is_some();
+        let plain = match opts.plain() {
+            Some(span) if frozen => {
+                return Err(Diagnostic::span_error(
+                    *span,
+                    "frozen structs are only freed once they've been garbage \
+                     collected, so they can't be converted to plain objects",
+                ));
+            }
+            Some(_) => true,
+            None => false,
+        };
         opts.check_used()?;
         Ok(ast::Struct {
             rust_name: self.ident.clone(),
             js_name,
             fields,
             comments,
+            js_tostring,
+            js_equals,
+            js_hash,
+            json,
+            enumerable,
+            frozen,
+            plain,
         })
     }
 }
 
+/// Returns the type parameter of a struct or impl with `instantiate`, which
+/// can only have exactly one.
+fn type_param(generics: &syn::Generics) -> Result<&Ident, Diagnostic> {
+    let mut params = generics.params.iter();
+    match (params.next(), params.next()) {
+        (Some(syn::GenericParam::Type(param)), None) => Ok(&param.ident),
+        _ => bail_span!(
+            generics,
+            "`instantiate` requires exactly one type parameter, and no lifetimes",
+        ),
+    }
+}
+
+/// Returns the Rust name of the type alias for an instance of a generic
+/// struct, which is the same as its class in JS.
+fn instance_ident(js_name: &str, ty: &syn::Type) -> Result<Ident, Diagnostic> {
+    match syn::parse_str::<Ident>(js_name) {
+        Ok(ident) => Ok(ident),
+        Err(_) => bail_span!(
+            ty,
+            "`{}` must be a valid Rust identifier to be the name of an instance",
+            js_name,
+        ),
+    }
+}
+
+/// Returns the struct `Name<ty>` of the generic struct `s` whose type
+/// parameter is `param`, exported as the class `js_name`.
+fn instantiate_struct(
+    s: &ast::Struct,
+    param: &Ident,
+    ty: &syn::Type,
+    js_name: &str,
+) -> Result<ast::Struct, Diagnostic> {
+    let alias = instance_ident(js_name, ty)?;
+    let mut instance = s.clone();
+    instance.rust_name = alias.clone();
+    instance.js_name = js_name.to_string();
+    for field in instance.fields.iter_mut() {
+        let getter = shared::struct_field_get(js_name, &field.js_name);
+        let setter = shared::struct_field_set(js_name, &field.js_name);
+        field.struct_name = alias.clone();
+        field.ty = substitute_type_param(&field.ty, param, ty)?;
+        field.getter = Ident::new(&getter, Span::call_site());
+        field.setter = Ident::new(&setter, Span::call_site());
+    }
+    Ok(instance)
+}
+
+/// Replaces the type parameter `param` with `ty` everywhere in `t`.
+fn substitute_type_param(
+    t: &syn::Type,
+    param: &Ident,
+    ty: &syn::Type,
+) -> Result<syn::Type, Diagnostic> {
+    fn substitute(tokens: TokenStream, param: &Ident, ty: &syn::Type) -> TokenStream {
+        let mut dst = TokenStream::new();
+        for tt in tokens {
+            match tt {
+                TokenTree::Ident(ref i) if i == param => ty.to_tokens(&mut dst),
+                TokenTree::Group(g) => {
+                    let mut group =
+                        proc_macro2::Group::new(g.delimiter(), substitute(g.stream(), param, ty));
+                    group.set_span(g.span());
+                    dst.extend(Some(TokenTree::Group(group)));
+                }
+                tt => dst.extend(Some(tt)),
+            }
+        }
+        dst
+    }
+    match syn::parse2(substitute(t.into_token_stream(), param, ty)) {
+        Ok(t) => Ok(t),
+        Err(_) => bail_span!(
+            t,
+            "can't use `{}` as the type parameter `{}` here",
+            quote::quote!(#ty),
+            param,
+        ),
+    }
+}
+
+/// Replaces the type parameter `param` with `ty` in the types of the arguments
+/// and the return type of `sig`, for a method of a generic impl.
+pub fn substitute_type_param_in_sig(
+    sig: &mut syn::MethodSig,
+    param: &Ident,
+    ty: &syn::Type,
+) -> Result<(), Diagnostic> {
+    for arg in sig.decl.inputs.iter_mut() {
+        if let syn::FnArg::Captured(arg) = arg {
+            arg.ty = substitute_type_param(&arg.ty, param, ty)?;
+        }
+    }
+    if let syn::ReturnType::Type(_, ret) = &mut sig.decl.output {
+        **ret = substitute_type_param(ret, param, ty)?;
+    }
+    Ok(())
+}
+
+/// The methods generated for a struct by attributes like
+/// `#[wasm_bindgen(js_tostring)]`, which are implemented with traits.
+fn generated_methods(s: &ast::Struct) -> Vec<ast::Export> {
+    let ty = &s.rust_name;
+    let method = |js_name: &str, rust_name: &str, arguments, ret| ast::Export {
+        rust_class: Some(s.rust_name.clone()),
+        js_class: Some(s.js_name.clone()),
+        method_self: Some(ast::MethodSelf::RefShared),
+        is_constructor: false,
+        is_async: false,
+        function: ast::Function {
+            name: js_name.to_string(),
+            name_span: s.rust_name.span(),
+            renamed_via_js_name: true,
+            arguments,
+            ret: Some(ret),
+            rust_attrs: Vec::new(),
+            rust_vis: syn::Visibility::Inherited,
+        },
+        comments: Vec::new(),
+        rust_name: Ident::new(rust_name, Span::call_site()),
+        start: false,
+        lazy: false,
+        maps_as_objects: false,
+    };
+    let mut exports = Vec::new();
+    if s.js_tostring {
+        // Formatted with `Display`
+        exports.push(method(
+            "toString",
+            "to_string",
+            Vec::new(),
+            syn::parse_quote!(::std::string::String),
+        ));
+    }
+    if s.js_equals {
+        // Compared with `PartialEq`
+        exports.push(method(
+            "equals",
+            "eq",
+            vec![syn::ArgCaptured {
+                pat: syn::parse_quote!(other),
+                colon_token: Default::default(),
+                ty: syn::parse_quote!(&#ty),
+            }],
+            syn::parse_quote!(bool),
+        ));
+    }
+    if s.js_hash {
+        // Hashed with `Hash`, through a method generated in the backend
+        exports.push(method(
+            "hashCode",
+            "__wbindgen_hash_code",
+            Vec::new(),
+            syn::parse_quote!(u32),
+        ));
+    }
+    if s.json {
+        // Converted with serde, through methods generated in the backend
+        exports.push(method(
+            "toJSON",
+            "__wbindgen_to_json",
+            Vec::new(),
+            syn::parse_quote!(::wasm_bindgen::JsValue),
+        ));
+        let mut from_json = method(
+            "fromJSON",
+            "__wbindgen_from_json",
+            vec![syn::ArgCaptured {
+                pat: syn::parse_quote!(obj),
+                colon_token: Default::default(),
+                ty: syn::parse_quote!(&::wasm_bindgen::JsValue),
+            }],
+            syn::parse_quote!(#ty),
+        );
+        from_json.method_self = None;
+        exports.push(from_json);
+    }
+    exports
+}
+
 impl<'a> ConvertToAst<(BindgenAttrs, &'a Option<String>)> for syn::ForeignItemFn {
     type Target = ast::ImportKind;
 
@@ -358,6 +672,11 @@ impl<'a> ConvertToAst<(BindgenAttrs, &'a Option<String>)> for syn::ForeignItemFn
         .0;
         let catch = opts.catch().is_some();
         let variadic = opts.variadic().is_some();
+        let bind_this = opts.this().is_some();
+        let main_thread = opts.main_thread().is_some();
+        let static_getter = opts.static_getter().is_some();
+        let dynamic_import = opts.dynamic_import().is_some();
+        let cached = opts.cached().is_some();
         let js_ret = if catch {
             // TODO: this assumes a whole bunch:
             //
@@ -387,6 +706,14 @@ impl<'a> ConvertToAst<(BindgenAttrs, &'a Option<String>)> for syn::ForeignItemFn
         if opts.indexing_deleter().is_some() {
             operation_kind = ast::OperationKind::IndexingDeleter;
         }
+        if let Some(span) = opts.call() {
+            if opts.static_method_of().is_none() {
+                let msg = "the `call` attribute can only be used together \
+                           with `static_method_of`";
+                return Err(Diagnostic::span_error(*span, msg));
+            }
+            operation_kind = ast::OperationKind::Call;
+        }
 
         let kind = if opts.method().is_some() {
             let class = wasm.arguments.get(0).ok_or_else(|| {
@@ -466,6 +793,87 @@ impl<'a> ConvertToAst<(BindgenAttrs, &'a Option<String>)> for syn::ForeignItemFn
             ast::ImportFunctionKind::Normal
         };
 
+        if let Some(span) = opts.this() {
+            if let ast::ImportFunctionKind::Method { .. } = kind {
+                let msg = "the `this` attribute can only be used on imported \
+                           functions which aren't methods or constructors";
+                return Err(Diagnostic::span_error(*span, msg));
+            }
+            if wasm.arguments.is_empty() {
+                let msg = "imported functions with `this` must have at least \
+                           one argument to pass as `this`";
+                return Err(Diagnostic::span_error(*span, msg));
+            }
+        }
+
+        if let Some(span) = opts.static_getter() {
+            if let ast::ImportFunctionKind::Method { .. } = kind {
+                let msg = "the `static_getter` attribute can't be used on methods \
+                           or constructors, use `static_method_of = Class, getter` \
+                           to read static properties of a class";
+                return Err(Diagnostic::span_error(*span, msg));
+            }
+            if variadic || bind_this || !wasm.arguments.is_empty() {
+                let msg = "imported functions with `static_getter` must not \
+                           take any arguments";
+                return Err(Diagnostic::span_error(*span, msg));
+            }
+            if wasm.ret.is_none() {
+                let msg = "imported functions with `static_getter` must return \
+                           the value they read";
+                return Err(Diagnostic::span_error(*span, msg));
+            }
+        }
+
+        if let Some(span) = opts.cached() {
+            let accessor = match &kind {
+                ast::ImportFunctionKind::Method {
+                    kind: ast::MethodKind::Operation(op),
+                    ..
+                } => match op.kind {
+                    ast::OperationKind::Getter(_) | ast::OperationKind::Setter(_) => !op.is_static,
+                    _ => false,
+                },
+                _ => false,
+            };
+            if !accessor || opts.final_().is_some() {
+                let msg = "the `cached` attribute can only be used on structural \
+                           getters and setters of methods";
+                return Err(Diagnostic::span_error(*span, msg));
+            }
+        }
+
+        if let Some(span) = opts.main_thread() {
+            if catch {
+                let msg = "the `main_thread` attribute can't be combined with \
+                           `catch`, as exceptions can't be passed between threads";
+                return Err(Diagnostic::span_error(*span, msg));
+            }
+        }
+
+        if let Some(span) = opts.dynamic_import() {
+            if module.is_none() {
+                let msg = "the `dynamic_import` attribute needs the module to \
+                           import to be specified with `module = \"...\"`";
+                return Err(Diagnostic::span_error(*span, msg));
+            }
+            if let ast::ImportFunctionKind::Method { .. } = kind {
+                let msg = "the `dynamic_import` attribute can't be used on \
+                           methods or constructors";
+                return Err(Diagnostic::span_error(*span, msg));
+            }
+            if catch || variadic || bind_this || static_getter || !wasm.arguments.is_empty() {
+                let msg = "imported functions with `dynamic_import` must not \
+                           take any arguments";
+                return Err(Diagnostic::span_error(*span, msg));
+            }
+            if wasm.ret.is_none() {
+                let msg = "imported functions with `dynamic_import` must return \
+                           the `Promise` of the module";
+                return Err(Diagnostic::span_error(*span, msg));
+            }
+        }
+
         let shim = {
             let ns = match kind {
                 ast::ImportFunctionKind::Normal => (0, "n"),
@@ -493,6 +901,11 @@ impl<'a> ConvertToAst<(BindgenAttrs, &'a Option<String>)> for syn::ForeignItemFn
             js_ret,
             catch,
             variadic,
+            bind_this,
+            main_thread,
+            static_getter,
+            dynamic_import,
+            cached,
             structural: opts.structural().is_some() || opts.final_().is_none(),
             rust_name: self.ident.clone(),
             shim: Ident::new(&shim, Span::call_site()),
@@ -514,6 +927,7 @@ impl ConvertToAst<BindgenAttrs> for syn::ForeignItemType {
             .map(|s| s.0)
             .map_or_else(|| self.ident.to_string(), |s| s.to_string());
         let shim = format!("__wbg_instanceof_{}_{}", self.ident, ShortHash(&self.ident));
+        let duck_shim = format!("__wbg_duck_{}_{}", self.ident, ShortHash(&self.ident));
         let mut extends = Vec::new();
         let mut vendor_prefixes = Vec::new();
         for (used, attr) in attrs.attrs.iter() {
@@ -535,10 +949,12 @@ impl ConvertToAst<BindgenAttrs> for syn::ForeignItemType {
             attrs: self.attrs,
             doc_comment: None,
             instanceof_shim: shim,
+            duck_shim,
             rust_name: self.ident,
             js_name,
             extends,
             vendor_prefixes,
+            interface: false,
         }))
     }
 }
@@ -582,16 +998,36 @@ impl ConvertToAst<BindgenAttrs> for syn::ItemFn {
     fn convert(self, attrs: BindgenAttrs) -> Result<Self::Target, Diagnostic> {
         match self.vis {
             syn::Visibility::Public(_) => {}
-            _ => bail_span!(self, "can only #[wasm_bindgen] public functions"),
+            _ => {
+                let (fn_token, ident) = (&self.decl.fn_token, &self.ident);
+                return Err(err_span!(
+                    quote::quote!(#fn_token #ident),
+                    "can only #[wasm_bindgen] public functions"
+                )
+                .help(format!("make the function public: `pub fn {}`", ident)));
+            }
         }
         if self.constness.is_some() {
-            bail_span!(
+            return Err(err_span!(
                 self.constness,
                 "can only #[wasm_bindgen] non-const functions"
-            );
+            )
+            .help("remove `const`, functions exported to JS are never called at compile time"));
         }
         if self.unsafety.is_some() {
-            bail_span!(self.unsafety, "can only #[wasm_bindgen] safe functions");
+            return Err(
+                err_span!(self.unsafety, "can only #[wasm_bindgen] safe functions").help(
+                    "export a safe wrapper function which upholds the invariants of \
+                     the unsafe function and calls it",
+                ),
+            );
+        }
+        if self.asyncness.is_some() {
+            return Err(err_span!(
+                self.asyncness,
+                "can only #[wasm_bindgen] non-async functions"
+            )
+            .help("return a `js_sys::Promise` created with `wasm_bindgen_futures` instead"));
         }
         assert_not_variadic(&attrs)?;
 
@@ -623,10 +1059,14 @@ fn function_from_decl(
         bail_span!(decl.variadic, "can't #[wasm_bindgen] variadic functions");
     }
     if decl.generics.params.len() > 0 {
-        bail_span!(
+        return Err(err_span!(
             decl.generics,
             "can't #[wasm_bindgen] functions with lifetime or type parameters",
-        );
+        )
+        .help(
+            "export a non-generic wrapper function for each type you need, \
+             which calls the generic function",
+        ));
     }
 
     assert_no_lifetimes(&decl)?;
@@ -658,7 +1098,7 @@ fn function_from_decl(
         .into_iter()
         .filter_map(|arg| match arg {
             syn::FnArg::Captured(mut c) => {
-                c.ty = replace_self(c.ty);
+                c.ty = replace_impl_trait(replace_self(c.ty));
                 Some(c)
             }
             syn::FnArg::SelfValue(_) => {
@@ -739,21 +1179,63 @@ impl<'a> MacroParse<(Option<BindgenAttrs>, &'a mut TokenStream)> for syn::Item {
                     if f.decl.inputs.len() > 0 {
                         bail_span!(&f.decl.inputs, "the start function cannot have arguments",);
                     }
+                    if let Some(span) = opts.lazy() {
+                        let msg = "the start function cannot be lazy";
+                        return Err(Diagnostic::span_error(*span, msg));
+                    }
                 }
                 program.exports.push(ast::Export {
                     rust_class: None,
                     js_class: None,
                     method_self: None,
                     is_constructor: false,
+                    is_async: false,
                     comments,
                     rust_name: f.ident.clone(),
                     start: opts.start().is_some(),
+                    lazy: opts.lazy().is_some(),
+                    maps_as_objects: opts.maps_as_objects().is_some(),
                     function: f.convert(opts)?,
                 });
             }
             syn::Item::Struct(mut s) => {
                 let opts = opts.unwrap_or_default();
-                program.structs.push((&mut s).convert(opts)?);
+                if let Some((brand, _)) = opts.ts_brand() {
+                    let brand = brand.to_string();
+                    opts.check_used()?;
+                    program.brands.push(brand_newtype(&s, brand)?);
+                    s.to_tokens(tokens);
+                    return Ok(());
+                }
+                let instances = opts.instantiate().cloned();
+                let s_ast = (&mut s).convert(opts)?;
+                match instances {
+                    Some(instances) => {
+                        let param = type_param(&s.generics)?;
+                        for (ty, js_name) in instances {
+                            let instance = instantiate_struct(&s_ast, param, &ty, &js_name)?;
+                            let (vis, name, alias) = (&s.vis, &s.ident, &instance.rust_name);
+                            let doc = format!(
+                                "`{}<{}>`, exported to JS as `{}`.",
+                                name,
+                                quote::quote!(#ty),
+                                js_name
+                            );
+                            (quote::quote! {
+                                #[doc = #doc]
+                                #[allow(non_camel_case_types)]
+                                #vis type #alias = #name<#ty>;
+                            })
+                            .to_tokens(tokens);
+                            program.exports.extend(generated_methods(&instance));
+                            program.structs.push(instance);
+                        }
+                    }
+                    None => {
+                        program.exports.extend(generated_methods(&s_ast));
+                        program.structs.push(s_ast);
+                    }
+                }
                 s.to_tokens(tokens);
             }
             syn::Item::Impl(mut i) => {
@@ -769,11 +1251,15 @@ impl<'a> MacroParse<(Option<BindgenAttrs>, &'a mut TokenStream)> for syn::Item {
                 f.macro_parse(program, opts)?;
             }
             syn::Item::Enum(e) => {
-                if let Some(opts) = opts {
-                    opts.check_used()?;
+                let opts = opts.unwrap_or_default();
+                // Imported enums aren't valid Rust as written, so they're only
+                // emitted once they've been converted.
+                if is_import_enum(&e) {
+                    import_enum(e, program, opts)?;
+                } else {
+                    e.to_tokens(tokens);
+                    e.macro_parse(program, opts)?;
                 }
-                e.to_tokens(tokens);
-                e.macro_parse(program, ())?;
             }
             syn::Item::Const(mut c) => {
                 let opts = match opts {
@@ -782,11 +1268,15 @@ impl<'a> MacroParse<(Option<BindgenAttrs>, &'a mut TokenStream)> for syn::Item {
                 };
                 c.macro_parse(program, opts)?;
             }
+            syn::Item::Trait(t) => {
+                let opts = opts.unwrap_or_default();
+                export_trait(t, program, opts, tokens)?;
+            }
             _ => {
                 bail_span!(
                     self,
                     "#[wasm_bindgen] can only be applied to a function, \
-                     struct, enum, impl, or extern block",
+                     struct, enum, impl, trait, or extern block",
                 );
             }
         }
@@ -814,27 +1304,71 @@ impl<'a> MacroParse<BindgenAttrs> for &'a mut syn::ItemImpl {
             );
         }
         if let Some((_, path, _)) = &self.trait_ {
-            bail_span!(path, "#[wasm_bindgen] trait impls are not supported");
-        }
-        if self.generics.params.len() > 0 {
-            bail_span!(
-                self.generics,
-                "#[wasm_bindgen] generic impls aren't supported"
+            return Err(
+                err_span!(path, "#[wasm_bindgen] trait impls are not supported").help(
+                    "export the methods from an inherent `impl` block instead, \
+                     calling the trait's methods from there",
+                ),
             );
         }
-        let name = match *self.self_ty {
-            syn::Type::Path(syn::TypePath {
-                qself: None,
-                ref path,
-            }) => extract_path_ident(path)?,
-            _ => bail_span!(
-                self.self_ty,
-                "unsupported self type in #[wasm_bindgen] impl"
-            ),
+        let classes = match opts.instantiate() {
+            Some(instances) => {
+                if let Some((_, span)) = opts.js_class() {
+                    return Err(Diagnostic::span_error(
+                        span,
+                        "generic impls are for the classes listed in `instantiate`, \
+                         not `js_class`",
+                    ));
+                }
+                let param = type_param(&self.generics)?;
+                match *self.self_ty {
+                    syn::Type::Path(syn::TypePath { qself: None, .. }) => {}
+                    _ => bail_span!(
+                        self.self_ty,
+                        "unsupported self type in #[wasm_bindgen] impl"
+                    ),
+                }
+                instances
+                    .iter()
+                    .map(|(ty, js_name)| {
+                        let class = instance_ident(js_name, ty)?;
+                        let substitution = quote::quote! { (#param = #ty) };
+                        Ok(quote::quote! { #class = #js_name #substitution })
+                    })
+                    .collect::<Result<Vec<_>, Diagnostic>>()?
+            }
+            None => {
+                if self.generics.params.len() > 0 {
+                    return Err(err_span!(
+                        self.generics,
+                        "#[wasm_bindgen] generic impls aren't supported"
+                    )
+                    .help(
+                        "list the types to export the methods for with \
+                         `#[wasm_bindgen(instantiate(u8 = \"FooU8\"))]`, or use a \
+                         separate `impl` block for each concrete type you need",
+                    ));
+                }
+                let name = match *self.self_ty {
+                    syn::Type::Path(syn::TypePath {
+                        qself: None,
+                        ref path,
+                    }) => extract_path_ident(path)?,
+                    _ => bail_span!(
+                        self.self_ty,
+                        "unsupported self type in #[wasm_bindgen] impl"
+                    ),
+                };
+                let js_class = opts
+                    .js_class()
+                    .map(|s| s.0.to_string())
+                    .unwrap_or(name.to_string());
+                vec![quote::quote! { #name = #js_class }]
+            }
         };
         let mut errors = Vec::new();
         for item in self.items.iter_mut() {
-            if let Err(e) = prepare_for_impl_recursion(item, &name, &opts) {
+            if let Err(e) = prepare_for_impl_recursion(item, &classes) {
                 errors.push(e);
             }
         }
@@ -852,10 +1386,13 @@ impl<'a> MacroParse<BindgenAttrs> for &'a mut syn::ItemImpl {
 // Note that we currently do this because inner items may have things like cfgs
 // on them, so we want to expand the impl first, let the insides get cfg'd, and
 // then go for the rest.
+//
+// Methods of generic impls are exported for each class of `instantiate`, so
+// the marker lists every class along with the type its type parameter stands
+// for in there.
 fn prepare_for_impl_recursion(
     item: &mut syn::ImplItem,
-    class: &Ident,
-    impl_opts: &BindgenAttrs,
+    classes: &[TokenStream],
 ) -> Result<(), Diagnostic> {
     let method = match item {
         syn::ImplItem::Method(m) => m,
@@ -883,11 +1420,6 @@ fn prepare_for_impl_recursion(
         syn::ImplItem::Verbatim(_) => panic!("unparsed impl item?"),
     };
 
-    let js_class = impl_opts
-        .js_class()
-        .map(|s| s.0.to_string())
-        .unwrap_or(class.to_string());
-
     method.attrs.insert(
         0,
         syn::Attribute {
@@ -895,7 +1427,7 @@ fn prepare_for_impl_recursion(
             style: syn::AttrStyle::Outer,
             bracket_token: Default::default(),
             path: syn::Ident::new("__wasm_bindgen_class_marker", Span::call_site()).into(),
-            tts: quote::quote! { (#class = #js_class) }.into(),
+            tts: quote::quote! { (#(#classes),*) }.into(),
         },
     );
 
@@ -922,13 +1454,26 @@ impl<'a, 'b> MacroParse<(&'a Ident, &'a str)> for &'b mut syn::ImplItemMethod {
             );
         }
         if self.sig.unsafety.is_some() {
-            bail_span!(self.sig.unsafety, "can only bindgen safe functions",);
+            return Err(
+                err_span!(self.sig.unsafety, "can only bindgen safe functions").help(
+                    "export a safe wrapper method which upholds the invariants of \
+                     the unsafe method and calls it",
+                ),
+            );
         }
 
         let opts = BindgenAttrs::find(&mut self.attrs)?;
         let comments = extract_doc_comments(&self.attrs);
         let is_constructor = opts.constructor().is_some();
-        let (function, method_self) = function_from_decl(
+        let is_async = self.sig.asyncness.is_some();
+        if is_async && !is_constructor {
+            return Err(err_span!(
+                self.sig.asyncness,
+                "only constructors can be `async` with #[wasm_bindgen]"
+            )
+            .help("return a `js_sys::Promise` created with `wasm_bindgen_futures` instead"));
+        }
+        let (mut function, method_self) = function_from_decl(
             &self.sig.ident,
             &opts,
             Box::new(self.sig.decl.clone()),
@@ -938,14 +1483,47 @@ impl<'a, 'b> MacroParse<(&'a Ident, &'a str)> for &'b mut syn::ImplItemMethod {
             Some(class),
         )?;
 
+        // JS constructors can't be asynchronous, so an `async` constructor is
+        // exported as a static `create` method returning a `Promise` instead.
+        if is_async {
+            if method_self.is_some() {
+                bail_span!(self.sig.decl.inputs, "constructors can't take `self`");
+            }
+            for arg in function.arguments.iter() {
+                if let syn::Type::Reference(_) = arg.ty {
+                    bail_span!(
+                        arg.ty,
+                        "arguments of `async` constructors can't be references",
+                    );
+                }
+            }
+            if function.ret.is_none() {
+                bail_span!(
+                    self.sig.ident,
+                    "`async` constructors must return `Result<Self, JsValue>`",
+                );
+            }
+            if !function.renamed_via_js_name {
+                function.name = "create".to_string();
+            }
+        }
+
+        if let Some(span) = opts.lazy() {
+            let msg = "only free functions can be lazy, not methods";
+            return Err(Diagnostic::span_error(*span, msg));
+        }
+
         program.exports.push(ast::Export {
             rust_class: Some(class.clone()),
             js_class: Some(js_class.to_string()),
             method_self,
-            is_constructor,
+            is_constructor: is_constructor && !is_async,
+            is_async,
             function,
             comments,
             start: false,
+            lazy: false,
+            maps_as_objects: opts.maps_as_objects().is_some(),
             rust_name: self.sig.ident.clone(),
         });
         opts.check_used()?;
@@ -953,17 +1531,40 @@ impl<'a, 'b> MacroParse<(&'a Ident, &'a str)> for &'b mut syn::ImplItemMethod {
     }
 }
 
-impl MacroParse<()> for syn::ItemEnum {
-    fn macro_parse(self, program: &mut ast::Program, (): ()) -> Result<(), Diagnostic> {
+impl MacroParse<BindgenAttrs> for syn::ItemEnum {
+    fn macro_parse(self, program: &mut ast::Program, opts: BindgenAttrs) -> Result<(), Diagnostic> {
+        let flags = opts.flags().is_some();
+        opts.check_used()?;
+
         match self.vis {
             syn::Visibility::Public(_) => {}
-            _ => bail_span!(self, "only public enums are allowed with #[wasm_bindgen]"),
+            _ => {
+                let (enum_token, ident) = (&self.enum_token, &self.ident);
+                return Err(err_span!(
+                    quote::quote!(#enum_token #ident),
+                    "only public enums are allowed with #[wasm_bindgen]"
+                )
+                .help(format!("make the enum public: `pub enum {}`", ident)));
+            }
         }
 
         if self.variants.len() == 0 {
             bail_span!(self, "cannot export empty enums to JS");
         }
 
+        let has_data = self.variants.iter().any(|v| match v.fields {
+            syn::Fields::Unit => false,
+            _ => true,
+        });
+        if has_data {
+            if flags {
+                bail_span!(self.ident, "flags enums with #[wasm_bindgen] can't have data");
+            }
+            program.tagged_unions.push(tagged_union(&self)?);
+            return Ok(());
+        }
+
+        let repr = enum_repr(&self.attrs)?;
         let has_discriminant = self.variants[0].discriminant.is_some();
 
         let variants = self
@@ -971,11 +1572,6 @@ impl MacroParse<()> for syn::ItemEnum {
             .iter()
             .enumerate()
             .map(|(i, v)| {
-                match v.fields {
-                    syn::Fields::Unit => (),
-                    _ => bail_span!(v.fields, "only C-Style enums allowed with #[wasm_bindgen]"),
-                }
-
                 // Require that everything either has a discriminant or doesn't.
                 // We don't really want to get in the business of emulating how
                 // rustc assigns values to enums.
@@ -987,65 +1583,647 @@ impl MacroParse<()> for syn::ItemEnum {
                 }
 
                 let value = match v.discriminant {
-                    Some((
-                        _,
-                        syn::Expr::Lit(syn::ExprLit {
-                            attrs: _,
-                            lit: syn::Lit::Int(ref int_lit),
-                        }),
-                    )) => {
-                        if int_lit.value() > <u32>::max_value() as u64 {
-                            bail_span!(
-                                int_lit,
-                                "enums with #[wasm_bindgen] can only support \
-                                 numbers that can be represented as u32"
-                            );
-                        }
-                        int_lit.value() as u32
-                    }
-                    None => i as u32,
-                    Some((_, ref expr)) => bail_span!(
-                        expr,
-                        "enums with #[wasm_bidngen] may only have \
-                         number literal values",
-                    ),
+                    Some((_, ref expr)) => eval_discriminant(expr, &repr)?,
+                    None => Some(i as i128),
                 };
 
-                Ok(ast::Variant {
-                    name: v.ident.clone(),
-                    value,
-                })
+                Ok((v.ident.clone(), value))
             })
             .collect::<Result<Vec<_>, Diagnostic>>()?;
 
-        let mut values = variants.iter().map(|v| v.value).collect::<Vec<_>>();
-        values.sort();
-        let hole = values
-            .windows(2)
-            .filter_map(|window| {
-                if window[0] + 1 != window[1] {
-                    Some(window[0] + 1)
-                } else {
-                    None
+        // The hole is a value of the ABI type which no variant has, and is used
+        // to represent `None`. Enums with 64-bit reprs don't support `Option`
+        // so it's unused for them.
+        //
+        // If any discriminant refers to something like a constant then we
+        // can't know its value here. In that case all the values are read from
+        // the compiled module instead, and the largest value is reserved for
+        // `None` so we don't have to know them to find a hole.
+        let (abi_min, abi_max) = repr.abi_range();
+        let dynamic_values = variants.iter().any(|(_, value)| value.is_none());
+
+        // Sets of flags are combined with JS's bitwise operators, which only
+        // work with 32 bits, and every flag has to be a single bit.
+        if flags {
+            if repr.is_64() {
+                bail_span!(
+                    repr.ty,
+                    "flags enums with #[wasm_bindgen] can't be represented as {}",
+                    repr.ty
+                );
+            }
+            for (v, (_, value)) in self.variants.iter().zip(variants.iter()) {
+                match value {
+                    Some(value) if value.count_ones() == 1 && *value > 0 => {}
+                    _ => {
+                        return Err(err_span!(
+                            v,
+                            "every variant of a flags enum with #[wasm_bindgen] \
+                             must be a single bit"
+                        )
+                        .help(format!("use values like `{} = 1 << 0`", v.ident)))
+                    }
                 }
-            })
-            .next()
-            .unwrap_or(*values.last().unwrap() + 1);
-        for value in values {
-            assert!(hole != value);
+            }
         }
+        let hole = if repr.is_64() {
+            0
+        } else if dynamic_values {
+            abi_max
+        } else {
+            let mut values = variants
+                .iter()
+                .filter_map(|(_, value)| *value)
+                .collect::<Vec<_>>();
+            values.sort();
+            values
+                .windows(2)
+                .filter_map(|window| {
+                    if window[0] + 1 != window[1] {
+                        Some(window[0] + 1)
+                    } else {
+                        None
+                    }
+                })
+                .next()
+                .or_else(|| Some(*values.last().unwrap() + 1).filter(|v| *v <= abi_max))
+                .unwrap_or(values[0] - 1)
+        };
+        assert!(hole >= abi_min);
 
+        let variants = variants
+            .into_iter()
+            .map(|(name, value)| ast::Variant {
+                name,
+                value: value.unwrap_or(0) as u64,
+            })
+            .collect::<Vec<_>>();
         let comments = extract_doc_comments(&self.attrs);
         program.enums.push(ast::Enum {
             name: self.ident,
             variants,
             comments,
-            hole,
+            repr: repr.ty,
+            hole: hole as u32,
+            dynamic_values,
+            flags,
         });
         Ok(())
     }
 }
 
+/// The integer type an exported enum is represented as.
+struct EnumRepr {
+    ty: Ident,
+    min: i128,
+    max: i128,
+    /// Whether the type was given with `#[repr]`, rather than being the
+    /// default of `u32`.
+    explicit: bool,
+}
+
+impl EnumRepr {
+    fn is_64(&self) -> bool {
+        self.max > i128::from(u32::max_value())
+    }
+
+    /// The range of the type enums of this repr are passed to wasm as, which
+    /// is `i32` or `u32` unless the repr is 64 bits wide.
+    fn abi_range(&self) -> (i128, i128) {
+        if self.min < 0 {
+            (i128::from(i32::min_value()), i128::from(i32::max_value()))
+        } else {
+            (0, i128::from(u32::max_value()))
+        }
+    }
+}
+
+/// Finds the `#[repr]` of an enum, defaulting to `u32`.
+fn enum_repr(attrs: &[syn::Attribute]) -> Result<EnumRepr, Diagnostic> {
+    for attr in attrs {
+        if attr.path.segments.len() != 1 || attr.path.segments[0].ident != "repr" {
+            continue;
+        }
+        let list = match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => list,
+            _ => continue,
+        };
+        for nested in list.nested.iter() {
+            let ty = match nested {
+                syn::NestedMeta::Meta(syn::Meta::Word(ty)) => ty,
+                _ => continue,
+            };
+            let (min, max) = match &ty.to_string()[..] {
+                "u8" => (0, i128::from(u8::max_value())),
+                "u16" => (0, i128::from(u16::max_value())),
+                "u32" => (0, i128::from(u32::max_value())),
+                "u64" => (0, i128::from(u64::max_value())),
+                "i8" => (i128::from(i8::min_value()), i128::from(i8::max_value())),
+                "i16" => (i128::from(i16::min_value()), i128::from(i16::max_value())),
+                "i32" => (i128::from(i32::min_value()), i128::from(i32::max_value())),
+                "i64" => (i128::from(i64::min_value()), i128::from(i64::max_value())),
+                "isize" | "usize" | "i128" | "u128" => {
+                    return Err(err_span!(
+                        ty,
+                        "enums with #[wasm_bindgen] can't be represented as {}",
+                        ty
+                    )
+                    .help("use a fixed-size integer type like `i32` or `i64` instead"))
+                }
+                _ => continue,
+            };
+            return Ok(EnumRepr {
+                ty: ty.clone(),
+                min,
+                max,
+                explicit: true,
+            });
+        }
+    }
+    Ok(EnumRepr {
+        ty: Ident::new("u32", Span::call_site()),
+        min: 0,
+        max: i128::from(u32::max_value()),
+        explicit: false,
+    })
+}
+
+/// Evaluates an enum discriminant made up of integer literals and arithmetic.
+///
+/// Returns `None` if the discriminant refers to anything else, like a
+/// constant, in which case its value is found by the CLI instead.
+/// Converts a newtype like `struct Milliseconds(pub f64);`, which is passed to
+/// JS as the value it wraps.
+/// Converts an enum whose variants have data to a tagged union, which is passed
+/// to JS as an object with the name of its variant as its `tag` and its data
+/// as its `value`.
+fn tagged_union(e: &syn::ItemEnum) -> Result<ast::TaggedUnion, Diagnostic> {
+    if e.generics.params.len() > 0 {
+        bail_span!(
+            e.generics,
+            "enums with data and #[wasm_bindgen] cannot have lifetime or \
+             type parameters"
+        );
+    }
+    let variants = e
+        .variants
+        .iter()
+        .map(|v| {
+            let ty = match &v.fields {
+                syn::Fields::Unit => None,
+                syn::Fields::Unnamed(f) if f.unnamed.len() == 1 => Some(f.unnamed[0].ty.clone()),
+                fields => {
+                    return Err(err_span!(
+                        fields,
+                        "variants of enums with data and #[wasm_bindgen] can only \
+                         have a single unnamed field"
+                    )
+                    .help(format!(
+                        "export a struct for the data and use it as the field: `{}(Data)`",
+                        v.ident
+                    )))
+                }
+            };
+            Ok(ast::TaggedVariant {
+                name: v.ident.clone(),
+                ty,
+            })
+        })
+        .collect::<Result<Vec<_>, Diagnostic>>()?;
+    Ok(ast::TaggedUnion {
+        name: e.ident.clone(),
+        variants,
+        comments: extract_doc_comments(&e.attrs),
+    })
+}
+
+fn brand_newtype(s: &syn::ItemStruct, ts_name: String) -> Result<ast::Brand, Diagnostic> {
+    if s.generics.params.len() > 0 {
+        bail_span!(
+            s.generics,
+            "branded types can't have lifetime or type parameters"
+        );
+    }
+    let inner = match &s.fields {
+        syn::Fields::Unnamed(f) if f.unnamed.len() == 1 => f.unnamed[0].ty.clone(),
+        _ => {
+            return Err(err_span!(
+                s,
+                "`ts_brand` can only be used on newtypes, structs with a single \
+                 unnamed field"
+            )
+            .help(format!("use a struct like `struct {}(f64);`", s.ident)))
+        }
+    };
+    Ok(ast::Brand {
+        rust_name: s.ident.clone(),
+        ts_name,
+        inner,
+    })
+}
+
+/// The type of JS objects implementing the exported trait `name`.
+fn js_trait_type(name: &Ident) -> Ident {
+    Ident::new(&format!("Js{}", name), name.span())
+}
+
+/// Replaces `impl Trait` in the type of an argument of an export with the
+/// type of JS objects implementing `Trait`, which has to be a trait exported
+/// with `#[wasm_bindgen]` itself.
+fn replace_impl_trait(ty: syn::Type) -> syn::Type {
+    match ty {
+        syn::Type::ImplTrait(t) => {
+            let mut traits = t.bounds.iter().filter_map(|b| match b {
+                syn::TypeParamBound::Trait(t) => Some(&t.path),
+                syn::TypeParamBound::Lifetime(_) => None,
+            });
+            let mut path = match (traits.next(), traits.next()) {
+                (Some(path), None) => path.clone(),
+                _ => return syn::Type::ImplTrait(t),
+            };
+            {
+                let last = path.segments.last_mut().unwrap().into_value();
+                last.ident = js_trait_type(&last.ident);
+            }
+            syn::Type::Path(syn::TypePath { qself: None, path })
+        }
+        syn::Type::Reference(mut r) => {
+            r.elem = Box::new(replace_impl_trait(*r.elem));
+            syn::Type::Reference(r)
+        }
+        other => other,
+    }
+}
+
+/// Exports the trait `t` as an interface in TypeScript, along with the type of
+/// JS objects implementing that interface, `Js<Trait>`, which is imported with
+/// the trait's methods and implements the trait by calling them.
+fn export_trait(
+    mut t: syn::ItemTrait,
+    program: &mut ast::Program,
+    opts: BindgenAttrs,
+    tokens: &mut TokenStream,
+) -> Result<(), Diagnostic> {
+    if t.generics.params.len() > 0 {
+        bail_span!(
+            t.generics,
+            "#[wasm_bindgen] traits can't have lifetime or type parameters"
+        );
+    }
+    let js_name = opts
+        .js_name()
+        .map(|s| s.0.to_string())
+        .unwrap_or_else(|| t.ident.to_string());
+    opts.check_used()?;
+
+    let vis = &t.vis;
+    let js_ty = js_trait_type(&t.ident);
+    let type_doc = format!(
+        "A JS object implementing the `{}` interface, whose methods are \
+         called to implement [`{}`].",
+        js_name, t.ident
+    );
+    let mut imports = vec![quote::quote! {
+        #[wasm_bindgen(js_name = #js_name)]
+        #[doc = #type_doc]
+        #[derive(Debug, Clone)]
+        #vis type #js_ty;
+    }];
+    let mut impls = Vec::new();
+    let mut methods = Vec::new();
+    for item in t.items.iter_mut() {
+        let method = match item {
+            syn::TraitItem::Method(m) => m,
+            other => bail_span!(other, "#[wasm_bindgen] traits can only have methods"),
+        };
+        let method_opts = BindgenAttrs::find(&mut method.attrs)?;
+        let sig = &mut method.sig;
+        if sig.decl.generics.params.len() > 0 {
+            bail_span!(
+                sig.decl.generics,
+                "methods of #[wasm_bindgen] traits can't have lifetime or type parameters"
+            );
+        }
+        if sig.decl.variadic.is_some() || sig.asyncness.is_some() || sig.constness.is_some() {
+            bail_span!(sig.ident, "unsupported method of a #[wasm_bindgen] trait");
+        }
+        match sig.decl.inputs.iter().next() {
+            Some(syn::FnArg::SelfRef(r)) if r.mutability.is_none() => {}
+            _ => bail_span!(
+                sig.ident,
+                "methods of #[wasm_bindgen] traits have to take `&self`"
+            ),
+        }
+        let method_js_name = method_opts
+            .js_name()
+            .map(|s| s.0.to_string())
+            .unwrap_or_else(|| sig.ident.to_string());
+        method_opts.check_used()?;
+
+        // The implementation of the trait passes its arguments on by name, so
+        // they're all given one.
+        let mut args = Vec::new();
+        for (i, arg) in sig.decl.inputs.iter().skip(1).enumerate() {
+            let arg = match arg {
+                syn::FnArg::Captured(c) => c,
+                other => bail_span!(other, "unsupported argument of a #[wasm_bindgen] trait"),
+            };
+            let name = match &arg.pat {
+                syn::Pat::Ident(p) => p.ident.clone(),
+                _ => Ident::new(&format!("arg{}", i), Span::call_site()),
+            };
+            args.push((name, arg.ty.clone()));
+        }
+        let names = args.iter().map(|a| &a.0).collect::<Vec<_>>();
+        let tys = args.iter().map(|a| &a.1);
+        let docs = method
+            .attrs
+            .iter()
+            .filter(|a| a.path.is_ident("doc"))
+            .collect::<Vec<_>>();
+        let (ident, output) = (&sig.ident, &sig.decl.output);
+        imports.push(quote::quote! {
+            #[wasm_bindgen(method, structural, js_class = #js_name, js_name = #method_js_name)]
+            #(#docs)*
+            #vis fn #ident(this: &#js_ty, #(#names: #tys),*) #output;
+        });
+
+        let mut impl_sig = sig.clone();
+        for (arg, name) in impl_sig.decl.inputs.iter_mut().skip(1).zip(&names) {
+            if let syn::FnArg::Captured(c) = arg {
+                c.pat = syn::parse_quote!(#name);
+            }
+        }
+        impls.push(quote::quote! {
+            #impl_sig {
+                #js_ty::#ident(self, #(#names),*)
+            }
+        });
+
+        methods.push((method_js_name, extract_doc_comments(&method.attrs)));
+    }
+
+    let foreign: syn::ItemForeignMod = syn::parse2(quote::quote! {
+        extern "C" {
+            #(#imports)*
+        }
+    })
+    .expect("generated extern block should parse");
+    let start = program.imports.len();
+    foreign.macro_parse(program, BindgenAttrs::default())?;
+
+    // Everything imported above is either the type or one of its methods, in
+    // the order of the trait's methods.
+    let mut shims = Vec::new();
+    for import in program.imports[start..].iter_mut() {
+        match &mut import.kind {
+            ast::ImportKind::Type(ty) => ty.interface = true,
+            ast::ImportKind::Function(f) => shims.push(f.shim.clone()),
+            _ => unreachable!(),
+        }
+    }
+    program.traits.push(ast::Trait {
+        js_name,
+        methods: methods
+            .into_iter()
+            .zip(shims)
+            .map(|((js_name, comments), shim)| ast::TraitMethod {
+                js_name,
+                shim,
+                comments,
+            })
+            .collect(),
+        comments: extract_doc_comments(&t.attrs),
+    });
+
+    let (unsafety, name) = (&t.unsafety, &t.ident);
+    t.to_tokens(tokens);
+    (quote::quote! {
+        #unsafety impl #name for #js_ty {
+            #(#impls)*
+        }
+    })
+    .to_tokens(tokens);
+    Ok(())
+}
+
+/// Whether `e` is an enum of JS values being imported rather than an enum being
+/// exported, which is the case if its discriminants are strings, or if it has
+/// discriminants and a variant holding other values, like `Other(String)`.
+fn is_import_enum(e: &syn::ItemEnum) -> bool {
+    let strings = e.variants.iter().any(|v| match v.discriminant {
+        Some((_, syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(_),
+            ..
+        }))) => true,
+        _ => false,
+    });
+    let discriminants = e.variants.iter().any(|v| v.discriminant.is_some());
+    let other = e.variants.iter().any(|v| match v.fields {
+        syn::Fields::Unnamed(ref f) => f.unnamed.len() == 1,
+        _ => false,
+    });
+    strings || (discriminants && other)
+}
+
+fn import_enum(
+    e: syn::ItemEnum,
+    program: &mut ast::Program,
+    opts: BindgenAttrs,
+) -> Result<(), Diagnostic> {
+    opts.check_used()?;
+
+    let mut variants = Vec::new();
+    let mut variant_values = Vec::new();
+    let mut numeric = None;
+    let mut other = None;
+    for v in e.variants.iter() {
+        match v.fields {
+            syn::Fields::Unit => {}
+            syn::Fields::Unnamed(ref f) if f.unnamed.len() == 1 && v.discriminant.is_none() => {
+                if other.is_some() {
+                    bail_span!(v, "imported enums can only have one variant for other values");
+                }
+                other = Some((v.ident.clone(), f.unnamed[0].ty.clone()));
+                continue;
+            }
+            _ => {
+                return Err(err_span!(
+                    v,
+                    "variants of imported enums must either have a JS value \
+                     or hold any other value"
+                )
+                .help(format!("use a value like `{} = \"value\"`", v.ident)))
+            }
+        }
+        let (value, is_number) = match v.discriminant {
+            Some((_, ref expr)) => match js_literal(expr) {
+                Some(value) => value,
+                None => bail_span!(
+                    expr,
+                    "the values of imported enums must be string or number literals"
+                ),
+            },
+            None => {
+                return Err(err_span!(
+                    v,
+                    "variants of imported enums must have the JS value they stand for"
+                )
+                .help(format!("use a value like `{} = \"value\"`", v.ident)))
+            }
+        };
+        if *numeric.get_or_insert(is_number) != is_number {
+            bail_span!(
+                v,
+                "the values of an imported enum must either all be strings or all be numbers"
+            );
+        }
+        if variant_values.contains(&value) {
+            bail_span!(v, "the value of `{}` is used by another variant", v.ident);
+        }
+        variants.push(v.ident.clone());
+        variant_values.push(value);
+    }
+    if variants.len() == 0 {
+        bail_span!(e, "imported enums must have a variant with a JS value");
+    }
+    let numeric = numeric.unwrap_or(false);
+    let other = match other {
+        Some((ident, ty)) => {
+            let expected = if numeric { "f64" } else { "String" };
+            let ok = match ty {
+                syn::Type::Path(syn::TypePath { qself: None, ref path }) => {
+                    path.segments.len() == 1
+                        && path.segments[0].ident == expected
+                        && path.segments[0].arguments.is_empty()
+                }
+                _ => false,
+            };
+            if !ok {
+                bail_span!(
+                    ty,
+                    "the variant holding other values of this imported enum must hold a `{}`",
+                    expected
+                );
+            }
+            Some(ident)
+        }
+        None => None,
+    };
+
+    program.imports.push(ast::Import {
+        module: None,
+        js_namespace: None,
+        kind: ast::ImportKind::Enum(ast::ImportEnum {
+            vis: e.vis,
+            name: e.ident,
+            variants,
+            variant_values,
+            numeric,
+            other,
+            comments: extract_doc_comments(&e.attrs),
+            rust_attrs: e.attrs,
+        }),
+    });
+    Ok(())
+}
+
+/// Reads a string or number literal, returning its value and whether it's a
+/// number.
+fn js_literal(expr: &syn::Expr) -> Option<(String, bool)> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit { lit, .. }) => match lit {
+            syn::Lit::Str(s) => Some((s.value(), false)),
+            syn::Lit::Int(i) => Some((i.value().to_string(), true)),
+            syn::Lit::Float(f) => Some((f.value().to_string(), true)),
+            _ => None,
+        },
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => match js_literal(expr) {
+            Some((value, true)) => Some((format!("-{}", value), true)),
+            _ => None,
+        },
+        syn::Expr::Paren(e) => js_literal(&e.expr),
+        syn::Expr::Group(e) => js_literal(&e.expr),
+        _ => None,
+    }
+}
+
+fn eval_discriminant(expr: &syn::Expr, repr: &EnumRepr) -> Result<Option<i128>, Diagnostic> {
+    let value = match eval_int(expr, repr)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    if value < repr.min || value > repr.max {
+        return Err(out_of_range(expr, repr, value < 0));
+    }
+    Ok(Some(value))
+}
+
+fn eval_int(expr: &syn::Expr, repr: &EnumRepr) -> Result<Option<i128>, Diagnostic> {
+    let (l, r, op) = match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(i),
+            ..
+        }) => return Ok(Some(i128::from(i.value()))),
+        syn::Expr::Paren(e) => return eval_int(&e.expr, repr),
+        syn::Expr::Group(e) => return eval_int(&e.expr, repr),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr: e,
+            ..
+        }) => return Ok(eval_int(e, repr)?.map(|v| -v)),
+        syn::Expr::Binary(e) => match (eval_int(&e.left, repr)?, eval_int(&e.right, repr)?) {
+            (Some(l), Some(r)) => (l, r, &e.op),
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    let value = match op {
+        syn::BinOp::Add(_) => l.checked_add(r),
+        syn::BinOp::Sub(_) => l.checked_sub(r),
+        syn::BinOp::Mul(_) => l.checked_mul(r),
+        syn::BinOp::Div(_) => l.checked_div(r),
+        syn::BinOp::Rem(_) => l.checked_rem(r),
+        syn::BinOp::Shl(_) if r >= 0 && r < 64 => l.checked_mul(1 << r),
+        syn::BinOp::Shr(_) if r >= 0 && r < 128 => Some(l >> r),
+        syn::BinOp::BitAnd(_) => Some(l & r),
+        syn::BinOp::BitOr(_) => Some(l | r),
+        syn::BinOp::BitXor(_) => Some(l ^ r),
+        _ => return Ok(None),
+    };
+    match value {
+        Some(value) => Ok(Some(value)),
+        None => Err(out_of_range(expr, repr, false)),
+    }
+}
+
+fn out_of_range(expr: &syn::Expr, repr: &EnumRepr, negative: bool) -> Diagnostic {
+    let err = err_span!(
+        expr,
+        "enums with #[wasm_bindgen] can only support numbers that can be \
+         represented as {}",
+        repr.ty
+    );
+    if repr.explicit {
+        err.help(format!(
+            "values must be between {} and {}",
+            repr.min, repr.max
+        ))
+    } else if negative {
+        err.help("add `#[repr(i32)]` to the enum to use negative values")
+    } else {
+        err.help(format!(
+            "add `#[repr(u64)]` to the enum to use values larger than {}, \
+             which are represented as `BigInt`s in JS",
+            repr.max
+        ))
+    }
+}
+
 impl MacroParse<BindgenAttrs> for syn::ItemConst {
     fn macro_parse(self, program: &mut ast::Program, opts: BindgenAttrs) -> Result<(), Diagnostic> {
         // Shortcut
@@ -1110,10 +2288,18 @@ impl<'a> MacroParse<&'a BindgenAttrs> for syn::ForeignItem {
             };
             BindgenAttrs::find(attrs)?
         };
-        let module = item_opts
-            .module()
-            .or(opts.module())
-            .map(|s| s.0.to_string());
+        let module = match (item_opts.module().or(opts.module()), opts.inline_js()) {
+            (Some(_), Some((_, span))) => {
+                let msg = "cannot specify both `module` and `inline_js`";
+                return Err(Diagnostic::span_error(span, msg));
+            }
+            (Some((path, span)), None) if path.starts_with('/') => {
+                Some(local_snippet(program, path, span)?)
+            }
+            (Some((module, _)), None) => Some(module.to_string()),
+            (None, Some((js, _))) => Some(inline_snippet(program, js)),
+            (None, None) => None,
+        };
         let js_namespace = item_opts.js_namespace().or(opts.js_namespace()).cloned();
         let kind = match self {
             syn::ForeignItem::Fn(f) => f.convert((item_opts, &module))?,
@@ -1132,6 +2318,55 @@ impl<'a> MacroParse<&'a BindgenAttrs> for syn::ForeignItem {
     }
 }
 
+/// The directory in the `snippets` directory of the output where the snippets
+/// of this crate are written to, named uniquely among all crates in the
+/// dependency graph.
+fn snippets_dir() -> String {
+    let name = env::var("CARGO_PKG_NAME").expect("should have CARGO_PKG_NAME env var");
+    let dir = env::var("CARGO_MANIFEST_DIR").expect("should have CARGO_MANIFEST_DIR env var");
+    format!("snippets/{}-{}", name, ShortHash(dir))
+}
+
+/// Records JS written with `inline_js` as a snippet, returning the module to
+/// import it from.
+///
+/// Snippets are named after a hash of their contents, so the same JS written
+/// out twice ends up in one file.
+fn inline_snippet(program: &mut ast::Program, js: &str) -> String {
+    let path = format!("{}/inline-{}.js", snippets_dir(), ShortHash(js));
+    if !program.snippets.iter().any(|s| s.path == path) {
+        program.snippets.push(ast::Snippet {
+            path: path.clone(),
+            contents: js.to_string(),
+            file: None,
+        });
+    }
+    format!("./{}", path)
+}
+
+/// Records the file `path`, relative to the root of the crate, as a snippet,
+/// returning the module to import it from.
+fn local_snippet(program: &mut ast::Program, path: &str, span: Span) -> Result<String, Diagnostic> {
+    let dir = env::var("CARGO_MANIFEST_DIR").expect("should have CARGO_MANIFEST_DIR env var");
+    let file = Path::new(&dir).join(&path[1..]);
+    let contents = match fs::read_to_string(&file) {
+        Ok(contents) => contents,
+        Err(e) => {
+            let msg = format!("failed to read `{}`: {}", file.display(), e);
+            return Err(Diagnostic::span_error(span, msg));
+        }
+    };
+    let path = format!("{}{}", snippets_dir(), path);
+    if !program.snippets.iter().any(|s| s.path == path) {
+        program.snippets.push(ast::Snippet {
+            path: path.clone(),
+            contents,
+            file: Some(file),
+        });
+    }
+    Ok(format!("./{}", path))
+}
+
 /// Get the first type parameter of a generic type, errors on incorrect input.
 fn extract_first_ty_param(ty: Option<&syn::Type>) -> Result<Option<syn::Type>, Diagnostic> {
     let t = match ty {
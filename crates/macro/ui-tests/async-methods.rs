@@ -0,0 +1,39 @@
+// compile-flags: --edition 2018
+#![crate_type = "rlib"]
+
+extern crate wasm_bindgen;
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub async fn free() {}
+
+#[wasm_bindgen]
+pub struct A;
+
+#[wasm_bindgen]
+impl A {
+    pub async fn method(&self) {}
+}
+
+#[wasm_bindgen]
+impl A {
+    #[wasm_bindgen(constructor)]
+    pub async fn with_self(&self) -> Result<A, JsValue> {
+        Ok(A)
+    }
+}
+
+#[wasm_bindgen]
+impl A {
+    #[wasm_bindgen(constructor)]
+    pub async fn with_ref(_: &str) -> Result<A, JsValue> {
+        Ok(A)
+    }
+}
+
+#[wasm_bindgen]
+impl A {
+    #[wasm_bindgen(constructor)]
+    pub async fn without_return() {}
+}
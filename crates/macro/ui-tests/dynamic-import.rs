@@ -0,0 +1,19 @@
+#![crate_type = "rlib"]
+
+extern crate wasm_bindgen;
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(dynamic_import)]
+    fn no_module() -> JsValue;
+}
+
+#[wasm_bindgen(module = "./foo.js")]
+extern "C" {
+    #[wasm_bindgen(dynamic_import)]
+    fn with_argument(x: u32) -> JsValue;
+    #[wasm_bindgen(dynamic_import)]
+    fn no_return();
+}
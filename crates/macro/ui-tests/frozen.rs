@@ -0,0 +1,35 @@
+#![crate_type = "rlib"]
+
+extern crate wasm_bindgen;
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(frozen)]
+pub struct A {
+    pub a: u32,
+    #[wasm_bindgen(skip_getter)]
+    pub b: u32,
+}
+
+#[wasm_bindgen(frozen)]
+pub struct B {
+    pub a: u32,
+}
+
+#[wasm_bindgen]
+impl B {
+    pub fn get(&self) -> u32 {
+        self.a
+    }
+
+    pub fn bump(&mut self) {
+        self.a += 1;
+    }
+}
+
+#[wasm_bindgen(frozen, plain)]
+pub struct C {
+    pub a: u32,
+}
+
+fn main() {}
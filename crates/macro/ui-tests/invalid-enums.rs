@@ -9,15 +9,38 @@ enum A {}
 
 #[wasm_bindgen]
 pub enum B {
-    D(u32),
+    D { x: u32 },
 }
 
 #[wasm_bindgen]
 pub enum C {
-    X = 1 + 3,
+    X = -1,
 }
 
 #[wasm_bindgen]
 pub enum D {
     X = 4294967296,
 }
+
+#[wasm_bindgen]
+pub enum E {
+    X = 1 << 16 << 16,
+}
+
+#[wasm_bindgen]
+#[repr(u8)]
+pub enum F {
+    X = 256,
+}
+
+#[wasm_bindgen]
+#[repr(isize)]
+pub enum G {
+    X = 1,
+}
+
+#[wasm_bindgen(flags)]
+pub enum H {
+    X = 1,
+    Y = 3,
+}
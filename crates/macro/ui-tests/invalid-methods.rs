@@ -47,3 +47,9 @@ impl A {
 impl A {
     pub unsafe fn foo() {}
 }
+
+#[wasm_bindgen]
+impl A {
+    #[wasm_bindgen(lazy)]
+    pub fn bar() {}
+}
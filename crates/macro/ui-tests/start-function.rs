@@ -12,3 +12,6 @@ pub fn foo2(x: u32) {}
 
 #[wasm_bindgen(start)]
 pub fn foo3<T>() {}
+
+#[wasm_bindgen(start, lazy)]
+pub fn foo4() {}
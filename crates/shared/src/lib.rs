@@ -13,11 +13,19 @@ macro_rules! shared_api {
             enums: Vec<Enum<'a>>,
             imports: Vec<Import<'a>>,
             structs: Vec<Struct<'a>>,
+            tagged_unions: Vec<TaggedUnion<'a>>,
+            traits: Vec<Trait<'a>>,
             typescript_custom_sections: Vec<&'a str>,
+            snippets: Vec<Snippet<'a>>,
             // version: &'a str,
             // schema_version: &'a str,
         }
 
+        struct Snippet<'a> {
+            path: &'a str,
+            contents: &'a str,
+        }
+
         struct Import<'a> {
             module: Option<&'a str>,
             js_namespace: Option<&'a str>,
@@ -28,7 +36,7 @@ macro_rules! shared_api {
             Function(ImportFunction<'a>),
             Static(ImportStatic<'a>),
             Type(ImportType<'a>),
-            Enum(ImportEnum),
+            Enum(ImportEnum<'a>),
         }
 
         struct ImportFunction<'a> {
@@ -37,6 +45,11 @@ macro_rules! shared_api {
             variadic: bool,
             method: Option<MethodData<'a>>,
             structural: bool,
+            bind_this: bool,
+            main_thread: bool,
+            static_getter: bool,
+            dynamic_import: bool,
+            cached: bool,
             function: Function<'a>,
         }
 
@@ -57,6 +70,7 @@ macro_rules! shared_api {
 
         enum OperationKind<'a> {
             Regular,
+            Call,
             Getter(&'a str),
             Setter(&'a str),
             IndexingGetter,
@@ -72,10 +86,17 @@ macro_rules! shared_api {
         struct ImportType<'a> {
             name: &'a str,
             instanceof_shim: &'a str,
+            duck_shim: &'a str,
             vendor_prefixes: Vec<&'a str>,
         }
 
-        struct ImportEnum {}
+        struct ImportEnum<'a> {
+            name: &'a str,
+            variant_values: Vec<&'a str>,
+            numeric: bool,
+            other: bool,
+            comments: Vec<&'a str>,
+        }
 
         struct Export<'a> {
             class: Option<&'a str>,
@@ -85,17 +106,23 @@ macro_rules! shared_api {
             function: Function<'a>,
             comments: Vec<&'a str>,
             start: bool,
+            lazy: bool,
+            maps_as_objects: bool,
+            throws: Option<&'a str>,
         }
 
         struct Enum<'a> {
             name: &'a str,
             variants: Vec<EnumVariant<'a>>,
             comments: Vec<&'a str>,
+            repr: &'a str,
+            dynamic_values: bool,
+            flags: bool,
         }
 
         struct EnumVariant<'a> {
             name: &'a str,
-            value: u32,
+            value: u64,
         }
 
         struct Function<'a> {
@@ -106,11 +133,39 @@ macro_rules! shared_api {
             name: &'a str,
             fields: Vec<StructField<'a>>,
             comments: Vec<&'a str>,
+            js_tostring: bool,
+            enumerable: bool,
+            frozen: bool,
+            plain: bool,
         }
 
         struct StructField<'a> {
             name: &'a str,
             readonly: bool,
+            writeonly: bool,
+            comments: Vec<&'a str>,
+        }
+
+        struct TaggedUnion<'a> {
+            name: &'a str,
+            variants: Vec<TaggedVariant<'a>>,
+            comments: Vec<&'a str>,
+        }
+
+        struct TaggedVariant<'a> {
+            name: &'a str,
+            has_value: bool,
+        }
+
+        struct Trait<'a> {
+            name: &'a str,
+            methods: Vec<TraitMethod<'a>>,
+            comments: Vec<&'a str>,
+        }
+
+        struct TraitMethod<'a> {
+            name: &'a str,
+            shim: &'a str,
             comments: Vec<&'a str>,
         }
         }
@@ -145,6 +200,12 @@ pub fn struct_function_export_name(struct_: &str, f: &str) -> String {
     return name;
 }
 
+/// The name of the function exported for enums with `dynamic_values`, which
+/// informs the values of each variant in order.
+pub fn enum_values(enum_: &str) -> String {
+    format!("__wbindgen_enum_values_{}", enum_)
+}
+
 pub fn struct_field_get(struct_: &str, f: &str) -> String {
     let mut name = String::from("__wbg_get_");
     name.extend(struct_.chars().flat_map(|s| s.to_lowercase()));
@@ -161,6 +222,24 @@ pub fn struct_field_set(struct_: &str, f: &str) -> String {
     return name;
 }
 
+/// The name of the function exported for tagged unions which returns the
+/// index of the variant of one.
+pub fn tagged_union_tag(union: &str) -> String {
+    format!("__wbg_tag_{}", union)
+}
+
+/// The name of the function exported for each variant of a tagged union which
+/// consumes one of that variant, returning its value if it has one.
+pub fn tagged_union_unwrap(union: &str, variant: &str) -> String {
+    format!("__wbg_unwrap_{}_{}", union, variant)
+}
+
+/// The name of the function exported for each variant of a tagged union which
+/// creates one of that variant, taking its value if it has one.
+pub fn tagged_union_new(union: &str, variant: &str) -> String {
+    format!("__wbg_new_{}_{}", union, variant)
+}
+
 pub fn version() -> String {
     let mut v = env!("CARGO_PKG_VERSION").to_string();
     if let Some(s) = option_env!("WBG_VERSION") {
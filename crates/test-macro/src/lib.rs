@@ -18,9 +18,33 @@ pub fn wasm_bindgen_test(
 ) -> proc_macro::TokenStream {
     let mut attr = attr.into_iter();
     let mut async = false;
+    let mut register = false;
+    let mut cases = None;
+    let mut timeout = None;
     while let Some(token) = attr.next() {
         match &token {
             proc_macro::TokenTree::Ident(i) if i.to_string() == "async" => async = true,
+            proc_macro::TokenTree::Ident(i) if i.to_string() == "register" => register = true,
+            proc_macro::TokenTree::Ident(i) if i.to_string() == "cases" => match attr.next() {
+                Some(proc_macro::TokenTree::Group(g))
+                    if g.delimiter() == proc_macro::Delimiter::Parenthesis =>
+                {
+                    cases = Some(split_cases(g.stream().into()));
+                }
+                _ => panic!("malformed `#[wasm_bindgen_test]` attribute"),
+            },
+            proc_macro::TokenTree::Ident(i) if i.to_string() == "timeout_ms" => {
+                match (attr.next(), attr.next()) {
+                    (
+                        Some(proc_macro::TokenTree::Punct(ref op)),
+                        Some(proc_macro::TokenTree::Literal(ref ms)),
+                    ) if op.as_char() == '=' => match ms.to_string().parse::<u32>() {
+                        Ok(ms) => timeout = Some(ms),
+                        Err(_) => panic!("`timeout_ms` must be a number of milliseconds"),
+                    },
+                    _ => panic!("malformed `#[wasm_bindgen_test]` attribute"),
+                }
+            }
             _ => panic!("malformed `#[wasm_bindgen_test]` attribute"),
         }
         match &attr.next() {
@@ -30,48 +54,135 @@ pub fn wasm_bindgen_test(
         }
     }
 
-    let mut body = TokenStream::from(body).into_iter();
+    let (leading_tokens, ident, body) = split_fn(body.into());
 
-    // Skip over other attributes to `fn #ident ...`, and extract `#ident`
-    let mut leading_tokens = Vec::new();
-    while let Some(token) = body.next() {
-        leading_tokens.push(token.clone());
-        if let TokenTree::Ident(token) = token {
-            if token == "fn" {
-                break;
-            }
-        }
+    let mut tokens = Vec::<TokenTree>::new();
+
+    if register && (async || cases.is_some() || timeout.is_some()) {
+        panic!("`#[wasm_bindgen_test(register)]` can't be `async` or have `cases` or `timeout_ms`");
     }
-    let ident = match body.next() {
-        Some(TokenTree::Ident(token)) => token,
-        _ => panic!("expected a function name"),
+
+    // Parameterized tests get an entry point for each of their cases, which
+    // are all passed to the test as its argument and included in its name.
+    let entries = match cases {
+        Some(cases) => cases
+            .into_iter()
+            .map(|case| {
+                let test_name = quote! {
+                    concat!(
+                        module_path!(), "::", stringify!(#ident),
+                        "(", stringify!(#case), ")",
+                    )
+                };
+                let test = quote! { move || #ident(#case) };
+                (test_name, test)
+            })
+            .collect(),
+        None => {
+            let test_name = quote! { concat!(module_path!(), "::", stringify!(#ident)) };
+            vec![(test_name, quote! { #ident })]
+        }
     };
 
-    let mut tokens = Vec::<TokenTree>::new();
+    let set_timeout = match timeout {
+        Some(ms) => quote! { cx.set_next_timeout(#ms); },
+        None => quote! {},
+    };
+
+    for (test_name, test) in entries {
+        let test_body = if register {
+            quote! { cx.execute_generator(test_name, #test); }
+        } else if async {
+            quote! { cx.execute_async(test_name, #test); }
+        } else {
+            quote! { cx.execute_sync(test_name, #test); }
+        };
+
+        // We generate a `#[no_mangle]` with a known prefix so the test harness
+        // can later slurp up all of these functions and pass them as arguments
+        // to the main test harness. This is the entry point for all tests.
+        let name = format!(
+            "__wbg_test_{}_{}",
+            ident,
+            CNT.fetch_add(1, Ordering::SeqCst)
+        );
+        let name = Ident::new(&name, Span::call_site());
+        tokens.extend(
+            (quote! {
+                #[no_mangle]
+                pub extern "C" fn #name(cx: *const ::wasm_bindgen_test::__rt::Context) {
+                    unsafe {
+                        let cx = &*cx;
+                        let test_name = #test_name;
+                        #set_timeout
+                        #test_body
+                    }
+                }
+            })
+            .into_iter(),
+        );
+    }
+
+    tokens.extend(leading_tokens);
+    tokens.push(ident.into());
+    tokens.extend(body);
+
+    tokens.into_iter().collect::<TokenStream>().into()
+}
+
+#[proc_macro_attribute]
+pub fn wasm_bindgen_test_setup(
+    attr: proc_macro::TokenStream,
+    body: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    hook("setup", attr, body)
+}
+
+#[proc_macro_attribute]
+pub fn wasm_bindgen_test_teardown(
+    attr: proc_macro::TokenStream,
+    body: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    hook("teardown", attr, body)
+}
 
-    let test_body = if async {
-        quote! { cx.execute_async(test_name, #ident); }
-    } else {
-        quote! { cx.execute_sync(test_name, #ident); }
+/// Expands `#[wasm_bindgen_test_setup]` and `#[wasm_bindgen_test_teardown]`,
+/// which generate an entry point like `#[wasm_bindgen_test]` does, only it
+/// informs the harness of the function to run around tests instead.
+fn hook(
+    kind: &str,
+    attr: proc_macro::TokenStream,
+    body: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let mut attr = attr.into_iter();
+    let async = match attr.next() {
+        Some(proc_macro::TokenTree::Ident(i)) if i.to_string() == "async" => true,
+        None => false,
+        _ => panic!("malformed `#[wasm_bindgen_test_{}]` attribute", kind),
     };
+    if attr.next().is_some() {
+        panic!("malformed `#[wasm_bindgen_test_{}]` attribute", kind);
+    }
+
+    let (leading_tokens, ident, body) = split_fn(body.into());
 
-    // We generate a `#[no_mangle]` with a known prefix so the test harness can
-    // later slurp up all of these functions and pass them as arguments to the
-    // main test harness. This is the entry point for all tests.
+    let method = format!("{}_{}", kind, if async { "async" } else { "sync" });
+    let method = Ident::new(&method, Span::call_site());
     let name = format!(
-        "__wbg_test_{}_{}",
+        "__wbg_test_{}_{}_{}",
+        kind,
         ident,
         CNT.fetch_add(1, Ordering::SeqCst)
     );
     let name = Ident::new(&name, Span::call_site());
+    let mut tokens = Vec::<TokenTree>::new();
     tokens.extend(
         (quote! {
             #[no_mangle]
             pub extern "C" fn #name(cx: *const ::wasm_bindgen_test::__rt::Context) {
                 unsafe {
                     let cx = &*cx;
-                    let test_name = concat!(module_path!(), "::", stringify!(#ident));
-                    #test_body
+                    cx.#method(module_path!(), #ident);
                 }
             }
         })
@@ -84,3 +195,94 @@ pub fn wasm_bindgen_test(
 
     tokens.into_iter().collect::<TokenStream>().into()
 }
+
+/// Splits a function into the tokens before its name, including `fn`, its
+/// name, and the tokens after its name.
+fn split_fn(body: TokenStream) -> (Vec<TokenTree>, Ident, Vec<TokenTree>) {
+    let mut body = body.into_iter();
+
+    // Skip over other attributes to `fn #ident ...`, and extract `#ident`
+    let mut leading_tokens = Vec::new();
+    while let Some(token) = body.next() {
+        leading_tokens.push(token.clone());
+        if let TokenTree::Ident(token) = token {
+            if token == "fn" {
+                break;
+            }
+        }
+    }
+    let ident = match body.next() {
+        Some(TokenTree::Ident(token)) => token,
+        _ => panic!("expected a function name"),
+    };
+    (leading_tokens, ident, body.collect())
+}
+
+/// Splits the arguments of `cases(...)` into the tokens of each case.
+///
+/// Commas between the angle brackets of generic arguments, like in
+/// `foo::<A, B>()`, don't separate cases. Every `<` is taken to open generic
+/// arguments, so a comparison with `<` has to be parenthesized.
+fn split_cases(tokens: TokenStream) -> Vec<TokenStream> {
+    let mut cases = Vec::new();
+    let mut case = Vec::<TokenTree>::new();
+    let mut depth = 0usize;
+    for token in tokens {
+        match &token {
+            TokenTree::Punct(op) if op.as_char() == ',' && depth == 0 => {
+                cases.push(case.drain(..).collect());
+                continue;
+            }
+            TokenTree::Punct(op) if op.as_char() == '<' => depth += 1,
+            TokenTree::Punct(op) if op.as_char() == '>' => {
+                // The `>` of `->` and `=>` doesn't close anything.
+                let arrow = match case.last() {
+                    Some(TokenTree::Punct(prev)) => {
+                        prev.spacing() == Spacing::Joint
+                            && (prev.as_char() == '-' || prev.as_char() == '=')
+                    }
+                    _ => false,
+                };
+                if !arrow {
+                    depth = depth.saturating_sub(1);
+                }
+            }
+            _ => {}
+        }
+        case.push(token);
+    }
+    if !case.is_empty() {
+        cases.push(case.into_iter().collect());
+    }
+    if cases.is_empty() {
+        panic!("`cases` in `#[wasm_bindgen_test]` must list at least one case");
+    }
+    cases
+}
+
+#[test]
+fn splits_cases_on_top_level_commas() {
+    let split = |cases: &str| {
+        split_cases(cases.parse().unwrap())
+            .iter()
+            .map(|case| case.to_string())
+            .collect::<Vec<_>>()
+    };
+    // Compares the tokens of the cases, which aren't printed as written.
+    let tokens = |cases: &[&str]| {
+        cases
+            .iter()
+            .map(|case| case.parse::<TokenStream>().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(split("1, 2, 3,"), tokens(&["1", "2", "3"]));
+    assert_eq!(split("(1, 2), [3, 4]"), tokens(&["(1, 2)", "[3, 4]"]));
+    assert_eq!(
+        split("foo::<A, B>(), Vec::<Vec<u8>>::new(), 1"),
+        tokens(&["foo::<A, B>()", "Vec::<Vec<u8>>::new()", "1"])
+    );
+    assert_eq!(
+        split("foo::<fn() -> u8, B>(), |x| match x { _ => 1 }"),
+        tokens(&["foo::<fn() -> u8, B>()", "|x| match x { _ => 1 }"])
+    );
+}
@@ -21,6 +21,11 @@ fn pass_after_2s() -> impl Future<Item = (), Error = JsValue> {
     })
 }
 
+#[wasm_bindgen_test(cases(1, 2, 3))]
+fn pass_with_case(n: u32) {
+    console_log!("case {}", n);
+}
+
 #[wasm_bindgen_test]
 fn fail() {
     console_log!("helpful messsage, please see me");
@@ -40,3 +45,35 @@ fn fail_after_3s() -> impl Future<Item = (), Error = JsValue> {
         })
     })
 }
+
+#[wasm_bindgen_test(async, timeout_ms = 500)]
+fn fail_timeout() -> impl Future<Item = (), Error = JsValue> {
+    console_log!("this test takes too long");
+    Timeout::new(Duration::new(5, 0))
+}
+
+#[wasm_bindgen_test(async, timeout_ms = 2000)]
+fn pass_within_timeout() -> impl Future<Item = (), Error = JsValue> {
+    Timeout::new(Duration::from_millis(100))
+}
+
+#[wasm_bindgen_test(register)]
+fn registered() {
+    for &delay in [0, 1].iter() {
+        wasm_bindgen_test::register(&format!("pass_after_{}s", delay), move || {
+            Timeout::new(Duration::new(delay, 0)).map(|()| {
+                console_log!("log after {}s", delay);
+            })
+        });
+    }
+}
+
+#[wasm_bindgen_test_setup]
+fn setup() {
+    console_log!("setup");
+}
+
+#[wasm_bindgen_test_teardown(async)]
+fn teardown() -> impl Future<Item = (), Error = JsValue> {
+    Timeout::new(Duration::new(0, 0)).map(|()| console_log!("teardown"))
+}
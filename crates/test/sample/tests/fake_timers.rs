@@ -0,0 +1,87 @@
+//! Tests of the fake timers installed by `fake_timers`, which run in node.js
+//! like any other tests.
+
+extern crate js_sys;
+extern crate wasm_bindgen;
+extern crate wasm_bindgen_test;
+
+use js_sys::{Date, Function};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::timers;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(closure: &Closure<FnMut()>, millis: f64) -> u32;
+
+    #[wasm_bindgen(js_name = setInterval)]
+    fn set_interval(closure: &Closure<FnMut()>, millis: f64) -> u32;
+
+    #[wasm_bindgen(js_name = clearInterval)]
+    fn clear_interval(id: u32);
+}
+
+wasm_bindgen_test_configure!(fake_timers);
+
+/// Returns a closure which records the fake time it's called at in `log`.
+fn recorder(log: &Rc<RefCell<Vec<f64>>>) -> Closure<FnMut()> {
+    let log = log.clone();
+    Closure::wrap(Box::new(move || log.borrow_mut().push(timers::now())) as Box<FnMut()>)
+}
+
+#[wasm_bindgen_test]
+fn advance_runs_due_timers_in_order() {
+    assert!(timers::enabled());
+    let start = timers::now();
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let late = recorder(&log);
+    let early = recorder(&log);
+    set_timeout(&late, 20.0);
+    set_timeout(&early, 10.0);
+
+    timers::advance_time(15.0);
+    assert_eq!(*log.borrow(), [start + 10.0]);
+    assert_eq!(timers::now(), start + 15.0);
+    assert_eq!(timers::pending_timers(), 1);
+
+    timers::advance_time(5.0);
+    assert_eq!(*log.borrow(), [start + 10.0, start + 20.0]);
+    assert_eq!(timers::pending_timers(), 0);
+}
+
+#[wasm_bindgen_test]
+fn run_all_runs_intervals_until_cleared() {
+    let start = timers::now();
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let id = Rc::new(RefCell::new(None));
+    let interval = {
+        let (log, id) = (log.clone(), id.clone());
+        Closure::wrap(Box::new(move || {
+            log.borrow_mut().push(timers::now());
+            if log.borrow().len() == 3 {
+                clear_interval(id.borrow().unwrap());
+            }
+        }) as Box<FnMut()>)
+    };
+    *id.borrow_mut() = Some(set_interval(&interval, 5.0));
+
+    timers::run_all_timers();
+    assert_eq!(*log.borrow(), [start + 5.0, start + 10.0, start + 15.0]);
+    assert_eq!(timers::pending_timers(), 0);
+}
+
+#[wasm_bindgen_test]
+fn clock_follows_fake_time() {
+    let date = Date::now();
+    timers::advance_time(1000.0);
+    assert_eq!(Date::now(), date + 1000.0);
+    assert_eq!(Date::new_0().get_time(), date + 1000.0);
+
+    // Called as a function `Date` returns the current time as a string.
+    let now = Function::new_no_args("return Date();").call0(&JsValue::NULL);
+    let now = now.unwrap().as_string().unwrap();
+    assert_eq!(Date::parse(&now), ((date + 1000.0) / 1000.0).floor() * 1000.0);
+}
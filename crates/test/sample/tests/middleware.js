@@ -0,0 +1,64 @@
+const wasm = require('wasm-bindgen-test.js');
+const assert = require('assert');
+
+// Returns the hooks called while running `f`, as `[hook, name, value]`.
+function record(f) {
+    const calls = [];
+    const remove = wasm.addMiddleware({
+        onCall(name, args) { calls.push(['onCall', name, args]); },
+        onReturn(name, ret) { calls.push(['onReturn', name, ret]); },
+        onError(name, e) { calls.push(['onError', name, e.message]); },
+    });
+    try {
+        f();
+    } finally {
+        remove();
+    }
+    return calls;
+}
+
+exports.js_reports_calls = () => {
+    const calls = record(() => assert.strictEqual(wasm.middleware_double(2), 4));
+    assert.deepStrictEqual(calls, [
+        ['onCall', 'middleware_double', [2]],
+        ['onReturn', 'middleware_double', 4],
+    ]);
+
+    // Nothing is reported once the middleware is removed.
+    const before = calls.length;
+    wasm.middleware_double(3);
+    assert.strictEqual(calls.length, before);
+};
+
+exports.js_validates_arguments = () => {
+    const remove = wasm.addMiddleware({
+        onCall(name, args) {
+            if (args[0] > 10)
+                throw new Error(`${name} called with ${args[0]}`);
+        },
+    });
+    try {
+        assert.throws(() => wasm.middleware_double(11), /middleware_double called with 11/);
+        assert.strictEqual(wasm.middleware_double(1), 2);
+    } finally {
+        remove();
+    }
+};
+
+exports.js_reports_accessors_by_kind = () => {
+    const counter = new wasm.Counter();
+    const calls = record(() => {
+        counter.count = 3;
+        assert.strictEqual(counter.count, 3);
+        assert.strictEqual(counter.add(1), 4);
+    });
+    assert.deepStrictEqual(calls, [
+        ['onCall', 'set Counter.count', [3]],
+        ['onReturn', 'set Counter.count', undefined],
+        ['onCall', 'get Counter.count', []],
+        ['onReturn', 'get Counter.count', 3],
+        ['onCall', 'Counter.add', [1]],
+        ['onReturn', 'Counter.add', 4],
+    ]);
+    counter.free();
+};
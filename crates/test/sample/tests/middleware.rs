@@ -0,0 +1,55 @@
+//! Tests of the bindings generated with `--middleware`, whose JS half
+//! registers middleware around calls to the exports below.
+
+extern crate wasm_bindgen;
+extern crate wasm_bindgen_test;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(middleware);
+
+#[wasm_bindgen]
+pub struct Counter {
+    pub count: u32,
+}
+
+#[wasm_bindgen]
+impl Counter {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Counter {
+        Counter { count: 0 }
+    }
+
+    pub fn add(&mut self, n: u32) -> u32 {
+        self.count += n;
+        self.count
+    }
+}
+
+#[wasm_bindgen]
+pub fn middleware_double(n: u32) -> u32 {
+    n * 2
+}
+
+#[wasm_bindgen(module = "tests/middleware.js")]
+extern "C" {
+    fn js_reports_calls();
+    fn js_validates_arguments();
+    fn js_reports_accessors_by_kind();
+}
+
+#[wasm_bindgen_test]
+fn reports_calls() {
+    js_reports_calls();
+}
+
+#[wasm_bindgen_test]
+fn validates_arguments() {
+    js_validates_arguments();
+}
+
+#[wasm_bindgen_test]
+fn reports_accessors_by_kind() {
+    js_reports_accessors_by_kind();
+}
@@ -0,0 +1,36 @@
+//! Tests of `assert_js_snapshot!`, checked against the snapshots in
+//! `tests/snapshots`.
+
+extern crate js_sys;
+extern crate wasm_bindgen;
+extern crate wasm_bindgen_test;
+
+use js_sys::{Array, DataView, Map, Object, Reflect, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test]
+fn unnamed_snapshots_are_numbered() {
+    assert_js_snapshot!(JsValue::from(1));
+    assert_js_snapshot!(JsValue::from_str("two"));
+}
+
+#[wasm_bindgen_test]
+fn object_keys_are_sorted() {
+    let obj = Object::new();
+    let list = Array::of2(&JsValue::from(1), &JsValue::from_str("x"));
+    Reflect::set(&obj, &"b".into(), &list).unwrap();
+    Reflect::set(&obj, &"a".into(), &JsValue::NULL).unwrap();
+    let map = Map::new();
+    map.set(&"k".into(), &JsValue::TRUE);
+    Reflect::set(&obj, &"map".into(), &map).unwrap();
+    assert_js_snapshot!("object", obj);
+}
+
+#[wasm_bindgen_test]
+fn views_list_their_bytes() {
+    let bytes = Uint8Array::new_with_length(4);
+    bytes.copy_from_slice(&[1, 2, 3, 4]);
+    let view = DataView::new(&bytes.buffer(), 1, 2);
+    assert_js_snapshot!("views", Array::of2(&bytes, &view));
+}
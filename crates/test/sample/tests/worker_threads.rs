@@ -0,0 +1,67 @@
+//! Tests of the pool of worker threads, which need to be compiled with
+//! `RUSTFLAGS='-C target-feature=+atomics'`. Running them with
+//! `WASM_BINDGEN_TEST_THREADS=1` makes sure work is queued.
+
+#![cfg(target_feature = "atomics")]
+
+extern crate futures;
+extern crate js_sys;
+extern crate wasm_bindgen;
+extern crate wasm_bindgen_futures;
+extern crate wasm_bindgen_test;
+
+use futures::Future;
+use js_sys::{global, Function, Promise, Reflect};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::threads::MainThreadValue;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(main_thread, js_name = __sample_double)]
+    fn main_thread_double(a: u32) -> u32;
+}
+
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_worker_threads);
+
+#[wasm_bindgen_test]
+fn spawns_more_closures_than_threads() {
+    let closures = threads::available() * 4 + 1;
+    let counter = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..closures {
+        let (counter, tx) = (counter.clone(), tx.clone());
+        threads::spawn(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            tx.send(()).unwrap();
+        });
+    }
+    // The main thread blocks here, so the queued closures have to be picked
+    // up by the workers on their own.
+    for _ in 0..closures {
+        rx.recv().unwrap();
+    }
+    assert_eq!(counter.load(Ordering::SeqCst), closures);
+}
+
+#[wasm_bindgen_test(async)]
+fn calls_main_thread_imports_from_workers() -> impl Future<Item = (), Error = JsValue> {
+    let double = Function::new_with_args("a", "return a * 2;");
+    Reflect::set(&global(), &"__sample_double".into(), &double).unwrap();
+    let promise = Promise::new(&mut |resolve, _reject| {
+        let resolve = MainThreadValue::new(resolve);
+        threads::spawn(move || {
+            // The worker blocks until the main thread has run the call, which
+            // it's free to do as the test is waiting on a promise.
+            let doubled = main_thread_double(21);
+            threads::run_on_main(move || {
+                let resolve = resolve.into_inner();
+                resolve.call1(&JsValue::UNDEFINED, &doubled.into()).unwrap();
+            });
+        });
+    });
+    JsFuture::from(promise).map(|doubled| assert_eq!(doubled.as_f64(), Some(42.0)))
+}
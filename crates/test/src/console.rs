@@ -0,0 +1,106 @@
+//! Assertions about console output produced by tests.
+//!
+//! The test harness intercepts `console.log`, `console.error` and friends to
+//! capture the output of each test. The functions here hook into that to
+//! assert that a block of code did, or didn't, log something.
+//!
+//! By default a test which logs with `console.error` fails even if it
+//! otherwise passes, unless the message was expected through one of these
+//! functions. Suites where that's undesirable can be configured with
+//! `wasm_bindgen_test_configure!(allow_console_errors)`.
+//!
+//! Note that only output logged synchronously while the closures here execute
+//! is observed.
+
+use __rt::capture_console;
+
+/// The console methods whose output can be asserted on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Level {
+    /// `console.debug`
+    Debug,
+    /// `console.log`
+    Log,
+    /// `console.info`
+    Info,
+    /// `console.warn`
+    Warn,
+    /// `console.error`
+    Error,
+}
+
+impl Level {
+    fn method(&self) -> &'static str {
+        match self {
+            Level::Debug => "console.debug",
+            Level::Log => "console.log",
+            Level::Info => "console.info",
+            Level::Warn => "console.warn",
+            Level::Error => "console.error",
+        }
+    }
+}
+
+/// Runs `f` and asserts that it logged at least one message at `level`
+/// containing `pattern`, returning the result of `f`.
+///
+/// # Panics
+///
+/// Panics if no matching message was logged, or if called outside of a test.
+pub fn expect_console_output<R>(level: Level, pattern: &str, f: impl FnOnce() -> R) -> R {
+    let (ret, messages) = capture_console(level, f);
+    if !messages.iter().any(|m| m.contains(pattern)) {
+        panic!(
+            "expected a message containing {:?} from `{}`, but got {}",
+            pattern,
+            level.method(),
+            render(&messages),
+        );
+    }
+    ret
+}
+
+/// Runs `f` and asserts that it didn't log anything at `level`, returning the
+/// result of `f`.
+///
+/// # Panics
+///
+/// Panics if anything was logged, or if called outside of a test.
+pub fn expect_no_console_output<R>(level: Level, f: impl FnOnce() -> R) -> R {
+    let (ret, messages) = capture_console(level, f);
+    if messages.len() > 0 {
+        panic!(
+            "expected no output from `{}`, but got {}",
+            level.method(),
+            render(&messages),
+        );
+    }
+    ret
+}
+
+/// Shorthand for `expect_console_output(Level::Error, pattern, f)`.
+pub fn expect_console_error<R>(pattern: &str, f: impl FnOnce() -> R) -> R {
+    expect_console_output(Level::Error, pattern, f)
+}
+
+/// Shorthand for `expect_console_output(Level::Warn, pattern, f)`.
+pub fn expect_console_warn<R>(pattern: &str, f: impl FnOnce() -> R) -> R {
+    expect_console_output(Level::Warn, pattern, f)
+}
+
+/// Runs `f`, allowing it to log any errors without failing the test.
+pub fn allow_console_errors<R>(f: impl FnOnce() -> R) -> R {
+    capture_console(Level::Error, f).0
+}
+
+fn render(messages: &[String]) -> String {
+    if messages.len() == 0 {
+        return String::from("nothing");
+    }
+    let mut ret = String::from("these messages:\n");
+    for message in messages {
+        ret.push_str("\n    ");
+        ret.push_str(message);
+    }
+    ret
+}
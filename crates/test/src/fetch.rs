@@ -0,0 +1,255 @@
+//! Hermetic network access in tests.
+//!
+//! When a test suite is configured with
+//! `wasm_bindgen_test_configure!(mock_fetch)` the test runner replaces the
+//! global `fetch` function before any tests are loaded. The replacement only
+//! knows how to answer requests for which a response was registered with
+//! `mock_fetch`, rejecting everything else, and records every request it sees
+//! so tests can assert on them afterwards.
+//!
+//! ```ignore
+//! use wasm_bindgen_test::fetch::{self, MockResponse};
+//!
+//! wasm_bindgen_test_configure!(run_in_browser mock_fetch);
+//!
+//! #[wasm_bindgen_test(async)]
+//! fn loads_users() -> impl Future<Item = (), Error = JsValue> {
+//!     let users = JsValue::from_serde(&["alice", "bob"]).unwrap();
+//!     fetch::mock_fetch("/api/users", MockResponse::new().json(&users));
+//!
+//!     load_users().map(|users| {
+//!         assert_eq!(users.len(), 2);
+//!         fetch::assert_fetched("GET", "/api/users");
+//!     })
+//! }
+//! ```
+//!
+//! Mocks and recorded requests persist across tests in a suite, so use
+//! `reset` to start from a clean slate.
+
+use js_sys::{global, Array, Object, Reflect, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = __wbgtest_fetch, js_name = mock)]
+    fn fetch_mock(
+        method: Option<&str>,
+        url: &str,
+        status: u16,
+        headers: &Object,
+        body: Option<&str>,
+        delay: f64,
+        error: Option<&str>,
+        times: u32,
+    );
+    #[wasm_bindgen(js_namespace = __wbgtest_fetch, js_name = requests)]
+    fn fetch_requests() -> Array;
+    #[wasm_bindgen(js_namespace = __wbgtest_fetch, js_name = reset)]
+    fn fetch_reset();
+}
+
+/// A canned response returned by the fake `fetch`.
+///
+/// Responses default to an empty body with a `200` status, and are returned
+/// for any number of matching requests.
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    delay: f64,
+    error: Option<String>,
+    times: u32,
+}
+
+impl MockResponse {
+    /// Creates a new successful response with no body.
+    pub fn new() -> MockResponse {
+        MockResponse {
+            status: 200,
+            headers: Vec::new(),
+            body: None,
+            delay: 0.0,
+            error: None,
+            times: 0,
+        }
+    }
+
+    /// Creates a response which fails the request as if there were a network
+    /// error, rejecting the `fetch` promise with a `TypeError` containing
+    /// `message`.
+    pub fn network_error(message: &str) -> MockResponse {
+        let mut ret = MockResponse::new();
+        ret.error = Some(message.to_string());
+        ret
+    }
+
+    /// Sets the HTTP status code of this response.
+    pub fn status(mut self, status: u16) -> MockResponse {
+        self.status = status;
+        self
+    }
+
+    /// Adds a header to this response.
+    pub fn header(mut self, name: &str, value: &str) -> MockResponse {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the body of this response to the text provided.
+    pub fn text(mut self, body: &str) -> MockResponse {
+        self.body = Some(body.to_string());
+        self
+    }
+
+    /// Sets the body of this response to `value` serialized as JSON, also
+    /// setting the `content-type` header appropriately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` can't be serialized with `JSON.stringify`.
+    pub fn json(self, value: &JsValue) -> MockResponse {
+        let body = JSON::stringify(value).expect("failed to serialize mock response");
+        self.header("content-type", "application/json")
+            .text(&String::from(body))
+    }
+
+    /// Delays the response by `ms` milliseconds.
+    ///
+    /// The delay is implemented with `setTimeout`, so it's controlled by the
+    /// `timers` module if fake timers are enabled as well.
+    pub fn delay(mut self, ms: f64) -> MockResponse {
+        self.delay = ms;
+        self
+    }
+
+    /// Only use this response for the next `n` matching requests.
+    ///
+    /// Mocks registered later take priority over earlier ones, so this can be
+    /// used to return a sequence of responses for the same URL.
+    pub fn times(mut self, n: u32) -> MockResponse {
+        self.times = n;
+        self
+    }
+}
+
+impl Default for MockResponse {
+    fn default() -> MockResponse {
+        MockResponse::new()
+    }
+}
+
+/// A request which was received by the fake `fetch`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request {
+    /// The request method, in upper case.
+    pub method: String,
+    /// The URL passed to `fetch`, unresolved.
+    pub url: String,
+    /// The request body, converted to a string, if any.
+    pub body: Option<String>,
+    /// The request headers, with lower case names.
+    pub headers: Vec<(String, String)>,
+}
+
+/// Returns whether the fake `fetch` was installed for this test suite.
+pub fn enabled() -> bool {
+    Reflect::has(&global(), &JsValue::from_str("__wbgtest_fetch")).unwrap_or(false)
+}
+
+fn assert_enabled() {
+    if !enabled() {
+        panic!(
+            "fetch mocking is not enabled, add \
+             `wasm_bindgen_test_configure!(mock_fetch)` to this test suite"
+        );
+    }
+}
+
+/// Registers `response` to be returned for requests of any method to `url`.
+///
+/// `url` may be absolute or relative to the page. A `url` without a query
+/// string matches requests regardless of their query string.
+pub fn mock_fetch(url: &str, response: MockResponse) {
+    mock(None, url, response)
+}
+
+/// Same as `mock_fetch`, except only matches requests with the given
+/// `method`.
+pub fn mock_fetch_method(method: &str, url: &str, response: MockResponse) {
+    mock(Some(method), url, response)
+}
+
+fn mock(method: Option<&str>, url: &str, response: MockResponse) {
+    assert_enabled();
+    let headers = Object::new();
+    for (name, value) in response.headers.iter() {
+        let name = JsValue::from_str(&name.to_lowercase());
+        Reflect::set(&headers, &name, &JsValue::from_str(value)).unwrap();
+    }
+    fetch_mock(
+        method,
+        url,
+        response.status,
+        &headers,
+        response.body.as_ref().map(|s| &s[..]),
+        response.delay,
+        response.error.as_ref().map(|s| &s[..]),
+        response.times,
+    );
+}
+
+/// Returns all requests made so far, in order.
+pub fn requests() -> Vec<Request> {
+    assert_enabled();
+    let mut ret = Vec::new();
+    fetch_requests().for_each(&mut |request, _, _| {
+        let get = |name: &str| Reflect::get(&request, &JsValue::from_str(name)).unwrap();
+        let headers = get("headers");
+        let mut pairs = Vec::new();
+        Object::keys(headers.unchecked_ref()).for_each(&mut |name, _, _| {
+            let value = Reflect::get(&headers, &name).unwrap();
+            pairs.push((name.as_string().unwrap(), value.as_string().unwrap()));
+        });
+        ret.push(Request {
+            method: get("method").as_string().unwrap(),
+            url: get("url").as_string().unwrap(),
+            body: get("body").as_string(),
+            headers: pairs,
+        });
+    });
+    ret
+}
+
+/// Asserts that at least one request to `url` with `method` was made,
+/// returning the most recent one.
+///
+/// Note that `url` is compared exactly against the URL passed to `fetch`.
+pub fn assert_fetched(method: &str, url: &str) -> Request {
+    let requests = requests();
+    let found = requests
+        .iter()
+        .rev()
+        .find(|r| r.method.eq_ignore_ascii_case(method) && r.url == url);
+    match found {
+        Some(r) => r.clone(),
+        None => {
+            let seen = requests
+                .iter()
+                .map(|r| format!("    {} {}\n", r.method, r.url))
+                .collect::<String>();
+            panic!(
+                "expected a request to {} {}, but only saw:\n{}",
+                method, url, seen
+            )
+        }
+    }
+}
+
+/// Removes all registered mocks and forgets all recorded requests.
+pub fn reset() {
+    assert_enabled();
+    fetch_reset()
+}
@@ -0,0 +1,147 @@
+//! Comparing rendered images against golden images.
+//!
+//! Tests which render something with a 2D canvas, WebGL or WebGPU can compare
+//! the result against a PNG checked in to `tests/golden`, named after the
+//! name passed to the functions here. The pixels are sent to the test runner
+//! which does the comparison, allowing for small differences between
+//! platforms and drivers with a perceptual threshold. When an image doesn't
+//! match, the rendered image and a diff highlighting the differences are
+//! written out to the artifacts directory so they can be inspected or
+//! uploaded by CI.
+//!
+//! ```ignore
+//! wasm_bindgen_test_configure!(run_in_browser);
+//!
+//! #[wasm_bindgen_test]
+//! fn draws_logo() {
+//!     let canvas = create_canvas(64, 64);
+//!     draw_logo(&canvas);
+//!     wasm_bindgen_test::golden::assert_canvas_matches(
+//!         "logo",
+//!         &canvas,
+//!         wasm_bindgen_test::golden::DEFAULT_THRESHOLD,
+//!     );
+//! }
+//! ```
+//!
+//! Golden images are created and updated just like snapshots, by setting
+//! `WASM_BINDGEN_TEST_UPDATE_SNAPSHOTS=1` in the environment of the test
+//! runner. Comparisons are only supported in browsers.
+
+use js_sys::{global, Reflect};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = __wbgtest_golden_compare)]
+    fn golden_compare(
+        name: &str,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        threshold: f64,
+    ) -> Option<String>;
+    #[wasm_bindgen(js_name = __wbgtest_golden_read_canvas)]
+    fn golden_read_canvas(canvas: &JsValue) -> Vec<u8>;
+}
+
+/// A threshold which tolerates the slight differences in antialiasing and
+/// color precision typically seen between GPUs, while still catching any
+/// visible change.
+pub const DEFAULT_THRESHOLD: f64 = 0.1;
+
+/// Returns whether golden images can be compared in this environment, which
+/// is only the case in browsers.
+pub fn supported() -> bool {
+    Reflect::has(&global(), &JsValue::from_str("__wbgtest_golden_compare")).unwrap_or(false)
+}
+
+/// Asserts that the contents of `canvas` match the golden image `name`.
+///
+/// `canvas` may be an `HTMLCanvasElement` or an `OffscreenCanvas` rendered to
+/// with any kind of context. Note that the drawing buffer of a WebGL canvas is
+/// cleared once it's been presented, so either compare it in the same task as
+/// drawing to it or create the context with `preserveDrawingBuffer: true`.
+///
+/// Pixels are compared by their perceptual distance, from 0 for identical
+/// pixels to 1 for black and white, and they match if their distance is at
+/// most `threshold`.
+///
+/// # Panics
+///
+/// Panics if any pixel doesn't match, if the golden image doesn't exist or is
+/// of a different size, or if `name` contains anything other than ASCII
+/// letters, digits, `_` and `-`.
+pub fn assert_canvas_matches(name: &str, canvas: &JsValue, threshold: f64) {
+    assert_supported();
+    let width = dimension(canvas, "width");
+    let height = dimension(canvas, "height");
+    let pixels = golden_read_canvas(canvas);
+    assert_pixels_match(name, width, height, &pixels, threshold);
+}
+
+/// Asserts that an image of `width` by `height` pixels matches the golden
+/// image `name`, in the same way as `assert_canvas_matches`.
+///
+/// This is useful for textures read back from the GPU, for example with
+/// WebGL's `readPixels` or by mapping a WebGPU buffer a texture was copied
+/// into. `rgba` must contain 8-bit RGBA pixels without any padding between
+/// rows, starting with the top row, so images read with `readPixels` need to
+/// be flipped with `flip_rows` first.
+pub fn assert_pixels_match(name: &str, width: u32, height: u32, rgba: &[u8], threshold: f64) {
+    assert_supported();
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        panic!(
+            "invalid golden image name `{}`, only ASCII letters, digits, `_` \
+             and `-` are allowed",
+            name
+        );
+    }
+    if rgba.len() as u64 != u64::from(width) * u64::from(height) * 4 {
+        panic!(
+            "expected {} bytes of pixels for a {}x{} image, got {}",
+            u64::from(width) * u64::from(height) * 4,
+            width,
+            height,
+            rgba.len()
+        );
+    }
+    if let Some(msg) = golden_compare(name, width, height, rgba, threshold) {
+        panic!("{}", msg);
+    }
+}
+
+/// Reverses the order of the rows of an image with `width` RGBA pixels per
+/// row, turning the bottom-up rows returned by WebGL's `readPixels` into
+/// top-down ones.
+pub fn flip_rows(rgba: &mut [u8], width: u32) {
+    let row = width as usize * 4;
+    if row == 0 {
+        return;
+    }
+    let rows = rgba.len() / row;
+    for i in 0..rows / 2 {
+        let (top, bottom) = rgba.split_at_mut((rows - 1 - i) * row);
+        top[i * row..(i + 1) * row].swap_with_slice(&mut bottom[..row]);
+    }
+}
+
+fn assert_supported() {
+    if !supported() {
+        panic!(
+            "golden images can only be compared in browsers, add \
+             `wasm_bindgen_test_configure!(run_in_browser)` to this test suite"
+        );
+    }
+}
+
+fn dimension(canvas: &JsValue, name: &str) -> u32 {
+    match Reflect::get(canvas, &JsValue::from_str(name)).map(|v| v.as_f64()) {
+        Ok(Some(n)) => n as u32,
+        _ => panic!("expected a canvas with a numeric `{}`", name),
+    }
+}
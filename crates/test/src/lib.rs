@@ -7,13 +7,20 @@
 extern crate console_error_panic_hook;
 extern crate futures;
 extern crate js_sys;
+#[cfg(feature = "coverage")]
+extern crate minicov;
 #[macro_use]
 extern crate scoped_tls;
 extern crate wasm_bindgen;
 extern crate wasm_bindgen_futures;
 extern crate wasm_bindgen_test_macro;
 
-pub use wasm_bindgen_test_macro::wasm_bindgen_test;
+pub use wasm_bindgen_test_macro::{
+    wasm_bindgen_test, wasm_bindgen_test_setup, wasm_bindgen_test_teardown,
+};
+
+use futures::Future;
+use wasm_bindgen::JsValue;
 
 /// Helper macro which acts like `println!` only routes to `console.log`
 /// instead.
@@ -24,6 +31,44 @@ macro_rules! console_log {
     )
 }
 
+/// Asserts that a JS value matches a snapshot stored on disk.
+///
+/// This macro is invoked as either:
+///
+/// ```ignore
+/// assert_js_snapshot!(value);
+/// assert_js_snapshot!("my-snapshot", value);
+/// ```
+///
+/// where `value` is anything that implements `AsRef<JsValue>`. The value is
+/// serialized into a stable textual form (object keys are sorted, for example)
+/// and compared against the contents of a `*.snap` file in the snapshot
+/// directory, `tests/snapshots` by default. Without an explicit name
+/// snapshots are named after the test they're asserted in.
+///
+/// When a snapshot doesn't match, or doesn't exist yet, the test fails. Set
+/// `WASM_BINDGEN_TEST_UPDATE_SNAPSHOTS=1` in the environment of
+/// `wasm-bindgen-test-runner` to instead write out the new snapshot.
+#[macro_export]
+macro_rules! assert_js_snapshot {
+    ($value:expr) => (
+        $crate::__rt::snapshot::assert(
+            None,
+            &$value,
+            file!(),
+            line!(),
+        )
+    );
+    ($name:expr, $value:expr) => (
+        $crate::__rt::snapshot::assert(
+            Some($name),
+            &$value,
+            file!(),
+            line!(),
+        )
+    );
+}
+
 /// A macro used to configured how this test is executed by the
 /// `wasm-bindgen-test-runner` harness.
 ///
@@ -38,6 +83,24 @@ macro_rules! console_log {
 ///
 /// * `run_in_browser` - requires that this test is run in a browser rather than
 ///   node.js, which is the default for executing tests.
+/// * `fake_timers` - installs fake versions of `setTimeout`, `setInterval`,
+///   `Date.now` and friends before any tests run, whose notion of time is
+///   controlled through the functions in the `timers` module.
+/// * `mock_fetch` - replaces `fetch` with a fake implementation which only
+///   returns responses registered through the `fetch` module.
+/// * `allow_console_errors` - don't fail tests which log messages with
+///   `console.error` that weren't expected through the `console` module.
+/// * `run_in_worker_threads` - runs tests in node.js with a pool of worker
+///   threads sharing the test's memory, which tests can use through the
+///   `threads` module. The test must be compiled with the `atomics` target
+///   feature.
+/// * `run_in_deno` - runs tests in Deno rather than node.js. Deno must be
+///   installed and on `PATH`.
+/// * `run_in_dedicated_worker`, `run_in_shared_worker` and
+///   `run_in_service_worker` - runs tests in a browser like `run_in_browser`,
+///   but inside a worker of that type started by the test page.
+/// * `middleware` - generates bindings with `--middleware`, so tests can
+///   observe calls to exports with `addMiddleware`.
 ///
 /// This macro may be invoked at most one time per test suite (an entire binary
 /// like `tests/foo.rs`, not per module)
@@ -49,8 +112,95 @@ macro_rules! wasm_bindgen_test_configure {
         pub static __WBG_TEST_RUN_IN_BROWSER: [u8; 1] = [0x01];
         $crate::wasm_bindgen_test_configure!($($others)*);
     );
+    (fake_timers $($others:tt)*) => (
+        #[link_section = "__wasm_bindgen_test_unstable"]
+        #[cfg(target_arch = "wasm32")]
+        pub static __WBG_TEST_FAKE_TIMERS: [u8; 1] = [0x02];
+        $crate::wasm_bindgen_test_configure!($($others)*);
+    );
+    (mock_fetch $($others:tt)*) => (
+        #[link_section = "__wasm_bindgen_test_unstable"]
+        #[cfg(target_arch = "wasm32")]
+        pub static __WBG_TEST_MOCK_FETCH: [u8; 1] = [0x03];
+        $crate::wasm_bindgen_test_configure!($($others)*);
+    );
+    (allow_console_errors $($others:tt)*) => (
+        #[link_section = "__wasm_bindgen_test_unstable"]
+        #[cfg(target_arch = "wasm32")]
+        pub static __WBG_TEST_ALLOW_CONSOLE_ERRORS: [u8; 1] = [0x04];
+        $crate::wasm_bindgen_test_configure!($($others)*);
+    );
+    (run_in_worker_threads $($others:tt)*) => (
+        #[link_section = "__wasm_bindgen_test_unstable"]
+        #[cfg(target_arch = "wasm32")]
+        pub static __WBG_TEST_RUN_IN_WORKER_THREADS: [u8; 1] = [0x05];
+        $crate::wasm_bindgen_test_configure!($($others)*);
+    );
+    (run_in_deno $($others:tt)*) => (
+        #[link_section = "__wasm_bindgen_test_unstable"]
+        #[cfg(target_arch = "wasm32")]
+        pub static __WBG_TEST_RUN_IN_DENO: [u8; 1] = [0x06];
+        $crate::wasm_bindgen_test_configure!($($others)*);
+    );
+    (run_in_dedicated_worker $($others:tt)*) => (
+        #[link_section = "__wasm_bindgen_test_unstable"]
+        #[cfg(target_arch = "wasm32")]
+        pub static __WBG_TEST_RUN_IN_DEDICATED_WORKER: [u8; 1] = [0x07];
+        $crate::wasm_bindgen_test_configure!($($others)*);
+    );
+    (run_in_shared_worker $($others:tt)*) => (
+        #[link_section = "__wasm_bindgen_test_unstable"]
+        #[cfg(target_arch = "wasm32")]
+        pub static __WBG_TEST_RUN_IN_SHARED_WORKER: [u8; 1] = [0x08];
+        $crate::wasm_bindgen_test_configure!($($others)*);
+    );
+    (run_in_service_worker $($others:tt)*) => (
+        #[link_section = "__wasm_bindgen_test_unstable"]
+        #[cfg(target_arch = "wasm32")]
+        pub static __WBG_TEST_RUN_IN_SERVICE_WORKER: [u8; 1] = [0x09];
+        $crate::wasm_bindgen_test_configure!($($others)*);
+    );
+    (middleware $($others:tt)*) => (
+        #[link_section = "__wasm_bindgen_test_unstable"]
+        #[cfg(target_arch = "wasm32")]
+        pub static __WBG_TEST_MIDDLEWARE: [u8; 1] = [0x0a];
+        $crate::wasm_bindgen_test_configure!($($others)*);
+    );
     () => ()
 }
 
+/// Registers a test to be executed by the harness.
+///
+/// This can only be called from a function annotated with
+/// `#[wasm_bindgen_test(register)]`, which the harness runs while collecting
+/// tests to execute. Tests registered this way are asynchronous, the test
+/// passes if the future returned by `test` resolves without panicking or
+/// returning an error. They're named after the function they're registered
+/// from, and they're filtered and reported just like any other test.
+///
+/// ```ignore
+/// #[wasm_bindgen_test(register)]
+/// fn fixtures() {
+///     for (name, input) in FIXTURES {
+///         wasm_bindgen_test::register(name, move || {
+///             future::lazy(move || Ok(check_fixture(input)))
+///         });
+///     }
+/// }
+/// ```
+pub fn register<F>(name: &str, test: impl FnOnce() -> F + 'static)
+where
+    F: Future<Item = (), Error = JsValue> + 'static,
+{
+    __rt::register(name, test)
+}
+
+pub mod console;
+pub mod fetch;
+pub mod golden;
+pub mod profiling;
+pub mod threads;
+pub mod timers;
+
 #[path = "rt/mod.rs"]
 pub mod __rt;
@@ -0,0 +1,189 @@
+//! Tracking the time and memory used by parts of a test.
+//!
+//! `start_profiling` and `stop_profiling` bracket a region of a test, which is
+//! recorded as a CPU profile with `console.profile` and measured for how long
+//! it took and how much the JS heap grew in the meantime. Regions can be
+//! nested, in which case `stop_profiling` stops the innermost one.
+//!
+//! ```ignore
+//! use wasm_bindgen_test::profiling::{start_profiling, stop_profiling};
+//!
+//! #[wasm_bindgen_test]
+//! fn parse_large_document() {
+//!     let input = large_document();
+//!     start_profiling("parse");
+//!     let doc = parse(&input);
+//!     let profile = stop_profiling();
+//!     assert!(profile.duration_ms < 500.0);
+//! }
+//! ```
+//!
+//! Every profile is also handed to the test runner, which collects them into
+//! `target/wasm32-unknown-unknown/wbg-profiles/<test binary>.jsonl` so they
+//! can be tracked across runs without opening DevTools. The CPU profiles
+//! themselves are only recorded when DevTools are attached, or in the trace
+//! written by the runner's `--profile-tests` option.
+
+use std::cell::RefCell;
+
+use futures::future::{self, Either};
+use futures::Future;
+use js_sys::{global, Function, Object, Promise, Reflect, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = profile)]
+    fn console_profile(name: &str);
+    #[wasm_bindgen(js_namespace = console, js_name = profileEnd)]
+    fn console_profile_end(name: &str);
+    #[wasm_bindgen(js_namespace = __wbgtest_real_timers, js_name = now)]
+    fn real_now() -> f64;
+    #[wasm_bindgen(js_name = __wbgtest_profiles_write)]
+    fn profiles_write(line: &str);
+}
+
+/// The measurements of a region of a test, returned by `stop_profiling`.
+#[derive(Clone, Debug)]
+pub struct Profile {
+    /// The name passed to `start_profiling`.
+    pub name: String,
+    /// How long the region took to run, in milliseconds of real time even if
+    /// fake timers are installed.
+    pub duration_ms: f64,
+    /// Bytes of JS heap in use when the region started, if the environment
+    /// exposes it.
+    pub heap_before: Option<f64>,
+    /// Bytes of JS heap in use when the region finished.
+    pub heap_after: Option<f64>,
+}
+
+impl Profile {
+    /// Returns by how many bytes the JS heap grew during the region, which
+    /// is negative if it shrank.
+    ///
+    /// Note that the heap is garbage collected whenever the engine sees fit,
+    /// so this is only a rough indication of how much memory was allocated.
+    pub fn heap_delta(&self) -> Option<f64> {
+        Some(self.heap_after? - self.heap_before?)
+    }
+}
+
+struct Started {
+    name: String,
+    start: f64,
+    heap: Option<f64>,
+}
+
+thread_local!(static STARTED: RefCell<Vec<Started>> = RefCell::new(Vec::new()));
+
+/// Starts profiling a region of a test called `name`.
+pub fn start_profiling(name: &str) {
+    console_profile(name);
+    let started = Started {
+        name: name.to_string(),
+        start: real_now(),
+        heap: heap_used(),
+    };
+    STARTED.with(|s| s.borrow_mut().push(started));
+}
+
+/// Stops profiling the region most recently started with `start_profiling`,
+/// returning its measurements.
+///
+/// # Panics
+///
+/// Panics if there's no region being profiled.
+pub fn stop_profiling() -> Profile {
+    let started = match STARTED.with(|s| s.borrow_mut().pop()) {
+        Some(started) => started,
+        None => panic!("`stop_profiling` called without a matching `start_profiling`"),
+    };
+    let profile = Profile {
+        duration_ms: real_now() - started.start,
+        heap_before: started.heap,
+        heap_after: heap_used(),
+        name: started.name,
+    };
+    console_profile_end(&profile.name);
+    record(&profile);
+    profile
+}
+
+/// Measures the memory used by the whole page in bytes with
+/// `performance.measureUserAgentSpecificMemory`.
+///
+/// This is a lot more accurate than the heap usage measured by
+/// `stop_profiling`, but it's only available in Chrome and only if the page
+/// is cross-origin isolated, which the test server does when
+/// `WASM_BINDGEN_TEST_CROSS_ORIGIN_ISOLATED=1` is set. The returned future
+/// resolves to `None` otherwise. Note that browsers wait for the next garbage
+/// collection to measure memory, which may take several seconds.
+pub fn measure_memory() -> impl Future<Item = Option<f64>, Error = JsValue> {
+    let global = global();
+    let isolated = Reflect::get(&global, &"crossOriginIsolated".into())
+        .ok()
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let measure = Reflect::get(&global, &"performance".into())
+        .ok()
+        .filter(|p| p.is_object())
+        .and_then(|p| {
+            let f = Reflect::get(&p, &"measureUserAgentSpecificMemory".into()).ok()?;
+            Some((p, f.dyn_into::<Function>().ok()?))
+        });
+    let (performance, measure) = match measure {
+        Some(pair) if isolated => pair,
+        _ => return Either::A(future::ok(None)),
+    };
+    let promise = match measure.call0(&performance) {
+        Ok(promise) => Promise::from(promise),
+        Err(e) => return Either::A(future::err(e)),
+    };
+    Either::B(JsFuture::from(promise).map(|result| {
+        Reflect::get(&result, &"bytes".into())
+            .ok()
+            .and_then(|v| v.as_f64())
+    }))
+}
+
+/// Returns how many bytes of JS heap are in use, through
+/// `performance.memory` in Chrome or `process.memoryUsage` in node.js.
+fn heap_used() -> Option<f64> {
+    let global = global();
+    let get = |obj: &JsValue, key: &str| {
+        Reflect::get(obj, &key.into())
+            .ok()
+            .filter(|v| !v.is_undefined() && !v.is_null())
+    };
+    if let Some(memory) = get(&global, "performance").and_then(|p| get(&p, "memory")) {
+        return get(&memory, "usedJSHeapSize").and_then(|v| v.as_f64());
+    }
+    let process = get(&global, "process")?;
+    let usage = get(&process, "memoryUsage")?
+        .dyn_into::<Function>()
+        .ok()?
+        .call0(&process)
+        .ok()?;
+    get(&usage, "heapUsed").and_then(|v| v.as_f64())
+}
+
+/// Hands a profile to the test runner to be collected, if it supports that.
+fn record(profile: &Profile) {
+    let global = global();
+    if !Reflect::has(&global, &"__wbgtest_profiles_write".into()).unwrap_or(false) {
+        return;
+    }
+    let obj = Object::new();
+    let set = |key: &str, val: JsValue| {
+        Reflect::set(&obj, &key.into(), &val).unwrap();
+    };
+    set("name", profile.name.as_str().into());
+    set("duration_ms", profile.duration_ms.into());
+    set("heap_before", profile.heap_before.into());
+    set("heap_after", profile.heap_after.into());
+    let line = String::from(JSON::stringify(&obj).unwrap());
+    profiles_write(&line);
+}
@@ -3,15 +3,19 @@
 //! Currently this is quite simple, rendering the same as the console tests in
 //! node.js. Output here is rendered in a `pre`, however.
 
-use js_sys::Error;
+use js_sys::{global, Error, Reflect};
 use wasm_bindgen::prelude::*;
 
 /// Implementation of `Formatter` for browsers.
 ///
 /// Routes all output to a `pre` on the page currently. Eventually this probably
 /// wants to be a pretty table with colors and folding and whatnot.
+///
+/// Tests running in a worker don't have a page, so their output is instead
+/// handed to `__wbgtest_output`, which the test runner defines to send it to
+/// the page that started the worker.
 pub struct Browser {
-    pre: Element,
+    pre: Option<Element>,
 }
 
 #[wasm_bindgen]
@@ -30,23 +34,33 @@ extern "C" {
     type BrowserError;
     #[wasm_bindgen(method, getter, structural)]
     fn stack(this: &BrowserError) -> JsValue;
+
+    #[wasm_bindgen(js_name = __wbgtest_output)]
+    fn worker_output(text: &str);
 }
 
 impl Browser {
     /// Creates a new instance of `Browser`, assuming that its APIs will work
     /// (requires `Node::new()` to have return `None` first).
     pub fn new() -> Browser {
+        if !Reflect::has(&global(), &JsValue::from_str("document")).unwrap_or(false) {
+            return Browser { pre: None };
+        }
         let pre = document.getElementById("output");
         pre.set_text_content("");
-        Browser { pre }
+        Browser { pre: Some(pre) }
     }
 }
 
 impl super::Formatter for Browser {
     fn writeln(&self, line: &str) {
-        let mut html = self.pre.text_content();
+        let pre = match &self.pre {
+            Some(pre) => pre,
+            None => return worker_output(&format!("{}\n", line)),
+        };
+        let mut html = pre.text_content();
         html.extend(line.chars().chain(Some('\n')));
-        self.pre.set_text_content(&html);
+        pre.set_text_content(&html);
     }
 
     fn log_test(&self, name: &str, result: &Result<(), JsValue>) {
@@ -0,0 +1,38 @@
+//! Code coverage of test runs.
+//!
+//! When a test binary is compiled with `-C instrument-coverage` and this
+//! crate's `coverage` feature, `minicov` provides the parts of LLVM's
+//! profiling runtime which count how often each region of code runs. Once all
+//! tests have finished we capture the raw profile and hand it to the runner
+//! through `__wbgtest_coverage_write`, which writes it to a `.profraw` file,
+//! either directly in node.js or through the test server in a browser.
+
+#[cfg(feature = "coverage")]
+use js_sys::{global, Reflect};
+#[cfg(feature = "coverage")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "coverage")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = __wbgtest_coverage_write)]
+    fn coverage_write(data: &[u8]);
+}
+
+/// Sends the coverage of the run to the runner, if it can take it.
+#[cfg(feature = "coverage")]
+pub(super) fn write() {
+    if !Reflect::has(&global(), &JsValue::from_str("__wbgtest_coverage_write")).unwrap_or(false) {
+        return;
+    }
+    let mut data = Vec::new();
+    // This is only unsafe if other threads are running instrumented code at
+    // the same time, which they aren't once all tests have finished.
+    match unsafe { minicov::capture_coverage(&mut data) } {
+        Ok(()) => coverage_write(&data),
+        Err(_) => super::js_console_log("failed to capture the coverage of this run"),
+    }
+}
+
+#[cfg(not(feature = "coverage"))]
+pub(super) fn write() {}
@@ -1,4 +1,4 @@
-//! Runtime detection of whether we're in node.js or a browser.
+//! Runtime detection of whether we're in node.js (or Deno) or a browser.
 
 use js_sys;
 use wasm_bindgen::prelude::*;
@@ -9,10 +9,12 @@ extern "C" {
     type This;
     #[wasm_bindgen(method, getter, structural, js_name = self)]
     fn self_(me: &This) -> JsValue;
+    #[wasm_bindgen(method, getter, structural, js_name = Deno)]
+    fn deno(me: &This) -> JsValue;
 }
 
 /// Returns whether it's likely we're executing in a browser environment, as
-/// opposed to node.js.
+/// opposed to node.js or Deno.
 // If this function is inlined then there's no other functions in this module
 // (which becomes an object file) to actually pull in the custom section listed
 // above. Force this to never be inlined so if this module is needed its forced
@@ -21,6 +23,7 @@ extern "C" {
 pub fn is_browser() -> bool {
     // Test whether we're in a browser by seeing if the `self` property is
     // defined on the global object, which should in turn only be true in
-    // browsers.
-    js_sys::global().unchecked_into::<This>().self_() != JsValue::undefined()
+    // browsers and Deno, which is told apart by its `Deno` global.
+    let global = js_sys::global().unchecked_into::<This>();
+    global.self_() != JsValue::undefined() && global.deno() == JsValue::undefined()
 }
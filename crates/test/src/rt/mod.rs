@@ -91,13 +91,18 @@ use std::cell::{Cell, RefCell};
 use std::fmt;
 use std::rc::Rc;
 
+use console::Level;
 use console_error_panic_hook;
 use futures::future;
 use futures::prelude::*;
-use js_sys::{Array, Function, Promise};
+use futures::task;
+use js_sys::{Array, Function, Promise, Reflect};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::future_to_promise;
 
+// Number of shims listed after the test results with `--profile-boundary`.
+const MAX_BOUNDARY_STATS: u32 = 20;
+
 // Maximum number of tests to execute concurrently. Eventually this should be a
 // configuration option specified at runtime or at compile time rather than
 // baked in here.
@@ -107,8 +112,11 @@ use wasm_bindgen_futures::future_to_promise;
 const CONCURRENCY: usize = 1;
 
 pub mod browser;
+mod coverage;
 pub mod detect;
 pub mod node;
+mod report;
+pub mod snapshot;
 
 /// Runtime test harness support instantiated in JS.
 ///
@@ -126,12 +134,38 @@ struct State {
     /// this is the only "CLI option"
     filter: RefCell<Option<String>>,
 
+    /// Whether to only print the names of the tests which would run instead
+    /// of running them, from `--list`.
+    list: Cell<bool>,
+
+    /// Whether `--list` prints only the names of the tests, without a
+    /// summary, from `--format terse`.
+    terse: Cell<bool>,
+
+    /// Whether only ignored tests should run, from `--ignored`. Tests can't
+    /// be ignored yet, so this runs nothing, but it lets tools which list the
+    /// ignored tests separately work.
+    only_ignored: Cell<bool>,
+
     /// Counter of the number of tests that have succeeded.
     succeeded: Cell<usize>,
 
     /// Counter of the number of tests that have been ignored
     ignored: Cell<usize>,
 
+    /// Whether tests which log to `console.error` outside of an
+    /// `expect_console_*` block should fail. Defaults to `true` unless the
+    /// suite is configured with `allow_console_errors`.
+    fail_on_console_error: bool,
+
+    /// Timeout in milliseconds of tests which don't have their own, set with
+    /// the runner's `WASM_BINDGEN_TEST_TIMEOUT`.
+    default_timeout: Option<u32>,
+
+    /// Timeout of the next test to be executed, set by
+    /// `#[wasm_bindgen_test(timeout_ms = ...)]` right before it is.
+    next_timeout: Cell<Option<u32>>,
+
     /// A list of all tests which have failed.
     ///
     /// Each test listed here is paired with a `JsValue` that represents the
@@ -146,9 +180,25 @@ struct State {
     /// of asynchronous work, so they're sitting on the running list.
     running: RefCell<Vec<Test>>,
 
+    /// Functions to run before each test in their module, registered with
+    /// `#[wasm_bindgen_test_setup]`.
+    setups: RefCell<Vec<Hook>>,
+
+    /// Functions to run after each test in their module, registered with
+    /// `#[wasm_bindgen_test_teardown]`.
+    teardowns: RefCell<Vec<Hook>>,
+
     /// How to actually format output, either node.js or browser-specific
     /// implementation.
     formatter: Box<Formatter>,
+
+    /// Machine-readable report of all results, if the runner asked for one
+    /// with `--format`.
+    report: Option<report::Report>,
+
+    /// Whether the runner is recording a performance trace, in which case
+    /// each test is marked on the timeline with the User Timing API.
+    profile: bool,
 }
 
 /// Representation of one test that needs to be executed.
@@ -159,6 +209,21 @@ struct Test {
     name: String,
     future: Box<Future<Item = (), Error = JsValue>>,
     output: Rc<RefCell<Output>>,
+
+    /// When the test started executing, in milliseconds.
+    started: f64,
+}
+
+/// A function run around each test in a module, and its submodules.
+struct Hook {
+    module: String,
+    f: Rc<Fn() -> Box<Future<Item = (), Error = JsValue>>>,
+}
+
+impl Hook {
+    fn applies_to(&self, test: &str) -> bool {
+        test.starts_with(&self.module) && test[self.module.len()..].starts_with("::")
+    }
 }
 
 /// Captured output of each test.
@@ -169,6 +234,14 @@ struct Output {
     info: String,
     warn: String,
     error: String,
+
+    /// Messages logged with `console.error` which weren't captured by an
+    /// `expect_console_*` block.
+    unexpected_errors: Vec<String>,
+
+    /// Stack of console messages currently being captured by
+    /// `capture_console`, innermost last.
+    captures: Vec<(Level, Vec<String>)>,
 }
 
 trait Formatter {
@@ -192,6 +265,19 @@ extern "C" {
     // General-purpose conversion into a `String`.
     #[wasm_bindgen(js_name = String)]
     fn stringify(val: &JsValue) -> String;
+
+    // The runner saves these before they may be replaced by fake timers.
+    #[wasm_bindgen(js_namespace = __wbgtest_real_timers, js_name = setTimeout)]
+    fn real_set_timeout(f: &Closure<FnMut()>, ms: u32) -> JsValue;
+    #[wasm_bindgen(js_namespace = __wbgtest_real_timers, js_name = clearTimeout)]
+    fn real_clear_timeout(id: &JsValue);
+    #[wasm_bindgen(js_namespace = __wbgtest_real_timers, js_name = now)]
+    fn real_now() -> f64;
+
+    #[wasm_bindgen(js_namespace = performance, js_name = mark)]
+    fn performance_mark(name: &str);
+    #[wasm_bindgen(js_namespace = performance, js_name = measure)]
+    fn performance_measure(name: &str, start: &str);
 }
 
 /// Internal implementation detail of the `console_log!` macro.
@@ -214,15 +300,45 @@ impl Context {
             Some(node) => Box::new(node) as Box<Formatter>,
             None => Box::new(browser::Browser::new()),
         };
+        // The test runner defines this global for suites configured with
+        // `allow_console_errors`.
+        let allow_console_errors = Reflect::get(
+            &js_sys::global(),
+            &JsValue::from_str("__wbgtest_allow_console_errors"),
+        )
+        .ok()
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+        let default_timeout = Reflect::get(
+            &js_sys::global(),
+            &JsValue::from_str("__wbgtest_timeout"),
+        )
+        .ok()
+        .and_then(|v| v.as_f64())
+        .map(|ms| ms as u32);
+        let profile = Reflect::get(&js_sys::global(), &JsValue::from_str("__wbgtest_profile"))
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
         Context {
             state: Rc::new(State {
                 filter: Default::default(),
+                list: Default::default(),
+                terse: Default::default(),
+                only_ignored: Default::default(),
                 failures: Default::default(),
                 ignored: Default::default(),
+                fail_on_console_error: !allow_console_errors,
+                default_timeout,
+                next_timeout: Default::default(),
                 remaining: Default::default(),
                 running: Default::default(),
+                setups: Default::default(),
+                teardowns: Default::default(),
                 succeeded: Default::default(),
                 formatter,
+                report: report::Report::from_global(),
+                profile,
             }),
         }
     }
@@ -230,17 +346,38 @@ impl Context {
     /// Inform this context about runtime arguments passed to the test
     /// harness.
     ///
-    /// Eventually this will be used to support flags, but for now it's just
-    /// used to support test filters.
+    /// This supports a test filter along with `--list`, `--format` and
+    /// `--ignored` for listing tests like libtest does.
     pub fn args(&mut self, args: Vec<JsValue>) {
-        // Here we want to reject all flags like `--foo` or `-f` as we don't
-        // support anything, and also we only support at most one non-flag
-        // argument as a test filter.
+        // Here we want to reject all flags like `--foo` or `-f` we don't
+        // support, and also we only support at most one non-flag argument as
+        // a test filter.
         //
         // Everything else is rejected.
         let mut filter = self.state.filter.borrow_mut();
-        for arg in args {
-            let arg = arg.as_string().unwrap();
+        let mut args = args.into_iter().map(|arg| arg.as_string().unwrap());
+        while let Some(arg) = args.next() {
+            if arg == "--list" {
+                self.state.list.set(true);
+                continue;
+            }
+            if arg == "--ignored" {
+                self.state.only_ignored.set(true);
+                continue;
+            }
+            if arg == "--format" || arg.starts_with("--format=") {
+                let format = match arg.find('=') {
+                    Some(i) => Some(arg[i + 1..].to_string()),
+                    None => args.next(),
+                };
+                match format.as_ref().map(|s| &s[..]) {
+                    Some("pretty") => self.state.terse.set(false),
+                    Some("terse") => self.state.terse.set(true),
+                    Some(other) => panic!("format {} not supported", other),
+                    None => panic!("`--format` requires a value"),
+                }
+                continue;
+            }
             if arg.starts_with("-") {
                 panic!("flag {} not supported", arg);
             } else if filter.is_some() {
@@ -260,16 +397,11 @@ impl Context {
     /// The promise returned resolves to either `true` if all tests passed or
     /// `false` if at least one test failed.
     pub fn run(&self, tests: Vec<JsValue>) -> Promise {
-        let noun = if tests.len() == 1 { "test" } else { "tests" };
-        self.state
-            .formatter
-            .writeln(&format!("running {} {}", tests.len(), noun));
-        self.state.formatter.writeln("");
-
         // Execute all our test functions through their wasm shims (unclear how
         // to pass native function pointers around here). Each test will
         // execute one of the `execute_*` tests below which will push a
-        // future onto our `remaining` list, which we'll process later.
+        // future onto our `remaining` list, which we'll process later. Test
+        // generators may push any number of futures instead.
         let cx_arg = (self as *const Context as u32).into();
         for test in tests {
             match Function::from(test).call1(&JsValue::null(), &cx_arg) {
@@ -283,6 +415,17 @@ impl Context {
             }
         }
 
+        if self.state.list.get() {
+            return self.list();
+        }
+
+        let total = self.state.remaining.borrow().len() + self.state.ignored.get();
+        let noun = if total == 1 { "test" } else { "tests" };
+        self.state
+            .formatter
+            .writeln(&format!("running {} {}", total, noun));
+        self.state.formatter.writeln("");
+
         // Now that we've collected all our tests we wrap everything up in a
         // future to actually do all the processing, and pass it out to JS as a
         // `Promise`.
@@ -293,7 +436,39 @@ impl Context {
     }
 }
 
+impl Context {
+    /// Prints the names of the tests which would run in the same format as
+    /// libtest's `--list`, without running them.
+    fn list(&self) -> Promise {
+        let remaining = self.state.remaining.borrow();
+        for test in remaining.iter() {
+            self.state
+                .formatter
+                .writeln(&format!("{}: test", test.name));
+        }
+        if !self.state.terse.get() {
+            let noun = if remaining.len() == 1 {
+                "test"
+            } else {
+                "tests"
+            };
+            self.state.formatter.writeln("");
+            self.state
+                .formatter
+                .writeln(&format!("{} {}, 0 benchmarks", remaining.len(), noun));
+        }
+        Promise::resolve(&JsValue::TRUE)
+    }
+}
+
 scoped_thread_local!(static CURRENT_OUTPUT: RefCell<Output>);
+scoped_thread_local!(static CURRENT_GENERATOR: Generator);
+
+/// A test generator which is currently registering tests.
+struct Generator {
+    cx: Context,
+    name: String,
+}
 
 /// Handler for `console.log` invocations.
 ///
@@ -309,51 +484,86 @@ scoped_thread_local!(static CURRENT_OUTPUT: RefCell<Output>);
 // how not all output is captured, causing some inconsistencies sometimes.
 #[wasm_bindgen]
 pub fn __wbgtest_console_log(args: &Array) {
-    record(args, |output| &mut output.log)
+    record(args, Level::Log)
 }
 
 /// Handler for `console.debug` invocations. See above.
 #[wasm_bindgen]
 pub fn __wbgtest_console_debug(args: &Array) {
-    record(args, |output| &mut output.debug)
+    record(args, Level::Debug)
 }
 
 /// Handler for `console.info` invocations. See above.
 #[wasm_bindgen]
 pub fn __wbgtest_console_info(args: &Array) {
-    record(args, |output| &mut output.info)
+    record(args, Level::Info)
 }
 
 /// Handler for `console.warn` invocations. See above.
 #[wasm_bindgen]
 pub fn __wbgtest_console_warn(args: &Array) {
-    record(args, |output| &mut output.warn)
+    record(args, Level::Warn)
 }
 
 /// Handler for `console.error` invocations. See above.
 #[wasm_bindgen]
 pub fn __wbgtest_console_error(args: &Array) {
-    record(args, |output| &mut output.error)
+    record(args, Level::Error)
 }
 
-fn record(args: &Array, dst: impl FnOnce(&mut Output) -> &mut String) {
+fn record(args: &Array, level: Level) {
     if !CURRENT_OUTPUT.is_set() {
         return;
     }
 
+    let mut msg = String::new();
+    args.for_each(&mut |val, idx, _array| {
+        if idx != 0 {
+            msg.push_str(" ");
+        }
+        msg.push_str(&stringify(&val));
+    });
+
     CURRENT_OUTPUT.with(|output| {
         let mut out = output.borrow_mut();
-        let dst = dst(&mut out);
-        args.for_each(&mut |val, idx, _array| {
-            if idx != 0 {
-                dst.push_str(" ");
+        let captured = match out.captures.iter_mut().rev().find(|c| c.0 == level) {
+            Some(capture) => {
+                capture.1.push(msg.clone());
+                true
             }
-            dst.push_str(&stringify(&val));
-        });
+            None => false,
+        };
+        if level == Level::Error && !captured {
+            out.unexpected_errors.push(msg.clone());
+        }
+        let dst = match level {
+            Level::Debug => &mut out.debug,
+            Level::Log => &mut out.log,
+            Level::Info => &mut out.info,
+            Level::Warn => &mut out.warn,
+            Level::Error => &mut out.error,
+        };
+        dst.push_str(&msg);
         dst.push_str("\n");
     });
 }
 
+/// Runs `f`, returning its result along with all messages logged at `level`
+/// in the meantime.
+///
+/// Messages are still recorded as normal output of the test, but captured
+/// `console.error` messages aren't considered unexpected. This is the
+/// implementation of the `console` module's helpers.
+pub fn capture_console<R>(level: Level, f: impl FnOnce() -> R) -> (R, Vec<String>) {
+    if !CURRENT_OUTPUT.is_set() {
+        panic!("console output can only be captured while a test is running");
+    }
+    CURRENT_OUTPUT.with(|output| output.borrow_mut().captures.push((level, Vec::new())));
+    let ret = f();
+    let (_, messages) = CURRENT_OUTPUT.with(|output| output.borrow_mut().captures.pop().unwrap());
+    (ret, messages)
+}
+
 impl Context {
     /// Entry point for a synchronous test in wasm. The `#[wasm_bindgen_test]`
     /// macro generates invocations of this method.
@@ -371,16 +581,85 @@ impl Context {
         self.execute(name, future::lazy(f))
     }
 
+    /// Entry point for a test generator in wasm. The
+    /// `#[wasm_bindgen_test(register)]` macro generates invocations of this
+    /// method.
+    ///
+    /// Tests registered with `register` while `f` runs are named after the
+    /// generator, `name::test`.
+    pub fn execute_generator(&self, name: &str, f: impl FnOnce()) {
+        let generator = Generator {
+            cx: Context {
+                state: self.state.clone(),
+            },
+            name: name.to_string(),
+        };
+        CURRENT_GENERATOR.set(&generator, f);
+    }
+
+    /// Entry point for a synchronous `#[wasm_bindgen_test_setup]` function.
+    pub fn setup_sync(&self, module: &str, f: impl Fn() + 'static) {
+        self.hook(&self.state.setups, module, move || future::lazy(|| Ok(f())));
+    }
+
+    /// Entry point for an asynchronous `#[wasm_bindgen_test_setup(async)]`
+    /// function.
+    pub fn setup_async<F>(&self, module: &str, f: impl Fn() -> F + 'static)
+    where
+        F: Future<Item = (), Error = JsValue> + 'static,
+    {
+        self.hook(&self.state.setups, module, f);
+    }
+
+    /// Entry point for a synchronous `#[wasm_bindgen_test_teardown]`
+    /// function.
+    pub fn teardown_sync(&self, module: &str, f: impl Fn() + 'static) {
+        self.hook(&self.state.teardowns, module, move || future::lazy(|| Ok(f())));
+    }
+
+    /// Entry point for an asynchronous `#[wasm_bindgen_test_teardown(async)]`
+    /// function.
+    pub fn teardown_async<F>(&self, module: &str, f: impl Fn() -> F + 'static)
+    where
+        F: Future<Item = (), Error = JsValue> + 'static,
+    {
+        self.hook(&self.state.teardowns, module, f);
+    }
+
+    fn hook<F>(&self, hooks: &RefCell<Vec<Hook>>, module: &str, f: impl Fn() -> F + 'static)
+    where
+        F: Future<Item = (), Error = JsValue> + 'static,
+    {
+        hooks.borrow_mut().push(Hook {
+            module: module.to_string(),
+            f: Rc::new(move || Box::new(f()) as Box<Future<Item = (), Error = JsValue>>),
+        });
+    }
+
+    /// Sets the timeout of the next test to be executed. The
+    /// `#[wasm_bindgen_test(timeout_ms = ...)]` macro generates invocations
+    /// of this method.
+    pub fn set_next_timeout(&self, ms: u32) {
+        self.state.next_timeout.set(Some(ms));
+    }
+
     fn execute(&self, name: &str, test: impl Future<Item = (), Error = JsValue> + 'static) {
+        let timeout = self.state.next_timeout.take().or(self.state.default_timeout);
+
         // If our test is filtered out, record that it was filtered and move
         // on, nothing to do here.
         let filter = self.state.filter.borrow();
-        if let Some(filter) = &*filter {
-            if !name.contains(filter) {
-                let ignored = self.state.ignored.get();
-                self.state.ignored.set(ignored + 1);
-                return;
+        let filtered = match &*filter {
+            Some(filter) => !name.contains(filter),
+            None => false,
+        };
+        if filtered || self.state.only_ignored.get() {
+            let ignored = self.state.ignored.get();
+            self.state.ignored.set(ignored + 1);
+            if let Some(report) = &self.state.report {
+                report.ignored(name);
             }
+            return;
         }
 
         // Looks like we've got a test that needs to be executed! Push it onto
@@ -388,14 +667,84 @@ impl Context {
         let output = Rc::new(RefCell::new(Output::default()));
         let future = TestFuture {
             output: output.clone(),
-            test,
+            snapshots: snapshot::Snapshots::new(name),
+            test: Timeout {
+                test: self.with_hooks(name, test),
+                ms: timeout,
+                timer: None,
+                fired: Default::default(),
+            },
         };
         self.state.remaining.borrow_mut().push(Test {
             name: name.to_string(),
             future: Box::new(future),
             output,
+            started: 0.0,
         });
     }
+
+    /// Wraps a test in the setup and teardown functions of its modules.
+    ///
+    /// Hooks are only looked up once the test starts, as they may be
+    /// registered after the test itself. Setups of outer modules run first,
+    /// and teardowns run in the opposite order. Teardowns run even if the
+    /// test fails, and the test fails if any of them do.
+    fn with_hooks(
+        &self,
+        name: &str,
+        test: impl Future<Item = (), Error = JsValue> + 'static,
+    ) -> impl Future<Item = (), Error = JsValue> {
+        let state = self.state.clone();
+        let name = name.to_string();
+        future::lazy(move || {
+            let applicable = |hooks: &RefCell<Vec<Hook>>| {
+                let mut hooks = hooks
+                    .borrow()
+                    .iter()
+                    .filter(|hook| hook.applies_to(&name))
+                    .map(|hook| (hook.module.len(), hook.f.clone()))
+                    .collect::<Vec<_>>();
+                hooks.sort_by_key(|hook| hook.0);
+                hooks.into_iter().map(|hook| hook.1).collect::<Vec<_>>()
+            };
+            let setups = applicable(&state.setups);
+            let mut teardowns = applicable(&state.teardowns);
+            teardowns.reverse();
+
+            let setup = future::loop_fn(setups.into_iter(), |mut setups| match setups.next() {
+                Some(f) => future::Either::A(f().map(move |()| future::Loop::Continue(setups))),
+                None => future::Either::B(future::ok(future::Loop::Break(()))),
+            });
+            setup.and_then(move |()| test).then(move |result| {
+                future::loop_fn(teardowns.into_iter(), |mut teardowns| {
+                    match teardowns.next() {
+                        Some(f) => future::Either::A(f().then(move |teardown| {
+                            teardown.map(|()| future::Loop::Continue(teardowns))
+                        })),
+                        None => future::Either::B(future::ok(future::Loop::Break(()))),
+                    }
+                })
+                .then(move |teardown| result.and(teardown))
+            })
+        })
+    }
+}
+
+/// Implementation of `wasm_bindgen_test::register`.
+pub fn register<F>(name: &str, test: impl FnOnce() -> F + 'static)
+where
+    F: Future<Item = (), Error = JsValue> + 'static,
+{
+    if !CURRENT_GENERATOR.is_set() {
+        panic!(
+            "tests can only be registered from a \
+             `#[wasm_bindgen_test(register)]` function"
+        );
+    }
+    CURRENT_GENERATOR.with(|generator| {
+        let name = format!("{}::{}", generator.name, name);
+        generator.cx.execute_async(&name, test);
+    });
 }
 
 struct ExecuteTests(Rc<State>);
@@ -431,6 +780,10 @@ impl Future for ExecuteTests {
                 Some(test) => test,
                 None => break,
             };
+            test.started = real_now();
+            if self.0.profile {
+                performance_mark(&format!("start {}", test.name));
+            }
             let result = match test.future.poll() {
                 Ok(Async::Ready(())) => Ok(()),
                 Ok(Async::NotReady) => {
@@ -452,6 +805,12 @@ impl Future for ExecuteTests {
         // so we shouldn't have any more remaining tests either.
         assert_eq!(remaining.len(), 0);
 
+        // The report is written before the results are printed, as that's
+        // when the runner considers the tests finished.
+        if let Some(report) = &self.0.report {
+            report.write();
+        }
+        coverage::write();
         self.0.print_results();
         let all_passed = self.0.failures.borrow().len() == 0;
         Ok(Async::Ready(all_passed))
@@ -459,10 +818,39 @@ impl Future for ExecuteTests {
 }
 
 impl State {
-    fn log_test_result(&self, test: Test, result: Result<(), JsValue>) {
+    fn log_test_result(&self, test: Test, mut result: Result<(), JsValue>) {
+        // Tests which otherwise pass still fail if they've logged errors
+        // nobody was expecting.
+        if result.is_ok() && self.fail_on_console_error {
+            let output = test.output.borrow();
+            if output.unexpected_errors.len() > 0 {
+                let msg = format!(
+                    "test logged unexpected `console.error` output:\n\n{}\n\
+                     wrap code which is expected to log errors in \
+                     `wasm_bindgen_test::console::expect_console_error`, or \
+                     configure this suite with `allow_console_errors`",
+                    tab(&output.unexpected_errors.join("\n")),
+                );
+                result = Err(js_sys::Error::new(&msg).into());
+            }
+        }
+
         // Print out information about the test passing or failing
         self.formatter.log_test(&test.name, &result);
 
+        if self.profile {
+            performance_measure(&test.name, &format!("start {}", test.name));
+        }
+
+        if let Some(report) = &self.report {
+            let error = result
+                .as_ref()
+                .err()
+                .map(|e| self.formatter.stringify_error(e));
+            let duration = real_now() - test.started;
+            report.finished(&test.name, duration, &test.output.borrow(), error);
+        }
+
         // Save off the test for later processing when we print the final
         // results.
         match result {
@@ -483,6 +871,7 @@ impl State {
                 self.formatter.writeln(&format!("    {}", test.name));
             }
         }
+        self.print_boundary_stats();
         self.formatter.writeln("");
         self.formatter.writeln(&format!(
             "test result: {}. \
@@ -496,6 +885,54 @@ impl State {
         ));
     }
 
+    /// Prints the import and export shims which took the most time, if the
+    /// bindings were generated with `--profile-boundary`, in which case the
+    /// test runner defines `__wbgtest_boundary_stats`.
+    fn print_boundary_stats(&self) {
+        let stats = match Reflect::get(
+            &js_sys::global(),
+            &JsValue::from_str("__wbgtest_boundary_stats"),
+        ) {
+            Ok(f) => {
+                if !f.is_function() {
+                    return;
+                }
+                match Function::from(f).call0(&JsValue::undefined()) {
+                    Ok(stats) => Array::from(&stats),
+                    Err(_) => return,
+                }
+            }
+            Err(_) => return,
+        };
+        if stats.length() == 0 {
+            return;
+        }
+        self.formatter.writeln("\nboundary crossings:\n");
+        self.formatter.writeln(&format!(
+            "    {:>10} {:>12}  {}",
+            "calls", "total ms", "shim"
+        ));
+        for i in 0..stats.length().min(MAX_BOUNDARY_STATS) {
+            let entry = stats.get(i);
+            let field = |name: &str| Reflect::get(&entry, &JsValue::from_str(name)).ok();
+            let name = field("name")
+                .and_then(|v| v.as_string())
+                .unwrap_or_default();
+            let calls = field("calls").and_then(|v| v.as_f64()).unwrap_or(0.0);
+            let time = field("time").and_then(|v| v.as_f64()).unwrap_or(0.0);
+            self.formatter.writeln(&format!(
+                "    {:>10} {:>12.3}  {}",
+                calls as u64, time, name
+            ));
+        }
+        if stats.length() > MAX_BOUNDARY_STATS {
+            self.formatter.writeln(&format!(
+                "    ... and {} more",
+                stats.length() - MAX_BOUNDARY_STATS
+            ));
+        }
+    }
+
     fn accumulate_console_output(&self, logs: &mut String, which: &str, output: &str) {
         if output.is_empty() {
             return;
@@ -526,12 +963,15 @@ impl State {
 /// A wrapper future around each test
 ///
 /// This future is what's actually executed for each test and is what's stored
-/// inside of a `Test`. This wrapper future performs two critical functions:
+/// inside of a `Test`. This wrapper future performs a few critical functions:
 ///
 /// * First, every time when polled, it configures the `CURRENT_OUTPUT` tls
 ///   variable to capture output for the current test. That way at least when
 ///   we've got Rust code running we'll be able to capture output.
 ///
+/// * Similarly it configures the `CURRENT_SNAPSHOTS` tls variable so
+///   `assert_js_snapshot!` knows which test it's being invoked from.
+///
 /// * Next, this "catches panics". Right now all wasm code is configured as
 ///   panic=abort, but it's more like an exception in JS. It's pretty sketchy
 ///   to actually continue executing Rust code after an "abort", but we don't
@@ -552,6 +992,7 @@ impl State {
 /// perfect code on the first try, right? *sobs*
 struct TestFuture<F> {
     output: Rc<RefCell<Output>>,
+    snapshots: snapshot::Snapshots,
     test: F,
 }
 
@@ -567,14 +1008,68 @@ impl<F: Future<Error = JsValue>> Future for TestFuture<F> {
 
     fn poll(&mut self) -> Poll<F::Item, F::Error> {
         let test = &mut self.test;
+        let snapshots = &self.snapshots;
         let mut future_output = None;
         CURRENT_OUTPUT.set(&self.output, || {
-            __wbg_test_invoke(&mut || future_output = Some(test.poll()))
+            snapshot::CURRENT_SNAPSHOTS.set(snapshots, || {
+                __wbg_test_invoke(&mut || future_output = Some(test.poll()))
+            })
         })?;
         future_output.unwrap()
     }
 }
 
+/// Fails a test which hasn't finished after `ms` milliseconds, including its
+/// setup and teardown functions.
+///
+/// The timer only starts once the test is first polled, and of course only
+/// asynchronous tests can be interrupted, a synchronous one which never
+/// returns still hangs the whole run.
+struct Timeout<F> {
+    test: F,
+    ms: Option<u32>,
+    timer: Option<(JsValue, Closure<FnMut()>)>,
+    fired: Rc<Cell<bool>>,
+}
+
+impl<F: Future<Item = (), Error = JsValue>> Future for Timeout<F> {
+    type Item = ();
+    type Error = JsValue;
+
+    fn poll(&mut self) -> Poll<(), JsValue> {
+        if let Async::Ready(()) = self.test.poll()? {
+            return Ok(Async::Ready(()));
+        }
+        let ms = match self.ms {
+            Some(ms) => ms,
+            None => return Ok(Async::NotReady),
+        };
+        if self.fired.get() {
+            let msg = format!("test timed out after {} ms", ms);
+            return Err(js_sys::Error::new(&msg).into());
+        }
+        if self.timer.is_none() {
+            let task = task::current();
+            let fired = self.fired.clone();
+            let closure = Closure::wrap(Box::new(move || {
+                fired.set(true);
+                task.notify();
+            }) as Box<FnMut()>);
+            let id = real_set_timeout(&closure, ms);
+            self.timer = Some((id, closure));
+        }
+        Ok(Async::NotReady)
+    }
+}
+
+impl<F> Drop for Timeout<F> {
+    fn drop(&mut self) {
+        if let Some((id, _closure)) = self.timer.take() {
+            real_clear_timeout(&id);
+        }
+    }
+}
+
 fn tab(s: &str) -> String {
     let mut result = String::new();
     for line in s.lines() {
@@ -1,4 +1,4 @@
-//! Support for printing status information of a test suite in node.js
+//! Support for printing status information of a test suite in node.js and Deno
 //!
 //! This currently uses the same output as `libtest`, only reimplemented here
 //! for node itself.
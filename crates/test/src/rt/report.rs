@@ -0,0 +1,272 @@
+//! Machine-readable reports of test results.
+//!
+//! When `wasm-bindgen-test-runner` is passed `--format json` or
+//! `--format junit` it defines a `__wbgtest_report` global describing the
+//! report to generate. We collect the result of every test here, render the
+//! report once all tests have finished, and hand it back to the runner
+//! through `__wbgtest_report.write` which writes it to a file, either directly
+//! in node.js or through the test server in a browser.
+
+use std::cell::RefCell;
+
+use js_sys::{global, Array, Object, Reflect, JSON};
+use wasm_bindgen::prelude::*;
+
+use super::Output;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = __wbgtest_report, js_name = write)]
+    fn report_write(contents: &str);
+}
+
+enum Format {
+    Json,
+    Junit,
+}
+
+enum Status {
+    Passed,
+    Failed(String),
+    Ignored,
+}
+
+struct Entry {
+    name: String,
+    status: Status,
+    /// How long the test took to execute, in milliseconds.
+    duration: f64,
+    /// Console output of the test, by level.
+    output: Vec<(&'static str, String)>,
+}
+
+pub(super) struct Report {
+    format: Format,
+    suite: String,
+    entries: RefCell<Vec<Entry>>,
+}
+
+impl Report {
+    /// Returns the report requested by the runner, if any.
+    pub(super) fn from_global() -> Option<Report> {
+        let config = Reflect::get(&global(), &JsValue::from_str("__wbgtest_report")).ok()?;
+        if !config.is_object() {
+            return None;
+        }
+        let get = |key: &str| {
+            Reflect::get(&config, &JsValue::from_str(key))
+                .ok()
+                .and_then(|v| v.as_string())
+        };
+        let format = match get("format")?.as_str() {
+            "json" => Format::Json,
+            "junit" => Format::Junit,
+            other => panic!("unknown report format `{}`", other),
+        };
+        Some(Report {
+            format,
+            suite: get("suite").unwrap_or_default(),
+            entries: Default::default(),
+        })
+    }
+
+    /// Records a test which was filtered out.
+    pub(super) fn ignored(&self, name: &str) {
+        self.entries.borrow_mut().push(Entry {
+            name: name.to_string(),
+            status: Status::Ignored,
+            duration: 0.0,
+            output: Vec::new(),
+        });
+    }
+
+    /// Records a test which finished, with the error it failed with if it
+    /// didn't pass.
+    pub(super) fn finished(
+        &self,
+        name: &str,
+        duration: f64,
+        output: &Output,
+        error: Option<String>,
+    ) {
+        let output = [
+            ("debug", &output.debug),
+            ("log", &output.log),
+            ("info", &output.info),
+            ("warn", &output.warn),
+            ("error", &output.error),
+        ]
+        .iter()
+        .filter(|(_, s)| !s.is_empty())
+        .map(|(level, s)| (*level, s.to_string()))
+        .collect();
+        self.entries.borrow_mut().push(Entry {
+            name: name.to_string(),
+            status: match error {
+                Some(error) => Status::Failed(error),
+                None => Status::Passed,
+            },
+            duration,
+            output,
+        });
+    }
+
+    /// Renders the report and sends it to the runner to be written out.
+    pub(super) fn write(&self) {
+        let contents = match self.format {
+            Format::Json => self.json(),
+            Format::Junit => self.junit(),
+        };
+        report_write(&contents);
+    }
+
+    /// Returns the number of tests which passed, failed and were ignored.
+    fn counts(&self) -> (u32, u32, u32) {
+        let mut counts = (0, 0, 0);
+        for entry in self.entries.borrow().iter() {
+            match entry.status {
+                Status::Passed => counts.0 += 1,
+                Status::Failed(_) => counts.1 += 1,
+                Status::Ignored => counts.2 += 1,
+            }
+        }
+        counts
+    }
+
+    fn json(&self) -> String {
+        let set = |obj: &Object, key: &str, val: JsValue| {
+            Reflect::set(obj, &JsValue::from_str(key), &val).unwrap();
+        };
+        let tests = Array::new();
+        for entry in self.entries.borrow().iter() {
+            let test = Object::new();
+            set(&test, "name", entry.name.as_str().into());
+            let status = match entry.status {
+                Status::Passed => "passed",
+                Status::Failed(_) => "failed",
+                Status::Ignored => "ignored",
+            };
+            set(&test, "status", status.into());
+            set(&test, "duration_ms", entry.duration.into());
+            let output = Object::new();
+            for (level, s) in entry.output.iter() {
+                set(&output, level, s.as_str().into());
+            }
+            set(&test, "output", output.into());
+            if let Status::Failed(error) = &entry.status {
+                set(&test, "error", error.as_str().into());
+            }
+            tests.push(&test);
+        }
+        let (passed, failed, ignored) = self.counts();
+        let report = Object::new();
+        set(&report, "suite", self.suite.as_str().into());
+        set(&report, "passed", passed.into());
+        set(&report, "failed", failed.into());
+        set(&report, "ignored", ignored.into());
+        set(&report, "tests", tests.into());
+        let json = JSON::stringify_with_replacer_and_space(
+            &report,
+            &JsValue::null(),
+            &JsValue::from_f64(2.0),
+        )
+        .unwrap();
+        let mut json = String::from(json);
+        json.push('\n');
+        json
+    }
+
+    fn junit(&self) -> String {
+        let entries = self.entries.borrow();
+        let total: f64 = entries.iter().map(|e| e.duration).sum();
+        let (_, failed, ignored) = self.counts();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<testsuites>\n");
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+            escape(&self.suite),
+            entries.len(),
+            failed,
+            ignored,
+            total / 1000.0,
+        ));
+        for entry in entries.iter() {
+            // Tools group test cases by their "class", which for us is the
+            // module the test is defined in.
+            let (classname, name) = match entry.name.rfind("::") {
+                Some(i) => (&entry.name[..i], &entry.name[i + 2..]),
+                None => (&self.suite[..], &entry.name[..]),
+            };
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape(name),
+                escape(classname),
+                entry.duration / 1000.0,
+            ));
+            let stdout = entry
+                .output
+                .iter()
+                .filter(|(level, _)| *level != "warn" && *level != "error")
+                .map(|(_, s)| &s[..])
+                .collect::<String>();
+            let stderr = entry
+                .output
+                .iter()
+                .filter(|(level, _)| *level == "warn" || *level == "error")
+                .map(|(_, s)| &s[..])
+                .collect::<String>();
+            let empty = stdout.is_empty() && stderr.is_empty();
+            match &entry.status {
+                Status::Passed if empty => {
+                    xml.push_str("/>\n");
+                    continue;
+                }
+                Status::Passed => xml.push_str(">\n"),
+                Status::Failed(error) => {
+                    let message = error.lines().next().unwrap_or("");
+                    xml.push_str(&format!(
+                        ">\n      <failure message=\"{}\">{}</failure>\n",
+                        escape(message),
+                        escape(error),
+                    ));
+                }
+                Status::Ignored => xml.push_str(">\n      <skipped/>\n"),
+            }
+            if !stdout.is_empty() {
+                xml.push_str(&format!(
+                    "      <system-out>{}</system-out>\n",
+                    escape(&stdout)
+                ));
+            }
+            if !stderr.is_empty() {
+                xml.push_str(&format!(
+                    "      <system-err>{}</system-err>\n",
+                    escape(&stderr)
+                ));
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+/// Escapes text for use in XML attributes and elements, dropping characters
+/// which XML 1.0 doesn't allow at all.
+fn escape(s: &str) -> String {
+    let mut dst = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => dst.push_str("&amp;"),
+            '<' => dst.push_str("&lt;"),
+            '>' => dst.push_str("&gt;"),
+            '"' => dst.push_str("&quot;"),
+            '\'' => dst.push_str("&apos;"),
+            '\t' | '\n' | '\r' => dst.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => dst.push(c),
+        }
+    }
+    dst
+}
@@ -0,0 +1,260 @@
+//! Support for the `assert_js_snapshot!` macro.
+//!
+//! Values are serialized into a stable, human readable format here in Rust and
+//! then compared against snapshot files that the `wasm-bindgen-test-runner`
+//! makes available to us. The runner is responsible for actually reading and
+//! writing snapshot files, either directly through node's `fs` module or via
+//! the test server when executing in a browser. All we see here are a few
+//! global functions it defines for us.
+
+use std::cell::Cell;
+
+use js_sys::{
+    Array, ArrayBuffer, DataView, Date, Error, Function, Map, Object, Reflect, Set, Uint8Array,
+    JSON,
+};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = __wbgtest_snapshot_read)]
+    fn snapshot_read(name: &str) -> Option<String>;
+    #[wasm_bindgen(js_name = __wbgtest_snapshot_write)]
+    fn snapshot_write(name: &str, contents: &str);
+    #[wasm_bindgen(js_name = __wbgtest_snapshot_update)]
+    static UPDATE: JsValue;
+}
+
+/// Per-test state used to automatically name snapshots.
+pub(super) struct Snapshots {
+    test: String,
+    next: Cell<usize>,
+}
+
+impl Snapshots {
+    pub(super) fn new(test: &str) -> Snapshots {
+        Snapshots {
+            test: test.to_string(),
+            next: Cell::new(1),
+        }
+    }
+}
+
+scoped_thread_local!(pub(super) static CURRENT_SNAPSHOTS: Snapshots);
+
+/// Internal implementation detail of the `assert_js_snapshot!` macro.
+///
+/// Serializes `value` and compares it against the snapshot called `name`, or
+/// an automatically generated name based on the currently executing test if
+/// `name` is `None`.
+pub fn assert<T>(name: Option<&str>, value: &T, file: &str, line: u32)
+where
+    T: AsRef<JsValue> + ?Sized,
+{
+    let name = match name {
+        Some(name) => sanitize(name),
+        None => {
+            if !CURRENT_SNAPSHOTS.is_set() {
+                panic!(
+                    "`assert_js_snapshot!` without an explicit name can only \
+                     be used while a test is running ({}:{})",
+                    file, line
+                );
+            }
+            CURRENT_SNAPSHOTS.with(|s| {
+                let n = s.next.get();
+                s.next.set(n + 1);
+                format!("{}-{}", sanitize(&s.test), n)
+            })
+        }
+    };
+    let name = format!("{}.snap", name);
+
+    let mut actual = String::new();
+    serialize(value.as_ref(), 0, &mut Vec::new(), &mut actual);
+    actual.push('\n');
+
+    let update = UPDATE.as_bool() == Some(true);
+    match snapshot_read(&name) {
+        Some(ref expected) if *expected == actual => {}
+        _ if update => snapshot_write(&name, &actual),
+        Some(expected) => panic!(
+            "snapshot `{}` does not match ({}:{})\n\n\
+             expected:\n{}\n\
+             actual:\n{}\n\
+             rerun with `WASM_BINDGEN_TEST_UPDATE_SNAPSHOTS=1` in the \
+             environment to accept the new value",
+            name,
+            file,
+            line,
+            super::tab(&expected),
+            super::tab(&actual),
+        ),
+        None => panic!(
+            "snapshot `{}` does not exist ({}:{})\n\n\
+             actual:\n{}\n\
+             rerun with `WASM_BINDGEN_TEST_UPDATE_SNAPSHOTS=1` in the \
+             environment to create it",
+            name,
+            file,
+            line,
+            super::tab(&actual),
+        ),
+    }
+}
+
+/// Snapshot names end up as file names, so restrict them to a conservative
+/// set of characters. Module paths like `foo::bar` become `foo__bar`.
+fn sanitize(name: &str) -> String {
+    name.replace("::", "__")
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Renders `val` into `dst`, recursing into arrays, maps, sets and objects.
+///
+/// The output is meant to be stable across runs and engines, so object keys
+/// are sorted and anything engine-specific (like stack traces) is omitted.
+/// `parents` is the chain of objects we're currently inside of, used to
+/// detect cycles.
+fn serialize(val: &JsValue, indent: usize, parents: &mut Vec<JsValue>, dst: &mut String) {
+    if val.is_undefined() || val.is_null() || val.as_bool().is_some() {
+        return dst.push_str(&super::stringify(val));
+    }
+    if val.as_f64().is_some() || val.is_symbol() {
+        return dst.push_str(&super::stringify(val));
+    }
+    if val.is_string() {
+        return dst.push_str(&String::from(JSON::stringify(val).unwrap()));
+    }
+    if let Some(f) = val.dyn_ref::<Function>() {
+        let name = String::from(f.name());
+        if name.is_empty() {
+            return dst.push_str("[Function]");
+        }
+        return dst.push_str(&format!("[Function {}]", name));
+    }
+    if !val.is_object() {
+        // things like `BigInt` which we don't have a special rendering for
+        return dst.push_str(&super::stringify(val));
+    }
+
+    if parents.iter().any(|p| p == val) {
+        return dst.push_str("[Circular]");
+    }
+
+    if let Some(date) = val.dyn_ref::<Date>() {
+        if date.get_time().is_nan() {
+            return dst.push_str("Date(Invalid Date)");
+        }
+        return dst.push_str(&format!("Date({})", String::from(date.to_iso_string())));
+    }
+    if let Some(err) = val.dyn_ref::<Error>() {
+        return dst.push_str(&format!(
+            "[{}: {}]",
+            String::from(err.name()),
+            String::from(err.message())
+        ));
+    }
+
+    parents.push(val.clone());
+    if Array::is_array(val) || ArrayBuffer::is_view(val) {
+        let prefix = if Array::is_array(val) {
+            String::new()
+        } else {
+            format!("{} ", constructor_name(val))
+        };
+        // `Array.from` doesn't see anything in a `DataView`, so its bytes are
+        // listed instead.
+        let items = match val.dyn_ref::<DataView>() {
+            Some(view) => Array::from(&Uint8Array::new_with_byte_offset_and_length(
+                &view.buffer(),
+                view.byte_offset() as u32,
+                view.byte_length() as u32,
+            )),
+            None => Array::from(val),
+        };
+        let mut entries = Vec::new();
+        items.for_each(&mut |item, _, _| entries.push(item));
+        list(dst, &format!("{}[", prefix), "]", indent, &entries, |item, dst| {
+            serialize(item, indent + 1, parents, dst)
+        });
+    } else if let Some(map) = val.dyn_ref::<Map>() {
+        let mut entries = Vec::new();
+        map.for_each(&mut |value, key| entries.push((key, value)));
+        list(dst, "Map {", "}", indent, &entries, |(key, value), dst| {
+            serialize(key, indent + 1, parents, dst);
+            dst.push_str(" => ");
+            serialize(value, indent + 1, parents, dst);
+        });
+    } else if let Some(set) = val.dyn_ref::<Set>() {
+        let mut entries = Vec::new();
+        set.for_each(&mut |value, _, _| entries.push(value));
+        list(dst, "Set {", "}", indent, &entries, |value, dst| {
+            serialize(value, indent + 1, parents, dst)
+        });
+    } else {
+        let name = constructor_name(val);
+        let prefix = if name.is_empty() || name == "Object" {
+            String::from("{")
+        } else {
+            format!("{} {{", name)
+        };
+        let mut keys = Vec::new();
+        Object::keys(val.unchecked_ref()).for_each(&mut |key, _, _| {
+            keys.push(key.as_string().unwrap());
+        });
+        keys.sort();
+        list(dst, &prefix, "}", indent, &keys, |key, dst| {
+            let key = JsValue::from_str(key);
+            dst.push_str(&String::from(JSON::stringify(&key).unwrap()));
+            dst.push_str(": ");
+            let value = Reflect::get(val, &key).unwrap_or(JsValue::undefined());
+            serialize(&value, indent + 1, parents, dst);
+        });
+    }
+    parents.pop();
+}
+
+/// Renders a bracketed list of `items`, one per line, or just `open` and
+/// `close` next to each other if the list is empty.
+fn list<T>(
+    dst: &mut String,
+    open: &str,
+    close: &str,
+    indent: usize,
+    items: &[T],
+    mut item: impl FnMut(&T, &mut String),
+) {
+    dst.push_str(open);
+    if items.is_empty() {
+        return dst.push_str(close);
+    }
+    dst.push('\n');
+    for i in items {
+        push_indent(dst, indent + 1);
+        item(i, dst);
+        dst.push_str(",\n");
+    }
+    push_indent(dst, indent);
+    dst.push_str(close);
+}
+
+fn push_indent(dst: &mut String, indent: usize) {
+    for _ in 0..indent {
+        dst.push_str("  ");
+    }
+}
+
+fn constructor_name(val: &JsValue) -> String {
+    Reflect::get(val, &JsValue::from_str("constructor"))
+        .ok()
+        .and_then(|c| c.dyn_into::<Function>().ok())
+        .map(|c| String::from(c.name()))
+        .unwrap_or_default()
+}
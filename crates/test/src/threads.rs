@@ -0,0 +1,307 @@
+//! Running code on other threads in tests.
+//!
+//! When a test suite is configured with
+//! `wasm_bindgen_test_configure!(run_in_worker_threads)` the test runner
+//! executes it in Node.js with a pool of `worker_threads` workers, each of
+//! which instantiates the test binary with the same shared
+//! `WebAssembly.Memory`. This requires the test binary to be compiled with
+//! `-C target-feature=+atomics`. Tests can then run closures on the workers
+//! with `spawn`, and synchronize with them through `std::sync` and atomics:
+//!
+//! ```ignore
+//! wasm_bindgen_test_configure!(run_in_worker_threads);
+//!
+//! #[wasm_bindgen_test]
+//! fn counts_in_parallel() {
+//!     let counter = Arc::new(AtomicUsize::new(0));
+//!     let (tx, rx) = mpsc::channel();
+//!     for _ in 0..4 {
+//!         let (counter, tx) = (counter.clone(), tx.clone());
+//!         wasm_bindgen_test::threads::spawn(move || {
+//!             counter.fetch_add(1, Ordering::SeqCst);
+//!             tx.send(()).unwrap();
+//!         });
+//!     }
+//!     for _ in 0..4 {
+//!         rx.recv().unwrap();
+//!     }
+//!     assert_eq!(counter.load(Ordering::SeqCst), 4);
+//! }
+//! ```
+//!
+//! JS objects can't be shared between threads though, as each thread has its
+//! own JS heap. Closures running on the workers can hold on to a value of the
+//! main thread wrapped in a `MainThreadValue`, and use it by sending work back
+//! to the main thread with `run_on_main`.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::mem::{self, ManuallyDrop};
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+use js_sys::{global, Reflect};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = __wbgtest_spawn)]
+    fn worker_spawn();
+    #[wasm_bindgen(js_name = __wbgtest_work_done)]
+    fn work_done();
+    #[wasm_bindgen(js_name = __wbgtest_run_on_main)]
+    fn main_spawn(work: u32);
+}
+
+/// Work sent to a worker or to the main thread.
+type Work = Box<FnMut() + Send>;
+
+/// Work queued for the workers by `spawn`, which lives in the shared memory.
+///
+/// Workers take work from the queue themselves rather than having it handed
+/// to them by the main thread, which may well be blocked waiting for the
+/// work to be done.
+struct Queue {
+    work: Mutex<VecDeque<Work>>,
+    ready: Condvar,
+}
+
+fn queue() -> &'static Queue {
+    static QUEUE: AtomicUsize = AtomicUsize::new(0);
+    let mut ptr = QUEUE.load(Ordering::SeqCst);
+    if ptr == 0 {
+        let queue = Box::new(Queue {
+            work: Mutex::new(VecDeque::new()),
+            ready: Condvar::new(),
+        });
+        let new = Box::into_raw(queue) as usize;
+        ptr = match QUEUE.compare_and_swap(0, new, Ordering::SeqCst) {
+            0 => new,
+            // Another thread got there first.
+            other => {
+                drop(unsafe { Box::from_raw(new as *mut Queue) });
+                other
+            }
+        };
+    }
+    unsafe { &*(ptr as *const Queue) }
+}
+
+/// Returns whether tests are running with a pool of worker threads.
+pub fn enabled() -> bool {
+    Reflect::has(&global(), &JsValue::from_str("__wbgtest_spawn")).unwrap_or(false)
+}
+
+/// Returns the number of worker threads in the pool, which is the number of
+/// CPUs unless `WASM_BINDGEN_TEST_THREADS` is set for the test runner.
+pub fn available() -> usize {
+    assert_enabled();
+    Reflect::get(&global(), &JsValue::from_str("__wbgtest_threads"))
+        .ok()
+        .and_then(|threads| threads.as_f64())
+        .unwrap() as usize
+}
+
+/// Runs `f` on one of the worker threads.
+///
+/// Workers run one closure at a time, so `f` is queued if they're all busy,
+/// and run by the first worker to finish what it's doing. Workers take work
+/// from the queue without any help from the main thread, so the main thread
+/// can block until `f` is done.
+///
+/// Note that a closure which never returns keeps its worker busy for the rest
+/// of the test suite, and if `f` panics then so does the whole test suite.
+pub fn spawn(f: impl FnOnce() + Send + 'static) {
+    assert_enabled();
+    let mut f = Some(f);
+    let work: Work = Box::new(move || (f.take().unwrap())());
+    // Keep the process alive until the work is done.
+    worker_spawn();
+    let queue = queue();
+    queue.work.lock().unwrap().push_back(work);
+    queue.ready.notify_one();
+}
+
+/// Runs `f` on the main thread, where tests run.
+///
+/// This can be called from the workers as well as from the main thread, and
+/// `f` is run the next time the main thread gets to process events. This means
+/// that a test waiting for `f` must do so asynchronously, by returning a
+/// future, since blocking the main thread with something like
+/// `mpsc::Receiver::recv` prevents `f` from ever running.
+pub fn run_on_main(f: impl FnOnce() + Send + 'static) {
+    if !Reflect::has(&global(), &JsValue::from_str("__wbgtest_run_on_main")).unwrap_or(false) {
+        assert_enabled();
+    }
+    let mut f = Some(f);
+    let work: Box<Work> = Box::new(Box::new(move || (f.take().unwrap())()));
+    main_spawn(Box::into_raw(work) as u32);
+}
+
+/// A value which can be sent to and shared with other threads, but can only
+/// be accessed on the thread which created it.
+///
+/// This is mostly useful to hand JS objects, which belong to the JS heap of
+/// the thread which created them, to closures passed to `spawn`, which can
+/// then send them back to be used on the main thread with `run_on_main`.
+/// Accessing the value on another thread panics, as does dropping it there,
+/// since that can't be done without its thread's JS heap.
+///
+/// ```ignore
+/// let document = MainThreadValue::new(web_sys::window().unwrap().document().unwrap());
+/// threads::spawn(move || {
+///     let title = expensive_computation();
+///     threads::run_on_main(move || document.set_title(&title));
+/// });
+/// ```
+pub struct MainThreadValue<T> {
+    value: ManuallyDrop<T>,
+    thread: usize,
+}
+
+// The value is only ever accessed or dropped on the thread which created it,
+// which is checked at runtime.
+unsafe impl<T> Send for MainThreadValue<T> {}
+unsafe impl<T> Sync for MainThreadValue<T> {}
+
+impl<T> MainThreadValue<T> {
+    /// Wraps `value`, which will only be accessible on the current thread.
+    pub fn new(value: T) -> MainThreadValue<T> {
+        MainThreadValue {
+            value: ManuallyDrop::new(value),
+            thread: thread_id(),
+        }
+    }
+
+    /// Returns whether the value can be accessed on the current thread.
+    pub fn is_valid(&self) -> bool {
+        self.thread == thread_id()
+    }
+
+    /// Returns the value if it can be accessed on the current thread.
+    pub fn get(&self) -> Option<&T> {
+        if self.is_valid() {
+            Some(&self.value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value if it can be accessed on the current thread.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if self.is_valid() {
+            Some(&mut self.value)
+        } else {
+            None
+        }
+    }
+
+    /// Unwraps the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called on a thread other than the one which created it.
+    pub fn into_inner(mut self) -> T {
+        self.assert_valid();
+        let value = unsafe { ptr::read(&*self.value) };
+        mem::forget(self);
+        value
+    }
+
+    fn assert_valid(&self) {
+        if !self.is_valid() {
+            panic!("`MainThreadValue` accessed on a thread other than the one which created it");
+        }
+    }
+}
+
+impl<T> Deref for MainThreadValue<T> {
+    type Target = T;
+
+    /// # Panics
+    ///
+    /// Panics if called on a thread other than the one which created it.
+    fn deref(&self) -> &T {
+        self.assert_valid();
+        &self.value
+    }
+}
+
+impl<T> DerefMut for MainThreadValue<T> {
+    /// # Panics
+    ///
+    /// Panics if called on a thread other than the one which created it.
+    fn deref_mut(&mut self) -> &mut T {
+        self.assert_valid();
+        &mut self.value
+    }
+}
+
+impl<T> Drop for MainThreadValue<T> {
+    fn drop(&mut self) {
+        if self.is_valid() {
+            unsafe { ManuallyDrop::drop(&mut self.value) }
+        } else if !thread::panicking() {
+            // Leak the value rather than drop it on the wrong thread.
+            panic!("`MainThreadValue` dropped on a thread other than the one which created it");
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MainThreadValue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.get() {
+            Some(value) => f.debug_tuple("MainThreadValue").field(value).finish(),
+            None => f.write_str("MainThreadValue(<other thread>)"),
+        }
+    }
+}
+
+/// Returns an identifier of the current thread, unique among the main thread
+/// and the workers.
+fn thread_id() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    thread_local!(static ID: usize = NEXT.fetch_add(1, Ordering::SeqCst));
+    ID.with(|id| *id)
+}
+
+fn assert_enabled() {
+    if !enabled() {
+        panic!(
+            "worker threads are not available, add \
+             `wasm_bindgen_test_configure!(run_in_worker_threads)` to this \
+             test suite"
+        );
+    }
+}
+
+/// Entry point of workers, which run work queued by `spawn` forever.
+#[wasm_bindgen]
+#[doc(hidden)]
+pub fn __wbgtest_worker_entry() {
+    let queue = queue();
+    loop {
+        let mut work = {
+            let mut queued = queue.work.lock().unwrap();
+            loop {
+                match queued.pop_front() {
+                    Some(work) => break work,
+                    None => queued = queue.ready.wait(queued).unwrap(),
+                }
+            }
+        };
+        work();
+        work_done();
+    }
+}
+
+/// Entry point of the main thread for work queued by `run_on_main`.
+#[wasm_bindgen]
+#[doc(hidden)]
+pub fn __wbgtest_main_entry(work: u32) {
+    let mut work = unsafe { Box::from_raw(work as *mut Work) };
+    work();
+}
@@ -0,0 +1,91 @@
+//! Deterministic control over time in tests.
+//!
+//! When a test suite is configured with
+//! `wasm_bindgen_test_configure!(fake_timers)` the test runner replaces
+//! `setTimeout`, `setInterval`, `requestAnimationFrame`, `Date` and
+//! `performance.now` with fake versions before any tests are loaded. Time then
+//! stands still until a test moves it forward with the functions here, which
+//! also synchronously run any timers that become due along the way.
+//!
+//! ```ignore
+//! wasm_bindgen_test_configure!(fake_timers);
+//!
+//! #[wasm_bindgen_test]
+//! fn debounce() {
+//!     let calls = start_debounced_counter(100);
+//!     wasm_bindgen_test::timers::advance_time(99.0);
+//!     assert_eq!(calls.get(), 0);
+//!     wasm_bindgen_test::timers::advance_time(1.0);
+//!     assert_eq!(calls.get(), 1);
+//! }
+//! ```
+
+use js_sys::{global, Reflect};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = __wbgtest_timers, js_name = now)]
+    fn timers_now() -> f64;
+    #[wasm_bindgen(js_namespace = __wbgtest_timers, js_name = advance)]
+    fn timers_advance(ms: f64);
+    #[wasm_bindgen(js_namespace = __wbgtest_timers, js_name = runAll)]
+    fn timers_run_all(limit: u32);
+    #[wasm_bindgen(js_namespace = __wbgtest_timers, js_name = pending)]
+    fn timers_pending() -> u32;
+}
+
+/// Maximum number of timers `run_all_timers` will run before giving up,
+/// guarding against intervals which would otherwise run forever.
+const RUN_ALL_LIMIT: u32 = 10_000;
+
+/// Returns whether fake timers were installed for this test suite.
+pub fn enabled() -> bool {
+    Reflect::has(&global(), &JsValue::from_str("__wbgtest_timers")).unwrap_or(false)
+}
+
+fn assert_enabled() {
+    if !enabled() {
+        panic!(
+            "fake timers are not installed, add \
+             `wasm_bindgen_test_configure!(fake_timers)` to this test suite"
+        );
+    }
+}
+
+/// Returns the current fake time in milliseconds, as returned by
+/// `performance.now()`.
+///
+/// Fake time starts at zero when the test suite is loaded, and `Date.now()`
+/// reports the real time at that point plus this value.
+pub fn now() -> f64 {
+    assert_enabled();
+    timers_now()
+}
+
+/// Moves fake time forward by `ms` milliseconds.
+///
+/// Every timer which becomes due in the meantime is run, in order, with the
+/// clock set to the time it was scheduled for. Timers scheduled by those
+/// timers are also run if they're due before the new time.
+pub fn advance_time(ms: f64) {
+    assert_enabled();
+    timers_advance(ms)
+}
+
+/// Runs all pending timers, moving time forward as far as necessary.
+///
+/// # Panics
+///
+/// Panics if timers keep scheduling more timers, for example with an
+/// interval that is never cleared.
+pub fn run_all_timers() {
+    assert_enabled();
+    timers_run_all(RUN_ALL_LIMIT)
+}
+
+/// Returns the number of timers which are scheduled but haven't run yet.
+pub fn pending_timers() -> u32 {
+    assert_enabled();
+    timers_pending()
+}
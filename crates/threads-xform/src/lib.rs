@@ -75,12 +75,15 @@ impl Config {
     /// * A `start` function is injected (or prepended if one already exists)
     ///   which initializes memory for the first thread and otherwise allocates
     ///   thread ids for all threads.
+    /// * If the module uses LLVM's thread-local storage, the `start` function
+    ///   also gives each thread its own block of it.
     ///
     /// More and/or less may happen here over time, stay tuned!
     pub fn run(&self, module: &mut Module) -> Result<(), Error> {
         let memory = update_memory(module, self.maximum_memory)?;
         let segments = switch_data_segments_to_passive(module, memory)?;
-        let stack_pointer = find_stack_pointer(module)?;
+        let tls = find_tls(module)?;
+        let stack_pointer = find_stack_pointer(module, tls.as_ref())?;
 
         let zero = InitExpr::Value(Value::I32(0));
         let globals = Globals {
@@ -95,6 +98,7 @@ impl Config {
             addr,
             stack_pointer,
             self.thread_stack_size,
+            tls.as_ref(),
             memory,
         );
         implement_thread_intrinsics(module, &globals)?;
@@ -220,12 +224,67 @@ fn inject_thread_id_counter(module: &mut Module, memory: MemoryId) -> Result<u32
     Ok(address)
 }
 
-fn find_stack_pointer(module: &mut Module) -> Result<Option<GlobalId>, Error> {
+/// The parts of LLVM's thread-local storage ABI, which rustc exports from
+/// modules using threads.
+struct Tls {
+    /// The `__tls_base` global pointing at the current thread's block
+    base: GlobalId,
+    /// `__wasm_init_tls`, which initializes a block and points `__tls_base`
+    /// at it
+    init: FunctionId,
+    /// The size of a block, from `__tls_size`
+    size: u32,
+}
+
+fn find_tls(module: &Module) -> Result<Option<Tls>, Error> {
+    let export = |name: &str| {
+        module
+            .exports
+            .iter()
+            .find(|e| e.name == name)
+            .map(|e| &e.item)
+    };
+    let constant = |item: Option<&ExportItem>, name: &str| {
+        let id = match item {
+            Some(ExportItem::Global(id)) => *id,
+            _ => bail!(
+                "`__wasm_init_tls` is exported but not the `{}` global",
+                name
+            ),
+        };
+        match module.globals.get(id).kind {
+            GlobalKind::Local(InitExpr::Value(Value::I32(n))) => Ok(n as u32),
+            _ => bail!("`{}` isn't a constant `i32`", name),
+        }
+    };
+
+    // Modules which don't use thread-locals don't have any of these.
+    let init = match export("__wasm_init_tls") {
+        Some(ExportItem::Function(id)) => *id,
+        Some(_) => bail!("`__wasm_init_tls` isn't a function"),
+        None => return Ok(None),
+    };
+    let base = match export("__tls_base") {
+        Some(ExportItem::Global(id)) => *id,
+        _ => bail!("`__wasm_init_tls` is exported but not the `__tls_base` global"),
+    };
+    let size = constant(export("__tls_size"), "__tls_size")?;
+    // Blocks are allocated a page at a time, so any alignment up to that of a
+    // page is satisfied.
+    if constant(export("__tls_align"), "__tls_align")? > PAGE_SIZE {
+        bail!("thread-locals aligned to more than a page aren't supported");
+    }
+    Ok(Some(Tls { base, init, size }))
+}
+
+fn find_stack_pointer(module: &mut Module, tls: Option<&Tls>) -> Result<Option<GlobalId>, Error> {
     let candidates = module
         .globals
         .iter()
         .filter(|g| g.ty == ValType::I32)
         .filter(|g| g.mutable)
+        // `__tls_base` is the only other mutable global LLVM generates.
+        .filter(|g| tls.map_or(true, |tls| g.id() != tls.base))
         .filter(|g| match g.kind {
             GlobalKind::Local(_) => true,
             GlobalKind::Import(_) => false,
@@ -252,6 +311,7 @@ fn start_with_init_memory(
     addr: u32,
     stack_pointer: Option<GlobalId>,
     stack_size: u32,
+    tls: Option<&Tls>,
     memory: MemoryId,
 ) {
     use walrus::ir::*;
@@ -346,6 +406,43 @@ fn start_with_init_memory(
     let block = builder.if_else(thread_id_is_nonzero, if_nonzero_block, if_zero_block);
     exprs.push(block);
 
+    // Every thread, including the first, needs its own block of thread-local
+    // storage, which `__wasm_init_tls` initializes from the passive `.tdata`
+    // segment. Like the stacks above these are allocated with `memory.grow`,
+    // which gives us page-aligned memory, and this happens before the
+    // original start function which may well use thread-locals.
+    if let Some(tls) = tls.filter(|tls| tls.size > 0) {
+        // local0 = grow_memory(tls_pages);
+        let pages = (tls.size + PAGE_SIZE - 1) / PAGE_SIZE;
+        let grow_amount = builder.i32_const(pages as i32);
+        let memory_growth = builder.memory_grow(memory, grow_amount);
+        exprs.push(builder.local_set(local, memory_growth));
+
+        // if local0 == -1 then trap
+        let if_negative_trap = {
+            let mut block = builder.block(Box::new([]), Box::new([]));
+
+            let lhs = block.local_get(local);
+            let rhs = block.i32_const(-1);
+            let condition = block.binop(BinaryOp::I32Ne, lhs, rhs);
+            let id = block.id();
+            let br_if = block.br_if(condition, id, Box::new([]));
+            block.expr(br_if);
+
+            let unreachable = block.unreachable();
+            block.expr(unreachable);
+
+            id
+        };
+        exprs.push(if_negative_trap.into());
+
+        // __wasm_init_tls(local0 * page_size)
+        let get_local = builder.local_get(local);
+        let page_size = builder.i32_const(PAGE_SIZE as i32);
+        let tls_base = builder.binop(BinaryOp::I32Mul, get_local, page_size);
+        exprs.push(builder.call(tls.init, Box::new([tls_base])));
+    }
+
     // On all threads now memory segments are no longer needed
     for segment in segments {
         exprs.push(builder.data_drop(segment.id));
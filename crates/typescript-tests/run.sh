@@ -0,0 +1,23 @@
+#!/bin/sh
+
+# Checks that the TypeScript in `src/*.ts` type checks against the declarations
+# generated for the Rust in `src/*.rs`, and that the glue generated with
+# `--emit-ts` type checks with `noImplicitAny`.
+
+set -ex
+
+cargo build --target wasm32-unknown-unknown
+cargo run -p wasm-bindgen-cli --bin wasm-bindgen -- \
+  ../../target/wasm32-unknown-unknown/debug/typescript_tests.wasm \
+  --out-dir pkg \
+  --typescript
+cargo run -p wasm-bindgen-cli --bin wasm-bindgen -- \
+  ../../target/wasm32-unknown-unknown/debug/typescript_tests.wasm \
+  --out-dir pkg-ts \
+  --emit-ts
+
+if [ ! -d node_modules ]; then
+  npm install
+fi
+npm run tsc
+npm run tsc-emit-ts
@@ -0,0 +1,22 @@
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct AsyncCounter {
+    count: u32,
+}
+
+#[wasm_bindgen]
+impl AsyncCounter {
+    #[wasm_bindgen(constructor)]
+    pub async fn new(start: u32) -> Result<AsyncCounter, JsValue> {
+        if start == 0 {
+            return Err(JsValue::from_str("counters can't start at zero"));
+        }
+        let count = async { start + 1 }.await;
+        Ok(AsyncCounter { count })
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
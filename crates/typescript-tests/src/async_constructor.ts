@@ -0,0 +1,6 @@
+import * as wbg from '../pkg/typescript_tests';
+
+const promise: Promise<wbg.AsyncCounter> = wbg.AsyncCounter.create(1);
+promise.then(counter => {
+  const count: number = counter.count();
+});
@@ -0,0 +1,4 @@
+//! Rust exports whose generated TypeScript declarations are checked by the
+//! `.ts` files next to them, with `run.sh`.
+
+pub mod async_constructor;
@@ -0,0 +1,12 @@
+const wasm = require('wasm-bindgen-test.js');
+const assert = require('assert');
+
+exports.create_counter = start => {
+    // `async` constructors are exported as a static `create` method, since JS
+    // constructors can't return a promise.
+    assert.strictEqual(typeof wasm.AsyncCounter.create, 'function');
+    return wasm.AsyncCounter.create(start).then(counter => {
+        assert.ok(counter instanceof wasm.AsyncCounter);
+        return counter.count();
+    });
+};
@@ -0,0 +1,26 @@
+#![cfg(target_arch = "wasm32")]
+
+use futures::Future;
+use js_sys::Promise;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen(module = "tests/async_constructor.js")]
+extern "C" {
+    fn create_counter(start: u32) -> Promise;
+}
+
+#[wasm_bindgen_test(async)]
+fn resolves_to_an_instance() -> impl Future<Item = (), Error = JsValue> {
+    JsFuture::from(create_counter(1)).map(|count| assert_eq!(count.as_f64(), Some(2.0)))
+}
+
+#[wasm_bindgen_test(async)]
+fn rejects_with_the_error() -> impl Future<Item = (), Error = JsValue> {
+    JsFuture::from(create_counter(0)).then(|result| {
+        let err = result.unwrap_err();
+        assert_eq!(err.as_string().unwrap(), "counters can't start at zero");
+        Ok(())
+    })
+}
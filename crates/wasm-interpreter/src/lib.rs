@@ -27,7 +27,7 @@ use walrus::{FunctionId, LocalFunction, LocalId, Module, TableId};
 /// An interpreter currently represents effectively cached state. It is reused
 /// between calls to `interpret` and is precomputed from a `Module`. It houses
 /// state like the wasm stack, wasm memory, etc.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Interpreter {
     // Function index of the `__wbindgen_describe` and
     // `__wbindgen_describe_closure` imported functions. We special case this
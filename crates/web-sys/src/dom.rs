@@ -0,0 +1,207 @@
+//! Building whole subtrees of the DOM at once
+//!
+//! Creating elements one by one takes a call into JS for every element,
+//! attribute and child that's added, which adds up quickly for templates with
+//! many nodes. A [`Tree`] instead describes a subtree up front and creates it
+//! all in a single call.
+//!
+//! The JS function doing so is created with the `Function` constructor the
+//! first time a tree is built on a thread, so pages with a content security
+//! policy need to allow `'unsafe-eval'` to use this module.
+//!
+//! *This module requires the following crate features to be activated: `Document`, `DocumentFragment`, `Element`*
+
+use js_sys::{Array, Function};
+use wasm_bindgen::{JsCast, JsValue};
+use {Document, DocumentFragment, Element};
+
+// Reads the operations encoded by `Tree` and performs them. Every string is
+// prefixed with its length in UTF-16 code units and a colon.
+const BUILD: &str = "
+    return function (document, ops) {
+        const fragment = document.createDocumentFragment();
+        const marked = [];
+        const stack = [fragment];
+        let i = 0;
+        const next = () => {
+            const colon = ops.indexOf(':', i);
+            const len = Number(ops.slice(i, colon));
+            i = colon + 1 + len;
+            return ops.slice(colon + 1, i);
+        };
+        while (i < ops.length) {
+            const parent = stack[stack.length - 1];
+            switch (ops[i++]) {
+                case 'E': {
+                    const el = document.createElement(next());
+                    parent.appendChild(el);
+                    stack.push(el);
+                    break;
+                }
+                case 'N': {
+                    const ns = next();
+                    const el = document.createElementNS(ns, next());
+                    parent.appendChild(el);
+                    stack.push(el);
+                    break;
+                }
+                case 'A':
+                    parent.setAttribute(next(), next());
+                    break;
+                case 'T':
+                    parent.appendChild(document.createTextNode(next()));
+                    break;
+                case 'M':
+                    marked.push(parent);
+                    break;
+                case 'C':
+                    stack.pop();
+                    break;
+            }
+        }
+        return [fragment, marked];
+    };
+";
+
+thread_local!(static BUILDER: Function = Function::new_no_args(BUILD)
+    .call0(&JsValue::UNDEFINED)
+    .expect("failed to create the DOM tree builder")
+    .unchecked_into());
+
+/// A description of a subtree of the DOM, which is created all at once by
+/// [`build`](Tree::build)
+///
+/// Elements are opened with `element`, after which attributes, text and
+/// child elements are added to them until they're closed with `end`:
+///
+/// ```no_run
+/// # use web_sys::dom::Tree;
+/// # fn example(document: &web_sys::Document) -> Result<(), wasm_bindgen::JsValue> {
+/// let mut tree = Tree::new();
+/// tree.element("ul").attribute("class", "todos");
+/// for todo in &["write docs", "ship it"] {
+///     tree.element("li").text(todo).end();
+/// }
+/// tree.element("button").mark().text("add").end();
+/// tree.end();
+///
+/// let subtree = tree.build(document)?;
+/// let button = &subtree.marked[0];
+/// # Ok(())
+/// # }
+/// ```
+///
+/// *This API requires the following crate features to be activated: `Document`, `DocumentFragment`, `Element`*
+#[derive(Clone, Debug, Default)]
+pub struct Tree {
+    ops: String,
+    depth: usize,
+}
+
+/// A subtree created from a [`Tree`]
+///
+/// *This API requires the following crate features to be activated: `DocumentFragment`, `Element`*
+#[derive(Clone, Debug)]
+pub struct Subtree {
+    /// The fragment holding the top-level nodes of the tree, which can be
+    /// inserted anywhere in the document
+    pub fragment: DocumentFragment,
+    /// The elements marked with [`mark`](Tree::mark), in the order they were
+    /// marked in
+    pub marked: Vec<Element>,
+}
+
+impl Tree {
+    /// Creates an empty tree.
+    pub fn new() -> Tree {
+        Tree::default()
+    }
+
+    /// Opens an element named `tag` in the element that's currently open, or
+    /// at the top level of the tree.
+    pub fn element(&mut self, tag: &str) -> &mut Self {
+        self.op('E').string(tag);
+        self.depth += 1;
+        self
+    }
+
+    /// Opens an element named `tag` in the namespace `namespace`, like an
+    /// element of an SVG image.
+    pub fn element_ns(&mut self, namespace: &str, tag: &str) -> &mut Self {
+        self.op('N').string(namespace).string(tag);
+        self.depth += 1;
+        self
+    }
+
+    /// Sets an attribute of the element that's currently open.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no element is open.
+    pub fn attribute(&mut self, name: &str, value: &str) -> &mut Self {
+        assert!(self.depth > 0, "attributes can only be set on elements");
+        self.op('A').string(name).string(value)
+    }
+
+    /// Adds a text node to the element that's currently open, or at the top
+    /// level of the tree.
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.op('T').string(text)
+    }
+
+    /// Marks the element that's currently open to be returned in
+    /// [`Subtree::marked`], for example to add event listeners to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no element is open.
+    pub fn mark(&mut self) -> &mut Self {
+        assert!(self.depth > 0, "only elements can be marked");
+        self.op('M')
+    }
+
+    /// Closes the element that's currently open.
+    ///
+    /// Elements which are still open once the tree is built are closed
+    /// implicitly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no element is open.
+    pub fn end(&mut self) -> &mut Self {
+        assert!(self.depth > 0, "no element is open");
+        self.depth -= 1;
+        self.op('C')
+    }
+
+    /// Creates the nodes of this tree in `document`, with a single call into
+    /// JS.
+    ///
+    /// Fails with the exception thrown while creating them, like when a tag
+    /// or attribute name isn't valid.
+    pub fn build(&self, document: &Document) -> Result<Subtree, JsValue> {
+        let ops = JsValue::from_str(&self.ops);
+        let ret = BUILDER.with(|f| f.call2(&JsValue::UNDEFINED, document.as_ref(), &ops))?;
+        let ret = Array::from(&ret);
+        let marked = Array::from(&ret.get(1));
+        Ok(Subtree {
+            fragment: ret.get(0).unchecked_into(),
+            marked: (0..marked.length())
+                .map(|i| marked.get(i).unchecked_into())
+                .collect(),
+        })
+    }
+
+    fn op(&mut self, op: char) -> &mut Self {
+        self.ops.push(op);
+        self
+    }
+
+    fn string(&mut self, s: &str) -> &mut Self {
+        let len = s.encode_utf16().count();
+        self.ops.push_str(&len.to_string());
+        self.ops.push(':');
+        self.ops.push_str(s);
+        self
+    }
+}
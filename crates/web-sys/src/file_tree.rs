@@ -0,0 +1,207 @@
+//! Reading the files of directory trees dropped onto the page
+//!
+//! Dropped directories can only be read through callbacks, one batch of
+//! entries at a time, so going through all of their files otherwise takes
+//! quite a bit of glue. A [`FileTree`] instead walks a tree in JS and hands
+//! out its files one at a time, as a stream of promises.
+//!
+//! Like the `dom` module, the JS doing so is created with the `Function`
+//! constructor the first time a tree is walked on a thread, so pages with a
+//! content security policy need to allow `'unsafe-eval'` to use this module.
+//!
+//! *This module requires the following crate features to be activated: `DataTransfer`, `File`, `FileSystemEntry`*
+
+use js_sys::{Array, Function, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use {DataTransfer, File, FileSystemEntry};
+
+// Walks trees of `FileSystemEntry` and `FileSystemHandle` objects depth-first,
+// returning a function resolving to `[path, file]` for each of their files and
+// to `undefined` once they're done.
+const WALK: &str = "
+    const file = entry => new Promise((resolve, reject) => entry.file(resolve, reject));
+    const readEntries = reader => new Promise((resolve, reject) => {
+        reader.readEntries(resolve, reject);
+    });
+
+    async function* walkEntry(entry) {
+        if (entry.isFile) {
+            yield [entry.fullPath, await file(entry)];
+        } else if (entry.isDirectory) {
+            // Directories are read in batches, until an empty one.
+            const reader = entry.createReader();
+            for (;;) {
+                const batch = await readEntries(reader);
+                if (batch.length === 0) {
+                    break;
+                }
+                for (const child of batch) {
+                    yield* walkEntry(child);
+                }
+            }
+        }
+    }
+
+    async function* walkEntries(entries) {
+        for (const entry of entries) {
+            yield* walkEntry(entry);
+        }
+    }
+
+    async function* walkHandle(handle, path) {
+        path = path + '/' + handle.name;
+        if (handle.kind === 'file') {
+            yield [path, await handle.getFile()];
+        } else {
+            for await (const child of handle.values()) {
+                yield* walkHandle(child, path);
+            }
+        }
+    }
+
+    const stream = walk => () => walk.next().then(r => r.done ? undefined : r.value);
+
+    return {
+        dataTransfer: data => {
+            // Entries have to be taken out of the data transfer while the
+            // `drop` event is dispatched, after which its items are gone.
+            const entries = [];
+            for (const item of Array.from(data.items)) {
+                const entry = item.kind === 'file' && item.webkitGetAsEntry();
+                if (entry) {
+                    entries.push(entry);
+                }
+            }
+            return stream(walkEntries(entries));
+        },
+        entries: entries => stream(walkEntries(entries)),
+        handle: handle => stream(walkHandle(handle, '')),
+    };
+";
+
+thread_local!(static WALKER: Object = Function::new_no_args(WALK)
+    .call0(&JsValue::UNDEFINED)
+    .expect("failed to create the file tree walker")
+    .unchecked_into());
+
+/// A stream of the files in one or more directory trees, like those dropped
+/// onto the page
+///
+/// Files are found depth-first and read as they're asked for by
+/// [`next`](FileTree::next), so large trees don't have to be read all at
+/// once:
+///
+/// ```no_run
+/// # extern crate futures;
+/// # extern crate wasm_bindgen_futures;
+/// # use futures::future::{self, Future, Loop};
+/// # use wasm_bindgen_futures::JsFuture;
+/// # use web_sys::file_tree::{FileTree, TreeFile};
+/// # fn example(event: &web_sys::DragEvent) {
+/// let tree = FileTree::from_data_transfer(&event.data_transfer().unwrap());
+/// let done = future::loop_fn((), move |()| {
+///     JsFuture::from(tree.next()).map(|file| match TreeFile::from_js(&file) {
+///         Some(file) => {
+///             web_sys::console::log_2(&file.path.into(), &file.file.size().into());
+///             Loop::Continue(())
+///         }
+///         None => Loop::Break(()),
+///     })
+/// });
+/// # }
+/// ```
+///
+/// *This API requires the following crate features to be activated: `DataTransfer`, `File`, `FileSystemEntry`*
+#[derive(Clone, Debug)]
+pub struct FileTree {
+    next: Function,
+}
+
+/// A file found in a [`FileTree`]
+///
+/// *This API requires the following crate features to be activated: `File`*
+#[derive(Clone, Debug)]
+pub struct TreeFile {
+    /// The path of the file from the top of its tree, starting with a `/`
+    /// followed by the name of the dropped file or directory
+    pub path: String,
+    /// The contents of the file
+    pub file: File,
+}
+
+impl FileTree {
+    /// Walks the files and directories dropped in a `drop` event, skipping
+    /// anything that isn't a file, like dropped text.
+    ///
+    /// This has to be called while the `drop` event is dispatched, since the
+    /// items of its data transfer aren't available afterwards. The files
+    /// themselves can be read at any time.
+    pub fn from_data_transfer(data: &DataTransfer) -> FileTree {
+        FileTree::walk("dataTransfer", data.as_ref())
+    }
+
+    /// Walks the trees below `entries`, like those returned by
+    /// `DataTransferItem::webkit_get_as_entry`.
+    pub fn from_entries(entries: &[FileSystemEntry]) -> FileTree {
+        let array = Array::new();
+        for entry in entries {
+            array.push(entry.as_ref());
+        }
+        FileTree::walk("entries", array.as_ref())
+    }
+
+    /// Walks the tree below `handle`, like a directory returned by
+    /// `DataTransferItem::get_as_file_system_handle`.
+    ///
+    /// Paths of the files found start with the name of `handle`, the same way
+    /// as they do for entries.
+    ///
+    /// *This API requires the following crate features to be activated: `FileSystemDirectoryHandle`*
+    #[cfg(feature = "FileSystemDirectoryHandle")]
+    pub fn from_directory_handle(handle: &::FileSystemDirectoryHandle) -> FileTree {
+        FileTree::walk("handle", handle.as_ref())
+    }
+
+    /// Returns a promise resolving to the next file of the tree, which can be
+    /// turned into a [`TreeFile`] with [`TreeFile::from_js`], or to
+    /// `undefined` if there are no more files.
+    ///
+    /// The promise is rejected with the error of reading the tree, like a
+    /// directory that was removed after being dropped.
+    pub fn next(&self) -> js_sys::Promise {
+        self.next
+            .call0(&JsValue::UNDEFINED)
+            .expect("failed to read the next file of a tree")
+            .unchecked_into()
+    }
+
+    fn walk(kind: &str, roots: &JsValue) -> FileTree {
+        let next = WALKER.with(|walker| {
+            let start = Reflect::get(walker.as_ref(), &JsValue::from_str(kind))
+                .expect("file tree walker is missing a method");
+            start
+                .unchecked_ref::<Function>()
+                .call1(&JsValue::UNDEFINED, roots)
+        });
+        FileTree {
+            next: next
+                .expect("failed to start walking a file tree")
+                .unchecked_into(),
+        }
+    }
+}
+
+impl TreeFile {
+    /// Converts what a promise returned by [`FileTree::next`] resolved to,
+    /// returning `None` once there are no more files.
+    pub fn from_js(value: &JsValue) -> Option<TreeFile> {
+        if value.is_undefined() {
+            return None;
+        }
+        let value = Array::from(value);
+        Some(TreeFile {
+            path: value.get(0).as_string().unwrap_or_default(),
+            file: value.get(1).unchecked_into(),
+        })
+    }
+}
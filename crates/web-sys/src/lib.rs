@@ -20,8 +20,25 @@ extern crate wasm_bindgen;
 #[allow(unused_imports)]
 use js_sys::Object;
 
+#[cfg(all(
+    feature = "Document",
+    feature = "DocumentFragment",
+    feature = "Element"
+))]
+pub mod dom;
+
+#[cfg(all(
+    feature = "DataTransfer",
+    feature = "File",
+    feature = "FileSystemEntry"
+))]
+pub mod file_tree;
+
 /// Getter for the `Window` object
 ///
+/// Returns `None` if the global object isn't a `Window`, for example in a web
+/// worker or in Node.js. The result is looked up once per thread and cached.
+///
 /// [MDN Documentation]
 ///
 /// *This API requires the following crate features to be activated: `Window`*
@@ -29,9 +46,226 @@ use js_sys::Object;
 /// [MDN Documentation]: https://developer.mozilla.org/en-US/docs/Web/API/Window
 #[cfg(feature = "Window")]
 pub fn window() -> Option<Window> {
+    thread_local!(static WINDOW: Option<Window> = global_instance_of("Window"));
+    WINDOW.with(|w| w.clone())
+}
+
+/// Getter for the `Document` of the current `Window`
+///
+/// Returns `None` if there's no `Window` or it has no document. The result is
+/// looked up once per thread and cached.
+///
+/// [MDN Documentation]
+///
+/// *This API requires the following crate features to be activated: `Document`, `Window`*
+///
+/// [MDN Documentation]: https://developer.mozilla.org/en-US/docs/Web/API/Window/document
+#[cfg(all(feature = "Document", feature = "Window"))]
+pub fn document() -> Option<Document> {
+    thread_local!(static DOCUMENT: Option<Document> = window().and_then(|w| w.document()));
+    DOCUMENT.with(|d| d.clone())
+}
+
+/// Getter for the `WorkerGlobalScope` object
+///
+/// Returns `None` if the code isn't running in a worker of some kind. The
+/// result is looked up once per thread and cached, and can be downcast with
+/// `dyn_into` to the more specific scope of dedicated, shared or service
+/// workers.
+///
+/// [MDN Documentation]
+///
+/// *This API requires the following crate features to be activated: `WorkerGlobalScope`*
+///
+/// [MDN Documentation]: https://developer.mozilla.org/en-US/docs/Web/API/WorkerGlobalScope
+#[cfg(feature = "WorkerGlobalScope")]
+pub fn worker_scope() -> Option<WorkerGlobalScope> {
+    thread_local!(static SCOPE: Option<WorkerGlobalScope> =
+        global_instance_of("WorkerGlobalScope"));
+    SCOPE.with(|s| s.clone())
+}
+
+/// Returns the global object as a `T` if it's an instance of the global class
+/// named `class`.
+///
+/// The class is checked for first since an `instanceof` check against a class
+/// which doesn't exist in this environment would throw.
+#[allow(dead_code)]
+fn global_instance_of<T: wasm_bindgen::JsCast>(class: &str) -> Option<T> {
     use wasm_bindgen::JsCast;
 
-    js_sys::global().dyn_into::<Window>().ok()
+    let global = wasm_bindgen::global();
+    match js_sys::Reflect::has(&global, &wasm_bindgen::JsValue::from_str(class)) {
+        Ok(true) => global.dyn_into().ok(),
+        _ => None,
+    }
+}
+
+/// Returns the property `name` of a dictionary object handed to us by the
+/// browser, or `undefined` if there's no such property.
+#[allow(dead_code)]
+fn dictionary_field(obj: &wasm_bindgen::JsValue, name: &str) -> wasm_bindgen::JsValue {
+    js_sys::Reflect::get(obj, &wasm_bindgen::JsValue::from_str(name))
+        .unwrap_or(wasm_bindgen::JsValue::UNDEFINED)
+}
+
+#[cfg(feature = "History")]
+impl History {
+    /// Getter for the state of the current history entry as a `T`
+    ///
+    /// Returns `None` if the entry has no state, or if its state isn't an
+    /// instance of `T`, as is the case when it was pushed by some other code
+    /// sharing the page.
+    ///
+    /// [MDN Documentation]
+    ///
+    /// *This API requires the following crate features to be activated: `History`*
+    ///
+    /// [MDN Documentation]: https://developer.mozilla.org/en-US/docs/Web/API/History/state
+    pub fn state_as<T: wasm_bindgen::JsCast>(&self) -> Result<Option<T>, wasm_bindgen::JsValue> {
+        use wasm_bindgen::JsCast;
+
+        let state = self.state()?;
+        if state.is_null() || state.is_undefined() {
+            return Ok(None);
+        }
+        Ok(state.dyn_into().ok())
+    }
+}
+
+#[cfg(all(feature = "Range", feature = "Selection"))]
+impl Selection {
+    /// Returns all ranges of the selection, in the order of `getRangeAt`
+    ///
+    /// Browsers other than Firefox only ever have at most one range.
+    ///
+    /// *This API requires the following crate features to be activated: `Range`, `Selection`*
+    pub fn ranges(&self) -> Vec<Range> {
+        (0..self.range_count())
+            .filter_map(|i| self.get_range_at(i).ok())
+            .collect()
+    }
+}
+
+#[cfg(all(feature = "InputEvent", feature = "StaticRange"))]
+impl InputEvent {
+    /// Returns the ranges a `beforeinput` event is going to change
+    ///
+    /// These are empty for `input` events, once the change has happened.
+    ///
+    /// [MDN Documentation]
+    ///
+    /// *This API requires the following crate features to be activated: `InputEvent`, `StaticRange`*
+    ///
+    /// [MDN Documentation]: https://developer.mozilla.org/en-US/docs/Web/API/InputEvent/getTargetRanges
+    pub fn target_ranges(&self) -> Vec<StaticRange> {
+        use wasm_bindgen::JsCast;
+
+        let ranges = self.get_target_ranges();
+        (0..ranges.length())
+            .map(|i| ranges.get(i).unchecked_into())
+            .collect()
+    }
+}
+
+// Dictionaries are only generated with setters, so the results of
+// `UrlPattern::exec` get their getters here.
+
+#[cfg(feature = "UrlPatternResult")]
+impl UrlPatternResult {
+    /// Getter for the inputs the pattern was matched against, either strings
+    /// or `UrlPatternInit` dictionaries
+    ///
+    /// [MDN Documentation]
+    ///
+    /// *This API requires the following crate features to be activated: `UrlPatternResult`*
+    ///
+    /// [MDN Documentation]: https://developer.mozilla.org/en-US/docs/Web/API/URLPattern/exec
+    pub fn get_inputs(&self) -> js_sys::Array {
+        use wasm_bindgen::JsCast;
+
+        dictionary_field(self.as_ref(), "inputs").unchecked_into()
+    }
+}
+
+macro_rules! url_pattern_components {
+    ($($name:ident => $js:tt, $doc:tt,)*) => {
+        #[cfg(all(feature = "UrlPatternResult", feature = "UrlPatternComponentResult"))]
+        impl UrlPatternResult {
+            $(
+                #[doc = $doc]
+                ///
+                /// [MDN Documentation]
+                ///
+                /// *This API requires the following crate features to be activated: `UrlPatternComponentResult`, `UrlPatternResult`*
+                ///
+                /// [MDN Documentation]: https://developer.mozilla.org/en-US/docs/Web/API/URLPattern/exec
+                pub fn $name(&self) -> UrlPatternComponentResult {
+                    use wasm_bindgen::JsCast;
+
+                    dictionary_field(self.as_ref(), $js).unchecked_into()
+                }
+            )*
+        }
+    };
+}
+
+url_pattern_components! {
+    get_protocol => "protocol", "Getter for the match of the `protocol` component",
+    get_username => "username", "Getter for the match of the `username` component",
+    get_password => "password", "Getter for the match of the `password` component",
+    get_hostname => "hostname", "Getter for the match of the `hostname` component",
+    get_port => "port", "Getter for the match of the `port` component",
+    get_pathname => "pathname", "Getter for the match of the `pathname` component",
+    get_search => "search", "Getter for the match of the `search` component",
+    get_hash => "hash", "Getter for the match of the `hash` component",
+}
+
+#[cfg(feature = "UrlPatternComponentResult")]
+impl UrlPatternComponentResult {
+    /// Getter for the part of the input this component was matched against
+    ///
+    /// *This API requires the following crate features to be activated: `UrlPatternComponentResult`*
+    pub fn get_input(&self) -> String {
+        dictionary_field(self.as_ref(), "input")
+            .as_string()
+            .unwrap_or_default()
+    }
+
+    /// Returns what the group `name` matched, or `None` if there's no such
+    /// group or it didn't take part in the match
+    ///
+    /// Unnamed groups, like those of `*` wildcards, are named by their index
+    /// among the unnamed groups of the component, starting at `"0"`.
+    ///
+    /// *This API requires the following crate features to be activated: `UrlPatternComponentResult`*
+    pub fn group(&self, name: &str) -> Option<String> {
+        dictionary_field(&dictionary_field(self.as_ref(), "groups"), name).as_string()
+    }
+
+    /// Returns all groups of this component along with what they matched
+    ///
+    /// Groups are in the order of `Object.entries`, so the unnamed groups come
+    /// first.
+    ///
+    /// *This API requires the following crate features to be activated: `UrlPatternComponentResult`*
+    pub fn groups(&self) -> Vec<(String, Option<String>)> {
+        use wasm_bindgen::JsCast;
+
+        let groups = dictionary_field(self.as_ref(), "groups");
+        let groups = match groups.dyn_ref::<js_sys::Object>() {
+            Some(groups) => groups,
+            None => return Vec::new(),
+        };
+        let entries = js_sys::Object::entries(groups);
+        (0..entries.length())
+            .map(|i| {
+                let entry = js_sys::Array::from(&entries.get(i));
+                let name = entry.get(0).as_string().unwrap_or_default();
+                (name, entry.get(1).as_string())
+            })
+            .collect()
+    }
 }
 
 include!(env!("BINDINGS"));
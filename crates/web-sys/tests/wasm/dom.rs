@@ -0,0 +1,58 @@
+use wasm_bindgen_test::*;
+use web_sys::dom::Tree;
+
+#[wasm_bindgen_test]
+fn build_tree() {
+    let document = web_sys::document().unwrap();
+    let mut tree = Tree::new();
+    tree.element("ul").attribute("class", "todos");
+    for todo in &["write docs", "ship it 🚀"] {
+        tree.element("li").text(todo).end();
+    }
+    tree.end();
+    tree.element("button")
+        .attribute("data-label", "a:b")
+        .mark()
+        .text("add")
+        .end();
+    tree.text("trailing");
+
+    let subtree = tree.build(&document).unwrap();
+    let container = document.create_element("div").unwrap();
+    container.append_child(&subtree.fragment).unwrap();
+    assert_eq!(
+        container.inner_html(),
+        "<ul class=\"todos\"><li>write docs</li><li>ship it 🚀</li></ul>\
+         <button data-label=\"a:b\">add</button>trailing"
+    );
+
+    assert_eq!(subtree.marked.len(), 1);
+    assert_eq!(subtree.marked[0].tag_name(), "BUTTON");
+    assert_eq!(subtree.marked[0].text_content().unwrap(), "add");
+}
+
+#[wasm_bindgen_test]
+fn build_svg() {
+    let document = web_sys::document().unwrap();
+    let mut tree = Tree::new();
+    tree.element_ns("http://www.w3.org/2000/svg", "svg")
+        .element_ns("http://www.w3.org/2000/svg", "circle")
+        .attribute("r", "5")
+        .mark();
+
+    let subtree = tree.build(&document).unwrap();
+    let circle = &subtree.marked[0];
+    assert_eq!(
+        circle.namespace_uri().unwrap(),
+        "http://www.w3.org/2000/svg"
+    );
+    assert_eq!(circle.get_attribute("r").unwrap(), "5");
+}
+
+#[wasm_bindgen_test]
+fn invalid_tag() {
+    let document = web_sys::document().unwrap();
+    let mut tree = Tree::new();
+    tree.element("not a tag");
+    assert!(tree.build(&document).is_err());
+}
@@ -0,0 +1,53 @@
+const fileEntry = (fullPath, contents) => ({
+  isFile: true,
+  isDirectory: false,
+  fullPath,
+  file(resolve) {
+    setTimeout(() => resolve(new File([contents], fullPath.split('/').pop())));
+  },
+});
+
+// Hands out the children of a directory in batches of two, the way browsers
+// do with batches of a hundred.
+const directoryEntry = (fullPath, children) => ({
+  isFile: false,
+  isDirectory: true,
+  fullPath,
+  createReader() {
+    let i = 0;
+    return {
+      readEntries(resolve) {
+        const batch = children.slice(i, i + 2);
+        i += batch.length;
+        setTimeout(() => resolve(batch));
+      },
+    };
+  },
+});
+
+export function dropped_entries() {
+  return [
+    directoryEntry('/photos', [
+      fileEntry('/photos/a.jpg', 'a'),
+      directoryEntry('/photos/empty', []),
+      directoryEntry('/photos/2019', [fileEntry('/photos/2019/b.jpg', 'bb')]),
+      fileEntry('/photos/c.jpg', 'ccc'),
+    ]),
+    fileEntry('/notes.txt', 'notes'),
+  ];
+}
+
+export function failing_entries() {
+  return [{
+    isFile: false,
+    isDirectory: true,
+    fullPath: '/gone',
+    createReader() {
+      return {
+        readEntries(_resolve, reject) {
+          reject(new Error('directory is gone'));
+        },
+      };
+    },
+  }];
+}
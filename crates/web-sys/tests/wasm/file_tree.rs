@@ -0,0 +1,58 @@
+use futures::future::{self, Future, Loop};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+use web_sys::file_tree::{FileTree, TreeFile};
+use web_sys::FileSystemEntry;
+
+#[wasm_bindgen(module = "./tests/wasm/file_tree.js")]
+extern "C" {
+    fn dropped_entries() -> Vec<FileSystemEntry>;
+    fn failing_entries() -> Vec<FileSystemEntry>;
+}
+
+fn read_all(tree: FileTree) -> impl Future<Item = Vec<TreeFile>, Error = JsValue> {
+    future::loop_fn(Vec::new(), move |mut files| {
+        JsFuture::from(tree.next()).map(move |file| match TreeFile::from_js(&file) {
+            Some(file) => {
+                files.push(file);
+                Loop::Continue(files)
+            }
+            None => Loop::Break(files),
+        })
+    })
+}
+
+#[wasm_bindgen_test(async)]
+fn walks_dropped_trees() -> impl Future<Item = (), Error = JsValue> {
+    read_all(FileTree::from_entries(&dropped_entries())).map(|files| {
+        let files = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.file.name(), f.file.size()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            [
+                ("/photos/a.jpg", "a.jpg".to_string(), 1.0),
+                ("/photos/2019/b.jpg", "b.jpg".to_string(), 2.0),
+                ("/photos/c.jpg", "c.jpg".to_string(), 3.0),
+                ("/notes.txt", "notes.txt".to_string(), 5.0),
+            ]
+        );
+    })
+}
+
+#[wasm_bindgen_test(async)]
+fn empty_tree() -> impl Future<Item = (), Error = JsValue> {
+    read_all(FileTree::from_entries(&[])).map(|files| assert!(files.is_empty()))
+}
+
+#[wasm_bindgen_test(async)]
+fn read_errors_reject() -> impl Future<Item = (), Error = JsValue> {
+    read_all(FileTree::from_entries(&failing_entries())).then(|result| {
+        let err = result.err().expect("reading the tree should fail");
+        let err: js_sys::Error = err.into();
+        assert_eq!(String::from(err.message()), "directory is gone");
+        Ok(())
+    })
+}
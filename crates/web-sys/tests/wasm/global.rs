@@ -0,0 +1,14 @@
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test]
+fn accessors() {
+    let window = web_sys::window().expect("should have a window");
+    assert_eq!(JsValue::from(window.clone()), wasm_bindgen::global());
+
+    let document = web_sys::document().expect("should have a document");
+    let expected = window.document().unwrap();
+    assert_eq!(JsValue::from(document), JsValue::from(expected));
+
+    assert!(web_sys::worker_scope().is_none());
+}
@@ -26,3 +26,25 @@ fn history() {
         ScrollRestoration::Auto
     );
 }
+
+#[wasm_bindgen_test]
+fn state_as() {
+    let state = js_sys::Object::new();
+    js_sys::Reflect::set(&state, &"page".into(), &2.into()).unwrap();
+    HISTORY
+        .replace_state(&state, "")
+        .expect("failure to replace state");
+
+    // The state is a structured clone of what was pushed.
+    let current = HISTORY.state_as::<js_sys::Object>().unwrap().unwrap();
+    assert_eq!(
+        js_sys::Reflect::get(&current, &"page".into()).unwrap(),
+        JsValue::from(2)
+    );
+    assert!(HISTORY.state_as::<js_sys::Array>().unwrap().is_none());
+
+    HISTORY
+        .replace_state(&JsValue::NULL, "")
+        .expect("failure to replace state");
+    assert!(HISTORY.state_as::<js_sys::Object>().unwrap().is_none());
+}
@@ -19,6 +19,8 @@ pub mod console;
 pub mod div_element;
 pub mod element;
 pub mod event;
+pub mod file_tree;
+pub mod global;
 pub mod head_element;
 pub mod headers;
 pub mod heading_element;
@@ -30,6 +32,7 @@ pub mod input_element;
 //TODO: Both menu-related tests completely break in Chrome, but run fine in Firefox.
 //pub mod menu_element;
 //pub mod menu_item_element;
+pub mod dom;
 pub mod dom_point;
 pub mod indexeddb;
 pub mod location;
@@ -50,11 +53,13 @@ pub mod quote_element;
 pub mod response;
 pub mod script_element;
 pub mod select_element;
+pub mod selection;
 pub mod slot_element;
 pub mod span_element;
 pub mod style_element;
 pub mod table_element;
 pub mod title_element;
+pub mod url_pattern;
 pub mod whitelisted_immutable_slices;
 pub mod xpath_result;
 
@@ -1,6 +1,8 @@
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 use wasm_bindgen_test::*;
-use web_sys::Performance;
+use web_sys::{Performance, PerformanceEntry, PerformanceMark, PerformanceObserver};
+use web_sys::{PerformanceObserverEntryList, PerformanceObserverInit};
 
 #[wasm_bindgen]
 extern "C" {
@@ -13,3 +15,33 @@ fn to_json() {
     let perf = JsValue::from(PERFORMANCE.to_json());
     assert!(perf.is_object());
 }
+
+#[wasm_bindgen_test]
+fn supported_entry_types() {
+    let types = PerformanceObserver::supported_entry_types();
+    assert!(types.includes(&JsValue::from("mark"), 0));
+}
+
+#[wasm_bindgen_test]
+fn observe_buffered_marks() {
+    PERFORMANCE.mark("wasm-bindgen-observed").unwrap();
+
+    let callback =
+        Closure::wrap(Box::new(|_: PerformanceObserverEntryList| {})
+            as Box<FnMut(PerformanceObserverEntryList)>);
+    let observer = PerformanceObserver::new(callback.as_ref().unchecked_ref()).unwrap();
+    let mut init = PerformanceObserverInit::new();
+    init.type_("mark").buffered(true);
+    observer.observe(&init);
+
+    // Buffered entries are queued for the observer right away.
+    let records = observer.take_records();
+    let marks = (0..records.length())
+        .map(|i| records.get(i).unchecked_into::<PerformanceEntry>())
+        .filter(|entry| entry.name() == "wasm-bindgen-observed")
+        .collect::<Vec<_>>();
+    assert_eq!(marks.len(), 1);
+    assert!(marks[0].is_instance_of::<PerformanceMark>());
+    assert_eq!(marks[0].entry_type(), "mark");
+    observer.disconnect();
+}
@@ -0,0 +1,64 @@
+use wasm_bindgen_test::*;
+use web_sys::{InputEvent, InputEventInit, Range, StaticRange, StaticRangeInit};
+
+#[wasm_bindgen_test]
+fn static_range() {
+    let document = web_sys::document().unwrap();
+    let text = document.create_text_node("hello");
+    let range = StaticRange::new(&StaticRangeInit::new(&text, 4, &text, 1)).unwrap();
+    assert_eq!(range.start_offset(), 1);
+    assert_eq!(range.end_offset(), 4);
+    assert!(range.start_container().is_same_node(Some(text.as_ref())));
+    assert!(!range.collapsed());
+}
+
+#[wasm_bindgen_test]
+fn before_input_target_ranges() {
+    let document = web_sys::document().unwrap();
+    let text = document.create_text_node("hello");
+    let range = StaticRange::new(&StaticRangeInit::new(&text, 5, &text, 5)).unwrap();
+
+    let mut init = InputEventInit::new();
+    init.input_type("insertText")
+        .data(Some("!"))
+        .target_ranges(&js_sys::Array::of1(&range));
+    let event = InputEvent::new_with_event_init_dict("beforeinput", &init).unwrap();
+    assert_eq!(event.input_type(), "insertText");
+    assert_eq!(event.data(), Some("!".to_string()));
+    assert!(event.data_transfer().is_none());
+
+    let ranges = event.target_ranges();
+    assert_eq!(ranges.len(), 1);
+    assert_eq!(ranges[0].start_offset(), 5);
+    assert!(ranges[0].collapsed());
+
+    let event = InputEvent::new("input").unwrap();
+    assert_eq!(event.input_type(), "");
+    assert!(event.target_ranges().is_empty());
+}
+
+#[wasm_bindgen_test]
+fn selection_ranges() {
+    let document = web_sys::document().unwrap();
+    let div = document.create_element("div").unwrap();
+    div.set_text_content(Some("some text"));
+    document.body().unwrap().append_child(&div).unwrap();
+
+    let selection = document.get_selection().unwrap().unwrap();
+    selection.remove_all_ranges().unwrap();
+    assert!(selection.ranges().is_empty());
+
+    let range = Range::new().unwrap();
+    range.select_node_contents(&div).unwrap();
+    selection.add_range(&range).unwrap();
+    let ranges = selection.ranges();
+    assert_eq!(ranges.len(), 1);
+    assert!(ranges[0]
+        .start_container()
+        .unwrap()
+        .is_same_node(Some(div.as_ref())));
+    assert_eq!(ranges[0].end_offset().unwrap(), 1);
+
+    selection.remove_all_ranges().unwrap();
+    div.remove();
+}
@@ -0,0 +1,55 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+use web_sys::{UrlPattern, UrlPatternInit};
+
+// Not every browser we test in ships `URLPattern` yet.
+fn supported() -> bool {
+    js_sys::Reflect::has(&wasm_bindgen::global(), &JsValue::from("URLPattern")).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn test() {
+    if !supported() {
+        return;
+    }
+    let pattern = UrlPattern::new_with_str("https://example.com/books/:id").unwrap();
+    assert_eq!(pattern.pathname(), "/books/:id");
+    assert!(pattern
+        .test_with_str("https://example.com/books/123")
+        .unwrap());
+    assert!(!pattern
+        .test_with_str("https://example.com/authors/123")
+        .unwrap());
+}
+
+#[wasm_bindgen_test]
+fn exec_groups() {
+    if !supported() {
+        return;
+    }
+    let mut init = UrlPatternInit::new();
+    init.pathname("/books/:id/*");
+    let pattern = UrlPattern::new_with_url_pattern_init(&init).unwrap();
+
+    let result = pattern
+        .exec_with_str_and_base_url("/books/123/chapters", "https://example.com")
+        .unwrap()
+        .expect("pattern should match");
+    let pathname = result.get_pathname();
+    assert_eq!(pathname.get_input(), "/books/123/chapters");
+    assert_eq!(pathname.group("id"), Some("123".to_string()));
+    assert_eq!(pathname.group("missing"), None);
+    assert_eq!(
+        pathname.groups(),
+        vec![
+            ("0".to_string(), Some("chapters".to_string())),
+            ("id".to_string(), Some("123".to_string())),
+        ]
+    );
+    assert_eq!(result.get_inputs().length(), 2);
+
+    assert!(pattern
+        .exec_with_str("https://example.com/")
+        .unwrap()
+        .is_none());
+}
@@ -288,7 +288,10 @@ impl<'src> FirstPassRecord<'src> {
                     })
                     .collect(),
                 variant_values: variants.iter().map(|v| v.0.to_string()).collect(),
+                numeric: false,
+                other: None,
                 rust_attrs: vec![parse_quote!(#[derive(Copy, Clone, PartialEq, Debug)])],
+                comments: Vec::new(),
             }),
         });
     }
@@ -515,8 +518,10 @@ impl<'src> FirstPassRecord<'src> {
             attrs,
             doc_comment: None,
             instanceof_shim: format!("__widl_instanceof_{}", name),
+            duck_shim: format!("__widl_duck_{}", name),
             extends: Vec::new(),
             vendor_prefixes: Vec::new(),
+            interface: false,
         };
 
         // whitelist a few names that have known polyfills
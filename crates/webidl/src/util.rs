@@ -314,6 +314,11 @@ impl<'src> FirstPassRecord<'src> {
             variadic,
             catch,
             structural,
+            bind_this: false,
+            main_thread: false,
+            static_getter: false,
+            dynamic_import: false,
+            cached: false,
             shim: {
                 let ns = match kind {
                     backend::ast::ImportFunctionKind::Normal => "",
@@ -0,0 +1,80 @@
+/// Exports a `buildInfo` function describing the build of this crate.
+///
+/// Invoke this macro once, in the crate which is compiled to wasm, to export a
+/// `buildInfo()` function to JS which returns a new plain object, which
+/// doesn't need to be freed, with these properties:
+///
+/// * `name` - the name of the crate, as in `Cargo.toml`.
+/// * `version` - the version of the crate, as in `Cargo.toml`.
+/// * `gitHash` - the git hash of the build, or `undefined` if it's unknown.
+/// * `features` - which of the listed Cargo features are enabled.
+/// * `wasmBindgenVersion` - the version of `wasm-bindgen` used.
+///
+/// By default the git hash is read from the `GIT_HASH` environment variable at
+/// compile time, but any expression which evaluates to an `Option<&'static
+/// str>` or `&'static str` may be used instead. Macros can't see which Cargo
+/// features are enabled on their own, so features have to be listed to be
+/// reported.
+///
+/// ```ignore
+/// wasm_bindgen::build_info!(
+///     git_hash = option_env!("CI_COMMIT_SHA"),
+///     features = ["serde", "webgl"],
+/// );
+/// ```
+#[macro_export]
+macro_rules! build_info {
+    () => {
+        $crate::build_info!(git_hash = option_env!("GIT_HASH"), features = []);
+    };
+    (features = [$($feature:tt),* $(,)*] $(,)*) => {
+        $crate::build_info!(git_hash = option_env!("GIT_HASH"), features = [$($feature),*]);
+    };
+    (git_hash = $git_hash:expr, features = [$($feature:tt),* $(,)*] $(,)*) => {
+        #[$crate::prelude::wasm_bindgen]
+        extern "C" {
+            #[wasm_bindgen(js_name = Object)]
+            #[doc(hidden)]
+            pub type __WasmBindgenBuildInfo;
+
+            #[wasm_bindgen(constructor, js_class = "Object")]
+            fn new() -> __WasmBindgenBuildInfo;
+
+            #[wasm_bindgen(method, structural, setter = name)]
+            fn set_name(this: &__WasmBindgenBuildInfo, name: &str);
+            #[wasm_bindgen(method, structural, setter = version)]
+            fn set_version(this: &__WasmBindgenBuildInfo, version: &str);
+            #[wasm_bindgen(method, structural, setter = gitHash)]
+            fn set_git_hash(this: &__WasmBindgenBuildInfo, git_hash: &$crate::JsValue);
+            #[wasm_bindgen(method, structural, setter = features)]
+            fn set_features(this: &__WasmBindgenBuildInfo, features: Vec<$crate::JsValue>);
+            #[wasm_bindgen(method, structural, setter = wasmBindgenVersion)]
+            fn set_wasm_bindgen_version(this: &__WasmBindgenBuildInfo, version: &str);
+        }
+
+        #[$crate::prelude::wasm_bindgen(js_name = buildInfo)]
+        #[doc(hidden)]
+        pub fn __wasm_bindgen_build_info() -> __WasmBindgenBuildInfo {
+            let info = __WasmBindgenBuildInfo::new();
+            info.set_name(env!("CARGO_PKG_NAME"));
+            info.set_version(env!("CARGO_PKG_VERSION"));
+            let git_hash: Option<&'static str> = ($git_hash).into();
+            match git_hash {
+                Some(git_hash) => info.set_git_hash(&$crate::JsValue::from_str(git_hash)),
+                None => info.set_git_hash(&$crate::JsValue::UNDEFINED),
+            }
+            let mut features = Vec::new();
+            $(
+                if cfg!(feature = $feature) {
+                    features.push($crate::JsValue::from_str($feature));
+                }
+            )*
+            info.set_features(features);
+            info.set_wasm_bindgen_version($crate::__rt::VERSION);
+            info
+        }
+    };
+    (git_hash = $git_hash:expr $(,)*) => {
+        $crate::build_info!(git_hash = $git_hash, features = []);
+    };
+}
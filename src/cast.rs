@@ -1,5 +1,10 @@
 use JsValue;
 
+if_std! {
+    use core::fmt;
+    use std::prelude::v1::*;
+}
+
 /// A trait for checked and unchecked casting between JS types.
 ///
 /// Specified [in an RFC][rfc] this trait is intended to provide support for
@@ -44,6 +49,25 @@ where
         }
     }
 
+    /// Performs a dynamic cast (checked at runtime) of this value into the
+    /// target type `T`, like `dyn_into`, except that a failed cast is
+    /// described by the error.
+    ///
+    /// The error says which type was expected and which was found, can have a
+    /// preview of the value added with `CastError::with_preview`, and can be
+    /// turned back into the value with `CastError::into_value`.
+    #[cfg(feature = "std")]
+    fn dyn_into_named<T>(self) -> Result<T, CastError>
+    where
+        T: JsCast,
+    {
+        if self.is_instance_of::<T>() {
+            Ok(self.unchecked_into())
+        } else {
+            Err(CastError::new(self.into(), &[T::class_name()]))
+        }
+    }
+
     /// Performs a dynamic cast (checked at runtime) of this value into the
     /// target type `T`.
     ///
@@ -61,6 +85,92 @@ where
         }
     }
 
+    /// Test whether this JS value is an object of the class of type `T`, going
+    /// by the name of the class rather than by `instanceof`.
+    ///
+    /// Unlike `instanceof` this recognizes objects from other realms, like
+    /// iframes or workers, whose classes are different objects with the same
+    /// names. An object is considered to be of the class if a constructor on
+    /// its prototype chain or its `Symbol.toStringTag` has the class's name,
+    /// so unrelated classes with the same name are mistaken for each other.
+    /// Types whose class isn't known by name are checked with `instanceof`.
+    fn has_class_name<T>(&self) -> bool
+    where
+        T: JsCast,
+    {
+        match T::class_name() {
+            Some(name) => self.as_ref().has_class_named(name),
+            None => T::instanceof(self.as_ref()),
+        }
+    }
+
+    /// Performs a dynamic cast of this value into the target type `T`, checked
+    /// with `has_class_name` rather than `instanceof`.
+    fn dyn_into_by_name<T>(self) -> Result<T, Self>
+    where
+        T: JsCast,
+    {
+        if self.has_class_name::<T>() {
+            Ok(self.unchecked_into())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Performs a dynamic cast of a reference to this value into the target
+    /// type `T`, checked with `has_class_name` rather than `instanceof`.
+    fn dyn_ref_by_name<T>(&self) -> Option<&T>
+    where
+        T: JsCast,
+    {
+        if self.has_class_name::<T>() {
+            Some(self.unchecked_ref())
+        } else {
+            None
+        }
+    }
+
+    /// Test whether this JS value is an object with all of the methods
+    /// declared for type `T`, whatever its class.
+    ///
+    /// Only the methods declared on the imported type itself are checked, not
+    /// those of the types it `extends`, and only that they're functions. This
+    /// accepts any object with those methods, including ones from other
+    /// realms and plain objects standing in for instances. Types which aren't
+    /// imported are checked with `instanceof`.
+    fn has_methods_of<T>(&self) -> bool
+    where
+        T: JsCast,
+    {
+        T::has_methods(self.as_ref())
+    }
+
+    /// Performs a dynamic cast of this value into the target type `T`, checked
+    /// with `has_methods_of` rather than `instanceof`.
+    fn dyn_into_by_methods<T>(self) -> Result<T, Self>
+    where
+        T: JsCast,
+    {
+        if self.has_methods_of::<T>() {
+            Ok(self.unchecked_into())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Performs a dynamic cast of a reference to this value into the target
+    /// type `T`, checked with `has_methods_of` rather than `instanceof`.
+    fn dyn_ref_by_methods<T>(&self) -> Option<&T>
+    where
+        T: JsCast,
+    {
+        if self.has_methods_of::<T>() {
+            Some(self.unchecked_ref())
+        } else {
+            None
+        }
+    }
+
     /// Performs a zero-cost unchecked cast into the specified type.
     ///
     /// This method will convert the `self` value to the type `T`, where both
@@ -116,4 +226,151 @@ where
     /// This is intended to be an internal implementation detail, you likely
     /// won't need to call this.
     fn unchecked_from_js_ref(val: &JsValue) -> &Self;
+
+    /// The name of the JS class instances of this type are checked against,
+    /// if known, which is used to describe failed casts.
+    ///
+    /// This is intended to be an internal implementation detail, you likely
+    /// won't need to call this.
+    fn class_name() -> Option<&'static str> {
+        None
+    }
+
+    /// Performs a dynamic check of whether the `JsValue` provided has all of
+    /// the methods declared for this type.
+    ///
+    /// This is intended to be an internal implementation detail, you likely
+    /// won't need to call this.
+    fn has_methods(val: &JsValue) -> bool {
+        Self::instanceof(val)
+    }
+}
+
+/// Casts a value to the first of several types it's an instance of.
+///
+/// Each arm names a binding and a type to try casting the value to, and the
+/// first type the value is an instance of has its arm evaluated. The last arm
+/// is evaluated with a `CastError` listing all of the types if none of them
+/// match.
+///
+/// ```ignore
+/// let text = try_downcast!(node,
+///     element: Element => element.inner_html(),
+///     text: Text => text.data(),
+///     Err(err) => return Err(err.into()),
+/// );
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! try_downcast {
+    (@arms $value:ident [$($expected:expr,)*] Err($err:ident) => $fallback:expr $(,)*) => {{
+        let $err = $crate::CastError::new($value, &[$($expected),*]);
+        $fallback
+    }};
+    (@arms $value:ident [$($expected:expr,)*] $bind:ident: $ty:ty => $body:expr, $($rest:tt)*) => {
+        match <$crate::JsValue as $crate::JsCast>::dyn_into::<$ty>($value) {
+            Ok($bind) => $body,
+            Err($value) => $crate::try_downcast!(
+                @arms $value
+                [$($expected,)* <$ty as $crate::JsCast>::class_name(),]
+                $($rest)*
+            ),
+        }
+    };
+    ($value:expr, $($arms:tt)*) => {{
+        let value: $crate::JsValue = $value.into();
+        $crate::try_downcast!(@arms value [] $($arms)*)
+    }};
+}
+
+if_std! {
+    /// How many characters of a value `CastError::with_preview` shows.
+    const PREVIEW_LEN: usize = 100;
+
+    /// A value which couldn't be cast to the type it was expected to be, as
+    /// returned by `JsCast::dyn_into_named` and `try_downcast!`.
+    #[derive(Debug)]
+    pub struct CastError {
+        value: JsValue,
+        expected: Vec<&'static str>,
+        found: String,
+        preview: Option<String>,
+    }
+
+    impl CastError {
+        /// Creates the error for `value` not being an instance of any of the
+        /// classes named in `expected`, where `None` is a class whose name
+        /// isn't known.
+        pub fn new(value: JsValue, expected: &[Option<&'static str>]) -> CastError {
+            CastError {
+                expected: expected.iter().filter_map(|name| *name).collect(),
+                found: value.type_name(),
+                preview: None,
+                value,
+            }
+        }
+
+        /// Adds a preview of the value to the description of the error, the
+        /// start of what its `Debug` representation would be.
+        pub fn with_preview(mut self) -> CastError {
+            let mut preview = self.value.as_debug_string();
+            if let Some((end, _)) = preview.char_indices().nth(PREVIEW_LEN) {
+                preview.truncate(end);
+                preview.push_str("...");
+            }
+            self.preview = Some(preview);
+            self
+        }
+
+        /// The names of the classes the value was expected to be an instance
+        /// of, as far as they're known.
+        pub fn expected(&self) -> &[&'static str] {
+            &self.expected
+        }
+
+        /// The name of the constructor of the value, or its `typeof` if it
+        /// isn't an object.
+        pub fn found(&self) -> &str {
+            &self.found
+        }
+
+        /// The value which couldn't be cast.
+        pub fn value(&self) -> &JsValue {
+            &self.value
+        }
+
+        /// Returns the value which couldn't be cast.
+        pub fn into_value(self) -> JsValue {
+            self.value
+        }
+    }
+
+    impl fmt::Display for CastError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self.expected.split_last() {
+                None => f.write_str("expected a value of a different type")?,
+                Some((last, rest)) => {
+                    f.write_str("expected an instance of ")?;
+                    for (i, name) in rest.iter().enumerate() {
+                        let separator = if i + 1 < rest.len() { ", " } else { " or " };
+                        write!(f, "`{}`{}", name, separator)?;
+                    }
+                    write!(f, "`{}`", last)?;
+                }
+            }
+            write!(f, ", found `{}`", self.found)?;
+            if let Some(preview) = &self.preview {
+                write!(f, ": {}", preview)?;
+            }
+            Ok(())
+        }
+    }
+
+    impl std::error::Error for CastError {}
+
+    impl From<CastError> for JsValue {
+        fn from(err: CastError) -> JsValue {
+            err.to_string().into()
+        }
+    }
 }
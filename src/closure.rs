@@ -138,12 +138,7 @@ impl<T> Closure<T>
     /// type.
     ///
     /// This is the function where the JS closure is manufactured.
-    pub fn wrap(mut data: Box<T>) -> Closure<T> {
-        assert_eq!(mem::size_of::<*const T>(), mem::size_of::<FatPtr<T>>());
-        let (a, b) = unsafe {
-            FatPtr { ptr: &mut *data as *mut T }.fields
-        };
-
+    pub fn wrap(data: Box<T>) -> Closure<T> {
         // Here we need to create a `JsValue` with the data and `T::invoke()`
         // function pointer. To do that we... take a few unconventional turns.
         // In essence what happens here is this:
@@ -205,8 +200,55 @@ impl<T> Closure<T>
             )
         }
 
+        Closure::wrap_described(data, breaks_if_inlined::<T>)
+    }
+
+    /// Wraps a boxed closure like `Closure::wrap`, except that the JS closure
+    /// passes the `this` it's called with as the closure's first argument.
+    ///
+    /// This is useful for callbacks of APIs which pass information through
+    /// `this`, like event handlers which are called with the element they're
+    /// registered on as `this`. The JS closure takes one less argument than
+    /// the Rust closure, as the first argument of the Rust closure is `this`.
+    ///
+    /// ```rust,no_run
+    /// let cb = Closure::wrap_with_this(Box::new(|this: JsValue, event: JsValue| {
+    ///     // ...
+    /// }) as Box<FnMut(JsValue, JsValue)>);
+    /// ```
+    pub fn wrap_with_this(data: Box<T>) -> Closure<T> {
+        // See `wrap` for what's going on here.
+        extern "C" fn describe<T: WasmClosure + ?Sized>() {
+            inform(CLOSURE_WITH_THIS);
+            T::describe()
+        }
+
+        #[inline(never)]
+        unsafe fn breaks_if_inlined<T: WasmClosure + ?Sized>(
+            a: usize,
+            b: usize,
+        ) -> u32 {
+            super::__wbindgen_describe_closure(
+                a as u32,
+                b as u32,
+                describe::<T> as u32,
+            )
+        }
+
+        Closure::wrap_described(data, breaks_if_inlined::<T>)
+    }
+
+    fn wrap_described(
+        mut data: Box<T>,
+        breaks_if_inlined: unsafe fn(usize, usize) -> u32,
+    ) -> Closure<T> {
+        assert_eq!(mem::size_of::<*const T>(), mem::size_of::<FatPtr<T>>());
+        let (a, b) = unsafe {
+            FatPtr { ptr: &mut *data as *mut T }.fields
+        };
+
         let idx = unsafe {
-            breaks_if_inlined::<T>(a, b)
+            breaks_if_inlined(a, b)
         };
 
         Closure {
@@ -408,13 +408,13 @@ impl IntoWasmAbi for () {
     }
 }
 
-impl<T: IntoWasmAbi> ReturnWasmAbi for Result<T, JsValue> {
+impl<T: IntoWasmAbi, E: Into<JsValue>> ReturnWasmAbi for Result<T, E> {
     type Abi = T::Abi;
 
     fn return_abi(self, extra: &mut Stack) -> Self::Abi {
         match self {
             Ok(v) => v.into_abi(extra),
-            Err(e) => ::throw_val(e),
+            Err(e) => ::throw_val(e.into()),
         }
     }
 }
@@ -0,0 +1,200 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+use std::prelude::v1::*;
+
+use convert::{FromWasmAbi, IntoWasmAbi, OptionFromWasmAbi, OptionIntoWasmAbi};
+use convert::{Stack, WasmSlice};
+use describe::WasmDescribe;
+use {throw_str, JsValue};
+
+/// Values of `HashMap<String, V>` and `BTreeMap<String, V>`, which are passed
+/// to and from JS one `JsValue` at a time.
+///
+/// This is implemented for numbers, `bool`, `String` and `JsValue`, and
+/// `#[wasm_bindgen]` implements it for imported types.
+pub trait MapValue: WasmDescribe + Sized {
+    /// Converts the value into the `JsValue` JS sees in the map.
+    fn into_js_value(self) -> JsValue;
+
+    /// Recovers a value from a map passed from JS, returning `None` if `js`
+    /// isn't a `Self`.
+    ///
+    /// Like `JsCast::unchecked_into`, imported types don't check the class of
+    /// `js`.
+    fn from_js_value(js: JsValue) -> Option<Self>;
+}
+
+impl MapValue for JsValue {
+    #[inline]
+    fn into_js_value(self) -> JsValue {
+        self
+    }
+
+    #[inline]
+    fn from_js_value(js: JsValue) -> Option<JsValue> {
+        Some(js)
+    }
+}
+
+impl MapValue for String {
+    #[inline]
+    fn into_js_value(self) -> JsValue {
+        JsValue::from(self)
+    }
+
+    #[inline]
+    fn from_js_value(js: JsValue) -> Option<String> {
+        js.as_string()
+    }
+}
+
+impl MapValue for bool {
+    #[inline]
+    fn into_js_value(self) -> JsValue {
+        JsValue::from(self)
+    }
+
+    #[inline]
+    fn from_js_value(js: JsValue) -> Option<bool> {
+        js.as_bool()
+    }
+}
+
+macro_rules! integers {
+    ($($n:ident)*) => ($(
+        impl MapValue for $n {
+            #[inline]
+            fn into_js_value(self) -> JsValue {
+                JsValue::from(self)
+            }
+
+            // Numbers which would be truncated or wrapped around aren't
+            // integers of this type, and neither are `NaN` and infinities,
+            // whose fractional part is `NaN`.
+            #[inline]
+            fn from_js_value(js: JsValue) -> Option<$n> {
+                js.as_f64().and_then(|n| {
+                    if n.fract() == 0.0
+                        && n >= <$n>::min_value() as f64
+                        && n <= <$n>::max_value() as f64
+                    {
+                        Some(n as $n)
+                    } else {
+                        None
+                    }
+                })
+            }
+        }
+    )*)
+}
+
+integers! { i8 u8 i16 u16 i32 u32 }
+
+macro_rules! floats {
+    ($($n:ident)*) => ($(
+        impl MapValue for $n {
+            #[inline]
+            fn into_js_value(self) -> JsValue {
+                JsValue::from(self)
+            }
+
+            #[inline]
+            fn from_js_value(js: JsValue) -> Option<$n> {
+                js.as_f64().map(|n| n as $n)
+            }
+        }
+    )*)
+}
+
+floats! { f32 f64 }
+
+/// Flattens the entries of a map into keys followed by their values, which is
+/// how maps cross the ABI.
+fn into_abi<V, I>(entries: I, extra: &mut Stack) -> WasmSlice
+where
+    V: MapValue,
+    I: ExactSizeIterator<Item = (String, V)>,
+{
+    let mut flat = Vec::with_capacity(entries.len() * 2);
+    for (key, value) in entries {
+        flat.push(JsValue::from(key));
+        flat.push(value.into_js_value());
+    }
+    flat.into_boxed_slice().into_abi(extra)
+}
+
+unsafe fn from_abi<V: MapValue>(js: WasmSlice, extra: &mut Stack) -> Vec<(String, V)> {
+    let mut flat = <Box<[JsValue]>>::from_abi(js, extra).into_vec().into_iter();
+    let mut entries = Vec::with_capacity(flat.len() / 2);
+    while let (Some(key), Some(value)) = (flat.next(), flat.next()) {
+        let key = match key.as_string() {
+            Some(key) => key,
+            None => throw_str("keys of maps passed to Rust must be strings"),
+        };
+        match V::from_js_value(value) {
+            Some(value) => entries.push((key, value)),
+            None => throw_str(&(String::from("invalid value for map key `") + &key + "`")),
+        }
+    }
+    entries
+}
+
+impl<V: MapValue, S: BuildHasher> IntoWasmAbi for HashMap<String, V, S> {
+    type Abi = WasmSlice;
+
+    #[inline]
+    fn into_abi(self, extra: &mut Stack) -> WasmSlice {
+        into_abi(self.into_iter(), extra)
+    }
+}
+
+impl<V: MapValue, S: BuildHasher> OptionIntoWasmAbi for HashMap<String, V, S> {
+    fn none() -> WasmSlice {
+        WasmSlice { ptr: 0, len: 0 }
+    }
+}
+
+impl<V: MapValue, S: BuildHasher + Default> FromWasmAbi for HashMap<String, V, S> {
+    type Abi = WasmSlice;
+
+    #[inline]
+    unsafe fn from_abi(js: WasmSlice, extra: &mut Stack) -> Self {
+        from_abi(js, extra).into_iter().collect()
+    }
+}
+
+impl<V: MapValue, S: BuildHasher + Default> OptionFromWasmAbi for HashMap<String, V, S> {
+    fn is_none(slice: &WasmSlice) -> bool {
+        slice.ptr == 0
+    }
+}
+
+impl<V: MapValue> IntoWasmAbi for BTreeMap<String, V> {
+    type Abi = WasmSlice;
+
+    #[inline]
+    fn into_abi(self, extra: &mut Stack) -> WasmSlice {
+        into_abi(self.into_iter(), extra)
+    }
+}
+
+impl<V: MapValue> OptionIntoWasmAbi for BTreeMap<String, V> {
+    fn none() -> WasmSlice {
+        WasmSlice { ptr: 0, len: 0 }
+    }
+}
+
+impl<V: MapValue> FromWasmAbi for BTreeMap<String, V> {
+    type Abi = WasmSlice;
+
+    #[inline]
+    unsafe fn from_abi(js: WasmSlice, extra: &mut Stack) -> Self {
+        from_abi(js, extra).into_iter().collect()
+    }
+}
+
+impl<V: MapValue> OptionFromWasmAbi for BTreeMap<String, V> {
+    fn is_none(slice: &WasmSlice) -> bool {
+        slice.ptr == 0
+    }
+}
@@ -1,13 +1,99 @@
-//! This is mostly an internal module, no stability guarantees are provided. Use
-//! at your own risk.
+//! Traits for converting values to and from the wasm ABI.
+//!
+//! This is mostly an internal module, no stability guarantees are provided for
+//! most of it. The exception is the `custom_abi!` macro, which is the supported
+//! way for crates to make their own types usable in `#[wasm_bindgen]`
+//! signatures by converting them to and from a type which already is. Types
+//! implemented this way will continue to work across releases, whereas
+//! implementing the traits here by hand may break at any time.
 
 mod closures;
 mod impls;
+#[cfg(feature = "std")]
+mod maps;
 mod slices;
 mod traits;
 
+#[cfg(feature = "std")]
+pub use self::maps::MapValue;
 pub use self::slices::WasmSlice;
+#[cfg(feature = "std")]
+pub use self::slices::{VectorFromWasmAbi, VectorIntoWasmAbi};
 pub use self::traits::*;
+pub use describe::WasmDescribe;
+
+/// Makes a type usable in `#[wasm_bindgen]` function signatures by converting
+/// it to and from another type that already is.
+///
+/// `custom_abi!(MyType as Repr)` allows passing `MyType` by value to and from
+/// JS as if it were a `Repr`, requiring `MyType: Into<Repr>` and
+/// `Repr: Into<MyType>`. JS and TypeScript will see a `Repr`.
+///
+/// Adding `, optional` at the end also makes `Option<MyType>` usable, which is
+/// only possible if `Option<Repr>` is.
+///
+/// ```
+/// # #[macro_use] extern crate wasm_bindgen;
+/// pub struct Name(String);
+///
+/// impl From<Name> for String {
+///     fn from(n: Name) -> String { n.0 }
+/// }
+///
+/// impl From<String> for Name {
+///     fn from(s: String) -> Name { Name(s) }
+/// }
+///
+/// custom_abi!(Name as String, optional);
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! custom_abi {
+    ($ty:ty as $repr:ty) => {
+        impl $crate::convert::WasmDescribe for $ty {
+            fn describe() {
+                <$repr as $crate::convert::WasmDescribe>::describe()
+            }
+        }
+
+        impl $crate::convert::IntoWasmAbi for $ty {
+            type Abi = <$repr as $crate::convert::IntoWasmAbi>::Abi;
+
+            #[inline]
+            fn into_abi(self, extra: &mut $crate::convert::Stack) -> Self::Abi {
+                let repr: $repr = self.into();
+                $crate::convert::IntoWasmAbi::into_abi(repr, extra)
+            }
+        }
+
+        impl $crate::convert::FromWasmAbi for $ty {
+            type Abi = <$repr as $crate::convert::FromWasmAbi>::Abi;
+
+            #[inline]
+            unsafe fn from_abi(js: Self::Abi, extra: &mut $crate::convert::Stack) -> Self {
+                <$repr as $crate::convert::FromWasmAbi>::from_abi(js, extra).into()
+            }
+        }
+    };
+
+    ($ty:ty as $repr:ty, optional) => {
+        $crate::custom_abi!($ty as $repr);
+
+        impl $crate::convert::OptionIntoWasmAbi for $ty {
+            #[inline]
+            fn none() -> Self::Abi {
+                <$repr as $crate::convert::OptionIntoWasmAbi>::none()
+            }
+        }
+
+        impl $crate::convert::OptionFromWasmAbi for $ty {
+            #[inline]
+            fn is_none(abi: &Self::Abi) -> bool {
+                <$repr as $crate::convert::OptionFromWasmAbi>::is_none(abi)
+            }
+        }
+    };
+}
 
 pub struct GlobalStack {
     next: usize,
@@ -5,11 +5,12 @@ use core::slice;
 use core::str;
 
 use convert::{FromWasmAbi, IntoWasmAbi, RefFromWasmAbi, RefMutFromWasmAbi, WasmAbi};
-use convert::{OptionIntoWasmAbi, Stack};
+use convert::{OptionIntoWasmAbi, OptionRefFromWasmAbi, Stack};
 
 if_std! {
     use core::mem;
     use convert::OptionFromWasmAbi;
+    use describe::WasmDescribe;
 }
 
 #[repr(C)]
@@ -105,6 +106,10 @@ macro_rules! vectors {
             }
         }
 
+        impl OptionRefFromWasmAbi for [$t] {
+            fn is_none(slice: &WasmSlice) -> bool { slice.ptr == 0 }
+        }
+
         impl RefMutFromWasmAbi for [$t] {
             type Abi = WasmSlice;
             type Anchor = &'static mut [$t];
@@ -203,40 +208,76 @@ impl RefFromWasmAbi for str {
     }
 }
 
+impl OptionRefFromWasmAbi for str {
+    fn is_none(slice: &WasmSlice) -> bool {
+        slice.ptr == 0
+    }
+}
+
 if_std! {
     use JsValue;
 
-    impl IntoWasmAbi for Box<[JsValue]> {
+    /// Element types of boxed slices, and so of `Vec`s, which cross the ABI
+    /// as an array of the JS values of their elements.
+    ///
+    /// `#[wasm_bindgen]` implements this for exported structs and imported
+    /// types, and it's implemented for `JsValue` itself.
+    pub trait VectorIntoWasmAbi: WasmDescribe + Sized {
+        /// Same as `IntoWasmAbi::into_abi`, but for a boxed slice of `Self`.
+        fn vector_into_abi(vector: Box<[Self]>, extra: &mut Stack) -> WasmSlice;
+    }
+
+    /// Dual of the `VectorIntoWasmAbi` trait, recovering a boxed slice.
+    pub trait VectorFromWasmAbi: WasmDescribe + Sized {
+        /// Same as `FromWasmAbi::from_abi`, but for a boxed slice of `Self`.
+        unsafe fn vector_from_abi(js: WasmSlice, extra: &mut Stack) -> Box<[Self]>;
+    }
+
+    impl<T: VectorIntoWasmAbi> IntoWasmAbi for Box<[T]> {
         type Abi = WasmSlice;
 
         #[inline]
         fn into_abi(self, extra: &mut Stack) -> WasmSlice {
-            let ptr = self.as_ptr();
-            let len = self.len();
-            mem::forget(self);
-            WasmSlice {
-                ptr: ptr.into_abi(extra),
-                len: len as u32,
-            }
+            T::vector_into_abi(self, extra)
         }
     }
 
-    impl OptionIntoWasmAbi for Box<[JsValue]> {
+    impl<T: VectorIntoWasmAbi> OptionIntoWasmAbi for Box<[T]> {
         fn none() -> WasmSlice { null_slice() }
     }
 
-    impl FromWasmAbi for Box<[JsValue]> {
+    impl<T: VectorFromWasmAbi> FromWasmAbi for Box<[T]> {
         type Abi = WasmSlice;
 
         #[inline]
         unsafe fn from_abi(js: WasmSlice, extra: &mut Stack) -> Self {
-            let ptr = <*mut JsValue>::from_abi(js.ptr, extra);
-            let len = js.len as usize;
-            Vec::from_raw_parts(ptr, len, len).into_boxed_slice()
+            T::vector_from_abi(js, extra)
         }
     }
 
-    impl OptionFromWasmAbi for Box<[JsValue]> {
+    impl<T: VectorFromWasmAbi> OptionFromWasmAbi for Box<[T]> {
         fn is_none(slice: &WasmSlice) -> bool { slice.ptr == 0 }
     }
+
+    impl VectorIntoWasmAbi for JsValue {
+        #[inline]
+        fn vector_into_abi(vector: Box<[JsValue]>, extra: &mut Stack) -> WasmSlice {
+            let ptr = vector.as_ptr();
+            let len = vector.len();
+            mem::forget(vector);
+            WasmSlice {
+                ptr: ptr.into_abi(extra),
+                len: len as u32,
+            }
+        }
+    }
+
+    impl VectorFromWasmAbi for JsValue {
+        #[inline]
+        unsafe fn vector_from_abi(js: WasmSlice, extra: &mut Stack) -> Box<[JsValue]> {
+            let ptr = <*mut JsValue>::from_abi(js.ptr, extra);
+            let len = js.len as usize;
+            Vec::from_raw_parts(ptr, len, len).into_boxed_slice()
+        }
+    }
 }
@@ -90,6 +90,17 @@ pub trait OptionFromWasmAbi: FromWasmAbi {
     fn is_none(abi: &Self::Abi) -> bool;
 }
 
+/// Indicates that references to this type can be received from JS as
+/// `Option<&Self>`.
+///
+/// This trait is used for `Option<&T>` arguments of exported functions.
+pub trait OptionRefFromWasmAbi: RefFromWasmAbi {
+    /// Tests whether the argument is a "none" instance. If so it will be
+    /// deserialized as `None`, and otherwise it will be passed to
+    /// `RefFromWasmAbi`.
+    fn is_none(abi: &Self::Abi) -> bool;
+}
+
 pub trait Stack {
     fn push(&mut self, bits: u32);
 }
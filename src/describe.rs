@@ -41,6 +41,12 @@ tys! {
     OPTIONAL
     UNIT
     CLAMPED
+    NAMED
+    GENERIC
+    TAGGED_UNION
+    IMPORT_ENUM
+    MAP
+    CLOSURE_WITH_THIS
 }
 
 #[inline(always)] // see `interpret.rs` in the the cli-support crate
@@ -131,6 +137,22 @@ if_std! {
             <Box<[T]>>::describe();
         }
     }
+
+    use std::collections::{BTreeMap, HashMap};
+
+    impl<V: WasmDescribe, S> WasmDescribe for HashMap<String, V, S> {
+        fn describe() {
+            inform(MAP);
+            V::describe();
+        }
+    }
+
+    impl<V: WasmDescribe> WasmDescribe for BTreeMap<String, V> {
+        fn describe() {
+            inform(MAP);
+            V::describe();
+        }
+    }
 }
 
 impl<T: WasmDescribe> WasmDescribe for Option<T> {
@@ -146,9 +168,9 @@ impl WasmDescribe for () {
     }
 }
 
-// Note that this is only for `ReturnWasmAbi for Result<T, JsValue>`, which
-// throws the result, so we only need to inform about the `T`.
-impl<T: WasmDescribe> WasmDescribe for Result<T, JsValue> {
+// Note that this is only for `ReturnWasmAbi for Result<T, E>`, which throws
+// the error, so we only need to inform about the `T`.
+impl<T: WasmDescribe, E: Into<JsValue>> WasmDescribe for Result<T, E> {
     fn describe() {
         T::describe()
     }
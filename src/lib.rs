@@ -67,9 +67,14 @@ pub mod prelude {
 
 pub mod convert;
 pub mod describe;
+pub mod strided;
 
+mod build_info;
 mod cast;
 pub use cast::JsCast;
+if_std! {
+    pub use cast::CastError;
+}
 
 if_std! {
     extern crate std;
@@ -235,6 +240,54 @@ impl JsValue {
         }
     }
 
+    /// Converts this value to a JSON string with `JSON.stringify`.
+    ///
+    /// Unlike calling `JSON.stringify` through `js-sys`, which returns a JS
+    /// string that then has to be copied into Rust separately, this only calls
+    /// into JS once. It doesn't require the `serde-serialize` feature either,
+    /// so it's handy for passing JS values through something which only deals
+    /// in strings.
+    ///
+    /// # Errors
+    ///
+    /// Returns the exception thrown by `JSON.stringify`, for example for
+    /// cyclic objects or `BigInt`s, or a `TypeError` if the value has no JSON
+    /// representation at all, like `undefined` or a function.
+    pub fn to_json_string(&self) -> Result<String, JsValue> {
+        unsafe {
+            let mut len = 0;
+            let mut exn = [0; 2];
+            let ptr = __wbindgen_json_to_string(self.idx, &mut len, exn.as_mut_ptr());
+            if exn[0] == 1 {
+                return Err(JsValue::_new(exn[1]));
+            }
+            let data = Vec::from_raw_parts(ptr, len, len);
+            Ok(String::from_utf8_unchecked(data))
+        }
+    }
+
+    /// Parses `json` into a JS value with `JSON.parse`.
+    ///
+    /// This is the counterpart of `to_json_string`, and likewise only calls
+    /// into JS once and doesn't require the `serde-serialize` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `SyntaxError` thrown by `JSON.parse` if `json` isn't valid
+    /// JSON.
+    pub fn from_json_str(json: &str) -> Result<JsValue, JsValue> {
+        unsafe {
+            let mut failed = 0;
+            let idx = __wbindgen_json_from_str(json.as_ptr(), json.len(), &mut failed);
+            let val = JsValue::_new(idx);
+            if failed == 1 {
+                Err(val)
+            } else {
+                Ok(val)
+            }
+        }
+    }
+
     /// Returns the `f64` value of this JS value if it's an instance of a
     /// number.
     ///
@@ -326,6 +379,13 @@ impl JsValue {
         unsafe { __wbindgen_is_function(self.idx) == 1 }
     }
 
+    /// Tests whether the JavaScript object has the class `name`, either as the
+    /// name of a constructor on its prototype chain or as its
+    /// `Symbol.toStringTag`.
+    fn has_class_named(&self, name: &str) -> bool {
+        unsafe { __wbindgen_has_class_name(self.idx, name.as_ptr(), name.len()) == 1 }
+    }
+
     /// Get a string representation of the JavaScript object for debugging
     #[cfg(feature = "std")]
     fn as_debug_string(&self) -> String {
@@ -340,6 +400,22 @@ impl JsValue {
             }
         }
     }
+
+    /// Get the name of the constructor of the JavaScript object, or the
+    /// `typeof` of primitives, for describing what it is in errors.
+    #[cfg(feature = "std")]
+    fn type_name(&self) -> String {
+        unsafe {
+            let mut len = 0;
+            let ptr = __wbindgen_type_name(self.idx, &mut len);
+            if ptr.is_null() {
+                unreachable!("`__wbindgen_type_name` must return a valid string")
+            } else {
+                let data = Vec::from_raw_parts(ptr, len, len);
+                String::from_utf8_unchecked(data)
+            }
+        }
+    }
 }
 
 impl PartialEq for JsValue {
@@ -499,6 +575,8 @@ externs! {
         fn __wbindgen_is_string(idx: u32) -> u32;
         fn __wbindgen_string_get(idx: u32, len: *mut usize) -> *mut u8;
         fn __wbindgen_debug_string(idx: u32, len: *mut usize) -> *mut u8;
+        fn __wbindgen_type_name(idx: u32, len: *mut usize) -> *mut u8;
+        fn __wbindgen_has_class_name(idx: u32, ptr: *const u8, len: usize) -> u32;
         fn __wbindgen_throw(a: *const u8, b: usize) -> !;
         fn __wbindgen_rethrow(a: u32) -> !;
 
@@ -510,10 +588,18 @@ externs! {
 
         fn __wbindgen_json_parse(ptr: *const u8, len: usize) -> u32;
         fn __wbindgen_json_serialize(idx: u32, ptr: *mut *mut u8) -> usize;
+        fn __wbindgen_json_to_string(idx: u32, len: *mut usize, exn: *mut u32) -> *mut u8;
+        fn __wbindgen_json_from_str(ptr: *const u8, len: usize, failed: *mut u8) -> u32;
         fn __wbindgen_jsval_eq(a: u32, b: u32) -> u32;
 
         fn __wbindgen_memory() -> u32;
         fn __wbindgen_module() -> u32;
+        fn __wbindgen_global() -> u32;
+        fn __wbindgen_import(ptr: *const u8, len: usize) -> u32;
+
+        fn __wbindgen_now() -> f64;
+        fn __wbindgen_random() -> f64;
+        fn __wbindgen_random_fill(ptr: *mut u8, len: usize) -> ();
     }
 }
 
@@ -731,6 +817,74 @@ pub fn memory() -> JsValue {
     unsafe { JsValue::_new(__wbindgen_memory()) }
 }
 
+/// Returns a handle to the global scope object, `globalThis`.
+///
+/// The global object is looked up once by the generated JS glue, falling back
+/// to `self`, `window` or `global` in environments without `globalThis`, and
+/// doesn't require evaluating any code so it works under a strict Content
+/// Security Policy as well.
+///
+/// Each call returns a fresh handle to the same object, so callers which need
+/// it often may want to cache it themselves as `js_sys::global` does.
+pub fn global() -> JsValue {
+    unsafe { JsValue::_new(__wbindgen_global()) }
+}
+
+/// Dynamically imports the JS module `specifier`, returning a `Promise` which
+/// resolves to the module's namespace object.
+///
+/// This is the equivalent of JS's `import(specifier)`, and allows optional JS
+/// dependencies to be loaded lazily. When targeting Node.js without ES modules
+/// this uses `require` instead. The `wasm_bindgen_futures::import_module`
+/// function wraps this in a typed `Future`.
+///
+/// Note that because `specifier` is only known at runtime, bundlers can't
+/// resolve it ahead of time, so it should be a URL or a module the bundler is
+/// otherwise configured to make available. Imports with the `dynamic_import`
+/// attribute are written out literally and can be resolved by bundlers.
+pub fn import_module(specifier: &str) -> JsValue {
+    unsafe { JsValue::_new(__wbindgen_import(specifier.as_ptr(), specifier.len())) }
+}
+
+/// Returns the current time in milliseconds since the Unix epoch, like JS's
+/// `Date.now()`.
+///
+/// The generated JS glue can be handed another clock with `setHostSources`,
+/// for example to replay a recorded session or to run a simulation
+/// deterministically, in which case that's what's returned here instead.
+pub fn now() -> f64 {
+    unsafe { __wbindgen_now() }
+}
+
+/// Returns a random number in the range `[0, 1)`, like JS's `Math.random()`.
+///
+/// As with `now`, the source of randomness can be replaced from JS with
+/// `setHostSources`.
+pub fn random() -> f64 {
+    unsafe { __wbindgen_random() }
+}
+
+/// Fills `dest` with random bytes.
+///
+/// By default these come from the host's cryptographically secure random
+/// number generator, `crypto.getRandomValues` or node's `crypto` module. If a
+/// `random` source has been supplied with `setHostSources` the bytes are
+/// derived from that instead, and are then only as random as it is.
+///
+/// Crates using `getrandom` can route it through here, and so through the
+/// same source, by registering a custom backend:
+///
+/// ```ignore
+/// fn fill(dest: &mut [u8]) -> Result<(), getrandom::Error> {
+///     wasm_bindgen::fill_random(dest);
+///     Ok(())
+/// }
+/// getrandom::register_custom_getrandom!(fill);
+/// ```
+pub fn fill_random(dest: &mut [u8]) {
+    unsafe { __wbindgen_random_fill(dest.as_mut_ptr(), dest.len()) }
+}
+
 #[doc(hidden)]
 pub mod __rt {
     use core::cell::{Cell, UnsafeCell};
@@ -739,6 +893,8 @@ pub mod __rt {
     #[cfg(feature = "std")]
     pub extern crate std;
 
+    pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
     #[macro_export]
     #[doc(hidden)]
     #[cfg(feature = "std")]
@@ -753,6 +909,34 @@ pub mod __rt {
         ($($i:item)*) => ($($i)*)
     }
 
+    #[macro_export]
+    #[doc(hidden)]
+    #[cfg(feature = "std")]
+    macro_rules! __wbindgen_if_std {
+        ($($i:item)*) => ($($i)*)
+    }
+
+    #[macro_export]
+    #[doc(hidden)]
+    #[cfg(not(feature = "std"))]
+    macro_rules! __wbindgen_if_std {
+        ($($i:item)*) => {};
+    }
+
+    #[macro_export]
+    #[doc(hidden)]
+    #[cfg(feature = "serde-serialize")]
+    macro_rules! __wbindgen_if_not_serde {
+        ($($i:item)*) => {};
+    }
+
+    #[macro_export]
+    #[doc(hidden)]
+    #[cfg(not(feature = "serde-serialize"))]
+    macro_rules! __wbindgen_if_not_serde {
+        ($($i:item)*) => ($($i)*)
+    }
+
     #[inline]
     pub fn assert_not_null<T>(s: *mut T) {
         if s.is_null() {
@@ -766,6 +950,39 @@ pub mod __rt {
         super::throw_str("null pointer passed to rust");
     }
 
+    /// Hashes `value` for the `hashCode` method of structs exported with
+    /// `#[wasm_bindgen(js_hash)]`.
+    ///
+    /// The hasher uses fixed keys so the same value always hashes the same
+    /// way, even across instances of the module.
+    #[cfg(feature = "std")]
+    pub fn hash_code<T: std::hash::Hash + ?Sized>(value: &T) -> u32 {
+        use std::hash::Hasher;
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish() as u32
+    }
+
+    /// Serializes `value` for the `toJSON` method of structs exported with
+    /// `#[wasm_bindgen(json)]`, throwing if it fails.
+    #[cfg(feature = "serde-serialize")]
+    pub fn to_json<T: ::serde::ser::Serialize + ?Sized>(value: &T) -> super::JsValue {
+        match super::JsValue::from_serde(value) {
+            Ok(value) => value,
+            Err(e) => super::throw_str(&std::string::ToString::to_string(&e)),
+        }
+    }
+
+    /// Deserializes `value` for the `fromJSON` function of structs exported
+    /// with `#[wasm_bindgen(json)]`, throwing if it fails.
+    #[cfg(feature = "serde-serialize")]
+    pub fn from_json<T: for<'a> ::serde::de::Deserialize<'a>>(value: &super::JsValue) -> T {
+        match value.into_serde() {
+            Ok(value) => value,
+            Err(e) => super::throw_str(&std::string::ToString::to_string(&e)),
+        }
+    }
+
     /// A vendored version of `RefCell` from the standard library.
     ///
     /// Now why, you may ask, would we do that? Surely `RefCell` in libstd is
@@ -893,11 +1110,12 @@ pub mod __rt {
 
     if_std! {
         use std::alloc::{alloc, dealloc, Layout};
-        use std::mem;
 
         #[no_mangle]
         pub extern "C" fn __wbindgen_malloc(size: usize) -> *mut u8 {
-            let align = mem::align_of::<usize>();
+            // Slices passed from JS are allocated here, and are aligned so
+            // they can be loaded with SIMD instructions.
+            let align = ::strided::SLICE_ALIGN;
             if let Ok(layout) = Layout::from_size_align(size, align) {
                 unsafe {
                     if layout.size() > 0 {
@@ -925,7 +1143,7 @@ pub mod __rt {
             if size == 0 {
                 return
             }
-            let align = mem::align_of::<usize>();
+            let align = ::strided::SLICE_ALIGN;
             let layout = Layout::from_size_align_unchecked(size, align);
             dealloc(ptr, layout);
         }
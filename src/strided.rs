@@ -0,0 +1,221 @@
+//! Views of two-dimensional data, like images or matrices, laid out in rows
+//! which are a fixed number of elements apart.
+//!
+//! Data in JS is often a region of a larger buffer, like a rectangle of an
+//! image's pixels, whose rows aren't next to each other. Rather than copying
+//! the rows into a compact buffer in JS, the whole span from the start of the
+//! first row to the end of the last one can be passed as a slice, along with
+//! the shape of the region, and viewed with `Strided` on the Rust side:
+//!
+//! ```rust,no_run
+//! use wasm_bindgen::prelude::*;
+//! use wasm_bindgen::strided::Strided;
+//!
+//! #[wasm_bindgen]
+//! pub fn sum(data: &[f32], rows: usize, cols: usize, stride: usize) -> f32 {
+//!     let view = Strided::new(data, rows, cols, stride).expect_throw("invalid shape");
+//!     view.rows().map(|row| row.iter().sum::<f32>()).sum()
+//! }
+//! ```
+//!
+//! Slices passed from JS are always aligned to 16 bytes, so the first row can
+//! be loaded with SIMD instructions, as can every other row if the stride is
+//! a multiple of 16 bytes too.
+
+/// The alignment in bytes of the memory that slices passed from JS are
+/// copied into.
+pub const SLICE_ALIGN: usize = 16;
+
+/// Returns how many elements a strided view of this shape spans, or `None`
+/// if the shape doesn't make sense.
+fn span(rows: usize, cols: usize, stride: usize) -> Option<usize> {
+    if rows == 0 {
+        return Some(0);
+    }
+    if rows > 1 && cols > stride {
+        return None;
+    }
+    (rows - 1).checked_mul(stride)?.checked_add(cols)
+}
+
+/// A view of `rows` rows of `cols` elements each in a slice, where each row
+/// starts `stride` elements after the previous one.
+#[derive(Debug)]
+pub struct Strided<'a, T: 'a> {
+    data: &'a [T],
+    rows: usize,
+    cols: usize,
+    stride: usize,
+}
+
+impl<'a, T> Strided<'a, T> {
+    /// Creates a view of `data` of the given shape.
+    ///
+    /// Returns `None` if rows would overlap, with `cols` larger than
+    /// `stride`, or if `data` is too short to hold all the rows. The last row
+    /// doesn't need the padding between rows after it.
+    pub fn new(data: &'a [T], rows: usize, cols: usize, stride: usize) -> Option<Self> {
+        if span(rows, cols, stride)? > data.len() {
+            return None;
+        }
+        Some(Strided {
+            data,
+            rows,
+            cols,
+            stride,
+        })
+    }
+
+    /// Returns the number of rows.
+    pub fn height(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the number of elements in each row.
+    pub fn width(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the number of elements from the start of one row to the start
+    /// of the next one.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Returns the element in column `col` of row `row`, or `None` if it's
+    /// outside of the view.
+    pub fn get(&self, row: usize, col: usize) -> Option<&'a T> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        Some(&self.data[row * self.stride + col])
+    }
+
+    /// Returns row `row`, or `None` if there are fewer rows.
+    pub fn row(&self, row: usize) -> Option<&'a [T]> {
+        if row >= self.rows {
+            return None;
+        }
+        let start = row * self.stride;
+        Some(&self.data[start..start + self.cols])
+    }
+
+    /// Returns an iterator over the rows.
+    pub fn rows(&self) -> Rows<'a, T> {
+        Rows {
+            view: *self,
+            next: 0,
+        }
+    }
+}
+
+impl<'a, T> Clone for Strided<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Strided<'a, T> {}
+
+if_std! {
+    use std::vec::Vec;
+
+    impl<'a, T: Clone> Strided<'a, T> {
+        /// Copies the rows into a `Vec` with no space between them, which
+        /// can be returned to JS as a typed array.
+        pub fn to_vec(&self) -> Vec<T> {
+            let mut dst = Vec::with_capacity(self.rows * self.cols);
+            for row in self.rows() {
+                dst.extend_from_slice(row);
+            }
+            dst
+        }
+    }
+}
+
+/// An iterator over the rows of a `Strided` view.
+#[derive(Debug)]
+pub struct Rows<'a, T: 'a> {
+    view: Strided<'a, T>,
+    next: usize,
+}
+
+impl<'a, T> Iterator for Rows<'a, T> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<&'a [T]> {
+        let row = self.view.row(self.next)?;
+        self.next += 1;
+        Some(row)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.view.rows - self.next;
+        (left, Some(left))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Rows<'a, T> {}
+
+/// A mutable version of `Strided`, for writing the results of a computation
+/// into a region of a buffer passed as `&mut [T]`, which is copied back into
+/// the JS typed array it came from.
+#[derive(Debug)]
+pub struct StridedMut<'a, T: 'a> {
+    data: &'a mut [T],
+    rows: usize,
+    cols: usize,
+    stride: usize,
+}
+
+impl<'a, T> StridedMut<'a, T> {
+    /// Creates a mutable view of `data` of the given shape, returning `None`
+    /// under the same conditions as `Strided::new`.
+    pub fn new(data: &'a mut [T], rows: usize, cols: usize, stride: usize) -> Option<Self> {
+        if span(rows, cols, stride)? > data.len() {
+            return None;
+        }
+        Some(StridedMut {
+            data,
+            rows,
+            cols,
+            stride,
+        })
+    }
+
+    /// Returns an immutable view of the same data.
+    pub fn as_strided(&self) -> Strided<T> {
+        Strided {
+            data: self.data,
+            rows: self.rows,
+            cols: self.cols,
+            stride: self.stride,
+        }
+    }
+
+    /// Returns the element in column `col` of row `row` mutably, or `None`
+    /// if it's outside of the view.
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        Some(&mut self.data[row * self.stride + col])
+    }
+
+    /// Returns row `row` mutably, or `None` if there are fewer rows.
+    pub fn row_mut(&mut self, row: usize) -> Option<&mut [T]> {
+        if row >= self.rows {
+            return None;
+        }
+        let start = row * self.stride;
+        Some(&mut self.data[start..start + self.cols])
+    }
+
+    /// Calls `f` with each row in turn.
+    pub fn for_each_row<F: FnMut(usize, &mut [T])>(&mut self, mut f: F) {
+        for row in 0..self.rows {
+            let start = row * self.stride;
+            f(row, &mut self.data[start..start + self.cols]);
+        }
+    }
+}
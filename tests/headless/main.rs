@@ -8,6 +8,8 @@ use wasm_bindgen_test::*;
 
 wasm_bindgen_test_configure!(run_in_browser);
 
+pub mod snippets;
+
 #[wasm_bindgen]
 pub struct ConsumeRetString;
 
@@ -0,0 +1,14 @@
+export function local_add(a, b) {
+    return a + b;
+}
+
+export class LocalCounter {
+    constructor(start) {
+        this.count = start;
+    }
+
+    increment() {
+        this.count += 1;
+        return this.count;
+    }
+}
@@ -0,0 +1,54 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen(module = "/tests/headless/snippets.js")]
+extern "C" {
+    fn local_add(a: u32, b: u32) -> u32;
+
+    type LocalCounter;
+    #[wasm_bindgen(constructor)]
+    fn new(start: u32) -> LocalCounter;
+    #[wasm_bindgen(method)]
+    fn increment(this: &LocalCounter) -> u32;
+}
+
+#[wasm_bindgen(inline_js = "export function inline_add(a, b) { return a + b; }")]
+extern "C" {
+    fn inline_add(a: u32, b: u32) -> u32;
+}
+
+// The same JS written out twice is only written to one snippet.
+#[wasm_bindgen(inline_js = "export function inline_add(a, b) { return a + b; }")]
+extern "C" {
+    #[wasm_bindgen(js_name = inline_add)]
+    fn inline_add_again(a: u32, b: u32) -> u32;
+}
+
+#[wasm_bindgen(inline_js = "
+    export class InlineGreeter {
+        constructor(name) { this.name = name; }
+        greet() { return `hello ${this.name}`; }
+    }
+")]
+extern "C" {
+    type InlineGreeter;
+    #[wasm_bindgen(constructor)]
+    fn new(name: &str) -> InlineGreeter;
+    #[wasm_bindgen(method)]
+    fn greet(this: &InlineGreeter) -> String;
+}
+
+#[wasm_bindgen_test]
+fn local_module() {
+    assert_eq!(local_add(1, 2), 3);
+    let counter = LocalCounter::new(3);
+    assert_eq!(counter.increment(), 4);
+    assert_eq!(counter.increment(), 5);
+}
+
+#[wasm_bindgen_test]
+fn inline_js() {
+    assert_eq!(inline_add(1, 2), 3);
+    assert_eq!(inline_add_again(2, 3), 5);
+    assert_eq!(InlineGreeter::new("snippets").greet(), "hello snippets");
+}
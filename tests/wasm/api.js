@@ -5,6 +5,18 @@ exports.assert_null = x => {
     assert.strictEqual(x, null);
 };
 
+exports.assert_global = x => {
+    assert.strictEqual(x, global);
+};
+
+exports.use_fixed_host_sources = () => {
+    wasm.setHostSources({ now: () => 1234, random: () => 0.5 });
+};
+
+exports.reset_host_sources = () => {
+    wasm.setHostSources({ now: null, random: null });
+};
+
 exports.js_works = () => {
     assert.strictEqual(wasm.api_foo(), 'foo');
     assert.strictEqual(wasm.api_bar('a'), 'a');
@@ -8,7 +8,10 @@ extern "C" {
     fn js_works();
     fn js_eq_works();
     fn assert_null(v: JsValue);
+    fn assert_global(v: JsValue);
     fn debug_values() -> JsValue;
+    fn use_fixed_host_sources();
+    fn reset_host_sources();
 }
 
 #[wasm_bindgen_test]
@@ -147,6 +150,33 @@ fn memory_accessor_appears_to_work() {
     assert_eq!(v, [3, 0, 0, 0]);
 }
 
+#[wasm_bindgen_test]
+fn global_accessor() {
+    assert_global(wasm_bindgen::global());
+    assert_eq!(wasm_bindgen::global(), JsValue::from(js_sys::global()));
+}
+
+#[wasm_bindgen_test]
+fn host_sources() {
+    let now = wasm_bindgen::now();
+    assert!(now > 0.0);
+    let random = wasm_bindgen::random();
+    assert!(random >= 0.0 && random < 1.0);
+    let mut buf = [0; 100_000];
+    wasm_bindgen::fill_random(&mut buf);
+    assert!(buf.iter().any(|b| *b != 0));
+
+    use_fixed_host_sources();
+    assert_eq!(wasm_bindgen::now(), 1234.0);
+    assert_eq!(wasm_bindgen::random(), 0.5);
+    let mut buf = [0; 4];
+    wasm_bindgen::fill_random(&mut buf);
+    assert_eq!(buf, [128; 4]);
+
+    reset_host_sources();
+    assert!(wasm_bindgen::now() >= now);
+}
+
 #[wasm_bindgen_test]
 fn debug_output() {
     let test_iter = debug_values()
@@ -171,3 +201,23 @@ fn debug_output() {
         assert_eq!(format!("{:?}", test.unwrap()), expected);
     }
 }
+
+#[wasm_bindgen_test]
+fn json_strings() {
+    let val = JsValue::from_json_str(r#"{"a":[1,"two",null],"b":true}"#).unwrap();
+    assert!(val.is_object());
+    assert_eq!(
+        val.to_json_string().unwrap(),
+        r#"{"a":[1,"two",null],"b":true}"#
+    );
+    assert_eq!(JsValue::from("a\"b").to_json_string().unwrap(), r#""a\"b""#);
+    assert_eq!(JsValue::from_json_str("3").unwrap(), JsValue::from(3));
+
+    let err = JsValue::from_json_str("{").unwrap_err();
+    assert!(err.is_instance_of::<js_sys::SyntaxError>());
+    let err = JsValue::UNDEFINED.to_json_string().unwrap_err();
+    assert!(err.is_instance_of::<js_sys::TypeError>());
+    let cyclic = js_sys::Object::new();
+    js_sys::Reflect::set(&cyclic, &"self".into(), &cyclic).unwrap();
+    assert!(JsValue::from(cyclic).to_json_string().is_err());
+}
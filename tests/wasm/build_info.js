@@ -0,0 +1,15 @@
+const wasm = require('wasm-bindgen-test.js');
+const assert = require('assert');
+
+exports.js_works = () => {
+    const info = wasm.buildInfo();
+    assert.strictEqual(Object.getPrototypeOf(info), Object.prototype);
+    assert.deepStrictEqual(
+        Object.keys(info),
+        ['name', 'version', 'gitHash', 'features', 'wasmBindgenVersion'],
+    );
+    assert.strictEqual(info.name, 'wasm-bindgen');
+    assert.strictEqual(info.version, info.wasmBindgenVersion);
+    assert.strictEqual(info.gitHash, '0123abcd');
+    assert.deepStrictEqual(info.features, ['std']);
+};
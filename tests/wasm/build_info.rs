@@ -0,0 +1,17 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+build_info!(
+    git_hash = "0123abcd",
+    features = ["std", "not-a-feature"],
+);
+
+#[wasm_bindgen(module = "tests/wasm/build_info.js")]
+extern "C" {
+    fn js_works();
+}
+
+#[wasm_bindgen_test]
+fn works() {
+    js_works();
+}
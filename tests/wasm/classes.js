@@ -122,6 +122,38 @@ exports.js_readonly_fields = () => {
     a.free();
 };
 
+exports.js_validated_fields = () => {
+    const a = wasm.Validated.new();
+    assert.strictEqual(a.percent, 0);
+    a.percent = 100;
+    assert.strictEqual(a.percent, 100);
+    assert.throws(() => { a.percent = 101; }, RangeError);
+    assert.strictEqual(a.percent, 100);
+    a.free();
+};
+
+exports.js_field_attributes = () => {
+    const a = wasm.FieldAttributes.new();
+    assert.strictEqual(a.fooBar, 0);
+    a.fooBar = 3;
+    assert.strictEqual(a.fooBar, 3);
+    assert.strictEqual(a.foo_bar, undefined);
+
+    assert.strictEqual(a.name, '');
+    a.name = 'wasm';
+    assert.strictEqual(a.name, 'wasm');
+    assert.strictEqual(a.name, 'wasm');
+
+    assert.strictEqual(a.version, 2);
+    a.version = 3;
+    assert.strictEqual(a.version, 2);
+
+    a.secret = 4;
+    assert.strictEqual(a.secret, undefined);
+    assert.strictEqual(a.secret_value(), 4);
+    a.free();
+};
+
 exports.js_double_consume = () => {
     const r = new wasm.DoubleConsume();
     assert.throws(() => r.consume(r));
@@ -165,5 +197,118 @@ exports.js_test_option_classes = () => {
   wasm.option_class_assert_none(null);
   const c = wasm.option_class_some();
   assert.ok(c instanceof wasm.OptionClass);
+  assert.strictEqual(wasm.option_class_ref(undefined), undefined);
+  assert.strictEqual(wasm.option_class_ref(null), undefined);
+  assert.strictEqual(wasm.option_class_ref(c), 3);
+  // Borrowing doesn't take ownership
+  assert.strictEqual(wasm.option_class_ref(c), 3);
   wasm.option_class_assert_some(c);
 };
+
+exports.js_tostring = () => {
+  const p = new wasm.ClassesPoint(1, -2);
+  assert.strictEqual(p.toString(), '(1, -2)');
+  assert.strictEqual(`${p}`, '(1, -2)');
+  assert.strictEqual(require('util').inspect(p), '(1, -2)');
+  p.free();
+};
+
+exports.js_equals_and_hash = () => {
+  const a = new wasm.ClassesKey('a');
+  const a2 = new wasm.ClassesKey('a');
+  const b = new wasm.ClassesKey('b');
+  assert.ok(a !== a2);
+  assert.strictEqual(a.equals(a2), true);
+  assert.strictEqual(a.equals(b), false);
+  assert.strictEqual(a.hashCode(), a2.hashCode());
+  assert.strictEqual(typeof a.hashCode(), 'number');
+  a.free();
+  a2.free();
+  b.free();
+};
+
+exports.js_enumerable = () => {
+  const size = new wasm.ClassesSize(2, 3);
+  assert.deepStrictEqual(Object.keys(size).sort(), ['area', 'height', 'width']);
+  assert.deepStrictEqual({ ...size }, { width: 2, height: 3, area: 6 });
+  size.width = 4;
+  assert.strictEqual(size.area, 12);
+
+  const doubled = size.doubled();
+  assert.deepStrictEqual({ ...doubled }, { width: 8, height: 6, area: 48 });
+  size.free();
+  doubled.free();
+};
+
+exports.js_frozen = () => {
+  'use strict';
+  const color = new wasm.ClassesColor(255, 128, 0);
+  assert.ok(Object.isFrozen(color));
+  assert.strictEqual(color.free, undefined);
+  assert.throws(() => { color.r = 0; }, TypeError);
+  assert.throws(() => { color.ptr = 0; }, TypeError);
+  assert.throws(() => { color.hex = '#f80'; }, TypeError);
+  assert.strictEqual(color.r, 255);
+
+  const inverted = color.inverted();
+  assert.ok(Object.isFrozen(inverted));
+  assert.deepStrictEqual([inverted.r, inverted.g, inverted.b], [0, 127, 255]);
+  assert.ok(inverted.inverted().eq(color));
+};
+
+exports.js_typed_errors = () => {
+  const percent = new wasm.ClassesPercent(42);
+  assert.strictEqual(percent.value(), 42);
+  percent.free();
+
+  let err = null;
+  try {
+    new wasm.ClassesPercent(101);
+  } catch (e) {
+    err = e;
+  }
+  assert.ok(err instanceof wasm.ClassesRangeError);
+  assert.strictEqual(err.min(), 0);
+  assert.strictEqual(err.max(), 100);
+  err.free();
+
+  assert.strictEqual(wasm.classes_parse_percent('7%'), 7);
+  assert.throws(() => wasm.classes_parse_percent('700%'), wasm.ClassesRangeError);
+};
+
+exports.js_generic_structs = () => {
+  const floats = new wasm.ClassesGridF32(2, 2);
+  floats.set(1, 1, 0.5);
+  assert.strictEqual(floats.get(1, 1), 0.5);
+  assert.ok(floats.cells() instanceof Float32Array);
+  floats.fill = 1.5;
+  floats.clear();
+  assert.deepStrictEqual(Array.from(floats.cells()), [1.5, 1.5, 1.5, 1.5]);
+  const copy = floats.copy();
+  assert.ok(copy instanceof wasm.ClassesGridF32);
+  assert.strictEqual(copy.width, 2);
+  copy.free();
+  floats.free();
+
+  const bytes = new wasm.ClassesGridU8(3, 1);
+  assert.ok(!(bytes instanceof wasm.ClassesGridF32));
+  bytes.set(0, 0, 255);
+  bytes.set(2, 0, 1);
+  assert.ok(bytes.cells() instanceof Uint8Array);
+  assert.strictEqual(wasm.classes_grid_u8_sum(bytes), 256);
+  bytes.free();
+};
+
+exports.js_json = () => {
+  const settings = new wasm.ClassesSettings(7, 'dark');
+  assert.deepStrictEqual(settings.toJSON(), { volume: 7, theme: 'dark' });
+  const json = JSON.stringify({ settings });
+  assert.strictEqual(json, '{"settings":{"volume":7,"theme":"dark"}}');
+
+  const restored = wasm.ClassesSettings.fromJSON(JSON.parse(json).settings);
+  assert.ok(restored instanceof wasm.ClassesSettings);
+  assert.strictEqual(restored.volume, 7);
+  assert.throws(() => wasm.ClassesSettings.fromJSON({ volume: 'loud' }));
+  settings.free();
+  restored.free();
+};
@@ -18,6 +18,8 @@ extern "C" {
     fn js_public_fields();
     fn js_using_self();
     fn js_readonly_fields();
+    fn js_validated_fields();
+    fn js_field_attributes();
     fn js_double_consume();
     fn js_js_rename();
     fn js_access_fields();
@@ -30,6 +32,12 @@ extern "C" {
     fn js_return_none2() -> Option<OptionClass>;
     fn js_return_some(a: OptionClass) -> Option<OptionClass>;
     fn js_test_option_classes();
+    fn js_tostring();
+    fn js_equals_and_hash();
+    fn js_enumerable();
+    fn js_frozen();
+    fn js_typed_errors();
+    fn js_generic_structs();
 }
 
 #[wasm_bindgen_test]
@@ -317,6 +325,66 @@ impl Readonly {
     }
 }
 
+#[wasm_bindgen_test]
+fn validated_fields() {
+    js_validated_fields();
+}
+
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Validated {
+    #[wasm_bindgen(accessor, validate = "check_percent")]
+    percent: u32,
+}
+
+#[wasm_bindgen]
+impl Validated {
+    pub fn new() -> Validated {
+        Validated::default()
+    }
+}
+
+fn check_percent(percent: &u32) -> Result<(), js_sys::RangeError> {
+    if *percent > 100 {
+        return Err(js_sys::RangeError::new("percent must be at most 100"));
+    }
+    Ok(())
+}
+
+#[wasm_bindgen_test]
+fn field_attributes() {
+    js_field_attributes();
+}
+
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct FieldAttributes {
+    #[wasm_bindgen(js_name = "fooBar")]
+    pub foo_bar: u32,
+    #[wasm_bindgen(getter_with_clone)]
+    pub name: String,
+    #[wasm_bindgen(skip_setter)]
+    pub version: u32,
+    #[wasm_bindgen(skip_getter)]
+    pub secret: u32,
+    #[wasm_bindgen(skip)]
+    pub internal: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl FieldAttributes {
+    pub fn new() -> FieldAttributes {
+        FieldAttributes {
+            version: 2,
+            ..FieldAttributes::default()
+        }
+    }
+
+    pub fn secret_value(&self) -> u32 {
+        self.secret
+    }
+}
+
 #[wasm_bindgen_test]
 fn double_consume() {
     js_double_consume();
@@ -459,3 +527,247 @@ pub fn option_class_assert_none(x: Option<OptionClass>) {
 pub fn option_class_assert_some(x: Option<OptionClass>) {
     assert_eq!(x.unwrap().0, 3);
 }
+
+#[wasm_bindgen]
+pub fn option_class_ref(x: Option<&OptionClass>) -> Option<u32> {
+    x.map(|x| x.0)
+}
+
+#[wasm_bindgen(js_tostring)]
+pub struct ClassesPoint {
+    x: i32,
+    y: i32,
+}
+
+#[wasm_bindgen]
+impl ClassesPoint {
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: i32, y: i32) -> ClassesPoint {
+        ClassesPoint { x, y }
+    }
+}
+
+impl std::fmt::Display for ClassesPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+#[wasm_bindgen_test]
+fn tostring() {
+    js_tostring();
+}
+
+#[wasm_bindgen(js_equals, js_hash)]
+#[derive(PartialEq, Hash)]
+pub struct ClassesKey {
+    name: String,
+}
+
+#[wasm_bindgen]
+impl ClassesKey {
+    #[wasm_bindgen(constructor)]
+    pub fn new(name: String) -> ClassesKey {
+        ClassesKey { name }
+    }
+}
+
+#[wasm_bindgen_test]
+fn equals_and_hash() {
+    js_equals_and_hash();
+}
+
+#[wasm_bindgen(enumerable)]
+pub struct ClassesSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+#[wasm_bindgen]
+impl ClassesSize {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: u32, height: u32) -> ClassesSize {
+        ClassesSize { width, height }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn area(&self) -> u32 {
+        self.width * self.height
+    }
+
+    pub fn doubled(&self) -> ClassesSize {
+        ClassesSize::new(self.width * 2, self.height * 2)
+    }
+}
+
+#[wasm_bindgen_test]
+fn enumerable() {
+    js_enumerable();
+}
+
+#[wasm_bindgen(frozen)]
+pub struct ClassesColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+#[wasm_bindgen]
+impl ClassesColor {
+    #[wasm_bindgen(constructor)]
+    pub fn new(r: u8, g: u8, b: u8) -> ClassesColor {
+        ClassesColor { r, g, b }
+    }
+
+    pub fn inverted(&self) -> ClassesColor {
+        ClassesColor::new(255 - self.r, 255 - self.g, 255 - self.b)
+    }
+
+    pub fn eq(&self, other: &ClassesColor) -> bool {
+        (self.r, self.g, self.b) == (other.r, other.g, other.b)
+    }
+}
+
+#[wasm_bindgen_test]
+fn frozen() {
+    js_frozen();
+}
+
+#[wasm_bindgen]
+pub struct ClassesRangeError {
+    min: u32,
+    max: u32,
+}
+
+#[wasm_bindgen]
+impl ClassesRangeError {
+    pub fn min(&self) -> u32 {
+        self.min
+    }
+
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+}
+
+#[wasm_bindgen]
+pub struct ClassesPercent(u32);
+
+#[wasm_bindgen]
+impl ClassesPercent {
+    #[wasm_bindgen(constructor)]
+    pub fn new(value: u32) -> Result<ClassesPercent, ClassesRangeError> {
+        if value > 100 {
+            return Err(ClassesRangeError { min: 0, max: 100 });
+        }
+        Ok(ClassesPercent(value))
+    }
+
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+#[wasm_bindgen]
+pub fn classes_parse_percent(s: &str) -> Result<u32, ClassesRangeError> {
+    match s.trim_end_matches('%').parse() {
+        Ok(n) if n <= 100 => Ok(n),
+        _ => Err(ClassesRangeError { min: 0, max: 100 }),
+    }
+}
+
+#[wasm_bindgen_test]
+fn typed_errors() {
+    js_typed_errors();
+}
+
+#[wasm_bindgen(instantiate(f32 = "ClassesGridF32", u8 = "ClassesGridU8"))]
+pub struct ClassesGrid<T> {
+    pub width: u32,
+    pub fill: T,
+    cells: Vec<T>,
+}
+
+#[wasm_bindgen(instantiate(f32 = "ClassesGridF32", u8 = "ClassesGridU8"))]
+impl<T: Copy + Default> ClassesGrid<T> {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: u32, height: u32) -> ClassesGrid<T> {
+        ClassesGrid {
+            width,
+            fill: T::default(),
+            cells: vec![T::default(); (width * height) as usize],
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> T {
+        self.cells[(y * self.width + x) as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, value: T) {
+        self.cells[(y * self.width + x) as usize] = value;
+    }
+
+    pub fn clear(&mut self) {
+        for cell in self.cells.iter_mut() {
+            *cell = self.fill;
+        }
+    }
+
+    pub fn cells(&self) -> Vec<T> {
+        self.cells.clone()
+    }
+
+    pub fn copy(&self) -> Self {
+        ClassesGrid {
+            width: self.width,
+            fill: self.fill,
+            cells: self.cells.clone(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn classes_grid_u8_sum(grid: &ClassesGridU8) -> u32 {
+    grid.cells.iter().map(|&c| u32::from(c)).sum()
+}
+
+#[wasm_bindgen_test]
+fn generic_structs() {
+    js_generic_structs();
+}
+
+#[cfg(feature = "serde-serialize")]
+mod json {
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen(module = "tests/wasm/classes.js")]
+    extern "C" {
+        fn js_json();
+    }
+
+    #[wasm_bindgen(json)]
+    #[derive(Serialize, Deserialize)]
+    pub struct ClassesSettings {
+        volume: u32,
+        theme: String,
+    }
+
+    #[wasm_bindgen]
+    impl ClassesSettings {
+        #[wasm_bindgen(constructor)]
+        pub fn new(volume: u32, theme: String) -> ClassesSettings {
+            ClassesSettings { volume, theme }
+        }
+
+        #[wasm_bindgen(getter)]
+        pub fn volume(&self) -> u32 {
+            self.volume
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn json() {
+        js_json();
+    }
+}
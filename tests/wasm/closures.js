@@ -102,3 +102,5 @@ exports.drop_during_call_call = () => DROP_DURING_CALL();
 exports.js_test_closure_returner = () => {
   wasm.closure_returner().someKey();
 };
+
+exports.with_this_call = a => a.call({ n: 4 }, 2);
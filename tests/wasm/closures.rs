@@ -65,6 +65,8 @@ extern "C" {
     fn drop_during_call_call();
 
     fn js_test_closure_returner();
+
+    fn with_this_call(a: &Closure<Fn(JsValue, u32) -> u32>) -> u32;
 }
 
 #[wasm_bindgen_test]
@@ -318,3 +320,12 @@ fn test_closure_returner() {
         Ok(o)
     }
 }
+
+#[wasm_bindgen_test]
+fn with_this() {
+    let a = Closure::wrap_with_this(Box::new(|this: JsValue, b: u32| {
+        let n = js_sys::Reflect::get(&this, &"n".into()).unwrap();
+        n.as_f64().unwrap() as u32 + b
+    }) as Box<Fn(JsValue, u32) -> u32>);
+    assert_eq!(with_this_call(&a), 6);
+}
@@ -0,0 +1,11 @@
+const wasm = require('wasm-bindgen-test.js');
+const assert = require('assert');
+
+exports.js_identity = a => a;
+
+exports.js_works = () => {
+    assert.strictEqual(wasm.custom_abi_greet('world'), 'hello world');
+    assert.strictEqual(wasm.custom_abi_maybe('a'), 'a');
+    assert.strictEqual(wasm.custom_abi_maybe(undefined), undefined);
+    assert.strictEqual(wasm.custom_abi_double(1.5), 3);
+};
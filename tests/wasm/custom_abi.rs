@@ -0,0 +1,63 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen(module = "tests/wasm/custom_abi.js")]
+extern "C" {
+    fn js_works();
+    fn js_identity(name: Name) -> Name;
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Name(String);
+
+impl From<Name> for String {
+    fn from(n: Name) -> String {
+        n.0
+    }
+}
+
+impl From<String> for Name {
+    fn from(s: String) -> Name {
+        Name(s)
+    }
+}
+
+custom_abi!(Name as String, optional);
+
+#[derive(Debug, PartialEq)]
+pub struct Meters(f64);
+
+impl From<Meters> for f64 {
+    fn from(m: Meters) -> f64 {
+        m.0
+    }
+}
+
+impl From<f64> for Meters {
+    fn from(f: f64) -> Meters {
+        Meters(f)
+    }
+}
+
+custom_abi!(Meters as f64);
+
+#[wasm_bindgen]
+pub fn custom_abi_greet(name: Name) -> Name {
+    Name(format!("hello {}", name.0))
+}
+
+#[wasm_bindgen]
+pub fn custom_abi_maybe(name: Option<Name>) -> Option<Name> {
+    name
+}
+
+#[wasm_bindgen]
+pub fn custom_abi_double(m: Meters) -> Meters {
+    Meters(m.0 * 2.0)
+}
+
+#[wasm_bindgen_test]
+fn works() {
+    js_works();
+    assert_eq!(js_identity(Name("x".to_string())), Name("x".to_string()));
+}
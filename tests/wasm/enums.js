@@ -28,3 +28,98 @@ exports.js_expect_enum = (a, b) => {
 exports.js_expect_enum_none = a => {
   assert.strictEqual(a, undefined);
 };
+
+exports.js_enum_with_expression_values = () => {
+    assert.strictEqual(wasm.Arithmetic.A, 4);
+    assert.strictEqual(wasm.Arithmetic.B, 17);
+    assert.strictEqual(wasm.Arithmetic.C, 39);
+
+    assert.strictEqual(wasm.FromConstants.A, 100);
+    assert.strictEqual(wasm.FromConstants.B, 101);
+    assert.strictEqual(wasm.FromConstants.C, 7);
+    assert.strictEqual(wasm.from_constants_cycle(wasm.FromConstants.B), wasm.FromConstants.C);
+    assert.strictEqual(wasm.from_constants_identity(wasm.FromConstants.A), wasm.FromConstants.A);
+    assert.strictEqual(wasm.from_constants_identity(undefined), undefined);
+};
+
+exports.js_enum_with_reprs = () => {
+    assert.strictEqual(wasm.Signed.A, -2);
+    assert.strictEqual(wasm.Signed.B, -1);
+    assert.strictEqual(wasm.Signed.C, 2147483647);
+    assert.strictEqual(wasm.signed_cycle(wasm.Signed.A), wasm.Signed.B);
+    assert.strictEqual(wasm.signed_cycle(wasm.Signed.C), wasm.Signed.A);
+    assert.strictEqual(wasm.signed_identity(wasm.Signed.B), wasm.Signed.B);
+    assert.strictEqual(wasm.signed_identity(undefined), undefined);
+
+    assert.strictEqual(wasm.Wide.Small, BigInt(-1));
+    assert.strictEqual(wasm.Wide.Large, BigInt(1099511627776));
+    assert.strictEqual(wasm.wide_cycle(wasm.Wide.Small), wasm.Wide.Large);
+    assert.strictEqual(wasm.WideUnsigned.Max, BigInt('18446744073709551615'));
+};
+
+exports.js_enum_methods = () => {
+    assert.strictEqual(wasm.Color.label(wasm.Color.Red), 'red');
+    assert.strictEqual(wasm.Color.next(wasm.Color.Red), wasm.Color.Green);
+    const color = wasm.Color.Green;
+    assert.strictEqual(wasm.Color.advance(color), 'yellow');
+    assert.strictEqual(color, wasm.Color.Green);
+    assert.strictEqual(wasm.Color.from_label('yellow'), wasm.Color.Yellow);
+    assert.strictEqual(wasm.Color.from_label('blue'), undefined);
+    assert.deepStrictEqual(Object.keys(wasm.Color), ['Green', 'Yellow', 'Red']);
+
+    assert.strictEqual(wasm.Wide.is_small(wasm.Wide.Small), true);
+    assert.strictEqual(wasm.Wide.is_small(wasm.Wide.Large), false);
+};
+
+exports.js_flags_enum = () => {
+    const { Permissions } = wasm;
+    assert.strictEqual(Permissions.Read, 1);
+    assert.strictEqual(Permissions.Write, 2);
+    assert.strictEqual(Permissions.Execute, 4);
+    assert.deepStrictEqual(Object.keys(Permissions), ['Read', 'Write', 'Execute']);
+
+    const readWrite = Permissions.combine(Permissions.Read, Permissions.Write);
+    assert.strictEqual(readWrite, 3);
+    assert.strictEqual(Permissions.combine(), 0);
+    assert.strictEqual(Permissions.contains(readWrite, Permissions.Write), true);
+    assert.strictEqual(Permissions.contains(readWrite, Permissions.Execute), false);
+    assert.strictEqual(Permissions.contains(readWrite, readWrite), true);
+    assert.strictEqual(wasm.can_write(readWrite), true);
+    assert.strictEqual(wasm.can_write(Permissions.Read), false);
+};
+
+exports.js_request_mode = value => value;
+exports.js_request_mode_value = mode => {
+    assert.strictEqual(typeof mode, 'string');
+    return mode;
+};
+exports.js_exported_string_enum = () => {
+    assert.strictEqual(wasm.request_mode_round_trip('same-origin'), 'no-cors');
+    assert.strictEqual(wasm.request_mode_round_trip('navigate'), 'navigate');
+};
+exports.js_level = value => value;
+exports.js_level_value = level => {
+    assert.strictEqual(typeof level, 'number');
+    return level;
+};
+
+exports.js_tagged_union = () => {
+    assert.strictEqual(wasm.shape_area({ tag: 'Circle', value: 2 }), 12);
+    assert.strictEqual(wasm.shape_area({ tag: 'Label', value: 'abcd' }), 4);
+    assert.strictEqual(wasm.shape_area({ tag: 'Empty' }), 0);
+    assert.throws(() => wasm.shape_area({ tag: 'Square', value: 1 }), /Square/);
+
+    assert.deepStrictEqual(wasm.shape_circle(1.5), { tag: 'Circle', value: 1.5 });
+    assert.deepStrictEqual(wasm.shape_label('x'), { tag: 'Label', value: 'x' });
+    const at = wasm.shape_at(4, 5);
+    assert.strictEqual(at.tag, 'At');
+    assert.ok(at.value instanceof wasm.Point);
+    assert.strictEqual(at.value.x, 4);
+    assert.strictEqual(at.value.y, 5);
+    assert.strictEqual(wasm.shape_area(at), 20);
+    assert.deepStrictEqual(wasm.shape_empty(), { tag: 'Empty' });
+    assert.strictEqual(wasm.shape_none(undefined), true);
+    assert.strictEqual(wasm.shape_none({ tag: 'Empty' }), false);
+};
+
+exports.js_shape_area = shape => wasm.shape_area(shape);
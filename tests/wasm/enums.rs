@@ -9,6 +9,17 @@ extern "C" {
     fn js_handle_optional_enums(x: Option<Color>) -> Option<Color>;
     fn js_expect_enum(x: Color, y: Option<Color>);
     fn js_expect_enum_none(x: Option<Color>);
+    fn js_enum_with_expression_values();
+    fn js_enum_with_reprs();
+    fn js_enum_methods();
+    fn js_flags_enum();
+    fn js_request_mode(value: &str) -> RequestMode;
+    fn js_request_mode_value(mode: RequestMode) -> String;
+    fn js_level(value: f64) -> Level;
+    fn js_level_value(level: Level) -> f64;
+    fn js_exported_string_enum();
+    fn js_tagged_union();
+    fn js_shape_area(shape: Shape) -> f64;
 }
 
 #[wasm_bindgen]
@@ -19,6 +30,39 @@ pub enum Color {
     Red,
 }
 
+#[wasm_bindgen]
+impl Color {
+    pub fn label(&self) -> String {
+        match self {
+            Color::Green => "green".to_string(),
+            Color::Yellow => "yellow".to_string(),
+            Color::Red => "red".to_string(),
+        }
+    }
+
+    pub fn next(self) -> Color {
+        enum_cycle(self)
+    }
+
+    pub fn advance(&mut self) -> String {
+        *self = match self {
+            Color::Green => Color::Yellow,
+            Color::Yellow => Color::Red,
+            Color::Red => Color::Green,
+        };
+        self.label()
+    }
+
+    pub fn from_label(label: &str) -> Option<Color> {
+        match label {
+            "green" => Some(Color::Green),
+            "yellow" => Some(Color::Yellow),
+            "red" => Some(Color::Red),
+            _ => None,
+        }
+    }
+}
+
 pub mod inner {
     use wasm_bindgen::prelude::*;
 
@@ -30,6 +74,103 @@ pub mod inner {
     }
 }
 
+const BASE: u32 = 100;
+
+#[wasm_bindgen]
+pub enum Arithmetic {
+    A = 1 + 3,
+    B = (1 << 4) | 1,
+    C = 20 * 2 - 1,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, PartialEq)]
+pub enum FromConstants {
+    A = BASE as isize,
+    B = BASE as isize + 1,
+    C = 7,
+}
+
+#[wasm_bindgen]
+pub fn from_constants_cycle(x: FromConstants) -> FromConstants {
+    match x {
+        FromConstants::A => FromConstants::B,
+        FromConstants::B => FromConstants::C,
+        FromConstants::C => FromConstants::A,
+    }
+}
+
+#[wasm_bindgen]
+pub fn from_constants_identity(x: Option<FromConstants>) -> Option<FromConstants> {
+    x
+}
+
+#[wasm_bindgen]
+#[repr(i32)]
+#[derive(Debug, PartialEq)]
+pub enum Signed {
+    A = -2,
+    B = -1,
+    C = 0x7fff_ffff,
+}
+
+#[wasm_bindgen]
+pub fn signed_cycle(x: Signed) -> Signed {
+    match x {
+        Signed::A => Signed::B,
+        Signed::B => Signed::C,
+        Signed::C => Signed::A,
+    }
+}
+
+#[wasm_bindgen]
+pub fn signed_identity(x: Option<Signed>) -> Option<Signed> {
+    x
+}
+
+#[wasm_bindgen]
+#[repr(i64)]
+pub enum Wide {
+    Small = -1,
+    Large = 1 << 40,
+}
+
+#[wasm_bindgen]
+pub fn wide_cycle(x: Wide) -> Wide {
+    match x {
+        Wide::Small => Wide::Large,
+        Wide::Large => Wide::Small,
+    }
+}
+
+#[wasm_bindgen]
+impl Wide {
+    pub fn is_small(&self) -> bool {
+        match self {
+            Wide::Small => true,
+            Wide::Large => false,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[repr(u64)]
+pub enum WideUnsigned {
+    Max = 18446744073709551615,
+}
+
+#[wasm_bindgen(flags)]
+pub enum Permissions {
+    Read = 1,
+    Write = 1 << 1,
+    Execute = 1 << 2,
+}
+
+#[wasm_bindgen]
+pub fn can_write(permissions: u32) -> bool {
+    permissions & Permissions::Write as u32 != 0
+}
+
 #[wasm_bindgen]
 pub fn enum_cycle(color: Color) -> Color {
     match color {
@@ -82,3 +223,135 @@ fn test_optional_enum_values() {
     js_expect_enum(Red, Some(Red));
     js_expect_enum_none(None);
 }
+
+#[wasm_bindgen_test]
+fn enum_with_expression_values() {
+    js_enum_with_expression_values();
+}
+
+#[wasm_bindgen_test]
+fn enum_with_reprs() {
+    js_enum_with_reprs();
+}
+
+#[wasm_bindgen_test]
+fn enum_methods() {
+    js_enum_methods();
+}
+
+#[wasm_bindgen_test]
+fn flags_enum() {
+    js_flags_enum();
+}
+
+#[wasm_bindgen]
+#[derive(PartialEq, Debug)]
+pub enum RequestMode {
+    SameOrigin = "same-origin",
+    NoCors = "no-cors",
+    Other(String),
+}
+
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Level {
+    Low = 1,
+    High = 2.5,
+    Other(f64),
+}
+
+#[wasm_bindgen_test]
+fn imported_string_enum() {
+    assert_eq!(js_request_mode("no-cors"), RequestMode::NoCors);
+    assert_eq!(
+        js_request_mode("navigate"),
+        RequestMode::Other("navigate".to_string())
+    );
+    assert_eq!(js_request_mode_value(RequestMode::SameOrigin), "same-origin");
+    assert_eq!(
+        js_request_mode_value(RequestMode::Other("cors".to_string())),
+        "cors"
+    );
+    assert_eq!(RequestMode::from_js_value(&JsValue::from(1)), None);
+}
+
+#[wasm_bindgen]
+pub fn request_mode_round_trip(mode: RequestMode) -> RequestMode {
+    match mode {
+        RequestMode::SameOrigin => RequestMode::NoCors,
+        other => other,
+    }
+}
+
+#[wasm_bindgen_test]
+fn exported_string_enum() {
+    js_exported_string_enum();
+}
+
+#[wasm_bindgen_test]
+fn imported_number_enum() {
+    assert_eq!(js_level(1.0), Level::Low);
+    assert_eq!(js_level(2.5), Level::High);
+    assert_eq!(js_level(3.0), Level::Other(3.0));
+    assert_eq!(js_level_value(Level::High), 2.5);
+    assert_eq!(js_level_value(Level::Other(-1.0)), -1.0);
+    assert_eq!(Level::from_js_value(&JsValue::from("1")), None);
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[wasm_bindgen]
+pub enum Shape {
+    Circle(f64),
+    Label(String),
+    At(Point),
+    Empty,
+}
+
+#[wasm_bindgen]
+pub fn shape_area(shape: Shape) -> f64 {
+    match shape {
+        Shape::Circle(r) => 3.0 * r * r,
+        Shape::Label(s) => s.len() as f64,
+        Shape::At(p) => p.x * p.y,
+        Shape::Empty => 0.0,
+    }
+}
+
+#[wasm_bindgen]
+pub fn shape_circle(r: f64) -> Shape {
+    Shape::Circle(r)
+}
+
+#[wasm_bindgen]
+pub fn shape_label(s: &str) -> Shape {
+    Shape::Label(s.to_string())
+}
+
+#[wasm_bindgen]
+pub fn shape_at(x: f64, y: f64) -> Shape {
+    Shape::At(Point { x, y })
+}
+
+#[wasm_bindgen]
+pub fn shape_empty() -> Option<Shape> {
+    Some(Shape::Empty)
+}
+
+#[wasm_bindgen]
+pub fn shape_none(shape: Option<Shape>) -> bool {
+    shape.is_none()
+}
+
+#[wasm_bindgen_test]
+fn tagged_union() {
+    js_tagged_union();
+    assert_eq!(js_shape_area(Shape::Circle(2.0)), 12.0);
+    assert_eq!(js_shape_area(Shape::Label("abc".to_string())), 3.0);
+    assert_eq!(js_shape_area(Shape::Empty), 0.0);
+}
@@ -140,3 +140,27 @@ exports.StaticStructural = class {
     return x + 3;
   }
 };
+
+exports.CallableClass = function(x) {
+  if (!new.target) {
+    return x * 2;
+  }
+  this.value = x + 1;
+};
+
+exports.Chart = class {
+  constructor(options) {
+    if (Object.getPrototypeOf(options) !== Object.prototype) {
+      throw new Error('expected a plain object');
+    }
+    this.options = options;
+  }
+
+  get area() {
+    return this.options.width * this.options.height;
+  }
+
+  get keys() {
+    return Object.keys(this.options).sort().join();
+  }
+};
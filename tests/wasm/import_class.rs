@@ -23,6 +23,23 @@ extern "C" {
     #[wasm_bindgen(method)]
     fn assert_internal_string(this: &Construct, s: &str);
 
+    type CallableClass;
+    #[wasm_bindgen(constructor)]
+    fn new(x: u32) -> CallableClass;
+    #[wasm_bindgen(static_method_of = CallableClass, call)]
+    fn call(x: u32) -> u32;
+    #[wasm_bindgen(method, getter)]
+    fn value(this: &CallableClass) -> u32;
+
+    #[wasm_bindgen(js_name = Chart)]
+    type MyChart;
+    #[wasm_bindgen(constructor, js_class = "Chart")]
+    fn new(options: ChartOptions) -> MyChart;
+    #[wasm_bindgen(method, getter, js_class = "Chart")]
+    fn area(this: &MyChart) -> u32;
+    #[wasm_bindgen(method, getter, js_class = "Chart")]
+    fn keys(this: &MyChart) -> String;
+
     type NewConstructors;
     #[wasm_bindgen(constructor)]
     fn new(arg: i32) -> NewConstructors;
@@ -236,3 +253,28 @@ fn catch_constructors() {
 fn static_structural() {
     assert_eq!(StaticStructural::static_structural(30), 33);
 }
+
+#[wasm_bindgen_test]
+fn callable_class() {
+    assert_eq!(CallableClass::call(3), 6);
+    assert_eq!(CallableClass::new(3).value(), 4);
+}
+
+/// doc
+#[wasm_bindgen(plain)]
+pub struct ChartOptions {
+    /// doc
+    pub width: u32,
+    /// doc
+    pub height: u32,
+}
+
+#[wasm_bindgen_test]
+fn constructor_options() {
+    let chart = MyChart::new(ChartOptions {
+        width: 2,
+        height: 3,
+    });
+    assert_eq!(chart.area(), 6);
+    assert_eq!(chart.keys(), "height,width");
+}
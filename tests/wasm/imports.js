@@ -105,3 +105,43 @@ exports.assert_dead_import_not_generated = function() {
 exports.import_inside_function_works = function() {};
 exports.import_inside_private_module = function() {};
 exports.should_call_undefined_functions = () => false;
+
+exports.this_plus = function(a) { return this.base + a; };
+exports.this_namespace = {
+  get(key) { return this[key]; },
+};
+
+let main_thread_total = 0;
+exports.main_thread_add = function(a) { main_thread_total += a; };
+exports.main_thread_total = () => main_thread_total;
+
+// Without threads the worker has an instance of its own, which posts calls of
+// `main_thread` imports to us without waiting for them. Messages arrive in
+// order, so the call has been run by the time the worker says it's done.
+exports.js_main_thread_from_worker = () => {
+  const { Worker } = require('worker_threads');
+  const total = main_thread_total;
+  const worker = new Worker(`
+    const { parentPort, workerData } = require('worker_threads');
+    const wasm = require(workerData);
+    wasm.imports_main_thread_add_on_worker(4);
+    parentPort.postMessage('done');
+  `, { eval: true, workerData: require.resolve('wasm-bindgen-test') });
+  wasm.proxyMainThreadCalls(worker);
+  return new Promise((resolve, reject) => {
+    worker.on('error', reject);
+    worker.on('message', message => {
+      if (message !== 'done') {
+        return;
+      }
+      assert.strictEqual(main_thread_total, total + 4);
+      resolve();
+    });
+  });
+};
+
+exports.STATIC_VERSION = '1.2.3';
+exports.static_counter = {
+  count: 0,
+  bump() { this.count += 1; },
+};
@@ -1,4 +1,6 @@
+use futures::Future;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
 use wasm_bindgen_test::*;
 
 #[wasm_bindgen(module = "tests/wasm/imports.js")]
@@ -30,6 +32,23 @@ extern "C" {
 
     fn underscore(_: u8);
 
+    #[wasm_bindgen(this)]
+    fn this_plus(this: &JsValue, a: u32) -> u32;
+    #[wasm_bindgen(this, js_namespace = this_namespace, js_name = get)]
+    fn this_get(this: &JsValue, key: &str) -> JsValue;
+
+    #[wasm_bindgen(main_thread)]
+    fn main_thread_add(a: u32);
+    fn main_thread_total() -> u32;
+    fn js_main_thread_from_worker() -> js_sys::Promise;
+
+    #[wasm_bindgen(static_getter, js_name = STATIC_VERSION)]
+    fn static_version() -> String;
+    #[wasm_bindgen(static_getter, js_namespace = static_counter, js_name = count)]
+    fn static_counter_count() -> u32;
+    #[wasm_bindgen(js_namespace = static_counter)]
+    fn bump();
+
     #[wasm_bindgen(js_name = self)]
     fn js_function_named_rust_keyword() -> u32;
 
@@ -232,3 +251,38 @@ fn undefined_function_is_ok() {
     x.method();
     x.set_property(x.property());
 }
+
+#[wasm_bindgen_test]
+fn bind_this() {
+    let obj = js_sys::Object::new();
+    js_sys::Reflect::set(&obj, &"base".into(), &JsValue::from(10)).unwrap();
+    assert_eq!(this_plus(&obj, 5), 15);
+    assert_eq!(this_get(&obj, "base").as_f64(), Some(10.0));
+}
+
+#[wasm_bindgen_test]
+fn static_getter() {
+    assert_eq!(static_version(), "1.2.3");
+    assert_eq!(static_counter_count(), 0);
+    bump();
+    bump();
+    assert_eq!(static_counter_count(), 2);
+}
+
+#[wasm_bindgen_test]
+fn main_thread_called_directly_on_main_thread() {
+    let total = main_thread_total();
+    main_thread_add(2);
+    main_thread_add(3);
+    assert_eq!(main_thread_total(), total + 5);
+}
+
+#[wasm_bindgen]
+pub fn imports_main_thread_add_on_worker(a: u32) {
+    main_thread_add(a);
+}
+
+#[wasm_bindgen_test(async)]
+fn main_thread_called_from_worker() -> impl Future<Item = (), Error = JsValue> {
+    JsFuture::from(js_main_thread_from_worker()).map(|_| ())
+}
@@ -26,3 +26,12 @@ exports.JsCast1 = JsCast1;
 exports.JsCast2 = JsCast2;
 exports.JsCast3 = JsCast3;
 exports.JsCast4 = JsCast4;
+
+exports.js_cast1_from_other_realm = () => {
+  class JsCast1 {
+    myval() { return 10; }
+  }
+  return new JsCast1();
+};
+
+exports.js_cast1_lookalike = () => ({ myval() { return 20; } });
@@ -23,6 +23,9 @@ extern "C" {
     type JsCast4;
     #[wasm_bindgen(constructor)]
     fn new() -> JsCast4;
+
+    fn js_cast1_from_other_realm() -> JsValue;
+    fn js_cast1_lookalike() -> JsValue;
 }
 
 #[wasm_bindgen_test]
@@ -86,3 +89,67 @@ fn multiple_layers_of_inheritance() {
     let b: &JsCast1 = a.as_ref();
     assert_eq!(b.myval(), 4);
 }
+
+#[wasm_bindgen_test]
+fn dyn_into_named() {
+    let a = JsCast1::new();
+    let err = JsValue::from(a).dyn_into_named::<JsCast2>().unwrap_err();
+    assert_eq!(err.expected(), ["JsCast2"]);
+    assert_eq!(err.found(), "JsCast1");
+    assert_eq!(err.to_string(), "expected an instance of `JsCast2`, found `JsCast1`");
+    assert!(err.into_value().dyn_into_named::<JsCast1>().is_ok());
+
+    let err = JsValue::from(1).dyn_into_named::<JsCast1>().unwrap_err();
+    assert_eq!(err.found(), "number");
+    assert_eq!(
+        err.with_preview().to_string(),
+        "expected an instance of `JsCast1`, found `number`: 1"
+    );
+}
+
+#[wasm_bindgen_test]
+fn try_downcast() {
+    fn describe(value: JsValue) -> String {
+        try_downcast!(value,
+            a: JsCast3 => format!("JsCast3 {}", a.myval()),
+            _b: JsCast2 => "JsCast2".to_string(),
+            Err(err) => err.to_string(),
+        )
+    }
+
+    assert_eq!(describe(JsCast3::new().into()), "JsCast3 3");
+    assert_eq!(describe(JsCast4::new().into()), "JsCast3 4");
+    assert_eq!(describe(JsCast2::new().into()), "JsCast2");
+    assert_eq!(
+        describe(JsCast1::new().into()),
+        "expected an instance of `JsCast3` or `JsCast2`, found `JsCast1`"
+    );
+}
+
+#[wasm_bindgen_test]
+fn cast_by_name() {
+    let a = js_cast1_from_other_realm();
+    assert!(!a.is_instance_of::<JsCast1>());
+    assert!(a.has_class_name::<JsCast1>());
+    assert!(!a.has_class_name::<JsCast2>());
+    assert_eq!(a.dyn_ref_by_name::<JsCast1>().unwrap().myval(), 10);
+
+    let b = JsCast4::new();
+    assert!(b.has_class_name::<JsCast1>());
+    assert!(b.has_class_name::<JsCast4>());
+    assert!(!JsValue::from("JsCast1").has_class_name::<JsCast1>());
+}
+
+#[wasm_bindgen_test]
+fn cast_by_methods() {
+    let a = js_cast1_lookalike();
+    assert!(!a.is_instance_of::<JsCast1>());
+    assert!(!a.has_class_name::<JsCast1>());
+    assert!(a.has_methods_of::<JsCast1>());
+    assert_eq!(a.dyn_into_by_methods::<JsCast1>().unwrap().myval(), 20);
+
+    assert!(JsCast3::new().has_methods_of::<JsCast1>());
+    assert!(!JsCast2::new().has_methods_of::<JsCast1>());
+    assert!(JsCast2::new().has_methods_of::<JsCast2>());
+    assert!(!JsValue::from(1).has_methods_of::<JsCast2>());
+}
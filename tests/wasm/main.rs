@@ -1,7 +1,10 @@
 #![cfg(target_arch = "wasm32")]
 
+extern crate futures;
 extern crate js_sys;
+#[macro_use]
 extern crate wasm_bindgen;
+extern crate wasm_bindgen_futures;
 extern crate wasm_bindgen_test;
 extern crate wasm_bindgen_test_crate_a;
 extern crate wasm_bindgen_test_crate_b;
@@ -13,10 +16,12 @@ extern crate serde_derive;
 use wasm_bindgen::prelude::*;
 
 pub mod api;
+pub mod build_info;
 pub mod char;
 pub mod classes;
 pub mod closures;
 pub mod comments;
+pub mod custom_abi;
 pub mod duplicate_deps;
 pub mod duplicates;
 pub mod enums;
@@ -26,6 +31,7 @@ pub mod import_class;
 pub mod imports;
 pub mod js_objects;
 pub mod jscast;
+pub mod maps;
 pub mod math;
 pub mod node;
 pub mod option;
@@ -34,9 +40,11 @@ pub mod rethrow;
 pub mod simple;
 pub mod slice;
 pub mod structural;
+pub mod traits;
 pub mod u64;
 pub mod validate_prt;
 pub mod variadic;
+pub mod vec_class;
 pub mod vendor_prefix;
 
 // should not be executed
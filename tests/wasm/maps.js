@@ -0,0 +1,69 @@
+const wasm = require('wasm-bindgen-test.js');
+const assert = require('assert');
+
+exports.js_exported_maps = () => {
+    const counts = wasm.maps_word_counts('a b a c a');
+    assert.ok(counts instanceof Map);
+    assert.strictEqual(counts.size, 3);
+    assert.strictEqual(counts.get('a'), 3);
+    assert.strictEqual(counts.get('b'), 1);
+
+    assert.strictEqual(wasm.maps_total(counts), 5);
+    assert.strictEqual(wasm.maps_total({ x: 1, y: 2 }), 3);
+    assert.strictEqual(wasm.maps_total({}), 0);
+
+    assert.strictEqual(wasm.maps_keys(new Map([['b', null], ['a', {}]])), 'a,b');
+
+    assert.strictEqual(wasm.maps_maybe(undefined), undefined);
+    const flipped = wasm.maps_maybe({ yes: true, no: false });
+    assert.deepStrictEqual([...flipped], [['no', true], ['yes', false]]);
+
+    assert.throws(() => wasm.maps_total(new Map([[1, 2]])), /keys of maps passed to Rust must be strings/);
+    assert.throws(() => wasm.maps_total({ x: 'one' }), /invalid value for map key `x`/);
+
+    // Numbers which aren't a `u32` aren't silently truncated or wrapped.
+    assert.throws(() => wasm.maps_total({ x: 1.5 }), /invalid value for map key `x`/);
+    assert.throws(() => wasm.maps_total({ x: -1 }), /invalid value for map key `x`/);
+    assert.throws(() => wasm.maps_total({ x: 2 ** 32 }), /invalid value for map key `x`/);
+    assert.throws(() => wasm.maps_total({ x: NaN }), /invalid value for map key `x`/);
+    assert.throws(() => wasm.maps_total({ x: Infinity }), /invalid value for map key `x`/);
+    assert.strictEqual(wasm.maps_total({ x: 2 ** 32 - 1 }), 2 ** 32 - 1);
+};
+
+exports.js_maps_as_objects = () => {
+    const headers = wasm.maps_headers();
+    assert.ok(!(headers instanceof Map));
+    assert.strictEqual(Object.getPrototypeOf(headers), Object.prototype);
+    assert.deepStrictEqual(Object.keys(headers), ['__proto__', 'content-type']);
+    assert.strictEqual(headers['content-type'], 'text/plain');
+
+    const counter = new wasm.MapsCounter();
+    counter.add('x');
+    counter.add('x');
+    counter.add('y');
+    const counts = counter.counts();
+    assert.strictEqual(counts.x, 2);
+    assert.strictEqual(counts.y, 1);
+    counter.free();
+};
+
+class MapsTag {
+    constructor(name) {
+        this.name = name;
+    }
+}
+
+exports.js_tags = () => new Map([['a', new MapsTag('first')], ['b', new MapsTag('second')]]);
+
+exports.js_sizes = sizes => {
+    assert.ok(sizes instanceof Map);
+    return sizes.get('x') + sizes.get('y');
+};
+
+exports.js_maybe = map => {
+    if (map === undefined) {
+        return undefined;
+    }
+    assert.ok(map instanceof Map);
+    return Object.fromEntries(map);
+};
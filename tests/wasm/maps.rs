@@ -0,0 +1,121 @@
+use std::collections::{BTreeMap, HashMap};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen(module = "tests/wasm/maps.js")]
+extern "C" {
+    fn js_exported_maps();
+    fn js_maps_as_objects();
+
+    type MapsTag;
+    #[wasm_bindgen(method, getter)]
+    fn name(this: &MapsTag) -> String;
+
+    fn js_tags() -> BTreeMap<String, MapsTag>;
+    fn js_sizes(sizes: HashMap<String, f64>) -> u32;
+    fn js_maybe(map: Option<BTreeMap<String, String>>) -> Option<BTreeMap<String, String>>;
+}
+
+#[wasm_bindgen]
+pub fn maps_word_counts(text: &str) -> HashMap<String, u32> {
+    let mut counts = HashMap::new();
+    for word in text.split_whitespace() {
+        *counts.entry(word.to_string()).or_insert(0) += 1;
+    }
+    counts
+}
+
+#[wasm_bindgen]
+pub fn maps_total(counts: HashMap<String, u32>) -> u32 {
+    counts.values().sum()
+}
+
+#[wasm_bindgen]
+pub fn maps_keys(map: BTreeMap<String, JsValue>) -> String {
+    map.keys().cloned().collect::<Vec<_>>().join(",")
+}
+
+#[wasm_bindgen]
+pub fn maps_maybe(map: Option<BTreeMap<String, bool>>) -> Option<BTreeMap<String, bool>> {
+    map.map(|map| map.into_iter().map(|(k, v)| (k, !v)).collect())
+}
+
+#[wasm_bindgen(maps_as_objects)]
+pub fn maps_headers() -> BTreeMap<String, String> {
+    let mut headers = BTreeMap::new();
+    headers.insert("content-type".to_string(), "text/plain".to_string());
+    headers.insert("__proto__".to_string(), "not a prototype".to_string());
+    headers
+}
+
+#[wasm_bindgen]
+pub struct MapsCounter {
+    counts: HashMap<String, u32>,
+}
+
+#[wasm_bindgen]
+impl MapsCounter {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> MapsCounter {
+        MapsCounter {
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, name: String) {
+        *self.counts.entry(name).or_insert(0) += 1;
+    }
+
+    #[wasm_bindgen(maps_as_objects)]
+    pub fn counts(&self) -> HashMap<String, u32> {
+        self.counts.clone()
+    }
+}
+
+#[wasm_bindgen_test]
+fn exported() {
+    js_exported_maps();
+}
+
+#[wasm_bindgen_test]
+fn as_objects() {
+    js_maps_as_objects();
+}
+
+#[wasm_bindgen_test]
+fn imported() {
+    let tags = js_tags();
+    assert_eq!(tags.len(), 2);
+    assert_eq!(tags["a"].name(), "first");
+    assert_eq!(tags["b"].name(), "second");
+
+    let mut sizes = HashMap::new();
+    sizes.insert("x".to_string(), 1.5);
+    sizes.insert("y".to_string(), 2.5);
+    assert_eq!(js_sizes(sizes), 4);
+
+    assert_eq!(js_maybe(None), None);
+    let mut map = BTreeMap::new();
+    map.insert("k".to_string(), "v".to_string());
+    assert_eq!(js_maybe(Some(map.clone())), Some(map));
+}
+
+#[wasm_bindgen_test]
+fn integer_values() {
+    use wasm_bindgen::convert::MapValue;
+
+    assert_eq!(u8::from_js_value(JsValue::from(255.0)), Some(255));
+    assert_eq!(i8::from_js_value(JsValue::from(-128.0)), Some(-128));
+    assert_eq!(
+        u32::from_js_value(JsValue::from(4294967295.0)),
+        Some(u32::max_value())
+    );
+    assert_eq!(u8::from_js_value(JsValue::from(256.0)), None);
+    assert_eq!(u8::from_js_value(JsValue::from(-1.0)), None);
+    assert_eq!(i32::from_js_value(JsValue::from(1.5)), None);
+    assert_eq!(i32::from_js_value(JsValue::from(std::f64::NAN)), None);
+    assert_eq!(i32::from_js_value(JsValue::from(std::f64::INFINITY)), None);
+    assert_eq!(i32::from_js_value(JsValue::from("1")), None);
+
+    assert_eq!(f64::from_js_value(JsValue::from(1.5)), Some(1.5));
+}
@@ -84,9 +84,22 @@ exports.test_rust_optional = function() {
   wasm.take_optional_str_none(null);
   wasm.take_optional_str_none(undefined);
   wasm.take_optional_str_some('hello');
+  assert.strictEqual(wasm.take_optional_str_ref(undefined), undefined);
+  assert.strictEqual(wasm.take_optional_str_ref(null), undefined);
+  assert.strictEqual(wasm.take_optional_str_ref(''), '');
+  assert.strictEqual(wasm.take_optional_str_ref('hello'), 'HELLO');
+  assert.strictEqual(wasm.take_optional_slice_ref(undefined), undefined);
+  assert.strictEqual(wasm.take_optional_slice_ref(new Uint8Array([1, 2, 3])), 6);
   assert.strictEqual(wasm.return_optional_str_none(), undefined);
   assert.strictEqual(wasm.return_optional_str_some(), 'world');
 };
 
 exports.RenamedInRust = class {};
 exports.new_renamed = () => new exports.RenamedInRust;
+
+exports.test_branded_newtypes = function() {
+  assert.strictEqual(wasm.seconds_to_milliseconds(1.5), 1500);
+  assert.strictEqual(wasm.milliseconds_to_seconds(2500), 2.5);
+};
+
+exports.double_milliseconds = ms => ms * 2;
@@ -25,6 +25,9 @@ extern "C" {
     #[wasm_bindgen(js_name = RenamedInRust)]
     type Renamed;
     fn new_renamed() -> Renamed;
+
+    fn test_branded_newtypes();
+    fn double_milliseconds(ms: Milliseconds) -> Milliseconds;
 }
 
 #[wasm_bindgen_test]
@@ -173,6 +176,16 @@ pub fn take_optional_str_some(x: Option<String>) {
     assert_eq!(x, Some(String::from("hello")));
 }
 
+#[wasm_bindgen]
+pub fn take_optional_str_ref(x: Option<&str>) -> Option<String> {
+    x.map(|x| x.to_uppercase())
+}
+
+#[wasm_bindgen]
+pub fn take_optional_slice_ref(x: Option<&[u8]>) -> Option<u32> {
+    x.map(|x| x.iter().map(|b| *b as u32).sum())
+}
+
 #[wasm_bindgen]
 pub fn return_optional_str_none() -> Option<String> {
     None
@@ -194,3 +207,23 @@ fn renaming_imports_and_instanceof() {
     let renamed: JsValue = new_renamed().into();
     assert!(renamed.is_instance_of::<Renamed>());
 }
+
+#[wasm_bindgen(ts_brand = "Milliseconds")]
+#[derive(Debug, PartialEq)]
+pub struct Milliseconds(pub f64);
+
+#[wasm_bindgen]
+pub fn seconds_to_milliseconds(seconds: f64) -> Milliseconds {
+    Milliseconds(seconds * 1000.0)
+}
+
+#[wasm_bindgen]
+pub fn milliseconds_to_seconds(ms: Milliseconds) -> f64 {
+    ms.0 / 1000.0
+}
+
+#[wasm_bindgen_test]
+fn branded_newtypes() {
+    test_branded_newtypes();
+    assert_eq!(double_milliseconds(Milliseconds(1.5)), Milliseconds(3.0));
+}
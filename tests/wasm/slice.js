@@ -212,3 +212,19 @@ exports.js_clamped = (a, offset) => {
   assert.equal(a[1], offset + 1);
   assert.equal(a[2], offset + 2);
 };
+
+exports.js_strided = () => {
+    // A 2x2 region in the middle of a 4x4 image, passed without compacting it
+    // into a separate buffer first.
+    const image = new Float64Array(16).map((_, i) => i);
+    assert.strictEqual(wasm.strided_sum(image.subarray(5, 11), 2, 2, 4), 5 + 6 + 9 + 10);
+
+    const out = new Float32Array(7);
+    wasm.strided_fill(out, 2, 3, 4);
+    assert.deepStrictEqual(Array.from(out), [0, 0, 0, 0, 1, 1, 1]);
+
+    const bytes = new Uint8Array([1, 2, 0, 3, 4, 0, 5, 6]);
+    assert.deepStrictEqual(Array.from(wasm.strided_compact(bytes, 3, 2, 3)), [1, 2, 3, 4, 5, 6]);
+
+    assert.throws(() => wasm.strided_sum(image, 5, 4, 4), /invalid shape/);
+};
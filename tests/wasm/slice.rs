@@ -1,4 +1,5 @@
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::strided::{Strided, StridedMut, SLICE_ALIGN};
 use wasm_bindgen::Clamped;
 use wasm_bindgen_test::*;
 
@@ -14,6 +15,8 @@ extern "C" {
 
     fn js_return_vec();
 
+    fn js_strided();
+
     fn js_clamped(val: Clamped<&[u8]>, offset: u8);
     #[wasm_bindgen(js_name = js_clamped)]
     fn js_clamped2(val: Clamped<Vec<u8>>, offset: u8);
@@ -222,3 +225,43 @@ fn take_clamped() {
     js_clamped2(Clamped(vec![4, 5, 6]), 4);
     js_clamped3(Clamped(&mut [7, 8, 9]), 7);
 }
+
+#[wasm_bindgen]
+pub fn strided_sum(data: &[f64], rows: usize, cols: usize, stride: usize) -> f64 {
+    assert_eq!(data.as_ptr() as usize % SLICE_ALIGN, 0);
+    let view = Strided::new(data, rows, cols, stride).expect_throw("invalid shape");
+    view.rows().map(|row| row.iter().sum::<f64>()).sum()
+}
+
+#[wasm_bindgen]
+pub fn strided_fill(data: &mut [f32], rows: usize, cols: usize, stride: usize) {
+    let mut view = StridedMut::new(data, rows, cols, stride).expect_throw("invalid shape");
+    view.for_each_row(|i, row| {
+        for x in row.iter_mut() {
+            *x = i as f32;
+        }
+    });
+}
+
+#[wasm_bindgen]
+pub fn strided_compact(data: &[u8], rows: usize, cols: usize, stride: usize) -> Vec<u8> {
+    Strided::new(data, rows, cols, stride)
+        .expect_throw("invalid shape")
+        .to_vec()
+}
+
+#[wasm_bindgen_test]
+fn strided() {
+    js_strided();
+
+    let data = [1, 2, 3, 4, 5, 6, 7];
+    let view = Strided::new(&data[..], 3, 1, 3).unwrap();
+    assert_eq!(view.to_vec(), vec![1, 4, 7]);
+    assert_eq!(view.get(1, 0), Some(&4));
+    assert_eq!(view.get(1, 1), None);
+    assert_eq!(view.row(2), Some(&[7][..]));
+    assert_eq!(view.rows().len(), 3);
+    assert!(Strided::new(&data[..], 3, 2, 3).is_none());
+    assert!(Strided::new(&data[..], 2, 4, 3).is_none());
+    assert_eq!(Strided::new(&data[..], 1, 7, 0).unwrap().to_vec(), data.to_vec());
+}
@@ -11,3 +11,76 @@ exports.js_works = () => {
     });
     assert.strictEqual(called, true);
 };
+
+class Counter {
+    constructor(baz) {
+        this.value = baz;
+    }
+    get baz() {
+        return this.value;
+    }
+    set baz(baz) {
+        this.value = baz;
+    }
+}
+
+class TenTimes {
+    constructor(baz) {
+        this.value = baz;
+    }
+    get baz() {
+        return this.value * 10;
+    }
+    set baz(baz) {
+        this.value = baz / 10;
+    }
+}
+
+class SubCounter extends Counter {}
+
+class Offset {
+    constructor(baz) {
+        this.value = baz;
+    }
+    get baz() {
+        return this.value + 100;
+    }
+    set baz(baz) {
+        this.value = baz - 100;
+    }
+}
+
+exports.js_cached_accessors = () => {
+    // Objects of different classes, including plain objects and classes
+    // inheriting their accessors, go through the same getters and setters.
+    // There are more classes than cached accessors, and they're all seen
+    // twice.
+    const objects = () => [
+        new Counter(1),
+        new Counter(2),
+        { baz: 3 },
+        new TenTimes(4),
+        new SubCounter(5),
+        new Counter(6),
+        { baz: 7 },
+        new Offset(8),
+    ];
+    const expected = [2, 4, 6, 80, 10, 12, 14, 216];
+    for (const f of [wasm.structural_double_baz, wasm.cached_double_baz]) {
+        const all = objects().concat(objects());
+        for (let i = 0; i < all.length; i++) {
+            assert.strictEqual(f(all[i]), expected[i % expected.length]);
+        }
+        assert.strictEqual(all[0].value, 2);
+        assert.strictEqual(all[2].baz, 6);
+        assert.strictEqual(all[3].value, 8);
+        assert.strictEqual(all[7].value, 116);
+    }
+
+    // Only uncached getters see properties which shadow the accessors of the
+    // prototype.
+    const shadowed = new Counter(1);
+    Object.defineProperty(shadowed, 'baz', { value: 5, writable: true });
+    assert.strictEqual(wasm.structural_double_baz(shadowed), 10);
+    assert.strictEqual(shadowed.value, 1);
+};
@@ -16,6 +16,10 @@ extern "C" {
     fn baz(this: &Foo) -> u32;
     #[wasm_bindgen(method, setter, structural)]
     fn set_baz(this: &Foo, val: u32);
+    #[wasm_bindgen(method, getter = baz, structural, cached)]
+    fn cached_baz(this: &Foo) -> u32;
+    #[wasm_bindgen(method, setter = baz, structural, cached)]
+    fn set_cached_baz(this: &Foo, val: u32);
 }
 
 #[wasm_bindgen]
@@ -30,3 +34,27 @@ pub fn run(a: &Foo) {
 fn works() {
     js_works();
 }
+
+#[wasm_bindgen(module = "tests/wasm/structural.js")]
+extern "C" {
+    fn js_cached_accessors();
+}
+
+#[wasm_bindgen]
+pub fn structural_double_baz(a: &Foo) -> u32 {
+    let baz = a.baz();
+    a.set_baz(baz * 2);
+    a.baz()
+}
+
+#[wasm_bindgen]
+pub fn cached_double_baz(a: &Foo) -> u32 {
+    let baz = a.cached_baz();
+    a.set_cached_baz(baz * 2);
+    a.cached_baz()
+}
+
+#[wasm_bindgen_test]
+fn cached_accessors() {
+    js_cached_accessors();
+}
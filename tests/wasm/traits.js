@@ -0,0 +1,36 @@
+const wasm = require('wasm-bindgen-test.js');
+const assert = require('assert');
+
+class ClassGreeter {
+    constructor(greeting) {
+        this.greeting = greeting;
+    }
+
+    greet(name) {
+        return `${this.greeting} ${name}`;
+    }
+
+    greetCount() {
+        return 3;
+    }
+}
+
+exports.js_implements_traits = () => {
+    const greeter = {
+        greet: name => `hey ${name}`,
+        greetCount: () => 2,
+    };
+    assert.strictEqual(wasm.traits_greet_twice(greeter, 'a'), 'hey a, hey a');
+    assert.strictEqual(wasm.traits_total_count(greeter), 3);
+
+    const instance = new ClassGreeter('howdy');
+    assert.strictEqual(wasm.traits_greet_twice(instance, 'b'), 'howdy b, howdy b');
+    assert.strictEqual(wasm.traits_total_count(instance), 4);
+};
+
+exports.js_greeter = () => ({
+    greet: name => `hi ${name}`,
+    greetCount: () => 2,
+});
+
+exports.js_not_a_greeter = () => ({ greet: name => name });
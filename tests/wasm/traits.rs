@@ -0,0 +1,62 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen(module = "tests/wasm/traits.js")]
+extern "C" {
+    fn js_implements_traits();
+    fn js_greeter() -> JsValue;
+    fn js_not_a_greeter() -> JsValue;
+}
+
+/// Something which greets people.
+#[wasm_bindgen]
+pub trait Greeter {
+    /// Returns a greeting for `name`.
+    fn greet(&self, name: &str) -> String;
+
+    #[wasm_bindgen(js_name = greetCount)]
+    fn greet_count(&self) -> u32;
+}
+
+struct RustGreeter;
+
+impl Greeter for RustGreeter {
+    fn greet(&self, name: &str) -> String {
+        format!("hello {}", name)
+    }
+
+    fn greet_count(&self) -> u32 {
+        1
+    }
+}
+
+fn greet_all(greeter: &impl Greeter, names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| greeter.greet(name)).collect()
+}
+
+#[wasm_bindgen]
+pub fn traits_greet_twice(greeter: &impl Greeter, name: &str) -> String {
+    greet_all(greeter, &[name, name]).join(", ")
+}
+
+#[wasm_bindgen]
+pub fn traits_total_count(greeter: impl Greeter) -> u32 {
+    greeter.greet_count() + RustGreeter.greet_count()
+}
+
+#[wasm_bindgen_test]
+fn implemented_in_js() {
+    js_implements_traits();
+}
+
+#[wasm_bindgen_test]
+fn duck_typed() {
+    assert_eq!(greet_all(&RustGreeter, &["a"]), ["hello a"]);
+
+    let greeter = js_greeter().dyn_into_by_methods::<JsGreeter>().unwrap();
+    assert_eq!(greet_all(&greeter, &["a", "b"]), ["hi a", "hi b"]);
+    assert_eq!(greeter.greet_count(), 2);
+
+    assert!(!js_not_a_greeter().has_methods_of::<JsGreeter>());
+}
@@ -0,0 +1,54 @@
+const wasm = require('wasm-bindgen-test.js');
+const assert = require('assert');
+
+exports.js_exported_vectors = () => {
+    const line = wasm.vec_class_line(3);
+    assert.strictEqual(line.length, 3);
+    assert.ok(line.every(p => p instanceof wasm.VecClassPoint));
+    assert.deepStrictEqual(line.map(p => [p.x(), p.y()]), [[0, 0], [1, 2], [2, 4]]);
+    assert.strictEqual(wasm.vec_class_line(0).length, 0);
+
+    const boxed = wasm.vec_class_boxed();
+    assert.deepStrictEqual(boxed.map(p => p.y()), [2, 4]);
+
+    // Passing objects moves them into Rust.
+    assert.strictEqual(wasm.vec_class_sum(line), 9);
+    assert.ok(line.every(p => p.ptr === 0));
+    assert.throws(() => line[0].x(), /null pointer passed to rust/);
+
+    const reversed = wasm.vec_class_reverse(boxed);
+    assert.deepStrictEqual(reversed.map(p => p.x()), [3, 1]);
+
+    assert.strictEqual(wasm.vec_class_maybe(undefined), undefined);
+    const maybe = wasm.vec_class_maybe([new wasm.VecClassPoint(7, 8)]);
+    assert.deepStrictEqual(maybe.map(p => p.x()), [7]);
+
+    // None of the objects are moved if one of them is of the wrong class.
+    const point = new wasm.VecClassPoint(1, 1);
+    assert.throws(() => wasm.vec_class_sum([point, {}]), /expected instance of VecClassPoint/);
+    assert.strictEqual(point.x(), 1);
+};
+
+class VecClassTag {
+    constructor(name) {
+        this.name = name;
+    }
+}
+
+exports.VecClassTag = VecClassTag;
+
+exports.js_imported_vectors = () => {
+    const tags = wasm.vec_class_tags();
+    assert.deepStrictEqual(tags.map(t => t.name), ['a', 'b']);
+    assert.strictEqual(wasm.vec_class_names(tags), 'a,b');
+    assert.strictEqual(wasm.vec_class_names([]), '');
+};
+
+exports.js_wrap_points = points => {
+    assert.ok(points.every(p => p instanceof wasm.VecClassPoint));
+    return points.reverse();
+};
+
+exports.js_maybe_points = points => points;
+
+exports.js_tags = () => ['x', 'y', 'z'].map(name => new VecClassTag(name));
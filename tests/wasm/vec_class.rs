@@ -0,0 +1,108 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen(module = "tests/wasm/vec_class.js")]
+extern "C" {
+    fn js_exported_vectors();
+    fn js_imported_vectors();
+
+    fn js_wrap_points(points: Vec<VecClassPoint>) -> Vec<VecClassPoint>;
+    fn js_maybe_points(points: Option<Vec<VecClassPoint>>) -> Option<Vec<VecClassPoint>>;
+
+    type VecClassTag;
+    #[wasm_bindgen(constructor)]
+    fn new(name: &str) -> VecClassTag;
+    #[wasm_bindgen(method, getter)]
+    fn name(this: &VecClassTag) -> String;
+
+    fn js_tags() -> Vec<VecClassTag>;
+}
+
+#[wasm_bindgen]
+#[derive(Debug, PartialEq)]
+pub struct VecClassPoint {
+    x: i32,
+    y: i32,
+}
+
+#[wasm_bindgen]
+impl VecClassPoint {
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: i32, y: i32) -> VecClassPoint {
+        VecClassPoint { x, y }
+    }
+
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+}
+
+#[wasm_bindgen]
+pub fn vec_class_line(n: i32) -> Vec<VecClassPoint> {
+    (0..n).map(|i| VecClassPoint::new(i, i * 2)).collect()
+}
+
+#[wasm_bindgen]
+pub fn vec_class_boxed() -> Box<[VecClassPoint]> {
+    vec![VecClassPoint::new(1, 2), VecClassPoint::new(3, 4)].into_boxed_slice()
+}
+
+#[wasm_bindgen]
+pub fn vec_class_sum(points: Vec<VecClassPoint>) -> i32 {
+    points.iter().map(|p| p.x + p.y).sum()
+}
+
+#[wasm_bindgen]
+pub fn vec_class_reverse(points: Box<[VecClassPoint]>) -> Box<[VecClassPoint]> {
+    let mut points = points.into_vec();
+    points.reverse();
+    points.into_boxed_slice()
+}
+
+#[wasm_bindgen]
+pub fn vec_class_maybe(points: Option<Vec<VecClassPoint>>) -> Option<Vec<VecClassPoint>> {
+    points
+}
+
+#[wasm_bindgen]
+pub fn vec_class_names(tags: Vec<VecClassTag>) -> String {
+    tags.iter().map(|t| t.name()).collect::<Vec<_>>().join(",")
+}
+
+#[wasm_bindgen]
+pub fn vec_class_tags() -> Vec<VecClassTag> {
+    vec![VecClassTag::new("a"), VecClassTag::new("b")]
+}
+
+#[wasm_bindgen_test]
+fn exported() {
+    js_exported_vectors();
+}
+
+#[wasm_bindgen_test]
+fn imported() {
+    js_imported_vectors();
+}
+
+#[wasm_bindgen_test]
+fn passed_to_js() {
+    let points = js_wrap_points(vec![VecClassPoint::new(1, 2), VecClassPoint::new(3, 4)]);
+    assert_eq!(points, [VecClassPoint::new(3, 4), VecClassPoint::new(1, 2)]);
+    assert!(js_wrap_points(Vec::new()).is_empty());
+
+    assert_eq!(js_maybe_points(None), None);
+    assert_eq!(
+        js_maybe_points(Some(vec![VecClassPoint::new(5, 6)])),
+        Some(vec![VecClassPoint::new(5, 6)])
+    );
+}
+
+#[wasm_bindgen_test]
+fn returned_from_js() {
+    let names = js_tags().iter().map(|t| t.name()).collect::<Vec<_>>();
+    assert_eq!(names, ["x", "y", "z"]);
+}